@@ -0,0 +1,23 @@
+// Refreshes the offline filter list snapshot bundled with the app.
+//
+//     cargo run --example snapshot_adblock_lists [output dir]
+//
+// The output dir defaults to resources/adblock.
+
+use std::path::PathBuf;
+
+use sovereign_browser_lib::adblock_manager::write_bundled_snapshot;
+
+fn main() {
+    let dir = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/adblock"));
+    match write_bundled_snapshot(&dir) {
+        Ok(rule_count) => println!("[AdBlock] Wrote snapshot with {} rules to {:?}", rule_count, dir),
+        Err(e) => {
+            eprintln!("[AdBlock] Failed to write snapshot: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
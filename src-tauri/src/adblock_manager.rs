@@ -1,19 +1,45 @@
 use adblock::engine::Engine;
-use adblock::lists::{FilterSet, ParseOptions};
+use adblock::lists::{parse_filter, FilterSet, ParseOptions};
+use adblock::resources::Resource;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager};
 use arc_swap::ArcSwap;
 use dashmap::DashMap;
 
+#[cfg(target_os = "macos")]
+use crate::modules::channel_blocking;
+use crate::modules::list_updates;
+
 const EASYLIST_URL: &str = "https://easylist.to/easylist/easylist.txt";
 const EASYPRIVACY_URL: &str = "https://easylist.to/easylist/easyprivacy.txt";
+// Always present in the configured set; they can be disabled but not removed
+const BUILTIN_LISTS: &[(&str, &str)] = &[("EasyList", EASYLIST_URL), ("EasyPrivacy", EASYPRIVACY_URL)];
 const ENGINE_CACHE_FILE: &str = "adblock_engine.bin";
 const SAFARI_CACHE_FILE: &str = "safari_rules.json";
 const ALLOWLIST_FILE: &str = "adblock_allowlist.json";
+const ALLOWLIST_META_FILE: &str = "adblock_allowlist_meta.json";
+pub const ALLOWLIST_EXPORT_VERSION: u32 = 1;
+const STATUS_FILE: &str = "adblock_status.json";
+const USER_RULES_FILE: &str = "adblock_user_rules.json";
+// Scriptlet and redirect resources that `+js(...)` rules refer to by name
+const RESOURCES_URL: &str = "https://raw.githubusercontent.com/brave/adblock-resources/master/dist/resources.json";
+const RESOURCES_CACHE_FILE: &str = "adblock_resources.json";
+// Offline snapshot in the app resources, written by examples/snapshot_adblock_lists.rs:
+// the built-in lists gzip-compressed, plus the engine compiled from them (ENGINE_CACHE_FILE)
+const BUNDLED_DIR: &str = "adblock";
+const BUNDLED_LISTS: &[(&str, &str)] = &[(EASYLIST_URL, "easylist.txt.gz"), (EASYPRIVACY_URL, "easyprivacy.txt.gz")];
+const MAX_SELECTOR_LEN: usize = 1024;
+const STALE_AFTER_SECS: u64 = 7 * 24 * 60 * 60;
+const MAX_ERROR_SAMPLES: usize = 5;
 
 // Custom exception rules for webmail services (Option A: Granular Approach)
 // Syntax: @@||domain^$domain=context - "When on context domain, allow requests to domain"
@@ -29,20 +55,437 @@ const CUSTOM_EXCEPTION_RULES: &[&str] = &[
     // "@@||outlook.live.com^$domain=outlook.live.com",
 ];
 
+// Sites covered by the webmail handling above (see is_webmail_domain)
+const WEBMAIL_SITES: &[&str] = &["gmail.com", "mail.google.com"];
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub enum RuleExpiry {
     Forever,
     Until(SystemTime),
 }
 
+impl RuleExpiry {
+    /// Unix seconds, None for Forever.
+    pub fn expires_at(&self) -> Option<u64> {
+        match self {
+            RuleExpiry::Forever => None,
+            RuleExpiry::Until(t) => Some(t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)),
+        }
+    }
+
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        match self {
+            RuleExpiry::Forever => true,
+            RuleExpiry::Until(t) => now < *t,
+        }
+    }
+}
+
+/// When an exception was added and the user's note for it. Kept next to the
+/// allowlist file so that file keeps its original format.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ExceptionMeta {
+    pub created_at: u64, // Unix seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// One exception in an allowlist export.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AllowlistEntry {
+    pub domain: String,
+    #[serde(default)]
+    pub expiry: Option<u64>, // Unix seconds, null = forever
+    #[serde(default)]
+    pub created_at: Option<u64>, // Unix seconds
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// The portable allowlist file written by `export_allowlist`:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "exceptions": [
+///     { "domain": "example.com", "expiry": null, "created_at": 1760000000, "note": "Video player" },
+///     { "domain": "news.example", "expiry": 1760086400, "created_at": 1760000000, "note": null }
+///   ]
+/// }
+/// ```
+///
+/// Only `domain` is required on import. Entries are sorted by domain so exports
+/// diff cleanly under sync tools.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AllowlistExport {
+    pub version: u32,
+    pub exceptions: Vec<AllowlistEntry>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowlistImportSummary {
+    pub imported: usize,
+    pub skipped: usize, // Invalid domains, already expired, or no later than an existing entry
+}
+
+/// Whether `new` keeps a site excepted for longer than `existing`.
+fn outlasts(new: &RuleExpiry, existing: &RuleExpiry) -> bool {
+    match (new, existing) {
+        (_, RuleExpiry::Forever) => false,
+        (RuleExpiry::Forever, _) => true,
+        (RuleExpiry::Until(a), RuleExpiry::Until(b)) => a > b,
+    }
+}
+
+/// Where the engine currently in use came from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineSource {
+    #[default]
+    Empty,
+    Cache,   // Deserialized from adblock_engine.bin at startup
+    Bundled, // From the offline snapshot shipped with the app
+    Network, // Built from freshly fetched lists this session
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EngineInfo {
+    pub source: EngineSource,
+    pub built_at: Option<u64>, // Unix seconds
+    pub filter_count: usize,   // Rules fed to the engine, including custom exceptions
+    pub custom_rule_count: usize,
+    pub safari_rule_count: Option<usize>, // macOS only
+    pub resource_count: usize,            // Scriptlet/redirect resources loaded
+}
+
+/// Cosmetic filtering for one page: CSS to hide elements and scriptlets to run.
+#[derive(Debug, Clone, Default)]
+pub struct CosmeticResources {
+    pub css: String,
+    pub scriptlets: String,
+}
+
+/// One subscribed filter list: its configuration and the result of the last fetch.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterListStatus {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub builtin: bool,
+    #[serde(default)]
+    pub rule_count: usize,
+    pub last_updated: Option<u64>, // Last successful fetch, Unix seconds
+    pub last_error: Option<String>,
+    // Health details from the last update
+    #[serde(default)]
+    pub last_attempt: Option<u64>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub parse_errors: usize, // Rules the engine rejected
+    #[serde(default)]
+    pub parse_error_samples: Vec<String>,
+    #[serde(default)]
+    pub safari_skipped: Option<usize>, // Rules with no WKContentRuleList equivalent (macOS only)
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListHealth {
+    Disabled,
+    Pending, // Never fetched yet
+    Failing, // The last fetch failed
+    Broken,  // Fetched, but no usable rules or mostly rejected
+    Stale,   // No successful fetch in STALE_AFTER_SECS
+    Healthy,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterListHealth {
+    #[serde(flatten)]
+    pub list: FilterListStatus,
+    pub health: ListHealth,
+}
+
+impl FilterListStatus {
+    pub fn health(&self, now: u64) -> ListHealth {
+        if !self.enabled {
+            ListHealth::Disabled
+        } else if self.last_error.is_some() {
+            ListHealth::Failing
+        } else if self.last_updated.is_none() {
+            ListHealth::Pending
+        } else if self.rule_count == 0 || self.parse_errors * 2 > self.rule_count {
+            ListHealth::Broken
+        } else if self.last_updated.is_some_and(|t| now.saturating_sub(t) > STALE_AFTER_SECS) {
+            ListHealth::Stale
+        } else {
+            ListHealth::Healthy
+        }
+    }
+}
+
+/// Rule and parse-error counts for one fetched list.
+#[derive(Debug, Default, PartialEq)]
+struct ListStats {
+    rule_count: usize,
+    parse_errors: usize,
+    parse_error_samples: Vec<String>,
+    title: Option<String>,
+}
+
+fn list_stats(lines: &[&str]) -> ListStats {
+    let mut stats = ListStats { title: list_title(lines), ..Default::default() };
+    for line in lines.iter().map(|l| l.trim()).filter(|l| is_rule_line(l)) {
+        stats.rule_count += 1;
+        if let Err(e) = parse_filter(line, false, ParseOptions::default()) {
+            stats.parse_errors += 1;
+            if stats.parse_error_samples.len() < MAX_ERROR_SAMPLES {
+                stats.parse_error_samples.push(format!("{} ({:?})", line, e));
+            }
+        }
+    }
+    stats
+}
+
+/// Attributes Safari conversion skips back to the list each rule came from.
+#[cfg(any(target_os = "macos", test))]
+fn skipped_by_list(fetched: &[(String, String)], skipped: &[String]) -> std::collections::HashMap<String, usize> {
+    use std::collections::HashMap;
+    let owners: HashMap<&str, &str> = fetched
+        .iter()
+        .flat_map(|(url, text)| text.lines().map(move |l| (l.trim(), url.as_str())))
+        .collect();
+    let mut counts: HashMap<String, usize> = fetched.iter().map(|(url, _)| (url.clone(), 0)).collect();
+    for rule in skipped {
+        if let Some(url) = owners.get(rule.trim()) {
+            *counts.entry(url.to_string()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+impl FilterListStatus {
+    fn new(name: &str, url: &str, builtin: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            enabled: true,
+            builtin,
+            rule_count: 0,
+            last_updated: None,
+            last_error: None,
+            last_attempt: None,
+            consecutive_failures: 0,
+            parse_errors: 0,
+            parse_error_samples: Vec::new(),
+            safari_skipped: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// The configured set: stored lists, with any missing built-in list restored
+/// (enabled) ahead of the custom ones.
+fn with_builtins(stored: Vec<FilterListStatus>) -> Vec<FilterListStatus> {
+    let mut lists: Vec<FilterListStatus> = BUILTIN_LISTS
+        .iter()
+        .map(|(name, url)| {
+            let mut list = stored.iter().find(|l| l.url == *url).cloned().unwrap_or_else(|| FilterListStatus::new(name, url, true));
+            list.builtin = true;
+            list
+        })
+        .collect();
+    for list in stored {
+        if !lists.iter().any(|l| l.url == list.url) {
+            lists.push(FilterListStatus { builtin: false, ..list });
+        }
+    }
+    lists
+}
+
+/// Validates a subscription URL (http or https only).
+pub fn normalize_list_url(input: &str) -> Result<String, String> {
+    let url = url::Url::parse(input.trim()).map_err(|e| format!("Invalid filter list URL: {}", e))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err("Filter lists must be http or https URLs".to_string());
+    }
+    Ok(url.to_string())
+}
+
+/// Placeholder name until the list's own "! Title:" header is fetched.
+fn list_name_from_url(url: &str) -> String {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    parsed
+        .path_segments()
+        .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+        .map(|file| file.split('.').next().unwrap_or(file).to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| parsed.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// The "! Title: ..." header most lists carry near the top.
+fn list_title(lines: &[&str]) -> Option<String> {
+    lines
+        .iter()
+        .take(50)
+        .find_map(|l| l.trim().strip_prefix("! Title:"))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Persisted so update times survive restarts alongside the cached engine.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct StoredStatus {
+    engine: EngineInfo,
+    lists: Vec<FilterListStatus>,
+}
+
+/// How blocking behaves on a given site.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteMode {
+    Standard,
+    Allowed, // User exception: nothing blocked, no cosmetic filtering
+    Relaxed, // Built-in webmail handling: infrastructure allowed, no cosmetic filtering
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteModeInfo {
+    pub site: String,
+    pub mode: SiteMode,
+    pub expires_at: Option<u64>,
+}
+
+/// A hiding rule the user made with the element picker.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserCosmeticRule {
+    pub domain: String, // Host the element was picked on; also applies to its subdomains
+    pub selector: String,
+    pub created_at: u64,
+}
+
+/// Rejects selectors that could break out of the generated `{ display: none }` block.
+pub fn validate_selector(selector: &str) -> Result<String, String> {
+    let selector = selector.trim();
+    if selector.is_empty() {
+        return Err("Empty selector".to_string());
+    }
+    if selector.len() > MAX_SELECTOR_LEN {
+        return Err("Selector is too long".to_string());
+    }
+    if selector.contains(['{', '}', '<', '\n', '\r']) || selector.contains("/*") {
+        return Err("Selector contains characters that aren't allowed".to_string());
+    }
+    Ok(selector.to_string())
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+/// Everything an about:adblock page needs in one call.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AdblockDashboard {
+    pub enabled: bool,
+    pub updating: bool,
+    pub engine: EngineInfo,
+    pub subscriptions: Vec<FilterListStatus>,
+    pub exceptions: Vec<SiteModeInfo>,
+    pub site_modes: Vec<SiteModeInfo>,
+}
+
+/// Non-empty, non-comment lines (`!` comments and the `[Adblock Plus]` header).
+fn is_rule_line(line: &str) -> bool {
+    !line.is_empty() && !line.starts_with('!') && !line.starts_with('[')
+}
+
+
+fn read_gzip_list(path: &Path) -> Option<String> {
+    let mut text = String::new();
+    GzDecoder::new(fs::File::open(path).ok()?).read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+/// The bundled snapshot of a built-in list.
+fn bundled_list_text(dir: &Path, url: &str) -> Option<String> {
+    let (_, file) = BUNDLED_LISTS.iter().find(|(u, _)| *u == url)?;
+    read_gzip_list(&dir.join(file))
+}
+
+/// The engine `update_rules` would build from these lists.
+fn engine_from_lists(texts: &[String]) -> Engine {
+    let mut filter_set = FilterSet::new(true);
+    for text in texts {
+        let lines: Vec<&str> = text.lines().collect();
+        filter_set.add_filters(&lines, ParseOptions::default());
+    }
+    filter_set.add_filters(CUSTOM_EXCEPTION_RULES, ParseOptions::default());
+    Engine::from_filter_set(filter_set, true)
+}
+
+/// Downloads the built-in lists and writes the offline snapshot bundled with the
+/// app into `dir`. Returns the number of rules. Run before a release through
+/// `cargo run --example snapshot_adblock_lists`.
+pub fn write_bundled_snapshot(dir: &Path) -> Result<usize, String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let mut texts = Vec::new();
+    for (url, file) in BUNDLED_LISTS {
+        println!("[AdBlock] Fetching {}...", url);
+        let text = reqwest::blocking::get(*url)
+            .and_then(|resp| resp.error_for_status()?.text())
+            .map_err(|e| format!("{}: {}", url, e))?;
+        let mut encoder = GzEncoder::new(fs::File::create(dir.join(file)).map_err(|e| e.to_string())?, Compression::best());
+        encoder.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+        encoder.finish().map_err(|e| e.to_string())?;
+        texts.push(text);
+    }
+    let rule_count = texts.iter().flat_map(|t| t.lines()).filter(|l| is_rule_line(l.trim())).count();
+    fs::write(dir.join(ENGINE_CACHE_FILE), engine_from_lists(&texts).serialize()).map_err(|e| e.to_string())?;
+    Ok(rule_count)
+}
+
+fn lists_due(lists: &[FilterListStatus], now: u64, max_age: u64) -> bool {
+    lists.iter().filter(|l| l.enabled).any(|l| l.last_updated.map_or(true, |t| now.saturating_sub(t) >= max_age))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
 pub struct AdBlockManager {
     // Lock-free reader for the hot path
     engine: ArcSwap<Engine>,
     // Concurrent map for exceptions
     allowlist: DashMap<String, RuleExpiry>,
+    allowlist_meta: DashMap<String, ExceptionMeta>,
+    app: AppHandle, // For the proxy list downloads go through
     app_dir: PathBuf,
+    bundled_dir: Option<PathBuf>, // Offline snapshot in the app resources
     // Cache Safari rules in memory for fast injection
     pub safari_rules_json: ArcSwap<String>,
+    // Dashboard info; not on the hot path
+    engine_info: ArcSwap<EngineInfo>,
+    lists: Mutex<Vec<FilterListStatus>>,
+    updating: AtomicBool,
+    update_requested: AtomicBool, // Config changed mid-update; run again when done
+    user_rules: Mutex<Vec<UserCosmeticRule>>,
 }
 
 impl AdBlockManager {
@@ -54,19 +497,38 @@ impl AdBlockManager {
         let allowlist_path = app_dir.join(ALLOWLIST_FILE);
         let safari_path = app_dir.join(SAFARI_CACHE_FILE);
 
-        // 1. Load Rust Engine
+        let bundled_dir = app.path().resource_dir().ok().map(|d| d.join(BUNDLED_DIR));
+
+        // 1. Load Rust Engine: the cache from the last update, else the bundled
+        // snapshot so a fresh install blocks before its first download
         println!("[AdBlock] Initializing ad blocking engine...");
-        let engine = if cache_path.exists() {
+        let cached = if cache_path.exists() {
             println!("[AdBlock] Loading cached engine from {:?}...", cache_path);
-            Self::load_engine_from_disk(&cache_path).unwrap_or_else(|_| {
-                println!("[AdBlock] Failed to load cache, using empty engine");
-                Engine::default()
-            })
+            let engine = Self::load_engine_from_disk(&cache_path).ok();
+            if engine.is_none() {
+                println!("[AdBlock] Failed to load cache");
+            }
+            engine
         } else {
-            println!("[AdBlock] No cache found, starting with empty engine");
-            Engine::default()
+            println!("[AdBlock] No cache found");
+            None
+        };
+        let (mut engine, source) = match cached {
+            Some(engine) => (engine, EngineSource::Cache),
+            None => match bundled_dir.as_deref().and_then(Self::load_bundled_engine) {
+                Some(engine) => (engine, EngineSource::Bundled),
+                None => {
+                    println!("[AdBlock] Starting with empty engine");
+                    (Engine::default(), EngineSource::Empty)
+                }
+            },
         };
 
+        // The cached engine doesn't carry its resources; they're cached separately
+        let resources = Self::load_resources_from_disk(&app_dir.join(RESOURCES_CACHE_FILE));
+        let resource_count = resources.len();
+        engine.use_resources(resources);
+
         // 2. Load Allowlist
         let allowlist = DashMap::new();
         if allowlist_path.exists() {
@@ -80,6 +542,12 @@ impl AdBlockManager {
             }
         }
 
+        let allowlist_meta: DashMap<String, ExceptionMeta> = fs::read_to_string(app_dir.join(ALLOWLIST_META_FILE))
+            .ok()
+            .and_then(|c| serde_json::from_str::<std::collections::HashMap<String, ExceptionMeta>>(&c).ok())
+            .map(|m| m.into_iter().collect())
+            .unwrap_or_default();
+
         // 3. Load Safari Rules
         let safari_json = if safari_path.exists() {
             let json = fs::read_to_string(&safari_path).unwrap_or_else(|_| "[]".to_string());
@@ -89,47 +557,123 @@ impl AdBlockManager {
             "[]".to_string()
         };
 
+        // 4. Load filter list subscriptions and the last build status
+        let mut status: StoredStatus = fs::read_to_string(app_dir.join(STATUS_FILE))
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default();
+        status.engine.source = source;
+        status.engine.resource_count = resource_count;
+
+        // 5. Load user cosmetic rules (element picker)
+        let user_rules: Vec<UserCosmeticRule> = fs::read_to_string(app_dir.join(USER_RULES_FILE))
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default();
+        if !user_rules.is_empty() {
+            println!("[AdBlock] Loaded {} user cosmetic rules", user_rules.len());
+        }
+
         println!("[AdBlock] Ad blocking engine initialized.");
 
         Self {
             engine: ArcSwap::from_pointee(engine),
             allowlist,
+            allowlist_meta,
+            app: app.clone(),
             app_dir,
+            bundled_dir,
             safari_rules_json: ArcSwap::from_pointee(safari_json),
+            engine_info: ArcSwap::from_pointee(status.engine),
+            lists: Mutex::new(with_builtins(status.lists)),
+            updating: AtomicBool::new(false),
+            update_requested: AtomicBool::new(false),
+            user_rules: Mutex::new(user_rules),
         }
     }
 
     /// Spawn a background thread to fetch and update rules.
-    /// Call this after creating the manager.
+    /// Call this after creating the manager. If an update is already running, another
+    /// pass is queued so configuration changes made meanwhile are picked up.
     pub fn spawn_update_thread(self: &Arc<Self>) {
+        if self.updating.swap(true, Ordering::SeqCst) {
+            self.update_requested.store(true, Ordering::SeqCst);
+            return;
+        }
         let manager = self.clone();
         std::thread::spawn(move || {
-            manager.update_rules();
+            loop {
+                manager.update_rules();
+                if !manager.update_requested.swap(false, Ordering::SeqCst) {
+                    break;
+                }
+            }
+            manager.updating.store(false, Ordering::SeqCst);
         });
     }
 
+    pub fn is_updating(&self) -> bool {
+        self.updating.load(Ordering::SeqCst)
+    }
+
     fn update_rules(&self) {
         println!("[AdBlock] Background: Fetching filter lists...");
         
-        let urls = vec![EASYLIST_URL, EASYPRIVACY_URL];
         let mut filter_set = FilterSet::new(true); // debug=true required for Safari conversion
         let mut lines_count = 0;
+        let mut rule_count = 0;
+        let mut network_lists = 0;
+        let urls: Vec<String> = self.lists.lock().unwrap().iter()
+            .filter(|l| l.enabled)
+            .map(|l| l.url.clone())
+            .collect();
+        // Kept to attribute skipped Safari conversions to their lists
+        #[cfg(target_os = "macos")]
+        let mut fetched: Vec<(String, String)> = Vec::new();
 
         for url in &urls {
             println!("[AdBlock] Background: Fetching {}...", url);
-            if let Ok(resp) = reqwest::blocking::get(*url) {
-                if let Ok(text) = resp.text() {
-                    let lines: Vec<&str> = text.lines().collect();
-                    let count = lines.len();
-                    lines_count += count;
-                    filter_set.add_filters(&lines, ParseOptions::default());
-                    println!("[AdBlock] Background: Loaded {} lines from {}", count, url);
+            let (text, from_network) = match list_updates::fetch_text(&self.app, url) {
+                Ok(text) => (text, true),
+                Err(e) => {
+                    println!("[AdBlock] Background: Failed to fetch {}: {}", url, e);
+                    self.record_fetch(url, Err(e));
+                    // A built-in list falls back to its bundled snapshot rather than dropping out
+                    match self.bundled_dir.as_deref().and_then(|d| bundled_list_text(d, url)) {
+                        Some(text) => {
+                            println!("[AdBlock] Background: Using bundled snapshot of {}", url);
+                            (text, false)
+                        }
+                        None => continue,
+                    }
                 }
+            };
+            let lines: Vec<&str> = text.lines().collect();
+            let count = lines.len();
+            lines_count += count;
+            let stats = list_stats(&lines);
+            rule_count += stats.rule_count - stats.parse_errors;
+            if stats.parse_errors > 0 {
+                println!("[AdBlock] Background: {} rules rejected in {}", stats.parse_errors, url);
+            }
+            if from_network {
+                network_lists += 1;
+                self.record_fetch(url, Ok(stats));
             }
+            filter_set.add_filters(&lines, ParseOptions::default());
+            println!("[AdBlock] Background: Loaded {} lines from {}", count, url);
+            #[cfg(target_os = "macos")]
+            fetched.push((url.clone(), text));
         }
 
-        if lines_count == 0 {
-            println!("[AdBlock] Background: No filters loaded, aborting update");
+        // With every list disabled the engine is rebuilt from the custom rules alone.
+        // Otherwise nothing fetched means we're offline: keep the current engine,
+        // unless the bundled snapshot can still fill in what's missing
+        if urls.is_empty() {
+            println!("[AdBlock] Background: No filter lists enabled");
+        } else if lines_count == 0 || (network_lists == 0 && !self.needs_priming()) {
+            println!("[AdBlock] Background: No filters fetched, aborting update");
+            self.save_status();
             return;
         }
 
@@ -144,10 +688,23 @@ impl AdBlockManager {
 
         // Pipeline A: Rust Engine (Cosmetic & Windows/Linux network blocking)
         println!("[AdBlock] Background: Building Rust engine...");
-        let new_engine = Engine::from_filter_set(filter_set.clone(), true);
-        let serialized = new_engine.serialize();
-        let _ = fs::write(self.app_dir.join(ENGINE_CACHE_FILE), serialized);
+        let mut new_engine = Engine::from_filter_set(filter_set.clone(), true);
+        // An engine built only from the snapshot isn't cached; the bundled one loads as fast
+        if network_lists > 0 || urls.is_empty() {
+            let _ = fs::write(self.app_dir.join(ENGINE_CACHE_FILE), new_engine.serialize());
+        }
+        let resources = self.fetch_resources();
+        let resource_count = resources.len();
+        new_engine.use_resources(resources);
         self.engine.store(Arc::new(new_engine));
+        self.engine_info.store(Arc::new(EngineInfo {
+            source: if network_lists > 0 || urls.is_empty() { EngineSource::Network } else { EngineSource::Bundled },
+            built_at: Some(unix_now()),
+            filter_count: rule_count + CUSTOM_EXCEPTION_RULES.len(),
+            custom_rule_count: CUSTOM_EXCEPTION_RULES.len(),
+            safari_rule_count: None,
+            resource_count,
+        }));
         println!("[AdBlock] Background: Rust engine updated and cached.");
 
         // Pipeline B: Safari Rules (macOS Network blocking)
@@ -156,13 +713,17 @@ impl AdBlockManager {
             println!("[AdBlock] Background: Generating Safari content blocking rules...");
             if let Ok((rules, skipped)) = filter_set.into_content_blocking() {
                 println!("[AdBlock] Background: Generated {} Safari rules ({} skipped)", rules.len(), skipped.len());
+                self.record_safari_skipped(skipped_by_list(&fetched, &skipped));
 
                 // CRITICAL: The adblock crate's $domain syntax doesn't convert to Safari rules properly
                 // Manually inject exception rules for Gmail using Safari's format
                 // Work with JSON to add custom rules
 
                 if let Ok(json_str) = serde_json::to_string(&rules) {
-                    if let Ok(mut rules_json) = serde_json::from_str::<Vec<serde_json::Value>>(&json_str) {
+                    if let Ok(rules_json) = serde_json::from_str::<Vec<serde_json::Value>>(&json_str) {
+                        // Filters converted as ^https?:// would otherwise miss WebSocket handshakes
+                        let mut rules_json = channel_blocking::with_websocket_variants(rules_json);
+
                         // Add exception rules for Gmail
                         let gmail_domains = vec!["*mail.google.com", "*gmail.com"];
                         let whitelisted_domains = vec![
@@ -202,6 +763,9 @@ impl AdBlockManager {
                             println!("[AdBlock] Background: Safari rules serialized ({} chars)", final_json.len());
                             let _ = fs::write(self.app_dir.join(SAFARI_CACHE_FILE), &final_json);
                             self.safari_rules_json.store(Arc::new(final_json));
+                            let mut info = (**self.engine_info.load()).clone();
+                            info.safari_rule_count = Some(rules_json.len());
+                            self.engine_info.store(Arc::new(info));
                             println!("[AdBlock] Background: Safari rules updated and cached.");
                         }
                     }
@@ -211,9 +775,186 @@ impl AdBlockManager {
             }
         }
 
+        self.save_status();
         println!("[AdBlock] Background: Update complete!");
     }
 
+    /// Stores a fetch result on the list, if it's still configured.
+    fn record_fetch(&self, url: &str, result: Result<ListStats, String>) {
+        let mut lists = self.lists.lock().unwrap();
+        let Some(list) = lists.iter_mut().find(|l| l.url == url) else {
+            return;
+        };
+        let now = unix_now();
+        list.last_attempt = Some(now);
+        match result {
+            Ok(stats) => {
+                list.rule_count = stats.rule_count;
+                list.parse_errors = stats.parse_errors;
+                list.parse_error_samples = stats.parse_error_samples;
+                list.last_updated = Some(now);
+                list.last_error = None;
+                list.consecutive_failures = 0;
+                if let (Some(title), false) = (stats.title, list.builtin) {
+                    list.name = title;
+                }
+            }
+            Err(e) => {
+                list.last_error = Some(e);
+                list.consecutive_failures += 1;
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn record_safari_skipped(&self, counts: std::collections::HashMap<String, usize>) {
+        let mut lists = self.lists.lock().unwrap();
+        for list in lists.iter_mut() {
+            if let Some(count) = counts.get(&list.url) {
+                list.safari_skipped = Some(*count);
+            }
+        }
+    }
+
+    fn save_status(&self) {
+        let status = StoredStatus {
+            engine: (**self.engine_info.load()).clone(),
+            lists: self.lists.lock().unwrap().clone(),
+        };
+        let _ = fs::write(
+            self.app_dir.join(STATUS_FILE),
+            serde_json::to_string_pretty(&status).unwrap_or_default(),
+        );
+    }
+
+    // --- Dashboard Info ---
+
+    pub fn engine_info(&self) -> EngineInfo {
+        (**self.engine_info.load()).clone()
+    }
+
+    // --- Filter List Subscriptions ---
+
+    /// The configured lists, built-ins first.
+    pub fn filter_lists(&self) -> Vec<FilterListStatus> {
+        self.lists.lock().unwrap().clone()
+    }
+
+    /// Whether an enabled list hasn't been fetched in `max_age`, or ever.
+    pub fn lists_due(&self, max_age: Duration) -> bool {
+        lists_due(&self.lists.lock().unwrap(), unix_now(), max_age.as_secs())
+    }
+
+    /// Each configured list with its health, for spotting broken or stale lists.
+    pub fn filter_list_health(&self) -> Vec<FilterListHealth> {
+        let now = unix_now();
+        self.filter_lists()
+            .into_iter()
+            .map(|list| FilterListHealth { health: list.health(now), list })
+            .collect()
+    }
+
+    /// Subscribes to a list. It's fetched on the next update; until then it's named
+    /// `title` or after its URL.
+    pub fn add_filter_list(&self, url: &str, title: Option<&str>) -> Result<FilterListStatus, String> {
+        let url = normalize_list_url(url)?;
+        let list = {
+            let mut lists = self.lists.lock().unwrap();
+            if lists.iter().any(|l| l.url == url) {
+                return Err(format!("Already subscribed to {}", url));
+            }
+            let name = title.map(str::to_string).unwrap_or_else(|| list_name_from_url(&url));
+            let list = FilterListStatus::new(&name, &url, false);
+            lists.push(list.clone());
+            list
+        };
+        self.save_status();
+        println!("[AdBlock] Added filter list: {}", url);
+        Ok(list)
+    }
+
+    pub fn remove_filter_list(&self, url: &str) -> Result<(), String> {
+        {
+            let mut lists = self.lists.lock().unwrap();
+            let index = lists.iter().position(|l| l.url == url).ok_or_else(|| format!("No filter list {}", url))?;
+            if lists[index].builtin {
+                return Err("Built-in lists can be disabled but not removed".to_string());
+            }
+            lists.remove(index);
+        }
+        self.save_status();
+        println!("[AdBlock] Removed filter list: {}", url);
+        Ok(())
+    }
+
+    pub fn set_filter_list_enabled(&self, url: &str, enabled: bool) -> Result<FilterListStatus, String> {
+        let list = {
+            let mut lists = self.lists.lock().unwrap();
+            let list = lists.iter_mut().find(|l| l.url == url).ok_or_else(|| format!("No filter list {}", url))?;
+            list.enabled = enabled;
+            list.clone()
+        };
+        self.save_status();
+        println!("[AdBlock] Filter list {} {}", url, if enabled { "enabled" } else { "disabled" });
+        Ok(list)
+    }
+
+    pub fn dashboard(&self, enabled: bool) -> AdblockDashboard {
+        let site_modes = self.site_modes();
+        AdblockDashboard {
+            enabled,
+            updating: self.is_updating(),
+            engine: self.engine_info(),
+            subscriptions: self.filter_lists(),
+            exceptions: site_modes.iter().filter(|m| m.mode == SiteMode::Allowed).cloned().collect(),
+            site_modes,
+        }
+    }
+
+    /// Accepts a URL ("https://www.example.com/x") or a bare domain ("Example.com").
+    pub fn normalize_domain(input: &str) -> Option<String> {
+        if let Some(domain) = Self::extract_domain(input) {
+            return Some(domain);
+        }
+        let domain = input.trim().trim_end_matches('.').to_lowercase();
+        Self::extract_domain(&format!("https://{}/", domain)).filter(|d| *d == domain)
+    }
+
+    /// The mode for a site or URL: user exceptions first, then built-in webmail handling.
+    pub fn site_mode(&self, site_or_url: &str) -> SiteModeInfo {
+        let domain = Self::normalize_domain(site_or_url).unwrap_or_else(|| site_or_url.trim().to_lowercase());
+        if let Some(expiry) = self.allowlist.get(&domain) {
+            if expiry.is_active(SystemTime::now()) {
+                return SiteModeInfo { site: domain, mode: SiteMode::Allowed, expires_at: expiry.expires_at() };
+            }
+        }
+        let mode = if Self::is_webmail_domain(&format!("https://{}/", domain)) {
+            SiteMode::Relaxed
+        } else {
+            SiteMode::Standard
+        };
+        SiteModeInfo { site: domain, mode, expires_at: None }
+    }
+
+    /// Every site that doesn't get standard blocking: active exceptions and built-in
+    /// webmail sites, sorted by site.
+    pub fn site_modes(&self) -> Vec<SiteModeInfo> {
+        let now = SystemTime::now();
+        let mut modes: Vec<SiteModeInfo> = self
+            .allowlist
+            .iter()
+            .filter(|r| r.value().is_active(now))
+            .map(|r| SiteModeInfo { site: r.key().clone(), mode: SiteMode::Allowed, expires_at: r.value().expires_at() })
+            .collect();
+        for site in WEBMAIL_SITES {
+            if !modes.iter().any(|m| m.site == *site) {
+                modes.push(SiteModeInfo { site: site.to_string(), mode: SiteMode::Relaxed, expires_at: None });
+            }
+        }
+        modes.sort_by(|a, b| a.site.cmp(&b.site));
+        modes
+    }
+
     fn load_engine_from_disk(path: &PathBuf) -> Result<Engine, ()> {
         let data = fs::read(path).map_err(|_| ())?;
         let mut engine = Engine::default();
@@ -221,14 +962,69 @@ impl AdBlockManager {
         Ok(engine)
     }
 
+    /// The bundled engine: precompiled if it still deserializes with this adblock
+    /// version, otherwise built from the bundled lists.
+    fn load_bundled_engine(dir: &Path) -> Option<Engine> {
+        if let Ok(engine) = Self::load_engine_from_disk(&dir.join(ENGINE_CACHE_FILE)) {
+            println!("[AdBlock] Loaded bundled engine");
+            return Some(engine);
+        }
+        let texts: Vec<String> = BUNDLED_LISTS.iter().filter_map(|(url, _)| bundled_list_text(dir, url)).collect();
+        if texts.is_empty() {
+            println!("[AdBlock] No bundled filter lists found");
+            return None;
+        }
+        println!("[AdBlock] Building engine from {} bundled lists...", texts.len());
+        Some(engine_from_lists(&texts))
+    }
+
+    /// Whether rules from the bundled snapshot alone are an improvement: nothing
+    /// usable has been built yet (on macOS, no Safari rules either).
+    fn needs_priming(&self) -> bool {
+        self.engine_info.load().source == EngineSource::Empty
+            || (cfg!(target_os = "macos") && self.safari_rules_json.load().len() <= 2)
+    }
+
+    fn load_resources_from_disk(path: &PathBuf) -> Vec<Resource> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Downloads the scriptlet resources, falling back to the cached copy offline.
+    fn fetch_resources(&self) -> Vec<Resource> {
+        let cache_path = self.app_dir.join(RESOURCES_CACHE_FILE);
+        println!("[AdBlock] Background: Fetching scriptlet resources...");
+        let fetched = list_updates::fetch_text(&self.app, RESOURCES_URL)
+            .and_then(|json| serde_json::from_str::<Vec<Resource>>(&json).map(|r| (json, r)).map_err(|e| e.to_string()));
+        match fetched {
+            Ok((json, resources)) => {
+                let _ = fs::write(&cache_path, json);
+                println!("[AdBlock] Background: Loaded {} scriptlet resources", resources.len());
+                resources
+            }
+            Err(e) => {
+                println!("[AdBlock] Background: Failed to fetch scriptlet resources: {}", e);
+                Self::load_resources_from_disk(&cache_path)
+            }
+        }
+    }
+
     // --- Hot Path: Network Check (Windows/Linux only) ---
     
     /// Check if a request should be blocked.
     /// Uses lock-free ArcSwap::load() for maximum performance.
     /// NOTE: On macOS, this is bypassed - WKContentRuleList handles blocking.
     pub fn should_block_request(&self, url: &str, source_url: &str, request_type: &str) -> bool {
+        self.should_block_in_frame(url, source_url, source_url, request_type)
+    }
+
+    /// Like `should_block_request` for a request made inside a frame: filters see
+    /// the frame document, the allowlist sees the tab's top-level document.
+    pub fn should_block_in_frame(&self, url: &str, frame_url: &str, top_url: &str, request_type: &str) -> bool {
         // Check Allowlist first (Fast DashMap lookup)
-        if let Some(domain) = Self::extract_domain(source_url) {
+        if let Some(domain) = Self::extract_domain(top_url) {
             if let Some(expiry) = self.allowlist.get(&domain) {
                 match *expiry {
                     RuleExpiry::Forever => return false,
@@ -239,7 +1035,7 @@ impl AdBlockManager {
 
         // Check Engine (Lock-Free) - engine handles exception rules automatically
         let engine = self.engine.load();
-        let req = adblock::request::Request::new(url, source_url, request_type).ok();
+        let req = adblock::request::Request::new(url, frame_url, request_type).ok();
 
         if let Some(r) = req {
             engine.check_network_request(&r).matched
@@ -248,26 +1044,87 @@ impl AdBlockManager {
         }
     }
 
-    // --- Cosmetic CSS ---
+    // --- Cosmetic CSS & Scriptlets ---
     
-    /// Get cosmetic hiding CSS for a URL.
-    /// CRITICAL: Respects allowlist - returns empty string if site is excepted.
-    pub fn get_cosmetic_css(&self, url: &str) -> String {
+    /// Get cosmetic hiding CSS and the page's `+js(...)` scriptlets for a URL.
+    /// CRITICAL: Respects allowlist - only the user's own picker rules apply if the
+    /// site is excepted.
+    pub fn get_cosmetic_resources(&self, url: &str) -> CosmeticResources {
+        let mut css = self.user_rules_css(url);
+
         // CRITICAL: Respect allowlist AND webmail domains
         // Use url crate for security (no phishing vulnerabilities)
         if self.is_exception(url) || Self::is_webmail_domain(url) {
-            return String::new();
+            return CosmeticResources { css, scriptlets: String::new() };
         }
 
         let engine = self.engine.load();
         let resources = engine.url_cosmetic_resources(url);
 
-        let mut css = String::with_capacity(resources.hide_selectors.len() * 50);
+        css.reserve(resources.hide_selectors.len() * 50);
         for selector in resources.hide_selectors {
             css.push_str(selector.as_str());
             css.push_str(" { display: none !important; }\n");
         }
-        css
+        CosmeticResources { css, scriptlets: resources.injected_script }
+    }
+
+    // --- User Cosmetic Rules (element picker) ---
+
+    fn user_rules_css(&self, url: &str) -> String {
+        let Some(host) = Self::extract_domain(url) else {
+            return String::new();
+        };
+        self.user_rules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| domain_matches(&host, &r.domain))
+            .map(|r| format!("{} {{ display: none !important; }}\n", r.selector))
+            .collect()
+    }
+
+    pub fn add_user_rule(&self, domain: &str, selector: &str) -> Result<UserCosmeticRule, String> {
+        let selector = validate_selector(selector)?;
+        let domain = Self::normalize_domain(domain).ok_or_else(|| format!("Invalid domain: {}", domain))?;
+        let rule = {
+            let mut rules = self.user_rules.lock().unwrap();
+            if let Some(existing) = rules.iter().find(|r| r.domain == domain && r.selector == selector) {
+                return Ok(existing.clone());
+            }
+            let rule = UserCosmeticRule { domain, selector, created_at: unix_now() };
+            rules.push(rule.clone());
+            rule
+        };
+        self.save_user_rules();
+        println!("[AdBlock] Added user rule on {}: {}", rule.domain, rule.selector);
+        Ok(rule)
+    }
+
+    pub fn remove_user_rule(&self, domain: &str, selector: &str) -> Result<(), String> {
+        {
+            let mut rules = self.user_rules.lock().unwrap();
+            let before = rules.len();
+            rules.retain(|r| !(r.domain == domain && r.selector == selector));
+            if rules.len() == before {
+                return Err(format!("No rule '{}' on {}", selector, domain));
+            }
+        }
+        self.save_user_rules();
+        println!("[AdBlock] Removed user rule on {}: {}", domain, selector);
+        Ok(())
+    }
+
+    pub fn user_rules(&self) -> Vec<UserCosmeticRule> {
+        self.user_rules.lock().unwrap().clone()
+    }
+
+    fn save_user_rules(&self) {
+        let rules = self.user_rules.lock().unwrap().clone();
+        let _ = fs::write(
+            self.app_dir.join(USER_RULES_FILE),
+            serde_json::to_string_pretty(&rules).unwrap_or_default(),
+        );
     }
 
     // --- Exception Management ---
@@ -278,23 +1135,32 @@ impl AdBlockManager {
             None => RuleExpiry::Forever,
         };
         println!("[AdBlock] Added exception for: {}", domain);
+        self.allowlist_meta.entry(domain.clone()).or_insert_with(|| ExceptionMeta { created_at: unix_now(), note: None });
         self.allowlist.insert(domain, expiry);
         self.save_allowlist();
     }
 
     pub fn remove_exception(&self, domain: &str) {
         self.allowlist.remove(domain);
+        self.allowlist_meta.remove(domain);
         self.save_allowlist();
         println!("[AdBlock] Removed exception for: {}", domain);
     }
 
+    /// Remove every per-site exception. Returns how many were removed.
+    pub fn clear_exceptions(&self) -> usize {
+        let count = self.allowlist.len();
+        self.allowlist.clear();
+        self.allowlist_meta.clear();
+        self.save_allowlist();
+        println!("[AdBlock] Cleared {} exceptions", count);
+        count
+    }
+
     pub fn is_exception(&self, url: &str) -> bool {
         if let Some(domain) = Self::extract_domain(url) {
             if let Some(expiry) = self.allowlist.get(&domain) {
-                match *expiry {
-                    RuleExpiry::Forever => return true,
-                    RuleExpiry::Until(t) => return SystemTime::now() < t,
-                }
+                return expiry.is_active(SystemTime::now());
             }
         }
         false
@@ -312,6 +1178,83 @@ impl AdBlockManager {
             .map(|r| (r.key().clone(), r.value().clone()))
             .collect();
         let _ = fs::write(path, serde_json::to_string_pretty(&map).unwrap_or_default());
+        let meta: std::collections::HashMap<_, _> = self.allowlist_meta.iter()
+            .map(|r| (r.key().clone(), r.value().clone()))
+            .collect();
+        let _ = fs::write(self.app_dir.join(ALLOWLIST_META_FILE), serde_json::to_string_pretty(&meta).unwrap_or_default());
+    }
+
+    /// Sets the note shown for an exception.
+    pub fn set_exception_note(&self, domain: &str, note: Option<String>) -> Result<(), String> {
+        if !self.allowlist.contains_key(domain) {
+            return Err(format!("No exception for {}", domain));
+        }
+        let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+        self.allowlist_meta.entry(domain.to_string()).or_insert_with(|| ExceptionMeta { created_at: unix_now(), note: None }).note = note;
+        self.save_allowlist();
+        Ok(())
+    }
+
+    /// Active exceptions in the export format.
+    pub fn export_allowlist(&self) -> AllowlistExport {
+        let now = SystemTime::now();
+        let mut exceptions: Vec<AllowlistEntry> = self
+            .allowlist
+            .iter()
+            .filter(|r| r.value().is_active(now))
+            .map(|r| {
+                let meta = self.allowlist_meta.get(r.key()).map(|m| m.clone());
+                AllowlistEntry {
+                    domain: r.key().clone(),
+                    expiry: r.value().expires_at(),
+                    created_at: meta.as_ref().map(|m| m.created_at),
+                    note: meta.and_then(|m| m.note),
+                }
+            })
+            .collect();
+        exceptions.sort_by(|a, b| a.domain.cmp(&b.domain));
+        AllowlistExport { version: ALLOWLIST_EXPORT_VERSION, exceptions }
+    }
+
+    /// Merges exported exceptions in. An entry only replaces an existing exception
+    /// if it lasts longer; notes are taken from the import when it has one.
+    pub fn import_allowlist(&self, export: AllowlistExport) -> Result<AllowlistImportSummary, String> {
+        if export.version > ALLOWLIST_EXPORT_VERSION {
+            return Err(format!("Unsupported allowlist version {}", export.version));
+        }
+        let now = SystemTime::now();
+        let mut summary = AllowlistImportSummary::default();
+        for entry in export.exceptions {
+            let Some(domain) = Self::normalize_domain(&entry.domain) else {
+                summary.skipped += 1;
+                continue;
+            };
+            let expiry = match entry.expiry {
+                Some(secs) => RuleExpiry::Until(UNIX_EPOCH + Duration::from_secs(secs)),
+                None => RuleExpiry::Forever,
+            };
+            let replaces = match self.allowlist.get(&domain) {
+                Some(existing) if existing.is_active(now) => outlasts(&expiry, &existing),
+                _ => true,
+            };
+            if !expiry.is_active(now) || !replaces {
+                summary.skipped += 1;
+                continue;
+            }
+            let mut meta = self.allowlist_meta.entry(domain.clone()).or_insert_with(|| ExceptionMeta {
+                created_at: entry.created_at.unwrap_or_else(unix_now),
+                note: None,
+            });
+            if entry.note.is_some() {
+                meta.note = entry.note;
+            }
+            drop(meta);
+            self.allowlist.insert(domain, expiry);
+            summary.imported += 1;
+        }
+        self.save_allowlist();
+        println!("[AdBlock] Imported {} exceptions ({} skipped)", summary.imported, summary.skipped);
+        Ok(summary)
     }
 
     fn extract_domain(url: &str) -> Option<String> {
@@ -341,3 +1284,203 @@ impl AdBlockManager {
         (**self.safari_rules_json.load()).clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_snapshot_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut encoder = GzEncoder::new(fs::File::create(dir.path().join("easylist.txt.gz")).unwrap(), Compression::default());
+        encoder.write_all(b"[Adblock Plus 2.0]\n||ads.example^\n").unwrap();
+        encoder.finish().unwrap();
+
+        let text = bundled_list_text(dir.path(), EASYLIST_URL).unwrap();
+        assert!(text.contains("||ads.example^"));
+        assert_eq!(bundled_list_text(dir.path(), EASYPRIVACY_URL), None);
+        assert_eq!(bundled_list_text(dir.path(), "https://example.com/list.txt"), None);
+
+        let engine = engine_from_lists(&[text]);
+        let request = adblock::request::Request::new("https://ads.example/a.js", "https://news.example/", "script").unwrap();
+        assert!(engine.check_network_request(&request).matched);
+    }
+
+    #[test]
+    fn test_rule_lines_skip_comments() {
+        let lines = vec!["[Adblock Plus 2.0]", "! Title: EasyList", "", "||ads.example^", "##.banner", "  "];
+        assert_eq!(list_stats(&lines).rule_count, 2);
+    }
+
+    #[test]
+    fn test_outlasts() {
+        let soon = RuleExpiry::Until(UNIX_EPOCH + Duration::from_secs(100));
+        let later = RuleExpiry::Until(UNIX_EPOCH + Duration::from_secs(200));
+        assert!(outlasts(&later, &soon));
+        assert!(!outlasts(&soon, &later));
+        assert!(outlasts(&RuleExpiry::Forever, &later));
+        assert!(!outlasts(&later, &RuleExpiry::Forever));
+        assert!(!outlasts(&RuleExpiry::Forever, &RuleExpiry::Forever));
+    }
+
+    #[test]
+    fn test_allowlist_export_schema() {
+        // Only the domain is required
+        let parsed: AllowlistExport =
+            serde_json::from_str(r#"{ "version": 1, "exceptions": [{ "domain": "example.com" }] }"#).unwrap();
+        assert_eq!(
+            parsed.exceptions,
+            vec![AllowlistEntry { domain: "example.com".to_string(), expiry: None, created_at: None, note: None }]
+        );
+
+        let export = AllowlistExport {
+            version: ALLOWLIST_EXPORT_VERSION,
+            exceptions: vec![AllowlistEntry {
+                domain: "news.example".to_string(),
+                expiry: Some(1_760_086_400),
+                created_at: Some(1_760_000_000),
+                note: Some("Paywall".to_string()),
+            }],
+        };
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["exceptions"][0]["expiry"], 1_760_086_400);
+        assert_eq!(json["exceptions"][0]["created_at"], 1_760_000_000);
+        assert_eq!(serde_json::from_value::<AllowlistExport>(json).unwrap(), export);
+    }
+
+    #[test]
+    fn test_rule_expiry() {
+        let now = SystemTime::now();
+        assert!(RuleExpiry::Forever.is_active(now));
+        assert_eq!(RuleExpiry::Forever.expires_at(), None);
+
+        let later = RuleExpiry::Until(now + Duration::from_secs(60));
+        assert!(later.is_active(now));
+        assert!(!later.is_active(now + Duration::from_secs(61)));
+        assert_eq!(RuleExpiry::Until(UNIX_EPOCH + Duration::from_secs(100)).expires_at(), Some(100));
+    }
+
+    #[test]
+    fn test_normalize_domain() {
+        assert_eq!(AdBlockManager::normalize_domain("https://www.Example.com/path").as_deref(), Some("www.example.com"));
+        assert_eq!(AdBlockManager::normalize_domain(" Example.com. ").as_deref(), Some("example.com"));
+        assert_eq!(AdBlockManager::normalize_domain("not a domain"), None);
+        assert_eq!(AdBlockManager::normalize_domain("example.com/path"), None);
+    }
+
+    #[test]
+    fn test_validate_selector() {
+        assert_eq!(validate_selector("  div.ad > span  ").unwrap(), "div.ad > span");
+        assert_eq!(validate_selector("a[href*=\"x\"]:nth-of-type(2)").unwrap(), "a[href*=\"x\"]:nth-of-type(2)");
+        assert!(validate_selector("").is_err());
+        assert!(validate_selector("div } body { display: none").is_err());
+        assert!(validate_selector("div /* comment").is_err());
+        assert!(validate_selector(&"a".repeat(MAX_SELECTOR_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_domain_matches() {
+        assert!(domain_matches("example.com", "example.com"));
+        assert!(domain_matches("news.example.com", "example.com"));
+        assert!(!domain_matches("badexample.com", "example.com"));
+        assert!(!domain_matches("example.com", "news.example.com"));
+    }
+
+    #[test]
+    fn test_stored_status_tolerates_missing_fields() {
+        let status: StoredStatus = serde_json::from_str(
+            r#"{"engine":{"filterCount":5},"lists":[{"name":"Mine","url":"https://example.com/mine.txt","lastUpdated":10,"lastError":null}]}"#,
+        ).unwrap();
+        assert_eq!(status.engine.filter_count, 5);
+        assert_eq!(status.engine.source, EngineSource::Empty);
+        assert!(status.lists[0].enabled);
+        assert!(!status.lists[0].builtin);
+    }
+
+    #[test]
+    fn test_list_stats() {
+        let lines = vec!["! Title: Test List", "||ads.example^", "##.banner", "||bad^$unknownoption", ""];
+        let stats = list_stats(&lines);
+        assert_eq!(stats.title.as_deref(), Some("Test List"));
+        assert_eq!(stats.rule_count, 3);
+        assert_eq!(stats.parse_errors, 1);
+        assert!(stats.parse_error_samples[0].starts_with("||bad^$unknownoption"));
+    }
+
+    #[test]
+    fn test_skipped_by_list() {
+        let fetched = vec![
+            ("a".to_string(), "||one^\n##.two".to_string()),
+            ("b".to_string(), "||three^".to_string()),
+        ];
+        let counts = skipped_by_list(&fetched, &["##.two".to_string(), "||unknown^".to_string()]);
+        assert_eq!(counts.get("a"), Some(&1));
+        assert_eq!(counts.get("b"), Some(&0));
+    }
+
+    #[test]
+    fn test_lists_due() {
+        let now = 100 * 24 * 60 * 60;
+        let mut list = FilterListStatus::new("Test", "https://example.com/list.txt", false);
+        assert!(lists_due(&[list.clone()], now, 3600));
+        list.last_updated = Some(now - 60);
+        assert!(!lists_due(&[list.clone()], now, 3600));
+        list.last_updated = Some(now - 3600);
+        assert!(lists_due(&[list.clone()], now, 3600));
+        list.enabled = false;
+        assert!(!lists_due(&[list], now, 3600));
+    }
+
+    #[test]
+    fn test_list_health() {
+        let now = 100 * 24 * 60 * 60;
+        let mut list = FilterListStatus::new("Test", "https://example.com/list.txt", false);
+        assert_eq!(list.health(now), ListHealth::Pending);
+
+        list.last_updated = Some(now - 60);
+        list.rule_count = 100;
+        assert_eq!(list.health(now), ListHealth::Healthy);
+
+        list.parse_errors = 60;
+        assert_eq!(list.health(now), ListHealth::Broken);
+        list.parse_errors = 0;
+
+        list.last_updated = Some(now - STALE_AFTER_SECS - 1);
+        assert_eq!(list.health(now), ListHealth::Stale);
+
+        list.last_error = Some("HTTP 404".to_string());
+        assert_eq!(list.health(now), ListHealth::Failing);
+
+        list.enabled = false;
+        assert_eq!(list.health(now), ListHealth::Disabled);
+    }
+
+    #[test]
+    fn test_with_builtins() {
+        let mut easylist = FilterListStatus::new("EasyList", EASYLIST_URL, false);
+        easylist.enabled = false;
+        let custom = FilterListStatus::new("Mine", "https://example.com/mine.txt", true);
+
+        let lists = with_builtins(vec![custom, easylist]);
+        let urls: Vec<&str> = lists.iter().map(|l| l.url.as_str()).collect();
+        assert_eq!(urls, vec![EASYLIST_URL, EASYPRIVACY_URL, "https://example.com/mine.txt"]);
+        // Stored state is kept, but only the real built-ins are flagged as such
+        assert!(!lists[0].enabled);
+        assert!(lists[0].builtin && lists[1].builtin && !lists[2].builtin);
+    }
+
+    #[test]
+    fn test_normalize_list_url() {
+        assert_eq!(normalize_list_url(" https://example.com/list.txt ").unwrap(), "https://example.com/list.txt");
+        assert!(normalize_list_url("ftp://example.com/list.txt").is_err());
+        assert!(normalize_list_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_list_names() {
+        assert_eq!(list_name_from_url("https://example.com/filters/fanboy-annoyance.txt"), "fanboy-annoyance");
+        assert_eq!(list_name_from_url("https://lists.example.com/"), "lists.example.com");
+        assert_eq!(list_title(&["[Adblock Plus 2.0]", "! Title: Fanboy's Annoyance List", "||x^"]).as_deref(), Some("Fanboy's Annoyance List"));
+        assert_eq!(list_title(&["||x^"]), None);
+    }
+}
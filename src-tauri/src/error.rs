@@ -0,0 +1,134 @@
+// The error every Tauri command returns.
+//
+// It reaches the frontend as `{ code, message }`: the code says what kind of
+// failure it was, so the UI can decide what to tell the user (or whether to
+// offer a retry), and the message is for showing or logging as is. Tests match
+// on the variant.
+//
+// Internal helpers mostly still fail with a String; `?` turns one into
+// `BrowserError::Internal`, so commands pick a more specific variant where they
+// know better.
+
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", content = "message", rename_all = "snake_case")]
+pub enum BrowserError {
+    NotFound(String),     // The tab, window or entry doesn't exist (anymore)
+    InvalidInput(String), // An argument was rejected, e.g. a URL that doesn't parse
+    NotAllowed(String),   // The caller may not do this, or the feature is turned off
+    Unsupported(String),  // Not available on this platform
+    Io(String),           // Reading or writing a file
+    Network(String),      // A request to another server
+    Webview(String),      // Tauri couldn't do it to the window or webview
+    Internal(String),     // Anything else
+}
+
+impl BrowserError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::NotAllowed(_) => "not_allowed",
+            Self::Unsupported(_) => "unsupported",
+            Self::Io(_) => "io",
+            Self::Network(_) => "network",
+            Self::Webview(_) => "webview",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(m)
+            | Self::InvalidInput(m)
+            | Self::NotAllowed(m)
+            | Self::Unsupported(m)
+            | Self::Io(m)
+            | Self::Network(m)
+            | Self::Webview(m)
+            | Self::Internal(m) => m,
+        }
+    }
+
+    /// A missing tab, for the many commands that take a tab id.
+    pub fn tab_not_found(tab_id: &str) -> Self {
+        Self::NotFound(format!("Tab not found: {}", tab_id))
+    }
+}
+
+impl fmt::Display for BrowserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for BrowserError {}
+
+impl From<String> for BrowserError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+impl From<&str> for BrowserError {
+    fn from(message: &str) -> Self {
+        Self::Internal(message.to_string())
+    }
+}
+
+impl From<std::io::Error> for BrowserError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound(e.to_string()),
+            std::io::ErrorKind::PermissionDenied => Self::NotAllowed(e.to_string()),
+            _ => Self::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for BrowserError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::InvalidInput(e.to_string())
+    }
+}
+
+impl From<url::ParseError> for BrowserError {
+    fn from(e: url::ParseError) -> Self {
+        Self::InvalidInput(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for BrowserError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for BrowserError {
+    fn from(e: tauri::Error) -> Self {
+        Self::Webview(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let json = serde_json::to_value(BrowserError::tab_not_found("tab-1")).unwrap();
+        assert_eq!(json, serde_json::json!({ "code": "not_found", "message": "Tab not found: tab-1" }));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(BrowserError::from("oops".to_string()), BrowserError::Internal("oops".to_string()));
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(BrowserError::from(missing).code(), "not_found");
+        let bad_url = url::Url::parse("not a url").unwrap_err();
+        assert_eq!(BrowserError::from(bad_url).code(), "invalid_input");
+        assert_eq!(BrowserError::NotAllowed("no".to_string()).to_string(), "no");
+    }
+}
@@ -1,13 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, Write};
 use std::path::{PathBuf};
 use std::sync::Mutex;
+use chrono::TimeZone;
 use serde::{Deserialize, Serialize};
 use url::Url;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Individual visits older than this are dropped from visits.log on startup.
+const VISIT_RETENTION_DAYS: u64 = 366;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct HistoryEntry {
     pub url: String,
     pub title: String,
@@ -24,29 +28,112 @@ pub struct HistoryEntryScoped {
     pub is_ghost_candidate: bool,
 }
 
+/// One page of the history listing, newest first.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub total: usize, // Total matching entries (for pagination)
+}
+
+/// One visit, for the activity calendar. The index only keeps each URL's latest
+/// visit, so visits are also appended to visits.log.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Visit {
+    url: String,
+    time: u64, // Unix timestamp in seconds
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityPeriod {
+    Week,
+    Month,
+    Year,
+}
+
+impl ActivityPeriod {
+    pub fn days(&self) -> u64 {
+        match self {
+            Self::Week => 7,
+            Self::Month => 30,
+            Self::Year => 365,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DomainVisits {
+    pub domain: String,
+    pub visits: u64,
+}
+
+/// Visits on one day, for the history calendar heatmap. Days without visits are left out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DayActivity {
+    pub date: String, // YYYY-MM-DD in the given time zone
+    pub start: u64,   // Unix seconds at the start of the day, for get_history_page's `from`
+    pub visits: u64,
+    pub top_domains: Vec<DomainVisits>,
+}
+
 pub struct HistoryStore {
     index: Mutex<HashMap<String, HistoryEntry>>,
     log_path: PathBuf,
+    visits: Mutex<Vec<Visit>>, // Oldest first
+    visits_path: PathBuf,
 }
 
 impl HistoryStore {
     pub fn new(app_data_dir: PathBuf) -> Self {
         fs::create_dir_all(&app_data_dir).ok();
         let log_path = app_data_dir.join("history.log");
+        let visits_path = app_data_dir.join("visits.log");
         
         let mut store = HistoryStore {
             index: Mutex::new(HashMap::new()),
             log_path,
+            visits: Mutex::new(Vec::new()),
+            visits_path,
         };
         
         // Load existing history on startup
         if let Err(e) = store.load_from_log() {
             eprintln!("Failed to load history: {}", e);
         }
+        if let Err(e) = store.load_visits() {
+            eprintln!("Failed to load visits: {}", e);
+        }
         
         store
     }
 
+    fn load_visits(&mut self) -> std::io::Result<()> {
+        if !self.visits_path.exists() {
+            return Ok(());
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let cutoff = now.saturating_sub(VISIT_RETENTION_DAYS * 86400);
+
+        let file = fs::File::open(&self.visits_path)?;
+        let mut loaded = 0;
+        let mut kept: Vec<Visit> = Vec::new();
+        for line in std::io::BufReader::new(file).lines() {
+            if let Ok(visit) = serde_json::from_str::<Visit>(&line?) {
+                loaded += 1;
+                if visit.time >= cutoff {
+                    kept.push(visit);
+                }
+            }
+        }
+        kept.sort_by_key(|v| v.time);
+        let pruned = loaded != kept.len();
+        *self.visits.lock().unwrap() = kept;
+        if pruned {
+            self.rewrite_visits()?;
+        }
+        Ok(())
+    }
+
     fn load_from_log(&mut self) -> std::io::Result<()> {
         if !self.log_path.exists() {
             return Ok(());
@@ -116,6 +203,19 @@ impl HistoryStore {
                 eprintln!("Failed to write to history log: {}", e);
             }
         }
+
+        let visit = Visit { url: normalized, time: now };
+        if let Ok(json) = serde_json::to_string(&visit) {
+            let appended = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.visits_path)
+                .and_then(|mut file| writeln!(file, "{}", json));
+            if let Err(e) = appended {
+                eprintln!("Failed to write to visits log: {}", e);
+            }
+        }
+        self.visits.lock().unwrap().push(visit);
     }
 
     pub fn search(&self, query: String, limit: usize) -> Vec<HistoryEntryScoped> {
@@ -183,6 +283,183 @@ impl HistoryStore {
         results
     }
     
+    /// Paginated listing sorted by last visit (newest first).
+    /// `from`/`to` (Unix seconds, `to` exclusive) restrict the listing to a time range, e.g. one day.
+    pub fn list(&self, offset: usize, limit: usize, from: Option<u64>, to: Option<u64>) -> HistoryPage {
+        let index = self.index.lock().unwrap();
+        let mut entries: Vec<HistoryEntry> = index.values()
+            .filter(|e| in_range(e.last_visit, from, to))
+            .cloned()
+            .collect();
+
+        entries.sort_by(|a, b| b.last_visit.cmp(&a.last_visit).then_with(|| a.url.cmp(&b.url)));
+        let total = entries.len();
+        let entries = entries.into_iter().skip(offset).take(limit).collect();
+
+        HistoryPage { entries, total }
+    }
+
+    /// Visits per day since `from` (Unix seconds), oldest day first, with up to
+    /// `top` of each day's most visited domains. Days follow `tz`.
+    pub fn activity<Tz: TimeZone>(&self, from: u64, tz: &Tz, top: usize) -> Vec<DayActivity> {
+        let visits = self.visits.lock().unwrap();
+        let mut days: BTreeMap<chrono::NaiveDate, HashMap<String, u64>> = BTreeMap::new();
+        for visit in visits.iter().filter(|v| v.time >= from) {
+            let Some(time) = tz.timestamp_opt(visit.time as i64, 0).earliest() else { continue };
+            let domain = Url::parse(&visit.url).ok()
+                .and_then(|u| u.host_str().map(|h| h.trim_start_matches("www.").to_string()))
+                .unwrap_or_default();
+            *days.entry(time.date_naive()).or_default().entry(domain).or_default() += 1;
+        }
+
+        days.into_iter()
+            .map(|(date, domains)| {
+                let start = date.and_hms_opt(0, 0, 0)
+                    .and_then(|midnight| tz.from_local_datetime(&midnight).earliest())
+                    .map_or(0, |t| t.timestamp().max(0) as u64);
+                let visits = domains.values().sum();
+                let mut top_domains: Vec<DomainVisits> = domains.into_iter()
+                    .filter(|(domain, _)| !domain.is_empty())
+                    .map(|(domain, visits)| DomainVisits { domain, visits })
+                    .collect();
+                top_domains.sort_by(|a, b| b.visits.cmp(&a.visits).then_with(|| a.domain.cmp(&b.domain)));
+                top_domains.truncate(top);
+                DayActivity { date: date.format("%Y-%m-%d").to_string(), start, visits, top_domains }
+            })
+            .collect()
+    }
+
+    /// Drops visits older than the retention period. Returns how many.
+    pub fn prune_visits(&self, now: u64) -> std::io::Result<usize> {
+        let cutoff = now.saturating_sub(VISIT_RETENTION_DAYS * 86400);
+        let removed = {
+            let mut visits = self.visits.lock().unwrap();
+            let before = visits.len();
+            visits.retain(|v| v.time >= cutoff);
+            before - visits.len()
+        };
+        if removed > 0 {
+            self.rewrite_visits()?;
+        }
+        Ok(removed)
+    }
+
+    /// Size of history.log, which grows with every visit until it's compacted.
+    pub fn log_bytes(&self) -> u64 {
+        fs::metadata(&self.log_path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// Remove a single URL. Returns false if it wasn't in history.
+    pub fn delete_entry(&self, url: &str) -> std::io::Result<bool> {
+        let removed = {
+            let mut index = self.index.lock().unwrap();
+            index.remove(url).or_else(|| index.remove(&normalize_url(url)))
+        };
+        if let Some(entry) = &removed {
+            // The log is append-only, so deletions only stick once it is rewritten
+            self.compact()?;
+            self.visits.lock().unwrap().retain(|v| v.url != entry.url);
+            self.rewrite_visits()?;
+        }
+        Ok(removed.is_some())
+    }
+
+    /// Remove every entry last visited within [from, to). Returns the number removed.
+    /// NOTE: Only the most recent visit per URL is stored, so an entry visited both
+    /// inside and after the range is kept.
+    pub fn delete_range(&self, from: u64, to: u64) -> std::io::Result<usize> {
+        let removed = {
+            let mut index = self.index.lock().unwrap();
+            let before = index.len();
+            index.retain(|_, e| !in_range(e.last_visit, Some(from), Some(to)));
+            before - index.len()
+        };
+        if removed > 0 {
+            self.compact()?;
+        }
+        // Individual visits have their own times, so these go even when the entry stays
+        let visits_removed = {
+            let mut visits = self.visits.lock().unwrap();
+            let before = visits.len();
+            visits.retain(|v| !in_range(v.time, Some(from), Some(to)));
+            before - visits.len()
+        };
+        if visits_removed > 0 {
+            self.rewrite_visits()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn clear(&self) -> std::io::Result<()> {
+        self.index.lock().unwrap().clear();
+        self.visits.lock().unwrap().clear();
+        self.compact()?;
+        self.rewrite_visits()
+    }
+
+    fn rewrite_visits(&self) -> std::io::Result<()> {
+        let visits = self.visits.lock().unwrap();
+        let tmp_path = self.visits_path.with_extension("log.tmp");
+        {
+            let mut file = std::fs::File::create(&tmp_path)?;
+            for visit in visits.iter() {
+                writeln!(file, "{}", serde_json::to_string(visit).unwrap())?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(tmp_path, &self.visits_path)
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.index.lock().unwrap().contains_key(&normalize_url(url))
+    }
+
+    /// Merges entries brought over from another browser, see modules::importer.
+    /// Counts and the last visit take the larger of the two, so importing the
+    /// same data again changes nothing. Changed entries are appended to the log
+    /// like visits are. Returns how many URLs were new.
+    pub fn import(&self, entries: &[HistoryEntry]) -> std::io::Result<usize> {
+        let mut added = 0;
+        let mut changed = Vec::new();
+        {
+            let mut index = self.index.lock().unwrap();
+            for imported in entries {
+                let url = normalize_url(&imported.url);
+                match index.get_mut(&url) {
+                    Some(entry) => {
+                        let mut merged = entry.clone();
+                        merged.last_visit = entry.last_visit.max(imported.last_visit);
+                        merged.visit_count = entry.visit_count.max(imported.visit_count);
+                        merged.typed_count = entry.typed_count.max(imported.typed_count);
+                        if merged.title.is_empty() {
+                            merged.title = imported.title.clone();
+                        }
+                        if merged != *entry {
+                            *entry = merged.clone();
+                            changed.push(merged);
+                        }
+                    }
+                    None => {
+                        let entry = HistoryEntry { url: url.clone(), ..imported.clone() };
+                        index.insert(url, entry.clone());
+                        changed.push(entry);
+                        added += 1;
+                    }
+                }
+            }
+        }
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&self.log_path)?;
+        let mut writer = std::io::BufWriter::new(file);
+        for entry in &changed {
+            writeln!(writer, "{}", serde_json::to_string(entry).unwrap())?;
+        }
+        writer.flush()?;
+        Ok(added)
+    }
+
     pub fn compact(&self) -> std::io::Result<()> {
         let index = self.index.lock().unwrap();
         // Atomic write: write to .tmp then rename
@@ -202,6 +479,10 @@ impl HistoryStore {
     }
 }
 
+fn in_range(timestamp: u64, from: Option<u64>, to: Option<u64>) -> bool {
+    from.map_or(true, |f| timestamp >= f) && to.map_or(true, |t| timestamp < t)
+}
+
 fn normalize_url(url: &str) -> String {
     // Basic normalization:
     // 1. Ensure trailing slash for root domains if missing is handled by Url parser usually
@@ -214,3 +495,175 @@ fn normalize_url(url: &str) -> String {
         url.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_visits(dir: &std::path::Path, visits: &[(&str, u64)]) -> HistoryStore {
+        let store = HistoryStore::new(dir.to_path_buf());
+        for (url, _) in visits {
+            store.add_visit(url.to_string(), None, false);
+        }
+        {
+            // Backdate visits for deterministic ordering
+            let mut index = store.index.lock().unwrap();
+            for (url, ts) in visits {
+                index.get_mut(&normalize_url(url)).unwrap().last_visit = *ts;
+            }
+        }
+        store.compact().unwrap();
+        store
+    }
+
+    #[test]
+    fn test_import_merges_and_is_repeatable() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_visits(dir.path(), &[("https://a.com/", 500)]);
+        let imported = |url: &str, last_visit, visit_count| HistoryEntry {
+            url: url.to_string(),
+            title: "Imported".to_string(),
+            last_visit,
+            visit_count,
+            typed_count: 1,
+        };
+        let entries = vec![imported("https://a.com", 100, 9), imported("https://b.com/x", 200, 2)];
+
+        assert_eq!(store.import(&entries).unwrap(), 1);
+        assert_eq!(store.import(&entries).unwrap(), 0);
+
+        let reopened = HistoryStore::new(dir.path().to_path_buf());
+        let page = reopened.list(0, 10, None, None);
+        assert_eq!(page.total, 2);
+        let a = page.entries.iter().find(|e| e.url == "https://a.com/").unwrap();
+        assert_eq!((a.last_visit, a.visit_count, a.title.as_str()), (500, 9, "Imported"));
+        assert!(reopened.contains("https://b.com/x"));
+    }
+
+    #[test]
+    fn test_list_paginates_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_visits(dir.path(), &[
+            ("https://a.com/", 100),
+            ("https://b.com/", 300),
+            ("https://c.com/", 200),
+        ]);
+
+        let page = store.list(0, 2, None, None);
+        assert_eq!(page.total, 3);
+        assert_eq!(page.entries.iter().map(|e| e.url.as_str()).collect::<Vec<_>>(), vec!["https://b.com/", "https://c.com/"]);
+
+        let page = store.list(2, 2, None, None);
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].url, "https://a.com/");
+
+        // Time range: [150, 300)
+        let page = store.list(0, 10, Some(150), Some(300));
+        assert_eq!(page.total, 1);
+        assert_eq!(page.entries[0].url, "https://c.com/");
+    }
+
+    #[test]
+    fn test_delete_entry_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_visits(dir.path(), &[("https://a.com/", 100), ("https://b.com/", 200)]);
+
+        assert!(store.delete_entry("https://a.com").unwrap()); // normalized match
+        assert!(!store.delete_entry("https://missing.com/").unwrap());
+
+        let reloaded = HistoryStore::new(dir.path().to_path_buf());
+        assert_eq!(reloaded.list(0, 10, None, None).total, 1);
+        assert!(reloaded.search("a.com".to_string(), 10).is_empty());
+    }
+
+    #[test]
+    fn test_delete_range_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_visits(dir.path(), &[
+            ("https://a.com/", 100),
+            ("https://b.com/", 200),
+            ("https://c.com/", 300),
+        ]);
+
+        assert_eq!(store.delete_range(150, 300).unwrap(), 1);
+        assert_eq!(store.list(0, 10, None, None).total, 2);
+
+        store.clear().unwrap();
+        assert_eq!(store.list(0, 10, None, None).total, 0);
+        assert_eq!(HistoryStore::new(dir.path().to_path_buf()).list(0, 10, None, None).total, 0);
+    }
+
+    fn store_with_timed_visits(dir: &std::path::Path, visits: &[(&str, u64)]) -> HistoryStore {
+        let store = HistoryStore::new(dir.to_path_buf());
+        for (url, _) in visits {
+            store.add_visit(url.to_string(), None, false);
+        }
+        {
+            let mut logged = store.visits.lock().unwrap();
+            for (visit, (_, ts)) in logged.iter_mut().zip(visits) {
+                visit.time = *ts;
+            }
+        }
+        store
+    }
+
+    #[test]
+    fn test_activity_counts_visits_per_day() {
+        const DAY: u64 = 86400;
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_timed_visits(dir.path(), &[
+            ("https://www.a.com/1", DAY + 10),
+            ("https://a.com/2", DAY + 20),
+            ("https://b.com/", DAY + 30),
+            ("https://b.com/", 3 * DAY + 5),
+            ("https://c.com/", 10),
+        ]);
+
+        let days = store.activity(DAY, &chrono::Utc, 1);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, "1970-01-02");
+        assert_eq!(days[0].start, DAY);
+        assert_eq!(days[0].visits, 3);
+        assert_eq!(days[0].top_domains, vec![DomainVisits { domain: "a.com".to_string(), visits: 2 }]);
+        assert_eq!(days[1].date, "1970-01-04");
+        assert_eq!(days[1].visits, 1);
+    }
+
+    #[test]
+    fn test_prune_visits_drops_expired() {
+        const DAY: u64 = 86400;
+        let dir = tempfile::tempdir().unwrap();
+        let now = 400 * DAY;
+        let store = store_with_timed_visits(dir.path(), &[("https://a.com/", DAY), ("https://b.com/", now - DAY)]);
+        assert_eq!(store.prune_visits(now).unwrap(), 1);
+        assert_eq!(store.prune_visits(now).unwrap(), 0);
+        assert_eq!(store.visits.lock().unwrap()[0].url, "https://b.com/");
+    }
+
+    #[test]
+    fn test_visit_deletions_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_timed_visits(dir.path(), &[("https://a.com/", 100), ("https://b.com/", 200)]);
+        store.delete_range(0, 150).unwrap();
+        store.delete_entry("https://b.com/").unwrap();
+        store.add_visit("https://c.com/".to_string(), None, false);
+
+        let reloaded = HistoryStore::new(dir.path().to_path_buf());
+        let urls: Vec<String> = reloaded.visits.lock().unwrap().iter().map(|v| v.url.clone()).collect();
+        assert_eq!(urls, vec!["https://c.com/".to_string()]);
+    }
+
+    #[test]
+    fn test_cleared_entries_do_not_resurrect() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_visits(dir.path(), &[("https://a.com/", 100)]);
+
+        store.clear().unwrap();
+        store.add_visit("https://b.com/".to_string(), None, false);
+
+        // Visits appended after a clear must not bring back the wiped log
+        let reloaded = HistoryStore::new(dir.path().to_path_buf());
+        let page = reloaded.list(0, 10, None, None);
+        assert_eq!(page.entries.iter().map(|e| e.url.as_str()).collect::<Vec<_>>(), vec!["https://b.com/"]);
+    }
+}
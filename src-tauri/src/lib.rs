@@ -10,6 +10,9 @@ pub mod adblock_manager;
 pub mod history;
 pub mod settings;
 
+// The error type every command returns
+pub mod error;
+
 // Shared state (new)
 pub mod state;
 
@@ -1,4 +1,4 @@
-use tauri::{AppHandle, Manager, WebviewUrl, WebviewBuilder, PhysicalPosition, PhysicalSize, Window, Emitter, TitleBarStyle};
+use tauri::{AppHandle, Manager, WebviewUrl, PhysicalPosition, PhysicalSize, Window, Emitter};
 use tauri::menu::{MenuBuilder, SubmenuBuilder, PredefinedMenuItem, MenuItemBuilder};
 use url::Url;
 use std::fs;
@@ -7,20 +7,100 @@ use serde::{Deserialize, Serialize};
 use std::time::{Instant, Duration, SystemTime, UNIX_EPOCH};
 
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
 use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64};
 
 // Import from our library crate
-use sovereign_browser_lib::history::{HistoryStore, HistoryEntryScoped};
-use sovereign_browser_lib::adblock_manager::AdBlockManager;
-use sovereign_browser_lib::settings::Settings;
+use sovereign_browser_lib::history::{ActivityPeriod, DayActivity, HistoryStore, HistoryEntryScoped, HistoryPage};
+use sovereign_browser_lib::adblock_manager::{AdBlockManager, AdblockDashboard, AllowlistExport, AllowlistImportSummary, FilterListHealth, FilterListStatus, SiteMode, SiteModeInfo};
+use sovereign_browser_lib::error::BrowserError;
+use sovereign_browser_lib::settings::{Settings, SearchEngine};
 use sovereign_browser_lib::state::{Tab, AppState, DropdownPayload};
 use sovereign_browser_lib::modules::navigation::smart_parse_url;
 #[cfg(not(target_os = "macos"))]
-use sovereign_browser_lib::modules::navigation::guess_request_type;
-use sovereign_browser_lib::modules::devtools::DevToolsManager;
+use sovereign_browser_lib::modules::devtools::{self, DevToolsManager};
 use sovereign_browser_lib::modules::closed_tabs;
 use sovereign_browser_lib::modules::closed_tabs_store;
-use sovereign_browser_lib::modules::tabs;
+use sovereign_browser_lib::modules::session_store;
+use sovereign_browser_lib::modules::stash::{self, StashStore, StashedTab, TabStash};
+use sovereign_browser_lib::modules::tabs::{self, WindowMetrics};
+use sovereign_browser_lib::modules::tab_engine::AfterClose;
+use sovereign_browser_lib::modules::drag_out;
+use sovereign_browser_lib::modules::badges;
+use sovereign_browser_lib::modules::clipboard::{self, ClipboardManager};
+use sovereign_browser_lib::modules::focus::{FocusManager, FocusTarget};
+use sovereign_browser_lib::modules::doh::DohManager;
+use sovereign_browser_lib::modules::proxy;
+use sovereign_browser_lib::modules::fingerprint::{self, SpoofingProfile};
+use sovereign_browser_lib::modules::url_display;
+use sovereign_browser_lib::modules::site_storage::{self, StorageInspector};
+use sovereign_browser_lib::modules::site_data;
+use sovereign_browser_lib::modules::https_only::{self, HttpsOnlyManager};
+use sovereign_browser_lib::modules::element_picker;
+use sovereign_browser_lib::modules::channel_blocking;
+use sovereign_browser_lib::modules::frames::FrameTracker;
+use sovereign_browser_lib::modules::block_stats::{self, BlockStatsManager};
+use sovereign_browser_lib::modules::user_agent::{self, UserAgentManager};
+use sovereign_browser_lib::modules::data_saver::{self, DataSaverManager};
+use sovereign_browser_lib::modules::image_blocking::ImageBlocker;
+use sovereign_browser_lib::modules::regional_lists;
+use sovereign_browser_lib::modules::safebrowsing::{self, SafeBrowsingManager};
+use sovereign_browser_lib::modules::audio_output::{self, AudioOutputManager};
+use sovereign_browser_lib::modules::media_controls::{self, MediaControlsManager};
+use sovereign_browser_lib::modules::network_log::{self, NetworkLogManager};
+use sovereign_browser_lib::modules::permissions::PermissionsManager;
+use sovereign_browser_lib::modules::screen_capture;
+use sovereign_browser_lib::modules::console_log::{self, ConsoleLogManager};
+use sovereign_browser_lib::modules::webauthn;
+use sovereign_browser_lib::modules::task_manager::{self, TaskManager};
+use sovereign_browser_lib::modules::video_popout::{self, VideoPopoutManager};
+use sovereign_browser_lib::modules::read_aloud::{self, ReadAloudManager};
+use sovereign_browser_lib::modules::tab_audio;
+use sovereign_browser_lib::modules::text_replace::{self, TextReplaceManager};
+use sovereign_browser_lib::modules::snippets::{self, SnippetsManager};
+use sovereign_browser_lib::modules::tab_search;
+use sovereign_browser_lib::modules::tab_windows;
+use sovereign_browser_lib::modules::form_audit;
+use sovereign_browser_lib::modules::email_alias::{self, EmailAliasManager};
+use sovereign_browser_lib::modules::layout::{self, TOTAL_TOOLBAR_HEIGHT};
+use sovereign_browser_lib::modules::split_view::{self, SplitViewManager};
+use sovereign_browser_lib::modules::totp::{self, TotpVault};
+use sovereign_browser_lib::modules::breach_check::{self, BreachCheckStore};
+use sovereign_browser_lib::modules::top_sites::{self, TopSitesStore};
+use sovereign_browser_lib::modules::thumbnails::{self, ThumbnailCache};
+use sovereign_browser_lib::modules::annotations::{self, AnnotationStore};
+use sovereign_browser_lib::modules::site_apps::{self, SiteAppStore};
+use sovereign_browser_lib::modules::tracking_params;
+use sovereign_browser_lib::modules::privacy_report::{self, PrivacyReportStore};
+use sovereign_browser_lib::modules::maintenance::{self, MaintenanceScheduler};
+use sovereign_browser_lib::modules::external_protocols::{self, ExternalProtocolStore};
+use sovereign_browser_lib::modules::headless;
+use sovereign_browser_lib::modules::{cli, profile};
+use sovereign_browser_lib::modules::titlebar;
+use sovereign_browser_lib::modules::bookmarks::{self, BookmarkStore};
+use sovereign_browser_lib::modules::containers::{self, ContainerStore};
+use sovereign_browser_lib::modules::importer;
+use sovereign_browser_lib::modules::dropdown;
+use sovereign_browser_lib::modules::user_data::{self, Manifest, RestoreSummary};
+use sovereign_browser_lib::modules::touch;
+use sovereign_browser_lib::modules::text_input;
+use sovereign_browser_lib::modules::handoff;
+use sovereign_browser_lib::modules::dock::{self, DockManager};
+use sovereign_browser_lib::modules::guest;
+use sovereign_browser_lib::modules::jump_list;
+use sovereign_browser_lib::modules::offline;
+use sovereign_browser_lib::modules::list_updates;
+use sovereign_browser_lib::modules::site_search;
+use sovereign_browser_lib::modules::link_hover;
+use sovereign_browser_lib::modules::browsing_webview::{self, check_caller, Caller, DataStore};
+use sovereign_browser_lib::modules::tab_status;
+use sovereign_browser_lib::modules::appearance::{self, WindowMaterial};
+use sovereign_browser_lib::modules::toolbar_layout::{self, ToolbarWidget};
+use sovereign_browser_lib::modules::browsing_data;
+use sovereign_browser_lib::modules::commands::{self, BrowserCommand};
+use sovereign_browser_lib::modules::suggest::{self, SuggestManager};
+use sovereign_browser_lib::modules::context_menu::{self, ContextAction, LinkDisposition};
 
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -30,16 +110,15 @@ struct Suggestion {
 }
 
 fn get_suggestions_path(app: &AppHandle) -> PathBuf {
-    let app_data_dir = app.path().app_data_dir().expect("Failed to get app data dir");
-    fs::create_dir_all(&app_data_dir).ok();
+    let app_data_dir = profile::data_dir(app).expect("Failed to get app data dir");
     app_data_dir.join("suggestions.json")
 }
 
-fn save_suggestion_to_file(app: &AppHandle, text: String) -> Result<(), String> {
+fn save_suggestion_to_file(app: &AppHandle, text: String) -> Result<(), BrowserError> {
     let path = get_suggestions_path(app);
     
     let mut suggestions: Vec<Suggestion> = if path.exists() {
-        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let content = fs::read_to_string(&path)?;
         serde_json::from_str(&content).unwrap_or_default()
     } else {
         Vec::new()
@@ -51,14 +130,18 @@ fn save_suggestion_to_file(app: &AppHandle, text: String) -> Result<(), String>
     };
     suggestions.push(new_suggestion);
     
-    let json = serde_json::to_string_pretty(&suggestions).map_err(|e| e.to_string())?;
-    fs::write(&path, json).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&suggestions).map_err(|e| BrowserError::Internal(e.to_string()))?;
+    fs::write(&path, json)?;
     
     Ok(())
 }
 
 // Show settings window
 fn show_settings_window(app: &AppHandle) {
+    if let Some(state) = app.try_state::<AppState>() {
+        let active_id = state.active_tab_id.lock().unwrap().clone();
+        state.focus.lock().request(FocusTarget::Settings, active_id.as_deref());
+    }
     if let Some(win) = app.get_window("settings") {
         let _ = win.set_focus();
         return;
@@ -78,8 +161,43 @@ fn show_settings_window(app: &AppHandle) {
     .focused(true)
     .build();
     
-    if let Err(e) = settings_window {
-        println!("Failed to create settings window: {:?}", e);
+    match settings_window {
+        Ok(win) => {
+            // Closing settings returns focus to wherever it was before
+            let h = app.clone();
+            win.on_window_event(move |event| {
+                if let tauri::WindowEvent::Destroyed = event {
+                    if let Some(state) = h.try_state::<AppState>() {
+                        restore_focus(&h, &state);
+                    }
+                }
+            });
+        }
+        Err(e) => println!("Failed to create settings window: {:?}", e),
+    }
+}
+
+// Task Manager window (see modules::task_manager)
+fn show_task_manager_window(app: &AppHandle) {
+    if let Some(win) = app.get_window("task-manager") {
+        let _ = win.set_focus();
+        return;
+    }
+
+    let task_manager_window = tauri::WebviewWindowBuilder::new(
+        app,
+        "task-manager",
+        tauri::WebviewUrl::App("task-manager.html".into())
+    )
+    .title("Task Manager")
+    .inner_size(560.0, 400.0)
+    .resizable(true)
+    .center()
+    .focused(true)
+    .build();
+
+    if let Err(e) = task_manager_window {
+        println!("Failed to create task manager window: {:?}", e);
     }
 }
 
@@ -109,54 +227,76 @@ fn show_suggestion_window(app: &AppHandle) {
     }
 }
 
-// --- Layout Constants ---
-const TAB_BAR_HEIGHT: f64 = 40.0;
-const URL_BAR_HEIGHT: f64 = 56.0; // Includes padding
-const TOTAL_TOOLBAR_HEIGHT: f64 = TAB_BAR_HEIGHT + URL_BAR_HEIGHT;
 
 
 // --- Ad Blocking Commands ---
 
 #[tauri::command]
-fn get_cosmetic_rules(app: AppHandle, state: tauri::State<AppState>, url: String) {
+fn get_cosmetic_rules(app: AppHandle, webview: tauri::Webview, state: tauri::State<AppState>, url: String) -> Result<(), BrowserError> {
+    if !state.settings.read().unwrap().block_trackers {
+        return Ok(());
+    }
     let adblock = state.adblock.clone();
     let app_clone = app.clone();
     
     tauri::async_runtime::spawn(async move {
-        let css = adblock.get_cosmetic_css(&url);
-        if !css.is_empty() {
-            let _ = app_clone.emit("apply-cosmetic-css", serde_json::json!({ "css": css }));
+        let resources = adblock.get_cosmetic_resources(&url);
+        if !resources.css.is_empty() {
+            let _ = app_clone.emit("apply-cosmetic-css", serde_json::json!({ "css": resources.css }));
+        }
+        // Scriptlets go straight into the requesting page, once per document. They run as
+        // soon as the page asks (at document start), which beats most but not all page scripts.
+        if !resources.scriptlets.is_empty() {
+            let script = format!(
+                "(function() {{ if (window.__sovereignScriptlets || location.href !== {url}) return; \
+                 window.__sovereignScriptlets = true; {body}\n}})();",
+                url = serde_json::to_string(&url).unwrap_or_default(),
+                body = resources.scriptlets,
+            );
+            if let Err(e) = webview.eval(&script) {
+                eprintln!("[AdBlock] Failed to inject scriptlets: {}", e);
+            }
         }
     });
+    Ok(())
 }
 
 #[tauri::command]
-fn set_site_exception(state: tauri::State<AppState>, url: String, duration_type: String) {
+fn set_site_exception(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    duration_type: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
     let adblock = state.adblock.clone();
     
     // Extract domain from URL
-    if let Ok(parsed) = Url::parse(&url) {
-        if let Some(domain) = parsed.domain() {
-            let duration = match duration_type.as_str() {
-                "1hour" => Some(Duration::from_secs(3600)),
-                "24hours" => Some(Duration::from_secs(86400)),
-                "forever" => None,
-                "off" => {
-                    adblock.remove_exception(domain);
-                    return;
-                }
-                _ => return, // Invalid input
-            };
-            
-            adblock.add_exception(domain.to_string(), duration);
+    let parsed = Url::parse(&url)?;
+    let domain = parsed.domain().ok_or_else(|| BrowserError::InvalidInput(format!("No site to make an exception for: {}", url)))?;
+    let duration = match duration_type.as_str() {
+        "1hour" => Some(Duration::from_secs(3600)),
+        "24hours" => Some(Duration::from_secs(86400)),
+        "forever" => None,
+        "off" => {
+            adblock.remove_exception(domain);
+            return Ok(());
         }
-    }
+        _ => return Err(BrowserError::InvalidInput(format!("Unknown duration: {}", duration_type))),
+    };
+    
+    adblock.add_exception(domain.to_string(), duration);
+    Ok(())
 }
 
 #[tauri::command]
-fn get_exceptions(state: tauri::State<AppState>) -> Vec<serde_json::Value> {
+fn get_exceptions(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<serde_json::Value>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
     let exceptions = state.adblock.get_exceptions();
-    exceptions
+    Ok(exceptions
         .into_iter()
         .map(|(domain, expiry)| {
             let expiry_str = match expiry {
@@ -173,90 +313,578 @@ fn get_exceptions(state: tauri::State<AppState>) -> Vec<serde_json::Value> {
                 "expiry": expiry_str
             })
         })
-        .collect()
+        .collect())
+}
+
+/// Writes the active site exceptions to `path` (see `AllowlistExport` for the format).
+/// Returns how many were written. Settings picks the path; pages may not.
+#[tauri::command]
+fn export_allowlist(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    path: String,
+) -> Result<usize, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let export = state.adblock.export_allowlist();
+    let json = serde_json::to_string_pretty(&export).map_err(|e| BrowserError::Internal(e.to_string()))?;
+    std::fs::write(&path, json)?;
+    Ok(export.exceptions.len())
+}
+
+#[tauri::command]
+fn import_allowlist(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    path: String,
+) -> Result<AllowlistImportSummary, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let json = std::fs::read_to_string(&path)?;
+    let export: AllowlistExport = serde_json::from_str(&json)
+        .map_err(|e| BrowserError::InvalidInput(format!("Not an allowlist export: {}", e)))?;
+    state.adblock.import_allowlist(export).map_err(BrowserError::InvalidInput)
+}
+
+/// Writes history, bookmarks, settings, site exceptions, closed tabs and app
+/// link decisions to one archive at `path` (see modules::user_data).
+#[tauri::command]
+fn export_user_data(webview: tauri::Webview, state: tauri::State<AppState>, path: String) -> Result<Manifest, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let manifest = user_data::write_archive(std::path::Path::new(&path), &user_data::collect(&state))
+        .map_err(BrowserError::Io)?;
+    println!("[UserData] Exported {:?} to {}", manifest.counts, path);
+    Ok(manifest)
+}
+
+/// Merges an archive from `export_user_data` in; its settings replace the current ones.
+#[tauri::command]
+fn import_user_data(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    path: String,
+) -> Result<RestoreSummary, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let (_, data) = user_data::read_archive(std::path::Path::new(&path)).map_err(BrowserError::InvalidInput)?;
+    let (summary, settings) = user_data::restore(&state, data).map_err(BrowserError::Io)?;
+    let closed = closed_tabs_store::ClosedTabsStore { tabs: state.closed_tabs.lock().unwrap().clone() };
+    if let Err(e) = closed.save(&app) {
+        eprintln!("[UserData] Failed to save closed tabs: {}", e);
+    }
+    if let Some(settings) = settings {
+        apply_settings(app, state, settings)?;
+    }
+    println!("[UserData] Imported {:?}", summary);
+    Ok(summary)
+}
+
+#[tauri::command]
+fn set_exception_note(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    domain: String,
+    note: Option<String>,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    state.adblock.set_exception_note(&domain, note).map_err(BrowserError::NotFound)
+}
+
+// --- Ad Blocking Dashboard (about:adblock) ---
+
+#[tauri::command]
+fn get_adblock_dashboard(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<AdblockDashboard, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let enabled = state.settings.read().unwrap().block_trackers;
+    Ok(state.adblock.dashboard(enabled))
+}
+
+#[tauri::command]
+fn get_adblock_site_mode(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+) -> Result<SiteModeInfo, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.adblock.site_mode(&site))
+}
+
+/// Sets a site to standard blocking or allows it (optionally for `duration_secs`).
+#[tauri::command]
+fn set_adblock_site_mode(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    mode: SiteMode,
+    duration_secs: Option<u64>,
+) -> Result<SiteModeInfo, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let domain = AdBlockManager::normalize_domain(&site).ok_or_else(|| BrowserError::InvalidInput(format!("Invalid site: {}", site)))?;
+    match mode {
+        SiteMode::Standard => state.adblock.remove_exception(&domain),
+        SiteMode::Allowed => state.adblock.add_exception(domain.clone(), duration_secs.map(Duration::from_secs)),
+        SiteMode::Relaxed => return Err(BrowserError::NotAllowed("Relaxed mode is built in and can't be assigned".to_string())),
+    }
+    Ok(state.adblock.site_mode(&domain))
+}
+
+#[tauri::command]
+fn set_adblock_enabled(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    enabled: bool,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    settings.block_trackers = enabled;
+    apply_settings(app, state, settings)
+}
+
+/// Refetches all subscriptions and rebuilds the engine in the background.
+#[tauri::command]
+fn update_filter_lists(state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    state.adblock.spawn_update_thread();
+    Ok(())
 }
 
 #[tauri::command]
-fn save_suggestion(app: AppHandle, text: String) -> Result<(), String> {
+fn list_filter_lists(state: tauri::State<AppState>) -> Result<Vec<FilterListStatus>, BrowserError> {
+    Ok(state.adblock.filter_lists())
+}
+
+#[tauri::command]
+fn get_filter_list_health(state: tauri::State<AppState>) -> Result<Vec<FilterListHealth>, BrowserError> {
+    Ok(state.adblock.filter_list_health())
+}
+
+// Subscription changes rebuild the engine right away so they take effect
+// without waiting for the next startup. Only Settings may make them: pages go
+// through the confirmation in modules::filter_subscribe.
+
+#[tauri::command]
+fn add_filter_list(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+) -> Result<FilterListStatus, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let list = state.adblock.add_filter_list(&url, None).map_err(BrowserError::InvalidInput)?;
+    state.adblock.spawn_update_thread();
+    Ok(list)
+}
+
+#[tauri::command]
+fn remove_filter_list(webview: tauri::Webview, state: tauri::State<AppState>, url: String) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    state.adblock.remove_filter_list(&url).map_err(BrowserError::InvalidInput)?;
+    state.adblock.spawn_update_thread();
+    Ok(())
+}
+
+#[tauri::command]
+fn set_filter_list_enabled(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    enabled: bool,
+) -> Result<FilterListStatus, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let list = state.adblock.set_filter_list_enabled(&url, enabled).map_err(BrowserError::NotFound)?;
+    state.adblock.spawn_update_thread();
+    Ok(list)
+}
+
+// --- HTTPS-Only ---
+
+/// Called by the HTTPS-Only interstitial to load the blocked page over HTTP.
+#[tauri::command]
+fn continue_insecure(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    remember: bool,
+) -> Result<(), BrowserError> {
+    // Only the interstitial (an app page) may lift the block
+    check_caller(&webview, Caller::AppPage)?;
+    let target = Url::parse(&url)?;
+    if target.scheme() != "http" {
+        return Err(BrowserError::InvalidInput("Only http:// URLs can be continued".to_string()));
+    }
+
+    if remember {
+        let mut settings = state.settings.read().unwrap().clone();
+        let site = settings.add_https_only_exception(&url).map_err(BrowserError::InvalidInput)?;
+        println!("[HttpsOnly] Remembering {} as HTTP-only", site);
+        apply_settings(app.clone(), state, settings)?;
+    } else {
+        state.https_only.allow_once(webview.label(), target.as_str());
+    }
+    Ok(webview.navigate(target)?)
+}
+
+// --- Command Palette ---
+
+#[tauri::command]
+fn list_commands(query: String) -> Result<Vec<&'static BrowserCommand>, BrowserError> {
+    Ok(commands::search(&query))
+}
+
+#[tauri::command]
+fn execute_command(app: AppHandle, id: String) -> Result<(), BrowserError> {
+    commands::find(&id).ok_or_else(|| BrowserError::NotFound(format!("Unknown command: {}", id)))?;
+    run_browser_command(&app, &id);
+    Ok(())
+}
+
+/// Builds a menu item from the command registry so menu labels and accelerators
+/// match what the command palette shows.
+fn command_menu_item<M: Manager<tauri::Wry>>(manager: &M, id: &str) -> tauri::Result<tauri::menu::MenuItem<tauri::Wry>> {
+    let command = commands::find(id).expect("menu command missing from registry");
+    let mut builder = MenuItemBuilder::with_id(command.id, command.title);
+    if let Some(accelerator) = command.accelerator {
+        builder = builder.accelerator(accelerator);
+    }
+    builder.build(manager)
+}
+
+#[tauri::command]
+fn save_suggestion(app: AppHandle, webview: tauri::Webview, text: String) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
     save_suggestion_to_file(&app, text)
 }
 
+/// Opens the inspector for a tab (the active one by default), one window per tab.
 #[tauri::command]
-fn open_devtools(app: AppHandle, state: tauri::State<AppState>) {
-    let active_label = {
-        let active = state.active_tab_id.lock().unwrap();
+fn open_devtools(app: AppHandle, state: tauri::State<AppState>, tab_id: Option<String>) -> Result<(), BrowserError> {
+    let tab = {
+        let tab_id = tab_id.or_else(|| state.active_tab_id.lock().unwrap().clone());
         let tabs = state.tabs.lock().unwrap();
-        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+        tab_id.and_then(|id| tabs.iter().find(|t| t.id == id).map(|t| (t.id.clone(), t.webview_label.clone(), t.title.clone())))
     };
-    
-    if let Some(label) = active_label {
-        // 1. Trigger the specific tab to connect to bridge
-        if let Some(webview) = app.get_webview(&label) {
-            println!("[DevTools] Triggering loader for {}", label);
-            let _ = webview.eval("if (window.__SOVEREIGN_LOAD_DEVTOOLS__) window.__SOVEREIGN_LOAD_DEVTOOLS__();");
-        }
+    let (tab_id, label, title) = tab.ok_or_else(|| BrowserError::NotFound("No tab to inspect".to_string()))?;
 
-        // 2. Open the DevTools Frontend Window
-        if let Some(win) = app.get_window("devtools") {
-            let _ = win.set_focus();
-        } else {
-            // For MVP: Load the hosted Chii frontend which connects to our local bridge?
-            // Chii defaults to connecting to its own server. We need to point it to our WS.
-            // Actually, best to serve a minimal HTML that loads our target.js as a frontend?
-            // Or just use the bundled frontend assets.
-            // Since we don't have the assets bundled yet, we use a trick:
-            // We load a local HTML string that loads the chii frontend script from unpkg 
-            // and tells it to connect to localhost:9222.
-            
-            // NOTE: Chii frontend expects to be served relative to the backend usually.
-            // URL: https://chii.liriliri.io/front_end/chii_app.html?ws=localhost:9222/
-            
-            let devtools_url = "https://chii.liriliri.io/front_end/chii_app.html?ws=127.0.0.1:9222/client";
-            
-            let devtools_window = tauri::WebviewWindowBuilder::new(
-                &app,
-                "devtools",
-                tauri::WebviewUrl::External(Url::parse(devtools_url).unwrap())
-            )
-            .title("DevTools")
-            .inner_size(800.0, 600.0)
-            .build();
+    // 1. Trigger the specific tab to connect to bridge
+    if let Some(webview) = app.get_webview(&label) {
+        println!("[DevTools] Triggering loader for {}", label);
+        webview.eval(&state.devtools.loader_script(&label))?;
+    }
 
-            if let Err(e) = devtools_window {
-                println!("[DevTools] Failed to create window: {:?}", e);
-            }
-        }
+    // 2. Open (or focus) the tab's DevTools Frontend Window, attached to its target (see modules::devtools)
+    let window_label = format!("devtools-{}", tab_id);
+    if let Some(win) = app.get_webview_window(&window_label) {
+        win.set_focus()?;
+    } else {
+        // The bundled chii frontend, served by the bridge so it works offline
+        tauri::WebviewWindowBuilder::new(
+            &app,
+            &window_label,
+            tauri::WebviewUrl::External(state.devtools.frontend_url(&label))
+        )
+        .title(format!("DevTools - {}", title))
+        .inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| BrowserError::Webview(format!("Failed to open DevTools: {}", e)))?;
     }
+    Ok(())
 }
 
 // --- Settings Commands ---
 #[tauri::command]
-fn get_settings(state: tauri::State<AppState>) -> Settings {
-    state.settings.read().unwrap().clone()
+fn get_settings(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<Settings, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.settings.read().unwrap().clone())
 }
 
 #[tauri::command]
-fn save_settings(app: AppHandle, state: tauri::State<AppState>, settings: Settings) -> Result<(), String> {
+fn save_settings(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    settings: Settings,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    apply_settings(app, state, settings)
+}
+
+/// Saves, stores and applies new settings. Commands that change a single
+/// setting go through here after checking their own caller.
+fn apply_settings(app: AppHandle, state: tauri::State<AppState>, mut settings: Settings) -> Result<(), BrowserError> {
+    // The frontend only sends the proxy password when a new one was typed
+    let current_password = {
+        let current = state.settings.read().unwrap();
+        settings.proxy.keep_password(&current.proxy);
+        current.proxy.password.clone()
+    };
+    settings.proxy.validate().map_err(BrowserError::InvalidInput)?;
+    if settings.proxy.password != current_password {
+        let path = Settings::get_path(&app);
+        let dir = path.parent().ok_or_else(|| BrowserError::Io("No settings directory".to_string()))?;
+        proxy::store_password(dir, settings.proxy.password.as_deref()).map_err(BrowserError::Io)?;
+    }
+
     // 1. Save to disk (atomic write)
-    settings.save(&app)?;
+    settings.save(&app).map_err(BrowserError::Io)?;
     
     // 2. Update memory
-    {
+    let layout_changed = {
         let mut s = state.settings.write().unwrap();
+        let changed = s.chrome_layout() != settings.chrome_layout();
         *s = settings.clone();
-    }
+        changed
+    };
     
-    // 3. Propagate changes immediately to all windows
-    app.emit("settings-update", settings).map_err(|e| e.to_string())?;
+    // 3. Switch DoH resolver and proxy (already open tabs keep the proxy URL they
+    //    were created with)
+    state.doh.resolver.set_endpoint(settings.doh_endpoint());
+    state.doh.set_proxy_settings(settings.proxy.clone());
+    state.image_blocker.update(&settings);
+    audio_output::apply_to_tabs(&app, &state);
+    touch::apply_to_tabs(&app, &state);
+    text_input::apply_to_tabs(&app, &state);
+    text_input::set_dictation_menu(settings.dictation);
+
+    // 4. Apply window-level appearance (material, tint)
+    if let Some(main_window) = app.get_window("main") {
+        appearance::apply_window_appearance(&main_window, settings.window_material, settings.titlebar_tint.as_deref());
+    }
+
+    // 5. Propagate changes immediately to all windows
+    app.emit("settings-update", settings)?;
+
+    // 6. Tabs moved between the top strip and the sidebar
+    if layout_changed {
+        if let Some(metrics) = tabs::main_metrics(&app) {
+            layout_visible_tabs(&app, &state, metrics);
+        }
+        emit_tabs_update(&app, &state);
+    }
     
     Ok(())
 }
 
+#[tauri::command]
+fn get_toolbar_layout(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<ToolbarWidget>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.settings.read().unwrap().toolbar_layout.clone())
+}
+
+#[tauri::command]
+fn set_toolbar_layout(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    layout: Vec<ToolbarWidget>,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    toolbar_layout::validate(&layout).map_err(BrowserError::InvalidInput)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    settings.toolbar_layout = layout;
+    apply_settings(app, state, settings)
+}
+
+#[tauri::command]
+fn get_window_materials() -> Result<Vec<WindowMaterial>, BrowserError> {
+    Ok(WindowMaterial::available())
+}
+
+#[tauri::command]
+fn add_search_engine(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    name: String,
+    keyword: String,
+    query_template: String,
+    suggest_template: Option<String>,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let engine = SearchEngine { name, keyword, query_template, suggest_template, learned: false };
+    settings.add_search_engine(engine).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)
+}
+
+#[tauri::command]
+fn remove_search_engine(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    name: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    settings.remove_search_engine(&name).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)
+}
+
+// --- Third-Party Cookie Exceptions ---
+
+#[tauri::command]
+fn get_cookie_exceptions(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<Vec<String>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.settings.read().unwrap().third_party_cookie_exceptions.clone())
+}
+
+/// Allows third-party cookies on a site (e.g. for embedded logins). Applies to tabs opened
+/// afterwards on macOS; not on Linux, where one policy covers every site.
+#[tauri::command]
+fn add_cookie_exception(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let site = settings.add_cookie_exception(&site).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)?;
+    Ok(site)
+}
+
+#[tauri::command]
+fn remove_cookie_exception(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    settings.remove_cookie_exception(&site).map_err(BrowserError::NotFound)?;
+    apply_settings(app, state, settings)
+}
+
+/// Simplified URL for the toolbar while viewing a page. The full URL stays in tab state.
+#[tauri::command]
+fn format_url_for_display(url: String) -> Result<url_display::DisplayUrl, BrowserError> {
+    Ok(url_display::format_url_for_display(&url))
+}
+
+#[tauri::command]
+fn get_fingerprint_exceptions(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<String>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.settings.read().unwrap().fingerprint_exceptions.clone())
+}
+
+/// Turns fingerprint noise on or off for one site. Applies to tabs opened afterwards.
+#[tauri::command]
+fn set_site_fingerprint_protection(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    enabled: bool,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let site = settings.set_fingerprint_exception(&site, !enabled).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)?;
+    Ok(site)
+}
+
+/// Overrides the anti-bot spoofing profile for one site (None follows the global
+/// profile). Applies to tabs opened afterwards.
+#[tauri::command]
+fn set_site_spoofing_profile(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    profile: Option<SpoofingProfile>,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let site = settings.set_site_spoofing_profile(&site, profile).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)?;
+    Ok(site)
+}
+
+/// Blocks (true) or allows (false) a site's images regardless of the global
+/// setting; None follows it again.
+#[tauri::command]
+fn set_site_block_images(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    blocked: Option<bool>,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let site = settings.set_site_block_images(&site, blocked).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)?;
+    Ok(site)
+}
+
+/// Sets the timezone and locale a site sees (None reports the system's). Applies
+/// to tabs opened afterwards.
+#[tauri::command]
+fn set_site_region(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    timezone: Option<String>,
+    locale: Option<String>,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let site = settings.set_site_region(&site, timezone.as_deref(), locale.as_deref()).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)?;
+    Ok(site)
+}
+
+/// Adds or removes a site from the data saver exceptions. Applies to tabs opened
+/// afterwards.
+#[tauri::command]
+fn set_site_data_saver(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    enabled: bool,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let mut settings = state.settings.read().unwrap().clone();
+    let site = settings.set_data_saver_exception(&site, !enabled).map_err(BrowserError::InvalidInput)?;
+    apply_settings(app, state, settings)?;
+    Ok(site)
+}
+
+/// Sets the speed of a tab's media and remembers it for the tab's site (1.0
+/// forgets it).
+#[tauri::command]
+fn set_media_playback_rate(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    tab_id: String,
+    rate: f64,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let Some(site) = media_controls::apply_rate(&app, &state, &tab_id, rate)? else {
+        return Ok(());
+    };
+    let mut settings = state.settings.read().unwrap().clone();
+    settings.set_site_playback_rate(&site, (rate != 1.0).then_some(rate)).map_err(BrowserError::InvalidInput)?;
+    println!("[Media] Playback rate for {}: {}x", site, rate);
+    apply_settings(app, state, settings)
+}
+
 // --- Default Browser: Get pending launch URL for Cold Start ---
 #[tauri::command]
-fn get_pending_launch_url(state: tauri::State<AppState>) -> Option<String> {
+fn get_pending_launch_url(state: tauri::State<AppState>) -> Result<Option<String>, BrowserError> {
     let mut url = state.pending_launch_url.lock().unwrap();
-    url.take() // Return and clear
+    Ok(url.take()) // Return and clear
 }
 
 // --- Tab Management Commands ---
@@ -270,26 +898,48 @@ fn generate_tab_id() -> String {
 }
 
 #[tauri::command]
-async fn create_tab(app: AppHandle, state: tauri::State<'_, AppState>, url: String) -> Result<String, String> {
+async fn create_tab(app: AppHandle, state: tauri::State<'_, AppState>, url: String) -> Result<String, BrowserError> {
     create_tab_with_url(&app, &state, url)
 }
 
-// Initial script to track focus and clicks
-const FOCUS_INJECTION_SCRIPT: &str = r#"
-(function() {
-    window.addEventListener('focus', () => {
-        window.__TAURI__.event.emit('webview-focus', { focused: true });
-    });
-    window.addEventListener('blur', () => {
-        window.__TAURI__.event.emit('webview-focus', { focused: false });
-    });
-    window.addEventListener('click', () => {
-        window.__TAURI__.event.emit('webview-focus', { focused: true });
-    });
-})();
-"#;
+/// Opens a tab in a container (None for no container), unless a rule sends the URL
+/// elsewhere. An empty URL opens what a new tab does.
+#[tauri::command]
+async fn create_container_tab(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    url: String,
+    container: Option<String>,
+) -> Result<String, BrowserError> {
+    if let Some(id) = container.as_deref().filter(|id| state.containers.get(id).is_none()) {
+        return Err(BrowserError::NotFound(format!("No container '{}'", id)));
+    }
+    let url = if url.is_empty() { top_sites::new_tab_url(&state.settings.read().unwrap()) } else { url };
+    create_tab_in(&app, &state, url, container)
+}
 
-fn create_tab_with_url(app: &AppHandle, state: &AppState, url_str: String) -> Result<String, String> {
+fn create_tab_with_url(app: &AppHandle, state: &AppState, url_str: String) -> Result<String, BrowserError> {
+    create_tab_in(app, state, url_str, None)
+}
+
+/// Opens a tab in `container`, or in the one a rule picks for the URL.
+fn create_tab_in(
+    app: &AppHandle,
+    state: &AppState,
+    url_str: String,
+    container: Option<String>,
+) -> Result<String, BrowserError> {
+    open_tab(app, state, url_str, container, false)
+}
+
+/// `create_tab_in`, or with `background` a tab added behind the active one.
+fn open_tab(
+    app: &AppHandle,
+    state: &AppState,
+    url_str: String,
+    container: Option<String>,
+    background: bool,
+) -> Result<String, BrowserError> {
     let tab_id = generate_tab_id();
     let webview_label = format!("webview-{}", tab_id);
     
@@ -300,274 +950,51 @@ fn create_tab_with_url(app: &AppHandle, state: &AppState, url_str: String) -> Re
 
     let initial_url = if url_str.is_empty() {
         Url::parse(&settings.homepage).unwrap_or_else(|_| Url::parse("https://duckduckgo.com").unwrap())
+    } else if top_sites::is_new_tab_page(&url_str) {
+        https_only::app_page_url(top_sites::NEW_TAB_PAGE)
     } else {
-        Url::parse(&smart_parse_url(&url_str, &settings)).unwrap_or_else(|_| Url::parse(&settings.homepage).unwrap())
+        let parsed = tracking_params::clean(state, &settings, &smart_parse_url(&url_str, &settings));
+        Url::parse(&parsed).unwrap_or_else(|_| Url::parse(&settings.homepage).unwrap())
     };
 
-    // --- SECURITY & FINGERPRINTING CONFIGURATION ---
-    
-    // 1. User Agent: Identify strictly as Safari (Not Chrome) to match the WebKit engine.
-    const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15";
-
-    // 2. Anti-Fingerprinting Script
-    // We must hide the 'webdriver' property and populate plugins to look "human".
-    const ANTI_BOT_SCRIPT: &str = r#"
-        Object.defineProperty(navigator, 'webdriver', { get: () => undefined });
-        
-        // Mock Plugins to look like a standard Mac
-        if (navigator.plugins.length === 0) {
-            Object.defineProperty(navigator, 'plugins', {
-                get: () => [1, 2, 3, 4, 5],
-            });
-        }
-        
-        // Mock Languages if missing
-        if (!navigator.languages || navigator.languages.length === 0) {
-            Object.defineProperty(navigator, 'languages', {
-                get: () => ['en-US', 'en'],
-            });
-        }
-    "#;
-
-    // 3. Title Sync Listener
-    const TITLE_LISTENER_SCRIPT: &str = r#"
-        (function() {
-            const invoke = window.__TAURI__.core.invoke;
-            let lastSentTitle = null;
-
-            function sendTitle() {
-                const current = document.title;
-                if (current && current !== lastSentTitle) {
-                    lastSentTitle = current;
-                    invoke('handle_title_change', { title: current });
-                }
-            }
-
-            // 1. Send immediately
-            sendTitle();
-
-            // 2. Observe <head> for changes (covers <title> text updates and replacement)
-            const target = document.querySelector('head') || document.documentElement;
-            new MutationObserver(sendTitle).observe(target, { subtree: true, childList: true, characterData: true });
-        })();
-    "#;
-
-    // 4. Favicon Sync Listener
-    const FAVICON_LISTENER_SCRIPT: &str = r#"
-        (function() {
-            const invoke = window.__TAURI__.core.invoke;
-            let lastFavicon = "";
-
-            function getFavicon() {
-                let link = document.querySelector("link[rel*='icon']");
-                return link ? link.href : "";
-            }
-
-            function sendFavicon() {
-                const current = getFavicon();
-                if (current && current !== lastFavicon) {
-                    lastFavicon = current;
-                    invoke('handle_favicon_change', { favicon: current });
-                }
-            }
-
-            sendFavicon();
-            
-            // Observe head for changes to link tags
-            new MutationObserver(sendFavicon).observe(
-                document.querySelector('head') || document.documentElement, 
-                { subtree: true, childList: true, attributes: true }
-            );
-        })();
-    "#;
-
-    // 1. Setup Webview Builder
-    let mut builder = WebviewBuilder::new(
-        &webview_label, 
-        WebviewUrl::External(initial_url.clone())
-    )
-    .user_agent(USER_AGENT)
-    .initialization_script(ANTI_BOT_SCRIPT)
-    .initialization_script(FOCUS_INJECTION_SCRIPT)
-    .initialization_script(TITLE_LISTENER_SCRIPT)
-    .initialization_script(FAVICON_LISTENER_SCRIPT)
-    .initialization_script(&state.devtools.get_bootstrapper())
-    .initialization_script(r#"
-        // SPA History Hook & Security Hardening
-        (function() {
-            const invoke = window.__TAURI__.core.invoke;
-            const originalPushState = history.pushState;
-            const originalReplaceState = history.replaceState;
-
-            history.pushState = function() {
-                originalPushState.apply(this, arguments);
-                invoke('spa_navigate', { url: window.location.href });
-            };
-
-            history.replaceState = function() {
-                originalReplaceState.apply(this, arguments);
-                invoke('spa_navigate', { url: window.location.href });
-            };
-
-            window.addEventListener('popstate', () => {
-                invoke('spa_navigate', { url: window.location.href });
-            });
-            window.addEventListener('hashchange', () => {
-                invoke('spa_navigate', { url: window.location.href });
-            });
-            
-             window.addEventListener('pointerdown', () => {
-                invoke('content_pointer_down', {});
-            }, true);
-        })();
-    "#);
-
-    // 2. target="_blank" Handler (Window Open)
-    // This intercepts window.open() and <a target="_blank"> requests.
-    let app_handle_for_open = app.clone();
-    
-     builder = builder.on_new_window(move |initial_url, _features| {
-         println!("[Tabs] Intercepted new window request for: {:?}", initial_url);
-         
-         let handle = app_handle_for_open.clone();
-         let url_string = initial_url.to_string();
-         
-         tauri::async_runtime::spawn(async move {
-             if let Some(state) = handle.try_state::<AppState>() {
-                 let _ = create_tab_with_url(&handle, &state, url_string);
-             }
-         });
-
-         // Block the native window creation
-         // We use the Deny variant to prevent the new window from opening appropriately.
-         tauri::webview::NewWindowResponse::Deny
-    });
-
-    // --- Ad Blocking: Cosmetic Filter Injection Script ---
-    // This script runs at document_start. Uses safer generic hiding.
-    const COSMETIC_FILTER_SCRIPT: &str = r#"
-        (function() {
-            // Webmail domains to skip generic cosmetic filtering
-            const WEBMAIL_DOMAINS = ['mail.google.com', 'gmail.com'];
-
-            // Skip generic hiding on webmail
-            const hostname = window.location.hostname;
-            const isWebmail = WEBMAIL_DOMAINS.some(domain =>
-                hostname === domain || hostname.endsWith('.' + domain)
-            );
-
-            if (isWebmail) {
-                console.log('[AdBlock] Generic cosmetic filters disabled for webmail');
+    // A rule for the site wins; a container deleted since (closed tabs, sessions) means none
+    let container = state
+        .containers
+        .container_for(initial_url.as_str())
+        .or(container)
+        .filter(|id| state.containers.get(id).is_some());
 
-                // Still listen for site-specific rules (backend will return empty for webmail)
-                if (window.__TAURI__) {
-                    window.__TAURI__.core.invoke('get_cosmetic_rules', { url: window.location.href });
-                    window.__TAURI__.event.listen('apply-cosmetic-css', (event) => {
-                        // No-op for webmail
-                    });
-                }
-                return;
-            }
-
-            // Safer Generic Hiding: Targets high-confidence ad containers only
-            const style = document.createElement('style');
-            style.id = 'sovereign-generic-hiding';
-            style.textContent = `
-                [id^="google_ads_iframe"], [id^="taboola-"], [id^="outbrain-"],
-                [class^="ad-container-"], .pub_300x250, .pub_728x90, .text-ad-links
-                { display: none !important; }
-            `;
-            (document.head || document.documentElement).appendChild(style);
-
-            // Async: Request specific rules
-            if (window.__TAURI__) {
-                window.__TAURI__.core.invoke('get_cosmetic_rules', { url: window.location.href });
-
-                window.__TAURI__.event.listen('apply-cosmetic-css', (event) => {
-                    const css = event.payload.css;
-                    if (!css) return;
-                    const specificStyle = document.createElement('style');
-                    specificStyle.id = 'sovereign-site-hiding';
-                    specificStyle.textContent = css;
-                    (document.head || document.documentElement).appendChild(specificStyle);
-                });
-            }
-        })();
-    "#;
-
-    builder = builder.initialization_script(COSMETIC_FILTER_SCRIPT);
-    
-    // --- Ad Blocking: Network Request Interception ---
-    // This is the hot path - fires for every resource (images, scripts, etc.)
-    #[cfg(not(target_os = "macos"))]
-    let app_handle_for_adblock = app.clone();
-    
-    builder = builder.on_web_resource_request(move |_request, _response| {
-        // OPTIMIZATION: On macOS, WKContentRuleList handles blocking efficiently.
-        // Skip the Rust check to improve performance.
-        #[cfg(target_os = "macos")]
-        { return; }
-
-        #[cfg(not(target_os = "macos"))]
-        {
-            let url = _request.uri().to_string();
-            
-            // Get initiator from Referer header
-            let source_url = _request.headers()
-                .get("Referer")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or_else(|| {
-                    _request.headers()
-                        .get("Origin")
-                        .and_then(|v| v.to_str().ok())
-                        .unwrap_or(&url)
-                });
-            
-            // Determine request type from headers or URL
-            let request_type = guess_request_type(&url);
-            
-            // Check AdBlockManager (Windows/Linux only)
-            if let Some(state) = app_handle_for_adblock.try_state::<AppState>() {
-                if state.adblock.should_block_request(&url, source_url, &request_type) {
-                    println!("[AdBlock] Blocked: {}", url);
-                    *_response.status_mut() = http::StatusCode::FORBIDDEN;
-                    *_response.body_mut() = std::borrow::Cow::Borrowed(b"Blocked by Sovereign Browser");
-                    return;
-                }
-            }
+    let store = match &container {
+        Some(id) => DataStore::Container(id.clone()),
+        None => DataStore::Profile,
+    };
+    // Links opened from a container tab stay in its container
+    let container_for_open = container.clone();
+    let open_new = move |app: &AppHandle, url: String| {
+        if let Some(state) = app.try_state::<AppState>() {
+            let _ = create_tab_in(app, &state, url, container_for_open.clone());
         }
-
-    });
-    
-    // Note: in Tauri v2, we should use `on_navigation` for internal link control if needed.
-    // .on_navigation(...)
+    };
+    let builder =
+        browsing_webview::builder(app, &settings, &webview_label, initial_url.clone(), &store, open_new, |_| true);
 
     // 3. Add to Main Window
-    let main_window = app.get_window("main").ok_or("Main window not found")?;
+    let main_window = app.get_window("main").ok_or_else(|| BrowserError::NotFound("Main window not found".to_string()))?;
     
     // Calculate size (Initial size - will be updated by resize logic or immediately)
-    let physical_size = main_window.inner_size().map_err(|e| e.to_string())?;
-    let scale_factor = main_window.scale_factor().map_err(|e| e.to_string())?;
-    let toolbar_height_physical = (TOTAL_TOOLBAR_HEIGHT * scale_factor) as u32;
-    let content_height = physical_size.height.saturating_sub(toolbar_height_physical).max(100);
+    let physical_size = main_window.inner_size()?;
+    let scale_factor = main_window.scale_factor()?;
+    let chrome = tabs::window_layout(state, settings.chrome_layout());
+    let area = layout::content_area(physical_size.width, physical_size.height, scale_factor, chrome);
     
     let webview = main_window.add_child(
         builder,
-        PhysicalPosition::new(0, toolbar_height_physical as i32),
-        PhysicalSize::new(physical_size.width, content_height),
-    ).map_err(|e| e.to_string())?;
+        PhysicalPosition::new(area.x, area.y),
+        PhysicalSize::new(area.width, area.height),
+    )?;
 
     // Apply platform-specific settings immediately using the handle
-    enable_back_forward_gestures(&webview);
-    
-    // Apply content blocking rules on macOS
-    #[cfg(target_os = "macos")]
-    {
-        let rules = state.adblock.get_safari_rules();
-        if rules.len() > 2 {
-            apply_content_blocking_rules(&webview, &rules);
-        }
-    }
+    browsing_webview::setup(app, state, &settings, &webview);
 
     // 4. Update State
     let new_tab = Tab {
@@ -582,14 +1009,22 @@ fn create_tab_with_url(app: &AppHandle, state: &AppState, url_str: String) -> Re
         can_go_forward: false,
         last_focus_was_content: true,
         screenshot: None,
+        unread_count: None,
+        custom_title: None,
+        marker: None,
+        is_audible: false,
+        is_muted: false,
+        load_error: None,
+        window: None,
+        container,
     };
     
-    {
-        let mut tabs = state.tabs.lock().unwrap();
-        tabs.push(new_tab);
+    if background {
+        tabs::engine(app, state).add_in_background(new_tab);
+        return Ok(tab_id);
     }
+    tabs::engine(app, state).add(new_tab);
     
-    // 5. Switch to it (Activate)
     // 5. Switch to it (Activate)
     switch_tab_logic(app, state, tab_id.clone())?;
 
@@ -597,104 +1032,52 @@ fn create_tab_with_url(app: &AppHandle, state: &AppState, url_str: String) -> Re
 }
 
 #[tauri::command]
-async fn switch_tab(app: AppHandle, state: tauri::State<'_, AppState>, tab_id: String) -> Result<(), String> {
+async fn switch_tab(app: AppHandle, state: tauri::State<'_, AppState>, tab_id: String) -> Result<(), BrowserError> {
     switch_tab_logic(&app, &state, tab_id)
 }
 
-fn switch_tab_logic(app: &AppHandle, state: &AppState, tab_id: String) -> Result<(), String> {
+fn switch_tab_logic(app: &AppHandle, state: &AppState, tab_id: String) -> Result<(), BrowserError> {
     println!("[Tabs] Switching to tab: {}", tab_id);
 
-    // 1. Hide Dropdown (Safety)
-    if let Some(dd) = app.get_window("dropdown") {
-        let _ = dd.hide();
-    }
-
-    let mut old_active_id = String::new();
-    let mut target_label = String::new();
-    let mut should_focus_content = false;
-    let mut url_to_sync = String::new();
-
-    // 2. State Update
-    {
-        let mut active = state.active_tab_id.lock().unwrap();
-        if let Some(current) = active.as_ref() {
-            old_active_id = current.clone();
-            // Do not hide here yet, we want to show new one first if possible to avoid flickering? 
-            // Actually, hiding old first is safer for preventing input leaks.
-        }
-        *active = Some(tab_id.clone());
-
-        let mut tabs = state.tabs.lock().unwrap();
-        if let Some(tab) = tabs.iter_mut().find(|t| t.id == tab_id) {
-            tab.last_accessed = Some(Instant::now());
-            target_label = tab.webview_label.clone();
-            should_focus_content = tab.last_focus_was_content;
-            url_to_sync = tab.url.clone();
-            // TODO: Handle wake up if hibernated (screenshot logic here in future)
-        }
+    // Detached tabs are shown in their own window
+    if tab_windows::focus(app, state, &tab_id) {
+        return Ok(());
     }
 
-    if target_label.is_empty() {
-        return Err("Tab not found".to_string());
+    // Moving between the two panes keeps the split, any other tab ends it
+    if !state.split_view.contains(&tab_id) {
+        split_view::exit(app, state);
     }
 
-    // 3. Webview Visiblity Swap
-    // Hide old
-    if !old_active_id.is_empty() {
-        let old_label = {
-            let tabs = state.tabs.lock().unwrap();
-            tabs.iter().find(|t| t.id == old_active_id).map(|t| t.webview_label.clone()).unwrap_or_default()
-        };
-        if let Some(old_wv) = app.get_webview(&old_label) {
-             let _ = old_wv.hide();
-        }
-    }
+    // 1. Hide Dropdown (Safety)
+    let _ = dropdown::hide(app);
 
-    // Show new
-    if let Some(new_wv) = app.get_webview(&target_label) {
-        // Lazy Resize Check
-        if let Some(main) = app.get_window("main") {
-            let size = main.inner_size().unwrap();
-            let scale = main.scale_factor().unwrap();
-            let toolbar_h = (TOTAL_TOOLBAR_HEIGHT * scale) as u32;
-            let expected_h = size.height.saturating_sub(toolbar_h);
-            
-            // Just force resize to be safe (it's cheap if no change)
-            let _ = new_wv.set_bounds(tauri::Rect {
-                position: tauri::Position::Physical(PhysicalPosition::new(0, toolbar_h as i32)),
-                size: tauri::Size::Physical(PhysicalSize::new(size.width, expected_h)),
-            });
-        }
+    // 2. Active tab and webview visibility, see modules::tab_engine
+    tabs::engine(app, state).switch(&tab_id)?;
 
-        let _ = new_wv.show();
-        
-        // Focus Restoration
-        if should_focus_content {
-            let _ = new_wv.set_focus();
-        } else {
-            // Focus URL bar
-            if let Some(main) = app.get_window("main") {
-                 let _ = main.set_focus();
-                 let _ = main.emit("focus-url-bar", ());
-            }
-        }
+    // 3. Focus Restoration: toolbar or page, whichever this tab had last
+    {
+        let mut focus = state.focus.lock();
+        let target = focus.switch_tab(&tab_id);
+        let _ = apply_focus(app, state, target);
     }
 
-    // 4. Emit Events
-    emit_tabs_update(&app, &state);
-    let _ = app.emit("url-changed", url_to_sync);
+    // 4. URL bar and page status
+    tab_status::emit_tab_status(app, state, &tab_id);
     
     Ok(())
 }
 
 #[tauri::command]
-fn handle_title_change(webview: tauri::Webview, state: tauri::State<AppState>, title: String) {
+fn handle_title_change(webview: tauri::Webview, state: tauri::State<AppState>, title: String) -> Result<(), BrowserError> {
     let label = webview.label();
     let mut updated = false;
     {
         let mut tabs = state.tabs.lock().unwrap();
         if let Some(tab) = tabs.iter_mut().find(|t| t.webview_label == label) {
             tab.title = title.clone();
+            tab.unread_count = badges::unread_count(&tab.title, tab.favicon.as_deref());
+            tab_windows::set_title(webview.app_handle(), tab);
             updated = true;
         }
     }
@@ -702,16 +1085,19 @@ fn handle_title_change(webview: tauri::Webview, state: tauri::State<AppState>, t
         let app_handle = webview.app_handle();
         emit_tabs_update(&app_handle, &state);
     }
+    Ok(())
 }
 
 #[tauri::command]
-fn handle_favicon_change(webview: tauri::Webview, state: tauri::State<AppState>, favicon: String) {
+fn handle_favicon_change(webview: tauri::Webview, state: tauri::State<AppState>, favicon: String) -> Result<(), BrowserError> {
     let label = webview.label();
     let mut updated = false;
     {
         let mut tabs = state.tabs.lock().unwrap();
         if let Some(tab) = tabs.iter_mut().find(|t| t.webview_label == label) {
+            state.top_sites.remember_favicon(&tab.url, &favicon);
             tab.favicon = Some(favicon);
+            tab.unread_count = badges::unread_count(&tab.title, tab.favicon.as_deref());
             updated = true;
         }
     }
@@ -719,86 +1105,367 @@ fn handle_favicon_change(webview: tauri::Webview, state: tauri::State<AppState>,
         let app_handle = webview.app_handle();
         emit_tabs_update(&app_handle, &state);
     }
+    Ok(())
 }
 
 #[tauri::command]
-async fn close_tab(app: AppHandle, state: tauri::State<'_, AppState>, tab_id: String) -> Result<(), String> {
-    close_tab_logic(&app, &state, tab_id).await
+async fn close_tab(app: AppHandle, state: tauri::State<'_, AppState>, tab_id: String) -> Result<(), BrowserError> {
+    close_tab_logic(&app, &state, tab_id, true).await
 }
 
-async fn close_tab_logic(app: &AppHandle, state: &AppState, tab_id: String) -> Result<(), String> {
+/// `archive` is false when the tab is kept elsewhere (e.g. a stash) and shouldn't show up in "Reopen Closed Tab".
+async fn close_tab_logic(app: &AppHandle, state: &AppState, tab_id: String, archive: bool) -> Result<(), BrowserError> {
     println!("[Tabs] Closing tab: {}", tab_id);
-    
-    let mut label_to_close = String::new();
-    let mut next_tab_id = None;
-    let mut was_active = false;
-
-    {
-        let mut tabs = state.tabs.lock().unwrap();
-        if let Some(index) = tabs.iter().position(|t| t.id == tab_id) {
-             // Archive tab BEFORE removing it
-             closed_tabs::archive_tab(state, &tabs[index]);
-
-             let tab = tabs.remove(index);
-             label_to_close = tab.webview_label;
-             
-             // Determine next active if we closed the active one
-             let active_lock = state.active_tab_id.lock().unwrap();
-             if active_lock.as_ref() == Some(&tab_id) {
-                 was_active = true;
-                 // Try to pick the right neighbor, else left, else none
-                 if index < tabs.len() {
-                     next_tab_id = Some(tabs[index].id.clone());
-                 } else if !tabs.is_empty() {
-                     next_tab_id = Some(tabs[index - 1].id.clone());
-                 }
-             }
-        }
-    }
+    split_view::forget_tab(app, state, &tab_id);
 
-    // Destroy Webview
-    if let Some(wv) = app.get_webview(&label_to_close) {
-        let _ = wv.close();
+    // Removes the tab, destroys its webview and shows the next one, see modules::tab_engine
+    let Some(closed) = tabs::engine(app, state).close(&tab_id)? else {
+        return Ok(());
+    };
+    if archive {
+        closed_tabs::archive_tab(state, &closed.tab);
     }
 
-    // Switch if needed
-    if was_active {
-        if let Some(next_id) = next_tab_id {
-            switch_tab_logic(app, state, next_id)?;
-        } else {
-             // No tabs left? Create a new one? Or close app? 
-             // Chrome closes app on last tab close usually.
-             // For now, let's create a new tab so app doesn't look broken
-             // For now, let's create a new tab so app doesn't look broken
-             // Chromecast closes app on last tab close usually.
-             // For now, let's create a new tab so app doesn't look broken
-             let _ = create_tab_with_url(app, state, "https://duckduckgo.com".to_string());
+    let label_to_close = closed.tab.webview_label.clone();
+    state.focus.lock().forget_tab(&tab_id);
+    state.https_only.forget_webview(&label_to_close);
+    state.block_stats.clear_tab(&label_to_close);
+    state.frames.forget_webview(&label_to_close);
+    state.user_agents.forget_webview(&label_to_close);
+    state.data_saver.forget_webview(&label_to_close);
+    state.safe_browsing.forget_webview(&label_to_close);
+    state.audio_output.forget_webview(&label_to_close);
+    state.media_controls.forget_webview(&label_to_close);
+    state.network_log.clear_tab(&label_to_close);
+    state.permissions.forget_webview(&label_to_close);
+    state.console_log.forget_webview(&label_to_close);
+    state.task_manager.forget_webview(&label_to_close);
+    state.video_popout.forget_webview(&label_to_close);
+    state.read_aloud.forget_webview(&label_to_close);
+    video_popout::close_for_tab(app, &tab_id);
+
+    match closed.next {
+        // Focus and the URL bar follow the tab now shown
+        AfterClose::Switched(next_id) => {
+            {
+                let mut focus = state.focus.lock();
+                let target = focus.switch_tab(&next_id);
+                let _ = apply_focus(app, state, target);
+            }
+            tab_status::emit_tab_status(app, state, &next_id);
         }
+        // The last tab closed: open a new one rather than leave the window empty
+        AfterClose::NewTab => {
+            let url = top_sites::new_tab_url(&state.settings.read().unwrap());
+            create_tab_with_url(app, state, url)?;
+        }
+        AfterClose::Unchanged => {}
     }
-    
-    emit_tabs_update(&app, &state);
-
     Ok(())
 }
 
 #[tauri::command]
-fn get_tabs(state: tauri::State<AppState>) -> Vec<Tab> {
+fn get_tabs(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<Vec<Tab>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
     let tabs = state.tabs.lock().unwrap();
-    tabs.clone()
+    Ok(tabs.clone())
 }
 
 #[tauri::command]
 fn restore_closed_tab(
     app: AppHandle,
     state: tauri::State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<String, BrowserError> {
     // Get last closed tab
     let closed_tab = closed_tabs::pop_closed_tab(&state)
-        .ok_or("No closed tabs to restore")?;
+        .ok_or_else(|| BrowserError::NotFound("No closed tabs to restore".to_string()))?;
 
     // Simply create a new tab at the stored URL
     // This reuses ALL existing tab creation logic
-    create_tab_with_url(&app, &state, closed_tab.url)
+    create_tab_in(&app, &state, closed_tab.url, closed_tab.container)
+}
+
+/// Reopens one closed tab, e.g. a closed result from tab search.
+#[tauri::command]
+fn restore_closed_tab_by_id(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    closed_id: String,
+) -> Result<String, BrowserError> {
+    let closed_tab = closed_tabs::take_closed_tab(&state, &closed_id)
+        .ok_or_else(|| BrowserError::NotFound("Closed tab not found".to_string()))?;
+    create_tab_in(&app, &state, closed_tab.url, closed_tab.container)
+}
+
+/// Moves a tab into a new window of its own, see modules::tab_windows.
+/// `x`/`y` place the window, e.g. where a tab was dragged out.
+#[tauri::command]
+async fn detach_tab(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    tab_id: String,
+    x: Option<f64>,
+    y: Option<f64>,
+) -> Result<(), BrowserError> {
+    detach_tab_logic(&app, &state, tab_id, x.zip(y))
+}
+
+fn detach_tab_logic(
+    app: &AppHandle,
+    state: &AppState,
+    tab_id: String,
+    position: Option<(f64, f64)>,
+) -> Result<(), BrowserError> {
+    let neighbour = {
+        let tabs = state.tabs.lock().unwrap();
+        let index = tabs.iter().position(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+        if tabs[index].window.is_some() {
+            return Err(BrowserError::InvalidInput("Tab is already in its own window".to_string()));
+        }
+        tab_windows::main_neighbour(&tabs, index)
+            .ok_or_else(|| BrowserError::NotAllowed("The main window's only tab can't be moved out".to_string()))?
+    };
+    split_view::forget_tab(app, state, &tab_id);
+
+    // Leave the tab first, which hides it in the main window
+    let was_active = state.active_tab_id.lock().unwrap().as_deref() == Some(tab_id.as_str());
+    if was_active {
+        switch_tab_logic(app, state, neighbour)?;
+    }
+
+    let window = tab_windows::open(app, state, &tab_id, position)?;
+    let h = app.clone();
+    let label = window.label().to_string();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+            // Closing the window closes its tab; close_tab_logic destroys the window
+            api.prevent_close();
+            let h = h.clone();
+            let label = label.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    let tab_id = tab_windows::tab_of_window(&label).unwrap_or_default().to_string();
+                    let _ = close_tab_logic(&h, &state, tab_id, true).await;
+                }
+            });
+        }
+    });
+
+    emit_tabs_update(app, state);
+    Ok(())
+}
+
+/// Moves a detached tab back into the main window and switches to it.
+#[tauri::command]
+fn attach_tab(app: AppHandle, state: tauri::State<'_, AppState>, tab_id: String) -> Result<(), BrowserError> {
+    tab_windows::attach(&app, &state, &tab_id)?;
+    if let Some(main) = app.get_window("main") {
+        main.set_focus()?;
+    }
+    switch_tab_logic(&app, &state, tab_id)
+}
+
+/// Shows two tabs side by side, see modules::split_view.
+#[tauri::command]
+fn enter_split_view(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    left_tab: String,
+    right_tab: String,
+) -> Result<(), BrowserError> {
+    enter_split_view_logic(&app, &state, left_tab, right_tab)
+}
+
+fn enter_split_view_logic(app: &AppHandle, state: &AppState, left_tab: String, right_tab: String) -> Result<(), BrowserError> {
+    {
+        let tabs = state.tabs.lock().unwrap();
+        for id in [&left_tab, &right_tab] {
+            let tab = tabs.iter().find(|t| &t.id == id).ok_or_else(|| BrowserError::tab_not_found(id))?;
+            if tab.window.is_some() {
+                return Err(BrowserError::NotAllowed("Tabs in their own window can't be split".to_string()));
+            }
+        }
+    }
+    split_view::exit(app, state);
+    state.split_view.set(&left_tab, &right_tab).map_err(BrowserError::InvalidInput)?;
+
+    // Stay on whichever of the two is active, else start on the left
+    let active = state.active_tab_id.lock().unwrap().clone();
+    let target = active.filter(|id| *id == right_tab).unwrap_or_else(|| left_tab.clone());
+    switch_tab_logic(app, state, target)?;
+    split_view::emit(app, state);
+    println!("[SplitView] Split {} | {}", left_tab, right_tab);
+    Ok(())
+}
+
+#[tauri::command]
+fn exit_split_view(app: AppHandle, state: tauri::State<'_, AppState>) -> Result<(), BrowserError> {
+    split_view::exit(&app, &state);
+    Ok(())
+}
+
+/// Closes the given tabs and saves them as a named stash.
+#[tauri::command]
+async fn stash_tabs(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    tab_ids: Vec<String>,
+    name: String,
+) -> Result<TabStash, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    stash_tabs_logic(&app, &state, tab_ids, name).await
+}
+
+async fn stash_tabs_logic(
+    app: &AppHandle,
+    state: &AppState,
+    tab_ids: Vec<String>,
+    name: String,
+) -> Result<TabStash, BrowserError> {
+    let stashed: Vec<StashedTab> = {
+        let tabs = state.tabs.lock().unwrap();
+        tab_ids.iter()
+            .filter_map(|id| tabs.iter().find(|t| &t.id == id))
+            .map(StashedTab::from)
+            .collect()
+    };
+    if stashed.is_empty() {
+        return Err(BrowserError::InvalidInput("No tabs to stash".to_string()));
+    }
+
+    let stash = TabStash::new(&name, stashed, SystemTime::now());
+    let mut store = StashStore::load(app);
+    store.push(stash.clone());
+    store.save(app).map_err(BrowserError::Io)?;
+    println!("[Stash] Stashed {} tabs as '{}'", stash.tabs.len(), stash.name);
+
+    // Only close once the stash is safely on disk
+    for id in tab_ids {
+        close_tab_logic(app, state, id, false).await?;
+    }
+    Ok(stash)
+}
+
+/// Reopens every tab in a stash and removes the stash.
+#[tauri::command]
+fn restore_stash(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    stash_id: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let mut store = StashStore::load(&app);
+    let stash = store.take(&stash_id).ok_or_else(|| BrowserError::NotFound("Stash not found".to_string()))?;
+
+    for tab in &stash.tabs {
+        if let Err(e) = create_tab_with_url(&app, &state, tab.url.clone()) {
+            eprintln!("[Stash] Failed to restore {}: {}", tab.url, e);
+        }
+    }
+    store.save(&app).map_err(BrowserError::Io)
+}
+
+/// Opens a link from the content context menu according to the chosen disposition.
+#[tauri::command]
+fn open_link(
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+    url: String,
+    disposition: LinkDisposition,
+) -> Result<(), BrowserError> {
+    match disposition {
+        LinkDisposition::CurrentTab => navigate(app, state, url)?,
+        LinkDisposition::NewTab => {
+            create_tab_with_url(&app, &state, url)?;
+        }
+        LinkDisposition::NewWindow | LinkDisposition::PrivateWindow => {
+            context_menu::open_link_window(&app, &url, disposition.is_private()).map_err(BrowserError::Webview)?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs an item of the page context menu on what was right-clicked.
+fn run_context_menu_action(
+    app: &AppHandle,
+    state: tauri::State<AppState>,
+    action: ContextAction,
+) -> Result<(), BrowserError> {
+    let target = context_menu::target().ok_or_else(|| BrowserError::NotFound("Nothing was right-clicked".to_string()))?;
+    let opener = state.tabs.lock().unwrap().iter().find(|t| t.webview_label == target.webview_label).cloned();
+    let opener = opener.ok_or_else(|| BrowserError::NotFound("The tab was closed".to_string()))?;
+    let link = || target.link.clone().ok_or_else(|| BrowserError::InvalidInput("Not a link".to_string()));
+    let image = || target.image.clone().ok_or_else(|| BrowserError::InvalidInput("Not an image".to_string()));
+
+    match action {
+        // New tabs stay in the opener's container unless a rule says otherwise
+        ContextAction::OpenLinkInNewTab | ContextAction::OpenLinkInBackgroundTab => {
+            let background = action == ContextAction::OpenLinkInBackgroundTab;
+            open_tab(app, &state, link()?.to_string(), opener.container, background)?;
+        }
+        ContextAction::OpenLinkInNewWindow | ContextAction::OpenLinkInPrivateWindow => {
+            let private = action == ContextAction::OpenLinkInPrivateWindow;
+            context_menu::open_link_window(app, link()?.as_str(), private).map_err(BrowserError::Webview)?;
+        }
+        ContextAction::CopyCleanLink => {
+            let url = context_menu::clean_link(&link()?);
+            app.clipboard().write_text(url).map_err(|e| BrowserError::Internal(e.to_string()))?;
+        }
+        ContextAction::OpenImageInNewTab => {
+            open_tab(app, &state, image()?.to_string(), opener.container, false)?;
+        }
+        ContextAction::SaveImage => context_menu::save_image(app, image()?),
+        ContextAction::CopyImageAddress => {
+            app.clipboard().write_text(image()?.to_string()).map_err(|e| BrowserError::Internal(e.to_string()))?;
+        }
+        ContextAction::InspectElement => open_devtools(app.clone(), state, Some(opener.id))?,
+    }
+    Ok(())
+}
+
+fn active_webview_label(state: &AppState) -> Option<String> {
+    // Tabs before the active id, like every other place that holds both
+    let tabs = state.tabs.lock().unwrap();
+    let active = state.active_tab_id.lock().unwrap();
+    active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+}
+
+/// Recreates the tabs saved in the last session (with their custom titles and markers).
+/// Opens the default start page if there is nothing to restore.
+fn restore_session(app: &AppHandle, state: &AppState) {
+    let session = session_store::SessionStore::load(app);
+    let mut restored: Vec<Option<String>> = Vec::new();
+
+    for saved in &session.tabs {
+        match create_tab_in(app, state, saved.url.clone(), saved.container.clone()) {
+            Ok(tab_id) => {
+                let mut tabs = state.tabs.lock().unwrap();
+                if let Some(tab) = tabs.iter_mut().find(|t| t.id == tab_id) {
+                    tab.custom_title = saved.custom_title.clone();
+                    tab.marker = saved.marker.clone();
+                }
+                restored.push(Some(tab_id));
+            }
+            Err(e) => {
+                eprintln!("[Session] Failed to restore tab {}: {}", saved.url, e);
+                restored.push(None);
+            }
+        }
+    }
+
+    if restored.iter().all(|id| id.is_none()) {
+        let url = top_sites::new_tab_url(&state.settings.read().unwrap());
+        let _ = create_tab_with_url(app, state, url);
+        return;
+    }
+
+    println!("[Session] Restored {} tabs", restored.iter().flatten().count());
+    if let Some(Some(active_id)) = session.active_index.and_then(|i| restored.get(i)) {
+        let _ = switch_tab_logic(app, state, active_id.clone());
+    }
+    emit_tabs_update(app, state);
 }
 
 fn emit_tabs_update(app: &AppHandle, state: &AppState) {
@@ -808,10 +1475,25 @@ fn emit_tabs_update(app: &AppHandle, state: &AppState) {
     let tabs = state.tabs.lock().unwrap();
     let active_id = state.active_tab_id.lock().unwrap().clone();
     
-    let _ = app.emit("update-tabs", serde_json::json!({
-        "tabs": *tabs,
-        "activeTabId": active_id
-    }));
+    let _ = app.emit("update-tabs", tabs::update_payload(state, &tabs, active_id));
+    dock::update(app, state, &tabs);
+}
+
+/// Puts the active tab, or both panes of a split, where the window size and
+/// scale factor in `metrics` and the tab placement say.
+fn layout_visible_tabs(app: &AppHandle, state: &AppState, metrics: WindowMetrics) {
+    if state.split_view.get().is_some() {
+        split_view::arrange_in(app, state, metrics);
+        return;
+    }
+    let active_label = {
+        let tabs = state.tabs.lock().unwrap();
+        let active = state.active_tab_id.lock().unwrap();
+        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+    };
+    if let Some(webview) = active_label.and_then(|label| app.get_webview(&label)) {
+        let _ = webview.set_bounds(tabs::content_bounds_in(state, metrics));
+    }
 }
 
 // Logic to resize ALL webviews (debounced)
@@ -842,10 +1524,11 @@ fn resize_all_webviews(app: &AppHandle, width: u32, height: u32, scale_factor: f
     // We will do it in `main` loop where we have state handle if possible.
 }
 #[tauri::command]
-fn get_suggestions(app: AppHandle) -> Result<Vec<Suggestion>, String> {
+fn get_suggestions(app: AppHandle, webview: tauri::Webview) -> Result<Vec<Suggestion>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
     let path = get_suggestions_path(&app);
     if path.exists() {
-        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let content = fs::read_to_string(&path)?;
         let suggestions: Vec<Suggestion> = serde_json::from_str(&content).unwrap_or_default();
         Ok(suggestions)
     } else {
@@ -854,45 +1537,52 @@ fn get_suggestions(app: AppHandle) -> Result<Vec<Suggestion>, String> {
 }
 
 #[tauri::command]
-fn get_current_url(app: AppHandle) -> Option<String> {
+fn get_current_url(app: AppHandle) -> Result<Option<String>, BrowserError> {
     if let Some(webview) = app.get_webview("content") {
-        webview.url().ok().map(|u| u.to_string())
+        Ok(Some(webview.url()?.to_string()))
     } else {
-        None
+        Ok(None)
     }
 }
 
 #[tauri::command]
-fn hard_reload(app: AppHandle) {
+fn hard_reload(app: AppHandle) -> Result<(), BrowserError> {
     if let Some(webview) = app.get_webview("content") {
-        if let Ok(url) = webview.url() {
-            let js_script = format!("window.location.href = '{}'", url);
-            let _ = webview.eval(&js_script);
-        }
+        let js_script = format!("window.location.href = '{}'", webview.url()?);
+        webview.eval(&js_script)?;
     }
+    Ok(())
 }
 
 #[tauri::command]
-fn clear_site_data(app: AppHandle) -> Result<(), String> {
-    if let Some(webview) = app.get_webview("content") {
-        let js_script = r#"
-            localStorage.clear();
-            sessionStorage.clear();
-            document.cookie.split(";").forEach(function(c) { 
-                document.cookie = c.replace(/^ +/, "").replace(/=.*/, "=;expires=" + new Date().toUTCString() + ";path=/"); 
-            });
-            window.location.reload();
-        "#;
-        webview.eval(js_script).map_err(|e| e.to_string())?;
+async fn clear_site_data(app: AppHandle, state: tauri::State<'_, AppState>) -> Result<(), BrowserError> {
+    let active_label = active_webview_label(&state);
+    if let Some(webview) = active_label.and_then(|label| app.get_webview(&label)) {
+        let url = webview.url()?;
+
+        // Delete cookies through the platform store (also reaches HttpOnly cookies)
+        let cookies = webview.cookies_for_url(url.clone())?;
+        let count = cookies.len();
+        for cookie in cookies {
+            webview.delete_cookie(cookie)?;
+        }
+        println!("[ClearData] Removed {} cookies for {}", count, url);
+
+        // Storage, IndexedDB, caches and service workers, from the tab's own data store
+        site_data::clear_page_data(&webview, &url).await?;
+        // Session storage belongs to the tab rather than the data store
+        webview.eval("sessionStorage.clear();")?;
+        webview.reload()?;
     }
     Ok(())
 }
 
 #[tauri::command]
-fn navigate(app: AppHandle, state: tauri::State<AppState>, url: String) {
+fn navigate(app: AppHandle, state: tauri::State<AppState>, url: String) -> Result<(), BrowserError> {
     // Read settings for parsing
     let settings = state.settings.read().unwrap();
     let final_url = smart_parse_url(&url, &settings);
+    let final_url = tracking_params::clean(&state, &settings, &final_url);
     drop(settings); // Release read lock before history write
 
     // Record intent to visit (typed)
@@ -915,36 +1605,50 @@ fn navigate(app: AppHandle, state: tauri::State<AppState>, url: String) {
     if let Some(label) = active_label {
         if let Some(webview) = app.get_webview(&label) {
              let js_script = format!("window.location.href = '{}'", final_url);
-             let _ = webview.eval(&js_script);
+             webview.eval(&js_script)?;
         }
     }
+
+    // Dropdown is gone after a navigation; hand focus to the page instead of
+    // leaving it wherever the dropdown dismissal dropped it
+    request_focus_logic(&app, &state, FocusTarget::Content)
 }
 
 #[tauri::command]
-fn spa_navigate(app: AppHandle, state: tauri::State<AppState>, url: String) {
-    // SPA navigation event from frontend hook
+fn spa_navigate(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+) -> Result<(), BrowserError> {
+    // SPA navigation event from the page hook. Only tabs are recorded: the other
+    // browsing windows (private, guest, links) keep no history.
+    let tab_id = state.tabs.lock().unwrap().iter().find(|t| t.webview_label == webview.label()).map(|t| t.id.clone());
+    let Some(id) = tab_id else {
+        return Ok(());
+    };
+    // pushState can't leave the page's origin, so neither may the report
+    let reported = Url::parse(&url)?;
+    if reported.origin() != webview.url()?.origin() {
+        return Err(BrowserError::InvalidInput("URL is not on the page's origin".to_string()));
+    }
     state.history.add_visit(url.clone(), None, false);
 
-    // Update active tab's URL
-    let active_id = state.active_tab_id.lock().unwrap().clone();
-    if let Some(id) = active_id {
-        let mut tabs = state.tabs.lock().unwrap();
-        if let Some(tab) = tabs.iter_mut().find(|t| t.id == id) {
-            tab.url = url.clone();
-        }
+    if let Some(tab) = state.tabs.lock().unwrap().iter_mut().find(|t| t.id == id) {
+        tab.url = url;
     }
-
-    // Emit for URL bar sync - Global App Event
-    let _ = app.emit("url-changed", url);
+    // URL bar sync
+    tab_status::emit_tab_status(&app, &state, &id);
+    Ok(())
 }
 
 #[tauri::command]
-fn navigate_from_dropdown(app: AppHandle, state: tauri::State<AppState>, url: String) {
-    navigate(app, state, url);
+fn navigate_from_dropdown(app: AppHandle, state: tauri::State<AppState>, url: String) -> Result<(), BrowserError> {
+    navigate(app, state, url)
 }
 
 #[tauri::command]
-fn dropdown_ready(app: AppHandle, state: tauri::State<AppState>) {
+fn dropdown_ready(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
     println!("[dropdown] dropdown_ready called!");
     if let Ok(mut ready) = state.dropdown_ready.lock() {
         *ready = true;
@@ -955,56 +1659,64 @@ fn dropdown_ready(app: AppHandle, state: tauri::State<AppState>) {
             if let Some(payload) = pending.take() {
                 println!("[dropdown] Found pending payload, emitting and showing");
                 // Emit and Show
-                if let Some(win) = app.get_window("dropdown") {
-                    let _ = win.emit("update-dropdown", payload);
-                    let _ = win.show();
-                    let _ = win.set_always_on_top(true);
-                }
+                let _ = app.emit_to(dropdown::LABEL, "update-dropdown", payload);
+                dropdown::show(&app);
             }
         }
     }
+    Ok(())
 }
 
 #[tauri::command]
-fn set_dropdown_bounds(app: AppHandle, x: f64, y: f64, width: f64, height: f64) {
+fn set_dropdown_bounds(app: AppHandle, x: f64, y: f64, width: f64, height: f64) -> Result<(), BrowserError> {
     println!("[dropdown] set_dropdown_bounds called: x={}, y={}, width={}, height={}", x, y, width, height);
-    
-    if let Some(main) = app.get_window("main") {
-        match main.inner_position() {
-            Ok(content_pos) => {
-                match main.scale_factor() {
-                    Ok(scale_factor) => {
-                        // inner_position gives the top-left of the content area (below titlebar)
-                        // x, y are logical coordinates within the content area
-                        let screen_x = content_pos.x + (x * scale_factor) as i32;
-                        let screen_y = content_pos.y + (y * scale_factor) as i32;
-                        let screen_w = (width * scale_factor) as u32;
-                        let screen_h = (height * scale_factor) as u32;
-                        
-                        println!("[dropdown] content_pos: ({}, {}), screen pos: ({}, {}), size: ({}, {}), scale: {}", 
-                            content_pos.x, content_pos.y, screen_x, screen_y, screen_w, screen_h, scale_factor);
-
-                        if let Some(dd) = app.get_window("dropdown") {
-                            let pos_result = dd.set_position(tauri::Position::Physical(PhysicalPosition::new(screen_x, screen_y)));
-                            let size_result = dd.set_size(tauri::Size::Physical(PhysicalSize::new(screen_w, screen_h)));
-                            println!("[dropdown] set_position result: {:?}, set_size result: {:?}", pos_result, size_result);
-                        } else {
-                            println!("[dropdown] ERROR: dropdown window not found!");
-                        }
-                    },
-                    Err(e) => println!("[dropdown] ERROR getting scale_factor: {:?}", e),
-                }
-            },
-            Err(e) => println!("[dropdown] ERROR getting inner_position: {:?}", e),
-        }
-    } else {
-        println!("[dropdown] ERROR: main window not found!");
+    // Screen coordinates for the dropdown window, window coordinates for the
+    // child webview used on Wayland, see modules::dropdown
+    if let Err(e) = dropdown::set_bounds(&app, x, y, width, height) {
+        println!("[dropdown] ERROR: {}", e);
     }
+    Ok(())
 }
 
 #[tauri::command]
-fn update_dropdown(app: AppHandle, state: tauri::State<AppState>, query: String, results: Vec<serde_json::Value>, selected_index: i32) {
+fn update_dropdown(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    query: String,
+    results: Vec<serde_json::Value>,
+    selected_index: i32,
+) -> Result<Vec<serde_json::Value>, BrowserError> {
     println!("[dropdown] update_dropdown called: results={}, selected_index={}, query='{}'", results.len(), selected_index, query);
+
+    // Opt-in remote suggestions: merge cached ones now, fetch fresh ones in the background
+    let mut results = results;
+    let suggestions_enabled = state.settings.read().unwrap().search_suggestions;
+    if results.is_empty() || query.trim().is_empty() || query.trim().starts_with('>') {
+        // Nothing to suggest for, or a command palette query that must stay local
+        state.suggest.cancel();
+    } else if suggestions_enabled {
+        let engine = state.settings.read().unwrap().default_engine();
+        match state.suggest.cached(&query) {
+            Some(cached) => results = suggest::merge_results(results, &query, &cached, &engine),
+            None => {
+                let suggest_manager = state.suggest.clone();
+                let app_clone = app.clone();
+                let query_clone = query.clone();
+                match proxy::http_client(&state, suggest::REQUEST_TIMEOUT) {
+                    Ok(client) => {
+                        tauri::async_runtime::spawn(async move {
+                            if suggest_manager.fetch(&engine, &query_clone, client).await.is_some() {
+                                // Omnibox re-renders if the query is still current
+                                let _ = app_clone
+                                    .emit("search-suggestions-ready", serde_json::json!({ "query": query_clone }));
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("[Suggest] {}", e),
+                }
+            }
+        }
+    }
     
     let is_ready = state.dropdown_ready.lock().map(|r| *r).unwrap_or(false);
     let payload = DropdownPayload { query: query.clone(), results: results.clone(), selected_index: selected_index };
@@ -1014,131 +1726,265 @@ fn update_dropdown(app: AppHandle, state: tauri::State<AppState>, query: String,
         if let Ok(mut pending) = state.pending_payload.lock() {
             *pending = Some(payload);
         }
-        return;
+        return Ok(results);
     }
     
-    if let Some(win) = app.get_window("dropdown") {
-        if results.is_empty() {
-            println!("[dropdown] No results, hiding dropdown");
-            let hide_result = win.hide();
-            println!("[dropdown] hide() result: {:?}", hide_result);
-            return;
-        }
+    if dropdown::active().is_none() {
+        println!("[dropdown] ERROR: dropdown not created, can't show results!");
+        return Ok(results);
+    }
+    if results.is_empty() {
+        println!("[dropdown] No results, hiding dropdown");
+        let hide_result = dropdown::hide(&app);
+        println!("[dropdown] hide() result: {:?}", hide_result);
+        return Ok(results);
+    }
 
-        // Emit payload FIRST
-        let emit_result = win.emit("update-dropdown", payload);
-        println!("[dropdown] emit result: {:?}", emit_result);
-        
-        // Show window WITHOUT stealing focus
-        let show_result = win.show();
-        println!("[dropdown] show() result: {:?}", show_result);
-        
-        // Force always on top to ensure visibility
-        let aot_result = win.set_always_on_top(true);
-        println!("[dropdown] set_always_on_top result: {:?}", aot_result);
-        
-        // Immediately refocus main window to prevent dropdown from stealing focus
-        if let Some(main) = app.get_window("main") {
-            let main_focus = main.set_focus();
-            println!("[dropdown] refocused main window result: {:?}", main_focus);
-        }
-    } else {
-        println!("[dropdown] ERROR: dropdown window not found in update_dropdown!");
+    // Emit payload FIRST
+    let emit_result = app.emit_to(dropdown::LABEL, "update-dropdown", payload);
+    println!("[dropdown] emit result: {:?}", emit_result);
+
+    // Show WITHOUT stealing focus from the address bar (nothing is shown when headless)
+    dropdown::show(&app);
+
+    // Returned so the omnibox keeps keyboard selection in sync with merged suggestions
+    Ok(results)
+}
+
+#[tauri::command]
+fn search_history(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    query: String,
+) -> Result<Vec<HistoryEntryScoped>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.history.search(query, 10))
+}
+
+/// Shift+Delete on a history row in the omnibox dropdown. Returns false if it wasn't in history.
+#[tauri::command]
+fn delete_suggestion(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+) -> Result<bool, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let removed = state.history.delete_entry(&url)?;
+    if removed {
+        println!("[History] Removed suggestion {}", url);
     }
+    Ok(removed)
 }
 
+// --- History Page Commands ---
+
 #[tauri::command]
-fn search_history(state: tauri::State<AppState>, query: String) -> Vec<HistoryEntryScoped> {
-    state.history.search(query, 10)
+fn get_history_page(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    offset: usize,
+    limit: usize,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<HistoryPage, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.history.list(offset, limit, from, to))
 }
 
+/// Per-day visit counts and top domains for the history calendar, in local time.
 #[tauri::command]
-fn go_back(app: AppHandle, state: tauri::State<AppState>) {
-    let active_label = {
-        let active = state.active_tab_id.lock().unwrap();
-        let tabs = state.tabs.lock().unwrap();
-        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-    }; 
-    if let Some(label) = active_label {
-        if let Some(webview) = app.get_webview(&label) {
-            let _ = webview.eval("window.history.back()");
-        }
+fn get_history_activity(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    period: ActivityPeriod,
+) -> Result<Vec<DayActivity>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let from = now.saturating_sub(period.days() * 86400);
+    Ok(state.history.activity(from, &chrono::Local, 5))
+}
+
+#[tauri::command]
+fn delete_history_entry(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+) -> Result<bool, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.history.delete_entry(&url)?)
+}
+
+#[tauri::command]
+fn delete_history_range(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    from: u64,
+    to: u64,
+) -> Result<usize, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.history.delete_range(from, to)?)
+}
+
+#[tauri::command]
+fn clear_history(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.history.clear()?)
+}
+
+#[tauri::command]
+fn go_back(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    let label = active_webview_label(&state).ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+    if let Some(webview) = app.get_webview(&label) {
+        webview.eval("window.history.back()")?;
     }
+    Ok(())
 }
 
 #[tauri::command]
-fn go_forward(app: AppHandle, state: tauri::State<AppState>) {
-    let active_label = {
-        let active = state.active_tab_id.lock().unwrap();
-        let tabs = state.tabs.lock().unwrap();
-        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-    }; 
-    if let Some(label) = active_label {
-        if let Some(webview) = app.get_webview(&label) {
-            let _ = webview.eval("window.history.forward()");
-        }
+fn go_forward(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    let label = active_webview_label(&state).ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+    if let Some(webview) = app.get_webview(&label) {
+        webview.eval("window.history.forward()")?;
     }
+    Ok(())
 }
 
 #[tauri::command]
-fn copy_current_url(app: AppHandle) -> Result<(), String> {
+fn copy_current_url(app: AppHandle) -> Result<(), BrowserError> {
     if let Some(webview) = app.get_webview("content") {
         if let Ok(url) = webview.url() {
-            app.clipboard().write_text(url.to_string()).map_err(|e| e.to_string())?;
+            app.clipboard().write_text(url.to_string()).map_err(|e| BrowserError::Internal(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Offers a copied link to the toolbar ("Open copied link" chip). Opt-in; the
+/// clipboard is only read locally and nothing is sent anywhere.
+fn check_clipboard_for_url(app: &AppHandle) {
+    let state = match app.try_state::<AppState>() {
+        Some(state) => state,
+        None => return,
+    };
+    if !state.settings.read().unwrap().clipboard_url_detection {
+        return;
+    }
+
+    let text = app.clipboard().read_text().unwrap_or_default();
+    if let Some(url) = state.clipboard.check(&text) {
+        // Don't offer the page that is already open
+        let current = active_webview_label(&state)
+            .and_then(|label| app.get_webview(&label))
+            .and_then(|wv| wv.url().ok());
+        if current.map(|u| u.to_string()) != Some(url.clone()) {
+            let _ = app.emit_to("main", "clipboard-url", serde_json::json!({ "url": url }));
+        }
+    }
+}
+
+/// Moves platform focus to `target`. Callers hold the FocusManager lock so
+/// transitions can't interleave.
+fn apply_focus(app: &AppHandle, state: &AppState, target: FocusTarget) -> Result<(), BrowserError> {
+    match target {
+        FocusTarget::Toolbar => {
+            // Invariant: Main window must be focused first
+            if let Some(main_win) = app.get_window("main") {
+                main_win.set_focus()?;
+            }
+            // Invariant: Explicitly focus the toolbar webview (which has label "main" in this setup)
+            if let Some(webview) = app.get_webview("main") {
+                webview.set_focus()?;
+            }
+            // Signal frontend to focus the specific DOM element
+            app.emit_to("main", "focus-url-bar", ())?;
+        }
+        FocusTarget::Content => {
+            if let Some(main_win) = app.get_window("main") {
+                main_win.set_focus()?;
+            }
+            // Invariant: Active Webview must be explicitly focused
+            let active_label = {
+                let active = state.active_tab_id.lock().unwrap();
+                let tabs = state.tabs.lock().unwrap();
+                active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+            };
+            if let Some(wv) = active_label.and_then(|label| app.get_webview(&label)) {
+                wv.set_focus()?;
+            }
+        }
+        FocusTarget::Find => {
+            if let Some(find_win) = app.get_window("find") {
+                find_win.set_focus()?;
+            }
+        }
+        FocusTarget::Settings => {
+            if let Some(win) = app.get_window("settings") {
+                win.set_focus()?;
+            }
         }
     }
     Ok(())
 }
 
+/// Bottom-right corner of the main window for the find bar, in screen pixels.
+/// Uses the find window's own size, which follows the scale of the monitor it's on.
+fn find_window_position(main: &Window, find_size: PhysicalSize<u32>) -> Option<PhysicalPosition<i32>> {
+    let main_size = main.inner_size().ok()?;
+    let main_pos = main.inner_position().ok()?;
+    let margin = (20.0 * main.scale_factor().ok()?) as i32;
+    Some(PhysicalPosition::new(
+        main_pos.x + main_size.width as i32 - find_size.width as i32 - margin,
+        main_pos.y + main_size.height as i32 - find_size.height as i32 - margin,
+    ))
+}
+
+fn request_focus_logic(app: &AppHandle, state: &AppState, target: FocusTarget) -> Result<(), BrowserError> {
+    let active_id = state.active_tab_id.lock().unwrap().clone();
+    let mut focus = state.focus.lock();
+    let target = focus.request(target, active_id.as_deref());
+    apply_focus(app, state, target)
+}
+
+/// An overlay closed: put focus back on the toolbar or page underneath.
+fn restore_focus(app: &AppHandle, state: &AppState) {
+    let mut focus = state.focus.lock();
+    let target = focus.restore();
+    let _ = apply_focus(app, state, target);
+}
+
 #[tauri::command]
-fn focus_toolbar(app: AppHandle) -> Result<(), String> {
-    // Invariant: Main window must be focused first
-    if let Some(main_win) = app.get_window("main") {
-        main_win.set_focus().map_err(|e| e.to_string())?;
-    }
-    
-    // Invariant: Explicitly focus the toolbar webview (which has label "main" in this setup)
-    if let Some(webview) = app.get_webview("main") {
-        webview.set_focus().map_err(|e| e.to_string())?;
-    }
+fn request_focus(app: AppHandle, state: tauri::State<AppState>, target: FocusTarget) -> Result<(), BrowserError> {
+    request_focus_logic(&app, &state, target)
+}
 
-    // Signal frontend to focus the specific DOM element
-    app.emit("focus-url-bar", ()).map_err(|e| e.to_string())?;
-    
+/// Reports focus that moved on its own (user clicked the URL bar or the page),
+/// so the manager restores to the right place later.
+#[tauri::command]
+fn focus_changed(state: tauri::State<AppState>, target: FocusTarget) -> Result<(), BrowserError> {
+    let active_id = state.active_tab_id.lock().unwrap().clone();
+    state.focus.lock().observe(target, active_id.as_deref());
     Ok(())
 }
 
 #[tauri::command]
-fn focus_content(app: AppHandle, state: tauri::State<AppState>) -> Result<(), String> {
-    // Invariant: Main window must be focused first
-    if let Some(main_win) = app.get_window("main") {
-        main_win.set_focus().map_err(|e| e.to_string())?;
-    }
+fn focus_toolbar(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    request_focus_logic(&app, &state, FocusTarget::Toolbar)
+}
 
-    // Invariant: Active Webview must be explicitly focused
-    let active_label = {
-        let active = state.active_tab_id.lock().unwrap();
-        let tabs = state.tabs.lock().unwrap();
-        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-    };
-    
-    if let Some(label) = active_label {
-        if let Some(wv) = app.get_webview(&label) {
-            wv.set_focus().map_err(|e| e.to_string())?;
-        }
-    }
-    Ok(())
+#[tauri::command]
+fn focus_content(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    request_focus_logic(&app, &state, FocusTarget::Content)
 }
 
 #[tauri::command]
-fn toggle_window_maximize(app: AppHandle) -> Result<(), String> {
+fn toggle_window_maximize(app: AppHandle) -> Result<(), BrowserError> {
     if let Some(window) = app.get_window("main") {
-        let is_maximized = window.is_maximized().map_err(|e| e.to_string())?;
+        let is_maximized = window.is_maximized()?;
 
         if is_maximized {
-            window.unmaximize().map_err(|e| e.to_string())?;
+            window.unmaximize()?;
         } else {
-            window.maximize().map_err(|e| e.to_string())?;
+            window.maximize()?;
         }
     }
     Ok(())
@@ -1157,7 +2003,7 @@ async fn find_in_webview(
     state: tauri::State<'_, AppState>,
     query: String,
     forward: bool,
-) -> Result<FindResult, String> {
+) -> Result<FindResult, BrowserError> {
     let active_id = state.active_tab_id.lock().unwrap().clone();
     let webview_label = {
         let tabs = state.tabs.lock().unwrap();
@@ -1295,7 +2141,7 @@ async fn find_in_webview(
                 forward
             );
 
-            webview.eval(&find_script).map_err(|e| e.to_string())?;
+            webview.eval(&find_script)?;
 
             Ok(FindResult {
                 found: true,
@@ -1303,10 +2149,10 @@ async fn find_in_webview(
                 total_matches: 1,
             })
         } else {
-            Err("Webview not found".to_string())
+            Err(BrowserError::NotFound("Webview not found".to_string()))
         }
     } else {
-        Err("No active tab".to_string())
+        Err(BrowserError::NotFound("No active tab".to_string()))
     }
 }
 
@@ -1314,7 +2160,7 @@ async fn find_in_webview(
 async fn clear_find_highlights(
     app: AppHandle,
     state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), BrowserError> {
     let active_id = state.active_tab_id.lock().unwrap().clone();
     let webview_label = {
         let tabs = state.tabs.lock().unwrap();
@@ -1341,7 +2187,7 @@ async fn clear_find_highlights(
                     }
                 })()
                 "#
-            ).map_err(|e| e.to_string())?;
+            )?;
         }
     }
     Ok(())
@@ -1353,7 +2199,7 @@ async fn report_find_result(
     found: bool,
     current_match: usize,
     total_matches: usize,
-) -> Result<(), String> {
+) -> Result<(), BrowserError> {
     println!("[RUST] report_find_result called: found={}, current={}, total={}",
              found, current_match, total_matches);
 
@@ -1367,7 +2213,7 @@ async fn report_find_result(
     app.emit("find-result", &payload)
         .map_err(|e| {
             println!("[RUST] Failed to emit globally: {}", e);
-            e.to_string()
+            BrowserError::from(e)
         })?;
 
     println!("[RUST] Successfully emitted find-result globally to all windows");
@@ -1376,31 +2222,436 @@ async fn report_find_result(
 }
 
 #[tauri::command]
-async fn hide_find_window(app: AppHandle) -> Result<(), String> {
+async fn hide_find_window(app: AppHandle, state: tauri::State<'_, AppState>) -> Result<(), BrowserError> {
     if let Some(find_win) = app.get_window("find") {
-        find_win.hide().map_err(|e| e.to_string())?;
+        find_win.hide()?;
     }
+    restore_focus(&app, &state);
     Ok(())
 }
 
+/// Runs a browser command by id. Shared by the native menu and the omnibox command palette
+/// so both stay in sync with `modules::commands::COMMANDS`.
+fn run_browser_command(app: &AppHandle, id: &str) {
+    match id {
+        "settings" => show_settings_window(app),
+        "leave_suggestion" => show_suggestion_window(app),
+        
+        // Tab Actions
+        "new_tab" => {
+            let h = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    let url = top_sites::new_tab_url(&state.settings.read().unwrap());
+                    let _ = create_tab_with_url(&h, &state, url);
+                    // New tabs start in the URL bar; remembered for this tab
+                    let _ = request_focus_logic(&h, &state, FocusTarget::Toolbar);
+                }
+            });
+        },
+        "guest_window" => {
+            if let Err(e) = guest::open_window(app) {
+                eprintln!("[Guest] Failed to open guest window: {}", e);
+            }
+        },
+        "close_tab" => {
+             let h = app.clone();
+             tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    let active_id = {
+                        let active = state.active_tab_id.lock().unwrap();
+                        active.clone()
+                    };
+                    if let Some(id) = active_id {
+                        let _ = close_tab_logic(&h, &state, id, true).await;
+                    }
+                }
+             });
+        },
+        "export_har" => {
+            let active = app.try_state::<AppState>().and_then(|state| state.active_tab_id.lock().unwrap().clone());
+            if let Some(tab_id) = active {
+                network_log::export(app, tab_id);
+            }
+        },
+        "export_console_log" => {
+            let active = app.try_state::<AppState>().and_then(|state| state.active_tab_id.lock().unwrap().clone());
+            if let Some(tab_id) = active {
+                let h = app.clone();
+                app.dialog()
+                    .file()
+                    .add_filter("Log", &["log", "txt"])
+                    .set_file_name("console.log")
+                    .save_file(move |path| {
+                        let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+                            return;
+                        };
+                        if let Some(state) = h.try_state::<AppState>() {
+                            if let Err(e) = console_log::write_text(&state, &tab_id, path) {
+                                eprintln!("[ConsoleLog] Failed to export console log: {}", e);
+                            }
+                        }
+                    });
+            }
+        },
+        "webauthn_support" => webauthn::show_report(app),
+        "task_manager" => show_task_manager_window(app),
+        "pop_out_video" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                if let Err(e) = video_popout::pop_out_video(app.clone(), state, None) {
+                    eprintln!("[VideoPopout] {}", e);
+                }
+            }
+        },
+        "read_aloud" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                if let Err(e) = read_aloud::read_aloud(app.clone(), state, None) {
+                    eprintln!("[ReadAloud] {}", e);
+                }
+            }
+        },
+        "generate_email_alias" => email_alias::run(app),
+        "fill_otp_code" => totp::run(app),
+        "highlight_selection" => annotations::highlight_selection(app),
+        "export_annotations" => annotations::export(app),
+        "install_site_app" => site_apps::install_active_tab(app),
+        "stop_read_aloud" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                let _ = read_aloud::stop_read_aloud(state);
+            }
+        },
+        "toggle_mute_tab" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                let active = state.active_tab_id.lock().unwrap().clone();
+                let muted = active.as_ref().and_then(|id| {
+                    state.tabs.lock().unwrap().iter().find(|t| &t.id == id).map(|t| t.is_muted)
+                });
+                if let (Some(id), Some(muted)) = (active, muted) {
+                    if let Err(e) = tab_audio::set_tab_muted(app, &state, &id, !muted) {
+                        eprintln!("[TabAudio] {}", e);
+                    }
+                }
+            }
+        },
+        "toggle_split_view" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                if split_view::exit(app, &state) {
+                    return;
+                }
+                // Split the active tab with the next one
+                let pair = {
+                    let tabs = state.tabs.lock().unwrap();
+                    let active = state.active_tab_id.lock().unwrap().clone();
+                    active.and_then(|id| {
+                        let index = tabs.iter().position(|t| t.id == id)?;
+                        Some((id, tab_windows::main_neighbour(&tabs, index)?))
+                    })
+                };
+                if let Some((left, right)) = pair {
+                    if let Err(e) = enter_split_view_logic(app, &state, left, right) {
+                        eprintln!("[SplitView] {}", e);
+                    }
+                }
+            }
+        },
+        "move_tab_to_window" => {
+            let h = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    let active = state.active_tab_id.lock().unwrap().clone();
+                    if let Some(id) = active {
+                        if let Err(e) = detach_tab_logic(&h, &state, id, None) {
+                            eprintln!("[TabWindows] {}", e);
+                        }
+                    }
+                }
+            });
+        },
+        "stash_other_tabs" => {
+            let h = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    let ids: Vec<String> = {
+                        let active = state.active_tab_id.lock().unwrap().clone();
+                        let tabs = state.tabs.lock().unwrap();
+                        tabs.iter().filter(|t| Some(&t.id) != active.as_ref()).map(|t| t.id.clone()).collect()
+                    };
+                    if let Err(e) = stash_tabs_logic(&h, &state, ids, String::new()).await {
+                        eprintln!("[Commands] Failed to stash tabs: {}", e);
+                    }
+                }
+            });
+        },
+        "next_tab" | "prev_tab" => {
+             let h = app.clone();
+             let is_next = id == "next_tab";
+             tauri::async_runtime::spawn(async move {
+                 if let Some(state) = h.try_state::<AppState>() {
+                     // Logic to find next ID
+                     let mut target_id = None;
+                     {
+                         let tabs = state.tabs.lock().unwrap();
+                         let tabs: Vec<&Tab> = tabs.iter().filter(|t| t.window.is_none()).collect(); // Main window only
+                         let active = state.active_tab_id.lock().unwrap();
+                         if let Some(act) = active.as_ref() {
+                             if let Some(pos) = tabs.iter().position(|t| t.id == *act) {
+                                 let new_pos = if is_next {
+                                     (pos + 1) % tabs.len()
+                                 } else {
+                                     (pos + tabs.len() - 1) % tabs.len()
+                                 };
+                                 target_id = Some(tabs[new_pos].id.clone());
+                             }
+                         }
+                     }
+
+                     if let Some(tid) = target_id {
+                         let _ = switch_tab_logic(&h, &state, tid);
+                     }
+                 }
+             });
+        },
+
+        // Focus Actions - Emit to Main Window
+        "focus_location" | "focus_location_alt" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                let _ = request_focus_logic(app, &state, FocusTarget::Toolbar);
+            }
+        },
+        "find_in_page" => {
+            if headless::enabled() {
+                return;
+            }
+            if let Some(find_win) = app.get_window("find") {
+                if let Some(main_win) = app.get_window("main") {
+                    // Position find window at bottom-right of main window
+                    let find_size = find_win.outer_size().ok();
+                    if let Some(position) = find_size.and_then(|size| find_window_position(&main_win, size)) {
+                        let _ = find_win.set_position(tauri::Position::Physical(position));
+                    }
+                    let _ = find_win.show();
+                    if let Some(state) = app.try_state::<AppState>() {
+                        let _ = request_focus_logic(app, &state, FocusTarget::Find);
+                    }
+                }
+            }
+        },
+
+        // Navigation Actions (Delegated to Active Tab)
+        "reload" => {
+             if let Some(state) = app.try_state::<AppState>() {
+                 let label = {
+                     let tabs = state.tabs.lock().unwrap();
+                     let active = state.active_tab_id.lock().unwrap();
+                     active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+                 };
+                 if let Some(l) = label {
+                     if let Some(wv) = app.get_webview(&l) {
+                         let _ = wv.eval("window.location.reload()");
+                     }
+                 }
+            }
+        },
+        "hard_reload" => {
+             if let Some(state) = app.try_state::<AppState>() {
+                 let label = {
+                     let tabs = state.tabs.lock().unwrap();
+                     let active = state.active_tab_id.lock().unwrap();
+                     active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+                 };
+                 if let Some(l) = label {
+                     if let Some(wv) = app.get_webview(&l) {
+                         if let Ok(url) = wv.url() {
+                            let js = format!("window.location.href = '{}'", url);
+                            let _ = wv.eval(&js);
+                         }
+                     }
+                 }
+            }
+        },
+        "go_back" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                 let label = {
+                     let tabs = state.tabs.lock().unwrap();
+                     let active = state.active_tab_id.lock().unwrap();
+                     active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+                 };
+                 if let Some(l) = label {
+                     if let Some(wv) = app.get_webview(&l) {
+                         let _ = wv.eval("window.history.back()");
+                     }
+                 }
+            }
+        },
+        "go_forward" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                 let label = {
+                     let tabs = state.tabs.lock().unwrap();
+                     let active = state.active_tab_id.lock().unwrap();
+                     active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+                 };
+                 if let Some(l) = label {
+                     if let Some(wv) = app.get_webview(&l) {
+                         let _ = wv.eval("window.history.forward()");
+                     }
+                 }
+            }
+        },
+        "reopen_closed_tab" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                match restore_closed_tab(app.clone(), state) {
+                    Ok(tab_id) => println!("[Menu] Restored tab: {}", tab_id),
+                    Err(e) => eprintln!("[Menu] Failed to restore tab: {}", e),
+                }
+            }
+        },
+
+        "print" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                 let label = {
+                     let tabs = state.tabs.lock().unwrap();
+                     let active = state.active_tab_id.lock().unwrap();
+                     active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+                 };
+                 if let Some(l) = label {
+                     if let Some(wv) = app.get_webview(&l) {
+                         let _ = wv.eval("window.print()");
+                     }
+                 }
+            }
+        },
+        "open_devtools" => {
+            let h = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    if let Err(e) = open_devtools(h.clone(), state, None) {
+                        eprintln!("[DevTools] {}", e);
+                    }
+                }
+            });
+        },
+        "clear_site_data" => {
+            let h = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Some(state) = h.try_state::<AppState>() {
+                    if let Err(e) = clear_site_data(h.clone(), state).await {
+                        eprintln!("[Commands] Failed to clear site data: {}", e);
+                    }
+                }
+            });
+        },
+        "element_picker" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                if let Err(e) = element_picker::start(app, &state) {
+                    eprintln!("[Commands] Failed to start element picker: {}", e);
+                }
+            }
+        },
+        "toggle_offline" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                offline::set(app, &state, !offline::is_offline(&state));
+            }
+        },
+        "toggle_adblock" => {
+            if let Some(state) = app.try_state::<AppState>() {
+                let mut settings = state.settings.read().unwrap().clone();
+                settings.block_trackers = !settings.block_trackers;
+                println!("[Commands] Ad blocking {}", if settings.block_trackers { "enabled" } else { "disabled" });
+                if let Err(e) = apply_settings(app.clone(), state, settings) {
+                    eprintln!("[Commands] Failed to save settings: {}", e);
+                }
+            }
+        },
+        _ => {
+            // Snippet hotkeys and menu items (snippet:<id>)
+            if let Some(snippet_id) = id.strip_prefix(snippets::MENU_ITEM_PREFIX).and_then(|s| s.parse::<u64>().ok()) {
+                if let Some(state) = app.try_state::<AppState>() {
+                    if let Err(e) = snippets::run(app, &state, snippet_id) {
+                        eprintln!("[Snippets] {}", e);
+                    }
+                }
+                return;
+            }
+
+            // Page context menu items (context_menu:<action>)
+            if let Some(action) = ContextAction::from_menu_id(id) {
+                let h = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Some(state) = h.try_state::<AppState>() {
+                        if let Err(e) = run_context_menu_action(&h, state, action) {
+                            eprintln!("[ContextMenu] {}", e);
+                        }
+                    }
+                });
+                return;
+            }
+
+            // Apps menu items (site_app:<id>)
+            if let Some(app_id) = id.strip_prefix(site_apps::MENU_ITEM_PREFIX) {
+                site_apps::open_by_id(app, app_id);
+                return;
+            }
+
+            // Numeric Shortcuts (tab_1 .. tab_9)
+            if id.starts_with("tab_") && id.len() == 5 {
+                if let Ok(num) = id["tab_".len()..].parse::<usize>() {
+                    let index = num - 1; // 0-indexed
+                    let h = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Some(state) = h.try_state::<AppState>() {
+                            let target_id_opt = {
+                                let tabs = state.tabs.lock().unwrap();
+                                let tabs: Vec<&Tab> = tabs.iter().filter(|t| t.window.is_none()).collect();
+                                if index < tabs.len() {
+                                    Some(tabs[index].id.clone())
+                                } else {
+                                    None
+                                }
+                            };
+                            if let Some(tid) = target_id_opt {
+                                let _ = switch_tab_logic(&h, &state, tid);
+                            }
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
+
 fn main() {
+    // --- Command-line flags (see modules::cli) ---
+    let launch = match cli::parse(&std::env::args().collect::<Vec<_>>()) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("{}\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if launch.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if let Some(name) = &launch.profile {
+        profile::set_active(name);
+    }
+
     tauri::Builder::default()
+        .register_uri_scheme_protocol(thumbnails::SCHEME, |ctx, request| {
+            thumbnails::handle_request(ctx.app_handle(), ctx.webview_label(), &request)
+        })
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_opener::init())
         // Single Instance: Handle "Hot Start" - focus existing window on second launch
         .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
-            // For file paths passed as args (double-click on .html file)
-            let url = argv.iter().skip(1).find(|arg| {
-                arg.starts_with("/") && (arg.ends_with(".html") || arg.ends_with(".htm"))
-            });
-            
-            if let Some(raw_path) = url {
-                // Normalize: macOS passes /path/to/file.html, we need file://
-                let normalized = format!("file://{}", raw_path);
-                let _ = app.emit("request-open-url", &normalized);
-            }
+            // Flags, URLs and file paths (double-click on .html file) of the second launch
+            let opened_window = cli::handle_second_launch(app, &argv);
             
             // Focus main window
+            if headless::enabled() || opened_window {
+                return;
+            }
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.set_focus();
             }
@@ -1409,31 +2660,48 @@ fn main() {
         .plugin(tauri_plugin_deep_link::init())
         .setup(move |app| {
             let main_window: Window = app.get_window("main").unwrap();
-            
-            // --- Title Bar Style (macOS) ---
-            #[cfg(target_os = "macos")]
-            {
-               let _ = main_window.set_title_bar_style(TitleBarStyle::Overlay);
-               // Also make transparent if needed for vibrancy, but Overlay is key.
+            if headless::enabled() {
+                headless::apply(app, &main_window);
             }
+            
+            // --- Title Bar Style (macOS overlay, Windows custom) ---
+            titlebar::apply(&main_window);
             let handle = app.handle().clone();
             
             // Initialize History Store
-            let app_data_dir = app.path().app_data_dir().expect("failed to get app data dir");
+            let app_data_dir = profile::data_dir(app.handle()).expect("failed to get app data dir");
             let history_store = Arc::new(HistoryStore::new(app_data_dir));
             
             // Initialize Settings (load from disk or default)
             let settings = Arc::new(RwLock::new(Settings::load(app.handle())));
+
+            // --- Window Material & Titlebar Tint ---
+            {
+                let s = settings.read().unwrap();
+                appearance::apply_window_appearance(&main_window, s.window_material, s.titlebar_tint.as_deref());
+                text_input::set_dictation_menu(s.dictation);
+            }
             
+            // Initialize DNS-over-HTTPS and proxy settings (the local proxy starts with the first tab that needs it)
+            let doh_manager = Arc::new(DohManager::new());
+            doh_manager.resolver.set_endpoint(settings.read().unwrap().doh_endpoint());
+            doh_manager.set_proxy_settings(settings.read().unwrap().proxy.clone());
+
             // Initialize Ad Blocking Engine
             let adblock_manager = Arc::new(AdBlockManager::new(app.handle()));
             
-            // Start background thread to fetch/update rules
-            // Start background thread to fetch/update rules
-            adblock_manager.spawn_update_thread();
-
             // Initialize DevTools Manager
-            let devtools_manager = Arc::new(DevToolsManager::new(9222));
+            // Blocked request counters, saved periodically
+            let block_stats = Arc::new(BlockStatsManager::new(app.handle()));
+            block_stats.spawn_flush_thread();
+
+            let image_blocker = Arc::new(ImageBlocker::new(&settings.read().unwrap()));
+            let data_saver = Arc::new(DataSaverManager::new());
+            data_saver.spawn_monitor(app.handle().clone());
+            let safe_browsing = Arc::new(SafeBrowsingManager::new(app.handle()));
+            safe_browsing.spawn_update_thread(app.handle().clone());
+
+            let devtools_manager = Arc::new(DevToolsManager::new(9222, devtools::frontend_dir(app.handle())));
             devtools_manager.clone().start();
 
             // Load closed tabs from disk
@@ -1452,7 +2720,84 @@ fn main() {
                 adblock: adblock_manager.clone(),
                 devtools: devtools_manager,
                 closed_tabs: closed_tabs,
+                suggest: Arc::new(SuggestManager::new()),
+                clipboard: Arc::new(ClipboardManager::new()),
+                doh: doh_manager,
+                status_seq: Arc::new(AtomicU64::new(0)),
+                focus: Arc::new(FocusManager::new()),
+                fingerprint_secret: fingerprint::load_or_create_secret(&fingerprint::secret_path(app.handle())),
+                storage_inspector: Arc::new(StorageInspector::new()),
+                https_only: Arc::new(HttpsOnlyManager::new()),
+                block_stats,
+                frames: Arc::new(FrameTracker::new()),
+                user_agents: Arc::new(UserAgentManager::new()),
+                data_saver,
+                image_blocker,
+                safe_browsing,
+                audio_output: Arc::new(AudioOutputManager::new()),
+                media_controls: Arc::new(MediaControlsManager::new()),
+                network_log: Arc::new(NetworkLogManager::new()),
+                permissions: Arc::new(PermissionsManager::new()),
+                console_log: Arc::new(ConsoleLogManager::new(
+                    profile::log_dir(app.handle()).expect("failed to get app log dir"),
+                )),
+                task_manager: Arc::new(TaskManager::new()),
+                video_popout: Arc::new(VideoPopoutManager::new()),
+                read_aloud: Arc::new(ReadAloudManager::new()),
+                text_replace: Arc::new(TextReplaceManager::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                snippets: Arc::new(SnippetsManager::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                email_aliases: Arc::new(EmailAliasManager::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                split_view: Arc::new(SplitViewManager::new()),
+                totp: Arc::new(TotpVault::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                breach_check: Arc::new(BreachCheckStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                top_sites: Arc::new(TopSitesStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                thumbnails: Arc::new(ThumbnailCache::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                annotations: Arc::new(AnnotationStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                site_apps: Arc::new(SiteAppStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                privacy_reports: Arc::new(PrivacyReportStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                maintenance: Arc::new(MaintenanceScheduler::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                external_protocols: Arc::new(ExternalProtocolStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                main_maximized: Arc::new(AtomicBool::new(main_window.is_maximized().unwrap_or(false))),
+                bookmarks: Arc::new(BookmarkStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                containers: Arc::new(ContainerStore::new(
+                    profile::data_dir(app.handle()).expect("failed to get app data dir"),
+                )),
+                dock: Arc::new(DockManager::new()),
+                offline: Arc::new(AtomicBool::new(false)),
             });
+            task_manager::spawn_sampler(app.handle().clone());
+            privacy_report::spawn_notification_thread(app.handle().clone());
+            maintenance::spawn_scheduler(app.handle().clone());
+            jump_list::spawn_updater(app.handle().clone());
+            list_updates::spawn_scheduler(app.handle().clone());
+            guest::remove_leftovers();
+            cli::handle_cold_start(app.handle(), &launch);
             
             // macOS: Apply cached Safari rules to existing webviews after a delay
             // (gives time for the first tab to be created)
@@ -1477,7 +2822,8 @@ fn main() {
                         for tab in tabs.iter() {
                             if let Some(webview) = app_handle.get_webview(&tab.webview_label) {
                                 println!("[AdBlock] Applying content blocking to: {}", tab.webview_label);
-                                apply_content_blocking_rules(&webview, &rules_json);
+                                let list_id = "SovereignBrowserAdBlock";
+                                browsing_webview::apply_content_blocking_rules(&webview, list_id, &rules_json);
                             }
                         }
                     }
@@ -1525,15 +2871,16 @@ fn main() {
             let sovereign_menu = SubmenuBuilder::new(app, "Sovereign")
                 .item(&PredefinedMenuItem::about(app, Some("About Sovereign Browser"), None)?)
                 .separator()
-                .item(&MenuItemBuilder::with_id("settings", "Settings").accelerator("CmdOrCtrl+,").build(app)?)
+                .item(&command_menu_item(app, "settings")?)
                 .separator()
                 .item(&PredefinedMenuItem::quit(app, Some("Quit Sovereign Browser"))?)
                 .build()?;
 
             let file_menu = SubmenuBuilder::new(app, "File")
-                .item(&MenuItemBuilder::with_id("new_tab", "New Tab").accelerator("CmdOrCtrl+T").build(app)?)
-                .item(&MenuItemBuilder::with_id("print", "Print...").accelerator("CmdOrCtrl+P").build(app)?)
-                .item(&MenuItemBuilder::with_id("close_tab", "Close Tab").accelerator("CmdOrCtrl+W").build(app)?)
+                .item(&command_menu_item(app, "new_tab")?)
+                .item(&command_menu_item(app, "guest_window")?)
+                .item(&command_menu_item(app, "print")?)
+                .item(&command_menu_item(app, "close_tab")?)
                 .build()?;
 
             let edit_menu = SubmenuBuilder::new(app, "Edit")
@@ -1545,30 +2892,37 @@ fn main() {
                 .item(&PredefinedMenuItem::paste(app, Some("Paste"))?)
                 .item(&PredefinedMenuItem::select_all(app, Some("Select All"))?)
                 .separator()
-                .item(&MenuItemBuilder::with_id("find_in_page", "Find in Page").accelerator("CmdOrCtrl+F").build(app)?)
+                .item(&command_menu_item(app, "find_in_page")?)
                 .build()?;
 
             let view_menu = SubmenuBuilder::new(app, "View")
-                .item(&MenuItemBuilder::with_id("focus_location", "Open Location").accelerator("CmdOrCtrl+L").build(app)?)
-                .item(&MenuItemBuilder::with_id("focus_location_alt", "Open Location (Alt)").accelerator("CmdOrCtrl+K").build(app)?)
-                .item(&MenuItemBuilder::with_id("reload", "Reload Page").accelerator("CmdOrCtrl+R").build(app)?)
-                .item(&MenuItemBuilder::with_id("hard_reload", "Hard Reload").accelerator("CmdOrCtrl+Shift+R").build(app)?)
+                .item(&command_menu_item(app, "focus_location")?)
+                .item(&command_menu_item(app, "focus_location_alt")?)
+                .item(&command_menu_item(app, "reload")?)
+                .item(&command_menu_item(app, "hard_reload")?)
                 .separator()
-                .item(&MenuItemBuilder::with_id("next_tab", "Next Tab").accelerator("CmdOrCtrl+Shift+]").build(app)?)
-                .item(&MenuItemBuilder::with_id("prev_tab", "Previous Tab").accelerator("CmdOrCtrl+Shift+[").build(app)?)
+                .item(&command_menu_item(app, "next_tab")?)
+                .item(&command_menu_item(app, "prev_tab")?)
                 .separator()
-                .item(&MenuItemBuilder::with_id("open_devtools", "Developer Tools").accelerator("CmdOrCtrl+Option+I").build(app)?)
+                .item(&command_menu_item(app, "open_devtools")?)
+                .item(&command_menu_item(app, "task_manager")?)
                 .build()?;
 
             let history_menu = SubmenuBuilder::new(app, "History")
-                .item(&MenuItemBuilder::with_id("go_back", "Back").accelerator("CmdOrCtrl+[").build(app)?)
-                .item(&MenuItemBuilder::with_id("go_forward", "Forward").accelerator("CmdOrCtrl+]").build(app)?)
+                .item(&command_menu_item(app, "go_back")?)
+                .item(&command_menu_item(app, "go_forward")?)
                 .separator()
-                .item(&MenuItemBuilder::with_id("reopen_closed_tab", "Reopen Closed Tab").accelerator("CmdOrCtrl+Shift+T").build(app)?)
+                .item(&command_menu_item(app, "reopen_closed_tab")?)
                 .build()?;
 
+            // Filled from the snippet store, see modules::snippets
+            let snippets_menu = SubmenuBuilder::with_id(app, snippets::MENU_ID, "Snippets").build()?;
+
+            // Filled from the installed apps, see modules::site_apps
+            let apps_menu = SubmenuBuilder::with_id(app, site_apps::MENU_ID, "Apps").build()?;
+
             let feedback_menu = SubmenuBuilder::new(app, "Feedback")
-                .item(&MenuItemBuilder::with_id("leave_suggestion", "Leave a Suggestion...").build(app)?)
+                .item(&command_menu_item(app, "leave_suggestion")?)
                 .build()?;
 
             let window_menu = SubmenuBuilder::new(app, "Window")
@@ -1584,34 +2938,19 @@ fn main() {
                 .build()?;
 
             let menu = MenuBuilder::new(app)
-                .items(&[&sovereign_menu, &file_menu, &edit_menu, &view_menu, &history_menu, &window_menu, &feedback_menu])
+                .items(&[&sovereign_menu, &file_menu, &edit_menu, &view_menu, &history_menu, &snippets_menu, &apps_menu, &window_menu, &feedback_menu])
                 .build()?;
 
             app.set_menu(menu)?;
-            
-            // --- Create Dropdown Window (Hidden) ---
-            let dropdown_window = tauri::WebviewWindowBuilder::new(
-                app,
-                "dropdown",
-                tauri::WebviewUrl::App("dropdown.html".into())
-            )
-            .title("Dropdown")
-            .inner_size(400.0, 300.0) // Smaller default size
-            .decorations(false)
-            .visible(false)
-            .always_on_top(true) 
-            .skip_taskbar(true)
-            .focused(false) // Don't take focus
-            .build();
-            
-            match dropdown_window {
-                Ok(win) => {
-                    println!("[dropdown] Dropdown window created successfully: {:?}", win.label());
-                },
-                Err(e) => {
-                    println!("[dropdown] ERROR: Failed to create dropdown window: {:?}", e);
-                }
+            if let Err(e) = snippets::rebuild_menu(app.handle()) {
+                eprintln!("[Snippets] Failed to build menu: {}", e);
+            }
+            if let Err(e) = site_apps::rebuild_menu(app.handle()) {
+                eprintln!("[SiteApps] Failed to build menu: {}", e);
             }
+            
+            // --- Create Dropdown (Hidden): a window, or a child webview on Wayland ---
+            dropdown::create(app.handle());
 
             // --- Create Find Window (Hidden) ---
             let find_window = tauri::WebviewWindowBuilder::new(
@@ -1641,24 +2980,16 @@ fn main() {
                         let find_window_for_event = find_win_clone.clone();
 
                         main_window.on_window_event(move |event| {
-                            if let tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) = event {
+                            if let tauri::WindowEvent::Moved(_)
+                            | tauri::WindowEvent::Resized(_)
+                            | tauri::WindowEvent::ScaleFactorChanged { .. } = event
+                            {
                                 // Reposition find window when main window moves or resizes
                                 if let Some(main_ref) = find_window_for_event.app_handle().get_window("main") {
-                                    if let Ok(main_size) = main_ref.inner_size() {
-                                        if let Ok(main_pos) = main_ref.inner_position() {
-                                            if let Ok(scale) = main_ref.scale_factor() {
-                                                let find_width_physical = (400.0 * scale) as i32;
-                                                let find_height_physical = (50.0 * scale) as i32;
-                                                let margin_physical = (20.0 * scale) as i32;
-
-                                                let x = main_pos.x + (main_size.width as i32) - find_width_physical - margin_physical;
-                                                let y = main_pos.y + (main_size.height as i32) - find_height_physical - margin_physical;
-
-                                                let _ = find_window_for_event.set_position(tauri::Position::Physical(
-                                                    tauri::PhysicalPosition::new(x, y)
-                                                ));
-                                            }
-                                        }
+                                    let find_size = find_window_for_event.outer_size().ok();
+                                    let position = find_size.and_then(|size| find_window_position(&main_ref, size));
+                                    if let Some(position) = position {
+                                        let _ = find_window_for_event.set_position(tauri::Position::Physical(position));
                                     }
                                 }
                             }
@@ -1674,222 +3005,7 @@ fn main() {
             let handle_for_menu = handle.clone();
             
             app.on_menu_event(move |_app_handle, event| {
-                let id = event.id().0.as_str();
-                match id {
-                    "settings" => show_settings_window(&handle_for_menu),
-                    "leave_suggestion" => show_suggestion_window(&handle_for_menu),
-                    
-                    // Tab Actions
-                    "new_tab" => {
-                        let h = handle_for_menu.clone();
-                        tauri::async_runtime::spawn(async move {
-                            if let Some(state) = h.try_state::<AppState>() {
-                                let _ = create_tab_with_url(&h, &state, "https://duckduckgo.com".into());
-                                // Focus URL bar implicitly done by create_tab? 
-                                // Actually create_tab focuses content usually if URL provided, or we can force it here.
-                                // In the impl of create_tab, we switch to it. 
-                                // Let's ensure URL bar focus for "New Tab".
-                                if let Some(main) = h.get_window("main") {
-                                    let _ = main.set_focus();
-                                    let _ = main.emit("focus-url-bar", ());
-                                }
-                            }
-                        });
-                    },
-                    "close_tab" => {
-                         let h = handle_for_menu.clone();
-                         tauri::async_runtime::spawn(async move {
-                            if let Some(state) = h.try_state::<AppState>() {
-                                let active_id = {
-                                    let active = state.active_tab_id.lock().unwrap();
-                                    active.clone()
-                                };
-                                if let Some(id) = active_id {
-                                    let _ = close_tab_logic(&h, &state, id).await;
-                                }
-                            }
-                         });
-                    },
-                    "next_tab" | "prev_tab" => {
-                         let h = handle_for_menu.clone();
-                         let is_next = id == "next_tab";
-                         tauri::async_runtime::spawn(async move {
-                             if let Some(state) = h.try_state::<AppState>() {
-                                 // Logic to find next ID
-                                 let mut target_id = None;
-                                 {
-                                     let tabs = state.tabs.lock().unwrap();
-                                     let active = state.active_tab_id.lock().unwrap();
-                                     if let Some(act) = active.as_ref() {
-                                         if let Some(pos) = tabs.iter().position(|t| t.id == *act) {
-                                             let new_pos = if is_next {
-                                                 (pos + 1) % tabs.len()
-                                             } else {
-                                                 (pos + tabs.len() - 1) % tabs.len()
-                                             };
-                                             target_id = Some(tabs[new_pos].id.clone());
-                                         }
-                                     }
-                                 }
-
-                                 if let Some(tid) = target_id {
-                                     let _ = switch_tab_logic(&h, &state, tid);
-                                 }
-                             }
-                         });
-                    },
-
-                    // Focus Actions - Emit to Main Window
-                    "focus_location" | "focus_location_alt" => {
-                        if let Some(main_win) = handle_for_menu.get_window("main") {
-                             // Force window focus first
-                             let _ = main_win.set_focus();
-                             // Then emit event
-                             let _ = main_win.emit("focus-url-bar", ());
-                        }
-                    },
-                    "find_in_page" => {
-                        if let Some(find_win) = handle_for_menu.get_window("find") {
-                            if let Some(main_win) = handle_for_menu.get_window("main") {
-                                // Position find window at bottom-right of main window
-                                if let Ok(main_size) = main_win.inner_size() {
-                                    if let Ok(main_pos) = main_win.inner_position() {
-                                        if let Ok(scale) = main_win.scale_factor() {
-                                            // Calculate position in physical pixels
-                                            let find_width_physical = (400.0 * scale) as i32;
-                                            let find_height_physical = (50.0 * scale) as i32;
-                                            let margin_physical = (20.0 * scale) as i32;
-
-                                            // Position at bottom-right in physical coordinates
-                                            let x = main_pos.x + (main_size.width as i32) - find_width_physical - margin_physical;
-                                            let y = main_pos.y + (main_size.height as i32) - find_height_physical - margin_physical;
-
-                                            let _ = find_win.set_position(tauri::Position::Physical(tauri::PhysicalPosition::new(x, y)));
-                                        }
-                                    }
-                                }
-                                let _ = find_win.show();
-                                let _ = find_win.set_focus();
-                            }
-                        }
-                    },
-
-                    // Navigation Actions (Delegated to Active Tab)
-                    "reload" => {
-                         if let Some(state) = handle_for_menu.try_state::<AppState>() {
-                             let label = {
-                                 let tabs = state.tabs.lock().unwrap();
-                                 let active = state.active_tab_id.lock().unwrap();
-                                 active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-                             };
-                             if let Some(l) = label {
-                                 if let Some(wv) = handle_for_menu.get_webview(&l) {
-                                     let _ = wv.eval("window.location.reload()");
-                                 }
-                             }
-                        }
-                    },
-                    "hard_reload" => {
-                         if let Some(state) = handle_for_menu.try_state::<AppState>() {
-                             let label = {
-                                 let tabs = state.tabs.lock().unwrap();
-                                 let active = state.active_tab_id.lock().unwrap();
-                                 active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-                             };
-                             if let Some(l) = label {
-                                 if let Some(wv) = handle_for_menu.get_webview(&l) {
-                                     if let Ok(url) = wv.url() {
-                                        let js = format!("window.location.href = '{}'", url);
-                                        let _ = wv.eval(&js);
-                                     }
-                                 }
-                             }
-                        }
-                    },
-                    "go_back" => {
-                        if let Some(state) = handle_for_menu.try_state::<AppState>() {
-                             let label = {
-                                 let tabs = state.tabs.lock().unwrap();
-                                 let active = state.active_tab_id.lock().unwrap();
-                                 active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-                             };
-                             if let Some(l) = label {
-                                 if let Some(wv) = handle_for_menu.get_webview(&l) {
-                                     let _ = wv.eval("window.history.back()");
-                                 }
-                             }
-                        }
-                    },
-                    "go_forward" => {
-                        if let Some(state) = handle_for_menu.try_state::<AppState>() {
-                             let label = {
-                                 let tabs = state.tabs.lock().unwrap();
-                                 let active = state.active_tab_id.lock().unwrap();
-                                 active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-                             };
-                             if let Some(l) = label {
-                                 if let Some(wv) = handle_for_menu.get_webview(&l) {
-                                     let _ = wv.eval("window.history.forward()");
-                                 }
-                             }
-                        }
-                    },
-                    "reopen_closed_tab" => {
-                        if let Some(state) = handle_for_menu.try_state::<AppState>() {
-                            match restore_closed_tab(handle_for_menu.clone(), state) {
-                                Ok(tab_id) => println!("[Menu] Restored tab: {}", tab_id),
-                                Err(e) => eprintln!("[Menu] Failed to restore tab: {}", e),
-                            }
-                        }
-                    },
-
-                    "print" => {
-                        if let Some(state) = handle_for_menu.try_state::<AppState>() {
-                             let label = {
-                                 let tabs = state.tabs.lock().unwrap();
-                                 let active = state.active_tab_id.lock().unwrap();
-                                 active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
-                             };
-                             if let Some(l) = label {
-                                 if let Some(wv) = handle_for_menu.get_webview(&l) {
-                                     let _ = wv.eval("window.print()");
-                                 }
-                             }
-                        }
-                    },
-                    "open_devtools" => {
-                        let h = handle_for_menu.clone();
-                        tauri::async_runtime::spawn(async move {
-                            if let Some(state) = h.try_state::<AppState>() {
-                                open_devtools(h.clone(), state);
-                            }
-                        });
-                    },
-                    _ => {
-                        // Numeric Shortcuts (tab_1 .. tab_9)
-                        if id.starts_with("tab_") && id.len() == 5 {
-                            if let Ok(num) = id["tab_".len()..].parse::<usize>() {
-                                let index = num - 1; // 0-indexed
-                                let h = handle_for_menu.clone();
-                                tauri::async_runtime::spawn(async move {
-                                    if let Some(state) = h.try_state::<AppState>() {
-                                        let target_id_opt = {
-                                            let tabs = state.tabs.lock().unwrap();
-                                            if index < tabs.len() {
-                                                Some(tabs[index].id.clone())
-                                            } else {
-                                                None
-                                            }
-                                        };
-                                        if let Some(tid) = target_id_opt {
-                                            let _ = switch_tab_logic(&h, &state, tid);
-                                        }
-                                    }
-                                });
-                            }
-                        }
-                    }
-                }
+                run_browser_command(&handle_for_menu, event.id().0.as_str());
             });
 
             // --- Setup Content Webview ---
@@ -1898,53 +3014,50 @@ fn main() {
             let handle_for_startup = handle.clone();
             tauri::async_runtime::spawn(async move {
                 if let Some(state) = handle_for_startup.try_state::<AppState>() {
-                    // Create defaults to "Home" (about:blank or passed arg)
-                    // Currently hardcoded to Google for test, or about:blank
-                    let _ = create_tab_with_url(&handle_for_startup, &state, "https://duckduckgo.com".into());
+                    // Restore last session, or fall back to the default start page
+                    restore_session(&handle_for_startup, &state);
                 }
             });
 
             // Handle Window Resizing / Moving / Blur to hide dropdown
-            let main_window_clone = main_window.clone();
             let handle_clone = handle.clone();
+            let window_for_events = main_window.clone();
             main_window.on_window_event(move |event| {
                 match event {
-                    tauri::WindowEvent::Resized(new_physical_size) => {
-                         let scale = main_window_clone.scale_factor().unwrap_or(1.0);
-                         let toolbar_physical = (TOTAL_TOOLBAR_HEIGHT * scale) as u32;
-                         let content_h = new_physical_size.height.saturating_sub(toolbar_physical).max(100);
-                        
-                         // Resize Active Tab's Webview
+                    tauri::WindowEvent::Resized(_) => {
+                         // Resize Active Tab's Webview, or both panes of a split
                          if let Some(state) = handle_clone.try_state::<AppState>() {
-                             let active_label = {
-                                 // Lock scope
-                                 let tabs = state.tabs.lock().unwrap();
-                                 let active = state.active_tab_id.lock().unwrap();
-                                 active.as_ref().and_then(|id| {
-                                     tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone())
-                                 })
-                             };
-
-                             if let Some(label) = active_label {
-                                 if let Some(wv) = handle_clone.get_webview(&label) {
-                                     let _ = wv.set_bounds(tauri::Rect {
-                                        position: tauri::Position::Physical(PhysicalPosition::new(0, toolbar_physical as i32)),
-                                        size: tauri::Size::Physical(PhysicalSize::new(new_physical_size.width, content_h)),
-                                    });
-                                 }
+                             // Maximizing drops the resize border above the tab strip
+                             if titlebar::track_maximized(&window_for_events, &state) {
+                                 emit_tabs_update(&handle_clone, &state);
+                             }
+                             if let Some(metrics) = WindowMetrics::of(&window_for_events) {
+                                 layout_visible_tabs(&handle_clone, &state, metrics);
                              }
                          }
 
                          // Hide dropdown on resize
-                         if let Some(dd) = handle_clone.get_window("dropdown") {
-                             let _ = dd.hide();
+                         let _ = dropdown::hide(&handle_clone);
+                     }
+                    tauri::WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size, .. } => {
+                         // Dragged onto a monitor with another DPI: the window only reports the
+                         // new scale and size afterwards, so lay out from the event's
+                         if let Some(state) = handle_clone.try_state::<AppState>() {
+                             let metrics = WindowMetrics { size: *new_inner_size, scale: *scale_factor };
+                             layout_visible_tabs(&handle_clone, &state, metrics);
                          }
+                         let _ = dropdown::hide(&handle_clone);
                      }
                     tauri::WindowEvent::Moved(_) => {
                          // Hide dropdown on move (removed Focused(false) check to prevent auto-hide on dropdown show)
-                         if let Some(dd) = handle_clone.get_window("dropdown") {
-                             let _ = dd.hide();
-                         }
+                         let _ = dropdown::hide(&handle_clone);
+                    }
+                    tauri::WindowEvent::Focused(true) => {
+                        check_clipboard_for_url(&handle_clone);
+                        // Back from a private window: offer the active page again
+                        if let Some(state) = handle_clone.try_state::<AppState>() {
+                            handoff::update(&handle_clone, &state);
+                        }
                     }
                     tauri::WindowEvent::CloseRequested { .. } => {
                         // Save closed tabs to disk before closing
@@ -1954,7 +3067,16 @@ fn main() {
                                 tabs: closed.clone(),
                             };
                             let _ = store.save(&handle_clone);
+                            drop(closed);
+
+                            // Save open tabs (incl. custom titles and markers) for the next launch
+                            session_store::save_session(&handle_clone, &state);
+                            tab_windows::close_all(&handle_clone, &state);
+
+                            // Runs last so it also wipes the closed tabs saved above
+                            browsing_data::clear_on_exit(&handle_clone, &state);
                         }
+                        handoff::clear(&handle_clone);
                     }
                     _ => {}
                 }
@@ -1968,21 +3090,68 @@ fn main() {
             close_tab,
             get_tabs,
             restore_closed_tab,
+            restore_closed_tab_by_id,
+            detach_tab,
+            attach_tab,
+            tab_windows::tab_window_navigate,
+            form_audit::audit_form_submission,
+            form_audit::respond_form_audit,
+            stash_tabs,
+            restore_stash,
+            stash::get_stashes,
+            stash::delete_stash,
+            open_link,
             tabs::reorder_tabs,
+            tabs::rename_tab,
+            tabs::set_tab_marker,
+            drag_out::start_url_drag,
+            clipboard::copy_with_expiry,
+            proxy::test_proxy,
+            get_window_materials,
+            get_toolbar_layout,
+            set_toolbar_layout,
+            get_cookie_exceptions,
+            add_cookie_exception,
+            remove_cookie_exception,
+            get_fingerprint_exceptions,
+            format_url_for_display,
+            site_storage::get_tab_storage_summary,
+            user_agent::get_tab_user_agent,
+            data_saver::data_saver_load_image,
+            set_site_data_saver,
+            set_site_block_images,
+            site_storage::report_tab_storage,
+            site_storage::delete_tab_storage_item,
+            site_data::get_site_data_usage,
+            site_data::purge_site_data,
+            set_site_fingerprint_protection,
+            set_site_spoofing_profile,
+            set_site_region,
             toggle_window_maximize,
             navigate, 
             go_back, 
             go_forward,
             save_suggestion,
+            list_commands,
+            execute_command,
             get_suggestions,
             get_current_url,
             hard_reload,
             clear_site_data,
+            browsing_data::clear_browsing_data,
             copy_current_url,
             focus_toolbar,
             focus_content,
+            request_focus,
+            focus_changed,
             spa_navigate,
             search_history,
+            delete_suggestion,
+            get_history_page,
+            get_history_activity,
+            delete_history_entry,
+            delete_history_range,
+            clear_history,
             update_dropdown,
             navigate_from_dropdown,
             set_dropdown_bounds,
@@ -1994,10 +3163,143 @@ fn main() {
             // Settings Commands
             get_settings,
             save_settings,
+            add_search_engine,
+            remove_search_engine,
             // Ad Blocking Commands
             get_cosmetic_rules,
             set_site_exception,
             get_exceptions,
+            export_allowlist,
+            import_allowlist,
+            export_user_data,
+            import_user_data,
+            set_exception_note,
+            get_adblock_dashboard,
+            get_adblock_site_mode,
+            set_adblock_site_mode,
+            set_adblock_enabled,
+            update_filter_lists,
+            list_filter_lists,
+            regional_lists::get_regional_lists,
+            regional_lists::set_regional_list,
+            get_filter_list_health,
+            element_picker::start_element_picker,
+            element_picker::add_user_cosmetic_rule,
+            element_picker::list_user_cosmetic_rules,
+            element_picker::delete_user_cosmetic_rule,
+            channel_blocking::check_websocket,
+            channel_blocking::check_service_worker,
+            block_stats::report_page_resources,
+            block_stats::get_block_stats,
+            block_stats::get_lifetime_block_stats,
+            add_filter_list,
+            remove_filter_list,
+            set_filter_list_enabled,
+            continue_insecure,
+            safebrowsing::proceed_to_unsafe_site,
+            audio_output::get_audio_outputs,
+            audio_output::set_tab_audio_output,
+            media_controls::get_media_controls,
+            set_media_playback_rate,
+            media_controls::skip_media,
+            media_controls::set_media_loop,
+            network_log::report_resource_timings,
+            network_log::get_network_log,
+            network_log::export_har,
+            screen_capture::request_display_capture,
+            screen_capture::get_capture_request,
+            screen_capture::respond_display_capture,
+            console_log::report_console_messages,
+            console_log::get_console_log,
+            webauthn::get_webauthn_support,
+            webauthn::report_webauthn_unavailable,
+            task_manager::get_tab_resource_usage,
+            video_popout::pop_out_video,
+            video_popout::open_video_popout,
+            video_popout::update_video_popout,
+            read_aloud::read_aloud,
+            read_aloud::speak_article,
+            read_aloud::pause_read_aloud,
+            read_aloud::resume_read_aloud,
+            read_aloud::stop_read_aloud,
+            read_aloud::set_read_aloud_speed,
+            tab_audio::report_tab_audio,
+            tab_audio::mute_tab,
+            tab_audio::unmute_tab,
+            text_replace::get_text_replacements,
+            text_replace::list_replacement_rules,
+            text_replace::add_replacement_rule,
+            text_replace::delete_replacement_rule,
+            text_replace::set_replacement_rule_enabled,
+            text_replace::export_replacement_pack,
+            text_replace::import_replacement_pack,
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            snippets::run_snippet,
+            tab_search::search_open_tabs,
+            email_alias::generate_email_alias,
+            email_alias::list_email_aliases,
+            email_alias::forget_email_alias,
+            email_alias::get_alias_provider,
+            email_alias::set_alias_provider,
+            enter_split_view,
+            exit_split_view,
+            split_view::set_split_ratio,
+            split_view::get_split_view,
+            totp::otp_field_detected,
+            totp::fill_totp,
+            totp::list_totp_accounts,
+            totp::add_totp_account,
+            totp::remove_totp_account,
+            breach_check::run_breach_check,
+            breach_check::get_breach_report,
+            breach_check::clear_breach_report,
+            breach_check::check_password_breach,
+            top_sites::get_top_sites,
+            top_sites::pin_top_site,
+            top_sites::unpin_top_site,
+            top_sites::hide_top_site,
+            top_sites::restore_hidden_top_sites,
+            annotations::get_page_annotations,
+            annotations::add_annotation,
+            annotations::update_annotation_note,
+            annotations::remove_annotation,
+            annotations::list_annotations,
+            annotations::export_annotations,
+            site_apps::install_site_as_app,
+            site_apps::list_site_apps,
+            site_apps::open_site_app,
+            site_apps::uninstall_site_app,
+            tracking_params::report_stripped_params,
+            privacy_report::get_privacy_report,
+            privacy_report::list_privacy_report_weeks,
+            maintenance::get_maintenance_status,
+            maintenance::run_maintenance,
+            external_protocols::get_external_protocols,
+            external_protocols::set_external_protocol,
+            headless::is_headless,
+            titlebar::minimize_window,
+            titlebar::close_window,
+            titlebar::show_snap_layouts,
+            create_container_tab,
+            containers::get_containers,
+            containers::create_container,
+            containers::delete_container,
+            containers::set_container_rule,
+            containers::remove_container_rule,
+            offline::get_offline,
+            offline::set_offline,
+            context_menu::show_context_menu,
+            site_search::site_search_form,
+            site_search::site_search_opensearch,
+            site_search::find_site_search,
+            link_hover::link_hovered,
+            bookmarks::get_bookmarks,
+            bookmarks::delete_bookmark,
+            importer::list_import_sources,
+            importer::preview_import,
+            importer::import_browser_data,
             open_devtools,
             // Find in Page Commands
             find_in_webview,
@@ -2005,8 +3307,24 @@ fn main() {
             report_find_result,
             hide_find_window
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| match event {
+            // Quitting from the menus doesn't close the main window first; webviews are still up here
+            tauri::RunEvent::ExitRequested { .. } => {
+                if let Some(state) = app.try_state::<AppState>() {
+                    browsing_data::clear_on_exit(app, &state);
+                }
+            }
+            tauri::RunEvent::Exit => {
+                if let Some(state) = app.try_state::<AppState>() {
+                    if let Err(e) = state.block_stats.flush() {
+                        eprintln!("[BlockStats] Failed to save: {}", e);
+                    }
+                }
+            }
+            _ => {}
+        });
 }
 
 #[cfg(test)]
@@ -2062,120 +3380,16 @@ mod tests {
 }
 
 #[tauri::command]
-fn content_pointer_down(app: AppHandle) {
+fn content_pointer_down(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
     // 1. Hide dropdown
-    if let Some(win) = app.get_window("dropdown") {
-        let _ = win.hide();
-    }
+    dropdown::hide(&app)?;
     // 2. Notify toolbar (so it can blur input or reset state)
     // We emit to the main window (toolbar)
     if let Some(main) = app.get_window("main") {
-        let _ = main.emit("content-focused", ());
-    }
-}
-
-// --- Platform-Specific Gesture Helpers ---
-
-#[cfg(target_os = "macos")]
-fn enable_back_forward_gestures(webview: &tauri::Webview) {
-    use objc::{msg_send, sel, sel_impl};
-    use objc::runtime::YES;
-
-    unsafe {
-        let _ = webview.with_webview(|platform_webview| {
-            let wk_webview = platform_webview.inner() as *mut objc::runtime::Object;
-            let _: () = msg_send![wk_webview, setAllowsBackForwardNavigationGestures: YES];
-        });
-    }
-}
-
-#[cfg(not(target_os = "macos"))]
-fn enable_back_forward_gestures(_webview: &tauri::Webview) {
-    // No-op for Windows/Linux
-}
-
-/// Apply Safari-compatible content blocking rules to a WKWebView.
-/// This blocks network requests at the WebKit level, not just hides elements.
-#[cfg(target_os = "macos")]
-fn apply_content_blocking_rules(webview: &tauri::Webview, rules_json: &str) {
-    use objc::{msg_send, sel, sel_impl, class};
-    use objc::runtime::Object;
-    use block::ConcreteBlock;
-    use std::ffi::CString;
-    
-    // Convert Rust string to NSString
-    fn to_nsstring(s: &str) -> *mut Object {
-        unsafe {
-            let ns_string_class = class!(NSString);
-            let string_c = CString::new(s).unwrap_or_else(|_| CString::new("").unwrap());
-            let ns_string: *mut Object = msg_send![ns_string_class, alloc];
-            let ns_string: *mut Object = msg_send![ns_string, initWithUTF8String: string_c.as_ptr()];
-            ns_string
-        }
-    }
-    
-    let rules = rules_json.to_string();
-    
-    unsafe {
-        let webview_result = webview.with_webview(move |platform_webview| {
-            let wk_webview = platform_webview.inner() as *mut Object;
-            
-            // Get WKContentRuleListStore.defaultStore
-            let store_class = class!(WKContentRuleListStore);
-            let store: *mut Object = msg_send![store_class, defaultStore];
-            
-            if store.is_null() {
-                println!("[AdBlock] WKContentRuleListStore.defaultStore is null");
-                return;
-            }
-            
-            // Get the WKUserContentController from the webview's configuration
-            let config: *mut Object = msg_send![wk_webview, configuration];
-            let user_content_controller: *mut Object = msg_send![config, userContentController];
-            
-            // Create rule identifier and rules NSString
-            let identifier = to_nsstring("SovereignBrowserAdBlock");
-            let rules_ns = to_nsstring(&rules);
-            
-            // Store the user content controller pointer for the completion block
-            let ucc = user_content_controller;
-            
-            // Create completion block for compileContentRuleListForIdentifier:encodedContentRuleList:completionHandler:
-            let completion_block = ConcreteBlock::new(move |rule_list: *mut Object, error: *mut Object| {
-                if error.is_null() && !rule_list.is_null() {
-                    println!("[AdBlock] Content rule list compiled successfully!");
-                    // Add the compiled rule list to the user content controller
-                    let _: () = msg_send![ucc, addContentRuleList: rule_list];
-                    println!("[AdBlock] Content blocking rules applied to webview!");
-                } else {
-                    if !error.is_null() {
-                        let description: *mut Object = msg_send![error, localizedDescription];
-                        let utf8: *const std::os::raw::c_char = msg_send![description, UTF8String];
-                        if !utf8.is_null() {
-                            let error_str = std::ffi::CStr::from_ptr(utf8).to_string_lossy();
-                            println!("[AdBlock] Failed to compile content rules: {}", error_str);
-                        }
-                    } else {
-                        println!("[AdBlock] Failed to compile content rules: unknown error");
-                    }
-                }
-            });
-            let completion_block = completion_block.copy();
-            
-            // Call compileContentRuleListForIdentifier:encodedContentRuleList:completionHandler:
-            println!("[AdBlock] Compiling content blocking rules ({} chars)...", rules.len());
-            let _: () = msg_send![store, compileContentRuleListForIdentifier:identifier 
-                                        encodedContentRuleList:rules_ns 
-                                        completionHandler:&*completion_block];
-        });
-        
-        if let Err(e) = webview_result {
-            println!("[AdBlock] Failed to access webview: {:?}", e);
-        }
+        main.emit("content-focused", ())?;
     }
+    // 3. The page has focus now; remember it for tab switches and overlay restores
+    let active_id = state.active_tab_id.lock().unwrap().clone();
+    state.focus.lock().observe(FocusTarget::Content, active_id.as_deref());
+    Ok(())
 }
-
-#[cfg(not(target_os = "macos"))]
-fn apply_content_blocking_rules(_webview: &tauri::Webview, _rules_json: &str) {
-    // No-op for Windows/Linux - they may use different mechanisms
-}
\ No newline at end of file
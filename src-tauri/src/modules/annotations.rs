@@ -0,0 +1,555 @@
+// Highlights and notes on web pages.
+//
+// "Highlight Selection" asks the page's `HIGHLIGHT_SCRIPT` to turn the current
+// selection into a highlight. The script describes it as a text quote plus a
+// little of the text before and after it (like the W3C annotation model's
+// TextQuoteSelector), and `add_annotation` stores that under the tab's real URL,
+// without the fragment. On every load the script asks for the page's
+// annotations and marks the quotes it can still find; ones whose text has gone
+// stay in the list. Clicking a highlight edits its note or removes it.
+//
+// Pages only ever see and change the annotations for their own URL. Everything
+// is kept in annotations.json; "Export Annotations" writes them as Markdown.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, is_ui_webview, Caller};
+use crate::state::AppState;
+
+const ANNOTATIONS_FILE: &str = "annotations.json";
+const MAX_QUOTE_LEN: usize = 4096;
+const MAX_CONTEXT_LEN: usize = 64;
+
+/// Marks stored quotes on load and turns selections into highlights.
+pub const HIGHLIGHT_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_ANNOTATE__ || !window.__TAURI__ || window.top !== window) return;
+    if (!['http:', 'https:', 'file:'].includes(location.protocol)) return;
+    const invoke = window.__TAURI__.core.invoke;
+    const CONTEXT = 32;
+    const MARK = '__sovereign-highlight';
+    const pending = new Map(); // id -> annotation not yet found on the page
+    let editor = null;
+
+    function pageText() {
+        const nodes = [];
+        let text = '';
+        const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT, {
+            acceptNode: n => n.parentElement && n.parentElement.closest('script, style, noscript, textarea')
+                ? NodeFilter.FILTER_REJECT : NodeFilter.FILTER_ACCEPT
+        });
+        while (walker.nextNode()) {
+            nodes.push({ node: walker.currentNode, start: text.length });
+            text += walker.currentNode.data;
+        }
+        return { text, nodes };
+    }
+
+    // The occurrence of the quote whose surroundings match best
+    function locate(text, a) {
+        let best = -1, bestScore = -1;
+        for (let i = text.indexOf(a.quote); i !== -1; i = text.indexOf(a.quote, i + 1)) {
+            const end = i + a.quote.length;
+            const score = (text.slice(Math.max(0, i - a.prefix.length), i) === a.prefix ? 1 : 0)
+                + (text.slice(end, end + a.suffix.length) === a.suffix ? 1 : 0);
+            if (score > bestScore) {
+                best = i;
+                bestScore = score;
+                if (score === 2) break;
+            }
+        }
+        return best;
+    }
+
+    function wrap(nodes, start, end, a) {
+        for (const { node, start: s } of nodes) {
+            const e = s + node.data.length;
+            if (e <= start || s >= end) continue;
+            let target = node;
+            const from = Math.max(start - s, 0), to = Math.min(end - s, node.data.length);
+            if (to < target.data.length) target.splitText(to);
+            if (from > 0) target = target.splitText(from);
+            const mark = document.createElement('mark');
+            mark.className = MARK;
+            mark.dataset.annotationId = a.id;
+            mark.title = a.note || '';
+            mark.style.cssText = 'background: #ffe066; color: inherit; cursor: pointer;';
+            target.parentNode.insertBefore(mark, target);
+            mark.appendChild(target);
+        }
+    }
+
+    function apply() {
+        for (const a of [...pending.values()]) {
+            const { text, nodes } = pageText();
+            const start = locate(text, a);
+            if (start === -1) continue;
+            wrap(nodes, start, start + a.quote.length, a);
+            pending.delete(a.id);
+        }
+    }
+
+    function marksFor(id) {
+        return [...document.querySelectorAll('mark.' + MARK)].filter(m => m.dataset.annotationId === id);
+    }
+
+    function unwrap(id) {
+        for (const mark of marksFor(id)) {
+            const parent = mark.parentNode;
+            while (mark.firstChild) parent.insertBefore(mark.firstChild, mark);
+            parent.removeChild(mark);
+            parent.normalize();
+        }
+    }
+
+    // Selection boundaries as offsets into the page text, when they're in text nodes
+    function offsetOf(nodes, container, offset) {
+        const entry = nodes.find(n => n.node === container);
+        return entry ? entry.start + offset : -1;
+    }
+
+    async function highlightSelection() {
+        const sel = window.getSelection();
+        if (!sel || sel.isCollapsed || !sel.toString().trim()) return;
+        const range = sel.getRangeAt(0);
+        const { text, nodes } = pageText();
+        let start = offsetOf(nodes, range.startContainer, range.startOffset);
+        let end = offsetOf(nodes, range.endContainer, range.endOffset);
+        if (start === -1 || end <= start) {
+            start = text.indexOf(sel.toString());
+            end = start + sel.toString().length;
+            if (start === -1) return;
+        }
+        const quote = text.slice(start, end);
+        const prefix = text.slice(Math.max(0, start - CONTEXT), start);
+        const suffix = text.slice(end, end + CONTEXT);
+        try {
+            const a = await invoke('add_annotation', { quote, prefix, suffix, note: '' });
+            sel.removeAllRanges();
+            wrap(nodes, start, end, a);
+        } catch (e) {
+            console.warn('[Sovereign] Could not save highlight:', e);
+        }
+    }
+
+    function closeEditor() {
+        if (editor) editor.remove();
+        editor = null;
+    }
+
+    function openEditor(mark) {
+        closeEditor();
+        const id = mark.dataset.annotationId;
+        const rect = mark.getBoundingClientRect();
+        editor = document.createElement('div');
+        editor.style.cssText = `position: fixed; z-index: 2147483647; left: ${Math.max(8, rect.left)}px; top: ${rect.bottom + 6}px;`;
+        const root = editor.attachShadow({ mode: 'closed' });
+        root.innerHTML = `
+            <style>
+                .box { width: 260px; padding: 10px; border-radius: 8px; background: #1e1e2e; color: #e0e0e0;
+                       font: 13px -apple-system, BlinkMacSystemFont, 'Segoe UI', sans-serif; box-shadow: 0 4px 16px rgba(0,0,0,.35); }
+                textarea { width: 100%; height: 70px; box-sizing: border-box; background: #2a2a3e; color: inherit;
+                           border: 1px solid #3a3a5a; border-radius: 6px; padding: 6px; font: inherit; resize: vertical; }
+                .row { display: flex; justify-content: flex-end; gap: 6px; margin-top: 8px; }
+                button { border: 1px solid #3a3a5a; background: transparent; color: inherit; border-radius: 6px; padding: 4px 10px; cursor: pointer; }
+                button.save { background: #0a84ff; border-color: #0a84ff; color: #fff; }
+            </style>
+            <div class="box">
+                <textarea placeholder="Add a note"></textarea>
+                <div class="row"><button class="remove">Remove</button><button class="save">Save</button></div>
+            </div>`;
+        const textarea = root.querySelector('textarea');
+        textarea.value = mark.title;
+        root.querySelector('.save').addEventListener('click', async () => {
+            const note = textarea.value.trim();
+            try {
+                await invoke('update_annotation_note', { id, note });
+                marksFor(id).forEach(m => { m.title = note; });
+            } catch (e) {
+                console.warn('[Sovereign] Could not save note:', e);
+            }
+            closeEditor();
+        });
+        root.querySelector('.remove').addEventListener('click', async () => {
+            try {
+                await invoke('remove_annotation', { id });
+                unwrap(id);
+            } catch (e) {
+                console.warn('[Sovereign] Could not remove highlight:', e);
+            }
+            closeEditor();
+        });
+        document.documentElement.appendChild(editor);
+        textarea.focus();
+    }
+
+    document.addEventListener('click', (e) => {
+        if (editor && e.composedPath().includes(editor)) return;
+        const mark = e.target instanceof Element && e.target.closest('mark.' + MARK);
+        if (mark) {
+            e.preventDefault();
+            openEditor(mark);
+        } else {
+            closeEditor();
+        }
+    }, true);
+
+    async function load() {
+        try {
+            const annotations = await invoke('get_page_annotations');
+            for (const a of annotations) pending.set(a.id, a);
+            apply();
+            // Late content (lazy sections, client rendering) gets one more try
+            if (pending.size) setTimeout(apply, 2000);
+        } catch (e) {}
+    }
+
+    window.__SOVEREIGN_ANNOTATE__ = { highlightSelection };
+    if (document.readyState === 'loading') {
+        document.addEventListener('DOMContentLoaded', load, { once: true });
+    } else {
+        load();
+    }
+})();
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub id: String,
+    pub url: String,
+    pub title: String, // The page's title when it was made
+    pub quote: String,
+    pub prefix: String, // Text just before and after the quote, to find the right occurrence
+    pub suffix: String,
+    pub note: String,
+    pub created_at: u64,
+}
+
+/// The URL annotations are kept under: no fragment, web and file pages only.
+pub fn page_key(url: &Url) -> Option<String> {
+    if !matches!(url.scheme(), "http" | "https" | "file") {
+        return None;
+    }
+    let mut url = url.clone();
+    url.set_fragment(None);
+    Some(url.to_string())
+}
+
+fn truncate(s: &str, max: usize, from_end: bool) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() <= max {
+        return s.to_string();
+    }
+    let kept = if from_end { &chars[chars.len() - max..] } else { &chars[..max] };
+    kept.iter().collect()
+}
+
+/// Annotations grouped by page, oldest page first, as Markdown.
+pub fn to_markdown(annotations: &[Annotation]) -> String {
+    let mut pages: Vec<(&str, Vec<&Annotation>)> = Vec::new();
+    for a in annotations {
+        match pages.iter_mut().find(|(url, _)| *url == a.url) {
+            Some((_, list)) => list.push(a),
+            None => pages.push((&a.url, vec![a])),
+        }
+    }
+
+    let mut out = String::from("# Annotations\n");
+    for (url, list) in pages {
+        let title = list.iter().map(|a| a.title.as_str()).find(|t| !t.is_empty()).unwrap_or(url);
+        out.push_str(&format!("\n## {}\n\n<{}>\n", title, url));
+        for a in list {
+            out.push('\n');
+            for line in a.quote.lines().filter(|l| !l.trim().is_empty()) {
+                out.push_str(&format!("> {}\n", line.trim()));
+            }
+            if !a.note.is_empty() {
+                out.push_str(&format!("\n{}\n", a.note));
+            }
+        }
+    }
+    out
+}
+
+pub struct AnnotationStore {
+    path: PathBuf,
+    annotations: Mutex<Vec<Annotation>>,
+}
+
+impl AnnotationStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(ANNOTATIONS_FILE);
+        let annotations =
+            fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, annotations: Mutex::new(annotations) }
+    }
+
+    fn save(&self, annotations: &[Annotation]) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(annotations).unwrap_or_default()) {
+            eprintln!("[Annotations] Failed to save: {}", e);
+        }
+    }
+
+    pub fn add(&self, url: &str, title: &str, quote: &str, prefix: &str, suffix: &str, note: &str) -> Annotation {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let annotation = Annotation {
+            id: format!("{:x}", now.as_nanos()),
+            url: url.to_string(),
+            title: title.to_string(),
+            quote: truncate(quote, MAX_QUOTE_LEN, false),
+            prefix: truncate(prefix, MAX_CONTEXT_LEN, true),
+            suffix: truncate(suffix, MAX_CONTEXT_LEN, false),
+            note: note.to_string(),
+            created_at: now.as_secs(),
+        };
+        let mut annotations = self.annotations.lock().unwrap();
+        annotations.push(annotation.clone());
+        self.save(&annotations);
+        annotation
+    }
+
+    pub fn get(&self, id: &str) -> Option<Annotation> {
+        self.annotations.lock().unwrap().iter().find(|a| a.id == id).cloned()
+    }
+
+    pub fn for_url(&self, url: &str) -> Vec<Annotation> {
+        self.annotations.lock().unwrap().iter().filter(|a| a.url == url).cloned().collect()
+    }
+
+    pub fn all(&self) -> Vec<Annotation> {
+        self.annotations.lock().unwrap().clone()
+    }
+
+    pub fn set_note(&self, id: &str, note: &str) -> Result<(), String> {
+        let mut annotations = self.annotations.lock().unwrap();
+        let annotation = annotations.iter_mut().find(|a| a.id == id).ok_or("No such annotation")?;
+        annotation.note = note.to_string();
+        self.save(&annotations);
+        Ok(())
+    }
+
+    pub fn remove(&self, id: &str) -> bool {
+        let mut annotations = self.annotations.lock().unwrap();
+        let before = annotations.len();
+        annotations.retain(|a| a.id != id);
+        let removed = annotations.len() != before;
+        if removed {
+            self.save(&annotations);
+        }
+        removed
+    }
+
+    pub fn write_markdown(&self, path: PathBuf) -> Result<usize, String> {
+        let annotations = self.all();
+        fs::write(&path, to_markdown(&annotations)).map_err(|e| e.to_string())?;
+        println!("[Annotations] Exported {} to {}", annotations.len(), path.display());
+        Ok(annotations.len())
+    }
+}
+
+/// The annotation key for the page a tab webview is really showing.
+fn webview_page(webview: &tauri::Webview) -> Option<String> {
+    page_key(&webview.url().ok()?)
+}
+
+/// Pages may only change annotations on their own page; browser UI may change any.
+fn check_access(webview: &tauri::Webview, annotation: &Annotation) -> Result<(), BrowserError> {
+    if !is_ui_webview(webview.label()) && webview_page(webview).as_deref() != Some(annotation.url.as_str()) {
+        return Err(BrowserError::NotAllowed("Not allowed from this page".to_string()));
+    }
+    Ok(())
+}
+
+/// Palette: turn the active tab's selection into a highlight.
+pub fn highlight_selection(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let active_id = state.active_tab_id.lock().unwrap().clone();
+    let label =
+        state.tabs.lock().unwrap().iter().find(|t| Some(&t.id) == active_id.as_ref()).map(|t| t.webview_label.clone());
+    if let Some(webview) = label.and_then(|l| app.get_webview(&l)) {
+        let _ = webview.eval("window.__SOVEREIGN_ANNOTATE__ && window.__SOVEREIGN_ANNOTATE__.highlightSelection()");
+    }
+}
+
+/// Palette: save every annotation as a Markdown file.
+pub fn export(app: &AppHandle) {
+    use tauri_plugin_dialog::DialogExt;
+
+    let h = app.clone();
+    app.dialog().file().add_filter("Markdown", &["md"]).set_file_name("annotations.md").save_file(move |path| {
+        let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+            return;
+        };
+        if let Some(state) = h.try_state::<AppState>() {
+            if let Err(e) = state.annotations.write_markdown(path) {
+                eprintln!("[Annotations] Failed to export: {}", e);
+            }
+        }
+    });
+}
+
+/// From `HIGHLIGHT_SCRIPT`: the annotations for the page it's running in.
+#[tauri::command]
+pub fn get_page_annotations(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<Annotation>, BrowserError> {
+    Ok(webview_page(&webview).map(|url| state.annotations.for_url(&url)).unwrap_or_default())
+}
+
+/// From `HIGHLIGHT_SCRIPT`: a new highlight on the page it's running in.
+#[tauri::command]
+pub fn add_annotation(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    quote: String,
+    prefix: String,
+    suffix: String,
+    note: String,
+) -> Result<Annotation, BrowserError> {
+    if quote.trim().is_empty() {
+        return Err(BrowserError::InvalidInput("Nothing selected".to_string()));
+    }
+    let url = webview_page(&webview)
+        .ok_or_else(|| BrowserError::Unsupported("Highlights aren't available on this page".to_string()))?;
+    let title = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.webview_label == webview.label())
+        .map(|t| t.title.clone())
+        .unwrap_or_default();
+    Ok(state.annotations.add(&url, &title, &quote, &prefix, &suffix, &note))
+}
+
+#[tauri::command]
+pub fn update_annotation_note(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: String,
+    note: String,
+) -> Result<(), BrowserError> {
+    let annotation =
+        state.annotations.get(&id).ok_or_else(|| BrowserError::NotFound("No such annotation".to_string()))?;
+    check_access(&webview, &annotation)?;
+    state.annotations.set_note(&id, &note).map_err(BrowserError::NotFound)
+}
+
+#[tauri::command]
+pub fn remove_annotation(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: String,
+) -> Result<bool, BrowserError> {
+    let Some(annotation) = state.annotations.get(&id) else {
+        return Ok(false);
+    };
+    check_access(&webview, &annotation)?;
+    Ok(state.annotations.remove(&id))
+}
+
+/// Every annotation, or those for one page, for the settings window. Not for pages.
+#[tauri::command]
+pub fn list_annotations(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: Option<String>,
+) -> Result<Vec<Annotation>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(match url {
+        Some(url) => Url::parse(&url)
+            .ok()
+            .and_then(|u| page_key(&u))
+            .map(|key| state.annotations.for_url(&key))
+            .unwrap_or_default(),
+        None => state.annotations.all(),
+    })
+}
+
+/// Writes every annotation as Markdown to `path`. Returns how many.
+#[tauri::command]
+pub fn export_annotations(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    path: String,
+) -> Result<usize, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let path = Path::new(&path).to_path_buf();
+    state.annotations.write_markdown(path).map_err(BrowserError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_key_drops_fragment() {
+        let url = Url::parse("https://example.com/post?id=1#comments").unwrap();
+        assert_eq!(page_key(&url).as_deref(), Some("https://example.com/post?id=1"));
+        assert_eq!(page_key(&Url::parse("about:blank").unwrap()), None);
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = AnnotationStore::new(dir.path().to_path_buf());
+        let a = store.add("https://a.example/", "A", "quoted text", "before ", " after", "");
+        store.add("https://b.example/", "B", "other", "", "", "note");
+        store.set_note(&a.id, "why this matters").unwrap();
+        assert!(store.set_note("missing", "x").is_err());
+
+        let reopened = AnnotationStore::new(dir.path().to_path_buf());
+        let on_a = reopened.for_url("https://a.example/");
+        assert_eq!(on_a.len(), 1);
+        assert_eq!(on_a[0].note, "why this matters");
+        assert_eq!(on_a[0].prefix, "before ");
+        assert!(reopened.remove(&a.id));
+        assert!(!reopened.remove(&a.id));
+        assert_eq!(reopened.all().len(), 1);
+    }
+
+    #[test]
+    fn test_context_is_trimmed_next_to_the_quote() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = AnnotationStore::new(dir.path().to_path_buf());
+        let long = "x".repeat(MAX_CONTEXT_LEN) + "near";
+        let a = store.add("https://a.example/", "", "quote", &long, &format!("near{}", long), "");
+        assert!(a.prefix.ends_with("near") && a.prefix.chars().count() == MAX_CONTEXT_LEN);
+        assert!(a.suffix.starts_with("near") && a.suffix.chars().count() == MAX_CONTEXT_LEN);
+    }
+
+    #[test]
+    fn test_markdown_groups_by_page() {
+        let annotation = |url: &str, title: &str, quote: &str, note: &str| Annotation {
+            id: quote.to_string(),
+            url: url.to_string(),
+            title: title.to_string(),
+            quote: quote.to_string(),
+            prefix: String::new(),
+            suffix: String::new(),
+            note: note.to_string(),
+            created_at: 0,
+        };
+        let markdown = to_markdown(&[
+            annotation("https://a.example/", "Page A", "first", "a note"),
+            annotation("https://b.example/", "", "second", ""),
+            annotation("https://a.example/", "Page A", "two\nlines", ""),
+        ]);
+        assert_eq!(
+            markdown,
+            "# Annotations\n\n## Page A\n\n<https://a.example/>\n\n> first\n\na note\n\n> two\n> lines\n\n\
+             ## https://b.example/\n\n<https://b.example/>\n\n> second\n"
+        );
+    }
+}
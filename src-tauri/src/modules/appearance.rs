@@ -0,0 +1,118 @@
+// Window appearance - vibrancy/material and titlebar tint for the main window.
+//
+// The title bar stays in Overlay style (the tab strip draws into it), so the tint
+// is applied to the window background and to the toolbar UI via `settings-update`.
+// Materials only show through where the toolbar page is translucent; the UI
+// switches to translucent chrome when a material is active.
+
+use serde::{Deserialize, Serialize};
+use tauri::window::{Color, Effect, EffectState, EffectsBuilder};
+use tauri::Window;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowMaterial {
+    #[default]
+    None,
+    // macOS NSVisualEffectView materials
+    Sidebar,
+    Titlebar,
+    UnderWindow,
+    HudWindow,
+    // Windows 11 backdrops
+    Mica,
+    Tabbed,
+    Acrylic,
+}
+
+impl WindowMaterial {
+    /// The platform effect for this material, or None if it isn't available here.
+    pub fn effect(&self) -> Option<Effect> {
+        match self {
+            Self::None => None,
+            Self::Sidebar if cfg!(target_os = "macos") => Some(Effect::Sidebar),
+            Self::Titlebar if cfg!(target_os = "macos") => Some(Effect::Titlebar),
+            Self::UnderWindow if cfg!(target_os = "macos") => Some(Effect::UnderWindowBackground),
+            Self::HudWindow if cfg!(target_os = "macos") => Some(Effect::HudWindow),
+            Self::Mica if cfg!(target_os = "windows") => Some(Effect::Mica),
+            Self::Tabbed if cfg!(target_os = "windows") => Some(Effect::Tabbed),
+            Self::Acrylic if cfg!(target_os = "windows") => Some(Effect::Acrylic),
+            _ => None,
+        }
+    }
+
+    /// Materials offered in Settings on this platform.
+    pub fn available() -> Vec<WindowMaterial> {
+        [
+            Self::None, Self::Sidebar, Self::Titlebar, Self::UnderWindow, Self::HudWindow,
+            Self::Mica, Self::Tabbed, Self::Acrylic,
+        ]
+        .into_iter()
+        .filter(|m| *m == Self::None || m.effect().is_some())
+        .collect()
+    }
+}
+
+/// Parses "#rgb" or "#rrggbb" into an opaque color.
+pub fn parse_tint(hex: &str) -> Option<Color> {
+    let digits = hex.trim().strip_prefix('#')?;
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let expanded: String = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 => digits.to_string(),
+        _ => return None,
+    };
+    let channel = |i: usize| u8::from_str_radix(&expanded[i..i + 2], 16).ok();
+    Some(Color(channel(0)?, channel(2)?, channel(4)?, 255))
+}
+
+/// Applies material and tint to a window. Called at startup and on settings change.
+pub fn apply_window_appearance(window: &Window, material: WindowMaterial, tint: Option<&str>) {
+    let effects = material.effect().map(|effect| {
+        EffectsBuilder::new()
+            .effect(effect)
+            .state(EffectState::FollowsWindowActiveState)
+            .build()
+    });
+    if let Err(e) = window.set_effects(effects) {
+        eprintln!("[Appearance] Failed to set window effects: {}", e);
+    }
+
+    let background = tint.and_then(parse_tint);
+    if let Err(e) = window.set_background_color(background) {
+        eprintln!("[Appearance] Failed to set window background: {}", e);
+    }
+    println!("[Appearance] Applied material {:?}, tint {:?}", material, tint);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tint() {
+        assert_eq!(parse_tint("#0a84ff"), Some(Color(0x0a, 0x84, 0xff, 255)));
+        assert_eq!(parse_tint("#fff"), Some(Color(255, 255, 255, 255)));
+        assert_eq!(parse_tint("0a84ff"), None);
+        assert_eq!(parse_tint("#12345"), None);
+        assert_eq!(parse_tint("#gggggg"), None);
+    }
+
+    #[test]
+    fn test_available_materials_match_platform() {
+        let available = WindowMaterial::available();
+        assert_eq!(available[0], WindowMaterial::None);
+        assert!(available.iter().all(|m| *m == WindowMaterial::None || m.effect().is_some()));
+        if cfg!(target_os = "linux") {
+            assert_eq!(available, vec![WindowMaterial::None]);
+        }
+    }
+
+    #[test]
+    fn test_material_serde() {
+        let m: WindowMaterial = serde_json::from_str("\"under_window\"").unwrap();
+        assert_eq!(m, WindowMaterial::UnderWindow);
+    }
+}
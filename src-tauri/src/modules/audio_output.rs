@@ -0,0 +1,324 @@
+// Audio output device selection.
+//
+// `Settings.audio_output` names the device tabs play to (None: the system
+// default), and a tab can override it from the tab strip. Devices are listed
+// natively by name: system_profiler on macOS, the MMDevice API on Windows and
+// pactl on Linux.
+//
+// Routing itself happens in the page: `page_script` looks the device up by label
+// in `enumerateDevices()` and calls `setSinkId()` on media elements and Web Audio
+// contexts, including ones created later. That only works where the engine has
+// setSinkId (WebView2; not WKWebView or WebKitGTK), and pages only see device
+// labels once they have been granted microphone access, so otherwise the tab keeps
+// playing to the system default. macOS has no public API for routing a single
+// app's audio, so there's no native fallback either.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+/// Installs `window.__SOVEREIGN_SET_AUDIO_OUTPUT__(name)` and routes to the
+/// initial device, if any.
+const PAGE_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_SET_AUDIO_OUTPUT__) return;
+    let sinkId = ''; // '' is the default device
+
+    async function resolve(name) {
+        if (!name) return '';
+        if (!navigator.mediaDevices || !navigator.mediaDevices.enumerateDevices) return null;
+        const wanted = name.toLowerCase();
+        const devices = await navigator.mediaDevices.enumerateDevices();
+        const match = devices.find(d => d.kind === 'audiooutput' && d.label && d.label.toLowerCase().includes(wanted));
+        return match ? match.deviceId : null;
+    }
+    function route(target) {
+        if (typeof target.setSinkId === 'function' && target.sinkId !== sinkId) {
+            target.setSinkId(sinkId).catch(() => {});
+        }
+    }
+
+    const contexts = new Set(); // WeakRefs to the page's AudioContexts
+    const NativeAudioContext = window.AudioContext;
+    if (NativeAudioContext && typeof NativeAudioContext.prototype.setSinkId === 'function') {
+        window.AudioContext = class AudioContext extends NativeAudioContext {
+            constructor(...args) {
+                super(...args);
+                contexts.add(new WeakRef(this));
+                if (sinkId) route(this);
+            }
+        };
+    }
+
+    // Elements never added to the document (new Audio()) are caught on play()
+    const play = HTMLMediaElement.prototype.play;
+    HTMLMediaElement.prototype.play = function() {
+        if (sinkId) route(this);
+        return play.apply(this, arguments);
+    };
+    new MutationObserver(mutations => {
+        if (!sinkId) return;
+        for (const m of mutations) {
+            m.addedNodes.forEach(node => {
+                if (node.nodeType !== 1) return;
+                if (node.matches('audio,video')) route(node);
+                node.querySelectorAll('audio,video').forEach(route);
+            });
+        }
+    }).observe(document, { subtree: true, childList: true });
+
+    window.__SOVEREIGN_SET_AUDIO_OUTPUT__ = async function(name) {
+        const id = await resolve(name);
+        if (id === null) {
+            console.warn('[AudioOutput] Device not available to this page:', name);
+            return false;
+        }
+        sinkId = id;
+        document.querySelectorAll('audio,video').forEach(route);
+        contexts.forEach(ref => {
+            const ctx = ref.deref();
+            if (ctx) route(ctx);
+            else contexts.delete(ref);
+        });
+        return true;
+    };
+    const initial = __DEVICE__;
+    if (initial) window.__SOVEREIGN_SET_AUDIO_OUTPUT__(initial);
+})();
+"#;
+
+pub fn page_script(device: Option<&str>) -> String {
+    PAGE_SCRIPT_TEMPLATE.replace("__DEVICE__", &serde_json::to_string(&device).unwrap_or_else(|_| "null".to_string()))
+}
+
+fn set_device_script(device: Option<&str>) -> String {
+    format!(
+        "window.__SOVEREIGN_SET_AUDIO_OUTPUT__ && window.__SOVEREIGN_SET_AUDIO_OUTPUT__({});",
+        serde_json::to_string(&device).unwrap_or_else(|_| "null".to_string())
+    )
+}
+
+/// Output device names from `system_profiler SPAudioDataType -json`.
+pub fn parse_system_profiler(json: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Vec::new();
+    };
+    value["SPAudioDataType"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|group| group["_items"].as_array().into_iter().flatten())
+        .filter(|item| item.get("coreaudio_device_output").is_some())
+        .filter_map(|item| item["_name"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// Sink descriptions from `pactl --format=json list sinks`.
+pub fn parse_pactl_sinks(json: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return Vec::new();
+    };
+    value.as_array().into_iter().flatten().filter_map(|sink| sink["description"].as_str().map(str::to_string)).collect()
+}
+
+#[cfg(target_os = "macos")]
+fn output_devices() -> Vec<String> {
+    std::process::Command::new("system_profiler")
+        .args(["SPAudioDataType", "-json"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map_or_else(Vec::new, |output| parse_system_profiler(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(windows)]
+unsafe fn render_endpoints() -> windows::core::Result<Vec<String>> {
+    use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+    use windows::Win32::Media::Audio::{eRender, IMMDeviceEnumerator, MMDeviceEnumerator, DEVICE_STATE_ACTIVE};
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED, STGM_READ};
+
+    // Already initialized (possibly in another mode) is fine
+    let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+    let collection = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+    let mut names = Vec::new();
+    for i in 0..collection.GetCount()? {
+        let store = collection.Item(i)?.OpenPropertyStore(STGM_READ)?;
+        names.push(store.GetValue(&PKEY_Device_FriendlyName)?.to_string());
+    }
+    Ok(names)
+}
+
+#[cfg(windows)]
+fn output_devices() -> Vec<String> {
+    unsafe { render_endpoints() }.unwrap_or_else(|e| {
+        eprintln!("[AudioOutput] Failed to list devices: {}", e);
+        Vec::new()
+    })
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn output_devices() -> Vec<String> {
+    std::process::Command::new("pactl")
+        .args(["--format=json", "list", "sinks"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map_or_else(Vec::new, |output| parse_pactl_sinks(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Per-tab device overrides.
+pub struct AudioOutputManager {
+    overrides: Mutex<HashMap<String, Option<String>>>, // webview label -> device, None for the system default
+}
+
+impl AudioOutputManager {
+    pub fn new() -> Self {
+        Self { overrides: Mutex::new(HashMap::new()) }
+    }
+
+    /// Sets or (with `None`) clears the override for a webview.
+    pub fn set_override(&self, webview_label: &str, device: Option<Option<String>>) {
+        let mut overrides = self.overrides.lock().unwrap();
+        match device {
+            Some(device) => overrides.insert(webview_label.to_string(), device),
+            None => overrides.remove(webview_label),
+        };
+    }
+
+    pub fn get_override(&self, webview_label: &str) -> Option<Option<String>> {
+        self.overrides.lock().unwrap().get(webview_label).cloned()
+    }
+
+    /// The device a webview plays to: its override, else `global`.
+    pub fn device_for(&self, webview_label: &str, global: Option<&str>) -> Option<String> {
+        self.get_override(webview_label).unwrap_or_else(|| global.map(str::to_string))
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.overrides.lock().unwrap().remove(webview_label);
+    }
+}
+
+impl Default for AudioOutputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Re-applies a webview's device after a load; the init script only knows the
+/// device the tab was created with.
+pub fn on_page_finished(webview: &tauri::Webview, state: &AppState) {
+    let global = state.settings.read().unwrap().audio_output.clone();
+    let device = state.audio_output.device_for(webview.label(), global.as_deref());
+    if device.is_some() || global.is_some() {
+        let _ = webview.eval(&set_device_script(device.as_deref()));
+    }
+}
+
+/// Pushes the current devices to every open tab, e.g. after the global setting changed.
+pub fn apply_to_tabs(app: &AppHandle, state: &AppState) {
+    let global = state.settings.read().unwrap().audio_output.clone();
+    let labels: Vec<String> = state.tabs.lock().unwrap().iter().map(|t| t.webview_label.clone()).collect();
+    for label in labels {
+        if let Some(webview) = app.get_webview(&label) {
+            let device = state.audio_output.device_for(&label, global.as_deref());
+            let _ = webview.eval(&set_device_script(device.as_deref()));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioOutputs {
+    pub devices: Vec<String>,
+    pub selected: Option<String>, // The tab's override ("" for the system default) or the global setting
+    pub overridden: bool,
+}
+
+/// Output devices, with the selection for `tab_id` (or the global one without it).
+#[tauri::command]
+pub fn get_audio_outputs(state: tauri::State<AppState>, tab_id: Option<String>) -> Result<AudioOutputs, BrowserError> {
+    let global = state.settings.read().unwrap().audio_output.clone();
+    let tab_override = tab_id
+        .and_then(|id| state.tabs.lock().unwrap().iter().find(|t| t.id == id).map(|t| t.webview_label.clone()))
+        .and_then(|label| state.audio_output.get_override(&label));
+    Ok(AudioOutputs {
+        devices: output_devices(),
+        overridden: tab_override.is_some(),
+        selected: tab_override.map_or(global, |device| Some(device.unwrap_or_default())),
+    })
+}
+
+/// Routes one tab's audio. `device` is a device name, "" for the system default,
+/// or null to follow the global setting again.
+#[tauri::command]
+pub fn set_tab_audio_output(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    tab_id: String,
+    device: Option<String>,
+) -> Result<(), BrowserError> {
+    let label = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| t.webview_label.clone())
+        .ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+    state.audio_output.set_override(&label, device.map(|d| (!d.is_empty()).then_some(d)));
+
+    let global = state.settings.read().unwrap().audio_output.clone();
+    let effective = state.audio_output.device_for(&label, global.as_deref());
+    println!("[AudioOutput] Tab {} -> {}", tab_id, effective.as_deref().unwrap_or("system default"));
+    if let Some(webview) = app.get_webview(&label) {
+        webview.eval(&set_device_script(effective.as_deref()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_system_profiler() {
+        let json = r#"{"SPAudioDataType":[{"_name":"coreaudio_device","_items":[
+            {"_name":"MacBook Pro Microphone","coreaudio_device_input":1},
+            {"_name":"MacBook Pro Speakers","coreaudio_device_output":2,"coreaudio_default_audio_output_device":"spaudio_yes"},
+            {"_name":"AirPods Pro","coreaudio_device_input":1,"coreaudio_device_output":2}
+        ]}]}"#;
+        assert_eq!(parse_system_profiler(json), vec!["MacBook Pro Speakers", "AirPods Pro"]);
+        assert!(parse_system_profiler("not json").is_empty());
+    }
+
+    #[test]
+    fn test_parse_pactl_sinks() {
+        let json = r#"[{"index":0,"name":"alsa_output.pci","description":"Built-in Audio Analog Stereo"},
+            {"index":1,"name":"bluez_output.XX","description":"WH-1000XM4"}]"#;
+        assert_eq!(parse_pactl_sinks(json), vec!["Built-in Audio Analog Stereo", "WH-1000XM4"]);
+    }
+
+    #[test]
+    fn test_device_for() {
+        let manager = AudioOutputManager::new();
+        assert_eq!(manager.device_for("webview-tab-1", Some("Speakers")).as_deref(), Some("Speakers"));
+        manager.set_override("webview-tab-1", Some(Some("Headphones".to_string())));
+        assert_eq!(manager.device_for("webview-tab-1", Some("Speakers")).as_deref(), Some("Headphones"));
+        manager.set_override("webview-tab-1", Some(None));
+        assert_eq!(manager.device_for("webview-tab-1", Some("Speakers")), None);
+        manager.forget_webview("webview-tab-1");
+        assert_eq!(manager.device_for("webview-tab-1", None), None);
+    }
+
+    #[test]
+    fn test_page_script_embeds_device() {
+        assert!(page_script(Some("AirPods \"Pro\"")).contains(r#"const initial = "AirPods \"Pro\"";"#));
+        assert!(page_script(None).contains("const initial = null;"));
+    }
+}
@@ -0,0 +1,143 @@
+// Unread badge detection - pure logic, no Tauri imports.
+// Mail/chat sites advertise unread counts in the title ("(3) Inbox") or by
+// swapping the favicon for a badged variant (".../unreadcountfavicon/3/...").
+
+const FAVICON_BADGE_KEYWORDS: &[&str] = &["unreadcount", "unread", "badge", "notification", "notif"];
+const MAX_KEYWORD_DISTANCE: usize = 12;
+// A bracketed number mid-title only counts when one of these words sits right
+// next to it - otherwise "Movie (1999) - IMDb" would be 1999 unread.
+const TITLE_UNREAD_KEYWORDS: &[&str] = &["inbox", "unread", "messages", "notifications", "chat", "mail"];
+const MAX_MID_TITLE_COUNT: u32 = 99_999;
+
+/// Parse an unread count from a page title.
+/// Handles "(3) Inbox", "(99+) Chat", "[4] Feed" and, next to an unread keyword,
+/// "Inbox (12) - me@example.com".
+pub fn parse_title_unread(title: &str) -> Option<u32> {
+    let trimmed = title.trim();
+
+    // Prefix form: "(3) Inbox" / "[3] Inbox"
+    if let Some(count) = leading_count(trimmed) {
+        return Some(count);
+    }
+
+    // Gmail form: "Inbox (12) - me@example.com"
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        let mut rest = trimmed;
+        while let Some(start) = rest.find(open) {
+            let after = &rest[start + open.len_utf8()..];
+            if let Some(end) = after.find(close) {
+                let before = &rest[..start];
+                let next = &after[end + close.len_utf8()..];
+                if near_unread_keyword(before, next) {
+                    if let Some(count) = parse_count(&after[..end]).filter(|n| *n <= MAX_MID_TITLE_COUNT) {
+                        return Some(count);
+                    }
+                }
+                rest = &after[end..];
+            } else {
+                break;
+            }
+        }
+    }
+
+    None
+}
+
+/// True when the word just before or just after a bracketed number is an unread keyword.
+fn near_unread_keyword(before: &str, after: &str) -> bool {
+    let is_keyword = |word: Option<&str>| {
+        word.map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+            .is_some_and(|w| TITLE_UNREAD_KEYWORDS.contains(&w.as_str()))
+    };
+    is_keyword(before.split_whitespace().next_back()) || is_keyword(after.split_whitespace().next())
+}
+
+fn leading_count(title: &str) -> Option<u32> {
+    let close = match title.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let end = title.find(close)?;
+    parse_count(&title[1..end])
+}
+
+/// "3" -> 3, "99+" -> 99, "1,204" -> 1204. Anything else is not a count.
+fn parse_count(s: &str) -> Option<u32> {
+    let s = s.trim().trim_end_matches('+').replace(',', "");
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok().filter(|n| *n > 0)
+}
+
+/// Extract a count from a badged favicon URL, e.g.
+/// "https://ssl.gstatic.com/ui/v1/icons/mail/rfr/unreadcountfavicon/3/12_2x.png" -> 3.
+/// Canvas-generated data: URLs carry no readable count and are ignored.
+pub fn parse_favicon_unread(favicon: &str) -> Option<u32> {
+    if favicon.starts_with("data:") {
+        return None;
+    }
+    let lower = favicon.to_lowercase();
+
+    for keyword in FAVICON_BADGE_KEYWORDS {
+        if let Some(pos) = lower.find(keyword) {
+            let tail = &lower[pos + keyword.len()..];
+            let digits_start = tail.find(|c: char| c.is_ascii_digit())?;
+            if digits_start > MAX_KEYWORD_DISTANCE {
+                return None;
+            }
+            let digits: String = tail[digits_start..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            return digits.parse().ok().filter(|n: &u32| *n > 0);
+        }
+    }
+    None
+}
+
+/// Combined unread count for a tab. The title wins because it is usually exact.
+pub fn unread_count(title: &str, favicon: Option<&str>) -> Option<u32> {
+    parse_title_unread(title).or_else(|| favicon.and_then(parse_favicon_unread))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("(3) Inbox", Some(3))]
+    #[case("(99+) Slack", Some(99))]
+    #[case("[4] Feed", Some(4))]
+    #[case("Inbox (12) - me@example.com - Gmail", Some(12))]
+    #[case("Inbox (1,204) - me@example.com", Some(1204))]
+    #[case("(0) Inbox", None)]
+    #[case("Rust (programming language) - Wikipedia", None)]
+    #[case("Movie (1999) - IMDb", None)]
+    #[case("Release notes [2024] - Blog", None)]
+    #[case("Team Chat (5)", Some(5))]
+    #[case("Inbox", None)]
+    #[case("", None)]
+    fn test_parse_title_unread(#[case] title: &str, #[case] expected: Option<u32>) {
+        assert_eq!(parse_title_unread(title), expected);
+    }
+
+    #[rstest]
+    #[case("https://ssl.gstatic.com/ui/v1/icons/mail/rfr/unreadcountfavicon/3/12_2x.png", Some(3))]
+    #[case("https://chat.example.com/favicon-unread-7.png", Some(7))]
+    #[case("https://example.com/favicon.ico", None)]
+    #[case("data:image/png;base64,unread5", None)]
+    fn test_parse_favicon_unread(#[case] favicon: &str, #[case] expected: Option<u32>) {
+        assert_eq!(parse_favicon_unread(favicon), expected);
+    }
+
+    #[test]
+    fn test_title_takes_priority() {
+        let favicon = "https://chat.example.com/favicon-unread-7.png";
+        assert_eq!(unread_count("(2) Chat", Some(favicon)), Some(2));
+        assert_eq!(unread_count("Chat", Some(favicon)), Some(7));
+        assert_eq!(unread_count("Chat", None), None);
+    }
+}
@@ -0,0 +1,481 @@
+// Blocked request counters.
+//
+// Every request AdBlockManager blocks is counted against the tab that made it (reset
+// when the tab starts a new page load) and against today's aggregate, which is
+// persisted to block_stats.json together with a lifetime total.
+//
+// On macOS, WKContentRuleList blocks without telling anyone, so tabs run
+// `SAFARI_COUNTER_SCRIPT` instead: it reports the resource URLs the page references
+// and the ones the Rust engine would block are counted. That's an estimate; blocked
+// fetch()/XHR calls are invisible to it.
+//
+// The same counters track bytes the data saver kept from loading (see
+// modules::data_saver), and the daily ones count the other protections that
+// make up the weekly privacy report (see modules::privacy_report).
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::profile;
+use crate::state::AppState;
+
+const STATS_FILE: &str = "block_stats.json";
+const KEPT_DAYS: usize = 90;
+const TOP_DOMAINS: usize = 10;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reports the resources a page references to `report_page_resources`, in batches.
+#[cfg(target_os = "macos")]
+pub const SAFARI_COUNTER_SCRIPT: &str = r#"
+(function() {
+    if (!window.__TAURI__) return;
+    const invoke = window.__TAURI__.core.invoke;
+    const TYPES = {
+        IMG: 'image', SCRIPT: 'script', IFRAME: 'sub_frame', LINK: 'stylesheet',
+        VIDEO: 'media', AUDIO: 'media', SOURCE: 'media', EMBED: 'object', OBJECT: 'object'
+    };
+    const seen = new Set();
+    let pending = [];
+    let timer = null;
+
+    function collect(el) {
+        const type = TYPES[el.tagName];
+        if (!type) return;
+        if (el.tagName === 'LINK' && !/stylesheet/i.test(el.rel)) return;
+        const src = el.src || el.href || el.data;
+        if (typeof src !== 'string' || !/^https?:/.test(src) || seen.has(src)) return;
+        seen.add(src);
+        pending.push({ url: src, type });
+        if (!timer) timer = setTimeout(flush, 1000);
+    }
+
+    function flush() {
+        timer = null;
+        if (!pending.length) return;
+        const resources = pending;
+        pending = [];
+        invoke('report_page_resources', { resources, pageUrl: location.href }).catch(() => {});
+    }
+
+    function scan(root) {
+        if (root.nodeType !== 1) return;
+        collect(root);
+        root.querySelectorAll('img,script,iframe,link,video,audio,source,embed,object').forEach(collect);
+    }
+
+    new MutationObserver(mutations => {
+        for (const m of mutations) {
+            if (m.type === 'attributes') collect(m.target);
+            else m.addedNodes.forEach(scan);
+        }
+    }).observe(document, { subtree: true, childList: true, attributes: true, attributeFilter: ['src', 'href'] });
+    document.addEventListener('DOMContentLoaded', () => scan(document.documentElement));
+})();
+"#;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabBlockStats {
+    pub blocked: u64,
+    pub domains: HashMap<String, u64>,
+    pub saved_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DailyBlockStats {
+    pub blocked: u64,
+    #[serde(default)]
+    pub domains: HashMap<String, u64>,
+    #[serde(default)]
+    pub saved_bytes: u64,
+    #[serde(default)]
+    pub https_upgrades: u64,
+    #[serde(default)]
+    pub popups_blocked: u64,
+    #[serde(default)]
+    pub params_stripped: u64,
+}
+
+/// Protections other than blocked requests, counted per day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protection {
+    HttpsUpgrade,
+    PopupBlocked,
+    ParamsStripped(u64), // Tracking parameters removed from one URL
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredBlockStats {
+    lifetime: u64,
+    #[serde(default)]
+    lifetime_saved_bytes: u64,
+    #[serde(default)]
+    daily: BTreeMap<String, DailyBlockStats>, // "YYYY-MM-DD", local time
+}
+
+impl StoredBlockStats {
+    fn day(&mut self, day: &str) -> &mut DailyBlockStats {
+        if !self.daily.contains_key(day) {
+            self.daily.insert(day.to_string(), DailyBlockStats::default());
+            while self.daily.len() > KEPT_DAYS {
+                self.daily.pop_first();
+            }
+        }
+        self.daily.entry(day.to_string()).or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainCount {
+    pub domain: String,
+    pub blocked: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifetimeBlockStats {
+    pub total: u64,
+    pub today: u64,
+    pub daily: BTreeMap<String, u64>,
+    pub top_domains: Vec<DomainCount>, // Over the kept days
+    pub saved_bytes: u64,
+    pub saved_bytes_today: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockedCountPayload {
+    tab_id: String,
+    blocked: u64,
+}
+
+/// The host a blocked request is attributed to, without a leading "www.".
+pub fn blocked_domain(url: &str) -> Option<String> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+fn today() -> String {
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+pub struct BlockStatsManager {
+    path: PathBuf,
+    tabs: DashMap<String, TabBlockStats>, // webview label -> current page
+    stored: Mutex<StoredBlockStats>,
+    dirty: AtomicBool,
+}
+
+impl BlockStatsManager {
+    pub fn new(app: &AppHandle) -> Self {
+        let dir = profile::data_dir(app).expect("failed to get app data dir");
+        Self::load_from(dir.join(STATS_FILE))
+    }
+
+    fn load_from(path: PathBuf) -> Self {
+        let stored = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| match serde_json::from_str(&json) {
+                Ok(stored) => Some(stored),
+                Err(e) => {
+                    eprintln!("[BlockStats] Failed to parse {}: {}", STATS_FILE, e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { path, tabs: DashMap::new(), stored: Mutex::new(stored), dirty: AtomicBool::new(false) }
+    }
+
+    /// Counts a blocked request; returns the tab's new total.
+    pub fn record(&self, webview_label: &str, url: &str) -> u64 {
+        self.record_on(webview_label, url, &today())
+    }
+
+    fn record_on(&self, webview_label: &str, url: &str, day: &str) -> u64 {
+        let domain = blocked_domain(url).unwrap_or_default();
+        {
+            let mut stored = self.stored.lock().unwrap();
+            stored.lifetime += 1;
+            let daily = stored.day(day);
+            daily.blocked += 1;
+            *daily.domains.entry(domain.clone()).or_insert(0) += 1;
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+
+        let mut tab = self.tabs.entry(webview_label.to_string()).or_default();
+        tab.blocked += 1;
+        *tab.domains.entry(domain).or_insert(0) += 1;
+        tab.blocked
+    }
+
+    /// Counts bytes the data saver kept from loading.
+    pub fn record_saved(&self, webview_label: &str, bytes: u64) {
+        self.record_saved_on(webview_label, bytes, &today())
+    }
+
+    fn record_saved_on(&self, webview_label: &str, bytes: u64, day: &str) {
+        {
+            let mut stored = self.stored.lock().unwrap();
+            stored.lifetime_saved_bytes += bytes;
+            stored.day(day).saved_bytes += bytes;
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        self.tabs.entry(webview_label.to_string()).or_default().saved_bytes += bytes;
+    }
+
+    pub fn record_protection(&self, protection: Protection) {
+        self.record_protection_on(protection, &today())
+    }
+
+    fn record_protection_on(&self, protection: Protection, day: &str) {
+        {
+            let mut stored = self.stored.lock().unwrap();
+            let daily = stored.day(day);
+            match protection {
+                Protection::HttpsUpgrade => daily.https_upgrades += 1,
+                Protection::PopupBlocked => daily.popups_blocked += 1,
+                Protection::ParamsStripped(count) => daily.params_stripped += count,
+            }
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// The kept days' aggregates, "YYYY-MM-DD" in local time.
+    pub fn daily(&self) -> BTreeMap<String, DailyBlockStats> {
+        self.stored.lock().unwrap().daily.clone()
+    }
+
+    /// Drops a tab's counts, when it starts a new page or closes.
+    pub fn clear_tab(&self, webview_label: &str) {
+        self.tabs.remove(webview_label);
+    }
+
+    pub fn tab_stats(&self, webview_label: &str) -> TabBlockStats {
+        self.tabs.get(webview_label).map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn lifetime(&self) -> LifetimeBlockStats {
+        self.lifetime_on(&today())
+    }
+
+    fn lifetime_on(&self, day: &str) -> LifetimeBlockStats {
+        let stored = self.stored.lock().unwrap();
+        let mut domains: HashMap<&str, u64> = HashMap::new();
+        for daily in stored.daily.values() {
+            for (domain, count) in &daily.domains {
+                *domains.entry(domain.as_str()).or_insert(0) += count;
+            }
+        }
+        let mut top_domains: Vec<DomainCount> = domains
+            .into_iter()
+            .filter(|(domain, _)| !domain.is_empty())
+            .map(|(domain, blocked)| DomainCount { domain: domain.to_string(), blocked })
+            .collect();
+        top_domains.sort_by(|a, b| b.blocked.cmp(&a.blocked).then_with(|| a.domain.cmp(&b.domain)));
+        top_domains.truncate(TOP_DOMAINS);
+
+        LifetimeBlockStats {
+            total: stored.lifetime,
+            today: stored.daily.get(day).map_or(0, |d| d.blocked),
+            daily: stored.daily.iter().map(|(day, d)| (day.clone(), d.blocked)).collect(),
+            top_domains,
+            saved_bytes: stored.lifetime_saved_bytes,
+            saved_bytes_today: stored.daily.get(day).map_or(0, |d| d.saved_bytes),
+        }
+    }
+
+    /// Writes the aggregates if anything was counted since the last write.
+    pub fn flush(&self) -> Result<(), String> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let json = serde_json::to_string(&*self.stored.lock().unwrap()).map_err(|e| e.to_string())?;
+        write_atomic(&self.path, &json).inspect_err(|_| self.dirty.store(true, Ordering::Relaxed))
+    }
+
+    pub fn spawn_flush_thread(self: &Arc<Self>) {
+        let manager = self.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(FLUSH_INTERVAL);
+            if let Err(e) = manager.flush() {
+                eprintln!("[BlockStats] Failed to save: {}", e);
+            }
+        });
+    }
+}
+
+fn write_atomic(path: &Path, json: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, json).map_err(|e| e.to_string())?;
+    fs::rename(tmp_path, path).map_err(|e| e.to_string())
+}
+
+/// Counts a block for the tab owning `webview_label` and tells the toolbar.
+pub fn record_block(app: &AppHandle, state: &AppState, webview_label: &str, url: &str) {
+    let blocked = state.block_stats.record(webview_label, url);
+    let tab_id = {
+        let tabs = state.tabs.lock().unwrap();
+        tabs.iter().find(|t| t.webview_label == webview_label).map(|t| t.id.clone())
+    };
+    if let Some(tab_id) = tab_id {
+        emit_blocked_count(app, &tab_id, blocked);
+    }
+}
+
+/// "blocked-count" drives the toolbar's shield badge.
+pub fn emit_blocked_count(app: &AppHandle, tab_id: &str, blocked: u64) {
+    let _ = app.emit("blocked-count", BlockedCountPayload { tab_id: tab_id.to_string(), blocked });
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageResource {
+    pub url: String,
+    #[serde(rename = "type")]
+    pub request_type: String,
+}
+
+/// Safari counting heuristic: counts the page's resources the engine would block.
+#[tauri::command]
+pub fn report_page_resources(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    resources: Vec<PageResource>,
+    page_url: String,
+) -> Result<(), BrowserError> {
+    if !state.settings.read().unwrap().block_trackers {
+        return Ok(());
+    }
+    for resource in resources {
+        if state.adblock.should_block_request(&resource.url, &page_url, &resource.request_type) {
+            record_block(&app, &state, webview.label(), &resource.url);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_block_stats(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    tab_id: String,
+) -> Result<TabBlockStats, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let label = {
+        let tabs = state.tabs.lock().unwrap();
+        tabs.iter().find(|t| t.id == tab_id).map(|t| t.webview_label.clone())
+    };
+    let label = label.ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+    Ok(state.block_stats.tab_stats(&label))
+}
+
+#[tauri::command]
+pub fn get_lifetime_block_stats(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<LifetimeBlockStats, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.block_stats.lifetime())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    #[rstest]
+    #[case("https://www.doubleclick.net/ad.js", Some("doubleclick.net"))]
+    #[case("https://Stats.Example.com/p?x=1", Some("stats.example.com"))]
+    #[case("not a url", None)]
+    fn test_blocked_domain(#[case] url: &str, #[case] expected: Option<&str>) {
+        assert_eq!(blocked_domain(url).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_counts_per_tab_and_day() {
+        let dir = tempdir().unwrap();
+        let manager = BlockStatsManager::load_from(dir.path().join(STATS_FILE));
+
+        assert_eq!(manager.record_on("webview-tab-1", "https://ads.example.com/a.js", "2026-01-01"), 1);
+        assert_eq!(manager.record_on("webview-tab-1", "https://ads.example.com/b.js", "2026-01-01"), 2);
+        assert_eq!(manager.record_on("webview-tab-2", "https://tracker.net/p", "2026-01-02"), 1);
+
+        let tab = manager.tab_stats("webview-tab-1");
+        assert_eq!(tab.blocked, 2);
+        assert_eq!(tab.domains.get("ads.example.com"), Some(&2));
+
+        manager.clear_tab("webview-tab-1");
+        assert_eq!(manager.tab_stats("webview-tab-1").blocked, 0);
+
+        let lifetime = manager.lifetime_on("2026-01-02");
+        assert_eq!(lifetime.total, 3);
+        assert_eq!(lifetime.today, 1);
+        assert_eq!(lifetime.daily.get("2026-01-01"), Some(&2));
+        assert_eq!(lifetime.top_domains[0], DomainCount { domain: "ads.example.com".to_string(), blocked: 2 });
+    }
+
+    #[test]
+    fn test_saved_bytes() {
+        let dir = tempdir().unwrap();
+        let manager = BlockStatsManager::load_from(dir.path().join(STATS_FILE));
+        manager.record_saved_on("webview-tab-1", 300_000, "2026-01-01");
+        manager.record_saved_on("webview-tab-1", 200_000, "2026-01-02");
+        manager.record_on("webview-tab-1", "https://ads.example.com/a.js", "2026-01-02");
+
+        let tab = manager.tab_stats("webview-tab-1");
+        assert_eq!((tab.blocked, tab.saved_bytes), (1, 500_000));
+        let lifetime = manager.lifetime_on("2026-01-02");
+        assert_eq!((lifetime.saved_bytes, lifetime.saved_bytes_today), (500_000, 200_000));
+        // Saving bytes isn't a blocked request
+        assert_eq!(lifetime.daily.get("2026-01-01"), Some(&0));
+    }
+
+    #[test]
+    fn test_protections_count_per_day() {
+        let dir = tempdir().unwrap();
+        let manager = BlockStatsManager::load_from(dir.path().join(STATS_FILE));
+        manager.record_protection_on(Protection::HttpsUpgrade, "2026-01-01");
+        manager.record_protection_on(Protection::PopupBlocked, "2026-01-01");
+        manager.record_protection_on(Protection::ParamsStripped(3), "2026-01-01");
+        manager.record_protection_on(Protection::ParamsStripped(1), "2026-01-02");
+
+        let daily = manager.daily();
+        let first = &daily["2026-01-01"];
+        assert_eq!((first.https_upgrades, first.popups_blocked, first.params_stripped), (1, 1, 3));
+        assert_eq!(daily["2026-01-02"].params_stripped, 1);
+        assert_eq!(first.blocked, 0);
+    }
+
+    #[test]
+    fn test_flush_round_trip_and_pruning() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(STATS_FILE);
+        let manager = BlockStatsManager::load_from(path.clone());
+        for day in 1..=KEPT_DAYS + 5 {
+            manager.record_on("webview-tab-1", "https://ads.example.com/", &format!("2026-{:02}-{:02}", day / 28 + 1, day % 28 + 1));
+        }
+        manager.flush().unwrap();
+
+        let reloaded = BlockStatsManager::load_from(path);
+        let lifetime = reloaded.lifetime_on("2026-01-02");
+        assert_eq!(lifetime.total, (KEPT_DAYS + 5) as u64);
+        assert_eq!(lifetime.daily.len(), KEPT_DAYS);
+        assert!(!lifetime.daily.contains_key("2026-01-02")); // Oldest days dropped
+        // Per-tab counts are per session
+        assert_eq!(reloaded.tab_stats("webview-tab-1").blocked, 0);
+    }
+}
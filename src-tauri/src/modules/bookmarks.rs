@@ -0,0 +1,136 @@
+// Bookmarks, kept in bookmarks.json.
+//
+// Each bookmark remembers the folder it sat in ("Bookmarks Bar/Work"), so ones
+// brought over by modules::importer keep their place. The same URL may be in
+// several folders, but only once per folder: adding it again is a no-op, which
+// makes importing the same data twice harmless.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub url: String,
+    pub title: String,
+    pub folder: String, // Folder names joined with '/', empty at the top level
+    pub added_at: u64,  // Unix seconds
+}
+
+pub struct BookmarkStore {
+    path: PathBuf,
+    bookmarks: Mutex<Vec<Bookmark>>,
+}
+
+impl BookmarkStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(BOOKMARKS_FILE);
+        let bookmarks = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, bookmarks: Mutex::new(bookmarks) }
+    }
+
+    fn save(&self, bookmarks: &[Bookmark]) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(bookmarks).unwrap_or_default()) {
+            eprintln!("[Bookmarks] Failed to save: {}", e);
+        }
+    }
+
+    pub fn list(&self) -> Vec<Bookmark> {
+        self.bookmarks.lock().unwrap().clone()
+    }
+
+    pub fn contains(&self, url: &str, folder: &str) -> bool {
+        self.bookmarks.lock().unwrap().iter().any(|b| b.url == url && b.folder == folder)
+    }
+
+    /// Adds the bookmarks not already in their folder. Returns how many were added.
+    pub fn add_all(&self, new: Vec<Bookmark>) -> usize {
+        let mut bookmarks = self.bookmarks.lock().unwrap();
+        let before = bookmarks.len();
+        for bookmark in new {
+            if !bookmarks.iter().any(|b| b.url == bookmark.url && b.folder == bookmark.folder) {
+                bookmarks.push(bookmark);
+            }
+        }
+        let added = bookmarks.len() - before;
+        if added > 0 {
+            self.save(&bookmarks);
+        }
+        added
+    }
+
+    pub fn remove(&self, url: &str, folder: &str) -> bool {
+        let mut bookmarks = self.bookmarks.lock().unwrap();
+        let before = bookmarks.len();
+        bookmarks.retain(|b| !(b.url == url && b.folder == folder));
+        let removed = bookmarks.len() != before;
+        if removed {
+            self.save(&bookmarks);
+        }
+        removed
+    }
+}
+
+#[tauri::command]
+pub fn get_bookmarks(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<Vec<Bookmark>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.bookmarks.list())
+}
+
+#[tauri::command]
+pub fn delete_bookmark(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    folder: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    if !state.bookmarks.remove(&url, &folder) {
+        return Err(BrowserError::NotFound(format!("No bookmark for {} in '{}'", url, folder)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(url: &str, folder: &str) -> Bookmark {
+        Bookmark { url: url.to_string(), title: url.to_string(), folder: folder.to_string(), added_at: 1 }
+    }
+
+    #[test]
+    fn test_add_all_skips_duplicates_per_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BookmarkStore::new(dir.path().to_path_buf());
+        let batch = vec![
+            bookmark("https://a.com/", "Bar"),
+            bookmark("https://a.com/", "Other"),
+            bookmark("https://a.com/", "Bar"),
+        ];
+        assert_eq!(store.add_all(batch.clone()), 2);
+        assert_eq!(store.add_all(batch), 0);
+
+        let reopened = BookmarkStore::new(dir.path().to_path_buf());
+        assert_eq!(reopened.list().len(), 2);
+        assert!(reopened.contains("https://a.com/", "Other"));
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BookmarkStore::new(dir.path().to_path_buf());
+        store.add_all(vec![bookmark("https://a.com/", "Bar"), bookmark("https://a.com/", "Other")]);
+        assert!(store.remove("https://a.com/", "Bar"));
+        assert!(!store.remove("https://a.com/", "Bar"));
+        assert_eq!(store.list(), vec![bookmark("https://a.com/", "Other")]);
+    }
+}
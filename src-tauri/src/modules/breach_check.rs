@@ -0,0 +1,332 @@
+// Breach check: have the sites you keep logins for, or a password, shown up in a
+// known data breach? Opt-in (`breach_check`), and only ever run from Settings.
+//
+// Sites: the browser keeps no passwords, so "saved logins" are the sites with
+// one-time code accounts (modules::totp) or email aliases (modules::email_alias).
+// The whole Have I Been Pwned breach list is downloaded and matched here by
+// eTLD+1, so HIBP never learns which sites are in question. The report is kept in
+// breach_check.json and shown next to the accounts in Settings.
+//
+// Passwords: checked one at a time as typed in Settings against the Pwned
+// Passwords range API (k-anonymity). Only the first five hex digits of the
+// password's SHA-1 leave the device, with padding so the response size doesn't
+// give the rest away. Neither the password nor its hash is stored.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::{cookie_policy, proxy, totp};
+use crate::state::AppState;
+
+const REPORT_FILE: &str = "breach_check.json";
+const BREACHES_URL: &str = "https://haveibeenpwned.com/api/v3/breaches";
+const RANGE_URL: &str = "https://api.pwnedpasswords.com/range/";
+const USER_AGENT: &str = "Sovereign-Browser-Breach-Check";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One entry of HIBP's breach list (only the fields used here).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Breach {
+    pub name: String,
+    pub title: String,
+    #[serde(default)]
+    pub domain: String,
+    #[serde(default)]
+    pub breach_date: String,
+    #[serde(default)]
+    pub pwn_count: u64,
+    #[serde(default)]
+    pub data_classes: Vec<String>,
+    #[serde(default)]
+    pub is_fabricated: bool,
+    #[serde(default)]
+    pub is_spam_list: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachSummary {
+    pub name: String,
+    pub title: String,
+    pub date: String,
+    pub pwn_count: u64,
+    pub data_classes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteWarning {
+    pub site: String,
+    pub sources: Vec<String>, // What's saved for it, e.g. "one-time codes"
+    pub breaches: Vec<BreachSummary>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreachReport {
+    pub checked_at: u64,
+    pub sites_checked: usize,
+    pub warnings: Vec<SiteWarning>,
+}
+
+/// The range API's prefix and the suffix to look for, as uppercase hex.
+pub fn hash_parts(password: &str) -> (String, String) {
+    let hash: String = totp::sha1(password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect();
+    let (prefix, suffix) = hash.split_at(5);
+    (prefix.to_string(), suffix.to_string())
+}
+
+/// How often `suffix` was seen, from a range response ("SUFFIX:COUNT" lines;
+/// padding lines have a count of 0).
+pub fn count_in_range(body: &str, suffix: &str) -> u64 {
+    body.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Breaches of each saved site, newest first. `saved` maps site to what's saved for it.
+pub fn match_sites(breaches: &[Breach], saved: &BTreeMap<String, Vec<String>>) -> Vec<SiteWarning> {
+    let mut by_site: BTreeMap<String, Vec<BreachSummary>> = BTreeMap::new();
+    for breach in breaches {
+        if breach.domain.is_empty() || breach.is_fabricated || breach.is_spam_list {
+            continue;
+        }
+        let site = cookie_policy::site_of(&breach.domain);
+        if saved.contains_key(&site) {
+            by_site.entry(site).or_default().push(BreachSummary {
+                name: breach.name.clone(),
+                title: breach.title.clone(),
+                date: breach.breach_date.clone(),
+                pwn_count: breach.pwn_count,
+                data_classes: breach.data_classes.clone(),
+            });
+        }
+    }
+    by_site
+        .into_iter()
+        .map(|(site, mut breaches)| {
+            breaches.sort_by(|a, b| b.date.cmp(&a.date));
+            SiteWarning { sources: saved[&site].clone(), site, breaches }
+        })
+        .collect()
+}
+
+/// Sites with something saved for them, and what that is.
+fn saved_sites(state: &AppState) -> BTreeMap<String, Vec<String>> {
+    let mut saved: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut add = |site: &str, source: &str| {
+        let sources = saved.entry(site.to_string()).or_default();
+        if !sources.iter().any(|s| s == source) {
+            sources.push(source.to_string());
+        }
+    };
+    for account in state.totp.accounts() {
+        add(&account.site, "one-time codes");
+    }
+    for alias in state.email_aliases.aliases() {
+        add(&alias.site, "email alias");
+    }
+    saved
+}
+
+pub struct BreachCheckStore {
+    path: PathBuf,
+    report: Mutex<Option<BreachReport>>,
+}
+
+impl BreachCheckStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(REPORT_FILE);
+        let report = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok());
+        Self { path, report: Mutex::new(report) }
+    }
+
+    pub fn report(&self) -> Option<BreachReport> {
+        self.report.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, report: BreachReport) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(&report).unwrap_or_default()) {
+            eprintln!("[BreachCheck] Failed to save: {}", e);
+        }
+        *self.report.lock().unwrap() = Some(report);
+    }
+
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+        *self.report.lock().unwrap() = None;
+    }
+}
+
+fn client(state: &AppState) -> Result<reqwest::Client, BrowserError> {
+    Ok(proxy::http_client_builder(state, REQUEST_TIMEOUT)?.user_agent(USER_AGENT).build()?)
+}
+
+async fn fetch_text(request: reqwest::RequestBuilder) -> Result<String, BrowserError> {
+    let response =
+        request.send().await.map_err(|e| BrowserError::Network(format!("Couldn't reach Have I Been Pwned: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(BrowserError::Network(format!("Have I Been Pwned answered {}", response.status())));
+    }
+    Ok(response.text().await?)
+}
+
+fn ensure_enabled(state: &AppState) -> Result<(), BrowserError> {
+    if state.settings.read().unwrap().breach_check {
+        Ok(())
+    } else {
+        Err(BrowserError::NotAllowed("Turn on Breach Check in Settings first".to_string()))
+    }
+}
+
+/// Checks every saved site against the breach list and keeps the report.
+#[tauri::command]
+pub async fn run_breach_check(
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+) -> Result<BreachReport, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    ensure_enabled(&state)?;
+    let saved = saved_sites(&state);
+    let body = fetch_text(client(&state)?.get(BREACHES_URL)).await?;
+    let breaches: Vec<Breach> =
+        serde_json::from_str(&body).map_err(|e| BrowserError::Network(format!("Unexpected breach list: {}", e)))?;
+
+    let report = BreachReport {
+        checked_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        sites_checked: saved.len(),
+        warnings: match_sites(&breaches, &saved),
+    };
+    println!(
+        "[BreachCheck] {} of {} saved sites appear in {} known breaches",
+        report.warnings.len(),
+        report.sites_checked,
+        breaches.len()
+    );
+    state.breach_check.set(report.clone());
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn get_breach_report(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Option<BreachReport>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.breach_check.report())
+}
+
+#[tauri::command]
+pub fn clear_breach_report(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    state.breach_check.clear();
+    Ok(())
+}
+
+/// How many times the password appears in Pwned Passwords (0 = not found).
+#[tauri::command]
+pub async fn check_password_breach(
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    password: String,
+) -> Result<u64, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    ensure_enabled(&state)?;
+    if password.is_empty() {
+        return Err(BrowserError::InvalidInput("Enter a password to check".to_string()));
+    }
+    let (prefix, suffix) = hash_parts(&password);
+    let request = client(&state)?.get(format!("{}{}", RANGE_URL, prefix)).header("Add-Padding", "true");
+    Ok(count_in_range(&fetch_text(request).await?, &suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breach(name: &str, domain: &str, date: &str) -> Breach {
+        Breach {
+            name: name.to_string(),
+            title: name.to_string(),
+            domain: domain.to_string(),
+            breach_date: date.to_string(),
+            pwn_count: 1000,
+            data_classes: vec!["Passwords".to_string()],
+            is_fabricated: false,
+            is_spam_list: false,
+        }
+    }
+
+    #[test]
+    fn test_hash_parts() {
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        let (prefix, suffix) = hash_parts("password");
+        assert_eq!(prefix, "5BAA6");
+        assert_eq!(suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+    }
+
+    #[test]
+    fn test_count_in_range() {
+        let body = "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+                    1E4C9B93F3F0682250B6CF8331B7EE68FD8:10434004\r\n\
+                    1E4C9B93F3F0682250B6CF8331B7EE68FD9:0\r\n";
+        assert_eq!(count_in_range(body, "1E4C9B93F3F0682250B6CF8331B7EE68FD8"), 10434004);
+        assert_eq!(count_in_range(body, "1e4c9b93f3f0682250b6cf8331b7ee68fd8"), 10434004);
+        assert_eq!(count_in_range(body, "1E4C9B93F3F0682250B6CF8331B7EE68FD9"), 0); // Padding
+        assert_eq!(count_in_range(body, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"), 0);
+    }
+
+    #[test]
+    fn test_match_sites() {
+        let mut saved = BTreeMap::new();
+        saved.insert("example.com".to_string(), vec!["one-time codes".to_string()]);
+        saved.insert("shop.co.uk".to_string(), vec!["email alias".to_string()]);
+
+        let mut fake = breach("Fake", "example.com", "2024-01-01");
+        fake.is_fabricated = true;
+        let breaches = vec![
+            breach("Old", "example.com", "2015-06-01"),
+            breach("New", "login.example.com", "2023-02-10"),
+            breach("Other", "other.test", "2022-01-01"),
+            breach("Shop", "www.shop.co.uk", "2020-01-01"),
+            breach("NoDomain", "", "2021-01-01"),
+            fake,
+        ];
+        let warnings = match_sites(&breaches, &saved);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].site, "example.com");
+        assert_eq!(warnings[0].breaches.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), vec!["New", "Old"]);
+        assert_eq!(warnings[1].site, "shop.co.uk");
+        assert_eq!(warnings[1].sources, vec!["email alias"]);
+    }
+
+    #[test]
+    fn test_breach_list_parses() {
+        let body = r#"[{"Name":"Adobe","Title":"Adobe","Domain":"adobe.com","BreachDate":"2013-10-04",
+            "AddedDate":"2013-12-04T00:00:00Z","PwnCount":152445165,"DataClasses":["Email addresses","Passwords"],
+            "IsVerified":true,"IsFabricated":false,"IsSensitive":false,"IsRetired":false,"IsSpamList":false}]"#;
+        let breaches: Vec<Breach> = serde_json::from_str(body).unwrap();
+        assert_eq!(breaches[0].domain, "adobe.com");
+        assert_eq!(breaches[0].pwn_count, 152445165);
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = BreachCheckStore::new(dir.path().to_path_buf());
+        assert!(store.report().is_none());
+        store.set(BreachReport { checked_at: 1, sites_checked: 3, warnings: Vec::new() });
+        assert_eq!(BreachCheckStore::new(dir.path().to_path_buf()).report().map(|r| r.sites_checked), Some(3));
+        store.clear();
+        assert!(BreachCheckStore::new(dir.path().to_path_buf()).report().is_none());
+    }
+}
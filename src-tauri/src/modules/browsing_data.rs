@@ -0,0 +1,320 @@
+// Clear browsing data - granular wipe of local stores and the platform webview data store.
+//
+// History and closed tabs honour the time range. Site permissions (adblock
+// exceptions) carry no creation time and are always cleared.
+//
+// Platform data is coarser: the webviews only expose "delete cookie" and "clear
+// everything", so cookies are removed individually and cache/localStorage trigger
+// a full data store wipe (which also drops cookies). Neither can honour a time
+// range, so these types are refused for anything but "all time". They're cleared
+// in every persistent store: the profile's and each container's, through a hidden
+// webview when none of their tabs is open.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, State, WebviewBuilder, WebviewUrl};
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{self, check_caller, Caller, DataStore};
+use crate::state::{AppState, ClosedTab};
+
+/// Set once clear-on-exit has run; closing the main window and quitting both ask for it.
+static CLEARED_ON_EXIT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrowsingDataType {
+    History,
+    Cookies,
+    Cache,
+    LocalStorage,
+    SitePermissions,
+    ClosedTabs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeRange {
+    LastHour,
+    LastDay,
+    LastWeek,
+    LastFourWeeks,
+    AllTime,
+}
+
+impl TimeRange {
+    fn duration(&self) -> Option<Duration> {
+        match self {
+            Self::LastHour => Some(Duration::from_secs(3600)),
+            Self::LastDay => Some(Duration::from_secs(86400)),
+            Self::LastWeek => Some(Duration::from_secs(7 * 86400)),
+            Self::LastFourWeeks => Some(Duration::from_secs(28 * 86400)),
+            Self::AllTime => None,
+        }
+    }
+
+    /// Start of the range, or None for "all time".
+    pub fn since(&self, now: SystemTime) -> Option<SystemTime> {
+        self.duration().map(|d| now.checked_sub(d).unwrap_or(UNIX_EPOCH))
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ClearReport {
+    pub history_removed: usize,
+    pub closed_tabs_removed: usize,
+    pub cookies_removed: usize,
+    pub site_permissions_removed: usize,
+    pub webview_data_cleared: bool,
+}
+
+/// Cleared from the platform data store, which can only be cleared entirely.
+const ALL_TIME_ONLY: [BrowsingDataType; 3] =
+    [BrowsingDataType::Cookies, BrowsingDataType::Cache, BrowsingDataType::LocalStorage];
+
+/// Refuses ranges the platform data store can't honour, before anything is cleared.
+pub fn check_range(data_types: &HashSet<BrowsingDataType>, range: TimeRange) -> Result<(), String> {
+    if range != TimeRange::AllTime && ALL_TIME_ONLY.iter().any(|t| data_types.contains(t)) {
+        return Err("Cookies, cached files and site storage can only be cleared for all time".to_string());
+    }
+    Ok(())
+}
+
+/// Drop closed tabs that were closed within the range.
+pub fn clear_closed_tabs(closed: &mut VecDeque<ClosedTab>, since: Option<SystemTime>) -> usize {
+    let before = closed.len();
+    match since {
+        Some(since) => closed.retain(|t| t.closed_at < since),
+        None => closed.clear(),
+    }
+    before - closed.len()
+}
+
+fn to_unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// The persistent stores tabs keep cookies and site storage in: the profile's and
+/// each container's.
+fn data_stores(state: &AppState) -> Vec<DataStore> {
+    std::iter::once(DataStore::Profile)
+        .chain(state.containers.data().containers.into_iter().map(|c| DataStore::Container(c.id)))
+        .collect()
+}
+
+/// A webview using `store`: one of its tabs, or a hidden one opened for the job.
+/// The flag says whether it was opened here, so the caller closes it when done.
+fn webview_for(app: &AppHandle, state: &AppState, store: &DataStore) -> Result<(tauri::Webview, bool), String> {
+    let container = match store {
+        DataStore::Container(id) => Some(id.as_str()),
+        _ => None,
+    };
+    let label = state.tabs.lock().map_err(|e| e.to_string())?
+        .iter()
+        .find(|t| t.container.as_deref() == container)
+        .map(|t| t.webview_label.clone());
+    if let Some(webview) = label.and_then(|l| app.get_webview(&l)) {
+        return Ok((webview, false));
+    }
+
+    let window = app.get_window("main").ok_or("Main window not found")?;
+    let label = match container {
+        Some(id) => format!("clear-data-{}", id),
+        None => "clear-data".to_string(),
+    };
+    let url = WebviewUrl::External(url::Url::parse("about:blank").map_err(|e| e.to_string())?);
+    let builder = browsing_webview::with_data_store(app, WebviewBuilder::new(label, url), store);
+    let webview = window
+        .add_child(builder, PhysicalPosition::new(0, 0), PhysicalSize::new(0, 0))
+        .map_err(|e| e.to_string())?;
+    Ok((webview, true))
+}
+
+/// Clears the data store `webview` uses: everything, or only its cookies.
+fn clear_webview_data(webview: &tauri::Webview, full_wipe: bool, report: &mut ClearReport) -> Result<(), String> {
+    if full_wipe {
+        webview.clear_all_browsing_data().map_err(|e| e.to_string())?;
+        report.webview_data_cleared = true;
+        return Ok(());
+    }
+    let cookies = webview.cookies().map_err(|e| e.to_string())?;
+    for cookie in cookies {
+        if webview.delete_cookie(cookie).is_ok() {
+            report.cookies_removed += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Wipe the requested data types. Shared by the `clear_browsing_data` command and clear-on-exit.
+pub fn clear_browsing_data_logic(
+    app: &AppHandle,
+    state: &AppState,
+    data_types: &HashSet<BrowsingDataType>,
+    range: TimeRange,
+) -> Result<ClearReport, String> {
+    check_range(data_types, range)?;
+    let now = SystemTime::now();
+    let since = range.since(now);
+    let mut report = ClearReport::default();
+
+    println!("[ClearData] Clearing {:?} ({:?})", data_types, range);
+
+    if data_types.contains(&BrowsingDataType::History) {
+        report.history_removed = match since {
+            Some(since) => state.history.delete_range(to_unix_secs(since), u64::MAX),
+            None => {
+                let total = state.history.list(0, 0, None, None).total;
+                state.history.clear().map(|_| total)
+            }
+        }
+        .map_err(|e| e.to_string())?;
+        // Thumbnails show pages from the history
+        state.thumbnails.clear(since);
+    }
+
+    if data_types.contains(&BrowsingDataType::ClosedTabs) {
+        let mut closed = state.closed_tabs.lock().map_err(|e| e.to_string())?;
+        report.closed_tabs_removed = clear_closed_tabs(&mut closed, since);
+        let store = crate::modules::closed_tabs_store::ClosedTabsStore { tabs: closed.clone() };
+        store.save(app)?;
+    }
+
+    if data_types.contains(&BrowsingDataType::SitePermissions) {
+        report.site_permissions_removed = state.adblock.clear_exceptions();
+    }
+
+    // Platform webview data stores
+    let wants_full_wipe = data_types.contains(&BrowsingDataType::Cache)
+        || data_types.contains(&BrowsingDataType::LocalStorage);
+    let wants_cookies = data_types.contains(&BrowsingDataType::Cookies);
+
+    if wants_full_wipe || wants_cookies {
+        for store in data_stores(state) {
+            let (webview, opened) = match webview_for(app, state, &store) {
+                Ok(found) => found,
+                Err(e) => {
+                    eprintln!("[ClearData] No webview for {:?}, skipping its data: {}", store, e);
+                    continue;
+                }
+            };
+            let result = clear_webview_data(&webview, wants_full_wipe, &mut report);
+            if opened {
+                let _ = webview.close();
+            }
+            result?;
+        }
+    }
+
+    println!("[ClearData] Done: {:?}", report);
+    Ok(report)
+}
+
+/// What `Settings.clear_on_exit` wipes. Open tabs (the session) are kept.
+pub fn exit_data_types() -> HashSet<BrowsingDataType> {
+    [
+        BrowsingDataType::History,
+        BrowsingDataType::Cookies,
+        BrowsingDataType::Cache,
+        BrowsingDataType::ClosedTabs,
+    ].into_iter().collect()
+}
+
+/// Called while the main window closes and when the app is asked to quit (Cmd+Q,
+/// the app or Dock menu), whichever comes first. No-op unless `clear_on_exit` is set.
+pub fn clear_on_exit(app: &AppHandle, state: &AppState) {
+    if !state.settings.read().unwrap().clear_on_exit || CLEARED_ON_EXIT.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    println!("[ClearData] Clear on exit enabled");
+    if let Err(e) = clear_browsing_data_logic(app, state, &exit_data_types(), TimeRange::AllTime) {
+        eprintln!("[ClearData] Failed to clear data on exit: {}", e);
+    }
+}
+
+/// Tauri command: clear the selected data types within a time range.
+/// Async because reading cookies deadlocks WebView2 inside sync commands.
+#[tauri::command]
+pub async fn clear_browsing_data(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: State<'_, AppState>,
+    data_types: Vec<BrowsingDataType>,
+    range: TimeRange,
+) -> Result<ClearReport, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let data_types: HashSet<BrowsingDataType> = data_types.into_iter().collect();
+    check_range(&data_types, range).map_err(BrowserError::InvalidInput)?;
+    Ok(clear_browsing_data_logic(&app, &state, &data_types, range)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed_tab(id: &str, closed_at: SystemTime) -> ClosedTab {
+        ClosedTab {
+            id: id.to_string(),
+            title: id.to_string(),
+            url: format!("https://{}.com/", id),
+            favicon: None,
+            closed_at,
+            container: None,
+        }
+    }
+
+    #[test]
+    fn test_time_range_since() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        assert_eq!(TimeRange::LastHour.since(now), Some(now - Duration::from_secs(3600)));
+        assert_eq!(TimeRange::AllTime.since(now), None);
+        // Never underflows past the epoch
+        assert_eq!(TimeRange::LastFourWeeks.since(UNIX_EPOCH), Some(UNIX_EPOCH));
+    }
+
+    #[test]
+    fn test_check_range_refuses_partial_platform_clears() {
+        let types = |t: &[BrowsingDataType]| t.iter().copied().collect::<HashSet<_>>();
+        let local = types(&[BrowsingDataType::History, BrowsingDataType::ClosedTabs]);
+        assert!(check_range(&local, TimeRange::LastHour).is_ok());
+
+        for platform in ALL_TIME_ONLY {
+            let selected = types(&[BrowsingDataType::History, platform]);
+            assert!(check_range(&selected, TimeRange::LastDay).is_err());
+            assert!(check_range(&selected, TimeRange::AllTime).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_clear_closed_tabs_respects_range() {
+        let now = SystemTime::now();
+        let mut closed: VecDeque<ClosedTab> = vec![
+            closed_tab("old", now - Duration::from_secs(7200)),
+            closed_tab("recent", now - Duration::from_secs(60)),
+        ].into();
+
+        let removed = clear_closed_tabs(&mut closed, TimeRange::LastHour.since(now));
+        assert_eq!(removed, 1);
+        assert_eq!(closed[0].id, "old");
+
+        assert_eq!(clear_closed_tabs(&mut closed, None), 1);
+        assert!(closed.is_empty());
+    }
+
+    #[test]
+    fn test_exit_data_types() {
+        let types = exit_data_types();
+        assert!(types.contains(&BrowsingDataType::History));
+        assert!(types.contains(&BrowsingDataType::ClosedTabs));
+        // Site permissions are user choices, not browsing traces
+        assert!(!types.contains(&BrowsingDataType::SitePermissions));
+    }
+
+    #[test]
+    fn test_data_type_deserialize() {
+        let types: Vec<BrowsingDataType> = serde_json::from_str(r#"["history", "local_storage", "site_permissions"]"#).unwrap();
+        assert_eq!(types, vec![BrowsingDataType::History, BrowsingDataType::LocalStorage, BrowsingDataType::SitePermissions]);
+    }
+}
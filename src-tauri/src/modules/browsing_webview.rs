@@ -0,0 +1,793 @@
+// The setup every webview showing web pages gets: tabs, and the windows of their
+// own that links, private and guest browsing and site apps open in.
+//
+// `builder` wires up the page scripts, request and navigation handling (ad
+// blocking, HTTPS-Only, safe browsing, the offline switch...), the proxy and the
+// data store; `setup` applies what needs the created webview (WebView2 request
+// filter, Safari rule lists, gesture and cookie settings). Webviews that aren't
+// tabs (see `open_window`) get the same protections but no tab strip or toolbar
+// state. Guest windows get the default settings rather than the user's.
+//
+// Tauri doesn't check app commands against the capabilities, so any page in any
+// of these webviews can invoke any command. Commands that read or write user
+// data call `check_caller` first.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Manager, PhysicalPosition, WebviewBuilder, WebviewUrl, Window, WindowEvent, Wry};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::block_stats::{self, Protection};
+#[cfg(not(windows))]
+use crate::modules::cookie_policy;
+use crate::modules::text_input::{self, TextInputSettings};
+use crate::modules::touch::{self, TouchSettings};
+use crate::modules::{
+    annotations, audio_output, channel_blocking, console_log, containers, context_menu, data_saver, dock,
+    external_protocols, filter_subscribe, fingerprint, form_audit, https_only, image_blocking, link_hover,
+    media_controls, network_log, offline, profile, safebrowsing, screen_capture, site_search, tab_audio, tab_status,
+    tab_windows, text_replace, thumbnails, totp, tracking_params, user_agent, webauthn,
+};
+use crate::settings::Settings;
+use crate::state::AppState;
+
+/// Where a browsing webview keeps its cookies and site storage.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataStore {
+    Profile,
+    Container(String), // See modules::containers
+    Private,           // Non-persistent, gone when the webview closes
+    Guest(PathBuf),    // Non-persistent, in a data directory of its own (see modules::guest)
+}
+
+impl DataStore {
+    pub fn is_private(&self) -> bool {
+        matches!(self, DataStore::Private | DataStore::Guest(_))
+    }
+}
+
+/// The browser's own UI webviews. Detached tabs' toolbars (see modules::tab_windows)
+/// count too; everything else may be showing web content.
+const UI_LABELS: &[&str] = &["main", "settings", "task-manager", "suggestion", "find", "dropdown"];
+const SETTINGS_LABEL: &str = "settings";
+
+/// Who may call a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Caller {
+    Settings,  // The settings window only
+    BrowserUi, // Any of the browser's own UI webviews
+    AppPage,   // Whatever webview is showing one of our pages, e.g. an interstitial in a tab
+}
+
+pub fn is_ui_webview(label: &str) -> bool {
+    UI_LABELS.contains(&label) || tab_windows::tab_of_window(label).is_some()
+}
+
+fn allows(caller: Caller, label: &str, url: Option<&Url>) -> bool {
+    match caller {
+        Caller::Settings => label == SETTINGS_LABEL,
+        Caller::BrowserUi => is_ui_webview(label),
+        Caller::AppPage => url.is_some_and(https_only::is_app_page),
+    }
+}
+
+/// Refuses the command unless `webview` is one `caller` allows.
+pub fn check_caller(webview: &tauri::Webview, caller: Caller) -> Result<(), BrowserError> {
+    let url = webview.url().ok();
+    if !allows(caller, webview.label(), url.as_ref()) {
+        return Err(BrowserError::NotAllowed("Not allowed from this webview".to_string()));
+    }
+    Ok(())
+}
+
+// Initial script to track focus and clicks
+const FOCUS_INJECTION_SCRIPT: &str = r#"
+(function() {
+    window.addEventListener('focus', () => {
+        window.__TAURI__.event.emit('webview-focus', { focused: true });
+    });
+    window.addEventListener('blur', () => {
+        window.__TAURI__.event.emit('webview-focus', { focused: false });
+    });
+    window.addEventListener('click', () => {
+        window.__TAURI__.event.emit('webview-focus', { focused: true });
+    });
+})();
+"#;
+
+/// A webview for `url` with everything a tab gets. `open_new` takes the links the
+/// page opens in a new window; `navigation` can refuse navigations before the
+/// usual checks run.
+pub fn builder<F, N>(
+    app: &AppHandle,
+    settings: &Settings,
+    label: &str,
+    url: Url,
+    store: &DataStore,
+    open_new: F,
+    navigation: N,
+) -> WebviewBuilder<Wry>
+where
+    F: Fn(&AppHandle, String) + Send + Sync + 'static,
+    N: Fn(&Url) -> bool + Send + Sync + 'static,
+{
+    let state = app.state::<AppState>();
+
+    // --- SECURITY & FINGERPRINTING CONFIGURATION ---
+
+    // 1. User Agent: the engine the platform really runs, with per-site compatibility
+    // overrides and client hints settings (see modules::user_agent)
+    let navigator_script = user_agent::navigator_script(settings);
+
+    // 2. Anti-Fingerprinting Script (see modules::fingerprint)
+    // Hides the 'webdriver' property and populates plugins to look "human", as far as
+    // the global and per-site spoofing profiles allow.
+    let anti_bot_script = fingerprint::anti_bot_script(settings.spoofing_profile, &settings.site_settings);
+
+    // 3. Title Sync Listener
+    const TITLE_LISTENER_SCRIPT: &str = r#"
+        (function() {
+            const invoke = window.__TAURI__.core.invoke;
+            let lastSentTitle = null;
+
+            function sendTitle() {
+                const current = document.title;
+                if (current && current !== lastSentTitle) {
+                    lastSentTitle = current;
+                    invoke('handle_title_change', { title: current });
+                }
+            }
+
+            // 1. Send immediately
+            sendTitle();
+
+            // 2. Observe <head> for changes (covers <title> text updates and replacement)
+            const target = document.querySelector('head') || document.documentElement;
+            new MutationObserver(sendTitle).observe(target, { subtree: true, childList: true, characterData: true });
+        })();
+    "#;
+
+    // 4. Favicon Sync Listener
+    const FAVICON_LISTENER_SCRIPT: &str = r#"
+        (function() {
+            const invoke = window.__TAURI__.core.invoke;
+            let lastFavicon = "";
+
+            function getFavicon() {
+                let link = document.querySelector("link[rel*='icon']");
+                return link ? link.href : "";
+            }
+
+            function sendFavicon() {
+                const current = getFavicon();
+                if (current && current !== lastFavicon) {
+                    lastFavicon = current;
+                    invoke('handle_favicon_change', { favicon: current });
+                }
+            }
+
+            sendFavicon();
+            
+            // Observe head for changes to link tags
+            new MutationObserver(sendFavicon).observe(
+                document.querySelector('head') || document.documentElement, 
+                { subtree: true, childList: true, attributes: true }
+            );
+        })();
+    "#;
+
+    // 1. Setup Webview Builder
+    let mut builder = WebviewBuilder::new(label, WebviewUrl::External(url))
+        .initialization_script(&anti_bot_script)
+        .initialization_script(&navigator_script)
+        .initialization_script(FOCUS_INJECTION_SCRIPT)
+        .initialization_script(TITLE_LISTENER_SCRIPT)
+        .initialization_script(FAVICON_LISTENER_SCRIPT)
+        .initialization_script(&state.devtools.get_bootstrapper())
+        .initialization_script(
+            r#"
+        // SPA History Hook & Security Hardening
+        (function() {
+            const invoke = window.__TAURI__.core.invoke;
+            const originalPushState = history.pushState;
+            const originalReplaceState = history.replaceState;
+
+            history.pushState = function() {
+                originalPushState.apply(this, arguments);
+                invoke('spa_navigate', { url: window.location.href });
+            };
+
+            history.replaceState = function() {
+                originalReplaceState.apply(this, arguments);
+                invoke('spa_navigate', { url: window.location.href });
+            };
+
+            window.addEventListener('popstate', () => {
+                invoke('spa_navigate', { url: window.location.href });
+            });
+            window.addEventListener('hashchange', () => {
+                invoke('spa_navigate', { url: window.location.href });
+            });
+            
+             window.addEventListener('pointerdown', () => {
+                invoke('content_pointer_down', {});
+            }, true);
+        })();
+    "#,
+        );
+    if let Some(default_user_agent) = user_agent::default_user_agent() {
+        builder = builder.user_agent(&default_user_agent);
+    }
+    builder = with_data_store(app, builder, store);
+
+    // 2. target="_blank" Handler (Window Open)
+    // This intercepts window.open() and <a target="_blank"> requests.
+    let app_handle_for_open = app.clone();
+    let label_for_open = label.to_string();
+    let open_new = Arc::new(open_new);
+    builder = builder.on_new_window(move |initial_url, _features| {
+        println!("[Tabs] Intercepted new window request for: {:?}", initial_url);
+
+        let handle = app_handle_for_open.clone();
+        let url_string = initial_url.to_string();
+
+        // Popups the filter lists know ($popup rules, mostly pop-under ads) don't get opened
+        if let Some(state) = handle.try_state::<AppState>() {
+            let source = {
+                let tabs = state.tabs.lock().unwrap();
+                tabs.iter().find(|t| t.webview_label == label_for_open).map(|t| t.url.clone())
+            }
+            .or_else(|| handle.get_webview(&label_for_open).and_then(|w| w.url().ok()).map(|u| u.to_string()));
+            let blocked = state.settings.read().unwrap().block_trackers
+                && source.is_some_and(|s| state.adblock.should_block_request(&url_string, &s, "popup"));
+            if blocked {
+                println!("[AdBlock] Blocked popup: {}", url_string);
+                state.block_stats.record_protection(Protection::PopupBlocked);
+                return tauri::webview::NewWindowResponse::Deny;
+            }
+        }
+
+        let open_new = open_new.clone();
+        tauri::async_runtime::spawn(async move { open_new(&handle, url_string) });
+
+        // Never a native window: `open_new` decides where it goes
+        tauri::webview::NewWindowResponse::Deny
+    });
+
+    // --- Ad Blocking: Cosmetic Filter Injection Script ---
+    // This script runs at document_start. Uses safer generic hiding.
+    const COSMETIC_FILTER_SCRIPT: &str = r#"
+        (function() {
+            // Webmail domains to skip generic cosmetic filtering
+            const WEBMAIL_DOMAINS = ['mail.google.com', 'gmail.com'];
+
+            // Skip generic hiding on webmail
+            const hostname = window.location.hostname;
+            const isWebmail = WEBMAIL_DOMAINS.some(domain =>
+                hostname === domain || hostname.endsWith('.' + domain)
+            );
+
+            if (isWebmail) {
+                console.log('[AdBlock] Generic cosmetic filters disabled for webmail');
+
+                // Still listen for site-specific rules (backend will return empty for webmail)
+                if (window.__TAURI__) {
+                    window.__TAURI__.core.invoke('get_cosmetic_rules', { url: window.location.href });
+                    window.__TAURI__.event.listen('apply-cosmetic-css', (event) => {
+                        // No-op for webmail
+                    });
+                }
+                return;
+            }
+
+            // Safer Generic Hiding: Targets high-confidence ad containers only
+            const style = document.createElement('style');
+            style.id = 'sovereign-generic-hiding';
+            style.textContent = `
+                [id^="google_ads_iframe"], [id^="taboola-"], [id^="outbrain-"],
+                [class^="ad-container-"], .pub_300x250, .pub_728x90, .text-ad-links
+                { display: none !important; }
+            `;
+            (document.head || document.documentElement).appendChild(style);
+
+            // Async: Request specific rules
+            if (window.__TAURI__) {
+                window.__TAURI__.core.invoke('get_cosmetic_rules', { url: window.location.href });
+
+                window.__TAURI__.event.listen('apply-cosmetic-css', (event) => {
+                    const css = event.payload.css;
+                    if (!css) return;
+                    const specificStyle = document.createElement('style');
+                    specificStyle.id = 'sovereign-site-hiding';
+                    specificStyle.textContent = css;
+                    (document.head || document.documentElement).appendChild(specificStyle);
+                });
+            }
+        })();
+    "#;
+
+    builder = builder.initialization_script(COSMETIC_FILTER_SCRIPT);
+
+    // --- Network log: Resource Timing for every page (see modules::network_log) ---
+    builder = builder.initialization_script(network_log::TIMING_SCRIPT);
+
+    // --- Screen capture: getDisplayMedia asks through the source picker ---
+    builder = builder.initialization_script(screen_capture::PAGE_SCRIPT);
+
+    // --- Console capture: forwards page console output (see modules::console_log) ---
+    builder = builder.initialization_script(console_log::CONSOLE_SCRIPT);
+
+    // --- Passkeys: fail clearly where WebAuthn can't work (see modules::webauthn) ---
+    builder = builder.initialization_script(&webauthn::page_script());
+
+    // --- Ad Blocking: Network Request Interception ---
+    // This is the hot path - fires for every resource (images, scripts, etc.)
+    #[cfg(not(target_os = "macos"))]
+    let app_handle_for_adblock = app.clone();
+    #[cfg(not(target_os = "macos"))]
+    let label_for_adblock = label.to_string();
+
+    builder = builder.on_web_resource_request(move |_request, _response| {
+        // OPTIMIZATION: On macOS, WKContentRuleList handles blocking efficiently.
+        // Skip the Rust check to improve performance.
+        #[cfg(target_os = "macos")]
+        {
+            return;
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            let url = _request.uri().to_string();
+
+            // Frame context: the issuing frame from Referer/Origin, the type from Sec-Fetch-Dest
+            let headers = _request.headers();
+            let header = |name: &'static str| headers.get(name).and_then(|v| v.to_str().ok());
+            let frame_headers = crate::modules::frames::FrameHeaders {
+                referer: header("Referer"),
+                origin: header("Origin"),
+                fetch_dest: header("Sec-Fetch-Dest"),
+            };
+
+            // Check AdBlockManager (Windows/Linux only)
+            if let Some(state) = app_handle_for_adblock.try_state::<AppState>() {
+                let settings = state.settings.read().unwrap();
+                let ctx = state.frames.context(&label_for_adblock, &url, frame_headers, None);
+                let content_length = _response
+                    .headers()
+                    .get(http::header::CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok());
+                state.network_log.record_request(
+                    &label_for_adblock,
+                    &url,
+                    _request.method().as_str(),
+                    ctx.as_ref().map_or("other", |ctx| ctx.request_type.as_str()),
+                    content_length,
+                );
+                let blocked = settings.block_trackers
+                    && ctx.as_ref().is_some_and(|ctx| {
+                        state.adblock.should_block_in_frame(&url, &ctx.frame_url, &ctx.top_url, &ctx.request_type)
+                    });
+                if blocked {
+                    println!("[AdBlock] Blocked: {}", url);
+                    block_stats::record_block(&app_handle_for_adblock, &state, &label_for_adblock, &url);
+                    state.network_log.mark_blocked(&label_for_adblock, &url);
+                    *_response.status_mut() = http::StatusCode::FORBIDDEN;
+                    *_response.body_mut() = std::borrow::Cow::Borrowed(b"Blocked by Sovereign Browser");
+                    return;
+                }
+
+                // Image blocking, global or per site
+                if ctx.as_ref().is_some_and(|ctx| {
+                    ctx.request_type == "image" && state.image_blocker.should_block(&url, &ctx.frame_url)
+                }) {
+                    state.network_log.mark_blocked(&label_for_adblock, &url);
+                    *_response.status_mut() = http::StatusCode::FORBIDDEN;
+                    *_response.body_mut() = std::borrow::Cow::Borrowed(b"");
+                    return;
+                }
+
+                // Data saver: media, and images announcing a large body
+                if let Some(saved) = ctx.as_ref().and_then(|ctx| {
+                    data_saver::should_refuse(&state, &settings, &label_for_adblock, &url, ctx, content_length)
+                }) {
+                    state.block_stats.record_saved(&label_for_adblock, saved);
+                    state.network_log.mark_blocked(&label_for_adblock, &url);
+                    *_response.status_mut() = http::StatusCode::FORBIDDEN;
+                    _response.headers_mut().remove(http::header::CONTENT_LENGTH);
+                    *_response.body_mut() = std::borrow::Cow::Borrowed(b"");
+                    return;
+                }
+                // Third-party cookies are kept off requests elsewhere: this hook can't
+                // rewrite the request (see cookie_policy)
+            }
+        }
+    });
+
+    // --- Page Load Tracking: keeps loading/URL state for tab-status snapshots ---
+    let app_handle_for_load = app.clone();
+    builder = builder.on_page_load(move |webview, payload| {
+        let Some(state) = app_handle_for_load.try_state::<AppState>() else {
+            return;
+        };
+        let started = matches!(payload.event(), PageLoadEvent::Started);
+        if started {
+            state.block_stats.clear_tab(webview.label());
+            state.frames.set_top(webview.label(), payload.url().as_str());
+            state.media_controls.forget_webview(webview.label());
+            state.network_log.clear_tab(webview.label());
+            state.permissions.forget_webview(webview.label());
+            state.read_aloud.forget_webview(webview.label());
+            user_agent::on_page_started(&webview, &state, payload.url());
+        } else {
+            audio_output::on_page_finished(&webview, &state);
+            media_controls::on_page_finished(&webview, &state, payload.url());
+            tab_audio::on_page_finished(&webview, &state);
+            if state.devtools.is_inspected(webview.label()) {
+                // The new page reconnects to the open inspector
+                let _ = webview.eval(&state.devtools.loader_script(webview.label()));
+            }
+        }
+
+        // Tabs only: windows of their own have no tab strip or toolbar
+        let tab_id = {
+            let mut tabs = state.tabs.lock().unwrap();
+            tabs.iter_mut().find(|t| t.webview_label == webview.label()).map(|tab| {
+                tab.url = https_only::page_url(payload.url());
+                tab.is_loading = started;
+                if started {
+                    tab.is_audible = false;
+                    tab.load_error = None;
+                }
+                tab.id.clone()
+            })
+        };
+        if let Some(id) = tab_id {
+            tab_status::emit_tab_status(&app_handle_for_load, &state, &id);
+            if started {
+                block_stats::emit_blocked_count(&app_handle_for_load, &id, 0);
+                state.maintenance.touch();
+            } else {
+                thumbnails::on_page_finished(&app_handle_for_load, &webview, &state, payload.url());
+            }
+        }
+    });
+
+    // --- DNS-over-HTTPS / proxy settings: upstream proxy or the local proxy ---
+    if let Some(proxy_url) = state.doh.proxy_url() {
+        builder = builder.proxy_url(proxy_url);
+    }
+
+    // --- Fingerprinting: per-site canvas/audio/WebGL noise ---
+    if settings.fingerprint_protection {
+        builder = builder.initialization_script(&fingerprint::noise_script(
+            &state.fingerprint_secret,
+            &settings.fingerprint_exceptions,
+            settings.limit_font_detection,
+        ));
+    }
+
+    // --- Per-site timezone and locale overrides ---
+    if let Some(script) = fingerprint::region_script(&settings.site_settings) {
+        builder = builder.initialization_script(&script);
+    }
+
+    // --- Media controls: per-site playback speed, skip and loop ---
+    builder = builder.initialization_script(&media_controls::page_script(&settings.site_settings));
+
+    // --- Audio output: setSinkId routing to the chosen device ---
+    builder = builder.initialization_script(&audio_output::page_script(settings.audio_output.as_deref()));
+
+    // --- Touch: pinch zoom off on touchscreens (Linux; elsewhere it's a webview setting) ---
+    #[cfg(target_os = "linux")]
+    if !settings.pinch_zoom {
+        builder = builder.initialization_script(&touch::pinch_script(false));
+    }
+
+    // --- Tab audio: speaker indicator and (on Linux) muting ---
+    builder = builder.initialization_script(tab_audio::AUDIO_SCRIPT);
+
+    // --- Text replacement: the user's find-and-replace rules for the site ---
+    builder = builder.initialization_script(text_replace::REPLACER_SCRIPT);
+
+    // --- Form audit: hold forms posting to other sites or over http for a warning ---
+    if settings.form_audit {
+        builder = builder.initialization_script(form_audit::AUDIT_SCRIPT);
+    }
+
+    // --- One-time codes: offer to fill 2FA fields from the local vault ---
+    if settings.totp_autofill {
+        builder = builder.initialization_script(totp::DETECT_SCRIPT);
+    }
+
+    // --- Annotations: re-mark saved highlights and take new ones ---
+    builder = builder.initialization_script(annotations::HIGHLIGHT_SCRIPT);
+
+    // --- Context menu: the native page menu with browser actions ---
+    builder = builder.initialization_script(context_menu::PAGE_SCRIPT);
+
+    // --- Site search: learn the searches of sites for tab-to-search ---
+    builder = builder.initialization_script(site_search::DETECT_SCRIPT);
+
+    // --- Link hover: where the link under the pointer goes, for the toolbar ---
+    builder = builder.initialization_script(link_hover::HOVER_SCRIPT);
+
+    // --- Tracking parameters: cleaned from links as they're clicked ---
+    if settings.strip_tracking_params {
+        builder = builder.initialization_script(&tracking_params::link_cleaner_script());
+    }
+
+    // --- Ad Blocking: WebSockets and service workers ---
+    if settings.block_trackers {
+        builder = builder.initialization_script(channel_blocking::SERVICE_WORKER_GUARD_SCRIPT);
+        // WKContentRuleList doesn't report what it blocks; estimate it from the page
+        #[cfg(target_os = "macos")]
+        {
+            builder = builder.initialization_script(block_stats::SAFARI_COUNTER_SCRIPT);
+        }
+        // Safari rules already cover ws:// and wss://
+        #[cfg(not(target_os = "macos"))]
+        {
+            builder = builder.initialization_script(channel_blocking::WEBSOCKET_GUARD_SCRIPT);
+        }
+    }
+
+    // --- Image blocking: drop <img> sources on pages that block images ---
+    if let Some(script) = image_blocking::guard_script(settings) {
+        builder = builder.initialization_script(&script);
+    }
+
+    // --- Data saver: lazy loading, no media preloading, image placeholders ---
+    let data_saver_on = state.data_saver.is_active(settings.data_saver);
+    if data_saver_on {
+        builder = builder.initialization_script(&data_saver::page_script(settings));
+    }
+
+    // --- Navigation interception: the caller's own check, the offline switch, abp: subscription
+    // links, threat warnings, then HTTPS-Only upgrades ---
+    let app_handle_for_nav = app.clone();
+    let label_for_nav = label.to_string();
+    builder = builder.on_navigation(move |url| {
+        navigation(url)
+            && offline::on_navigation(&app_handle_for_nav, url)
+            && filter_subscribe::on_navigation(&app_handle_for_nav, url)
+            && external_protocols::on_navigation(&app_handle_for_nav, &label_for_nav, url)
+            && safebrowsing::on_navigation(&app_handle_for_nav, &label_for_nav, url)
+            && containers::on_navigation(&app_handle_for_nav, &label_for_nav, url)
+            && https_only::on_navigation(&app_handle_for_nav, &label_for_nav, url)
+            && user_agent::on_navigation(&app_handle_for_nav, &label_for_nav, url)
+    });
+
+    // --- Downloads: counted for the Dock/taskbar icon ---
+    builder = builder.on_download(|webview, event| dock::on_download(&webview, &event));
+
+    builder
+}
+
+/// Points `builder` at the cookies and site storage of `store`. Named profiles and
+/// containers keep their own.
+pub fn with_data_store(app: &AppHandle, mut builder: WebviewBuilder<Wry>, store: &DataStore) -> WebviewBuilder<Wry> {
+    let data_dir = match store {
+        DataStore::Profile => profile::webview_data_dir(app),
+        DataStore::Container(id) => containers::webview_data_dir(app, id),
+        DataStore::Private => None,
+        // macOS has no data directories; the store is non-persistent anyway
+        DataStore::Guest(dir) => (!cfg!(target_os = "macos")).then(|| dir.clone()),
+    };
+    if let Some(data_dir) = data_dir {
+        builder = builder.data_directory(data_dir);
+    }
+    #[cfg(target_os = "macos")]
+    let identifier = match store {
+        DataStore::Profile => profile::store_identifier(),
+        DataStore::Container(id) => Some(containers::store_identifier(id)),
+        DataStore::Private | DataStore::Guest(_) => None,
+    };
+    #[cfg(target_os = "macos")]
+    if let Some(identifier) = identifier {
+        builder = builder.data_store_identifier(identifier);
+    }
+    builder.incognito(store.is_private())
+}
+
+/// The rest of the setup, once the webview from `builder` is created.
+#[cfg_attr(not(windows), allow(unused_variables))]
+pub fn setup(app: &AppHandle, state: &AppState, settings: &Settings, webview: &tauri::Webview) {
+    touch::apply(webview, TouchSettings::from_settings(settings));
+    text_input::apply(webview, TextInputSettings::from_settings(settings));
+    offline::apply(webview, state);
+
+    // WebView2 request filter, including service worker and WebSocket traffic
+    #[cfg(windows)]
+    channel_blocking::install_webview2_filter(webview, app.clone());
+
+    // Apply content blocking rules on macOS
+    #[cfg(target_os = "macos")]
+    if settings.block_trackers {
+        let rules = state.adblock.get_safari_rules();
+        if rules.len() > 2 {
+            apply_content_blocking_rules(webview, "SovereignBrowserAdBlock", &rules);
+        }
+    }
+
+    // Image blocking on macOS
+    #[cfg(target_os = "macos")]
+    if let Some(rules) = image_blocking::safari_rules(settings.block_images, &settings.site_settings) {
+        apply_content_blocking_rules(webview, image_blocking::safari_rule_list_id(), &rules);
+    }
+
+    // Data saver media blocking on macOS
+    #[cfg(target_os = "macos")]
+    if state.data_saver.is_active(settings.data_saver) {
+        let rules = data_saver::safari_rules(&settings.data_saver_exceptions);
+        apply_content_blocking_rules(webview, data_saver::safari_rule_list_id(), &rules);
+    }
+
+    // Third-party cookie blocking on macOS (WebKit enforces it per load)
+    #[cfg(target_os = "macos")]
+    if settings.block_third_party_cookies {
+        let rules = cookie_policy::safari_rules(&settings.third_party_cookie_exceptions);
+        apply_content_blocking_rules(webview, cookie_policy::safari_rule_list_id(), &rules);
+    }
+
+    // Third-party cookie blocking on Linux (WebKitGTK's policy, shared by all webviews)
+    #[cfg(target_os = "linux")]
+    cookie_policy::apply_accept_policy(webview, settings.block_third_party_cookies);
+}
+
+/// Opens `url` in a window of its own, set up like a tab (see `builder`).
+pub fn open_window<F, N>(
+    app: &AppHandle,
+    label: &str,
+    title: &str,
+    url: Url,
+    store: DataStore,
+    open_new: F,
+    navigation: N,
+) -> Result<Window, String>
+where
+    F: Fn(&AppHandle, String) + Send + Sync + 'static,
+    N: Fn(&Url) -> bool + Send + Sync + 'static,
+{
+    let state = app.try_state::<AppState>().ok_or("App state not ready")?;
+    let settings = match store {
+        DataStore::Guest(_) => Settings::default(),
+        _ => state.settings.read().unwrap().clone(),
+    };
+    let window = tauri::window::WindowBuilder::new(app, label)
+        .title(title)
+        .inner_size(1024.0, 768.0)
+        .focused(true)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let builder = builder(app, &settings, label, url, &store, open_new, navigation);
+    let webview = window.add_child(builder, PhysicalPosition::new(0, 0), size).map_err(|e| e.to_string())?;
+    // Follows the window as it's resized
+    webview.set_auto_resize(true).map_err(|e| e.to_string())?;
+    setup(app, &state, &settings, &webview);
+
+    let app = app.clone();
+    let label = label.to_string();
+    window.on_window_event(move |event| {
+        if let (WindowEvent::Destroyed, Some(state)) = (event, app.try_state::<AppState>()) {
+            state.frames.forget_webview(&label);
+            state.network_log.clear_tab(&label);
+            state.block_stats.clear_tab(&label);
+        }
+    });
+    Ok(window)
+}
+
+/// Apply Safari-compatible content blocking rules to a WKWebView.
+/// This blocks network requests at the WebKit level, not just hides elements.
+#[cfg(target_os = "macos")]
+pub fn apply_content_blocking_rules(webview: &tauri::Webview, identifier: &str, rules_json: &str) {
+    use block::ConcreteBlock;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+
+    // Convert Rust string to NSString
+    fn to_nsstring(s: &str) -> *mut Object {
+        unsafe {
+            let ns_string_class = class!(NSString);
+            let string_c = CString::new(s).unwrap_or_else(|_| CString::new("").unwrap());
+            let ns_string: *mut Object = msg_send![ns_string_class, alloc];
+            let ns_string: *mut Object = msg_send![ns_string, initWithUTF8String: string_c.as_ptr()];
+            ns_string
+        }
+    }
+
+    let rules = rules_json.to_string();
+    let identifier = identifier.to_string();
+
+    unsafe {
+        let webview_result = webview.with_webview(move |platform_webview| {
+            let wk_webview = platform_webview.inner() as *mut Object;
+
+            // Get WKContentRuleListStore.defaultStore
+            let store_class = class!(WKContentRuleListStore);
+            let store: *mut Object = msg_send![store_class, defaultStore];
+
+            if store.is_null() {
+                println!("[AdBlock] WKContentRuleListStore.defaultStore is null");
+                return;
+            }
+
+            // Get the WKUserContentController from the webview's configuration
+            let config: *mut Object = msg_send![wk_webview, configuration];
+            let user_content_controller: *mut Object = msg_send![config, userContentController];
+
+            // Create rule identifier and rules NSString
+            let identifier = to_nsstring(&identifier);
+            let rules_ns = to_nsstring(&rules);
+
+            // Store the user content controller pointer for the completion block
+            let ucc = user_content_controller;
+
+            // Create completion block for compileContentRuleListForIdentifier:encodedContentRuleList:completionHandler:
+            let completion_block = ConcreteBlock::new(move |rule_list: *mut Object, error: *mut Object| {
+                if error.is_null() && !rule_list.is_null() {
+                    println!("[AdBlock] Content rule list compiled successfully!");
+                    // Add the compiled rule list to the user content controller
+                    let _: () = msg_send![ucc, addContentRuleList: rule_list];
+                    println!("[AdBlock] Content blocking rules applied to webview!");
+                } else {
+                    if !error.is_null() {
+                        let description: *mut Object = msg_send![error, localizedDescription];
+                        let utf8: *const std::os::raw::c_char = msg_send![description, UTF8String];
+                        if !utf8.is_null() {
+                            let error_str = std::ffi::CStr::from_ptr(utf8).to_string_lossy();
+                            println!("[AdBlock] Failed to compile content rules: {}", error_str);
+                        }
+                    } else {
+                        println!("[AdBlock] Failed to compile content rules: unknown error");
+                    }
+                }
+            });
+            let completion_block = completion_block.copy();
+
+            // Call compileContentRuleListForIdentifier:encodedContentRuleList:completionHandler:
+            println!("[AdBlock] Compiling content blocking rules ({} chars)...", rules.len());
+            let _: () = msg_send![store, compileContentRuleListForIdentifier:identifier 
+                                        encodedContentRuleList:rules_ns 
+                                        completionHandler:&*completion_block];
+        });
+
+        if let Err(e) = webview_result {
+            println!("[AdBlock] Failed to access webview: {:?}", e);
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn apply_content_blocking_rules(_webview: &tauri::Webview, _identifier: &str, _rules_json: &str) {
+    // No-op for Windows/Linux - they may use different mechanisms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        let page = Url::parse("https://example.com/").unwrap();
+        let app_page = Url::parse("tauri://localhost/https-interstitial.html").unwrap();
+
+        assert!(allows(Caller::Settings, "settings", None));
+        assert!(!allows(Caller::Settings, "main", None));
+
+        assert!(allows(Caller::BrowserUi, "main", None));
+        assert!(allows(Caller::BrowserUi, "tab-window-tab-1", None));
+        let web_content = ["webview-tab-1", "window-1", "private-window-1", "guest-window-1", "site-app-1", "devtools-tab-1"];
+        for label in web_content {
+            assert!(!allows(Caller::BrowserUi, label, Some(&page)), "{}", label);
+        }
+
+        assert!(allows(Caller::AppPage, "webview-tab-1", Some(&app_page)));
+        assert!(!allows(Caller::AppPage, "webview-tab-1", Some(&page)));
+    }
+}
@@ -0,0 +1,353 @@
+// Blocking for request channels the regular interception misses: WebSockets and
+// service workers.
+//
+// - macOS: WKContentRuleList covers ws:// and wss:// once `with_websocket_variants`
+//   adds scheme-specific copies of rules that are anchored to http(s).
+// - Windows: a WebView2 WebResourceRequested filter that includes service worker
+//   and WebSocket requests checks each one against AdBlockManager (and the image
+//   blocking and data saver rules, which share the filter). The filter also
+//   strips the Cookie header from third-party requests (see cookie_policy).
+// - Linux (and as a fallback on Windows): an injected guard holds each new WebSocket
+//   until `check_websocket` clears it, so the handshake never reaches a blocked host.
+//
+// On every platform, service worker registration is refused when the worker script
+// itself would be blocked; init scripts don't run inside workers, so that's the only
+// hook on macOS and Linux.
+
+use serde_json::Value;
+
+use crate::error::BrowserError;
+use crate::modules::block_stats;
+use crate::modules::frames::FrameHeaders;
+use crate::state::AppState;
+
+/// Replaces `window.WebSocket` with a wrapper that connects only after the backend
+/// clears the URL. Sending before the socket opens throws, as it does natively.
+pub const WEBSOCKET_GUARD_SCRIPT: &str = r#"
+(function() {
+    const NativeWebSocket = window.WebSocket;
+    if (!NativeWebSocket || !window.__TAURI__) return;
+    const invoke = window.__TAURI__.core.invoke;
+
+    class WebSocket extends EventTarget {
+        constructor(url, protocols) {
+            super();
+            this._url = new URL(url, location.href).href;
+            this._ws = null;
+            this._closed = false;
+            this._binaryType = 'blob';
+            this.onopen = this.onmessage = this.onerror = this.onclose = null;
+
+            invoke('check_websocket', { url: this._url, pageUrl: location.href })
+                .catch(() => false)
+                .then(blocked => {
+                    if (this._closed) return;
+                    if (blocked) {
+                        console.warn('[AdBlock] Blocked WebSocket:', this._url);
+                        this._closed = true;
+                        this._emit(new Event('error'));
+                        this._emit(new CloseEvent('close', { code: 1006, wasClean: false }));
+                        return;
+                    }
+                    this._ws = protocols === undefined
+                        ? new NativeWebSocket(this._url)
+                        : new NativeWebSocket(this._url, protocols);
+                    this._ws.binaryType = this._binaryType;
+                    this._ws.addEventListener('open', () => this._emit(new Event('open')));
+                    this._ws.addEventListener('error', () => this._emit(new Event('error')));
+                    this._ws.addEventListener('message', e => this._emit(new MessageEvent('message', {
+                        data: e.data, origin: e.origin, lastEventId: e.lastEventId
+                    })));
+                    this._ws.addEventListener('close', e => this._emit(new CloseEvent('close', {
+                        code: e.code, reason: e.reason, wasClean: e.wasClean
+                    })));
+                });
+        }
+
+        _emit(event) {
+            this.dispatchEvent(event);
+            const handler = this['on' + event.type];
+            if (typeof handler === 'function') handler.call(this, event);
+        }
+
+        get url() { return this._url; }
+        get readyState() { return this._ws ? this._ws.readyState : (this._closed ? 3 : 0); }
+        get protocol() { return this._ws ? this._ws.protocol : ''; }
+        get extensions() { return this._ws ? this._ws.extensions : ''; }
+        get bufferedAmount() { return this._ws ? this._ws.bufferedAmount : 0; }
+        get binaryType() { return this._binaryType; }
+        set binaryType(value) {
+            this._binaryType = value;
+            if (this._ws) this._ws.binaryType = value;
+        }
+
+        send(data) {
+            if (!this._ws) throw new DOMException("Failed to execute 'send' on 'WebSocket': Still in CONNECTING state.", 'InvalidStateError');
+            this._ws.send(data);
+        }
+
+        close(code, reason) {
+            if (this._ws) {
+                this._ws.close(code, reason);
+            } else if (!this._closed) {
+                this._closed = true;
+                setTimeout(() => this._emit(new CloseEvent('close', { code: 1005, wasClean: false })));
+            }
+        }
+    }
+    for (const [name, value] of [['CONNECTING', 0], ['OPEN', 1], ['CLOSING', 2], ['CLOSED', 3]]) {
+        Object.defineProperty(WebSocket, name, { value });
+        Object.defineProperty(WebSocket.prototype, name, { value });
+    }
+    window.WebSocket = WebSocket;
+})();
+"#;
+
+/// Refuses service worker registrations whose script would be blocked.
+pub const SERVICE_WORKER_GUARD_SCRIPT: &str = r#"
+(function() {
+    const container = navigator.serviceWorker;
+    if (!container || !window.__TAURI__) return;
+    const invoke = window.__TAURI__.core.invoke;
+    const register = container.register.bind(container);
+    container.register = async function(scriptURL, options) {
+        const url = new URL(scriptURL, location.href).href;
+        const blocked = await invoke('check_service_worker', { url, pageUrl: location.href }).catch(() => false);
+        if (blocked) {
+            console.warn('[AdBlock] Blocked service worker:', url);
+            throw new DOMException('Service worker blocked by content blocker', 'SecurityError');
+        }
+        return register(scriptURL, options);
+    };
+})();
+"#;
+
+/// Adds, right after each Safari rule anchored to `^https?://`, a copy anchored to
+/// `^wss?://` so blocks and exceptions keep their order. Unanchored rules already
+/// match any scheme; rules limited to other resource types never apply to sockets.
+#[cfg(any(target_os = "macos", test))]
+pub fn with_websocket_variants(rules: Vec<Value>) -> Vec<Value> {
+    let mut out = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let variant = websocket_variant(&rule);
+        out.push(rule);
+        out.extend(variant);
+    }
+    out
+}
+
+#[cfg(any(target_os = "macos", test))]
+fn websocket_variant(rule: &Value) -> Option<Value> {
+    let trigger = &rule["trigger"];
+    // WebKit classifies WebSocket handshakes as "raw"
+    if let Some(types) = trigger["resource-type"].as_array() {
+        if !types.iter().any(|t| t == "raw") {
+            return None;
+        }
+    }
+    let rest = trigger["url-filter"].as_str()?.strip_prefix("^https?://")?;
+    let mut variant = rule.clone();
+    variant["trigger"]["url-filter"] = Value::String(format!("^wss?://{}", rest));
+    Some(variant)
+}
+
+/// `page_url` is the document (possibly an iframe) the guard script ran in.
+fn should_block(state: &AppState, webview_label: &str, url: &str, page_url: &str, request_type: &str) -> bool {
+    if !state.settings.read().unwrap().block_trackers {
+        return false;
+    }
+    let headers = FrameHeaders { referer: Some(page_url), ..Default::default() };
+    state
+        .frames
+        .context(webview_label, url, headers, Some(request_type))
+        .is_some_and(|ctx| state.adblock.should_block_in_frame(url, &ctx.frame_url, &ctx.top_url, &ctx.request_type))
+}
+
+#[tauri::command]
+pub fn check_websocket(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    page_url: String,
+) -> Result<bool, BrowserError> {
+    let blocked = should_block(&state, webview.label(), &url, &page_url, "websocket");
+    if blocked {
+        println!("[AdBlock] Blocked WebSocket: {}", url);
+        block_stats::record_block(&app, &state, webview.label(), &url);
+    }
+    Ok(blocked)
+}
+
+#[tauri::command]
+pub fn check_service_worker(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    page_url: String,
+) -> Result<bool, BrowserError> {
+    let blocked = should_block(&state, webview.label(), &url, &page_url, "script");
+    if blocked {
+        println!("[AdBlock] Blocked service worker: {}", url);
+        block_stats::record_block(&app, &state, webview.label(), &url);
+    }
+    Ok(blocked)
+}
+
+/// Routes every subresource request of a WebView2 webview, including those made by
+/// service workers and WebSocket handshakes, through AdBlockManager with its frame
+/// context (see `frames`).
+#[cfg(windows)]
+pub fn install_webview2_filter(webview: &tauri::Webview, app: tauri::AppHandle) {
+    use crate::modules::{cookie_policy, data_saver, offline};
+    use tauri::Manager;
+    use webview2_com::Microsoft::Web::WebView2::Win32::*;
+    use webview2_com::{take_pwstr, WebResourceRequestedEventHandler};
+    use windows::core::{w, Interface, PCWSTR, PWSTR};
+
+    // Used when Sec-Fetch-Dest is missing
+    fn request_type(context: COREWEBVIEW2_WEB_RESOURCE_CONTEXT) -> Option<&'static str> {
+        Some(match context {
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT => return None,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_STYLESHEET => "stylesheet",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_IMAGE => "image",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_MEDIA => "media",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FONT => "font",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_SCRIPT => "script",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_XML_HTTP_REQUEST => "xmlhttprequest",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FETCH => "fetch",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_WEBSOCKET => "websocket",
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_PING => "ping",
+            _ => "other",
+        })
+    }
+
+    let label = webview.label().to_string();
+    let result = webview.with_webview(move |platform| unsafe {
+        let Ok(core) = platform.controller().CoreWebView2() else {
+            return;
+        };
+        let environment = platform.environment();
+
+        // Source kinds (service workers, shared workers) need WebView2 1.0.2478+
+        let filtered = match core.cast::<ICoreWebView2_22>() {
+            Ok(core22) => core22.AddWebResourceRequestedFilterWithRequestSourceKinds(
+                w!("*"),
+                COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL,
+                COREWEBVIEW2_WEB_RESOURCE_REQUEST_SOURCE_KINDS_ALL,
+            ),
+            Err(_) => core.AddWebResourceRequestedFilter(w!("*"), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL),
+        };
+        if let Err(e) = filtered {
+            eprintln!("[AdBlock] Failed to add WebView2 request filter: {}", e);
+            return;
+        }
+
+        let handler = WebResourceRequestedEventHandler::create(Box::new(move |_sender, args| {
+            let Some(args) = args else {
+                return Ok(());
+            };
+            let mut context = COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL;
+            args.ResourceContext(&mut context)?;
+            let request = args.Request()?;
+            let mut uri = PWSTR::null();
+            request.Uri(&mut uri)?;
+            let url = take_pwstr(uri);
+
+            let headers = request.Headers()?;
+            let header = |name: PCWSTR| {
+                let mut value = PWSTR::null();
+                headers.GetHeader(name, &mut value).ok().map(|_| take_pwstr(value))
+            };
+            let referer = header(w!("Referer"));
+            let origin = header(w!("Origin"));
+            // Both top-level and frame documents are DOCUMENT; only Sec-Fetch-Dest tells them apart
+            let fetch_dest = header(w!("Sec-Fetch-Dest"))
+                .or_else(|| (context == COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT).then(|| "document".to_string()));
+            let frame_headers = FrameHeaders {
+                referer: referer.as_deref(),
+                origin: origin.as_deref(),
+                fetch_dest: fetch_dest.as_deref(),
+            };
+
+            let Some(state) = app.try_state::<AppState>() else {
+                return Ok(());
+            };
+            // The kill switch refuses everything but app pages
+            if offline::blocks(&state, &url) {
+                let response = environment.CreateWebResourceResponse(
+                    None::<&windows::Win32::System::Com::IStream>,
+                    403,
+                    w!("Offline"),
+                    w!(""),
+                )?;
+                args.SetResponse(&response)?;
+                return Ok(());
+            }
+            let Some(ctx) = state.frames.context(&label, &url, frame_headers, request_type(context)) else {
+                return Ok(());
+            };
+            let settings = state.settings.read().unwrap();
+            let blocked = settings.block_trackers
+                && state.adblock.should_block_in_frame(&url, &ctx.frame_url, &ctx.top_url, &ctx.request_type);
+            if blocked {
+                block_stats::record_block(&app, &state, &label, &url);
+            }
+            let image_blocked = ctx.request_type == "image" && state.image_blocker.should_block(&url, &ctx.frame_url);
+            // The data saver also refuses media here; there's no response size to go by yet
+            if blocked || image_blocked || data_saver::should_refuse(&state, &settings, &label, &url, &ctx, None).is_some() {
+                let response = environment.CreateWebResourceResponse(
+                    None::<&windows::Win32::System::Com::IStream>,
+                    403,
+                    w!("Blocked"),
+                    w!(""),
+                )?;
+                args.SetResponse(&response)?;
+            } else if settings.block_third_party_cookies
+                && cookie_policy::strips_cookies(&settings.third_party_cookie_exceptions, &url, &ctx.top_url)
+            {
+                headers.RemoveHeader(w!("Cookie"))?;
+            }
+            Ok(())
+        }));
+        let mut token = 0;
+        if let Err(e) = core.add_WebResourceRequested(&handler, &mut token) {
+            eprintln!("[AdBlock] Failed to register WebView2 request handler: {}", e);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[AdBlock] Failed to access WebView2: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_websocket_variants() {
+        let rules = vec![
+            json!({ "trigger": { "url-filter": "^https?://ads\\.example\\.com/" }, "action": { "type": "block" } }),
+            json!({ "trigger": { "url-filter": "^https?://cdn\\.example\\.com/", "resource-type": ["script"] }, "action": { "type": "block" } }),
+            json!({ "trigger": { "url-filter": "^[^:]+:(//)?([^/]+\\.)?tracker\\.net" }, "action": { "type": "block" } }),
+            json!({ "trigger": { "url-filter": "^https?://ads\\.example\\.com/live", "resource-type": ["raw"] }, "action": { "type": "ignore-previous-rules" } }),
+        ];
+        let filters: Vec<String> = with_websocket_variants(rules)
+            .iter()
+            .map(|r| r["trigger"]["url-filter"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            filters,
+            vec![
+                "^https?://ads\\.example\\.com/",
+                "^wss?://ads\\.example\\.com/",
+                "^https?://cdn\\.example\\.com/",
+                "^[^:]+:(//)?([^/]+\\.)?tracker\\.net",
+                "^https?://ads\\.example\\.com/live",
+                "^wss?://ads\\.example\\.com/live",
+            ]
+        );
+    }
+}
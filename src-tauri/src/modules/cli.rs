@@ -0,0 +1,242 @@
+// Command-line flags.
+//
+//   sovereign [--new-tab <url>] [--private] [--profile <name>] [--headless] [<url or .html file>]
+//
+// `--new-tab` opens the URL in a new tab of the main window; with `--private` it
+// opens in a new private window instead (the homepage if no URL is given).
+// `--profile` picks the profile for the run (see modules::profile) and
+// `--headless` is read by modules::headless. A bare URL or .html path works like
+// `--new-tab`, which is how the OS hands over files and links.
+//
+// Flags are parsed in main() before the app is built. At cold start a tab URL
+// goes through `pending_launch_url` for the main window to pick up; a second
+// launch is forwarded by the single-instance plugin and handled by
+// `handle_second_launch` in the running browser. Unknown arguments (like the
+// -psn_ one macOS may add) are ignored.
+
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+use crate::modules::{context_menu, headless, profile, top_sites};
+use crate::state::AppState;
+
+pub const USAGE: &str =
+    "Usage: sovereign [--new-tab <url>] [--private] [--profile <name>] [--headless] [<url or .html file>]";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchArgs {
+    pub new_tab: Option<String>,
+    pub private: bool,
+    pub profile: Option<String>,
+    pub help: bool,
+}
+
+/// What a launch asks the running browser to open.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchAction {
+    Nothing,
+    NewTab(String),
+    PrivateWindow(String),
+}
+
+impl LaunchArgs {
+    pub fn action(&self, homepage: &str) -> LaunchAction {
+        match (&self.new_tab, self.private) {
+            (url, true) => LaunchAction::PrivateWindow(url.clone().unwrap_or_else(|| homepage.to_string())),
+            (Some(url), false) => LaunchAction::NewTab(url.clone()),
+            (None, false) => LaunchAction::Nothing,
+        }
+    }
+}
+
+/// Turns what was typed into a URL a tab can load: web and file URLs as they
+/// are, absolute paths as file URLs, `host`/`host:port` as https. Other schemes
+/// (`javascript:`, `mailto:`) are refused, except the new tab page's short name.
+pub fn normalize_url(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Err("Empty URL".to_string());
+    }
+    if raw == top_sites::NEW_TAB_ALIAS {
+        return Ok(raw.to_string());
+    }
+    if Path::new(raw).is_absolute() {
+        return Url::from_file_path(raw).map(String::from).map_err(|_| format!("Invalid file path '{}'", raw));
+    }
+    match Url::parse(raw) {
+        Ok(url) if matches!(url.scheme(), "http" | "https" | "file") => Ok(url.into()),
+        // `localhost:8080` parses as scheme "localhost"
+        Ok(url) if !raw[url.scheme().len() + 1..].starts_with(|c: char| c.is_ascii_digit()) => {
+            Err(format!("Can't open '{}' URLs from the command line", url.scheme()))
+        }
+        _ => Url::parse(&format!("https://{}", raw))
+            .map(String::from)
+            .map_err(|e| format!("Invalid URL '{}': {}", raw, e)),
+    }
+}
+
+/// Bare arguments the OS passes when opening a link or an .html file with the browser.
+fn is_launch_target(arg: &str) -> bool {
+    let lower = arg.to_ascii_lowercase();
+    lower.starts_with("http://")
+        || lower.starts_with("https://")
+        || lower.starts_with("file://")
+        || (Path::new(arg).is_absolute() && (lower.ends_with(".html") || lower.ends_with(".htm")))
+}
+
+/// Parses `argv`, program name first.
+pub fn parse(argv: &[String]) -> Result<LaunchArgs, String> {
+    let mut launch = LaunchArgs::default();
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline.clone().or_else(|| args.next().cloned()).ok_or_else(|| format!("{} needs a value", name))
+        };
+        match flag {
+            "--new-tab" => launch.new_tab = Some(normalize_url(&value("--new-tab")?)?),
+            "--profile" => launch.profile = Some(profile::validate_name(&value("--profile")?)?),
+            "--private" => launch.private = true,
+            "--help" | "-h" => launch.help = true,
+            headless::FLAG => {}
+            _ if launch.new_tab.is_none() && is_launch_target(arg) => launch.new_tab = Some(normalize_url(arg)?),
+            _ => eprintln!("[CLI] Ignoring argument '{}'", arg),
+        }
+    }
+    Ok(launch)
+}
+
+fn homepage(app: &AppHandle) -> String {
+    app.try_state::<AppState>().map(|state| state.settings.read().unwrap().homepage.clone()).unwrap_or_default()
+}
+
+fn open_private_window(app: &AppHandle, url: &str) {
+    if let Err(e) = context_menu::open_link_window(app, url, true) {
+        eprintln!("[CLI] Failed to open private window: {}", e);
+    }
+}
+
+/// Acts on this process's own flags once the app state is set up.
+pub fn handle_cold_start(app: &AppHandle, launch: &LaunchArgs) {
+    match launch.action(&homepage(app)) {
+        LaunchAction::Nothing => {}
+        LaunchAction::NewTab(url) => {
+            println!("[CLI] Cold start URL: {}", url);
+            if let Some(state) = app.try_state::<AppState>() {
+                *state.pending_launch_url.lock().unwrap() = Some(url);
+            }
+        }
+        LaunchAction::PrivateWindow(url) => open_private_window(app, &url),
+    }
+}
+
+/// Acts on the flags of a second launch, forwarded to this running browser.
+/// Returns whether it opened its own window (otherwise the main window should come forward).
+pub fn handle_second_launch(app: &AppHandle, argv: &[String]) -> bool {
+    let launch = match parse(argv) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("[CLI] Ignoring second launch: {}", e);
+            return false;
+        }
+    };
+    if launch.profile.is_some() && launch.profile.as_deref() != profile::active() {
+        eprintln!("[CLI] Already running with profile {:?}, opening there instead", profile::active());
+    }
+    match launch.action(&homepage(app)) {
+        LaunchAction::Nothing => false,
+        LaunchAction::NewTab(url) => {
+            println!("[CLI] Hot start URL: {}", url);
+            let _ = app.emit("request-open-url", &url);
+            false
+        }
+        LaunchAction::PrivateWindow(url) => {
+            open_private_window(app, &url);
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn argv(list: &[&str]) -> Vec<String> {
+        std::iter::once("sovereign").chain(list.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let launch = parse(&argv(&["--new-tab", "https://example.com/a", "--private", "--profile", "work"])).unwrap();
+        assert_eq!(
+            launch,
+            LaunchArgs {
+                new_tab: Some("https://example.com/a".to_string()),
+                private: true,
+                profile: Some("work".to_string()),
+                help: false,
+            }
+        );
+        assert_eq!(
+            parse(&argv(&["--new-tab=example.com", "--profile=home"])).unwrap().profile.as_deref(),
+            Some("home")
+        );
+        assert_eq!(parse(&argv(&[])).unwrap(), LaunchArgs::default());
+    }
+
+    #[rstest]
+    #[case(&["--new-tab"])]
+    #[case(&["--profile"])]
+    #[case(&["--profile", "../other"])]
+    #[case(&["--new-tab", "javascript:alert(1)"])]
+    #[case(&["--new-tab", "mailto:a@example.com"])]
+    fn test_parse_errors(#[case] args: &[&str]) {
+        assert!(parse(&argv(args)).is_err());
+    }
+
+    #[test]
+    fn test_parse_ignores_unknown_and_os_arguments() {
+        let launch = parse(&argv(&["-psn_0_12345", "--headless", "--unknown"])).unwrap();
+        assert_eq!(launch, LaunchArgs::default());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_bare_targets() {
+        assert_eq!(parse(&argv(&["/tmp/page.html"])).unwrap().new_tab.as_deref(), Some("file:///tmp/page.html"));
+        assert_eq!(parse(&argv(&["https://example.com"])).unwrap().new_tab.as_deref(), Some("https://example.com/"));
+        // --new-tab wins over a bare target
+        let launch = parse(&argv(&["--new-tab", "a.com", "https://b.com"])).unwrap();
+        assert_eq!(launch.new_tab.as_deref(), Some("https://a.com/"));
+        // Bare words aren't URLs
+        assert_eq!(parse(&argv(&["notes.html"])).unwrap().new_tab, None);
+    }
+
+    #[rstest]
+    #[case("https://example.com/a?b=1", "https://example.com/a?b=1")]
+    #[case("example.com", "https://example.com/")]
+    #[case("localhost:8080/x", "https://localhost:8080/x")]
+    #[case("example.com:8443", "https://example.com:8443/")]
+    #[case("file:///tmp/a.html", "file:///tmp/a.html")]
+    #[case("about:newtab", "about:newtab")]
+    fn test_normalize_url(#[case] raw: &str, #[case] expected: &str) {
+        assert_eq!(normalize_url(raw).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_action() {
+        let home = "https://duckduckgo.com";
+        let tab = LaunchArgs { new_tab: Some("https://a.com/".to_string()), ..Default::default() };
+        assert_eq!(tab.action(home), LaunchAction::NewTab("https://a.com/".to_string()));
+        let private = LaunchArgs { private: true, ..Default::default() };
+        assert_eq!(private.action(home), LaunchAction::PrivateWindow(home.to_string()));
+        let private_tab = LaunchArgs { private: true, ..tab.clone() };
+        assert_eq!(private_tab.action(home), LaunchAction::PrivateWindow("https://a.com/".to_string()));
+        assert_eq!(LaunchArgs::default().action(home), LaunchAction::Nothing);
+    }
+}
@@ -0,0 +1,204 @@
+// Clipboard helpers - "Open copied link" detection and self-clearing copies.
+// Everything here runs locally; clipboard contents never leave the process.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+const MAX_CLIPBOARD_URL_LEN: usize = 2048;
+const MAX_EXPIRY_SECS: u64 = 600;
+
+/// Returns the URL if the clipboard text is a single http(s) link or a bare
+/// domain such as "example.com/path". Prose, multi-line text and other schemes
+/// (javascript:, file:, ...) are ignored.
+pub fn detect_url(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > MAX_CLIPBOARD_URL_LEN || text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let candidate = if text.starts_with("http://") || text.starts_with("https://") {
+        text.to_string()
+    } else if text.contains("://") {
+        return None;
+    } else {
+        format!("https://{}", text)
+    };
+
+    let url = url::Url::parse(&candidate).ok()?;
+    let host = url.host_str()?;
+
+    // Bare domains need a dotted host with an alphabetic TLD ("v1.2" or "e.g" aren't links)
+    if !text.contains("://") {
+        if !url.username().is_empty() || url.password().is_some() {
+            return None; // "mailto:me@x.com" would otherwise parse as userinfo
+        }
+        let tld = host.rsplit('.').next()?;
+        if !host.contains('.') || tld.len() < 2 || !tld.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+    }
+
+    Some(url.to_string())
+}
+
+fn content_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Remembers the last URL offered so refocusing the window doesn't re-offer it,
+/// and tracks pending clipboard expiries.
+pub struct ClipboardManager {
+    last_offered: Mutex<Option<String>>,
+    next_expiry_id: AtomicU64,
+    // Expiry id -> hash of the copied text. Only a hash is kept so secrets
+    // don't linger in memory until the timer fires.
+    pending_expiries: Mutex<HashMap<u64, u64>>,
+}
+
+impl ClipboardManager {
+    pub fn new() -> Self {
+        Self {
+            last_offered: Mutex::new(None),
+            next_expiry_id: AtomicU64::new(1),
+            pending_expiries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a copy that should expire. A newer copy supersedes older timers,
+    /// since the clipboard holds only one value.
+    pub fn register_expiry(&self, text: &str) -> u64 {
+        let id = self.next_expiry_id.fetch_add(1, Ordering::SeqCst);
+        let mut pending = self.pending_expiries.lock().unwrap();
+        pending.clear();
+        pending.insert(id, content_hash(text));
+        id
+    }
+
+    /// Called when a timer fires. True if the clipboard still holds the copied
+    /// text and should be cleared.
+    pub fn take_expired(&self, id: u64, current_text: &str) -> bool {
+        match self.pending_expiries.lock().unwrap().remove(&id) {
+            Some(hash) => hash == content_hash(current_text),
+            None => false,
+        }
+    }
+
+    pub fn pending_expiries(&self) -> usize {
+        self.pending_expiries.lock().unwrap().len()
+    }
+
+    /// The URL to offer for this clipboard text, if it is new.
+    pub fn check(&self, text: &str) -> Option<String> {
+        let url = detect_url(text)?;
+        let mut last = self.last_offered.lock().unwrap();
+        if last.as_deref() == Some(url.as_str()) {
+            return None;
+        }
+        *last = Some(url.clone());
+        Some(url)
+    }
+}
+
+impl Default for ClipboardManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tauri command: copy text (a credential, a URL) and clear the clipboard after
+/// `seconds` unless the user has copied something else in the meantime.
+#[tauri::command]
+pub fn copy_with_expiry(
+    app: AppHandle,
+    state: State<AppState>,
+    text: String,
+    seconds: u64,
+) -> Result<(), BrowserError> {
+    if seconds == 0 || seconds > MAX_EXPIRY_SECS {
+        return Err(BrowserError::InvalidInput(format!("Expiry must be between 1 and {} seconds", MAX_EXPIRY_SECS)));
+    }
+
+    let id = state.clipboard.register_expiry(&text);
+    app.clipboard().write_text(text).map_err(|e| BrowserError::Internal(e.to_string()))?;
+
+    let clipboard = state.clipboard.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(seconds)).await;
+
+        let current = app.clipboard().read_text().unwrap_or_default();
+        if clipboard.take_expired(id, &current) {
+            match app.clipboard().clear() {
+                Ok(_) => println!("[Clipboard] Cleared expired copy"),
+                Err(e) => eprintln!("[Clipboard] Failed to clear clipboard: {}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://example.com/a?b=1", Some("https://example.com/a?b=1"))]
+    #[case("  http://example.com  ", Some("http://example.com/"))]
+    #[case("example.com/docs", Some("https://example.com/docs"))]
+    #[case("sub.example.co.uk", Some("https://sub.example.co.uk/"))]
+    #[case("hello world", None)]
+    #[case("https://a.com\nhttps://b.com", None)]
+    #[case("javascript:alert(1)", None)]
+    #[case("file:///etc/passwd", None)]
+    #[case("v1.2", None)]
+    #[case("localhost", None)]
+    #[case("mailto:me@example.com", None)]
+    #[case("", None)]
+    fn test_detect_url(#[case] text: &str, #[case] expected: Option<&str>) {
+        assert_eq!(detect_url(text).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_check_offers_each_url_once() {
+        let manager = ClipboardManager::new();
+        assert!(manager.check("https://a.com/").is_some());
+        assert!(manager.check("https://a.com/").is_none());
+        assert!(manager.check("not a url").is_none());
+        assert!(manager.check("https://b.com/").is_some());
+    }
+
+    #[test]
+    fn test_expiry_only_clears_unchanged_content() {
+        let manager = ClipboardManager::new();
+
+        let id = manager.register_expiry("hunter2");
+        assert!(!manager.take_expired(id, "something the user copied later"));
+        // A timer fires at most once
+        assert!(!manager.take_expired(id, "hunter2"));
+
+        let id = manager.register_expiry("hunter2");
+        assert!(manager.take_expired(id, "hunter2"));
+        assert_eq!(manager.pending_expiries(), 0);
+    }
+
+    #[test]
+    fn test_newer_copy_supersedes_older_timer() {
+        let manager = ClipboardManager::new();
+        let first = manager.register_expiry("same");
+        let second = manager.register_expiry("same");
+
+        assert!(!manager.take_expired(first, "same"));
+        assert!(manager.take_expired(second, "same"));
+    }
+}
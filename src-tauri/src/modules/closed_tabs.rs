@@ -1,3 +1,6 @@
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
 use crate::state::{AppState, ClosedTab, Tab};
 
 const MAX_CLOSED_TABS: usize = 25;
@@ -29,6 +32,43 @@ pub fn pop_closed_tab(state: &AppState) -> Option<ClosedTab> {
     tab
 }
 
+/// Takes a specific closed tab out of the stack, e.g. one picked in tab search
+pub fn take_closed_tab(state: &AppState, id: &str) -> Option<ClosedTab> {
+    let mut closed = state.closed_tabs.lock().unwrap();
+    let index = closed.iter().rposition(|t| t.id == id)?;
+    let tab = closed.remove(index);
+
+    if let Some(ref t) = tab {
+        println!("[ClosedTabs] Restored tab '{}' at URL: {}", t.title, t.url);
+    }
+
+    tab
+}
+
+/// Drops tabs closed before `cutoff`, returns how many (maintenance)
+pub fn expire_closed_tabs(closed: &mut VecDeque<ClosedTab>, cutoff: SystemTime) -> usize {
+    let before = closed.len();
+    closed.retain(|t| t.closed_at >= cutoff);
+    before - closed.len()
+}
+
+/// Adds tabs from elsewhere (a user data archive) that aren't in the stack yet,
+/// keeping it in closing order and within the size limit. Returns how many were added.
+pub fn merge_closed_tabs(closed: &mut VecDeque<ClosedTab>, tabs: Vec<ClosedTab>) -> usize {
+    let before = closed.len();
+    for tab in tabs {
+        if !closed.iter().any(|t| t.url == tab.url && t.closed_at == tab.closed_at) {
+            closed.push_back(tab);
+        }
+    }
+    let added = closed.len() - before;
+    closed.make_contiguous().sort_by_key(|t| t.closed_at);
+    while closed.len() > MAX_CLOSED_TABS {
+        closed.pop_front();
+    }
+    added
+}
+
 /// Gets count of closed tabs (for UI)
 pub fn closed_tab_count(state: &AppState) -> usize {
     let closed = state.closed_tabs.lock().unwrap();
@@ -38,5 +78,48 @@ pub fn closed_tab_count(state: &AppState) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     // TODO: Add unit tests for archive/restore cycle, max size enforcement
+
+    #[test]
+    fn test_expire_closed_tabs() {
+        let now = SystemTime::now();
+        let tab = |id: &str, closed_at: SystemTime| ClosedTab {
+            id: id.to_string(),
+            title: id.to_string(),
+            url: format!("https://{}.com/", id),
+            favicon: None,
+            closed_at,
+            container: None,
+        };
+        let mut closed = VecDeque::from([tab("old", now - Duration::from_secs(3600)), tab("new", now)]);
+
+        assert_eq!(expire_closed_tabs(&mut closed, now - Duration::from_secs(60)), 1);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].id, "new");
+    }
+
+    #[test]
+    fn test_merge_closed_tabs() {
+        let now = SystemTime::now();
+        let tab = |id: &str, secs_ago: u64| ClosedTab {
+            id: id.to_string(),
+            title: id.to_string(),
+            url: format!("https://{}.com/", id),
+            favicon: None,
+            closed_at: now - Duration::from_secs(secs_ago),
+            container: None,
+        };
+        let mut closed = VecDeque::from([tab("b", 20), tab("d", 0)]);
+
+        assert_eq!(merge_closed_tabs(&mut closed, vec![tab("a", 30), tab("c", 10), tab("b", 20)]), 2);
+        let ids: Vec<&str> = closed.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c", "d"]);
+
+        let many = (0..MAX_CLOSED_TABS as u64).map(|i| tab(&format!("old{}", i), 100 + i)).collect();
+        merge_closed_tabs(&mut closed, many);
+        assert_eq!(closed.len(), MAX_CLOSED_TABS);
+        // The oldest ones go first, the newest (the one reopened next) stays on top
+        assert_eq!(closed.back().unwrap().id, "d");
+    }
 }
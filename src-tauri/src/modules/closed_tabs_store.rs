@@ -1,9 +1,10 @@
+use crate::modules::profile;
 use crate::state::ClosedTab;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fs;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClosedTabsStore {
@@ -12,16 +13,18 @@ pub struct ClosedTabsStore {
 
 impl ClosedTabsStore {
     fn get_path(app: &AppHandle) -> PathBuf {
-        app.path().app_data_dir()
+        profile::data_dir(app)
             .expect("Failed to get app data dir")
             .join("closed_tabs.json")
     }
 
     pub fn load(app: &AppHandle) -> Self {
-        let path = Self::get_path(app);
+        Self::load_from(&Self::get_path(app))
+    }
 
+    fn load_from(path: &Path) -> Self {
         if path.exists() {
-            match fs::read_to_string(&path) {
+            match fs::read_to_string(path) {
                 Ok(json) => {
                     match serde_json::from_str(&json) {
                         Ok(store) => return store,
@@ -39,7 +42,10 @@ impl ClosedTabsStore {
     }
 
     pub fn save(&self, app: &AppHandle) -> Result<(), String> {
-        let path = Self::get_path(app);
+        self.save_to(&Self::get_path(app))
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), String> {
         let tmp_path = path.with_extension("tmp");
         let parent = path.parent().unwrap();
 
@@ -54,3 +60,55 @@ impl ClosedTabsStore {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::browsing_data::clear_closed_tabs;
+    use std::time::SystemTime;
+
+    fn closed_tab(id: &str) -> ClosedTab {
+        ClosedTab {
+            id: id.to_string(),
+            title: id.to_string(),
+            url: format!("https://{}.com/", id),
+            favicon: None,
+            closed_at: SystemTime::now(),
+            container: None,
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("closed_tabs.json");
+
+        let store = ClosedTabsStore { tabs: vec![closed_tab("a"), closed_tab("b")].into() };
+        store.save_to(&path).unwrap();
+
+        let loaded = ClosedTabsStore::load_from(&path);
+        assert_eq!(loaded.tabs.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_cleared_store_stays_empty() {
+        // What clear-on-exit does: wipe the queue, then persist it
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("closed_tabs.json");
+
+        let mut store = ClosedTabsStore { tabs: vec![closed_tab("a")].into() };
+        store.save_to(&path).unwrap();
+
+        assert_eq!(clear_closed_tabs(&mut store.tabs, None), 1);
+        store.save_to(&path).unwrap();
+        assert!(ClosedTabsStore::load_from(&path).tabs.is_empty());
+    }
+
+    #[test]
+    fn test_load_corrupt_file_falls_back_to_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("closed_tabs.json");
+        fs::write(&path, "not json").unwrap();
+        assert!(ClosedTabsStore::load_from(&path).tabs.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+// Browser command registry - pure logic, no Tauri imports.
+// Single source of truth for command ids, titles and accelerators so the native
+// menu, keybindings and the omnibox command palette (">" prefix) stay in sync.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserCommand {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub accelerator: Option<&'static str>,
+    #[serde(skip)]
+    pub in_palette: bool, // Menu-only aliases are hidden from the palette
+}
+
+const fn cmd(id: &'static str, title: &'static str, accelerator: Option<&'static str>) -> BrowserCommand {
+    BrowserCommand { id, title, accelerator, in_palette: true }
+}
+
+const fn alias(id: &'static str, title: &'static str, accelerator: Option<&'static str>) -> BrowserCommand {
+    BrowserCommand { id, title, accelerator, in_palette: false }
+}
+
+pub const COMMANDS: &[BrowserCommand] = &[
+    // App
+    cmd("settings", "Settings", Some("CmdOrCtrl+,")),
+    cmd("leave_suggestion", "Leave a Suggestion...", None),
+    // File
+    cmd("new_tab", "New Tab", Some("CmdOrCtrl+T")),
+    cmd("guest_window", "New Guest Window", None),
+    cmd("print", "Print...", Some("CmdOrCtrl+P")),
+    cmd("close_tab", "Close Tab", Some("CmdOrCtrl+W")),
+    // Edit
+    cmd("find_in_page", "Find in Page", Some("CmdOrCtrl+F")),
+    // View
+    cmd("focus_location", "Open Location", Some("CmdOrCtrl+L")),
+    alias("focus_location_alt", "Open Location (Alt)", Some("CmdOrCtrl+K")),
+    cmd("reload", "Reload Page", Some("CmdOrCtrl+R")),
+    cmd("hard_reload", "Hard Reload", Some("CmdOrCtrl+Shift+R")),
+    cmd("next_tab", "Next Tab", Some("CmdOrCtrl+Shift+]")),
+    cmd("prev_tab", "Previous Tab", Some("CmdOrCtrl+Shift+[")),
+    cmd("open_devtools", "Developer Tools", Some("CmdOrCtrl+Option+I")),
+    cmd("task_manager", "Task Manager", None),
+    // History
+    cmd("go_back", "Back", Some("CmdOrCtrl+[")),
+    cmd("go_forward", "Forward", Some("CmdOrCtrl+]")),
+    cmd("reopen_closed_tab", "Reopen Closed Tab", Some("CmdOrCtrl+Shift+T")),
+    // Palette-only actions
+    cmd("clear_site_data", "Clear Site Data", None),
+    cmd("toggle_adblock", "Toggle Ad Blocking", None),
+    cmd("toggle_offline", "Go Offline / Back Online", None),
+    cmd("element_picker", "Hide Element on Page", None),
+    cmd("stash_other_tabs", "Stash Other Tabs", None),
+    cmd("export_har", "Export Network Log (HAR)...", None),
+    cmd("export_console_log", "Export Console Log...", None),
+    cmd("webauthn_support", "Check Passkey Support", None),
+    cmd("pop_out_video", "Pop Out Video", None),
+    cmd("read_aloud", "Read Aloud", None),
+    cmd("stop_read_aloud", "Stop Reading Aloud", None),
+    cmd("toggle_mute_tab", "Mute/Unmute Tab", None),
+    cmd("move_tab_to_window", "Move Tab to New Window", None),
+    cmd("toggle_split_view", "Toggle Split View", None),
+    cmd("generate_email_alias", "Generate Email Alias", None),
+    cmd("fill_otp_code", "Fill One-Time Code", None),
+    cmd("highlight_selection", "Highlight Selection", None),
+    cmd("export_annotations", "Export Annotations...", None),
+    cmd("install_site_app", "Install Site as App...", None),
+];
+
+pub fn find(id: &str) -> Option<&'static BrowserCommand> {
+    COMMANDS.iter().find(|c| c.id == id)
+}
+
+/// Palette search: case-insensitive match on title or id. Empty query lists everything.
+pub fn search(query: &str) -> Vec<&'static BrowserCommand> {
+    let q = query.trim().to_lowercase();
+    COMMANDS.iter()
+        .filter(|c| c.in_palette)
+        .filter(|c| {
+            q.is_empty()
+                || c.title.to_lowercase().contains(&q)
+                || c.id.replace('_', " ").contains(&q)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_ids_are_unique() {
+        let ids: HashSet<_> = COMMANDS.iter().map(|c| c.id).collect();
+        assert_eq!(ids.len(), COMMANDS.len());
+    }
+
+    #[test]
+    fn test_search() {
+        let results = search("tab");
+        assert!(results.iter().any(|c| c.id == "new_tab"));
+        assert!(results.iter().any(|c| c.id == "reopen_closed_tab"));
+
+        // Aliases are menu-only
+        assert!(search("").iter().all(|c| c.id != "focus_location_alt"));
+        assert!(search("adblock").iter().any(|c| c.id == "toggle_adblock"));
+        assert!(search("zzz").is_empty());
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find("new_tab").and_then(|c| c.accelerator), Some("CmdOrCtrl+T"));
+        assert!(find("missing").is_none());
+    }
+}
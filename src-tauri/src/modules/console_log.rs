@@ -0,0 +1,332 @@
+// Per-tab console capture.
+//
+// `CONSOLE_SCRIPT` forwards the top document's console.log/warn/error calls, plus
+// uncaught errors and unhandled rejections, to `report_console_messages`. Errors
+// carry a stack trace: the thrown Error's own, or the console.error call site.
+// Each tab keeps its last MAX_ENTRIES messages across navigations (the page URL
+// is on every message), so a problem can still be reported after a redirect or
+// reload; the log goes when the tab closes.
+//
+// With `Settings.save_console_log` on, messages are also appended to
+// console.log in the app's log directory, rotated at MAX_FILE_BYTES (and daily
+// by modules::maintenance) with ROTATED_FILES older files kept.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const MAX_ENTRIES: usize = 1000;
+const MAX_MESSAGE_LEN: usize = 10_000;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const ROTATED_FILES: usize = 3;
+const LOG_FILE: &str = "console.log";
+
+/// Forwards console output to `report_console_messages`, in batches.
+pub const CONSOLE_SCRIPT: &str = r#"
+(function() {
+    if (!window.__TAURI__ || window !== window.top || window.__SOVEREIGN_CONSOLE__) return;
+    window.__SOVEREIGN_CONSOLE__ = true;
+    const invoke = window.__TAURI__.core.invoke;
+    let pending = [];
+    let timer = null;
+
+    function text(value) {
+        if (typeof value === 'string') return value;
+        if (value instanceof Error) return value.name + ': ' + value.message;
+        try {
+            const json = JSON.stringify(value);
+            if (json !== undefined) return json;
+        } catch (e) {}
+        return String(value);
+    }
+    function flush() {
+        timer = null;
+        if (!pending.length) return;
+        const messages = pending;
+        pending = [];
+        invoke('report_console_messages', { messages }).catch(() => {});
+    }
+    function record(level, args, stack) {
+        pending.push({
+            level,
+            message: args.map(text).join(' ').slice(0, 10000),
+            stack: stack || null,
+            timestamp: Date.now(),
+            url: location.href
+        });
+        if (!timer) timer = setTimeout(flush, 500);
+    }
+
+    for (const level of ['log', 'warn', 'error']) {
+        const original = console[level];
+        console[level] = function(...args) {
+            try {
+                let stack = null;
+                if (level === 'error') {
+                    const thrown = args.find(a => a instanceof Error);
+                    stack = thrown ? thrown.stack : (new Error().stack || '').split('\n').slice(2).join('\n');
+                }
+                record(level, args, stack);
+            } catch (e) {}
+            return original.apply(this, args);
+        };
+    }
+    window.addEventListener('error', e => {
+        record('error', ['Uncaught ' + (e.error ? text(e.error) : e.message)], e.error && e.error.stack);
+    });
+    window.addEventListener('unhandledrejection', e => {
+        record('error', ['Unhandled rejection: ' + text(e.reason)], e.reason && e.reason.stack);
+    });
+})();
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsoleLevel {
+    Log,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ConsoleMessage {
+    pub level: ConsoleLevel,
+    pub message: String,
+    pub stack: Option<String>,
+    pub timestamp: i64, // Unix time in milliseconds
+    pub url: String,    // Page that logged it
+}
+
+impl ConsoleMessage {
+    /// One message as text: timestamp, level, page, then the stack indented.
+    pub fn to_text(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.timestamp)
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let level = match self.level {
+            ConsoleLevel::Log => "log",
+            ConsoleLevel::Warn => "warn",
+            ConsoleLevel::Error => "error",
+        };
+        let mut text = format!("{} [{}] {} {}\n", time, level, self.url, self.message);
+        for line in self.stack.iter().flat_map(|s| s.lines()).filter(|l| !l.trim().is_empty()) {
+            text.push_str("    ");
+            text.push_str(line.trim());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn truncate(text: &mut String, max: usize) {
+    if text.len() > max {
+        let mut end = max;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+}
+
+pub struct ConsoleLogManager {
+    logs: Mutex<HashMap<String, VecDeque<ConsoleMessage>>>, // webview label -> messages, oldest first
+    log_dir: PathBuf,
+    file_lock: Mutex<()>,
+}
+
+impl ConsoleLogManager {
+    pub fn new(log_dir: PathBuf) -> Self {
+        Self { logs: Mutex::new(HashMap::new()), log_dir, file_lock: Mutex::new(()) }
+    }
+
+    pub fn record(&self, webview_label: &str, messages: &[ConsoleMessage]) {
+        let mut logs = self.logs.lock().unwrap();
+        let log = logs.entry(webview_label.to_string()).or_default();
+        for message in messages {
+            if log.len() >= MAX_ENTRIES {
+                log.pop_front();
+            }
+            log.push_back(message.clone());
+        }
+    }
+
+    pub fn entries(&self, webview_label: &str) -> Vec<ConsoleMessage> {
+        self.logs.lock().unwrap().get(webview_label).map(|log| log.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.logs.lock().unwrap().remove(webview_label);
+    }
+
+    pub fn log_path(&self) -> PathBuf {
+        self.log_dir.join(LOG_FILE)
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        self.log_dir.join(format!("console.{}.log", n))
+    }
+
+    /// Shifts console.log to console.1.log, console.1.log to console.2.log, ...
+    fn rotate(&self) -> std::io::Result<()> {
+        let _ = fs::remove_file(self.rotated_path(ROTATED_FILES));
+        for n in (1..ROTATED_FILES).rev() {
+            let from = self.rotated_path(n);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(n + 1))?;
+            }
+        }
+        fs::rename(self.log_path(), self.rotated_path(1))
+    }
+
+    /// Rotates the log file if anything was written to it. Returns whether it did.
+    pub fn rotate_now(&self) -> std::io::Result<bool> {
+        let _guard = self.file_lock.lock().unwrap();
+        if fs::metadata(self.log_path()).map(|m| m.len()).unwrap_or(0) == 0 {
+            return Ok(false);
+        }
+        self.rotate().map(|_| true)
+    }
+
+    /// Appends messages to the log file, rotating it when it's full.
+    pub fn append_to_file(&self, messages: &[ConsoleMessage]) -> std::io::Result<()> {
+        let text: String = messages.iter().map(ConsoleMessage::to_text).collect();
+        let _guard = self.file_lock.lock().unwrap();
+        fs::create_dir_all(&self.log_dir)?;
+        let size = fs::metadata(self.log_path()).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + text.len() as u64 > MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+        OpenOptions::new().create(true).append(true).open(self.log_path())?.write_all(text.as_bytes())
+    }
+}
+
+/// A tab's webview label.
+fn label_of(state: &AppState, tab_id: &str) -> Result<String, BrowserError> {
+    state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| t.webview_label.clone())
+        .ok_or_else(|| BrowserError::tab_not_found(tab_id))
+}
+
+/// Writes a tab's console log to `path` as text.
+pub fn write_text(state: &AppState, tab_id: &str, path: PathBuf) -> Result<(), BrowserError> {
+    let label = label_of(state, tab_id)?;
+    let text: String = state.console_log.entries(&label).iter().map(ConsoleMessage::to_text).collect();
+    fs::write(&path, text)?;
+    println!("[ConsoleLog] Exported {} to {}", tab_id, path.display());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn report_console_messages(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    mut messages: Vec<ConsoleMessage>,
+) -> Result<(), BrowserError> {
+    for message in &mut messages {
+        truncate(&mut message.message, MAX_MESSAGE_LEN);
+        if let Some(stack) = message.stack.as_mut() {
+            truncate(stack, MAX_MESSAGE_LEN);
+        }
+    }
+    state.console_log.record(webview.label(), &messages);
+    if state.settings.read().unwrap().save_console_log {
+        if let Err(e) = state.console_log.append_to_file(&messages) {
+            eprintln!("[ConsoleLog] Failed to write log file: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_console_log(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    tab_id: String,
+) -> Result<Vec<ConsoleMessage>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let label = label_of(&state, &tab_id)?;
+    Ok(state.console_log.entries(&label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn message(level: ConsoleLevel, text: &str) -> ConsoleMessage {
+        ConsoleMessage {
+            level,
+            message: text.to_string(),
+            stack: None,
+            timestamp: 1_700_000_000_000,
+            url: "https://a.example/".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_log_is_bounded() {
+        let manager = ConsoleLogManager::new(PathBuf::new());
+        let messages: Vec<_> = (0..MAX_ENTRIES + 5).map(|i| message(ConsoleLevel::Log, &i.to_string())).collect();
+        manager.record("tab", &messages);
+        let entries = manager.entries("tab");
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].message, "5");
+        manager.forget_webview("tab");
+        assert!(manager.entries("tab").is_empty());
+    }
+
+    #[test]
+    fn test_to_text() {
+        let mut error = message(ConsoleLevel::Error, "TypeError: x is undefined");
+        error.stack = Some("TypeError: x is undefined\n  at f (https://a.example/app.js:1:2)\n".to_string());
+        assert_eq!(
+            error.to_text(),
+            "2023-11-14T22:13:20.000Z [error] https://a.example/ TypeError: x is undefined\n\
+             \x20   TypeError: x is undefined\n\
+             \x20   at f (https://a.example/app.js:1:2)\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_keeps_char_boundary() {
+        let mut text = "ééé".to_string();
+        truncate(&mut text, 3);
+        assert_eq!(text, "é");
+    }
+
+    #[test]
+    fn test_file_rotation() {
+        let dir = tempdir().unwrap();
+        let manager = ConsoleLogManager::new(dir.path().to_path_buf());
+        let big = message(ConsoleLevel::Warn, &"x".repeat(MAX_FILE_BYTES as usize / 2));
+        for _ in 0..(ROTATED_FILES + 2) * 2 {
+            manager.append_to_file(std::slice::from_ref(&big)).unwrap();
+        }
+        assert!(fs::metadata(manager.log_path()).unwrap().len() <= MAX_FILE_BYTES);
+        assert!(manager.rotated_path(ROTATED_FILES).exists());
+        assert!(!manager.rotated_path(ROTATED_FILES + 1).exists());
+    }
+
+    #[test]
+    fn test_rotate_now_skips_empty_log() {
+        let dir = tempdir().unwrap();
+        let manager = ConsoleLogManager::new(dir.path().to_path_buf());
+        assert!(!manager.rotate_now().unwrap());
+        manager.append_to_file(&[message(ConsoleLevel::Log, "hello")]).unwrap();
+        assert!(manager.rotate_now().unwrap());
+        assert!(manager.rotated_path(1).exists());
+        assert!(!manager.rotate_now().unwrap());
+    }
+}
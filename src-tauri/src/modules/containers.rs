@@ -0,0 +1,446 @@
+// Containers: tabs with their own cookies and site storage within a profile.
+//
+// Like Firefox's containers. Each has a name and a color ("Personal", "Work",
+// "Banking" and "Shopping" to start with), and its tabs get their own data
+// directory (Windows, Linux) or website data store (macOS) under the
+// profile's `containers/<id>/`. Tabs outside any container use the profile's
+// own data as before.
+//
+// A tab's container is fixed when the tab is created. Rules send a site
+// (eTLD+1, see cookie_policy::site_of) to a container: new tabs for it open
+// there, and a tab in another container that navigates to it opens the page in
+// a new tab instead, staying where it was. Links a container tab opens in new
+// tabs stay in its container unless a rule says otherwise.
+//
+// Containers and rules are kept in containers.json.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::cookie_policy;
+use crate::modules::profile;
+use crate::modules::tabs;
+use crate::state::AppState;
+
+const CONTAINERS_FILE: &str = "containers.json";
+const CONTAINERS_DIR: &str = "containers";
+const MAX_NAME_LEN: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Container {
+    pub id: String, // Also its data directory's name
+    pub name: String,
+    pub color: String, // "#rrggbb"
+}
+
+/// Always open `site` in the container `container`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContainerRule {
+    pub site: String,
+    pub container: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContainerData {
+    pub containers: Vec<Container>,
+    #[serde(default)]
+    pub rules: Vec<ContainerRule>,
+}
+
+impl ContainerData {
+    fn builtins() -> Self {
+        let container = |id: &str, name: &str, color: &str| Container {
+            id: id.to_string(),
+            name: name.to_string(),
+            color: color.to_string(),
+        };
+        Self {
+            containers: vec![
+                container("personal", "Personal", "#0a84ff"),
+                container("work", "Work", "#ff9f0a"),
+                container("banking", "Banking", "#30d158"),
+                container("shopping", "Shopping", "#ff375f"),
+            ],
+            rules: Vec::new(),
+        }
+    }
+
+    /// The container a rule assigns to `url`, if any.
+    pub fn container_for(&self, url: &str) -> Option<String> {
+        let site = Url::parse(url).ok()?.host_str().map(cookie_policy::site_of)?;
+        self.rules.iter().find(|r| r.site == site).map(|r| r.container.clone())
+    }
+}
+
+/// Turns a name into an id that's a safe directory name and not taken.
+fn id_for(name: &str, taken: &[Container]) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let base = if slug.is_empty() { "container".to_string() } else { slug };
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken.iter().any(|c| c.id == id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+fn normalize_color(color: &str) -> Result<String, String> {
+    let color = color.trim().to_lowercase();
+    let hex = color.strip_prefix('#').unwrap_or("");
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(color)
+    } else {
+        Err(format!("Invalid container color: {}", color))
+    }
+}
+
+pub struct ContainerStore {
+    path: PathBuf,
+    data: Mutex<ContainerData>,
+}
+
+impl ContainerStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(CONTAINERS_FILE);
+        let data = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("[Containers] Failed to parse {}: {}", path.display(), e);
+                ContainerData::builtins()
+            }),
+            Err(_) => ContainerData::builtins(),
+        };
+        Self { path, data: Mutex::new(data) }
+    }
+
+    fn save(&self, data: &ContainerData) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(data).unwrap_or_default()) {
+            eprintln!("[Containers] Failed to save: {}", e);
+        }
+    }
+
+    pub fn data(&self) -> ContainerData {
+        self.data.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Container> {
+        self.data.lock().unwrap().containers.iter().find(|c| c.id == id).cloned()
+    }
+
+    pub fn container_for(&self, url: &str) -> Option<String> {
+        self.data.lock().unwrap().container_for(url)
+    }
+
+    pub fn create(&self, name: &str, color: &str) -> Result<Container, String> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(format!("Container names must be 1 to {} characters", MAX_NAME_LEN));
+        }
+        let color = normalize_color(color)?;
+        let mut data = self.data.lock().unwrap();
+        if data.containers.iter().any(|c| c.name.eq_ignore_ascii_case(name)) {
+            return Err(format!("There's already a container named '{}'", name));
+        }
+        let container = Container { id: id_for(name, &data.containers), name: name.to_string(), color };
+        data.containers.push(container.clone());
+        self.save(&data);
+        Ok(container)
+    }
+
+    /// Removes a container and the rules that point to it.
+    pub fn remove(&self, id: &str) -> bool {
+        let mut data = self.data.lock().unwrap();
+        let before = data.containers.len();
+        data.containers.retain(|c| c.id != id);
+        if data.containers.len() == before {
+            return false;
+        }
+        data.rules.retain(|r| r.container != id);
+        self.save(&data);
+        true
+    }
+
+    /// Sends a site to a container, replacing any rule it had.
+    pub fn set_rule(&self, site: &str, container: &str) -> Result<ContainerRule, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Enter a site like example.com")?;
+        let mut data = self.data.lock().unwrap();
+        if !data.containers.iter().any(|c| c.id == container) {
+            return Err(format!("No container '{}'", container));
+        }
+        let rule = ContainerRule { site, container: container.to_string() };
+        data.rules.retain(|r| r.site != rule.site);
+        data.rules.push(rule.clone());
+        self.save(&data);
+        Ok(rule)
+    }
+
+    pub fn remove_rule(&self, site: &str) -> bool {
+        let mut data = self.data.lock().unwrap();
+        let before = data.rules.len();
+        data.rules.retain(|r| r.site != site);
+        let removed = data.rules.len() != before;
+        if removed {
+            self.save(&data);
+        }
+        removed
+    }
+}
+
+fn container_dir(app: &AppHandle, id: &str) -> Option<PathBuf> {
+    profile::data_dir(app).ok().map(|dir| dir.join(CONTAINERS_DIR).join(id))
+}
+
+/// Web data directory for a container's tabs (Windows, Linux).
+pub fn webview_data_dir(app: &AppHandle, id: &str) -> Option<PathBuf> {
+    container_dir(app, id).map(|dir| dir.join("webview"))
+}
+
+/// WKWebsiteDataStore identifier for a container's tabs (macOS), distinct for
+/// each profile.
+pub fn store_identifier(id: &str) -> [u8; 16] {
+    profile::identifier_for(&format!("{}/{}/{}", profile::active().unwrap_or(""), CONTAINERS_DIR, id))
+}
+
+/// Deletes a container's WKWebsiteDataStore (macOS 14+). Fails while a webview
+/// still uses it, so only once its tabs are closed.
+#[cfg(target_os = "macos")]
+fn remove_data_store(app: &AppHandle, id: &str) {
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let identifier = store_identifier(id);
+    let id = id.to_string();
+    let result = app.run_on_main_thread(move || unsafe {
+        let store_class = class!(WKWebsiteDataStore);
+        let remove = sel!(removeDataStoreForIdentifier:completionHandler:);
+        let supported: BOOL = msg_send![store_class, respondsToSelector: remove];
+        if supported == NO {
+            return;
+        }
+        let uuid: *mut Object = msg_send![class!(NSUUID), alloc];
+        let uuid: *mut Object = msg_send![uuid, initWithUUIDBytes: identifier.as_ptr()];
+        let done = ConcreteBlock::new(move |error: *mut Object| {
+            if error.is_null() {
+                println!("[Containers] Removed the data store of {}", id);
+            } else {
+                let description: *mut Object = msg_send![error, localizedDescription];
+                let utf8: *const std::os::raw::c_char = msg_send![description, UTF8String];
+                let message = if utf8.is_null() {
+                    "unknown error".to_string()
+                } else {
+                    std::ffi::CStr::from_ptr(utf8).to_string_lossy().into_owned()
+                };
+                eprintln!("[Containers] Failed to remove the data store of {}: {}", id, message);
+            }
+        })
+        .copy();
+        let _: () = msg_send![store_class, removeDataStoreForIdentifier: uuid completionHandler: &*done];
+        let _: () = msg_send![uuid, release];
+    });
+    if let Err(e) = result {
+        eprintln!("[Containers] Failed to remove a data store: {}", e);
+    }
+}
+
+/// Opens `url` in a new tab when a rule puts it in another container than the
+/// navigating tab's. Returns whether the navigation goes ahead.
+pub fn on_navigation(app: &AppHandle, webview_label: &str, url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return true;
+    }
+    let Some(state) = app.try_state::<AppState>() else {
+        return true;
+    };
+    let Some(target) = state.containers.container_for(url.as_str()) else {
+        return true;
+    };
+    // Not in the list yet: a tab being created, which already got the rule's container
+    let current = {
+        let tabs = state.tabs.lock().unwrap();
+        match tabs.iter().find(|t| t.webview_label == webview_label) {
+            Some(tab) => tab.container.clone(),
+            None => return true,
+        }
+    };
+    if current.as_deref() == Some(target.as_str()) {
+        return true;
+    }
+    println!("[Containers] Opening {} in container {}", url, target);
+    // New tabs pick the rule's container, see create_tab_with_url
+    let _ = app.emit_to("main", "request-open-url", url.to_string());
+    false
+}
+
+fn emit_update(app: &AppHandle, state: &AppState) {
+    if let Err(e) = tabs::emit_tabs(app, state) {
+        eprintln!("[Containers] Failed to update tabs: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_containers(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<ContainerData, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.containers.data())
+}
+
+#[tauri::command]
+pub fn create_container(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    name: String,
+    color: String,
+) -> Result<Container, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let container = state.containers.create(&name, &color).map_err(BrowserError::InvalidInput)?;
+    println!("[Containers] Created {} ({})", container.name, container.id);
+    emit_update(&app, &state);
+    Ok(container)
+}
+
+/// Deletes a container with its cookies and site data. Its tabs have to be closed first.
+#[tauri::command]
+pub fn delete_container(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let open = state.tabs.lock().unwrap().iter().filter(|t| t.container.as_deref() == Some(id.as_str())).count();
+    if open > 0 {
+        return Err(BrowserError::NotAllowed(format!("Close the {} open tab(s) in this container first", open)));
+    }
+    if !state.containers.remove(&id) {
+        return Err(BrowserError::NotFound(format!("No container '{}'", id)));
+    }
+    if let Some(dir) = container_dir(&app, &id).filter(|dir| dir.exists()) {
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            eprintln!("[Containers] Failed to delete {}: {}", dir.display(), e);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    remove_data_store(&app, &id);
+    println!("[Containers] Deleted {}", id);
+    emit_update(&app, &state);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_container_rule(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    container: String,
+) -> Result<ContainerRule, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    state.containers.set_rule(&site, &container).map_err(BrowserError::InvalidInput)
+}
+
+#[tauri::command]
+pub fn remove_container_rule(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    if !state.containers.remove_rule(&site) {
+        return Err(BrowserError::NotFound(format!("No container rule for {}", site)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn store() -> (tempfile::TempDir, ContainerStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ContainerStore::new(dir.path().to_path_buf());
+        (dir, store)
+    }
+
+    #[rstest]
+    #[case("Work", "work")]
+    #[case("  Side Project!! ", "side-project")]
+    #[case("Ünïcode", "n-code")]
+    #[case("???", "container")]
+    #[case("Personal", "personal-2")]
+    fn test_id_for(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(id_for(name, &ContainerData::builtins().containers), expected);
+    }
+
+    #[test]
+    fn test_new_store_starts_with_builtins() {
+        let (_dir, store) = store();
+        let names: Vec<String> = store.data().containers.into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["Personal", "Work", "Banking", "Shopping"]);
+    }
+
+    #[test]
+    fn test_create_validates_and_persists() {
+        let (dir, store) = store();
+        let created = store.create(" Travel ", "#ABCDEF").unwrap();
+        assert_eq!(created, Container { id: "travel".into(), name: "Travel".into(), color: "#abcdef".into() });
+        assert!(store.create("travel", "#000000").is_err());
+        assert!(store.create("Games", "red").is_err());
+        assert!(store.create("", "#000000").is_err());
+
+        let reopened = ContainerStore::new(dir.path().to_path_buf());
+        assert_eq!(reopened.get("travel"), Some(created));
+    }
+
+    #[test]
+    fn test_rules_match_the_whole_site() {
+        let (_dir, store) = store();
+        store.set_rule("https://www.Amazon.co.uk/gp/cart", "shopping").unwrap();
+        assert_eq!(store.container_for("https://smile.amazon.co.uk/x"), Some("shopping".to_string()));
+        assert_eq!(store.container_for("https://amazon.com/"), None);
+        assert_eq!(store.container_for("not a url"), None);
+
+        // Setting the site again moves it
+        store.set_rule("amazon.co.uk", "work").unwrap();
+        assert_eq!(store.data().rules.len(), 1);
+        assert_eq!(store.container_for("https://amazon.co.uk/"), Some("work".to_string()));
+
+        assert!(store.set_rule("example.com", "nowhere").is_err());
+        assert!(store.set_rule("localhost", "work").is_err());
+        assert!(store.remove_rule("amazon.co.uk"));
+        assert!(!store.remove_rule("amazon.co.uk"));
+    }
+
+    #[test]
+    fn test_remove_drops_its_rules() {
+        let (_dir, store) = store();
+        store.set_rule("bank.example", "banking").unwrap();
+        store.set_rule("shop.example", "shopping").unwrap();
+        assert!(store.remove("banking"));
+        assert!(!store.remove("banking"));
+        assert_eq!(
+            store.data().rules,
+            vec![ContainerRule { site: "shop.example".into(), container: "shopping".into() }]
+        );
+    }
+
+    #[test]
+    fn test_store_identifier_differs_per_container() {
+        assert_ne!(store_identifier("work"), store_identifier("personal"));
+        assert_eq!(store_identifier("work"), store_identifier("work"));
+    }
+}
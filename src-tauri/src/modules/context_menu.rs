@@ -0,0 +1,392 @@
+// Content context menu backend - link dispositions and the native page menu.
+// Decides where a link from the page context menu should open.
+//
+// Tabs run `PAGE_SCRIPT`, which replaces the engine's menu with a native one
+// built here: link actions (new tab, background tab, windows, copy without
+// tracking parameters), image actions and Inspect Element, or Back/Forward/Reload
+// on the rest of the page. The engine's own menu is left for text fields and
+// selected text (spelling, copy and paste), pages with a menu of their own,
+// frames, and Shift+right-click. Picked items come back as menu events with
+// MENU_ITEM_PREFIX ids; main.rs runs them against the target kept here.
+//
+// Saved images are downloaded again through the browser's proxy, without the
+// page's cookies.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::menu::{Menu, MenuItemBuilder, PredefinedMenuItem};
+use tauri::{AppHandle, Emitter, Manager, WindowEvent};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{self, DataStore};
+use crate::modules::{commands, handoff, offline, proxy, tracking_params};
+use crate::state::AppState;
+
+pub const MENU_ITEM_PREFIX: &str = "context_menu:";
+
+const IMAGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What was right-clicked, kept until the next menu replaces it.
+static TARGET: Mutex<Option<ContextTarget>> = Mutex::new(None);
+
+/// Shows the native menu on right-click; see the top of this file for when it doesn't.
+pub const PAGE_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_CONTEXT_MENU__ || !window.__TAURI__ || window.top !== window) return;
+    window.__SOVEREIGN_CONTEXT_MENU__ = true;
+    // On window, so the page's own handlers have run by now
+    window.addEventListener('contextmenu', (e) => {
+        if (e.defaultPrevented || e.shiftKey || !(e.target instanceof Element)) return;
+        const el = e.target;
+        if (el.closest('input, textarea, select') || el.isContentEditable) return;
+        if (String(window.getSelection() || '').trim()) return;
+        const link = el.closest('a[href], area[href]');
+        const image = el.closest('img');
+        e.preventDefault();
+        window.__TAURI__.core.invoke('show_context_menu', {
+            link: link ? link.href : null,
+            image: image ? (image.currentSrc || image.src) : null,
+        }).catch(() => {});
+    });
+})();
+"#;
+
+/// Where a link chosen from the content context menu should open.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkDisposition {
+    CurrentTab,
+    NewTab,
+    NewWindow,
+    PrivateWindow,
+}
+
+impl LinkDisposition {
+    /// Dispositions that open outside the main window's tab strip.
+    pub fn opens_window(&self) -> bool {
+        matches!(self, Self::NewWindow | Self::PrivateWindow)
+    }
+
+    pub fn is_private(&self) -> bool {
+        matches!(self, Self::PrivateWindow)
+    }
+}
+
+/// Label for a secondary browser window.
+/// Private windows get their own prefix so they can be told apart (and never recorded in history).
+pub fn window_label(private: bool, nanos: u128) -> String {
+    if private {
+        format!("private-window-{}", nanos)
+    } else {
+        format!("window-{}", nanos)
+    }
+}
+
+/// What the page menu was opened on. Only web links and images are kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContextTarget {
+    pub webview_label: String,
+    pub link: Option<Url>,
+    pub image: Option<Url>,
+}
+
+/// An item of the page menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextAction {
+    OpenLinkInNewTab,
+    OpenLinkInBackgroundTab,
+    OpenLinkInNewWindow,
+    OpenLinkInPrivateWindow,
+    CopyCleanLink,
+    OpenImageInNewTab,
+    SaveImage,
+    CopyImageAddress,
+    InspectElement,
+}
+
+impl ContextAction {
+    const ALL: [Self; 9] = [
+        Self::OpenLinkInNewTab,
+        Self::OpenLinkInBackgroundTab,
+        Self::OpenLinkInNewWindow,
+        Self::OpenLinkInPrivateWindow,
+        Self::CopyCleanLink,
+        Self::OpenImageInNewTab,
+        Self::SaveImage,
+        Self::CopyImageAddress,
+        Self::InspectElement,
+    ];
+
+    fn id(self) -> &'static str {
+        match self {
+            Self::OpenLinkInNewTab => "open_link_new_tab",
+            Self::OpenLinkInBackgroundTab => "open_link_background_tab",
+            Self::OpenLinkInNewWindow => "open_link_new_window",
+            Self::OpenLinkInPrivateWindow => "open_link_private_window",
+            Self::CopyCleanLink => "copy_clean_link",
+            Self::OpenImageInNewTab => "open_image_new_tab",
+            Self::SaveImage => "save_image",
+            Self::CopyImageAddress => "copy_image_address",
+            Self::InspectElement => "inspect_element",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::OpenLinkInNewTab => "Open Link in New Tab",
+            Self::OpenLinkInBackgroundTab => "Open Link in Background Tab",
+            Self::OpenLinkInNewWindow => "Open Link in New Window",
+            Self::OpenLinkInPrivateWindow => "Open Link in Private Window",
+            Self::CopyCleanLink => "Copy Clean Link",
+            Self::OpenImageInNewTab => "Open Image in New Tab",
+            Self::SaveImage => "Save Image As...",
+            Self::CopyImageAddress => "Copy Image Address",
+            Self::InspectElement => "Inspect Element",
+        }
+    }
+
+    pub fn menu_id(self) -> String {
+        format!("{}{}", MENU_ITEM_PREFIX, self.id())
+    }
+
+    pub fn from_menu_id(id: &str) -> Option<Self> {
+        let id = id.strip_prefix(MENU_ITEM_PREFIX)?;
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+}
+
+/// A row of the page menu. Commands are browser commands (see modules::commands).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEntry {
+    Action(ContextAction),
+    Command(&'static str),
+    Separator,
+}
+
+/// The menu for `target`: link and image actions, or page navigation if it has neither.
+pub fn entries(target: &ContextTarget) -> Vec<MenuEntry> {
+    use ContextAction::*;
+
+    let mut entries = Vec::new();
+    if target.link.is_some() {
+        let actions = [OpenLinkInNewTab, OpenLinkInBackgroundTab, OpenLinkInNewWindow, OpenLinkInPrivateWindow];
+        entries.extend(actions.map(MenuEntry::Action));
+        entries.extend([MenuEntry::Action(CopyCleanLink), MenuEntry::Separator]);
+    }
+    if target.image.is_some() {
+        entries.extend([OpenImageInNewTab, SaveImage, CopyImageAddress].map(MenuEntry::Action));
+        entries.push(MenuEntry::Separator);
+    }
+    if entries.is_empty() {
+        entries.extend(["go_back", "go_forward", "reload"].map(MenuEntry::Command));
+        entries.push(MenuEntry::Separator);
+    }
+    entries.push(MenuEntry::Action(InspectElement));
+    entries
+}
+
+/// The target of the last menu shown.
+pub fn target() -> Option<ContextTarget> {
+    TARGET.lock().unwrap().clone()
+}
+
+/// A link without its tracking parameters, whether or not clicked links are cleaned.
+pub fn clean_link(link: &Url) -> String {
+    tracking_params::strip(link).map_or_else(|| link.to_string(), |(cleaned, _)| cleaned.to_string())
+}
+
+/// The name offered for a saved image: the last part of its path.
+pub fn image_file_name(image: &Url) -> String {
+    let name = image.path_segments().and_then(|segments| segments.filter(|s| !s.is_empty()).last()).unwrap_or("");
+    let name: String = name.chars().map(|c| if r#"\/:*?"<>|"#.contains(c) { '_' } else { c }).collect();
+    if name.is_empty() {
+        "image".to_string()
+    } else {
+        name
+    }
+}
+
+/// Only web links may be opened in a new window from page content.
+pub fn parse_link(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        other => Err(format!("Refusing to open '{}' link in a new window", other)),
+    }
+}
+
+/// Opens a link in a standalone browser window, set up like a tab (see
+/// modules::browsing_webview). Private windows use a non-persistent (incognito)
+/// data store, so cookies and storage are discarded when the window closes, and
+/// links they open in a new window get a private window too.
+pub fn open_link_window(app: &AppHandle, url: &str, private: bool) -> Result<String, String> {
+    let parsed = parse_link(url)?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_nanos();
+    let label = window_label(private, nanos);
+
+    println!("[ContextMenu] Opening {} in {} window", parsed, if private { "private" } else { "new" });
+
+    let (title, store) =
+        if private { ("Private Window", DataStore::Private) } else { ("Sovereign Browser", DataStore::Profile) };
+    let open_new = move |app: &AppHandle, url: String| {
+        if !private {
+            let _ = app.emit_to("main", "request-open-url", url);
+        } else if let Err(e) = open_link_window(app, &url, true) {
+            eprintln!("[ContextMenu] Failed to open private window: {}", e);
+        }
+    };
+    let window = browsing_webview::open_window(app, &label, title, parsed, store, open_new, |_| true)?;
+    if private {
+        // Nothing from a private window is handed off to other devices
+        let app = app.clone();
+        window.on_window_event(move |event| {
+            if let WindowEvent::Focused(true) = event {
+                handoff::clear(&app);
+            }
+        });
+    }
+
+    Ok(label)
+}
+
+/// Asks where to save `image`, then downloads it there.
+pub fn save_image(app: &AppHandle, image: Url) {
+    use tauri_plugin_dialog::DialogExt;
+
+    let h = app.clone();
+    app.dialog().file().set_file_name(image_file_name(&image)).save_file(move |path| {
+        let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+            return;
+        };
+        tauri::async_runtime::spawn(async move {
+            let Some(state) = h.try_state::<AppState>() else {
+                return;
+            };
+            match download(&state, &image, &path).await {
+                Ok(size) => println!("[ContextMenu] Saved image to {} ({} bytes)", path.display(), size),
+                Err(e) => eprintln!("[ContextMenu] Failed to save {}: {}", image, e),
+            }
+        });
+    });
+}
+
+async fn download(state: &AppState, url: &Url, path: &Path) -> Result<usize, BrowserError> {
+    if offline::blocks(state, url.as_str()) {
+        return Err(BrowserError::NotAllowed("The browser is offline".to_string()));
+    }
+    let client = proxy::http_client(state, IMAGE_TIMEOUT)?;
+    let bytes = client.get(url.as_str()).send().await?.error_for_status()?.bytes().await?;
+    std::fs::write(path, &bytes)?;
+    Ok(bytes.len())
+}
+
+/// From `PAGE_SCRIPT`: shows the page menu for what was right-clicked.
+#[tauri::command]
+pub fn show_context_menu(
+    webview: tauri::Webview,
+    link: Option<String>,
+    image: Option<String>,
+) -> Result<(), BrowserError> {
+    let target = ContextTarget {
+        webview_label: webview.label().to_string(),
+        link: link.as_deref().and_then(|l| parse_link(l).ok()),
+        image: image.as_deref().and_then(|i| parse_link(i).ok()),
+    };
+    let app = webview.app_handle();
+    let menu = Menu::new(app)?;
+    for entry in entries(&target) {
+        match entry {
+            MenuEntry::Action(action) => {
+                menu.append(&MenuItemBuilder::with_id(action.menu_id(), action.title()).build(app)?)?
+            }
+            MenuEntry::Command(id) => {
+                let title = commands::find(id).map_or(id, |c| c.title);
+                menu.append(&MenuItemBuilder::with_id(id, title).build(app)?)?
+            }
+            MenuEntry::Separator => menu.append(&PredefinedMenuItem::separator(app)?)?,
+        }
+    }
+    *TARGET.lock().unwrap() = Some(target);
+    webview.window().popup_menu(&menu)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_disposition_deserialize() {
+        let d: LinkDisposition = serde_json::from_str("\"private_window\"").unwrap();
+        assert_eq!(d, LinkDisposition::PrivateWindow);
+        assert!(d.opens_window());
+        assert!(d.is_private());
+        assert!(!LinkDisposition::NewTab.opens_window());
+    }
+
+    #[test]
+    fn test_window_label() {
+        assert_eq!(window_label(false, 42), "window-42");
+        assert_eq!(window_label(true, 42), "private-window-42");
+    }
+
+    #[test]
+    fn test_menu_ids_round_trip() {
+        for action in ContextAction::ALL {
+            assert_eq!(ContextAction::from_menu_id(&action.menu_id()), Some(action));
+        }
+        assert_eq!(ContextAction::from_menu_id("save_image"), None);
+        assert_eq!(ContextAction::from_menu_id("context_menu:missing"), None);
+    }
+
+    #[test]
+    fn test_entries() {
+        let link = Url::parse("https://example.com/a").ok();
+        let page = entries(&ContextTarget::default());
+        assert_eq!(page[0], MenuEntry::Command("go_back"));
+        assert_eq!(page.last(), Some(&MenuEntry::Action(ContextAction::InspectElement)));
+
+        let linked_image = entries(&ContextTarget { link: link.clone(), image: link, ..Default::default() });
+        assert!(linked_image.contains(&MenuEntry::Action(ContextAction::OpenLinkInBackgroundTab)));
+        assert!(linked_image.contains(&MenuEntry::Action(ContextAction::SaveImage)));
+        assert!(!linked_image.contains(&MenuEntry::Command("go_back")));
+
+        // Every command is in the registry
+        for entry in page {
+            if let MenuEntry::Command(id) = entry {
+                assert!(commands::find(id).is_some(), "{}", id);
+            }
+        }
+    }
+
+    #[rstest]
+    #[case("https://example.com/a?utm_source=x&id=3", "https://example.com/a?id=3")]
+    #[case("https://example.com/a?fbclid=x", "https://example.com/a")]
+    #[case("https://example.com/a?id=3", "https://example.com/a?id=3")]
+    fn test_clean_link(#[case] link: &str, #[case] expected: &str) {
+        assert_eq!(clean_link(&Url::parse(link).unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case("https://example.com/img/cat.png?w=200", "cat.png")]
+    #[case("https://example.com/img/", "img")]
+    #[case("https://example.com/", "image")]
+    #[case("https://example.com/a:b*.jpg", "a_b_.jpg")]
+    fn test_image_file_name(#[case] url: &str, #[case] expected: &str) {
+        assert_eq!(image_file_name(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_parse_link_rejects_non_web_schemes() {
+        assert!(parse_link("https://example.com/a").is_ok());
+        assert!(parse_link("javascript:alert(1)").is_err());
+        assert!(parse_link("file:///etc/passwd").is_err());
+    }
+}
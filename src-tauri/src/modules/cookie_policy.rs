@@ -0,0 +1,174 @@
+// Third-party cookie policy.
+//
+// Sites are registrable domains ("eTLD+1") by the Public Suffix List, so
+// "news.bbc.co.uk" and "bbc.co.uk" are one site but "alice.github.io" and
+// "bob.github.io" are two.
+//
+// macOS: WebKit enforces the policy itself through a `block-cookies` content rule
+// scoped to third-party loads (see `safari_rules`).
+// Windows: the WebView2 request filter strips the Cookie header from third-party
+// requests (see channel_blocking::install_webview2_filter).
+// Linux: WebKitGTK's cookie accept policy (see `apply_accept_policy`). It's shared
+// by every tab, so exceptions don't apply there.
+
+use url::Url;
+
+const SAFARI_RULE_LIST_ID: &str = "SovereignBrowserThirdPartyCookies";
+
+/// Identifier for the macOS content rule list, kept apart from the adblock list.
+pub fn safari_rule_list_id() -> &'static str {
+    SAFARI_RULE_LIST_ID
+}
+
+/// Registrable domain ("eTLD+1") of a host. IP addresses, public suffixes and
+/// single-label hosts are their own site.
+pub fn site_of(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+    psl::domain_str(&host).map_or_else(|| host.clone(), str::to_string)
+}
+
+fn site_of_url(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(site_of)
+}
+
+/// True when `request_url` belongs to a different site than the page that initiated it.
+/// Unknown initiators are treated as first-party so top-level navigations keep working.
+pub fn is_third_party(request_url: &str, initiator_url: &str) -> bool {
+    match (site_of_url(request_url), site_of_url(initiator_url)) {
+        (Some(request), Some(initiator)) => request != initiator,
+        _ => false,
+    }
+}
+
+/// Whether cookies are kept off a request for `request_url` made by the page at `top_url`.
+pub fn strips_cookies(exceptions: &[String], request_url: &str, top_url: &str) -> bool {
+    is_third_party(request_url, top_url) && !is_excepted(exceptions, top_url)
+}
+
+/// Turns user input ("https://www.Example.com/login", "example.com") into the
+/// site stored in the exception list.
+pub fn normalize_site(input: &str) -> Option<String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+    let url = Url::parse(&with_scheme).ok()?;
+    let host = url.host_str()?;
+    if !host.contains('.') {
+        return None;
+    }
+    Some(site_of(host))
+}
+
+/// Whether third-party cookies are allowed on the page at `page_url`.
+pub fn is_excepted(exceptions: &[String], page_url: &str) -> bool {
+    site_of_url(page_url).is_some_and(|site| exceptions.iter().any(|e| e == &site))
+}
+
+/// WKContentRuleList JSON blocking cookies on third-party loads, except on
+/// pages belonging to an excepted site.
+pub fn safari_rules(exceptions: &[String]) -> String {
+    let mut trigger = serde_json::json!({
+        "url-filter": ".*",
+        "load-type": ["third-party"],
+    });
+    if !exceptions.is_empty() {
+        // "*" prefix matches the site and all of its subdomains
+        let domains: Vec<String> = exceptions.iter().map(|d| format!("*{}", d)).collect();
+        trigger["unless-domain"] = serde_json::json!(domains);
+    }
+
+    serde_json::json!([{
+        "trigger": trigger,
+        "action": { "type": "block-cookies" },
+    }])
+    .to_string()
+}
+
+/// Sets WebKitGTK's cookie accept policy, which the tab shares with every other tab.
+#[cfg(target_os = "linux")]
+pub fn apply_accept_policy(webview: &tauri::Webview, block_third_party: bool) {
+    use webkit2gtk::{CookieAcceptPolicy, CookieManagerExt, WebViewExt, WebsiteDataManagerExt};
+
+    let policy = if block_third_party { CookieAcceptPolicy::NoThirdParty } else { CookieAcceptPolicy::Always };
+    let result = webview.with_webview(move |platform| {
+        match platform.inner().website_data_manager().and_then(|m| m.cookie_manager()) {
+            Some(cookies) => cookies.set_accept_policy(policy),
+            None => eprintln!("[Cookies] No cookie manager for the webview"),
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[Cookies] Failed to set the cookie policy: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("example.com", "example.com")]
+    #[case("www.example.com", "example.com")]
+    #[case("a.b.example.com", "example.com")]
+    #[case("news.bbc.co.uk", "bbc.co.uk")]
+    #[case("shop.example.com.au", "example.com.au")]
+    #[case("alice.github.io", "alice.github.io")]
+    #[case("github.io", "github.io")]
+    #[case("localhost", "localhost")]
+    #[case("127.0.0.1", "127.0.0.1")]
+    fn test_site_of(#[case] host: &str, #[case] expected: &str) {
+        assert_eq!(site_of(host), expected);
+    }
+
+    #[test]
+    fn test_is_third_party() {
+        assert!(is_third_party("https://tracker.net/pixel.gif", "https://example.com/page"));
+        assert!(!is_third_party("https://cdn.example.com/app.js", "https://www.example.com/"));
+        assert!(!is_third_party("https://example.com/", "not a url"));
+        // Different sites under a public suffix
+        assert!(is_third_party("https://bob.github.io/", "https://alice.github.io/"));
+    }
+
+    #[test]
+    fn test_strips_cookies() {
+        let exceptions = vec!["example.com".to_string()];
+        assert!(strips_cookies(&[], "https://tracker.net/pixel.gif", "https://example.com/"));
+        assert!(!strips_cookies(&exceptions, "https://tracker.net/pixel.gif", "https://www.example.com/"));
+        assert!(!strips_cookies(&[], "https://cdn.example.com/app.js", "https://example.com/"));
+    }
+
+    #[rstest]
+    #[case("https://www.Example.com/login", Some("example.com"))]
+    #[case("mail.example.co.uk", Some("example.co.uk"))]
+    #[case("  ", None)]
+    #[case("intranet", None)]
+    fn test_normalize_site(#[case] input: &str, #[case] expected: Option<&str>) {
+        assert_eq!(normalize_site(input).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_is_excepted() {
+        let exceptions = vec!["example.com".to_string()];
+        assert!(is_excepted(&exceptions, "https://login.example.com/"));
+        assert!(!is_excepted(&exceptions, "https://other.com/"));
+    }
+
+    #[test]
+    fn test_safari_rules() {
+        let rules: serde_json::Value = serde_json::from_str(&safari_rules(&[])).unwrap();
+        assert_eq!(rules[0]["action"]["type"], "block-cookies");
+        assert!(rules[0]["trigger"].get("unless-domain").is_none());
+
+        let rules: serde_json::Value = serde_json::from_str(&safari_rules(&["example.com".to_string()])).unwrap();
+        assert_eq!(rules[0]["trigger"]["unless-domain"][0], "*example.com");
+    }
+}
@@ -0,0 +1,366 @@
+// Data saver.
+//
+// With `Settings.data_saver` on (always, or only while the OS reports a metered
+// connection) tabs skip heavy media:
+// - Windows/Linux: audio and video requests are refused on the interception path,
+//   and so are images whose response announces more than LARGE_IMAGE_BYTES
+//   (Linux only: WebView2's request filter runs before there is a response).
+// - macOS: a content rule list blocks media loads; WebKit has no response hook, so
+//   images are only lazy-loaded.
+// - Everywhere, `page_script` makes images and iframes lazy-load, stops media
+//   preloading and, with `Settings.data_saver_placeholders`, swaps images that
+//   failed to load for a click-to-load placeholder.
+//
+// Sites in `Settings.data_saver_exceptions` load everything. The size a refused
+// response announced is added to the saved-bytes counter in block_stats; requests
+// refused before any response can't be measured and aren't counted.
+//
+// The metered state comes from the connection profile's cost on Windows, from
+// NetworkManager on Linux and from an NWPathMonitor (expensive or Low Data Mode
+// paths) on macOS, refreshed every METERED_POLL. The page script and the macOS
+// rules are fixed when a tab is created.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::cookie_policy;
+use crate::modules::frames::RequestContext;
+use crate::settings::Settings;
+use crate::state::AppState;
+
+pub const LARGE_IMAGE_BYTES: u64 = 200 * 1024;
+const METERED_POLL: Duration = Duration::from_secs(60);
+const SAFARI_RULE_LIST_ID: &str = "SovereignBrowserDataSaver";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSaverMode {
+    #[default]
+    Off,
+    Metered, // Only while the connection is metered
+    Always,
+}
+
+/// Whether a response of this type and announced size is refused, and if so the
+/// bytes that saves (0 when unknown).
+pub fn verdict(request_type: &str, content_length: Option<u64>) -> Option<u64> {
+    match request_type {
+        "media" => Some(content_length.unwrap_or(0)),
+        "image" => content_length.filter(|len| *len > LARGE_IMAGE_BYTES),
+        _ => None,
+    }
+}
+
+/// NetworkManager's `Metered` property as printed by busctl ("u 1"): yes (1) and
+/// guess-yes (3) count as metered.
+#[cfg(any(target_os = "linux", test))]
+fn parse_nm_metered(output: &str) -> bool {
+    matches!(output.split_whitespace().nth(1), Some("1") | Some("3"))
+}
+
+#[cfg(target_os = "linux")]
+pub fn probe_metered() -> bool {
+    std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .is_some_and(|o| parse_nm_metered(&String::from_utf8_lossy(&o.stdout)))
+}
+
+#[cfg(windows)]
+pub fn probe_metered() -> bool {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    NetworkInformation::GetInternetConnectionProfile()
+        .and_then(|profile| profile.GetConnectionCost())
+        .and_then(|cost| cost.NetworkCostType())
+        .is_ok_and(|cost| cost == NetworkCostType::Fixed || cost == NetworkCostType::Variable)
+}
+
+/// macOS only reports path changes asynchronously, so a monitor started on the
+/// first probe keeps the latest answer. The first probe waits briefly for it.
+#[cfg(target_os = "macos")]
+pub fn probe_metered() -> bool {
+    use block::ConcreteBlock;
+    use std::ffi::c_void;
+    use std::sync::Once;
+
+    static START: Once = Once::new();
+    static KNOWN: AtomicBool = AtomicBool::new(false);
+    static METERED: AtomicBool = AtomicBool::new(false);
+
+    #[link(name = "Network", kind = "framework")]
+    extern "C" {
+        fn nw_path_monitor_create() -> *mut c_void;
+        fn nw_path_monitor_set_queue(monitor: *mut c_void, queue: *mut c_void);
+        fn nw_path_monitor_set_update_handler(monitor: *mut c_void, handler: &block::Block<(*mut c_void,), ()>);
+        fn nw_path_monitor_start(monitor: *mut c_void);
+        fn nw_path_is_expensive(path: *mut c_void) -> bool;
+        fn nw_path_is_constrained(path: *mut c_void) -> bool;
+    }
+    extern "C" {
+        fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut c_void;
+    }
+
+    START.call_once(|| unsafe {
+        // Never cancelled: the monitor lives as long as the app
+        let monitor = nw_path_monitor_create();
+        let handler = ConcreteBlock::new(|path: *mut c_void| {
+            let metered = nw_path_is_expensive(path) || nw_path_is_constrained(path);
+            METERED.store(metered, Ordering::Relaxed);
+            KNOWN.store(true, Ordering::Relaxed);
+        })
+        .copy();
+        nw_path_monitor_set_queue(monitor, dispatch_get_global_queue(0, 0));
+        nw_path_monitor_set_update_handler(monitor, &handler);
+        nw_path_monitor_start(monitor);
+    });
+
+    for _ in 0..20 {
+        if KNOWN.load(Ordering::Relaxed) {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
+    METERED.load(Ordering::Relaxed)
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+pub fn probe_metered() -> bool {
+    false
+}
+
+pub struct DataSaverManager {
+    metered: AtomicBool,
+    allowed: Mutex<HashSet<(String, String)>>, // (webview label, URL) clicked to load, used once
+}
+
+impl DataSaverManager {
+    pub fn new() -> Self {
+        Self { metered: AtomicBool::new(false), allowed: Mutex::new(HashSet::new()) }
+    }
+
+    pub fn is_active(&self, mode: DataSaverMode) -> bool {
+        match mode {
+            DataSaverMode::Off => false,
+            DataSaverMode::Metered => self.metered.load(Ordering::Relaxed),
+            DataSaverMode::Always => true,
+        }
+    }
+
+    /// Polls the metered state while the mode depends on it.
+    pub fn spawn_monitor(self: &Arc<Self>, app: AppHandle) {
+        let manager = self.clone();
+        std::thread::spawn(move || loop {
+            let watching = app
+                .try_state::<AppState>()
+                .is_some_and(|s| s.settings.read().unwrap().data_saver == DataSaverMode::Metered);
+            if watching {
+                let metered = probe_metered();
+                if manager.metered.swap(metered, Ordering::Relaxed) != metered {
+                    println!("[DataSaver] Connection is {}metered", if metered { "" } else { "not " });
+                }
+            }
+            std::thread::sleep(METERED_POLL);
+        });
+    }
+
+    /// Lets the next request of `webview_label` for `url` through.
+    pub fn allow_once(&self, webview_label: &str, url: &str) {
+        self.allowed.lock().unwrap().insert((webview_label.to_string(), url.to_string()));
+    }
+
+    fn take_allowed(&self, webview_label: &str, url: &str) -> bool {
+        self.allowed.lock().unwrap().remove(&(webview_label.to_string(), url.to_string()))
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.allowed.lock().unwrap().retain(|(label, _)| label != webview_label);
+    }
+}
+
+impl Default for DataSaverManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the data saver refuses a request, and if so the bytes that saves.
+pub fn should_refuse(
+    state: &AppState,
+    settings: &Settings,
+    webview_label: &str,
+    url: &str,
+    ctx: &RequestContext,
+    content_length: Option<u64>,
+) -> Option<u64> {
+    if !state.data_saver.is_active(settings.data_saver)
+        || cookie_policy::is_excepted(&settings.data_saver_exceptions, &ctx.top_url)
+    {
+        return None;
+    }
+    let saved = verdict(&ctx.request_type, content_length)?;
+    if state.data_saver.take_allowed(webview_label, url) {
+        return None;
+    }
+    Some(saved)
+}
+
+/// Lazy loading, no media preloading and click-to-load placeholders for images
+/// that failed to load.
+const PAGE_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const EXCEPTIONS = __EXCEPTIONS__;
+    const PLACEHOLDERS = __PLACEHOLDERS__;
+    const host = location.hostname.toLowerCase();
+    if (EXCEPTIONS.some(s => host === s || host.endsWith('.' + s))) return;
+
+    // Best effort: parser-inserted elements may have started loading already
+    function tame(el) {
+        if (el.tagName === 'IMG' || el.tagName === 'IFRAME') {
+            if (el.getAttribute('loading') !== 'lazy') el.setAttribute('loading', 'lazy');
+        } else if (el.tagName === 'VIDEO' || el.tagName === 'AUDIO') {
+            el.preload = 'none';
+            el.autoplay = false;
+        }
+    }
+    function scan(root) {
+        if (root.nodeType !== 1) return;
+        tame(root);
+        root.querySelectorAll('img,iframe,video,audio').forEach(tame);
+    }
+    new MutationObserver(mutations => mutations.forEach(m => m.addedNodes.forEach(scan)))
+        .observe(document, { subtree: true, childList: true });
+
+    if (!PLACEHOLDERS || !window.__TAURI__) return;
+    const invoke = window.__TAURI__.core.invoke;
+    document.addEventListener('error', (e) => {
+        const img = e.target;
+        if (!(img instanceof HTMLImageElement) || img.dataset.sovereignDataSaver) return;
+        const src = img.currentSrc || img.src;
+        if (!/^https?:/.test(src)) return;
+        img.dataset.sovereignDataSaver = 'placeholder';
+
+        const box = document.createElement('button');
+        box.type = 'button';
+        box.textContent = 'Load image';
+        box.title = src;
+        box.style.cssText = `width:${img.width || 160}px;height:${img.height || 90}px;` +
+            'min-width:80px;min-height:32px;border:1px dashed #888;background:#8881;color:#888;' +
+            'font:12px system-ui,sans-serif;cursor:pointer;';
+        box.addEventListener('click', async (ev) => {
+            ev.preventDefault();
+            ev.stopPropagation();
+            await invoke('data_saver_load_image', { url: src }).catch(() => {});
+            img.removeAttribute('srcset');
+            img.src = src;
+            box.replaceWith(img);
+        });
+        img.replaceWith(box);
+    }, true);
+})();
+"#;
+
+pub fn page_script(settings: &Settings) -> String {
+    PAGE_SCRIPT_TEMPLATE
+        .replace(
+            "__EXCEPTIONS__",
+            &serde_json::to_string(&settings.data_saver_exceptions).unwrap_or_else(|_| "[]".to_string()),
+        )
+        .replace("__PLACEHOLDERS__", if settings.data_saver_placeholders { "true" } else { "false" })
+}
+
+/// Identifier for the macOS content rule list, kept apart from the adblock list.
+pub fn safari_rule_list_id() -> &'static str {
+    SAFARI_RULE_LIST_ID
+}
+
+/// WKContentRuleList JSON blocking audio and video, except on excepted sites.
+pub fn safari_rules(exceptions: &[String]) -> String {
+    let mut trigger = serde_json::json!({
+        "url-filter": ".*",
+        "resource-type": ["media"],
+    });
+    if !exceptions.is_empty() {
+        let domains: Vec<String> = exceptions.iter().map(|d| format!("*{}", d)).collect();
+        trigger["unless-domain"] = serde_json::json!(domains);
+    }
+    serde_json::json!([{
+        "trigger": trigger,
+        "action": { "type": "block" },
+    }])
+    .to_string()
+}
+
+/// Click-to-load from a placeholder.
+#[tauri::command]
+pub fn data_saver_load_image(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+) -> Result<(), BrowserError> {
+    state.data_saver.allow_once(webview.label(), &url);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("media", None, Some(0))]
+    #[case("media", Some(5_000_000), Some(5_000_000))]
+    #[case("image", Some(LARGE_IMAGE_BYTES + 1), Some(LARGE_IMAGE_BYTES + 1))]
+    #[case("image", Some(LARGE_IMAGE_BYTES), None)]
+    #[case("image", None, None)]
+    #[case("script", Some(5_000_000), None)]
+    fn test_verdict(#[case] request_type: &str, #[case] content_length: Option<u64>, #[case] expected: Option<u64>) {
+        assert_eq!(verdict(request_type, content_length), expected);
+    }
+
+    #[rstest]
+    #[case("u 1", true)]
+    #[case("u 3\n", true)]
+    #[case("u 2", false)]
+    #[case("u 0", false)]
+    #[case("", false)]
+    fn test_parse_nm_metered(#[case] output: &str, #[case] expected: bool) {
+        assert_eq!(parse_nm_metered(output), expected);
+    }
+
+    #[test]
+    fn test_modes_and_allow_once() {
+        let manager = DataSaverManager::new();
+        assert!(!manager.is_active(DataSaverMode::Off));
+        assert!(manager.is_active(DataSaverMode::Always));
+        assert!(!manager.is_active(DataSaverMode::Metered));
+        manager.metered.store(true, Ordering::Relaxed);
+        assert!(manager.is_active(DataSaverMode::Metered));
+
+        manager.allow_once("webview-tab-1", "https://img.example/big.jpg");
+        assert!(!manager.take_allowed("webview-tab-2", "https://img.example/big.jpg"));
+        assert!(manager.take_allowed("webview-tab-1", "https://img.example/big.jpg"));
+        assert!(!manager.take_allowed("webview-tab-1", "https://img.example/big.jpg"));
+    }
+
+    #[test]
+    fn test_safari_rules() {
+        let rules: serde_json::Value = serde_json::from_str(&safari_rules(&["example.com".to_string()])).unwrap();
+        assert_eq!(rules[0]["trigger"]["resource-type"], serde_json::json!(["media"]));
+        assert_eq!(rules[0]["trigger"]["unless-domain"], serde_json::json!(["*example.com"]));
+        assert_eq!(rules[0]["action"]["type"], "block");
+    }
+}
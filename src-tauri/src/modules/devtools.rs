@@ -1,34 +1,334 @@
+// DevTools bridge.
+//
+// A small HTTP + WebSocket server on localhost. Over HTTP it serves chii's
+// target.js and the DevTools frontend, bundled in the app resources
+// (resources/devtools), so inspecting works offline and nothing is loaded from a
+// third-party host.
+//
+// Inspected pages run target.js, which connects back
+// over a WebSocket at /t/<token>/target/<id>; the DevTools frontend connects at
+// /c/<secret>/client/<id>?target=<target id>. Target IDs are the tabs' webview
+// labels, set by the loader before target.js runs. CDP messages are routed between
+// the two:
+// - client -> target: requests get a bridge-wide id, so the response goes back to
+//   the client that asked, with its own id restored. Messages for a target that
+//   isn't connected (yet) are queued.
+// - target -> client: responses go to the requesting client, events to every
+//   client of that target.
+//
+// Every tab is its own target, so each can have an inspector window of its own
+// (`devtools-<tab id>`), and connected targets are listed at /c/<secret>/json/list
+// in the format Chrome's remote debugging uses.
+//
+// Any page or local process can reach the port, so connections have to show they
+// were handed out by the app. The secret is random for each launch and only goes
+// to the inspector windows; an inspected page gets a token of its own (a keyed
+// hash of its target ID), so it can't attach to other tabs or list them.
+// Inspectors also have to come from the bridge's own frontend: a WebSocket from
+// any other origin is refused, and nothing is served with CORS headers.
+//
+// A page reload or navigation drops the target's socket. Clients stay attached; the
+// loader is re-run for inspected tabs once the new page has loaded, and when the
+// target reconnects it's sent the `*.enable` commands its clients had issued, so
+// events keep flowing, and the clients get DOM.documentUpdated to refetch the tree.
+
+use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tauri::async_runtime::spawn;
+use tauri::{AppHandle, Manager};
 use futures_util::{StreamExt, SinkExt};
+use serde_json::Value;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
 use tokio_tungstenite::tungstenite::Message;
 
-// A simple broadcaster that relays messages to all other connected clients
-// This effectively bridges Target <-> Frontend
-struct SharedState {
-    peers: Mutex<Vec<mpsc::UnboundedSender<Message>>>,
+use crate::modules::{fingerprint, totp};
+
+const MAX_QUEUED: usize = 1000; // Per target, while it's disconnected
+const FRONTEND_DIR: &str = "devtools/front_end"; // In the app resources
+
+type Peer = mpsc::UnboundedSender<Message>;
+
+/// Which side of the bridge a WebSocket is, from its handshake path.
+#[derive(Debug, PartialEq)]
+enum Route {
+    Target { id: String, url: String, title: String },
+    Client { target: String },
+}
+
+/// The route and the token it was requested with.
+fn parse_route(path_and_query: &str) -> Option<(String, Route)> {
+    let url = url::Url::parse(&format!("http://bridge{}", path_and_query)).ok()?;
+    let query = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.into_owned());
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    let route = match segments.as_slice() {
+        ["t", token, "target", id] if !id.is_empty() => (
+            token,
+            Route::Target {
+                id: id.to_string(),
+                url: query("url").unwrap_or_default(),
+                title: query("title").unwrap_or_default(),
+            },
+        ),
+        ["c", token, "client", _] => (token, Route::Client { target: query("target").filter(|t| !t.is_empty())? }),
+        _ => return None,
+    };
+    Some((route.0.to_string(), route.1))
+}
+
+fn forbidden() -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some("Forbidden".to_string()));
+    *response.status_mut() = StatusCode::FORBIDDEN;
+    response
+}
+
+#[derive(Default)]
+struct TargetEntry {
+    peer: Option<Peer>, // None while the page is (re)loading
+    url: String,
+    title: String,
+    queued: Vec<String>,
+    enabled: Vec<Value>, // `*.enable` commands forwarded so far, replayed on reconnect
+}
+
+struct ClientEntry {
+    target: String,
+    peer: Peer,
+}
+
+enum Pending {
+    Client { client: u64, id: Value },
+    Replay, // Sent by the bridge itself; the response is dropped
+}
+
+#[derive(Default)]
+struct Router {
+    targets: HashMap<String, TargetEntry>,
+    clients: HashMap<u64, ClientEntry>,
+    pending: HashMap<u64, (String, Pending)>, // bridge id -> (target, origin)
+    next_id: u64,
+    next_client: u64,
+}
+
+fn send_text(peer: &Peer, text: String) {
+    let _ = peer.send(Message::text(text));
+}
+
+impl Router {
+    fn connect_target(&mut self, id: &str, url: &str, title: &str, peer: Peer) {
+        let entry = self.targets.entry(id.to_string()).or_default();
+        entry.peer = Some(peer);
+        entry.url = url.to_string();
+        entry.title = title.to_string();
+        let replay = std::mem::take(&mut entry.enabled);
+        let queued = std::mem::take(&mut entry.queued);
+        let reconnected = !replay.is_empty();
+
+        for mut command in replay {
+            self.next_id += 1;
+            command["id"] = self.next_id.into();
+            self.pending.insert(self.next_id, (id.to_string(), Pending::Replay));
+            self.forward_to_target(id, command);
+        }
+        for text in queued {
+            match serde_json::from_str::<Value>(&text) {
+                Ok(message) if message.get("id").is_some() => self.forward_to_target(id, message),
+                _ => self.send_to_target(id, text),
+            }
+        }
+        if reconnected {
+            let event = serde_json::json!({ "method": "DOM.documentUpdated", "params": {} }).to_string();
+            for client in self.clients.values().filter(|c| c.target == id) {
+                send_text(&client.peer, event.clone());
+            }
+        }
+    }
+
+    /// Forgets the socket if it's still the target's current one, failing the
+    /// requests it won't answer.
+    fn disconnect_target(&mut self, id: &str, peer: &Peer) {
+        let Some(entry) = self.targets.get_mut(id) else {
+            return;
+        };
+        if !entry.peer.as_ref().is_some_and(|p| p.same_channel(peer)) {
+            return;
+        }
+        entry.peer = None;
+        let unanswered: Vec<u64> =
+            self.pending.iter().filter(|(_, (t, _))| t == id).map(|(bridge_id, _)| *bridge_id).collect();
+        for bridge_id in unanswered {
+            if let Some((_, Pending::Client { client, id: request_id })) = self.pending.remove(&bridge_id) {
+                if let Some(client) = self.clients.get(&client) {
+                    let error =
+                        serde_json::json!({ "id": request_id, "error": { "code": -32000, "message": "Target closed" } });
+                    send_text(&client.peer, error.to_string());
+                }
+            }
+        }
+        self.remove_if_unused(id);
+    }
+
+    fn connect_client(&mut self, target: &str, peer: Peer) -> u64 {
+        self.targets.entry(target.to_string()).or_default();
+        self.next_client += 1;
+        self.clients.insert(self.next_client, ClientEntry { target: target.to_string(), peer });
+        self.next_client
+    }
+
+    fn disconnect_client(&mut self, client: u64) {
+        let Some(entry) = self.clients.remove(&client) else {
+            return;
+        };
+        self.pending.retain(|_, (_, p)| !matches!(p, Pending::Client { client: c, .. } if *c == client));
+        if !self.clients.values().any(|c| c.target == entry.target) {
+            // The next client starts a fresh session
+            if let Some(target) = self.targets.get_mut(&entry.target) {
+                target.enabled.clear();
+                target.queued.clear();
+            }
+        }
+        self.remove_if_unused(&entry.target);
+    }
+
+    fn remove_if_unused(&mut self, id: &str) {
+        let connected = self.targets.get(id).is_some_and(|t| t.peer.is_some());
+        if !connected && !self.clients.values().any(|c| c.target == id) {
+            self.targets.remove(id);
+        }
+    }
+
+    fn client_message(&mut self, client: u64, text: String) {
+        let Some(target) = self.clients.get(&client).map(|c| c.target.clone()) else {
+            return;
+        };
+        let Ok(mut message) = serde_json::from_str::<Value>(&text) else {
+            self.send_to_target(&target, text);
+            return;
+        };
+        let Some(id) = message.get("id").cloned() else {
+            self.send_to_target(&target, text);
+            return;
+        };
+        self.next_id += 1;
+        message["id"] = self.next_id.into();
+        self.pending.insert(self.next_id, (target.clone(), Pending::Client { client, id }));
+        self.forward_to_target(&target, message);
+    }
+
+    /// Sends a request to the target, remembering `*.enable` commands for replay.
+    fn forward_to_target(&mut self, target: &str, message: Value) {
+        let connected = self.targets.get(target).is_some_and(|t| t.peer.is_some());
+        let method = message.get("method").and_then(Value::as_str).unwrap_or_default().to_string();
+        if connected && method.ends_with(".enable") {
+            if let Some(entry) = self.targets.get_mut(target) {
+                let mut command = message.clone();
+                if let Some(object) = command.as_object_mut() {
+                    object.remove("id");
+                }
+                entry.enabled.retain(|c| c.get("method").and_then(Value::as_str) != Some(method.as_str()));
+                entry.enabled.push(command);
+            }
+        }
+        self.send_to_target(target, message.to_string());
+    }
+
+    fn send_to_target(&mut self, target: &str, text: String) {
+        let Some(entry) = self.targets.get_mut(target) else {
+            return;
+        };
+        match &entry.peer {
+            Some(peer) => send_text(peer, text),
+            None if entry.queued.len() < MAX_QUEUED => entry.queued.push(text),
+            None => {}
+        }
+    }
+
+    fn target_message(&mut self, target: &str, text: String) {
+        let response_id = serde_json::from_str::<Value>(&text)
+            .ok()
+            .filter(|m| m.get("method").is_none())
+            .and_then(|m| Some((m.get("id")?.as_u64()?, m)));
+        let Some((bridge_id, mut message)) = response_id else {
+            // Events go to every client of the target
+            for client in self.clients.values().filter(|c| c.target == target) {
+                send_text(&client.peer, text.clone());
+            }
+            return;
+        };
+        if let Some((_, Pending::Client { client, id })) = self.pending.remove(&bridge_id) {
+            if let Some(client) = self.clients.get(&client) {
+                message["id"] = id;
+                send_text(&client.peer, message.to_string());
+            }
+        }
+    }
+
+    fn is_inspected(&self, target: &str) -> bool {
+        self.clients.values().any(|c| c.target == target)
+    }
+
+    /// Connected targets as (id, url, title), by id.
+    fn connected_targets(&self) -> Vec<(&str, &str, &str)> {
+        let mut targets: Vec<_> = self
+            .targets
+            .iter()
+            .filter(|(_, t)| t.peer.is_some())
+            .map(|(id, t)| (id.as_str(), t.url.as_str(), t.title.as_str()))
+            .collect();
+        targets.sort();
+        targets
+    }
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "js" | "mjs" => "application/javascript",
+        "css" => "text/css",
+        "json" | "map" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "avif" => "image/avif",
+        "woff2" => "font/woff2",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn not_found() -> (&'static str, &'static str, Vec<u8>) {
+    let body = "<!DOCTYPE html><title>DevTools</title><p>This DevTools file isn't bundled with this build. \
+                See resources/devtools/README.md.</p>";
+    ("404 Not Found", "text/html; charset=utf-8", body.as_bytes().to_vec())
+}
+
+/// Where the bundled frontend is installed.
+pub fn frontend_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.path().resource_dir().ok().map(|d| d.join(FRONTEND_DIR))
 }
 
 pub struct DevToolsManager {
     port: u16,
-    target_js: String, 
-    state: Arc<SharedState>,
+    secret: String, // For this launch; see the module docs
+    target_js: String,
+    frontend_dir: Option<PathBuf>, // chii's front_end directory, served at /front_end/
+    router: Arc<Mutex<Router>>,
 }
 
 impl DevToolsManager {
-    pub fn new(port: u16) -> Self {
+    pub fn new(port: u16, frontend_dir: Option<PathBuf>) -> Self {
         let js_content = include_str!("assets/target.js");
-        
-        Self { 
+
+        Self {
             port,
+            secret: fingerprint::generate_secret(),
             target_js: js_content.to_string(),
-            state: Arc::new(SharedState {
-                peers: Mutex::new(Vec::new()),
-            })
+            frontend_dir,
+            router: Arc::new(Mutex::new(Router::default())),
         }
     }
 
@@ -47,7 +347,7 @@ impl DevToolsManager {
             };
 
             println!("[DevTools] Bridge listening on http://{}", addr);
-            
+
             while let Ok((stream, addr)) = listener.accept().await {
                 let manager_clone = manager.clone();
                 spawn(async move {
@@ -60,106 +360,208 @@ impl DevToolsManager {
     }
 
     async fn handle_connection(&self, mut stream: TcpStream, _addr: SocketAddr) -> std::io::Result<()> {
-        let mut buffer = [0; 1024]; 
+        let mut buffer = [0; 4096];
 
         // Peek to distinguish HTTP vs WS
         let n = stream.peek(&mut buffer).await?;
         let request_str = String::from_utf8_lossy(&buffer[..n]);
-        
-        if request_str.starts_with("GET /target.js") {
-             let mut devnull = [0; 1024];
-             let _ = stream.read(&mut devnull).await?; // Consume some bytes
-             
-             let response = format!(
-                 "HTTP/1.1 200 OK\r\nContent-Type: application/javascript\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\n\r\n{}",
-                 self.target_js.len(),
-                 self.target_js
-             );
-             stream.write_all(response.as_bytes()).await?;
-             stream.flush().await?;
-             return Ok(());
-        } 
-        
-        // WebSocket Upgrade
-        match tokio_tungstenite::accept_async(stream).await {
-            Ok(ws_stream) => {
-                 let (mut write, mut read) = ws_stream.split();
-                 
-                 // Channel for sending messages TO this client
-                 let (tx, mut rx) = mpsc::unbounded_channel();
-                 
-                 // Register peer
-                 {
-                     let mut peers = self.state.peers.lock().unwrap();
-                     peers.push(tx.clone());
-                 }
-                 
-                 // Spawn task to forward messages from RX channel -> WS Write
-                 let _forward_task = spawn(async move {
-                     while let Some(msg) = rx.recv().await {
-                         if let Err(_) = write.send(msg).await {
-                             break;
-                         }
-                     }
-                 });
-
-                 // Read loop: broadcast messages from THIS client -> ALL Peers
-                 while let Some(msg) = read.next().await {
-                     if let Ok(m) = msg {
-                         if m.is_text() || m.is_binary() {
-                             // Broadcast
-                             // Note: In a real implementation we would route by ID.
-                             // For now, we broadcast to everyone (which includes self, unless we filter).
-                             // Ideally, we filter out self.
-                             // But peers is locked. 
-                             // Optimized: Broadcast to everyone. The client usually ignores its own echoes if IDs match,
-                             // or we can implement filtering.
-                             // Let's filter by checking sender validity? No easy way with unbounded channel equality.
-                             // Let's just broadcast to all.
-                             
-                             let peers = self.state.peers.lock().unwrap();
-                             for peer in peers.iter() {
-                                 // Simple pointer check is hard with channels.
-                                 // We just send to all using clone.
-                                 // If echo is an issue, we can address it. 
-                                 // Chii/Weinre usually handle echoes fine or use rooms.
-                                 if !peer.same_channel(&tx) {
-                                     let _ = peer.send(m.clone());
-                                 }
-                             }
-                         }
-                     } else {
-                         break;
-                     }
-                 }
-                 
-                 // Cleanup
-                 {
-                     let mut peers = self.state.peers.lock().unwrap();
-                     peers.retain(|peer| !peer.same_channel(&tx));
-                 }
-            },
-            Err(_e) => {
-                // Not a websocket
+
+        if !request_str.to_ascii_lowercase().contains("upgrade: websocket") {
+            let path = request_str.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let _ = stream.read(&mut buffer).await?; // Consume the request
+            return self.serve_http(&mut stream, &path).await;
+        }
+
+        // WebSocket Upgrade; the handshake path says which side this is
+        let mut route = None;
+        let handshake = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+            let origin = request.headers().get("origin").and_then(|o| o.to_str().ok());
+            match parse_route(&request.uri().to_string()) {
+                Some((token, requested)) if self.authorized(&token, &requested, origin) => {
+                    route = Some(requested);
+                    Ok(response)
+                }
+                _ => {
+                    println!("[DevTools] Refused connection to {} from {:?}", request.uri().path(), origin);
+                    Err(forbidden())
+                }
+            }
+        })
+        .await;
+        let (Ok(ws_stream), Some(route)) = (handshake, route) else {
+            return Ok(()); // Not a websocket, or refused
+        };
+
+        let (mut write, mut read) = ws_stream.split();
+
+        // Channel for sending messages TO this socket
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let client = match &route {
+            Route::Target { id, url, title } => {
+                println!("[DevTools] Target connected: {} ({})", id, url);
+                self.router.lock().unwrap().connect_target(id, url, title, tx.clone());
+                None
+            }
+            Route::Client { target } => {
+                println!("[DevTools] Inspector attached to {}", target);
+                Some(self.router.lock().unwrap().connect_client(target, tx.clone()))
+            }
+        };
+
+        while let Some(Ok(msg)) = read.next().await {
+            let text = match msg {
+                Message::Text(text) => text.as_str().to_string(),
+                Message::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let mut router = self.router.lock().unwrap();
+            match (&route, client) {
+                (_, Some(client)) => router.client_message(client, text),
+                (Route::Target { id, .. }, None) => router.target_message(id, text),
+                _ => {}
             }
         }
-        
+
+        // Cleanup
+        let mut router = self.router.lock().unwrap();
+        match (&route, client) {
+            (_, Some(client)) => router.disconnect_client(client),
+            (Route::Target { id, .. }, None) => router.disconnect_target(id, &tx),
+            _ => {}
+        }
         Ok(())
     }
 
+    /// Whether a WebSocket for `route` may connect with `token`, from a page of `origin`.
+    fn authorized(&self, token: &str, route: &Route, origin: Option<&str>) -> bool {
+        match route {
+            Route::Target { id, .. } => token == self.target_token(id),
+            // No origin: not from a browser page
+            Route::Client { .. } => token == self.secret && origin.map_or(true, |o| o == self.origin()),
+        }
+    }
+
+    fn origin(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// What the page in `webview_label` connects with; see the module docs.
+    fn target_token(&self, webview_label: &str) -> String {
+        let digest = totp::hmac_sha1(self.secret.as_bytes(), webview_label.as_bytes());
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Plain HTTP: the page-side script, target discovery and the bundled frontend.
+    async fn serve_http(&self, stream: &mut TcpStream, path: &str) -> std::io::Result<()> {
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let list_path = |name: &str| format!("/c/{}/{}", self.secret, name);
+        // The script holds no secrets; it's under the page's token so it connects back with it
+        let (status, content_type, body) = if path.starts_with("/t/") && path.ends_with("/target.js") {
+            ("200 OK", "application/javascript", self.target_js.clone().into_bytes())
+        } else if path == list_path("json") || path == list_path("json/list") {
+            ("200 OK", "application/json", self.target_list().to_string().into_bytes())
+        } else if let Some(file) = path.strip_prefix("/front_end/").and_then(|p| self.frontend_file(p)) {
+            match tokio::fs::read(&file).await {
+                Ok(bytes) => ("200 OK", content_type(&file), bytes),
+                Err(_) => not_found(),
+            }
+        } else {
+            not_found()
+        };
+
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            content_type,
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(&body).await?;
+        stream.flush().await
+    }
+
+    /// A file of the bundled frontend, or None for paths that leave its directory.
+    fn frontend_file(&self, relative: &str) -> Option<PathBuf> {
+        let dir = self.frontend_dir.as_ref()?;
+        let safe = relative.split('/').all(|segment| {
+            !segment.is_empty() && segment != ".." && segment != "." && !segment.contains(['\\', '%'])
+        });
+        safe.then(|| dir.join(relative))
+    }
+
+    /// Host and path a frontend connects to for a tab, without the scheme.
+    fn client_address(&self, webview_label: &str) -> String {
+        format!("127.0.0.1:{}/c/{}/client/{}?target={}", self.port, self.secret, webview_label, webview_label)
+    }
+
+    /// The inspector page for a tab, served by the bridge itself.
+    pub fn frontend_url(&self, webview_label: &str) -> url::Url {
+        let mut url =
+            url::Url::parse(&format!("http://127.0.0.1:{}/front_end/chii_app.html", self.port)).expect("valid URL");
+        url.query_pairs_mut().append_pair("ws", &self.client_address(webview_label));
+        url
+    }
+
+    /// `/json/list`: the connected targets, as Chrome lists its pages.
+    fn target_list(&self) -> Value {
+        let router = self.router.lock().unwrap();
+        let targets: Vec<Value> = router
+            .connected_targets()
+            .into_iter()
+            .map(|(id, url, title)| {
+                serde_json::json!({
+                    "id": id,
+                    "type": "page",
+                    "title": title,
+                    "url": url,
+                    "description": "",
+                    "devtoolsFrontendUrl": self.frontend_url(id).as_str(),
+                    "webSocketDebuggerUrl": format!("ws://{}", self.client_address(id)),
+                })
+            })
+            .collect();
+        Value::Array(targets)
+    }
+
+    /// Whether an inspector is attached to the tab, so its pages should reconnect.
+    pub fn is_inspected(&self, webview_label: &str) -> bool {
+        self.router.lock().unwrap().is_inspected(webview_label)
+    }
+
+    /// Script that connects the page to the bridge as target `webview_label`.
+    pub fn loader_script(&self, webview_label: &str) -> String {
+        format!(
+            "if (window.__SOVEREIGN_LOAD_DEVTOOLS__) window.__SOVEREIGN_LOAD_DEVTOOLS__({}, {});",
+            serde_json::to_string(webview_label).unwrap_or_else(|_| "null".to_string()),
+            serde_json::to_string(&self.target_token(webview_label)).unwrap_or_else(|_| "null".to_string())
+        )
+    }
+
     pub fn get_bootstrapper(&self) -> String {
         format!(
             r#"
             (function() {{
                 if (window.__SOVEREIGN_DEVTOOLS_READY__) return;
                 window.__SOVEREIGN_DEVTOOLS_READY__ = true;
-                
-                window.__SOVEREIGN_LOAD_DEVTOOLS__ = function() {{
+
+                window.__SOVEREIGN_LOAD_DEVTOOLS__ = function(targetId, token) {{
                     if (document.getElementById('sovereign-devtools-script')) return;
+                    // target.js takes its target ID from here
+                    try {{ if (targetId) sessionStorage.setItem('chii-id', targetId); }} catch (e) {{}}
                     console.log('🔌 Sovereign: Connecting to DevTools Bridge...');
                     var script = document.createElement('script');
                     script.id = 'sovereign-devtools-script';
-                    script.src = 'http://127.0.0.1:{}/target.js'; 
+                    // target.js connects back under the directory it's loaded from
+                    script.src = 'http://127.0.0.1:{}/t/' + encodeURIComponent(token) + '/target.js';
                     document.head.appendChild(script);
                 }};
             }})();
@@ -168,3 +570,175 @@ impl DevToolsManager {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer() -> (Peer, mpsc::UnboundedReceiver<Message>) {
+        mpsc::unbounded_channel()
+    }
+
+    fn received(rx: &mut mpsc::UnboundedReceiver<Message>) -> Vec<Value> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|m| serde_json::from_str(m.to_text().unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_frontend_file() {
+        let manager = DevToolsManager::new(9222, Some(PathBuf::from("/res/devtools/front_end")));
+        assert_eq!(
+            manager.frontend_file("chii_app.html"),
+            Some(PathBuf::from("/res/devtools/front_end/chii_app.html"))
+        );
+        assert!(manager.frontend_file("core/sdk/sdk.js").is_some());
+        assert_eq!(manager.frontend_file("../../secret"), None);
+        assert_eq!(manager.frontend_file("core/%2e%2e/x"), None);
+        assert_eq!(manager.frontend_file("core//x"), None);
+        assert_eq!(DevToolsManager::new(9222, None).frontend_file("chii_app.html"), None);
+        assert_eq!(content_type(Path::new("a/b.js")), "application/javascript");
+    }
+
+    #[test]
+    fn test_frontend_url() {
+        let manager = DevToolsManager::new(9222, None);
+        let url = manager.frontend_url("webview-tab-1");
+        assert_eq!(url.host_str(), Some("127.0.0.1"));
+        let ws = url.query_pairs().find(|(k, _)| k == "ws").unwrap().1.into_owned();
+        assert_eq!(ws, format!("127.0.0.1:9222/c/{}/client/webview-tab-1?target=webview-tab-1", manager.secret));
+    }
+
+    #[test]
+    fn test_secret_differs_per_launch() {
+        let (a, b) = (DevToolsManager::new(9222, None), DevToolsManager::new(9222, None));
+        assert_eq!(a.secret.len(), 64);
+        assert_ne!(a.secret, b.secret);
+        assert_ne!(a.target_token("webview-tab-1"), b.target_token("webview-tab-1"));
+    }
+
+    #[test]
+    fn test_authorized() {
+        let manager = DevToolsManager::new(9222, None);
+        let target = |id: &str| Route::Target { id: id.to_string(), url: String::new(), title: String::new() };
+        let client = Route::Client { target: "webview-tab-1".to_string() };
+        let token = manager.target_token("webview-tab-1");
+
+        assert!(manager.authorized(&token, &target("webview-tab-1"), Some("https://example.com")));
+        // A page's token is only good for its own tab
+        assert!(!manager.authorized(&token, &target("webview-tab-2"), Some("https://example.com")));
+        assert!(!manager.authorized(&token, &client, None));
+        assert!(!manager.authorized("", &target("webview-tab-1"), None));
+
+        assert!(manager.authorized(&manager.secret, &client, Some("http://127.0.0.1:9222")));
+        assert!(manager.authorized(&manager.secret, &client, None));
+        assert!(!manager.authorized(&manager.secret, &client, Some("https://evil.example")));
+        assert!(!manager.authorized(&manager.secret, &client, Some("http://127.0.0.1:9333")));
+    }
+
+    #[test]
+    fn test_target_list() {
+        let manager = DevToolsManager::new(9222, None);
+        let (tx, _rx) = peer();
+        let (other_tx, _other_rx) = peer();
+        {
+            let mut router = manager.router.lock().unwrap();
+            router.connect_target("webview-tab-2", "https://b.example/", "B", tx);
+            router.connect_target("webview-tab-1", "https://a.example/", "A", other_tx);
+            router.connect_client("webview-tab-3", peer().0); // Not loaded yet
+        }
+        let list = manager.target_list();
+        let ids: Vec<&str> = list.as_array().unwrap().iter().map(|t| t["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["webview-tab-1", "webview-tab-2"]);
+        assert_eq!(list[1]["title"], "B");
+        assert_eq!(list[1]["url"], "https://b.example/");
+        assert_eq!(
+            list[1]["webSocketDebuggerUrl"],
+            format!("ws://127.0.0.1:9222/c/{}/client/webview-tab-2?target=webview-tab-2", manager.secret)
+        );
+    }
+
+    #[test]
+    fn test_parse_route() {
+        assert_eq!(
+            parse_route("/t/ab12/target/webview-tab-1?url=https%3A%2F%2Fexample.com%2F&title=Example&favicon=x"),
+            Some((
+                "ab12".to_string(),
+                Route::Target {
+                    id: "webview-tab-1".to_string(),
+                    url: "https://example.com/".to_string(),
+                    title: "Example".to_string()
+                }
+            ))
+        );
+        assert_eq!(
+            parse_route("/c/cd34/client/abc?target=webview-tab-1"),
+            Some(("cd34".to_string(), Route::Client { target: "webview-tab-1".to_string() }))
+        );
+        assert_eq!(parse_route("/target/webview-tab-1"), None);
+        assert_eq!(parse_route("/client/abc?target=webview-tab-1"), None);
+        assert_eq!(parse_route("/c/cd34/client/abc"), None);
+        assert_eq!(parse_route("/other"), None);
+    }
+
+    #[test]
+    fn test_responses_go_to_the_requesting_client() {
+        let mut router = Router::default();
+        let (target_tx, mut target_rx) = peer();
+        let (a_tx, mut a_rx) = peer();
+        let (b_tx, mut b_rx) = peer();
+        router.connect_target("tab", "https://a.example/", "A", target_tx);
+        let a = router.connect_client("tab", a_tx);
+        let b = router.connect_client("tab", b_tx);
+
+        router.client_message(a, r#"{"id":1,"method":"Runtime.evaluate"}"#.to_string());
+        router.client_message(b, r#"{"id":1,"method":"Page.reload"}"#.to_string());
+        let sent = received(&mut target_rx);
+        assert_eq!(sent.len(), 2);
+        assert_ne!(sent[0]["id"], sent[1]["id"]);
+
+        router.target_message("tab", serde_json::json!({ "id": sent[1]["id"], "result": {} }).to_string());
+        router.target_message("tab", r#"{"method":"Runtime.consoleAPICalled","params":{}}"#.to_string());
+        assert_eq!(received(&mut a_rx), vec![serde_json::json!({ "method": "Runtime.consoleAPICalled", "params": {} })]);
+        assert_eq!(
+            received(&mut b_rx),
+            vec![
+                serde_json::json!({ "id": 1, "result": {} }),
+                serde_json::json!({ "method": "Runtime.consoleAPICalled", "params": {} })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queue_and_reconnect() {
+        let mut router = Router::default();
+        let (client_tx, mut client_rx) = peer();
+        let client = router.connect_client("tab", client_tx);
+        // The inspector can be faster than the page
+        router.client_message(client, r#"{"id":1,"method":"DOM.enable"}"#.to_string());
+
+        let (first_tx, mut first_rx) = peer();
+        router.connect_target("tab", "https://a.example/", "A", first_tx.clone());
+        let sent = received(&mut first_rx);
+        assert_eq!(sent[0]["method"], "DOM.enable");
+
+        // Reload: a pending request fails, the new target gets DOM.enable again
+        router.client_message(client, r#"{"id":2,"method":"DOM.getDocument"}"#.to_string());
+        router.disconnect_target("tab", &first_tx);
+        assert_eq!(received(&mut client_rx)[0]["error"]["message"], "Target closed");
+        assert!(router.is_inspected("tab"));
+
+        let (second_tx, mut second_rx) = peer();
+        router.connect_target("tab", "https://a.example/", "A", second_tx);
+        let replayed = received(&mut second_rx);
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0]["method"], "DOM.enable");
+        assert_eq!(received(&mut client_rx), vec![serde_json::json!({ "method": "DOM.documentUpdated", "params": {} })]);
+        // The replay's response stays in the bridge
+        router.target_message("tab", serde_json::json!({ "id": replayed[0]["id"], "result": {} }).to_string());
+        assert!(received(&mut client_rx).is_empty());
+
+        router.disconnect_client(client);
+        assert!(!router.is_inspected("tab"));
+    }
+}
@@ -0,0 +1,145 @@
+// Dock and taskbar icon: download progress and a badge count.
+//
+// Webviews report when a download starts and when it finishes, not how far
+// along it is, so the icon shows indeterminate progress while any download is
+// running. The badge counts running downloads or, when there are none, the
+// unread items mail and chat tabs show in their titles (see modules::badges).
+//
+// macOS: the Dock tile. Linux: the launcher entry, on desktops with the Unity
+// launcher API. Windows: taskbar progress only; a badge there is an overlay
+// icon drawn for each count, which isn't done.
+
+use std::sync::Mutex;
+use tauri::webview::DownloadEvent;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager, Webview};
+
+use crate::state::{AppState, Tab};
+
+/// What the icon shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DockState {
+    pub downloading: bool,
+    pub badge: Option<u32>,
+}
+
+impl DockState {
+    pub fn new(active_downloads: u32, unread: u32) -> Self {
+        let count = if active_downloads > 0 { active_downloads } else { unread };
+        Self { downloading: active_downloads > 0, badge: (count > 0).then_some(count) }
+    }
+}
+
+/// Unread items across all tabs.
+pub fn unread_total(tabs: &[Tab]) -> u32 {
+    tabs.iter().filter_map(|t| t.unread_count).fold(0, u32::saturating_add)
+}
+
+pub struct DockManager {
+    active_downloads: Mutex<u32>,
+    shown: Mutex<Option<DockState>>,
+}
+
+impl DockManager {
+    pub fn new() -> Self {
+        Self { active_downloads: Mutex::new(0), shown: Mutex::new(None) }
+    }
+
+    pub fn active_downloads(&self) -> u32 {
+        *self.active_downloads.lock().unwrap()
+    }
+
+    pub fn download_started(&self) {
+        *self.active_downloads.lock().unwrap() += 1;
+    }
+
+    pub fn download_finished(&self) {
+        let mut active = self.active_downloads.lock().unwrap();
+        *active = active.saturating_sub(1);
+    }
+
+    /// Records `next` as shown. False if it already was, so the icon is left alone.
+    fn replace(&self, next: DockState) -> bool {
+        self.shown.lock().unwrap().replace(next) != Some(next)
+    }
+}
+
+impl Default for DockManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Brings the icon up to date with the running downloads and `tabs`.
+pub fn update(app: &AppHandle, state: &AppState, tabs: &[Tab]) {
+    let next = DockState::new(state.dock.active_downloads(), unread_total(tabs));
+    if !state.dock.replace(next) {
+        return;
+    }
+    let Some(window) = app.get_window("main") else {
+        return;
+    };
+
+    let status = if next.downloading { ProgressBarStatus::Indeterminate } else { ProgressBarStatus::None };
+    if let Err(e) = window.set_progress_bar(ProgressBarState { status: Some(status), progress: None }) {
+        eprintln!("[Dock] Failed to set progress: {}", e);
+    }
+
+    #[cfg(not(windows))]
+    if let Err(e) = window.set_badge_count(next.badge.map(i64::from)) {
+        eprintln!("[Dock] Failed to set badge: {}", e);
+    }
+}
+
+/// Download handler for tab webviews: counts the download and lets it proceed.
+pub fn on_download(webview: &Webview, event: &DownloadEvent) -> bool {
+    let app = webview.app_handle();
+    let Some(state) = app.try_state::<AppState>() else {
+        return true;
+    };
+    match event {
+        DownloadEvent::Requested { url, .. } => {
+            println!("[Dock] Download started: {}", url);
+            state.dock.download_started();
+        }
+        DownloadEvent::Finished { url, success, .. } => {
+            println!("[Dock] Download {}: {}", if *success { "finished" } else { "failed" }, url);
+            state.dock.download_finished();
+        }
+        _ => return true,
+    }
+    update(app, &state, &state.tabs.lock().unwrap());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0, DockState { downloading: false, badge: None })]
+    #[case(0, 5, DockState { downloading: false, badge: Some(5) })]
+    #[case(2, 0, DockState { downloading: true, badge: Some(2) })]
+    #[case(1, 9, DockState { downloading: true, badge: Some(1) })]
+    fn test_dock_state(#[case] downloads: u32, #[case] unread: u32, #[case] expected: DockState) {
+        assert_eq!(DockState::new(downloads, unread), expected);
+    }
+
+    #[test]
+    fn test_download_count_never_underflows() {
+        let dock = DockManager::new();
+        dock.download_started();
+        dock.download_finished();
+        dock.download_finished();
+        assert_eq!(dock.active_downloads(), 0);
+    }
+
+    #[test]
+    fn test_replace_reports_changes_only() {
+        let dock = DockManager::new();
+        assert!(dock.replace(DockState::new(0, 0)));
+        assert!(!dock.replace(DockState::new(0, 0)));
+        assert!(dock.replace(DockState::new(0, 3)));
+    }
+}
@@ -0,0 +1,450 @@
+// DNS-over-HTTPS - a local proxy that resolves hostnames through a DoH resolver.
+//
+// Webviews can't be given a custom resolver, but they can be given a proxy. When
+// DoH is enabled every tab is created with `proxy_url` pointing at a small HTTP
+// proxy on 127.0.0.1 that looks hosts up via the resolver's JSON API and then
+// connects directly, so no plain-text DNS query reaches the ISP.
+//
+// The same local proxy carries manual proxy settings the webview can't express
+// (credentials, bypass list, forced direct); see `proxy`. Hosts sent to an
+// upstream proxy are resolved by that proxy, not by DoH.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::modules::proxy::{self, ProxyScheme, ProxySettings, WebviewProxy};
+
+const MAX_HEAD_LEN: usize = 16 * 1024;
+const MIN_TTL_SECS: u64 = 30;
+const MAX_TTL_SECS: u64 = 3600;
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Resolver endpoints use IP literals so resolving the resolver doesn't leak either
+const CLOUDFLARE_ENDPOINT: &str = "https://1.1.1.1/dns-query";
+const QUAD9_ENDPOINT: &str = "https://9.9.9.9:5053/dns-query";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DohMode {
+    #[default]
+    Off,
+    Cloudflare,
+    Quad9,
+    Custom, // Uses `Settings.doh_custom_url`
+}
+
+impl DohMode {
+    /// Resolver endpoint for this mode. Custom URLs must be https.
+    pub fn endpoint(&self, custom_url: Option<&str>) -> Option<String> {
+        match self {
+            Self::Off => None,
+            Self::Cloudflare => Some(CLOUDFLARE_ENDPOINT.to_string()),
+            Self::Quad9 => Some(QUAD9_ENDPOINT.to_string()),
+            Self::Custom => custom_url
+                .map(str::trim)
+                .filter(|u| u.starts_with("https://"))
+                .map(str::to_string),
+        }
+    }
+}
+
+// --- DNS JSON API (application/dns-json) ---
+
+#[derive(Debug, Deserialize)]
+struct DnsJsonResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsJsonAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DnsJsonAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    #[serde(rename = "TTL", default)]
+    ttl: u64,
+    data: String,
+}
+
+/// Extracts A/AAAA addresses and the smallest TTL. CNAME records in the chain are skipped.
+pub fn parse_dns_json(body: &str) -> Result<(Vec<IpAddr>, u64), String> {
+    let response: DnsJsonResponse = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    if response.status != 0 {
+        return Err(format!("Resolver returned DNS status {}", response.status));
+    }
+
+    let mut ttl = MAX_TTL_SECS;
+    let addrs: Vec<IpAddr> = response.answer.iter()
+        .filter(|a| a.record_type == 1 || a.record_type == 28)
+        .filter_map(|a| {
+            ttl = ttl.min(a.ttl);
+            a.data.parse().ok()
+        })
+        .collect();
+
+    Ok((addrs, ttl.clamp(MIN_TTL_SECS, MAX_TTL_SECS)))
+}
+
+pub struct DohResolver {
+    endpoint: RwLock<Option<String>>,
+    client: reqwest::Client,
+    cache: Mutex<HashMap<String, (Vec<IpAddr>, Instant)>>,
+}
+
+impl DohResolver {
+    pub fn new() -> Self {
+        Self {
+            endpoint: RwLock::new(None),
+            client: reqwest::Client::builder()
+                .timeout(RESOLVE_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Switch resolvers (or disable with None). Cached answers are dropped.
+    pub fn set_endpoint(&self, endpoint: Option<String>) {
+        *self.endpoint.write().unwrap() = endpoint;
+        self.cache.lock().unwrap().clear();
+    }
+
+    pub fn endpoint(&self) -> Option<String> {
+        self.endpoint.read().unwrap().clone()
+    }
+
+    pub async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, String> {
+        if let Ok(ip) = host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+        if host.eq_ignore_ascii_case("localhost") {
+            return Ok(vec![IpAddr::from([127, 0, 0, 1])]);
+        }
+
+        let host = host.to_lowercase();
+        if let Some((addrs, expires)) = self.cache.lock().unwrap().get(&host) {
+            if Instant::now() < *expires {
+                return Ok(addrs.clone());
+            }
+        }
+
+        let endpoint = self.endpoint().ok_or("DNS-over-HTTPS is disabled")?;
+        let mut addrs = Vec::new();
+        let mut ttl = MAX_TTL_SECS;
+        for record_type in ["A", "AAAA"] {
+            let body = self.client.get(&endpoint)
+                .query(&[("name", host.as_str()), ("type", record_type)])
+                .header("accept", "application/dns-json")
+                .send().await.map_err(|e| e.to_string())?
+                .text().await.map_err(|e| e.to_string())?;
+            let (found, found_ttl) = parse_dns_json(&body)?;
+            addrs.extend(found);
+            ttl = ttl.min(found_ttl);
+            if !addrs.is_empty() {
+                break; // IPv4 is enough; only ask for AAAA on v6-only hosts
+            }
+        }
+
+        if addrs.is_empty() {
+            return Err(format!("No addresses for {}", host));
+        }
+        self.cache.lock().unwrap().insert(host, (addrs.clone(), Instant::now() + Duration::from_secs(ttl)));
+        Ok(addrs)
+    }
+}
+
+impl Default for DohResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// --- Proxy ---
+
+#[derive(Debug, PartialEq)]
+pub struct ProxyRequest {
+    pub host: String,
+    pub port: u16,
+    /// CONNECT tunnel (https). Otherwise a plain http request to forward.
+    pub tunnel: bool,
+    /// For plain http: the request head rewritten to origin-form.
+    pub forward_head: Option<String>,
+}
+
+/// Parses a proxy request head ("CONNECT host:443 HTTP/1.1" or
+/// "GET http://host/path HTTP/1.1" plus headers).
+pub fn parse_request_head(head: &str) -> Result<ProxyRequest, String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().ok_or("Empty request")?;
+    let mut parts = request_line.split(' ');
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(t), Some(v)) => (m, t, v),
+        _ => return Err(format!("Malformed request line: {}", request_line)),
+    };
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = target.rsplit_once(':').ok_or("CONNECT target needs a port")?;
+        let port = port.parse().map_err(|_| "Invalid CONNECT port")?;
+        return Ok(ProxyRequest {
+            host: host.trim_matches(|c| c == '[' || c == ']').to_string(),
+            port,
+            tunnel: true,
+            forward_head: None,
+        });
+    }
+
+    let url = url::Url::parse(target).map_err(|e| e.to_string())?;
+    if url.scheme() != "http" {
+        return Err(format!("Unsupported proxy scheme: {}", url.scheme()));
+    }
+    let host = url.host_str().ok_or("Missing host")?.trim_matches(|c| c == '[' || c == ']').to_string();
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let path = match url.query() {
+        Some(q) => format!("{}?{}", url.path(), q),
+        None => url.path().to_string(),
+    };
+    let mut forward_head = format!("{} {} {}\r\n", method, path, version);
+    for line in lines {
+        let name = line.split(':').next().unwrap_or("").to_ascii_lowercase();
+        if name.starts_with("proxy-") {
+            continue; // Proxy-Connection / Proxy-Authorization are for us, not the origin
+        }
+        forward_head.push_str(line);
+        forward_head.push_str("\r\n");
+    }
+    // `split` leaves the blank line(s) after the head; normalize to exactly one terminator
+    while forward_head.ends_with("\r\n\r\n") {
+        forward_head.truncate(forward_head.len() - 2);
+    }
+    forward_head.push_str("\r\n");
+
+    Ok(ProxyRequest { host, port, tunnel: false, forward_head: Some(forward_head) })
+}
+
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<(String, Vec<u8>)> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let rest = buf.split_off(end + 4);
+            return Ok((String::from_utf8_lossy(&buf).to_string(), rest));
+        }
+        if buf.len() > MAX_HEAD_LEN {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Request head too large"));
+        }
+    }
+}
+
+async fn connect(resolver: &DohResolver, host: &str, port: u16) -> Result<TcpStream, String> {
+    if resolver.endpoint().is_none() {
+        // Local proxy is only up for proxy settings; use the system resolver
+        return TcpStream::connect((host, port)).await.map_err(|e| e.to_string());
+    }
+    let addrs = resolver.resolve(host).await?;
+    let mut last_error = String::from("No addresses");
+    for ip in addrs {
+        match TcpStream::connect(SocketAddr::new(ip, port)).await {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
+
+async fn handle_client(mut client: TcpStream, resolver: Arc<DohResolver>, proxy_settings: ProxySettings) -> Result<(), String> {
+    let (head, rest) = read_head(&mut client).await.map_err(|e| e.to_string())?;
+    let request = parse_request_head(&head)?;
+
+    let via = proxy_settings.upstream_for(&request.host);
+    // Plain http through an HTTP upstream is forwarded as-is; everything else is tunneled
+    let forward_to_http_proxy = via.filter(|p| p.scheme == ProxyScheme::Http && !request.tunnel);
+    let connected = match (via, forward_to_http_proxy) {
+        (_, Some(p)) => TcpStream::connect((p.host.trim(), p.port)).await.map_err(|e| e.to_string()),
+        (Some(p), None) => proxy::connect_via(p, &request.host, request.port).await,
+        (None, _) => connect(&resolver, &request.host, request.port).await,
+    };
+    let mut upstream = match connected {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = client.write_all(b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n").await;
+            return Err(format!("{}: {}", request.host, e));
+        }
+    };
+
+    if request.tunnel {
+        client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await.map_err(|e| e.to_string())?;
+    } else if let Some(forward_head) = &request.forward_head {
+        let forward_head = match forward_to_http_proxy {
+            Some(p) => proxy::upstream_head(forward_head, &request.host, request.port, p),
+            None => forward_head.clone(),
+        };
+        upstream.write_all(forward_head.as_bytes()).await.map_err(|e| e.to_string())?;
+    }
+    if !rest.is_empty() {
+        upstream.write_all(&rest).await.map_err(|e| e.to_string())?;
+    }
+
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Owns the resolver, the proxy settings and the lazily started local proxy.
+pub struct DohManager {
+    pub resolver: Arc<DohResolver>,
+    proxy_settings: Arc<RwLock<ProxySettings>>,
+    port: Mutex<Option<u16>>,
+}
+
+impl DohManager {
+    pub fn new() -> Self {
+        Self {
+            resolver: Arc::new(DohResolver::new()),
+            proxy_settings: Arc::new(RwLock::new(ProxySettings::default())),
+            port: Mutex::new(None),
+        }
+    }
+
+    /// Applies to connections opened from now on; open tunnels are left alone.
+    pub fn set_proxy_settings(&self, settings: ProxySettings) {
+        *self.proxy_settings.write().unwrap() = settings;
+    }
+
+    /// Proxy URL for new webviews, starting the local proxy on first use.
+    /// None when neither DoH nor a proxy is configured (webviews then use the
+    /// system resolver and proxy).
+    pub fn proxy_url(&self) -> Option<url::Url> {
+        if self.resolver.endpoint().is_none() {
+            match self.proxy_settings.read().unwrap().webview_proxy() {
+                WebviewProxy::System => return None,
+                WebviewProxy::Direct(url) => return Some(url),
+                WebviewProxy::Local => {}
+            }
+        }
+        let mut port = self.port.lock().unwrap();
+        if port.is_none() {
+            match self.start_proxy() {
+                Ok(p) => *port = Some(p),
+                Err(e) => {
+                    eprintln!("[DoH] Failed to start local proxy: {}", e);
+                    return None;
+                }
+            }
+        }
+        url::Url::parse(&format!("http://127.0.0.1:{}", port.unwrap())).ok()
+    }
+
+    fn start_proxy(&self) -> std::io::Result<u16> {
+        // Bind synchronously so the port is known before the webview is built
+        let std_listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        std_listener.set_nonblocking(true)?;
+        let port = std_listener.local_addr()?.port();
+        let resolver = self.resolver.clone();
+        let proxy_settings = self.proxy_settings.clone();
+
+        tauri::async_runtime::spawn(async move {
+            let listener = match TcpListener::from_std(std_listener) {
+                Ok(l) => l,
+                Err(e) => {
+                    eprintln!("[DoH] Failed to start listener: {}", e);
+                    return;
+                }
+            };
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let resolver = resolver.clone();
+                        let settings = proxy_settings.read().unwrap().clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_client(stream, resolver, settings).await {
+                                eprintln!("[DoH] Proxy error: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("[DoH] Accept failed: {}", e),
+                }
+            }
+        });
+
+        println!("[DoH] Local proxy listening on 127.0.0.1:{}", port);
+        Ok(port)
+    }
+}
+
+impl Default for DohManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_endpoint() {
+        assert_eq!(DohMode::Off.endpoint(None), None);
+        assert_eq!(DohMode::Cloudflare.endpoint(None).as_deref(), Some(CLOUDFLARE_ENDPOINT));
+        assert_eq!(DohMode::Custom.endpoint(Some(" https://dns.example/q ")).as_deref(), Some("https://dns.example/q"));
+        assert_eq!(DohMode::Custom.endpoint(Some("http://dns.example/q")), None);
+        assert_eq!(DohMode::Custom.endpoint(None), None);
+    }
+
+    #[test]
+    fn test_parse_dns_json() {
+        let body = r#"{"Status":0,"Answer":[
+            {"name":"www.example.com","type":5,"TTL":600,"data":"example.com."},
+            {"name":"example.com","type":1,"TTL":120,"data":"93.184.216.34"}
+        ]}"#;
+        let (addrs, ttl) = parse_dns_json(body).unwrap();
+        assert_eq!(addrs, vec!["93.184.216.34".parse::<IpAddr>().unwrap()]);
+        assert_eq!(ttl, 120);
+
+        assert!(parse_dns_json(r#"{"Status":3}"#).is_err()); // NXDOMAIN
+        let (addrs, ttl) = parse_dns_json(r#"{"Status":0,"Answer":[{"type":1,"TTL":1,"data":"1.2.3.4"}]}"#).unwrap();
+        assert_eq!((addrs.len(), ttl), (1, MIN_TTL_SECS));
+    }
+
+    #[test]
+    fn test_parse_connect() {
+        let req = parse_request_head("CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n").unwrap();
+        assert_eq!(req, ProxyRequest { host: "example.com".into(), port: 443, tunnel: true, forward_head: None });
+
+        let req = parse_request_head("CONNECT [::1]:8443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!((req.host.as_str(), req.port), ("::1", 8443));
+    }
+
+    #[test]
+    fn test_parse_plain_http_rewrites_to_origin_form() {
+        let head = "GET http://example.com:8080/a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\nProxy-Connection: keep-alive\r\n\r\n";
+        let req = parse_request_head(head).unwrap();
+        assert_eq!((req.host.as_str(), req.port, req.tunnel), ("example.com", 8080, false));
+        assert_eq!(req.forward_head.unwrap(), "GET /a?b=1 HTTP/1.1\r\nHost: example.com:8080\r\n\r\n");
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(parse_request_head("hello").is_err());
+        assert!(parse_request_head("GET ftp://example.com/ HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ip_literals_without_network() {
+        let resolver = DohResolver::new();
+        assert_eq!(resolver.resolve("127.0.0.1").await.unwrap(), vec![IpAddr::from([127, 0, 0, 1])]);
+        assert_eq!(resolver.resolve("[::1]").await.unwrap(), vec!["::1".parse::<IpAddr>().unwrap()]);
+        // No endpoint configured
+        assert!(resolver.resolve("example.com").await.is_err());
+    }
+}
@@ -0,0 +1,433 @@
+// URL drag-out support - dragging a tab's favicon or the address bar into other
+// apps or the desktop.
+//
+// The webview can't start a drag that leaves the window, so the UI hands off to
+// a native OS drag (see `platform`). What's dragged is a platform shortcut
+// file (.webloc on macOS, .url on Windows, .desktop on Linux), which the
+// desktop, file managers and other browsers all accept. Some of them only read
+// the file after the drag has ended, so it's kept for DRAG_FILE_LIFETIME after
+// that; files a quit browser left behind go with the next drag.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, State, Window};
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+const DRAG_CACHE_DIR: &str = "drag";
+const DRAG_FILE_LIFETIME: Duration = Duration::from_secs(60);
+const MAX_FILE_NAME_LEN: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShortcutFormat {
+    Webloc,  // macOS property list
+    Url,     // Windows InternetShortcut
+    Desktop, // freedesktop.org Link entry
+}
+
+impl ShortcutFormat {
+    pub fn native() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Webloc
+        } else if cfg!(target_os = "windows") {
+            Self::Url
+        } else {
+            Self::Desktop
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Webloc => "webloc",
+            Self::Url => "url",
+            Self::Desktop => "desktop",
+        }
+    }
+}
+
+/// Turns a page title into a safe shortcut file name (falls back to the host).
+pub fn shortcut_file_name(title: &str, url: &str, format: ShortcutFormat) -> String {
+    let base = if title.trim().is_empty() {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()))
+            .unwrap_or_else(|| "Link".to_string())
+    } else {
+        title.trim().to_string()
+    };
+
+    let mut sanitized: String = base
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+
+    if sanitized.chars().count() > MAX_FILE_NAME_LEN {
+        sanitized = sanitized.chars().take(MAX_FILE_NAME_LEN).collect();
+    }
+
+    format!("{}.{}", sanitized.trim(), format.extension())
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn shortcut_contents(url: &str, title: &str, format: ShortcutFormat) -> String {
+    match format {
+        ShortcutFormat::Webloc => format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n<dict>\n\t<key>URL</key>\n\t<string>{}</string>\n</dict>\n</plist>\n",
+            escape_xml(url)
+        ),
+        ShortcutFormat::Url => format!("[InternetShortcut]\r\nURL={}\r\n", url),
+        ShortcutFormat::Desktop => format!(
+            "[Desktop Entry]\nVersion=1.0\nType=Link\nName={}\nURL={}\nIcon=text-html\n",
+            title.replace('\n', " "),
+            url
+        ),
+    }
+}
+
+/// Writes the shortcut file into `dir`, replacing any previous file of the same name.
+pub fn write_shortcut(dir: &Path, url: &str, title: &str, format: ShortcutFormat) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(shortcut_file_name(title, url, format));
+    fs::write(&path, shortcut_contents(url, title, format))?;
+    Ok(path)
+}
+
+/// Deletes shortcut files older than `max_age` from earlier drags.
+pub fn remove_stale(dir: &Path, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let modified = entry.metadata().and_then(|m| m.modified());
+        if modified.is_ok_and(|m| now.duration_since(m).unwrap_or_default() >= max_age) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+fn remove_later(path: PathBuf) {
+    std::thread::spawn(move || {
+        std::thread::sleep(DRAG_FILE_LIFETIME);
+        let _ = fs::remove_file(&path);
+    });
+}
+
+/// Tauri command: start an OS drag of a tab's URL (the active tab when `tab_id` is None).
+#[tauri::command]
+pub fn start_url_drag(
+    app: AppHandle,
+    window: Window,
+    state: State<AppState>,
+    tab_id: Option<String>,
+) -> Result<(), BrowserError> {
+    let (url, title) = {
+        let tabs = state.tabs.lock().map_err(|e| e.to_string())?;
+        let target_id = match tab_id {
+            Some(id) => Some(id),
+            None => state.active_tab_id.lock().map_err(|e| e.to_string())?.clone(),
+        };
+        let tab = tabs.iter()
+            .find(|t| Some(&t.id) == target_id.as_ref())
+            .ok_or_else(|| BrowserError::NotFound("Tab not found".to_string()))?;
+        (tab.url.clone(), tab.title.clone())
+    };
+
+    // Only web/file URLs make sense outside the browser
+    if !(url.starts_with("http://") || url.starts_with("https://") || url.starts_with("file://")) {
+        return Err(BrowserError::Unsupported("URL cannot be dragged out".to_string()));
+    }
+
+    let dir = app.path().app_cache_dir()
+        .map_err(|e| BrowserError::Io(e.to_string()))?
+        .join(DRAG_CACHE_DIR);
+    remove_stale(&dir, DRAG_FILE_LIFETIME);
+    let path = write_shortcut(&dir, &url, &title, ShortcutFormat::native())
+        .map_err(|e| BrowserError::Io(e.to_string()))?;
+
+    // Native drags have to start on the main thread
+    app.run_on_main_thread(move || {
+        let dragged = path.clone();
+        if let Err(e) = platform::start(&window, &path, move || remove_later(dragged)) {
+            eprintln!("[DragOut] Failed to start drag: {}", e);
+            let _ = fs::remove_file(&path);
+        }
+    })
+    .map_err(|e| BrowserError::Internal(e.to_string()))
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::path::Path;
+    use tauri::Window;
+    use windows::core::HSTRING;
+    use windows::Win32::System::Com::IDataObject;
+    use windows::Win32::System::Ole::{DROPEFFECT_COPY, DROPEFFECT_LINK};
+    use windows::Win32::UI::Shell::{BHID_DataObject, IShellItem, SHCreateItemFromParsingName, SHDoDragDrop};
+
+    /// Runs the shell's drag loop, which returns once the drop lands or the drag is cancelled.
+    pub fn start(window: &Window, path: &Path, on_end: impl FnOnce() + 'static) -> Result<(), String> {
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?;
+        unsafe {
+            let item: IShellItem = SHCreateItemFromParsingName(&HSTRING::from(path), None).map_err(|e| e.to_string())?;
+            let data: IDataObject = item.BindToHandler(None, &BHID_DataObject).map_err(|e| e.to_string())?;
+            // Without a drop source of our own the shell's follows the mouse buttons and Esc
+            let result = SHDoDragDrop(Some(hwnd), &data, None, DROPEFFECT_COPY | DROPEFFECT_LINK);
+            on_end();
+            result.map(|_| ()).map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl, Encode, Encoding};
+    use std::cell::{Cell, RefCell};
+    use std::ffi::CString;
+    use std::path::Path;
+    use tauri::Window;
+
+    const DRAG_ICON: &[u8] = include_bytes!("../../icons/32x32.png");
+    const ICON_SIZE: f64 = 32.0;
+    const NS_EVENT_TYPE_LEFT_MOUSE_DOWN: usize = 1;
+    const NS_EVENT_TYPE_LEFT_MOUSE_DRAGGED: usize = 6;
+    const NS_DRAG_OPERATION_COPY: usize = 1;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct NSPoint {
+        x: f64,
+        y: f64,
+    }
+
+    unsafe impl Encode for NSPoint {
+        fn encode() -> Encoding {
+            unsafe { Encoding::from_str("{CGPoint=dd}") }
+        }
+    }
+
+    #[repr(C)]
+    struct NSRect {
+        origin: NSPoint,
+        width: f64,
+        height: f64,
+    }
+
+    thread_local! {
+        // Only touched on the main thread; AppKit runs one drag at a time
+        static SOURCE: Cell<*mut Object> = const { Cell::new(std::ptr::null_mut()) };
+        static ON_END: RefCell<Option<Box<dyn FnOnce()>>> = const { RefCell::new(None) };
+    }
+
+    /// draggingSession:sourceOperationMaskForDraggingContext:
+    extern "C" fn operation_mask(_this: &Object, _sel: Sel, _session: *mut Object, _context: isize) -> usize {
+        NS_DRAG_OPERATION_COPY
+    }
+
+    /// draggingSession:endedAtPoint:operation:
+    extern "C" fn ended(_this: &Object, _sel: Sel, _session: *mut Object, _point: NSPoint, _operation: usize) {
+        if let Some(on_end) = ON_END.with(|cell| cell.borrow_mut().take()) {
+            on_end();
+        }
+    }
+
+    /// The NSDraggingSource every drag shares.
+    unsafe fn source() -> *mut Object {
+        SOURCE.with(|cell| {
+            if cell.get().is_null() {
+                let mut decl =
+                    ClassDecl::new("SovereignDragSource", class!(NSObject)).expect("drag source registered twice");
+                decl.add_method(
+                    sel!(draggingSession:sourceOperationMaskForDraggingContext:),
+                    operation_mask as extern "C" fn(&Object, Sel, *mut Object, isize) -> usize,
+                );
+                decl.add_method(
+                    sel!(draggingSession:endedAtPoint:operation:),
+                    ended as extern "C" fn(&Object, Sel, *mut Object, NSPoint, usize),
+                );
+                cell.set(msg_send![decl.register(), new]);
+            }
+            cell.get()
+        })
+    }
+
+    /// AppKit only starts drags from a mouse event; by the time the command runs
+    /// the current one may be something else, so make one at the mouse.
+    unsafe fn mouse_event(ns_window: *mut Object) -> *mut Object {
+        let ns_app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        let current: *mut Object = msg_send![ns_app, currentEvent];
+        if !current.is_null() {
+            let kind: usize = msg_send![current, type];
+            if kind == NS_EVENT_TYPE_LEFT_MOUSE_DOWN || kind == NS_EVENT_TYPE_LEFT_MOUSE_DRAGGED {
+                return current;
+            }
+        }
+        let location: NSPoint = msg_send![ns_window, mouseLocationOutsideOfEventStream];
+        let window_number: isize = msg_send![ns_window, windowNumber];
+        msg_send![class!(NSEvent),
+            mouseEventWithType: NS_EVENT_TYPE_LEFT_MOUSE_DRAGGED
+            location: location
+            modifierFlags: 0usize
+            timestamp: 0.0f64
+            windowNumber: window_number
+            context: std::ptr::null_mut::<Object>()
+            eventNumber: 0isize
+            clickCount: 1isize
+            pressure: 1.0f32]
+    }
+
+    pub fn start(window: &Window, path: &Path, on_end: impl FnOnce() + 'static) -> Result<(), String> {
+        let view = window.ns_view().map_err(|e| e.to_string())? as *mut Object;
+        let path = CString::new(path.to_string_lossy().into_owned()).map_err(|e| e.to_string())?;
+        unsafe {
+            let ns_window: *mut Object = msg_send![view, window];
+            let event = mouse_event(ns_window);
+
+            let path: *mut Object = msg_send![class!(NSString), stringWithUTF8String: path.as_ptr()];
+            let file_url: *mut Object = msg_send![class!(NSURL), fileURLWithPath: path];
+            let item: *mut Object = msg_send![class!(NSDraggingItem), alloc];
+            let item: *mut Object = msg_send![item, initWithPasteboardWriter: file_url];
+
+            let data: *mut Object =
+                msg_send![class!(NSData), dataWithBytes: DRAG_ICON.as_ptr() length: DRAG_ICON.len()];
+            let image: *mut Object = msg_send![class!(NSImage), alloc];
+            let image: *mut Object = msg_send![image, initWithData: data];
+            let location: NSPoint = msg_send![event, locationInWindow];
+            let at: NSPoint = msg_send![view, convertPoint: location fromView: std::ptr::null_mut::<Object>()];
+            let frame = NSRect {
+                origin: NSPoint { x: at.x - ICON_SIZE / 2.0, y: at.y - ICON_SIZE / 2.0 },
+                width: ICON_SIZE,
+                height: ICON_SIZE,
+            };
+            let _: () = msg_send![item, setDraggingFrame: frame contents: image];
+            let items: *mut Object = msg_send![class!(NSArray), arrayWithObject: item];
+
+            ON_END.with(|cell| *cell.borrow_mut() = Some(Box::new(on_end)));
+            let session: *mut Object =
+                msg_send![view, beginDraggingSessionWithItems: items event: event source: source()];
+            let _: () = msg_send![item, release];
+            let _: () = msg_send![image, release];
+            if session.is_null() {
+                ON_END.with(|cell| *cell.borrow_mut() = None);
+                return Err("AppKit refused the drag".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use gtk::glib::SignalHandlerId;
+    use gtk::prelude::*;
+    use gtk::{gdk, TargetEntry, TargetFlags, TargetList};
+    use std::cell::RefCell;
+    use std::path::Path;
+    use std::rc::Rc;
+    use tauri::Window;
+
+    pub fn start(window: &Window, path: &Path, on_end: impl FnOnce() + 'static) -> Result<(), String> {
+        let window = window.gtk_window().map_err(|e| e.to_string())?;
+        let uri = url::Url::from_file_path(path).map_err(|_| "Not an absolute path".to_string())?.to_string();
+
+        let handlers: Rc<RefCell<Vec<SignalHandlerId>>> = Rc::default();
+        let data_handler = window.connect_drag_data_get(move |_, _, data, _, _| {
+            data.set_uris(&[uri.as_str()]);
+        });
+        // drag-end comes whether the drop landed or not
+        let ended = handlers.clone();
+        let on_end = RefCell::new(Some(on_end));
+        let end_handler = window.connect_drag_end(move |window, _| {
+            for id in ended.borrow_mut().drain(..) {
+                window.disconnect(id);
+            }
+            if let Some(on_end) = on_end.borrow_mut().take() {
+                on_end();
+            }
+        });
+        handlers.borrow_mut().extend([data_handler, end_handler]);
+
+        let targets = TargetList::new(&[TargetEntry::new("text/uri-list", TargetFlags::OTHER_APP, 0)]);
+        let event = gtk::current_event();
+        if window.drag_begin_with_coordinates(&targets, gdk::DragAction::COPY, 1, event.as_ref(), -1, -1).is_none() {
+            for id in handlers.borrow_mut().drain(..) {
+                window.disconnect(id);
+            }
+            return Err("GTK refused the drag".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod platform {
+    use std::path::Path;
+    use tauri::Window;
+
+    pub fn start(_window: &Window, _path: &Path, _on_end: impl FnOnce() + 'static) -> Result<(), String> {
+        Err("Not supported on this platform".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcut_file_name_sanitizes() {
+        assert_eq!(
+            shortcut_file_name("Rust: A/B <test>", "https://rust-lang.org", ShortcutFormat::Webloc),
+            "Rust- A-B -test-.webloc"
+        );
+        assert_eq!(
+            shortcut_file_name("  ", "https://docs.rs/serde", ShortcutFormat::Url),
+            "docs.rs.url"
+        );
+    }
+
+    #[test]
+    fn test_shortcut_contents() {
+        let webloc = shortcut_contents("https://a.com/?x=1&y=2", "A", ShortcutFormat::Webloc);
+        assert!(webloc.contains("<string>https://a.com/?x=1&amp;y=2</string>"));
+
+        let url = shortcut_contents("https://a.com/", "A", ShortcutFormat::Url);
+        assert_eq!(url, "[InternetShortcut]\r\nURL=https://a.com/\r\n");
+
+        let desktop = shortcut_contents("https://a.com/", "A", ShortcutFormat::Desktop);
+        assert!(desktop.contains("Type=Link") && desktop.contains("URL=https://a.com/"));
+    }
+
+    #[test]
+    fn test_write_shortcut() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_shortcut(dir.path(), "https://a.com/", "A", ShortcutFormat::Url).unwrap();
+        assert_eq!(path.file_name().unwrap(), "A.url");
+        assert!(fs::read_to_string(path).unwrap().contains("URL=https://a.com/"));
+    }
+
+    #[test]
+    fn test_remove_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_shortcut(dir.path(), "https://a.com/", "A", ShortcutFormat::Url).unwrap();
+        remove_stale(dir.path(), DRAG_FILE_LIFETIME);
+        assert!(path.exists());
+        remove_stale(dir.path(), Duration::ZERO);
+        assert!(!path.exists());
+        remove_stale(&dir.path().join("missing"), Duration::ZERO);
+    }
+}
@@ -0,0 +1,281 @@
+// Where the omnibox suggestions dropdown lives.
+//
+// The dropdown is its own webview so it can hang over the page below the
+// toolbar. There are two ways to put it there:
+// - `Window`: a borderless, always-on-top window moved to screen coordinates
+//   under the address bar. Works on macOS, Windows and X11.
+// - `ChildWebview`: a webview inside the main window, placed in window
+//   coordinates. Wayland doesn't let clients place their own windows (the
+//   position is ignored and the compositor puts the window wherever it likes),
+//   so Linux Wayland sessions use this. Tauri has no popup surfaces, which
+//   would be the other way to do it.
+//
+// The strategy is picked at startup from the session: XDG_SESSION_TYPE and
+// WAYLAND_DISPLAY, unless GDK_BACKEND=x11 forces XWayland, where windows can
+// be placed again. SOVEREIGN_DROPDOWN=window or =webview overrides it, and if
+// the chosen one can't be created the other is tried. Both are labeled
+// "dropdown", so the toolbar and dropdown pages don't care which is in use.
+//
+// Child webviews stack in the order they were added and tabs are added after
+// the dropdown, so it's moved back on top of them each time it's shown.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tauri::{
+    AppHandle, LogicalPosition, LogicalSize, Manager, PhysicalPosition, PhysicalSize, WebviewBuilder, WebviewUrl,
+    WebviewWindowBuilder,
+};
+
+use crate::modules::headless;
+
+pub const LABEL: &str = "dropdown";
+pub const ENV_VAR: &str = "SOVEREIGN_DROPDOWN";
+const PAGE: &str = "dropdown.html";
+const DEFAULT_WIDTH: f64 = 400.0;
+const DEFAULT_HEIGHT: f64 = 300.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Window,
+    ChildWebview,
+}
+
+impl Strategy {
+    fn other(self) -> Self {
+        match self {
+            Self::Window => Self::ChildWebview,
+            Self::ChildWebview => Self::Window,
+        }
+    }
+}
+
+static ACTIVE: OnceLock<Strategy> = OnceLock::new();
+/// Whether the child webview was raised since it was last hidden.
+static RAISED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a Linux session's windows are Wayland surfaces, from its environment.
+pub fn is_wayland(session_type: Option<&str>, wayland_display: Option<&str>, gdk_backend: Option<&str>) -> bool {
+    // GDK tries backends in the listed order
+    if gdk_backend.and_then(|b| b.split(',').next()).is_some_and(|b| b.trim() == "x11") {
+        return false;
+    }
+    session_type.is_some_and(|t| t.eq_ignore_ascii_case("wayland")) || wayland_display.is_some_and(|d| !d.is_empty())
+}
+
+/// The strategy for a session, honoring an override from SOVEREIGN_DROPDOWN.
+pub fn choose(requested: Option<&str>, wayland: bool) -> Strategy {
+    match requested.map(|r| r.trim().to_ascii_lowercase()).as_deref() {
+        Some("window") => Strategy::Window,
+        Some("webview") => Strategy::ChildWebview,
+        Some(other) => {
+            eprintln!("[dropdown] Ignoring {}={}: use 'window' or 'webview'", ENV_VAR, other);
+            choose(None, wayland)
+        }
+        None if wayland => Strategy::ChildWebview,
+        None => Strategy::Window,
+    }
+}
+
+fn detect() -> Strategy {
+    let var = |name: &str| std::env::var(name).ok();
+    let wayland = cfg!(target_os = "linux")
+        && is_wayland(
+            var("XDG_SESSION_TYPE").as_deref(),
+            var("WAYLAND_DISPLAY").as_deref(),
+            var("GDK_BACKEND").as_deref(),
+        );
+    choose(var(ENV_VAR).as_deref(), wayland)
+}
+
+/// The strategy in use, None until `create` succeeded.
+pub fn active() -> Option<Strategy> {
+    ACTIVE.get().copied()
+}
+
+fn build(app: &AppHandle, strategy: Strategy) -> Result<(), String> {
+    let url = WebviewUrl::App(PAGE.into());
+    match strategy {
+        Strategy::Window => WebviewWindowBuilder::new(app, LABEL, url)
+            .title("Dropdown")
+            .inner_size(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+            .decorations(false)
+            .visible(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .focused(false) // Don't take focus
+            .build()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Strategy::ChildWebview => {
+            let main = app.get_window("main").ok_or("Main window not found")?;
+            let webview = main
+                .add_child(
+                    WebviewBuilder::new(LABEL, url).focused(false),
+                    LogicalPosition::new(0.0, 0.0),
+                    LogicalSize::new(DEFAULT_WIDTH, DEFAULT_HEIGHT),
+                )
+                .map_err(|e| e.to_string())?;
+            webview.hide().map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Creates the (hidden) dropdown, falling back to the other strategy if the
+/// preferred one fails.
+pub fn create(app: &AppHandle) {
+    let preferred = detect();
+    for strategy in [preferred, preferred.other()] {
+        match build(app, strategy) {
+            Ok(()) => {
+                let _ = ACTIVE.set(strategy);
+                println!("[dropdown] Dropdown created as {:?}", strategy);
+                return;
+            }
+            Err(e) => eprintln!("[dropdown] ERROR: Failed to create {:?} dropdown: {}", strategy, e),
+        }
+    }
+}
+
+/// Screen bounds for the window dropdown: `x`, `y`, `width` and `height` are
+/// logical coordinates in the main window's content area, which starts at `origin`.
+/// The offset is in the main window's scale; the size is in `monitor_scale`, the
+/// scale of the monitor the dropdown lands on, which differs when the main window
+/// straddles two monitors.
+pub fn screen_bounds(
+    origin: PhysicalPosition<i32>,
+    window_scale: f64,
+    monitor_scale: f64,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+    (
+        PhysicalPosition::new(origin.x + (x * window_scale) as i32, origin.y + (y * window_scale) as i32),
+        PhysicalSize::new((width * monitor_scale) as u32, (height * monitor_scale) as u32),
+    )
+}
+
+/// Places the dropdown at logical content coordinates of the main window.
+pub fn set_bounds(app: &AppHandle, x: f64, y: f64, width: f64, height: f64) -> Result<(), String> {
+    match active() {
+        Some(Strategy::Window) => {
+            let main = app.get_window("main").ok_or("Main window not found")?;
+            // inner_position is the top-left of the content area (below any titlebar)
+            let origin = main.inner_position().map_err(|e| e.to_string())?;
+            let window_scale = main.scale_factor().map_err(|e| e.to_string())?;
+            let (corner, _) = screen_bounds(origin, window_scale, window_scale, x, y, width, height);
+            let monitor_scale = app
+                .monitor_from_point(corner.x as f64, corner.y as f64)
+                .ok()
+                .flatten()
+                .map_or(window_scale, |monitor| monitor.scale_factor());
+            let (position, size) = screen_bounds(origin, window_scale, monitor_scale, x, y, width, height);
+            let dropdown = app.get_window(LABEL).ok_or("Dropdown window not found")?;
+            dropdown.set_position(position).map_err(|e| e.to_string())?;
+            dropdown.set_size(size).map_err(|e| e.to_string())
+        }
+        Some(Strategy::ChildWebview) => {
+            let dropdown = app.get_webview(LABEL).ok_or("Dropdown webview not found")?;
+            dropdown.set_position(LogicalPosition::new(x, y)).map_err(|e| e.to_string())?;
+            dropdown.set_size(LogicalSize::new(width, height)).map_err(|e| e.to_string())
+        }
+        None => Err("Dropdown wasn't created".to_string()),
+    }
+}
+
+/// Shows the dropdown without taking focus from the address bar.
+pub fn show(app: &AppHandle) {
+    if headless::enabled() {
+        return;
+    }
+    match active() {
+        Some(Strategy::Window) => {
+            if let Some(dropdown) = app.get_window(LABEL) {
+                let _ = dropdown.show();
+                // Force always on top to ensure visibility
+                let _ = dropdown.set_always_on_top(true);
+            }
+            if let Some(main) = app.get_window("main") {
+                let _ = main.set_focus();
+            }
+        }
+        Some(Strategy::ChildWebview) => {
+            let (Some(dropdown), Some(main)) = (app.get_webview(LABEL), app.get_window("main")) else {
+                return;
+            };
+            // Re-adding it to the main window stacks it above the tab webviews
+            if !RAISED.swap(true, Ordering::Relaxed) {
+                if let Err(e) = dropdown.reparent(&main) {
+                    eprintln!("[dropdown] Failed to raise dropdown: {}", e);
+                }
+            }
+            let _ = dropdown.show();
+            if let Some(toolbar) = app.get_webview("main") {
+                let _ = toolbar.set_focus();
+            }
+        }
+        None => {}
+    }
+}
+
+pub fn hide(app: &AppHandle) -> tauri::Result<()> {
+    match active() {
+        Some(Strategy::Window) => app.get_window(LABEL).map_or(Ok(()), |dropdown| dropdown.hide()),
+        Some(Strategy::ChildWebview) => {
+            RAISED.store(false, Ordering::Relaxed);
+            app.get_webview(LABEL).map_or(Ok(()), |dropdown| dropdown.hide())
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Some("wayland"), None, None, true)]
+    #[case(Some("Wayland"), Some("wayland-0"), Some("wayland,x11"), true)]
+    #[case(None, Some("wayland-0"), None, true)]
+    #[case(Some("x11"), None, None, false)]
+    #[case(Some("wayland"), Some("wayland-0"), Some("x11"), false)]
+    #[case(Some("wayland"), None, Some("x11,wayland"), false)]
+    #[case(Some("tty"), Some(""), None, false)]
+    #[case(None, None, None, false)]
+    fn test_is_wayland(
+        #[case] session_type: Option<&str>,
+        #[case] wayland_display: Option<&str>,
+        #[case] gdk_backend: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(is_wayland(session_type, wayland_display, gdk_backend), expected);
+    }
+
+    #[rstest]
+    #[case(None, false, Strategy::Window)]
+    #[case(None, true, Strategy::ChildWebview)]
+    #[case(Some("window"), true, Strategy::Window)]
+    #[case(Some(" WebView "), false, Strategy::ChildWebview)]
+    #[case(Some("popup"), true, Strategy::ChildWebview)]
+    #[case(Some(""), false, Strategy::Window)]
+    fn test_choose(#[case] requested: Option<&str>, #[case] wayland: bool, #[case] expected: Strategy) {
+        assert_eq!(choose(requested, wayland), expected);
+    }
+
+    #[test]
+    fn test_screen_bounds_scales_from_content_origin() {
+        let (position, size) = screen_bounds(PhysicalPosition::new(100, 50), 2.0, 2.0, 10.0, 40.5, 300.0, 120.0);
+        assert_eq!(position, PhysicalPosition::new(120, 131));
+        assert_eq!(size, PhysicalSize::new(600, 240));
+    }
+
+    #[test]
+    fn test_screen_bounds_sizes_for_the_dropdowns_monitor() {
+        // Main window on a 150% monitor, dropdown hanging onto a 100% one
+        let (position, size) = screen_bounds(PhysicalPosition::new(2000, 0), 1.5, 1.0, 100.0, 90.0, 400.0, 300.0);
+        assert_eq!(position, PhysicalPosition::new(2150, 135));
+        assert_eq!(size, PhysicalSize::new(400, 300));
+    }
+}
@@ -0,0 +1,174 @@
+// Element picker ("zapper") for user cosmetic rules.
+//
+// `start_element_picker` injects an overlay into the active tab. Hovering outlines
+// elements; clicking hides the element and reports a CSS selector for it. The rule
+// is stored by AdBlockManager under the tab's current host (taken from the webview,
+// not from the page) and served through `get_cosmetic_resources` on later loads.
+
+use tauri::{AppHandle, Manager};
+
+use crate::adblock_manager::UserCosmeticRule;
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const PICKER_SCRIPT: &str = r#"
+(function() {
+    if (window.__sovereignPicker) return;
+    window.__sovereignPicker = true;
+
+    const box = document.createElement('div');
+    box.style.cssText = 'position:fixed;z-index:2147483647;pointer-events:none;' +
+        'background:rgba(10,132,255,0.2);border:2px solid #0a84ff;border-radius:2px;display:none;';
+    const hint = document.createElement('div');
+    hint.textContent = 'Click an element to hide it · Esc to cancel';
+    hint.style.cssText = 'position:fixed;z-index:2147483647;top:12px;left:50%;transform:translateX(-50%);' +
+        'padding:6px 12px;border-radius:6px;background:#1a1a2e;color:#e0e0e0;' +
+        'font:13px -apple-system,BlinkMacSystemFont,sans-serif;pointer-events:none;';
+    document.documentElement.append(box, hint);
+
+    let target = null;
+
+    // Generated-looking classes (hashes, counters) change between loads
+    function stableClasses(node) {
+        return [...node.classList].filter(c => !/\d{3,}|^[a-z0-9_-]{20,}$/i.test(c)).slice(0, 3);
+    }
+
+    function unique(selector) {
+        try { return document.querySelectorAll(selector).length === 1; } catch (e) { return false; }
+    }
+
+    // Shortest child-combinator path from the element upwards that matches only it
+    function selectorFor(el) {
+        if (el.id && unique('#' + CSS.escape(el.id))) return '#' + CSS.escape(el.id);
+        const parts = [];
+        let node = el;
+        while (node && node.nodeType === 1 && node !== document.documentElement) {
+            let part = node.tagName.toLowerCase();
+            if (node.id) {
+                part = '#' + CSS.escape(node.id);
+            } else {
+                const classes = stableClasses(node);
+                if (classes.length) part += '.' + classes.map(c => CSS.escape(c)).join('.');
+                const parent = node.parentElement;
+                if (parent) {
+                    const same = [...parent.children].filter(c => c.tagName === node.tagName);
+                    if (same.length > 1) part += ':nth-of-type(' + (same.indexOf(node) + 1) + ')';
+                }
+            }
+            parts.unshift(part);
+            const selector = parts.join(' > ');
+            if (unique(selector)) return selector;
+            node = node.parentElement;
+        }
+        return parts.join(' > ');
+    }
+
+    function onMove(e) {
+        const el = document.elementFromPoint(e.clientX, e.clientY);
+        if (!el || el === box || el === hint || el === document.documentElement || el === document.body) {
+            target = null;
+            box.style.display = 'none';
+            return;
+        }
+        target = el;
+        const r = el.getBoundingClientRect();
+        Object.assign(box.style, {
+            display: 'block', top: r.top + 'px', left: r.left + 'px',
+            width: r.width + 'px', height: r.height + 'px'
+        });
+    }
+
+    function onClick(e) {
+        e.preventDefault();
+        e.stopPropagation();
+        if (!target) return;
+        const selector = selectorFor(target);
+        target.style.setProperty('display', 'none', 'important');
+        stop();
+        window.__TAURI__.core.invoke('add_user_cosmetic_rule', { selector })
+            .catch(err => console.warn('[AdBlock] Failed to save rule:', err));
+    }
+
+    function onKey(e) {
+        if (e.key === 'Escape') {
+            e.preventDefault();
+            stop();
+        }
+    }
+
+    // Swallow the rest of the click so the page doesn't react to it
+    function swallow(e) {
+        e.preventDefault();
+        e.stopPropagation();
+    }
+
+    function stop() {
+        document.removeEventListener('mousemove', onMove, true);
+        document.removeEventListener('click', onClick, true);
+        document.removeEventListener('mousedown', swallow, true);
+        document.removeEventListener('mouseup', swallow, true);
+        document.removeEventListener('keydown', onKey, true);
+        box.remove();
+        hint.remove();
+        window.__sovereignPicker = false;
+    }
+
+    document.addEventListener('mousemove', onMove, true);
+    document.addEventListener('click', onClick, true);
+    document.addEventListener('mousedown', swallow, true);
+    document.addEventListener('mouseup', swallow, true);
+    document.addEventListener('keydown', onKey, true);
+})();
+"#;
+
+/// Starts the picker in the active tab.
+pub fn start(app: &AppHandle, state: &AppState) -> Result<(), BrowserError> {
+    let label = {
+        let active = state.active_tab_id.lock().unwrap();
+        let tabs = state.tabs.lock().unwrap();
+        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+    };
+    let webview = label
+        .and_then(|l| app.get_webview(&l))
+        .ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+    webview.set_focus()?;
+    Ok(webview.eval(PICKER_SCRIPT)?)
+}
+
+#[tauri::command]
+pub fn start_element_picker(app: AppHandle, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    start(&app, &state)
+}
+
+/// Called by the picker overlay with the selector for the clicked element.
+#[tauri::command]
+pub fn add_user_cosmetic_rule(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    selector: String,
+) -> Result<UserCosmeticRule, BrowserError> {
+    let url = webview.url()?;
+    let host = url.host_str().ok_or_else(|| BrowserError::Unsupported("Page has no host".to_string()))?;
+    state.adblock.add_user_rule(host, &selector).map_err(BrowserError::InvalidInput)
+}
+
+#[tauri::command]
+pub fn list_user_cosmetic_rules(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<UserCosmeticRule>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.adblock.user_rules())
+}
+
+#[tauri::command]
+pub fn delete_user_cosmetic_rule(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    domain: String,
+    selector: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    state.adblock.remove_user_rule(&domain, &selector).map_err(BrowserError::NotFound)
+}
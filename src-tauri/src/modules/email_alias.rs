@@ -0,0 +1,507 @@
+// Email aliases: a fresh forwarding address for each site's signup form.
+//
+// The user picks a provider in Settings, SimpleLogin or Firefox Relay, or any
+// service speaking either API (a self-hosted SimpleLogin, say), with its API key.
+// `generate_email_alias` (the URL bar's alias button, or "Generate Email Alias" in
+// the command palette) asks the provider for a new alias for the current site,
+// types it into the page's email field and copies it, and records which site it
+// was made for. The aliases themselves live with the provider; the local record
+// only answers "which address did I give this site?".
+//
+// The provider is kept in email_aliases.json next to the records, not in
+// Settings: settings are broadcast to every window on save, and the key should
+// never reach a web page. On macOS the key goes to the login keychain (see
+// modules::keychain); elsewhere it stays in that file, readable by this user
+// only. A key belongs to the service it was entered for, so changing the
+// provider or its address drops it. Only Settings may read or change the
+// provider, and `get_alias_provider` only says whether a key is set.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::{cookie_policy, keychain, proxy};
+use crate::state::AppState;
+
+const ALIASES_FILE: &str = "email_aliases.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const KEYCHAIN_ACCOUNT: &str = "email-alias:api-key";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AliasProviderKind {
+    #[default]
+    None,
+    SimpleLogin,
+    FirefoxRelay,
+}
+
+impl AliasProviderKind {
+    pub fn default_api_url(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::SimpleLogin => Some("https://app.simplelogin.io"),
+            Self::FirefoxRelay => Some("https://relay.firefox.com"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AliasProvider {
+    pub kind: AliasProviderKind,
+    #[serde(default)]
+    pub api_url: Option<String>, // None for the provider's own service
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub api_key: String, // Only in the file where there's no keychain
+}
+
+impl AliasProvider {
+    fn api_url(&self) -> Option<String> {
+        self.api_url.clone().or_else(|| self.kind.default_api_url().map(str::to_string))
+    }
+}
+
+/// The provider as the settings page sees it, without the key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasProviderInfo {
+    pub kind: AliasProviderKind,
+    pub api_url: Option<String>,
+    pub has_key: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AliasRecord {
+    pub alias: String,
+    pub site: String, // eTLD+1 the alias was made for
+    pub provider: AliasProviderKind,
+    pub created_at: u64, // Unix seconds
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    provider: AliasProvider,
+    #[serde(default)]
+    aliases: Vec<AliasRecord>,
+}
+
+/// One call to the provider's "new alias" endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasRequest {
+    pub url: String,
+    pub header: (&'static str, String),
+    pub body: serde_json::Value,
+}
+
+/// Normalizes a custom API address: https only, no trailing slash. Empty means the default.
+pub fn validate_api_url(input: &str) -> Result<Option<String>, String> {
+    let input = input.trim().trim_end_matches('/');
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let url = Url::parse(input).map_err(|_| format!("Not a valid address: {}", input))?;
+    if url.scheme() != "https" {
+        return Err("The alias service must be reached over https".to_string());
+    }
+    Ok(Some(input.to_string()))
+}
+
+pub fn alias_request(provider: &AliasProvider, site: &str) -> Result<AliasRequest, String> {
+    let base = provider.api_url().ok_or("No email alias provider is set up. Choose one in Settings.")?;
+    if provider.api_key.trim().is_empty() {
+        return Err("The email alias provider needs an API key. Add it in Settings.".to_string());
+    }
+    let key = provider.api_key.trim();
+    match provider.kind {
+        AliasProviderKind::None => Err("No email alias provider is set up. Choose one in Settings.".to_string()),
+        AliasProviderKind::SimpleLogin => Ok(AliasRequest {
+            url: format!("{}/api/alias/random/new?hostname={}", base, site),
+            header: ("Authentication", key.to_string()),
+            body: serde_json::json!({ "note": format!("Created for {}", site) }),
+        }),
+        AliasProviderKind::FirefoxRelay => Ok(AliasRequest {
+            url: format!("{}/api/v1/relayaddresses/", base),
+            header: ("Authorization", format!("Token {}", key)),
+            body: serde_json::json!({ "enabled": true, "description": site, "generated_for": site }),
+        }),
+    }
+}
+
+/// The new address from the provider's response.
+pub fn parse_alias(kind: AliasProviderKind, response: &serde_json::Value) -> Result<String, String> {
+    let field = match kind {
+        AliasProviderKind::SimpleLogin => "alias",
+        AliasProviderKind::FirefoxRelay => "full_address",
+        AliasProviderKind::None => return Err("No provider".to_string()),
+    };
+    response.get(field).and_then(|v| v.as_str()).filter(|alias| alias.contains('@')).map(str::to_string).ok_or_else(
+        || match response.get("error").or_else(|| response.get("detail")).and_then(|v| v.as_str()) {
+            Some(message) => format!("The alias provider refused: {}", message),
+            None => "The alias provider sent an unexpected response".to_string(),
+        },
+    )
+}
+
+/// Types the alias into the focused email field, else the first visible one.
+fn fill_script(alias: &str) -> String {
+    format!(
+        r#"(function(alias) {{
+    function isEmailField(el) {{
+        if (!(el instanceof HTMLInputElement) || el.disabled || el.readOnly) return false;
+        const hints = [el.name, el.id, el.autocomplete, el.placeholder].join(' ');
+        return el.type === 'email' || (el.type === 'text' && /e-?mail/i.test(hints));
+    }}
+    let field = document.activeElement;
+    if (!isEmailField(field)) {{
+        field = [...document.querySelectorAll('input')].find(el => isEmailField(el) && el.offsetParent !== null);
+    }}
+    if (!field) return;
+    const setValue = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+    setValue.call(field, alias);
+    field.dispatchEvent(new Event('input', {{ bubbles: true }}));
+    field.dispatchEvent(new Event('change', {{ bubbles: true }}));
+    field.focus();
+}})({});"#,
+        serde_json::to_string(alias).unwrap_or_default()
+    )
+}
+
+/// Puts the key in the keychain (removes it when empty). Without one the file keeps it.
+fn store_key(key: &str) -> Result<(), String> {
+    if !keychain::is_available() {
+        return Ok(());
+    }
+    if key.is_empty() {
+        keychain::delete(keychain::SERVICE, KEYCHAIN_ACCOUNT)
+    } else {
+        keychain::set(keychain::SERVICE, KEYCHAIN_ACCOUNT, key)
+    }
+}
+
+pub struct EmailAliasManager {
+    path: PathBuf,
+    data: Mutex<AliasFile>,
+}
+
+impl EmailAliasManager {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(ALIASES_FILE);
+        let mut data: AliasFile =
+            fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        // A key saved before the keychain was used moves out of the file
+        let mut migrated = false;
+        if keychain::is_available() {
+            if data.provider.api_key.is_empty() {
+                match keychain::get(keychain::SERVICE, KEYCHAIN_ACCOUNT) {
+                    Ok(key) => data.provider.api_key = key.unwrap_or_default(),
+                    Err(e) => eprintln!("[EmailAlias] Failed to read API key: {}", e),
+                }
+            } else {
+                migrated = store_key(&data.provider.api_key).is_ok();
+            }
+        }
+        let manager = Self { path, data: Mutex::new(data) };
+        if migrated {
+            manager.save(&manager.data.lock().unwrap());
+        }
+        manager
+    }
+
+    fn save(&self, data: &AliasFile) {
+        let mut file = data.clone();
+        if keychain::is_available() {
+            file.provider.api_key.clear();
+        }
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(&file).unwrap_or_default()) {
+            eprintln!("[EmailAlias] Failed to save: {}", e);
+            return;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600));
+        }
+    }
+
+    pub fn provider(&self) -> AliasProvider {
+        self.data.lock().unwrap().provider.clone()
+    }
+
+    pub fn provider_info(&self) -> AliasProviderInfo {
+        let data = self.data.lock().unwrap();
+        AliasProviderInfo {
+            kind: data.provider.kind,
+            api_url: data.provider.api_url.clone(),
+            has_key: !data.provider.api_key.is_empty(),
+        }
+    }
+
+    /// `api_key` None keeps the stored key, unless the provider or its address changes.
+    pub fn set_provider(&self, kind: AliasProviderKind, api_url: &str, api_key: Option<String>) -> Result<(), String> {
+        let api_url = validate_api_url(api_url)?;
+        let mut data = self.data.lock().unwrap();
+        let same_service = data.provider.kind == kind && data.provider.api_url == api_url;
+        let api_key = match api_key {
+            Some(key) => key.trim().to_string(),
+            None if same_service => data.provider.api_key.clone(),
+            None => String::new(),
+        };
+        store_key(&api_key)?;
+        data.provider = AliasProvider { kind, api_url, api_key };
+        self.save(&data);
+        Ok(())
+    }
+
+    pub fn record(&self, alias: &str, site: &str, provider: AliasProviderKind) -> AliasRecord {
+        let record = AliasRecord {
+            alias: alias.to_string(),
+            site: site.to_string(),
+            provider,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        };
+        let mut data = self.data.lock().unwrap();
+        data.aliases.push(record.clone());
+        self.save(&data);
+        record
+    }
+
+    /// Newest first.
+    pub fn aliases(&self) -> Vec<AliasRecord> {
+        let mut aliases = self.data.lock().unwrap().aliases.clone();
+        aliases.reverse();
+        aliases
+    }
+
+    /// Forgets the local record; the alias keeps working at the provider.
+    pub fn forget(&self, alias: &str) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.aliases.len();
+        data.aliases.retain(|r| r.alias != alias);
+        if data.aliases.len() == before {
+            return Err(format!("No alias {}", alias));
+        }
+        self.save(&data);
+        Ok(())
+    }
+}
+
+async fn request_alias(state: &AppState, provider: &AliasProvider, site: &str) -> Result<String, String> {
+    let request = alias_request(provider, site)?;
+    let client = proxy::http_client(state, REQUEST_TIMEOUT).map_err(|e| e.to_string())?;
+    let response = client
+        .post(&request.url)
+        .header(request.header.0, request.header.1)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(request.body.to_string())
+        .send()
+        .await
+        .map_err(|e| format!("Couldn't reach the alias provider: {}", e))?;
+    let status = response.status();
+    let body: serde_json::Value =
+        serde_json::from_str(&response.text().await.unwrap_or_default()).unwrap_or(serde_json::Value::Null);
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err("The alias provider didn't accept the API key".to_string());
+    }
+    parse_alias(provider.kind, &body)
+}
+
+/// Makes an alias for the tab's site (the active tab by default), fills it in and copies it.
+pub async fn generate(app: &AppHandle, state: &AppState, tab_id: Option<String>) -> Result<AliasRecord, BrowserError> {
+    let label = {
+        let tabs = state.tabs.lock().unwrap();
+        let tab_id = tab_id
+            .or_else(|| state.active_tab_id.lock().unwrap().clone())
+            .ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+        let tab = tabs.iter().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+        tab.webview_label.clone()
+    };
+    let webview = app.get_webview(&label).ok_or_else(|| BrowserError::NotFound("Tab webview not found".to_string()))?;
+    let page = webview.url()?;
+    let site = match (page.scheme(), page.host_str()) {
+        ("http" | "https", Some(host)) => cookie_policy::site_of(host),
+        _ => return Err(BrowserError::Unsupported("Email aliases can only be made for web pages".to_string())),
+    };
+
+    let provider = state.email_aliases.provider();
+    let alias = request_alias(state, &provider, &site).await.map_err(BrowserError::Network)?;
+    let record = state.email_aliases.record(&alias, &site, provider.kind);
+    println!("[EmailAlias] New alias for {}", site);
+
+    let _ = webview.eval(&fill_script(&alias));
+    if let Err(e) = app.clipboard().write_text(alias) {
+        eprintln!("[EmailAlias] Failed to copy alias: {}", e);
+    }
+    Ok(record)
+}
+
+/// The toolbar button and palette command: generates for the active tab, errors in a dialog.
+pub fn run(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Some(state) = app.try_state::<AppState>() else {
+            return;
+        };
+        if let Err(e) = generate(&app, &state, None).await {
+            eprintln!("[EmailAlias] {}", e);
+            app.dialog().message(e.to_string()).title("Email Alias").kind(MessageDialogKind::Info).show(|_| {});
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn generate_email_alias(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    tab_id: Option<String>,
+) -> Result<AliasRecord, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    generate(&app, &state, tab_id).await
+}
+
+#[tauri::command]
+pub fn list_email_aliases(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<AliasRecord>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.email_aliases.aliases())
+}
+
+#[tauri::command]
+pub fn forget_email_alias(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    alias: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    state.email_aliases.forget(&alias).map_err(BrowserError::NotFound)
+}
+
+#[tauri::command]
+pub fn get_alias_provider(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<AliasProviderInfo, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.email_aliases.provider_info())
+}
+
+#[tauri::command]
+pub fn set_alias_provider(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    kind: AliasProviderKind,
+    api_url: String,
+    api_key: Option<String>,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    state.email_aliases.set_provider(kind, &api_url, api_key).map_err(BrowserError::InvalidInput)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    fn provider(kind: AliasProviderKind, api_url: Option<&str>) -> AliasProvider {
+        AliasProvider { kind, api_url: api_url.map(str::to_string), api_key: "secret".to_string() }
+    }
+
+    #[test]
+    fn test_simplelogin_request() {
+        let request = alias_request(&provider(AliasProviderKind::SimpleLogin, None), "example.com").unwrap();
+        assert_eq!(request.url, "https://app.simplelogin.io/api/alias/random/new?hostname=example.com");
+        assert_eq!(request.header, ("Authentication", "secret".to_string()));
+    }
+
+    #[test]
+    fn test_relay_request_on_custom_server() {
+        let request =
+            alias_request(&provider(AliasProviderKind::FirefoxRelay, Some("https://relay.internal")), "example.com")
+                .unwrap();
+        assert_eq!(request.url, "https://relay.internal/api/v1/relayaddresses/");
+        assert_eq!(request.header, ("Authorization", "Token secret".to_string()));
+        assert_eq!(request.body["generated_for"], "example.com");
+    }
+
+    #[test]
+    fn test_request_needs_provider_and_key() {
+        assert!(alias_request(&provider(AliasProviderKind::None, None), "example.com").is_err());
+        let mut no_key = provider(AliasProviderKind::SimpleLogin, None);
+        no_key.api_key = " ".to_string();
+        assert!(alias_request(&no_key, "example.com").is_err());
+    }
+
+    #[rstest]
+    #[case(
+        AliasProviderKind::SimpleLogin,
+        r#"{"alias": "shop.x1y2@simplelogin.com"}"#,
+        Ok("shop.x1y2@simplelogin.com")
+    )]
+    #[case(AliasProviderKind::FirefoxRelay, r#"{"full_address": "abc123@mozmail.com"}"#, Ok("abc123@mozmail.com"))]
+    #[case(
+        AliasProviderKind::SimpleLogin,
+        r#"{"error": "Alias quota exceeded"}"#,
+        Err("The alias provider refused: Alias quota exceeded")
+    )]
+    #[case(
+        AliasProviderKind::FirefoxRelay,
+        r#"{"full_address": ""}"#,
+        Err("The alias provider sent an unexpected response")
+    )]
+    fn test_parse_alias(#[case] kind: AliasProviderKind, #[case] body: &str, #[case] expected: Result<&str, &str>) {
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(parse_alias(kind, &body), expected.map(str::to_string).map_err(str::to_string));
+    }
+
+    #[rstest]
+    #[case("", Ok(None))]
+    #[case("https://sl.example.org/", Ok(Some("https://sl.example.org")))]
+    #[case("http://sl.example.org", Err(()))]
+    #[case("not a url", Err(()))]
+    fn test_validate_api_url(#[case] input: &str, #[case] expected: Result<Option<&str>, ()>) {
+        assert_eq!(validate_api_url(input).map_err(|_| ()), expected.map(|u| u.map(str::to_string)));
+    }
+
+    #[test]
+    fn test_manager_keeps_key_and_records() {
+        if keychain::is_available() {
+            return; // Would write to the real keychain
+        }
+        let dir = tempdir().unwrap();
+        let manager = EmailAliasManager::new(dir.path().to_path_buf());
+        manager.set_provider(AliasProviderKind::SimpleLogin, "", Some("key-1".to_string())).unwrap();
+        // Saving without a key keeps the stored one
+        manager.set_provider(AliasProviderKind::SimpleLogin, "", None).unwrap();
+        assert_eq!(manager.provider().api_key, "key-1");
+        assert!(manager.provider_info().has_key);
+
+        // ...but not for another service
+        manager.set_provider(AliasProviderKind::SimpleLogin, "https://sl.example.org", None).unwrap();
+        assert!(!manager.provider_info().has_key);
+        manager.set_provider(AliasProviderKind::SimpleLogin, "", Some("key-2".to_string())).unwrap();
+        manager.set_provider(AliasProviderKind::FirefoxRelay, "", None).unwrap();
+        assert_eq!(manager.provider().api_key, "");
+
+        manager.record("a@mozmail.com", "example.com", AliasProviderKind::FirefoxRelay);
+        manager.record("b@mozmail.com", "shop.test", AliasProviderKind::FirefoxRelay);
+
+        let reloaded = EmailAliasManager::new(dir.path().to_path_buf());
+        let sites: Vec<String> = reloaded.aliases().into_iter().map(|r| r.site).collect();
+        assert_eq!(sites, vec!["shop.test", "example.com"]);
+        assert!(reloaded.forget("a@mozmail.com").is_ok());
+        assert!(reloaded.forget("a@mozmail.com").is_err());
+    }
+}
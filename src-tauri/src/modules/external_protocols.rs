@@ -0,0 +1,329 @@
+// Links to other apps: `mailto:`, `zoommtg:`, `spotify:` and the like.
+//
+// A tab navigating to a scheme the webview can't load itself is stopped in
+// `on_navigation` and the user is asked first ("Open in Mail?"). Opening hands
+// the link to the OS handler through the opener plugin. "Always Open" is
+// remembered per scheme in external_protocols.json; settings can also set a
+// scheme to never open, or forget the decision so it's asked again.
+//
+// A page gets one prompt at a time, so a page looping on a link can't pile up
+// dialogs. Schemes with a history of running code through their handler are
+// never launched.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult};
+use tauri_plugin_opener::OpenerExt;
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::thumbnails;
+use crate::state::AppState;
+
+const EXTERNAL_PROTOCOLS_FILE: &str = "external_protocols.json";
+const OPEN: &str = "Open";
+const ALWAYS_OPEN: &str = "Always Open";
+const MAX_SCHEME_LEN: usize = 32;
+
+/// Schemes the webview loads itself, or that another navigation handler owns (abp:).
+const WEB_SCHEMES: &[&str] = &[
+    "http",
+    "https",
+    "about",
+    "blob",
+    "data",
+    "file",
+    "javascript",
+    "tauri",
+    "asset",
+    "ipc",
+    "abp",
+    thumbnails::SCHEME,
+];
+
+/// Handlers that have been used to run code from a link; never launched.
+const BLOCKED_SCHEMES: &[&str] = &["ms-msdt", "search-ms", "search", "ms-officecmd", "ms-cxh", "shell", "vbscript"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolDecision {
+    Allow,
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RememberedProtocol {
+    pub scheme: String,
+    pub app_name: String,
+    pub decision: ProtocolDecision,
+}
+
+/// Whether a navigation leaves the browser for another app.
+pub fn is_external(url: &Url) -> bool {
+    !WEB_SCHEMES.contains(&url.scheme())
+}
+
+pub fn is_blocked_scheme(scheme: &str) -> bool {
+    BLOCKED_SCHEMES.contains(&scheme)
+}
+
+/// What the prompt calls the handler. The OS doesn't tell us the app it will
+/// pick, so well-known schemes get a name and the rest a description.
+pub fn app_name(scheme: &str) -> String {
+    let name = match scheme {
+        "mailto" => "Mail",
+        "tel" | "callto" => "your phone app",
+        "sms" | "imessage" => "Messages",
+        "facetime" | "facetime-audio" => "FaceTime",
+        "zoommtg" | "zoomus" => "Zoom",
+        "msteams" => "Microsoft Teams",
+        "slack" => "Slack",
+        "discord" => "Discord",
+        "spotify" => "Spotify",
+        "steam" => "Steam",
+        "vscode" => "Visual Studio Code",
+        "magnet" => "your torrent client",
+        "webcal" => "Calendar",
+        "maps" => "Maps",
+        _ => return format!("the app for \"{}:\" links", scheme),
+    };
+    name.to_string()
+}
+
+fn normalize_scheme(scheme: &str) -> Result<String, String> {
+    let scheme = scheme.trim().trim_end_matches(':').to_ascii_lowercase();
+    let valid = scheme.len() <= MAX_SCHEME_LEN
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid || WEB_SCHEMES.contains(&scheme.as_str()) {
+        return Err(format!("Not an external protocol: {}", scheme));
+    }
+    Ok(scheme)
+}
+
+pub struct ExternalProtocolStore {
+    path: PathBuf,
+    decisions: Mutex<BTreeMap<String, ProtocolDecision>>,
+    prompting: Mutex<HashSet<String>>, // Webviews with a prompt open
+}
+
+impl ExternalProtocolStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(EXTERNAL_PROTOCOLS_FILE);
+        let decisions = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, decisions: Mutex::new(decisions), prompting: Mutex::new(HashSet::new()) }
+    }
+
+    fn save(&self, decisions: &BTreeMap<String, ProtocolDecision>) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(decisions).unwrap_or_default()) {
+            eprintln!("[ExternalProtocols] Failed to save: {}", e);
+        }
+    }
+
+    pub fn decision(&self, scheme: &str) -> Option<ProtocolDecision> {
+        self.decisions.lock().unwrap().get(scheme).copied()
+    }
+
+    pub fn list(&self) -> Vec<RememberedProtocol> {
+        self.decisions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(scheme, &decision)| RememberedProtocol {
+                scheme: scheme.clone(),
+                app_name: app_name(scheme),
+                decision,
+            })
+            .collect()
+    }
+
+    /// Remembers a decision for a scheme, or with None forgets it. Returns the normalized scheme.
+    pub fn set(&self, scheme: &str, decision: Option<ProtocolDecision>) -> Result<String, String> {
+        let scheme = normalize_scheme(scheme)?;
+        if decision == Some(ProtocolDecision::Allow) && is_blocked_scheme(&scheme) {
+            return Err(format!("\"{}:\" links can't be opened", scheme));
+        }
+        let mut decisions = self.decisions.lock().unwrap();
+        match decision {
+            Some(decision) => decisions.insert(scheme.clone(), decision),
+            None => decisions.remove(&scheme),
+        };
+        self.save(&decisions);
+        Ok(scheme)
+    }
+
+    /// Claims the prompt for a webview; false if it already has one open.
+    fn start_prompt(&self, webview_label: &str) -> bool {
+        self.prompting.lock().unwrap().insert(webview_label.to_string())
+    }
+
+    fn end_prompt(&self, webview_label: &str) {
+        self.prompting.lock().unwrap().remove(webview_label);
+    }
+}
+
+fn launch(app: &AppHandle, url: &Url) {
+    println!("[ExternalProtocols] Opening {}: link", url.scheme());
+    if let Err(e) = app.opener().open_url(url.as_str(), None::<&str>) {
+        eprintln!("[ExternalProtocols] Failed to open {}: {}", url.scheme(), e);
+    }
+}
+
+/// Navigation handler for tab webviews. Returns false for links to other apps,
+/// which never load in the tab.
+pub fn on_navigation(app: &AppHandle, webview_label: &str, url: &Url) -> bool {
+    if !is_external(url) {
+        return true;
+    }
+    let Some(state) = app.try_state::<AppState>() else {
+        return false;
+    };
+    let scheme = url.scheme();
+    if is_blocked_scheme(scheme) {
+        println!("[ExternalProtocols] Blocked a {}: link", scheme);
+        return false;
+    }
+    match state.external_protocols.decision(scheme) {
+        Some(ProtocolDecision::Allow) => launch(app, url),
+        Some(ProtocolDecision::Block) => println!("[ExternalProtocols] Not opening {}: links", scheme),
+        None => prompt(app, &state, webview_label, url),
+    }
+    false
+}
+
+fn prompt(app: &AppHandle, state: &AppState, webview_label: &str, url: &Url) {
+    if !state.external_protocols.start_prompt(webview_label) {
+        println!("[ExternalProtocols] Prompt already open, dropping {}: link", url.scheme());
+        return;
+    }
+    let page = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.webview_label == webview_label)
+        .and_then(|t| Url::parse(&t.url).ok())
+        .and_then(|u| u.host_str().map(str::to_string));
+    let name = app_name(url.scheme());
+    let message = match page {
+        Some(host) => format!("{} wants to open {}.\n\n{}", host, name, url),
+        None => format!("This page wants to open {}.\n\n{}", name, url),
+    };
+
+    let app_handle = app.clone();
+    let label = webview_label.to_string();
+    let url = url.clone();
+    app.dialog()
+        .message(message)
+        .title(format!("Open in {}?", name))
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            OPEN.to_string(),
+            ALWAYS_OPEN.to_string(),
+            "Cancel".to_string(),
+        ))
+        .show_with_result(move |result| {
+            let Some(state) = app_handle.try_state::<AppState>() else {
+                return;
+            };
+            state.external_protocols.end_prompt(&label);
+            let always = match result {
+                MessageDialogResult::Yes => false,
+                MessageDialogResult::No => true,
+                MessageDialogResult::Custom(button) if button == OPEN => false,
+                MessageDialogResult::Custom(button) if button == ALWAYS_OPEN => true,
+                _ => return,
+            };
+            if always {
+                if let Err(e) = state.external_protocols.set(url.scheme(), Some(ProtocolDecision::Allow)) {
+                    eprintln!("[ExternalProtocols] Failed to remember {}: {}", url.scheme(), e);
+                }
+            }
+            launch(&app_handle, &url);
+        });
+}
+
+#[tauri::command]
+pub fn get_external_protocols(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<RememberedProtocol>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.external_protocols.list())
+}
+
+/// Remembers (or with None forgets) whether links with a scheme open without asking.
+#[tauri::command]
+pub fn set_external_protocol(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    scheme: String,
+    decision: Option<ProtocolDecision>,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let scheme = state.external_protocols.set(&scheme, decision).map_err(BrowserError::InvalidInput)?;
+    println!("[ExternalProtocols] {}: {:?}", scheme, decision);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("mailto:someone@example.com", true)]
+    #[case("zoommtg://zoom.us/join?confno=123", true)]
+    #[case("spotify:track:4uLU6hMCjMI75M1A2tKUQC", true)]
+    #[case("https://example.com/", false)]
+    #[case("about:blank", false)]
+    #[case("blob:https://example.com/1234", false)]
+    #[case("abp:subscribe?location=https://example.com/list.txt", false)]
+    #[case("thumbnail://localhost/example.com", false)]
+    fn test_is_external(#[case] url: &str, #[case] expected: bool) {
+        assert_eq!(is_external(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[rstest]
+    #[case("mailto", "Mail")]
+    #[case("zoommtg", "Zoom")]
+    #[case("obsidian", "the app for \"obsidian:\" links")]
+    fn test_app_name(#[case] scheme: &str, #[case] expected: &str) {
+        assert_eq!(app_name(scheme), expected);
+    }
+
+    #[test]
+    fn test_decisions_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ExternalProtocolStore::new(dir.path().to_path_buf());
+        assert_eq!(store.set("MailTo:", Some(ProtocolDecision::Allow)), Ok("mailto".to_string()));
+        store.set("spotify", Some(ProtocolDecision::Block)).unwrap();
+        assert!(store.set("https", Some(ProtocolDecision::Allow)).is_err());
+        assert!(store.set("ms-msdt", Some(ProtocolDecision::Allow)).is_err());
+        assert!(store.set("not a scheme", Some(ProtocolDecision::Allow)).is_err());
+
+        let reopened = ExternalProtocolStore::new(dir.path().to_path_buf());
+        assert_eq!(reopened.decision("mailto"), Some(ProtocolDecision::Allow));
+        assert_eq!(reopened.decision("spotify"), Some(ProtocolDecision::Block));
+        reopened.set("spotify", None).unwrap();
+        assert_eq!(reopened.decision("spotify"), None);
+        assert_eq!(reopened.list().len(), 1);
+    }
+
+    #[test]
+    fn test_one_prompt_per_webview() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ExternalProtocolStore::new(dir.path().to_path_buf());
+        assert!(store.start_prompt("tab-1"));
+        assert!(!store.start_prompt("tab-1"));
+        assert!(store.start_prompt("tab-2"));
+        store.end_prompt("tab-1");
+        assert!(store.start_prompt("tab-1"));
+    }
+}
@@ -0,0 +1,173 @@
+// Filter list subscription links.
+//
+// `abp:subscribe?location=<url>&title=<name>` links (the de facto standard used by
+// filter list sites) are intercepted in tab navigation and turned into a prompt to
+// add the list to AdBlockManager. Plain .txt links are allowed to load as usual;
+// meanwhile the start of the file is fetched, and if it's an Adblock-style list the
+// same prompt is shown.
+
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use url::Url;
+
+use crate::adblock_manager::normalize_list_url;
+use crate::modules::proxy;
+use crate::state::AppState;
+
+const SNIFF_BYTES: usize = 16 * 1024;
+const SNIFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscribeRequest {
+    pub location: String,
+    pub title: Option<String>,
+}
+
+/// Parses `abp:subscribe?location=...` (and the `abp://subscribe/?...` form).
+pub fn parse_abp_url(url: &Url) -> Option<SubscribeRequest> {
+    if url.scheme() != "abp" {
+        return None;
+    }
+    let action = url.host_str().unwrap_or_else(|| url.path()).trim_matches('/');
+    if !action.eq_ignore_ascii_case("subscribe") {
+        return None;
+    }
+    let mut location = None;
+    let mut title = None;
+    for (key, value) in url.query_pairs() {
+        match &*key {
+            "location" => location = Some(value.into_owned()),
+            "title" if !value.trim().is_empty() => title = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    let location = normalize_list_url(&location?).ok()?;
+    Some(SubscribeRequest { location, title })
+}
+
+fn is_txt_link(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https") && url.path().to_ascii_lowercase().ends_with(".txt")
+}
+
+/// Adblock Plus syntax lists start with an `[Adblock Plus x.y]` header and/or
+/// `! Title:` metadata.
+pub fn looks_like_filter_list(head: &str) -> bool {
+    head.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .take(30)
+        .any(|l| l.starts_with("[Adblock") || l.starts_with("! Title:"))
+}
+
+/// Navigation handler for tab webviews. Returns false for abp: links, which never
+/// load in the tab.
+pub fn on_navigation(app: &AppHandle, url: &Url) -> bool {
+    if url.scheme() == "abp" {
+        match parse_abp_url(url) {
+            Some(request) => prompt_subscribe(app, request),
+            None => println!("[FilterSubscribe] Ignoring malformed link: {}", url),
+        }
+        return false;
+    }
+    if is_txt_link(url) {
+        let app = app.clone();
+        let url = url.clone();
+        tauri::async_runtime::spawn(async move {
+            if sniff_filter_list(&app, &url).await {
+                prompt_subscribe(&app, SubscribeRequest { location: url.to_string(), title: None });
+            }
+        });
+    }
+    true
+}
+
+/// Reads the start of a .txt link, through the tabs' proxy.
+async fn sniff_filter_list(app: &AppHandle, url: &Url) -> bool {
+    let Some(Ok(client)) = app.try_state::<AppState>().map(|s| proxy::http_client(&s, SNIFF_TIMEOUT)) else {
+        return false;
+    };
+    let Ok(mut response) = client.get(url.as_str()).send().await else {
+        return false;
+    };
+    if !response.status().is_success() {
+        return false;
+    }
+    let mut head: Vec<u8> = Vec::new();
+    while head.len() < SNIFF_BYTES {
+        match response.chunk().await {
+            Ok(Some(chunk)) => head.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    looks_like_filter_list(&String::from_utf8_lossy(&head))
+}
+
+fn prompt_subscribe(app: &AppHandle, request: SubscribeRequest) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    if state.adblock.filter_lists().iter().any(|l| l.url == request.location) {
+        println!("[FilterSubscribe] Already subscribed to {}", request.location);
+        return;
+    }
+
+    let name = request.title.clone().unwrap_or_else(|| request.location.clone());
+    let app_handle = app.clone();
+    app.dialog()
+        .message(format!("Add \"{}\" to your filter lists?\n\n{}", name, request.location))
+        .title("Subscribe to Filter List")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom("Subscribe".to_string(), "Cancel".to_string()))
+        .show(move |confirmed| {
+            if !confirmed {
+                return;
+            }
+            let Some(state) = app_handle.try_state::<AppState>() else {
+                return;
+            };
+            match state.adblock.add_filter_list(&request.location, request.title.as_deref()) {
+                Ok(_) => state.adblock.spawn_update_thread(),
+                Err(e) => eprintln!("[FilterSubscribe] Failed to subscribe: {}", e),
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "abp:subscribe?location=https%3A%2F%2Fexample.com%2Flist.txt&title=Example%20List",
+        Some(("https://example.com/list.txt", Some("Example List")))
+    )]
+    #[case("abp://subscribe/?location=https://example.com/list.txt", Some(("https://example.com/list.txt", None)))]
+    #[case("abp:subscribe?location=javascript:alert(1)", None)]
+    #[case("abp:subscribe?title=NoLocation", None)]
+    #[case("abp:unsubscribe?location=https://example.com/list.txt", None)]
+    #[case("https://example.com/list.txt", None)]
+    fn test_parse_abp_url(#[case] url: &str, #[case] expected: Option<(&str, Option<&str>)>) {
+        let parsed = parse_abp_url(&Url::parse(url).unwrap());
+        let expected = expected.map(|(location, title)| SubscribeRequest {
+            location: location.to_string(),
+            title: title.map(str::to_string),
+        });
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn test_looks_like_filter_list() {
+        assert!(looks_like_filter_list("[Adblock Plus 2.0]\n! Title: EasyList\n||ads^"));
+        assert!(looks_like_filter_list("\n\n! Title: My List\n##.ad"));
+        assert!(!looks_like_filter_list("User-agent: *\nDisallow: /"));
+    }
+
+    #[test]
+    fn test_is_txt_link() {
+        assert!(is_txt_link(&Url::parse("https://example.com/filters/List.TXT").unwrap()));
+        assert!(!is_txt_link(&Url::parse("https://example.com/list.txt.html").unwrap()));
+        assert!(!is_txt_link(&Url::parse("file:///tmp/list.txt").unwrap()));
+    }
+}
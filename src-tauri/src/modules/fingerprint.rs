@@ -0,0 +1,607 @@
+// Fingerprinting resistance.
+//
+// Canvas, AudioContext and WebGL readbacks get small deterministic noise. The noise
+// is keyed by a per-profile secret and the page's hostname, so one site always sees
+// the same fingerprint while two sites can't match theirs up. The secret lives in
+// its own file (not settings.json) and only ever appears inside the injected
+// closure. Sites in `Settings.fingerprint_exceptions` get no noise.
+//
+// With `Settings.limit_font_detection`, canvas `measureText` stops revealing which
+// fonts are installed: text in a font outside STANDARD_FONTS (and not loaded by
+// the page as a web font) is measured in the fallback font instead, and a
+// site-keyed subset of those fonts then gets a sub-pixel width offset. Each site
+// sees a stable but made-up font list. Probing through the layout of DOM elements
+// (offsetWidth of hidden spans) isn't covered.
+//
+// Separately, the anti-bot script hides automation hints from bot checks. How much
+// it spoofs is `Settings.spoofing_profile`, overridable per site through
+// `Settings.site_settings`.
+//
+// Sites can also be given a timezone and/or locale through `Settings.site_settings`
+// (`region_script`): Date, Intl and navigator.language then report those instead
+// of the system's. Dates built from local fields (`new Date(2024, 0, 1)`, setHours)
+// still use the real timezone, and the Accept-Language header is unchanged.
+//
+// The scripts are fixed when a tab's webview is created, so toggling protection or
+// exceptions applies to tabs opened afterwards.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::modules::profile;
+use crate::settings::SiteSettings;
+
+/// How far the anti-bot script goes in making the webview look like a regular browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpoofingProfile {
+    Off,
+    Minimal, // Only hides navigator.webdriver
+    #[default]
+    Full,    // Also fakes plugins and languages when they're empty
+}
+
+/// Hides automation hints and fills in navigator fields that headless-looking
+/// webviews leave empty, as far as the page's profile allows.
+const ANTI_BOT_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const DEFAULT_PROFILE = __DEFAULT_PROFILE__;
+    const SITE_PROFILES = __SITE_PROFILES__;
+    const host = location.hostname.toLowerCase();
+    const site = Object.keys(SITE_PROFILES)
+        .filter(s => host === s || host.endsWith('.' + s))
+        .sort((a, b) => b.length - a.length)[0];
+    const profile = site ? SITE_PROFILES[site] : DEFAULT_PROFILE;
+    if (profile === 'off') return;
+
+    Object.defineProperty(navigator, 'webdriver', { get: () => undefined });
+    if (profile !== 'full') return;
+
+    // Mock Plugins to look like a standard Mac
+    if (navigator.plugins.length === 0) {
+        Object.defineProperty(navigator, 'plugins', {
+            get: () => [1, 2, 3, 4, 5],
+        });
+    }
+
+    // Mock Languages if missing
+    if (!navigator.languages || navigator.languages.length === 0) {
+        Object.defineProperty(navigator, 'languages', {
+            get: () => ['en-US', 'en'],
+        });
+    }
+})();
+"#;
+
+/// The anti-bot script with the global profile and per-site overrides baked in;
+/// the most specific matching site wins.
+pub fn anti_bot_script(default: SpoofingProfile, sites: &BTreeMap<String, SiteSettings>) -> String {
+    let overrides: BTreeMap<&str, SpoofingProfile> = sites
+        .iter()
+        .filter_map(|(site, s)| Some((site.as_str(), s.spoofing_profile?)))
+        .collect();
+    ANTI_BOT_SCRIPT_TEMPLATE
+        .replace("__DEFAULT_PROFILE__", &serde_json::to_string(&default).unwrap_or_else(|_| "\"full\"".to_string()))
+        .replace("__SITE_PROFILES__", &serde_json::to_string(&overrides).unwrap_or_else(|_| "{}".to_string()))
+}
+
+/// Canonical form of an IANA timezone name ("utc" -> "UTC", "Europe/Paris").
+/// Whether the zone exists is up to the webview; unknown zones are ignored there.
+pub fn normalize_timezone(input: &str) -> Result<String, String> {
+    let tz = input.trim();
+    if tz.eq_ignore_ascii_case("utc") {
+        return Ok("UTC".to_string());
+    }
+    let valid = !tz.is_empty()
+        && tz.split('/').all(|part| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+        });
+    if valid {
+        Ok(tz.to_string())
+    } else {
+        Err(format!("Invalid timezone: {}", input))
+    }
+}
+
+/// Canonical form of a BCP 47 language tag ("pt_br" -> "pt-BR", "zh-hant-tw" -> "zh-Hant-TW").
+pub fn normalize_locale(input: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid locale: {}", input);
+    let mut subtags = input.trim().split(['-', '_']);
+    let language = subtags.next().filter(|l| (2..=3).contains(&l.len()) && l.chars().all(|c| c.is_ascii_alphabetic()));
+    let mut tag = language.ok_or_else(invalid)?.to_ascii_lowercase();
+    for subtag in subtags {
+        if !(1..=8).contains(&subtag.len()) || !subtag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+        tag.push('-');
+        match subtag.len() {
+            2 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => tag.push_str(&subtag.to_ascii_uppercase()),
+            4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                tag.push_str(&subtag[..1].to_ascii_uppercase());
+                tag.push_str(&subtag[1..].to_ascii_lowercase());
+            }
+            _ => tag.push_str(&subtag.to_ascii_lowercase()),
+        }
+    }
+    Ok(tag)
+}
+
+/// Makes Date, Intl and navigator report a site's chosen timezone and locale.
+const REGION_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const SITES = __SITES__;
+    const host = location.hostname.toLowerCase();
+    const site = Object.keys(SITES)
+        .filter(s => host === s || host.endsWith('.' + s))
+        .sort((a, b) => b.length - a.length)[0];
+    if (!site) return;
+    const TZ = SITES[site].timezone;
+    const LOCALE = SITES[site].locale;
+
+    // Patched functions keep reporting [native code]
+    const nativeToString = Function.prototype.toString;
+    const originals = new WeakMap();
+    const toString = function() {
+        return nativeToString.call(originals.get(this) || this);
+    };
+    originals.set(toString, nativeToString);
+    Function.prototype.toString = toString;
+
+    function patch(obj, name, make) {
+        const original = obj && obj[name];
+        if (typeof original !== 'function') return;
+        const replacement = make(original);
+        originals.set(replacement, original);
+        obj[name] = replacement;
+    }
+    const pickLocale = locales => (locales === undefined && LOCALE ? LOCALE : locales);
+    const withZone = options => (TZ ? { timeZone: TZ, ...options } : options);
+
+    try {
+        // Zone lookups go through the real Intl, before it's wrapped below
+        if (TZ) {
+            const getTime = Date.prototype.getTime;
+            const fields = new Intl.DateTimeFormat('en-US', {
+                timeZone: TZ, hourCycle: 'h23', year: 'numeric', month: 'numeric', day: 'numeric',
+                hour: 'numeric', minute: 'numeric', second: 'numeric',
+            });
+            const zoneNames = new Intl.DateTimeFormat('en-US', { timeZone: TZ, timeZoneName: 'long' });
+            const partsOf = (format, t) => Object.fromEntries(format.formatToParts(t).map(p => [p.type, p.value]));
+
+            // Minutes east of UTC in TZ at time t
+            function offset(t) {
+                const p = partsOf(fields, t);
+                const local = Date.UTC(+p.year, +p.month - 1, +p.day, +p.hour, +p.minute, +p.second);
+                return Math.round((local - (t - (((t % 1000) + 1000) % 1000))) / 60000);
+            }
+            // A Date whose UTC fields are this date's wall-clock fields in TZ
+            function shifted(date) {
+                const t = getTime.call(date);
+                return isNaN(t) ? null : new Date(t + offset(t) * 60000);
+            }
+
+            patch(Date.prototype, 'getTimezoneOffset', () => function() {
+                const t = getTime.call(this);
+                return isNaN(t) ? NaN : -offset(t);
+            });
+            for (const field of ['FullYear', 'Month', 'Date', 'Day', 'Hours', 'Minutes', 'Seconds', 'Milliseconds']) {
+                const getUTC = Date.prototype['getUTC' + field];
+                patch(Date.prototype, 'get' + field, () => function() {
+                    const s = shifted(this);
+                    return s ? getUTC.call(s) : NaN;
+                });
+            }
+
+            const DAYS = ['Sun', 'Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat'];
+            const MONTHS = ['Jan', 'Feb', 'Mar', 'Apr', 'May', 'Jun', 'Jul', 'Aug', 'Sep', 'Oct', 'Nov', 'Dec'];
+            const pad = (n, width = 2) => String(n).padStart(width, '0');
+            const datePart = s => `${DAYS[s.getUTCDay()]} ${MONTHS[s.getUTCMonth()]} ${pad(s.getUTCDate())} ${pad(s.getUTCFullYear(), 4)}`;
+            function timePart(s, t) {
+                const o = offset(t);
+                const zone = `GMT${o < 0 ? '-' : '+'}${pad(Math.floor(Math.abs(o) / 60))}${pad(Math.abs(o) % 60)}`;
+                return `${pad(s.getUTCHours())}:${pad(s.getUTCMinutes())}:${pad(s.getUTCSeconds())} ${zone} (${partsOf(zoneNames, t).timeZoneName})`;
+            }
+            patch(Date.prototype, 'toString', original => function() {
+                const s = shifted(this);
+                return s ? `${datePart(s)} ${timePart(s, getTime.call(this))}` : original.call(this);
+            });
+            patch(Date.prototype, 'toDateString', original => function() {
+                const s = shifted(this);
+                return s ? datePart(s) : original.call(this);
+            });
+            patch(Date.prototype, 'toTimeString', original => function() {
+                const s = shifted(this);
+                return s ? timePart(s, getTime.call(this)) : original.call(this);
+            });
+        }
+
+        for (const name of ['toLocaleString', 'toLocaleDateString', 'toLocaleTimeString']) {
+            patch(Date.prototype, name, original => function(locales, options) {
+                return original.call(this, pickLocale(locales), withZone(options));
+            });
+        }
+        for (const name of ['DateTimeFormat', 'NumberFormat', 'Collator', 'PluralRules', 'RelativeTimeFormat', 'ListFormat', 'Segmenter']) {
+            patch(Intl, name, Original => {
+                const wrapped = function(locales, options) {
+                    return new Original(pickLocale(locales), name === 'DateTimeFormat' ? withZone(options) : options);
+                };
+                Object.defineProperty(wrapped, 'name', { value: name });
+                wrapped.prototype = Original.prototype;
+                wrapped.supportedLocalesOf = Original.supportedLocalesOf;
+                return wrapped;
+            });
+        }
+
+        if (LOCALE) {
+            patch(Number.prototype, 'toLocaleString', original => function(locales, options) {
+                return original.call(this, pickLocale(locales), options);
+            });
+            patch(String.prototype, 'localeCompare', original => function(that, locales, options) {
+                return original.call(this, that, pickLocale(locales), options);
+            });
+            const language = LOCALE.split('-')[0];
+            const languages = Object.freeze(language === LOCALE ? [LOCALE] : [LOCALE, language]);
+            Object.defineProperty(navigator, 'language', { get: () => LOCALE, configurable: true });
+            Object.defineProperty(navigator, 'languages', { get: () => languages, configurable: true });
+        }
+    } catch (e) {
+        console.warn('[Fingerprint] Region override not fully applied:', e);
+    }
+})();
+"#;
+
+/// The timezone/locale shim, or None when no site overrides either.
+pub fn region_script(sites: &BTreeMap<String, SiteSettings>) -> Option<String> {
+    let overrides: BTreeMap<&str, serde_json::Value> = sites
+        .iter()
+        .filter(|(_, s)| s.timezone.is_some() || s.locale.is_some())
+        .map(|(site, s)| (site.as_str(), serde_json::json!({ "timezone": s.timezone, "locale": s.locale })))
+        .collect();
+    if overrides.is_empty() {
+        return None;
+    }
+    let overrides = serde_json::to_string(&overrides).unwrap_or_else(|_| "{}".to_string());
+    Some(REGION_SCRIPT_TEMPLATE.replace("__SITES__", &overrides))
+}
+
+const NOISE_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const SECRET = "__SECRET__";
+    const EXCEPTIONS = __EXCEPTIONS__;
+    const LIMIT_FONTS = __LIMIT_FONTS__;
+    const STANDARD_FONTS = __STANDARD_FONTS__;
+    const host = location.hostname.toLowerCase();
+    if (!host || EXCEPTIONS.some(site => host === site || host.endsWith('.' + site))) return;
+
+    // String hash -> 32-bit seed
+    function hash(str) {
+        let h = 1779033703 ^ str.length;
+        for (let i = 0; i < str.length; i++) {
+            h = Math.imul(h ^ str.charCodeAt(i), 3432918353);
+            h = (h << 13) | (h >>> 19);
+        }
+        h = Math.imul(h ^ (h >>> 16), 2246822507);
+        h = Math.imul(h ^ (h >>> 13), 3266489909);
+        return (h ^ (h >>> 16)) >>> 0;
+    }
+    const SITE_SEED = hash(SECRET + '|' + host);
+
+    // mulberry32: the same key always yields the same stream on this site
+    function rng(key) {
+        let a = (SITE_SEED ^ hash(key)) >>> 0;
+        return function() {
+            a = (a + 0x6D2B79F5) >>> 0;
+            let t = a;
+            t = Math.imul(t ^ (t >>> 15), t | 1);
+            t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+            return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+        };
+    }
+
+    // Patched functions keep reporting [native code]
+    const nativeToString = Function.prototype.toString;
+    const originals = new WeakMap();
+    const toString = function() {
+        return nativeToString.call(originals.get(this) || this);
+    };
+    originals.set(toString, nativeToString);
+    Function.prototype.toString = toString;
+
+    function patch(proto, name, make) {
+        const original = proto && proto[name];
+        if (typeof original !== 'function') return;
+        const replacement = make(original);
+        originals.set(replacement, original);
+        proto[name] = replacement;
+    }
+
+    // Flip the low bit of ~0.5% of pixels; positions depend only on site and size,
+    // so the same drawing always reads back the same
+    function noisePixels(data, width, height) {
+        const pixels = Math.floor(data.length / 4);
+        if (!pixels) return;
+        const next = rng('pixels:' + width + 'x' + height);
+        const count = Math.max(1, Math.floor(pixels / 200));
+        for (let i = 0; i < count; i++) {
+            const p = Math.floor(next() * pixels) * 4;
+            data[p + Math.floor(next() * 3)] ^= 1;
+        }
+    }
+
+    try {
+        // --- Canvas ---
+        const getImageData = CanvasRenderingContext2D.prototype.getImageData;
+        patch(CanvasRenderingContext2D.prototype, 'getImageData', original => function(...args) {
+            const image = original.apply(this, args);
+            noisePixels(image.data, image.width, image.height);
+            return image;
+        });
+
+        function noisedCopy(canvas) {
+            if (!canvas.width || !canvas.height) return canvas;
+            const copy = document.createElement('canvas');
+            copy.width = canvas.width;
+            copy.height = canvas.height;
+            const ctx = copy.getContext('2d');
+            ctx.drawImage(canvas, 0, 0);
+            const image = getImageData.call(ctx, 0, 0, copy.width, copy.height);
+            noisePixels(image.data, image.width, image.height);
+            ctx.putImageData(image, 0, 0);
+            return copy;
+        }
+        patch(HTMLCanvasElement.prototype, 'toDataURL', original => function(...args) {
+            return original.apply(noisedCopy(this), args);
+        });
+        patch(HTMLCanvasElement.prototype, 'toBlob', original => function(...args) {
+            return original.apply(noisedCopy(this), args);
+        });
+
+        // --- WebGL ---
+        for (const ctor of [window.WebGLRenderingContext, window.WebGL2RenderingContext]) {
+            if (!ctor) continue;
+            patch(ctor.prototype, 'readPixels', original => function(...args) {
+                original.apply(this, args);
+                const pixels = args[6];
+                if (pixels instanceof Uint8Array || pixels instanceof Uint8ClampedArray) {
+                    noisePixels(pixels, args[2], args[3]);
+                }
+            });
+            patch(ctor.prototype, 'getParameter', original => function(pname) {
+                // UNMASKED_VENDOR_WEBGL / UNMASKED_RENDERER_WEBGL name the exact GPU
+                if (pname === 0x9245) return 'WebKit';
+                if (pname === 0x9246) return 'WebKit WebGL';
+                return original.call(this, pname);
+            });
+        }
+
+        // --- Audio ---
+        const noisedBuffers = new WeakSet();
+        function noiseSamples(data, key) {
+            const next = rng(key);
+            for (let i = 0; i < data.length; i += 100) {
+                data[i] += (next() - 0.5) * 1e-7;
+            }
+        }
+        if (window.AudioBuffer) {
+            patch(AudioBuffer.prototype, 'getChannelData', original => function(channel) {
+                const data = original.call(this, channel);
+                if (!noisedBuffers.has(data)) {
+                    noisedBuffers.add(data);
+                    noiseSamples(data, 'audio:' + this.length + ':' + channel);
+                }
+                return data;
+            });
+            patch(AudioBuffer.prototype, 'copyFromChannel', original => function(destination, channel, offset) {
+                original.call(this, destination, channel, offset);
+                noiseSamples(destination, 'audio:' + this.length + ':' + channel + ':' + (offset || 0));
+            });
+        }
+        if (window.AnalyserNode) {
+            patch(AnalyserNode.prototype, 'getFloatFrequencyData', original => function(array) {
+                original.call(this, array);
+                const next = rng('analyser:' + array.length);
+                for (let i = 0; i < array.length; i++) {
+                    array[i] += (next() - 0.5) * 1e-4;
+                }
+            });
+        }
+
+        // --- Fonts ---
+        if (LIMIT_FONTS) {
+            // "italic bold 12px/1.5 'Foo Bar', serif" -> prefix and ["foo bar", "serif"]
+            const FONT_RE = /^(.*?[\d.]+(?:px|pt|pc|em|rem|ex|ch|%|in|cm|mm|q|vw|vh)(?:\s*\/\s*\S+)?\s+)(.+)$/i;
+            const standard = new Set(STANDARD_FONTS);
+            function isWebFont(family) {
+                if (!document.fonts) return false;
+                for (const face of document.fonts) {
+                    if (face.status === 'loaded' && face.family.replace(/["']/g, '').toLowerCase() === family) return true;
+                }
+                return false;
+            }
+            patch(CanvasRenderingContext2D.prototype, 'measureText', original => function(text) {
+                const font = this.font;
+                const match = FONT_RE.exec(font);
+                if (!match) return original.call(this, text);
+                const families = match[2].split(',').map(f => f.trim().replace(/^["']|["']$/g, '').toLowerCase());
+                const hidden = families.filter(f => !standard.has(f) && !isWebFont(f));
+                if (!hidden.length) return original.call(this, text);
+
+                const fallback = families.filter(f => !hidden.includes(f));
+                this.font = match[1] + (fallback.length ? fallback.map(f => JSON.stringify(f)).join(', ') : 'serif');
+                const metrics = original.call(this, text);
+                this.font = font;
+                if (families[0] !== hidden[0]) return metrics;
+                // About half the unknown fonts "exist" on a given site
+                const next = rng('font:' + hidden[0]);
+                if (next() < 0.5) return metrics;
+                const width = metrics.width * (1 + (next() + 0.5) * 1e-3);
+                return new Proxy(metrics, {
+                    get: (target, prop) => prop === 'width' ? width : Reflect.get(target, prop, target),
+                });
+            });
+        }
+    } catch (e) {
+        console.warn('[Fingerprint] Protection not fully applied:', e);
+    }
+})();
+"#;
+
+/// Font families that measure normally with `limit_font_detection` on: CSS generic
+/// families and fonts found on practically every desktop, which reveal nothing.
+const STANDARD_FONTS: &[&str] = &[
+    "serif", "sans-serif", "monospace", "cursive", "fantasy", "system-ui", "ui-serif", "ui-sans-serif",
+    "ui-monospace", "ui-rounded", "math", "emoji", "-apple-system", "blinkmacsystemfont", "arial", "helvetica",
+    "times", "times new roman", "courier", "courier new", "georgia", "verdana", "tahoma", "trebuchet ms",
+];
+
+/// Builds the noise script for a new tab.
+pub fn noise_script(secret: &str, exceptions: &[String], limit_fonts: bool) -> String {
+    let exceptions = serde_json::to_string(exceptions).unwrap_or_else(|_| "[]".to_string());
+    let standard_fonts = serde_json::to_string(STANDARD_FONTS).unwrap_or_else(|_| "[]".to_string());
+    NOISE_SCRIPT_TEMPLATE
+        .replace("__SECRET__", secret)
+        .replace("__EXCEPTIONS__", &exceptions)
+        .replace("__LIMIT_FONTS__", if limit_fonts { "true" } else { "false" })
+        .replace("__STANDARD_FONTS__", &standard_fonts)
+}
+
+pub fn secret_path(app: &AppHandle) -> PathBuf {
+    profile::data_dir(app)
+        .expect("failed to get app data dir")
+        .join("fingerprint_secret")
+}
+
+/// 256 bits of hex. RandomState is keyed from OS randomness, so this avoids a
+/// dependency on a random number crate.
+pub fn generate_secret() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    (0..4u64)
+        .map(|i| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(i);
+            hasher.write_u128(nanos);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// Reads the profile's secret, creating it on first use. A missing or corrupt
+/// file means a new secret, which changes every site's fingerprint once.
+pub fn load_or_create_secret(path: &Path) -> String {
+    if let Ok(existing) = fs::read_to_string(path) {
+        let existing = existing.trim();
+        if existing.len() == 64 && existing.chars().all(|c| c.is_ascii_hexdigit()) {
+            return existing.to_string();
+        }
+    }
+
+    let secret = generate_secret();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(path, &secret) {
+        eprintln!("[Fingerprint] Failed to save secret: {}", e);
+    }
+    secret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_secret() {
+        let a = generate_secret();
+        let b = generate_secret();
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_secret_persists() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("fingerprint_secret");
+        let first = load_or_create_secret(&path);
+        assert_eq!(load_or_create_secret(&path), first);
+
+        fs::write(&path, "garbage").unwrap();
+        let replaced = load_or_create_secret(&path);
+        assert_ne!(replaced, first);
+        assert_eq!(replaced.len(), 64);
+    }
+
+    #[test]
+    fn test_anti_bot_script_profiles() {
+        let mut sites = BTreeMap::new();
+        sites.insert("bank.example".to_string(), SiteSettings { spoofing_profile: Some(SpoofingProfile::Off), ..Default::default() });
+        sites.insert("other.example".to_string(), SiteSettings::default());
+        let script = anti_bot_script(SpoofingProfile::Minimal, &sites);
+        assert!(script.contains(r#"const DEFAULT_PROFILE = "minimal";"#));
+        // Sites without a spoofing override aren't listed
+        assert!(script.contains(r#"const SITE_PROFILES = {"bank.example":"off"};"#));
+    }
+
+    #[rstest]
+    #[case("utc", Ok("UTC"))]
+    #[case(" Europe/Paris ", Ok("Europe/Paris"))]
+    #[case("America/Argentina/Buenos_Aires", Ok("America/Argentina/Buenos_Aires"))]
+    #[case("Etc/GMT+5", Ok("Etc/GMT+5"))]
+    #[case("Europe//Paris", Err(()))]
+    #[case("Europe/Paris\"", Err(()))]
+    #[case("", Err(()))]
+    fn test_normalize_timezone(#[case] input: &str, #[case] expected: Result<&str, ()>) {
+        assert_eq!(normalize_timezone(input).as_deref().map_err(|_| ()), expected);
+    }
+
+    #[rstest]
+    #[case("de", Ok("de"))]
+    #[case("pt_br", Ok("pt-BR"))]
+    #[case("zh-hant-tw", Ok("zh-Hant-TW"))]
+    #[case("es-419", Ok("es-419"))]
+    #[case("english", Err(()))]
+    #[case("en-US\"", Err(()))]
+    #[case("", Err(()))]
+    fn test_normalize_locale(#[case] input: &str, #[case] expected: Result<&str, ()>) {
+        assert_eq!(normalize_locale(input).as_deref().map_err(|_| ()), expected);
+    }
+
+    #[test]
+    fn test_region_script() {
+        let mut sites = BTreeMap::new();
+        assert_eq!(region_script(&sites), None);
+        sites.insert(
+            "travel.example".to_string(),
+            SiteSettings { timezone: Some("UTC".to_string()), ..Default::default() },
+        );
+        sites.insert(
+            "bank.example".to_string(),
+            SiteSettings { spoofing_profile: Some(SpoofingProfile::Off), ..Default::default() },
+        );
+        let script = region_script(&sites).unwrap();
+        assert!(script.contains(r#"const SITES = {"travel.example":{"locale":null,"timezone":"UTC"}};"#));
+    }
+
+    #[test]
+    fn test_noise_script_substitution() {
+        let script = noise_script("ab12", &["example.com".to_string(), "a\"b".to_string()], false);
+        assert!(script.contains(r#"const SECRET = "ab12";"#));
+        assert!(script.contains(r#"const EXCEPTIONS = ["example.com","a\"b"];"#));
+        assert!(script.contains("const LIMIT_FONTS = false;"));
+        assert!(!script.contains("__"));
+
+        let script = noise_script("ab12", &[], true);
+        assert!(script.contains("const LIMIT_FONTS = true;"));
+        assert!(script.contains(r#"const STANDARD_FONTS = ["serif","sans-serif","#));
+    }
+}
@@ -0,0 +1,156 @@
+// Focus management - single owner of "what should have keyboard focus".
+//
+// The toolbar, tab webviews, dropdown, find bar and settings window all compete for
+// focus. Instead of ad-hoc set_focus calls, callers ask the FocusManager, which
+// records the desired target (per tab for toolbar/content) and hands back what to
+// apply. Holding the lock while applying serializes transitions, so two requests
+// can't interleave their platform calls.
+//
+// The dropdown never takes focus: while it is open the toolbar keeps it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusTarget {
+    Toolbar,  // URL bar in the "main" webview
+    Content,  // Active tab's webview
+    Find,     // Find-in-page window
+    Settings, // Settings window
+}
+
+impl FocusTarget {
+    /// Overlays are temporary; closing one restores the previous target.
+    pub fn is_overlay(&self) -> bool {
+        matches!(self, Self::Find | Self::Settings)
+    }
+}
+
+#[derive(Debug)]
+pub struct FocusState {
+    current: FocusTarget,
+    // Last non-overlay target, restored when an overlay closes
+    underlying: FocusTarget,
+    per_tab: HashMap<String, FocusTarget>,
+}
+
+impl FocusState {
+    fn new() -> Self {
+        Self {
+            current: FocusTarget::Content,
+            underlying: FocusTarget::Content,
+            per_tab: HashMap::new(),
+        }
+    }
+
+    pub fn current(&self) -> FocusTarget {
+        self.current
+    }
+
+    /// Record a request and return the target to apply.
+    pub fn request(&mut self, target: FocusTarget, active_tab: Option<&str>) -> FocusTarget {
+        if !target.is_overlay() {
+            self.underlying = target;
+            if let Some(tab) = active_tab {
+                self.per_tab.insert(tab.to_string(), target);
+            }
+        }
+        self.current = target;
+        target
+    }
+
+    /// Focus moved without us asking (e.g. the user clicked into the page).
+    /// Recorded so later restores go to the right place; nothing to apply.
+    pub fn observe(&mut self, target: FocusTarget, active_tab: Option<&str>) {
+        self.request(target, active_tab);
+    }
+
+    /// An overlay (find bar, settings) or the dropdown closed: go back to the
+    /// underlying target.
+    pub fn restore(&mut self) -> FocusTarget {
+        self.current = self.underlying;
+        self.current
+    }
+
+    /// Switching tabs restores that tab's last toolbar/content choice.
+    /// Tabs we haven't seen yet start in the page.
+    pub fn switch_tab(&mut self, tab_id: &str) -> FocusTarget {
+        let target = self.per_tab.get(tab_id).copied().unwrap_or(FocusTarget::Content);
+        self.underlying = target;
+        self.current = target;
+        target
+    }
+
+    pub fn forget_tab(&mut self, tab_id: &str) {
+        self.per_tab.remove(tab_id);
+    }
+}
+
+pub struct FocusManager {
+    state: Mutex<FocusState>,
+}
+
+impl FocusManager {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(FocusState::new()) }
+    }
+
+    /// Hold the guard while applying the returned target so transitions are serialized.
+    pub fn lock(&self) -> MutexGuard<'_, FocusState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Default for FocusManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use FocusTarget::*;
+
+    #[test]
+    fn test_overlay_restores_previous_target() {
+        let mut state = FocusState::new();
+        state.request(Toolbar, Some("tab-1"));
+        assert_eq!(state.request(Find, Some("tab-1")), Find);
+        assert_eq!(state.restore(), Toolbar);
+    }
+
+    #[test]
+    fn test_tab_switch_remembers_per_tab_focus() {
+        let mut state = FocusState::new();
+        state.request(Toolbar, Some("tab-1"));
+        state.request(Content, Some("tab-2"));
+
+        assert_eq!(state.switch_tab("tab-1"), Toolbar);
+        assert_eq!(state.switch_tab("tab-2"), Content);
+        assert_eq!(state.switch_tab("tab-new"), Content);
+
+        state.forget_tab("tab-1");
+        assert_eq!(state.switch_tab("tab-1"), Content);
+    }
+
+    #[test]
+    fn test_observe_updates_restore_target() {
+        let mut state = FocusState::new();
+        state.request(Toolbar, Some("tab-1"));
+        state.observe(Content, Some("tab-1")); // user clicked into the page
+        state.request(Settings, Some("tab-1"));
+        assert_eq!(state.restore(), Content);
+        assert_eq!(state.current(), Content);
+    }
+
+    #[test]
+    fn test_overlay_does_not_overwrite_tab_memory() {
+        let mut state = FocusState::new();
+        state.request(Toolbar, Some("tab-1"));
+        state.request(Find, Some("tab-1"));
+        assert_eq!(state.switch_tab("tab-1"), Toolbar);
+    }
+}
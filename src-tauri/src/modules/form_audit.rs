@@ -0,0 +1,233 @@
+// Form submission audit: a warning before a form sends data off-site or in the clear.
+//
+// `AUDIT_SCRIPT` holds back submissions that might be risky: POST forms, and any
+// form with a password field, whose action goes to another host or over plain
+// http. Both the submit event (buttons, Enter, `requestSubmit`) and
+// `HTMLFormElement.prototype.submit`, which skips that event, are covered. Held
+// submissions ask `audit_form_submission`, which does the real check (another
+// site by eTLD+1, not just another host; http other than to this device). A
+// clean one goes ahead straight away. Otherwise it registers a FormSubmission
+// request with the PermissionsManager and emits "form-audit-warning" to the main
+// window, which offers to send anyway or cancel and answers through
+// `respond_form_audit`. No answer within two minutes, a navigation or a closed
+// tab cancels it. The page only learns whether to go ahead; a cancelled form
+// simply stays on the page.
+
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::cookie_policy;
+use crate::modules::permissions::PermissionKind;
+use crate::state::AppState;
+
+const DECISION_TIMEOUT: Duration = Duration::from_secs(120);
+const SEND_ANYWAY: &str = "send";
+
+/// Holds back forms that may post elsewhere or over http until they're audited.
+pub const AUDIT_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_FORM_AUDIT__ || !window.__TAURI__) return;
+    window.__SOVEREIGN_FORM_AUDIT__ = true;
+    const invoke = window.__TAURI__.core.invoke;
+    const approved = new WeakSet();
+
+    function target(form, submitter) {
+        const own = (attr) => submitter && submitter.hasAttribute(attr);
+        return {
+            action: own('formaction') ? submitter.formAction : form.action,
+            method: ((own('formmethod') ? submitter.formMethod : form.method) || 'get').toLowerCase(),
+            hasPassword: !!form.querySelector('input[type=password]')
+        };
+    }
+    // A cheap first pass; modules::form_audit decides
+    function needsAudit(t) {
+        if (t.method !== 'post' && !t.hasPassword) return false;
+        try {
+            const url = new URL(t.action, location.href);
+            return url.protocol === 'http:' || url.hostname !== location.hostname;
+        } catch (e) {
+            return false;
+        }
+    }
+    function audit(t, proceed) {
+        invoke('audit_form_submission', {
+            pageUrl: location.href, action: t.action, method: t.method, hasPassword: t.hasPassword
+        }).then(ok => { if (ok) proceed(); }).catch(() => {});
+    }
+
+    window.addEventListener('submit', e => {
+        const form = e.target;
+        if (!(form instanceof HTMLFormElement)) return;
+        if (approved.has(form)) {
+            approved.delete(form);
+            return;
+        }
+        const t = target(form, e.submitter);
+        if (!needsAudit(t)) return;
+        // Page handlers see the submission once it's cleared
+        e.preventDefault();
+        e.stopImmediatePropagation();
+        const submitter = e.submitter && e.submitter.form === form ? e.submitter : undefined;
+        audit(t, () => {
+            approved.add(form);
+            form.requestSubmit(submitter);
+        });
+    }, true);
+
+    const submit = HTMLFormElement.prototype.submit;
+    HTMLFormElement.prototype.submit = function() {
+        const t = target(this, null);
+        if (!needsAudit(t)) return submit.call(this);
+        audit(t, () => submit.call(this));
+    };
+})();
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditFinding {
+    ThirdParty, // Posts to another site
+    Insecure,   // Sent over plain http
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormAuditWarning {
+    pub request_id: u64,
+    pub tab_id: Option<String>,
+    pub page_host: String,
+    pub action_host: String,
+    pub findings: Vec<AuditFinding>,
+}
+
+fn is_loopback(url: &Url) -> bool {
+    match url.host() {
+        Some(url::Host::Domain(domain)) => domain == "localhost",
+        Some(url::Host::Ipv4(ip)) => ip.is_loopback(),
+        Some(url::Host::Ipv6(ip)) => ip.is_loopback(),
+        None => false,
+    }
+}
+
+/// What's wrong with a submission from `page` to `action`, if anything.
+pub fn audit(page: &Url, action: &Url, method: &str, has_password: bool) -> Vec<AuditFinding> {
+    let mut findings = Vec::new();
+    if !method.eq_ignore_ascii_case("post") && !has_password {
+        return findings;
+    }
+    if !matches!(page.scheme(), "http" | "https") || !matches!(action.scheme(), "http" | "https") {
+        return findings;
+    }
+    if cookie_policy::is_third_party(action.as_str(), page.as_str()) {
+        findings.push(AuditFinding::ThirdParty);
+    }
+    if action.scheme() == "http" && !is_loopback(action) {
+        findings.push(AuditFinding::Insecure);
+    }
+    findings
+}
+
+/// Whether a held-back submission may go ahead. Waits for the user when the
+/// audit finds something.
+#[tauri::command]
+pub async fn audit_form_submission(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    page_url: String,
+    action: String,
+    method: String,
+    has_password: bool,
+) -> Result<bool, BrowserError> {
+    if !state.settings.read().unwrap().form_audit {
+        return Ok(true);
+    }
+    let page = Url::parse(&page_url)?;
+    let action = page.join(&action)?;
+    let findings = audit(&page, &action, &method, has_password);
+    if findings.is_empty() {
+        return Ok(true);
+    }
+
+    let origin = page.origin().ascii_serialization();
+    let (request, answer) = state.permissions.request(webview.label(), &origin, PermissionKind::FormSubmission);
+    let tab_id = state.tabs.lock().unwrap().iter().find(|t| t.webview_label == webview.label()).map(|t| t.id.clone());
+    let warning = FormAuditWarning {
+        request_id: request.id,
+        tab_id,
+        page_host: page.host_str().unwrap_or_default().to_string(),
+        action_host: action.host_str().unwrap_or_default().to_string(),
+        findings,
+    };
+    println!(
+        "[FormAudit] Holding a form on {} for {} ({:?})",
+        warning.page_host, warning.action_host, warning.findings
+    );
+    if let Err(e) = app.emit_to("main", "form-audit-warning", &warning) {
+        // Nobody to ask, so the form waits out the timeout and is cancelled
+        eprintln!("[FormAudit] Failed to show the warning: {}", e);
+    }
+
+    let proceed = match tokio::time::timeout(DECISION_TIMEOUT, answer).await {
+        Ok(Ok(choice)) => choice.as_deref() == Some(SEND_ANYWAY),
+        _ => {
+            state.permissions.respond(request.id, None);
+            false
+        }
+    };
+    let _ = app.emit_to("main", "form-audit-resolved", request.id);
+    println!("[FormAudit] Request {}: {}", request.id, if proceed { "sent" } else { "cancelled" });
+    Ok(proceed)
+}
+
+/// The user's decision from the warning in the main window.
+#[tauri::command]
+pub fn respond_form_audit(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    request_id: u64,
+    proceed: bool,
+) -> Result<(), BrowserError> {
+    // Only the browser's own toolbar may answer, never the page that's waiting
+    if webview.label() != "main" {
+        return Err(BrowserError::NotAllowed("Not allowed from this webview".to_string()));
+    }
+    match state.permissions.get(request_id) {
+        Some(request) if request.kind == PermissionKind::FormSubmission => {
+            state.permissions.respond(request_id, proceed.then(|| SEND_ANYWAY.to_string()));
+            Ok(())
+        }
+        _ => Err(BrowserError::NotFound("No such request".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://shop.example.com/cart", "https://pay.example.com/charge", "post", false, vec![])] // Same site
+    #[case("https://example.com/login", "https://collect.evil.test/", "post", true, vec![AuditFinding::ThirdParty])]
+    #[case("https://example.com/login", "http://example.com/login", "post", true, vec![AuditFinding::Insecure])]
+    #[case("https://example.com/", "http://evil.test/", "post", false, vec![AuditFinding::ThirdParty, AuditFinding::Insecure])]
+    #[case("https://example.com/", "https://search.test/?q=", "get", false, vec![])] // Plain GET forms aren't posts
+    #[case("https://example.com/", "https://evil.test/", "get", true, vec![AuditFinding::ThirdParty])] // Password in a GET
+    #[case("http://localhost:3000/", "http://127.0.0.1:3000/login", "post", true, vec![AuditFinding::ThirdParty])]
+    #[case("http://localhost:3000/", "http://localhost:3000/login", "post", true, vec![])]
+    #[case("https://example.com/", "mailto:someone@example.com", "post", false, vec![])]
+    fn test_audit(
+        #[case] page: &str,
+        #[case] action: &str,
+        #[case] method: &str,
+        #[case] has_password: bool,
+        #[case] expected: Vec<AuditFinding>,
+    ) {
+        let page = Url::parse(page).unwrap();
+        let action = Url::parse(action).unwrap();
+        assert_eq!(audit(&page, &action, method, has_password), expected);
+    }
+}
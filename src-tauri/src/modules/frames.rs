@@ -0,0 +1,166 @@
+// Frame context for network blocking.
+//
+// A request made inside an iframe has two contexts: the frame document that issued
+// it (what filter options like $third-party and $domain= are matched against) and
+// the tab's top-level document (what the user's allowlist is keyed by). Browsers
+// send the frame document as Referer (often trimmed to its origin, which is all the
+// engine needs) and say what is being fetched in Sec-Fetch-Dest, so an iframe's
+// own document request is a "subdocument" sourced from its parent frame. The top
+// document is tracked per webview from page loads.
+//
+// macOS doesn't need this: WebKit evaluates WKContentRuleList triggers against the
+// right frame itself.
+
+use dashmap::DashMap;
+
+use crate::modules::navigation::guess_request_type;
+
+/// The headers of a request that carry its frame context.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrameHeaders<'a> {
+    pub referer: Option<&'a str>,
+    pub origin: Option<&'a str>,
+    pub fetch_dest: Option<&'a str>, // Sec-Fetch-Dest
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestContext {
+    pub frame_url: String, // Document that issued the request
+    pub top_url: String,   // Tab's top-level document
+    pub request_type: String,
+}
+
+/// adblock request type for a Sec-Fetch-Dest value.
+pub fn request_type_for_dest(dest: &str) -> Option<&'static str> {
+    Some(match dest {
+        "iframe" | "frame" | "fencedframe" => "subdocument",
+        "script" | "worker" | "sharedworker" | "serviceworker" | "audioworklet" | "paintworklet" => "script",
+        "style" => "stylesheet",
+        "image" => "image",
+        "font" => "font",
+        "audio" | "video" | "track" => "media",
+        "object" | "embed" => "object",
+        "websocket" => "websocket",
+        "empty" => "xmlhttprequest",
+        "report" => "ping",
+        _ => return None,
+    })
+}
+
+/// Top-level document per webview.
+pub struct FrameTracker {
+    top: DashMap<String, String>,
+}
+
+impl FrameTracker {
+    pub fn new() -> Self {
+        Self { top: DashMap::new() }
+    }
+
+    /// Records the document a webview's main frame started loading.
+    pub fn set_top(&self, webview_label: &str, url: &str) {
+        self.top.insert(webview_label.to_string(), url.to_string());
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.top.remove(webview_label);
+    }
+
+    /// Frame and top-level context for a subresource request, or None for a
+    /// top-level navigation. `fallback_type` is used when Sec-Fetch-Dest is missing.
+    pub fn context(&self, webview_label: &str, url: &str, headers: FrameHeaders, fallback_type: Option<&str>) -> Option<RequestContext> {
+        // Top-level documents are navigations, not subresources
+        if headers.fetch_dest == Some("document") {
+            return None;
+        }
+        let request_type = match headers.fetch_dest.and_then(request_type_for_dest).or(fallback_type) {
+            Some(t) => t.to_string(),
+            None => guess_request_type(url),
+        };
+        let top_url = self.top.get(webview_label).map(|t| t.clone());
+        // Without Referer or Origin the request can only be attributed to the tab itself
+        let frame_url = headers
+            .referer
+            .or(headers.origin)
+            .map(str::to_string)
+            .or_else(|| top_url.clone())
+            .unwrap_or_else(|| url.to_string());
+        let top_url = top_url.unwrap_or_else(|| frame_url.clone());
+        Some(RequestContext { frame_url, top_url, request_type })
+    }
+}
+
+impl Default for FrameTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("iframe", Some("subdocument"))]
+    #[case("serviceworker", Some("script"))]
+    #[case("style", Some("stylesheet"))]
+    #[case("empty", Some("xmlhttprequest"))]
+    #[case("manifest", None)]
+    fn test_request_type_for_dest(#[case] dest: &str, #[case] expected: Option<&str>) {
+        assert_eq!(request_type_for_dest(dest), expected);
+    }
+
+    #[test]
+    fn test_iframe_request_context() {
+        let tracker = FrameTracker::new();
+        tracker.set_top("webview-tab-1", "https://news.example/article");
+
+        // The iframe's own document: sourced from the parent frame
+        let iframe = FrameHeaders {
+            referer: Some("https://news.example/article"),
+            fetch_dest: Some("iframe"),
+            ..Default::default()
+        };
+        let ctx = tracker.context("webview-tab-1", "https://ads.net/frame.html", iframe, None).unwrap();
+        assert_eq!(ctx.request_type, "subdocument");
+        assert_eq!(ctx.frame_url, "https://news.example/article");
+
+        // A script inside the iframe: sourced from the iframe, top stays the tab
+        let script = FrameHeaders {
+            referer: Some("https://ads.net/"),
+            fetch_dest: Some("script"),
+            ..Default::default()
+        };
+        let ctx = tracker.context("webview-tab-1", "https://cdn.ads.net/x.js", script, None).unwrap();
+        assert_eq!(
+            ctx,
+            RequestContext {
+                frame_url: "https://ads.net/".to_string(),
+                top_url: "https://news.example/article".to_string(),
+                request_type: "script".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_context_fallbacks() {
+        let tracker = FrameTracker::new();
+        let top = FrameHeaders { fetch_dest: Some("document"), ..Default::default() };
+        assert_eq!(tracker.context("webview-tab-1", "https://example.com/", top, None), None);
+
+        // No headers and no known top document: the request is its own context
+        let ctx = tracker.context("webview-tab-1", "https://example.com/a.css", FrameHeaders::default(), None).unwrap();
+        assert_eq!(ctx.frame_url, "https://example.com/a.css");
+        assert_eq!(ctx.request_type, "stylesheet");
+
+        tracker.set_top("webview-tab-1", "https://example.com/");
+        let ctx = tracker.context("webview-tab-1", "https://t.net/p", FrameHeaders::default(), Some("ping")).unwrap();
+        assert_eq!(ctx.frame_url, "https://example.com/");
+        assert_eq!(ctx.request_type, "ping");
+
+        tracker.forget_webview("webview-tab-1");
+        let ctx = tracker.context("webview-tab-1", "https://t.net/p", FrameHeaders::default(), None).unwrap();
+        assert_eq!(ctx.top_url, "https://t.net/p");
+    }
+}
@@ -0,0 +1,129 @@
+// Guest windows, for letting someone else borrow the browser.
+//
+// A guest window is a private window that also leaves the profile alone: it
+// starts at the default homepage rather than the user's and gets none of the
+// user's settings, site exceptions, cookies or storage. It still gets the
+// protections of a tab with the default settings, and the browser's proxy (see
+// modules::browsing_webview). Like private windows it isn't a tab, so nothing it
+// visits is recorded in history, and links it opens in a new window stay in it.
+//
+// Its data store is non-persistent. On Windows and Linux it also gets a data
+// directory of its own in the temp directory, so its engine session isn't shared
+// with private windows, and that directory is deleted when the window closes.
+// Directories a crash left behind are removed at the next launch.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, WindowEvent};
+use url::Url;
+
+use crate::modules::browsing_webview::{self, DataStore};
+use crate::modules::{handoff, profile};
+use crate::settings::Settings;
+
+pub const LABEL_PREFIX: &str = "guest-window-";
+
+// The engine may still hold files for a moment after the window is gone
+const REMOVE_ATTEMPTS: u32 = 5;
+const REMOVE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub fn window_label(nanos: u128) -> String {
+    format!("{}{}", LABEL_PREFIX, nanos)
+}
+
+/// Parent of this profile's guest data directories.
+fn guest_root() -> PathBuf {
+    std::env::temp_dir().join(format!("sovereign-guest-{}", profile::active().unwrap_or("default")))
+}
+
+fn data_dir(label: &str) -> PathBuf {
+    guest_root().join(label)
+}
+
+fn remove_dir(path: &Path) -> Result<(), std::io::Error> {
+    match std::fs::remove_dir_all(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Deletes a closed guest window's data directory, retrying while the engine lets go of it.
+fn wipe(path: PathBuf) {
+    std::thread::spawn(move || {
+        for attempt in 1..=REMOVE_ATTEMPTS {
+            match remove_dir(&path) {
+                Ok(()) => {
+                    println!("[Guest] Wiped guest data");
+                    return;
+                }
+                Err(e) if attempt == REMOVE_ATTEMPTS => {
+                    eprintln!("[Guest] Failed to wipe {}: {}", path.display(), e);
+                }
+                Err(_) => std::thread::sleep(REMOVE_RETRY_DELAY),
+            }
+        }
+    });
+}
+
+/// Removes guest data left by windows that didn't close cleanly.
+pub fn remove_leftovers() {
+    if let Err(e) = remove_dir(&guest_root()) {
+        eprintln!("[Guest] Failed to remove leftover guest data: {}", e);
+    }
+}
+
+/// Opens a guest window at the default homepage.
+pub fn open_window(app: &AppHandle) -> Result<String, String> {
+    let homepage = Url::parse(&Settings::default().homepage).map_err(|e| e.to_string())?;
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_nanos();
+    let label = window_label(nanos);
+
+    println!("[Guest] Opening guest window");
+
+    let store = DataStore::Guest(data_dir(&label));
+    let own_label = label.clone();
+    let open_new = move |app: &AppHandle, url: String| {
+        // A second guest window wouldn't share this one's session
+        if let (Some(webview), Ok(url)) = (app.get_webview(&own_label), Url::parse(&url)) {
+            let _ = webview.navigate(url);
+        }
+    };
+    let window = browsing_webview::open_window(app, &label, "Guest Window", homepage, store, open_new, |_| true)?;
+
+    let app = app.clone();
+    let dir = data_dir(&label);
+    window.on_window_event(move |event| match event {
+        // Nothing from a guest window is handed off to other devices
+        WindowEvent::Focused(true) => handoff::clear(&app),
+        WindowEvent::Destroyed => wipe(dir.clone()),
+        _ => {}
+    });
+
+    Ok(label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_guest_data_stays_under_the_root() {
+        let label = window_label(42);
+        assert_eq!(label, "guest-window-42");
+        assert!(data_dir(&label).starts_with(guest_root()));
+        assert!(guest_root().starts_with(std::env::temp_dir()));
+    }
+
+    #[test]
+    fn test_remove_dir() {
+        let dir = tempdir().unwrap();
+        let guest = dir.path().join("guest-window-1");
+        std::fs::create_dir_all(guest.join("EBWebView")).unwrap();
+        std::fs::write(guest.join("EBWebView").join("Cookies"), "x").unwrap();
+        remove_dir(&guest).unwrap();
+        assert!(!guest.exists());
+        // Already gone is fine
+        remove_dir(&guest).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+// Handoff of the current page to Safari on iPhone and iPad (macOS).
+//
+// The active tab's page is published as a browsing-web NSUserActivity, which
+// devices signed in to the same account offer to open in Safari. It follows the
+// tab-status updates, so it changes with navigations and tab switches. App pages
+// aren't offered, and nothing is while a private or guest window is in front: the
+// activity resigns until a web page in a normal window is active again.
+//
+// Windows/Linux: nothing is published.
+
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::modules::guest;
+use crate::state::AppState;
+
+/// Label prefixes of private windows (see context_menu::window_label) and guest windows.
+const PRIVATE_WINDOW_PREFIXES: [&str; 2] = ["private-window-", guest::LABEL_PREFIX];
+
+/// The URL last handed to the platform, so updates that don't change it are skipped.
+static PUBLISHED: Mutex<Option<String>> = Mutex::new(None);
+
+/// The URL to offer for a page. Only web pages can be continued in Safari.
+pub fn handoff_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    matches!(parsed.scheme(), "http" | "https").then(|| parsed.to_string())
+}
+
+fn active_url(state: &AppState) -> Option<String> {
+    let active = state.active_tab_id.lock().unwrap().clone()?;
+    state.tabs.lock().unwrap().iter().find(|t| t.id == active).map(|t| t.url.clone())
+}
+
+fn private_window_focused(app: &AppHandle) -> bool {
+    app.windows()
+        .iter()
+        .filter(|(label, _)| PRIVATE_WINDOW_PREFIXES.iter().any(|prefix| label.starts_with(prefix)))
+        .any(|(_, window)| window.is_focused().unwrap_or(false))
+}
+
+/// Offers the active tab's page, or nothing if it isn't a web page or a
+/// private or guest window is focused.
+pub fn update(app: &AppHandle, state: &AppState) {
+    let url = if private_window_focused(app) { None } else { active_url(state).as_deref().and_then(handoff_url) };
+    set(app, url);
+}
+
+/// Stops offering a page, e.g. when a private window comes to the front.
+pub fn clear(app: &AppHandle) {
+    set(app, None);
+}
+
+fn set(app: &AppHandle, url: Option<String>) {
+    {
+        let mut published = PUBLISHED.lock().unwrap();
+        if *published == url {
+            return;
+        }
+        published.clone_from(&url);
+    }
+    platform::publish(app, url);
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::cell::Cell;
+    use std::ffi::CString;
+    use tauri::AppHandle;
+
+    thread_local! {
+        // Only touched on the main thread, created by the first page offered
+        static ACTIVITY: Cell<*mut Object> = const { Cell::new(std::ptr::null_mut()) };
+    }
+
+    pub fn publish(app: &AppHandle, url: Option<String>) {
+        let url = url.and_then(|url| CString::new(url).ok());
+        let result = app.run_on_main_thread(move || unsafe {
+            let activity = ACTIVITY.with(|cell| {
+                if cell.get().is_null() && url.is_some() {
+                    let kind: *mut Object =
+                        msg_send![class!(NSString), stringWithUTF8String: c"NSUserActivityTypeBrowsingWeb".as_ptr()];
+                    let activity: *mut Object = msg_send![class!(NSUserActivity), alloc];
+                    let activity: *mut Object = msg_send![activity, initWithActivityType: kind];
+                    cell.set(activity);
+                }
+                cell.get()
+            });
+            if activity.is_null() {
+                return;
+            }
+            let webpage: *mut Object = match &url {
+                Some(url) => {
+                    let string: *mut Object = msg_send![class!(NSString), stringWithUTF8String: url.as_ptr()];
+                    msg_send![class!(NSURL), URLWithString: string]
+                }
+                None => std::ptr::null_mut(),
+            };
+            if webpage.is_null() {
+                let _: () = msg_send![activity, resignCurrent];
+            } else {
+                let _: () = msg_send![activity, setWebpageURL: webpage];
+                let _: () = msg_send![activity, becomeCurrent];
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("[Handoff] Failed to update the activity: {}", e);
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use tauri::AppHandle;
+
+    pub fn publish(_app: &AppHandle, _url: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::context_menu;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://example.com/a?b=c", Some("https://example.com/a?b=c"))]
+    #[case("http://example.com", Some("http://example.com/"))]
+    #[case("about:blank", None)]
+    #[case("file:///tmp/a.html", None)]
+    #[case("tauri://localhost/settings.html", None)]
+    #[case("", None)]
+    fn test_handoff_url(#[case] url: &str, #[case] expected: Option<&str>) {
+        assert_eq!(handoff_url(url).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_private_window_prefix_matches_labels() {
+        assert!(context_menu::window_label(true, 42).starts_with(PRIVATE_WINDOW_PREFIXES[0]));
+        assert!(!context_menu::window_label(false, 42).starts_with(PRIVATE_WINDOW_PREFIXES[0]));
+    }
+}
@@ -0,0 +1,80 @@
+// Headless mode for scripted runs.
+//
+// Started with `--headless` (or SOVEREIGN_HEADLESS=1), the browser sets up
+// exactly as usual (every store, the command layer, adblock, history, the
+// background threads) but never puts a window on screen. The main window is
+// hidden as soon as it exists and stays hidden; tab webviews live inside it, so
+// pages still load, navigate and get filtered, just offscreen. The dropdown and
+// find windows are never shown, and a second launch doesn't bring anything to
+// the front. On macOS the app also stays out of the Dock and app switcher.
+//
+// This is for integration tests and automation in CI-like environments. The
+// flag is read once at startup and can't change while running.
+
+use std::sync::OnceLock;
+use tauri::{App, Window};
+
+use crate::error::BrowserError;
+
+pub const FLAG: &str = "--headless";
+pub const ENV_VAR: &str = "SOVEREIGN_HEADLESS";
+
+/// Whether `args` (program name first) or the environment value ask for headless mode.
+pub fn requested(args: &[String], env: Option<&str>) -> bool {
+    let env_on = env.map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"));
+    args.iter().skip(1).any(|arg| arg == FLAG) || env_on.unwrap_or(false)
+}
+
+/// Checked once: the process's args and environment don't change while running.
+pub fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        let args: Vec<String> = std::env::args().collect();
+        requested(&args, std::env::var(ENV_VAR).ok().as_deref())
+    })
+}
+
+/// Keeps the app off screen; called from setup before anything else is shown.
+pub fn apply(app: &mut App, main_window: &Window) {
+    if let Err(e) = main_window.hide() {
+        eprintln!("[Headless] Failed to hide main window: {}", e);
+    }
+    #[cfg(target_os = "macos")]
+    app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+    println!("[Headless] Running without visible windows");
+}
+
+#[tauri::command]
+pub fn is_headless() -> Result<bool, BrowserError> {
+    Ok(enabled())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case(&["sovereign", "--headless"], None, true)]
+    #[case(&["sovereign", "https://example.com", "--headless"], None, true)]
+    #[case(&["sovereign"], Some("1"), true)]
+    #[case(&["sovereign"], Some(" TRUE "), true)]
+    #[case(&["sovereign"], None, false)]
+    #[case(&["sovereign"], Some("0"), false)]
+    #[case(&["sovereign"], Some(""), false)]
+    #[case(&["sovereign", "--headless=false"], None, false)]
+    fn test_requested(#[case] argv: &[&str], #[case] env: Option<&str>, #[case] expected: bool) {
+        assert_eq!(requested(&args(argv), env), expected);
+    }
+
+    #[test]
+    fn test_program_name_is_not_a_flag() {
+        assert!(!requested(&args(&["--headless"]), None));
+    }
+}
@@ -0,0 +1,269 @@
+// HTTPS-Only mode enforcement for tab navigations.
+//
+// With `https_only` on, an http:// navigation is held back while the https://
+// version is probed. If the probe gets any response the tab goes to https://;
+// otherwise it shows ui/https-interstitial.html, where the user can go back or
+// continue over HTTP (optionally remembering the site in
+// `Settings.https_only_exceptions`). Local and single-label hosts are never
+// upgraded. The probe goes through the same proxy as the tabs.
+//
+// `on_navigation` also sees subframe loads; those are upgraded the same way, which
+// matches HTTPS-Only's "no plain HTTP at all" promise.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use url::{Host, Url};
+
+use crate::error::BrowserError;
+use crate::modules::block_stats::Protection;
+use crate::modules::{cookie_policy, proxy};
+use crate::state::AppState;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// An http:// URL seen again this soon after being upgraded means the https://
+// site redirected back to http://; probing again would loop
+const REDIRECT_LOOP_WINDOW: Duration = Duration::from_secs(10);
+const INTERSTITIAL_PAGE: &str = "https-interstitial.html";
+
+/// Hosts that typically can't have a publicly trusted certificate.
+pub fn is_exempt_host(host: &Host<&str>) -> bool {
+    match host {
+        Host::Ipv4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Host::Ipv6(ip) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.');
+            !domain.contains('.')
+                || domain.ends_with(".localhost")
+                || domain.ends_with(".local")
+                || domain.parse::<IpAddr>().is_ok()
+        }
+    }
+}
+
+/// The https:// URL to try instead of `url`, or None if `url` should load as is.
+pub fn upgrade_url(url: &Url, exceptions: &[String]) -> Option<Url> {
+    if url.scheme() != "http" {
+        return None;
+    }
+    if url.host().map_or(true, |h| is_exempt_host(&h)) || cookie_policy::is_excepted(exceptions, url.as_str()) {
+        return None;
+    }
+    // An explicit :80 is already dropped by the parser, so the default port follows the scheme
+    let mut upgraded = url.clone();
+    upgraded.set_scheme("https").ok()?;
+    Some(upgraded)
+}
+
+/// A bundled page, served from the app's own origin so it can call commands.
+pub fn app_page_url(page: &str) -> Url {
+    let base = if cfg!(windows) { "http://tauri.localhost/" } else { "tauri://localhost/" };
+    Url::parse(base).and_then(|b| b.join(page)).expect("valid app URL")
+}
+
+/// The interstitial page, which calls `continue_insecure`.
+pub fn interstitial_url(blocked: &Url) -> Url {
+    let mut url = app_page_url(INTERSTITIAL_PAGE);
+    url.query_pairs_mut().append_pair("url", blocked.as_str());
+    url
+}
+
+pub fn is_app_page(url: &Url) -> bool {
+    url.scheme() == "tauri" || url.host_str() == Some("tauri.localhost")
+}
+
+/// The URL a tab should report: the blocked URL while an interstitial (this one or
+/// safebrowsing's) is showing, so the toolbar shows the site rather than the
+/// internal page.
+pub fn page_url(url: &Url) -> String {
+    if is_app_page(url) && url.path().ends_with("-interstitial.html") {
+        if let Some((_, blocked)) = url.query_pairs().find(|(k, _)| k == "url") {
+            return blocked.into_owned();
+        }
+    }
+    url.to_string()
+}
+
+/// Per-webview state for in-flight upgrades and "continue anyway" decisions.
+pub struct HttpsOnlyManager {
+    bypass: Mutex<HashSet<(String, String)>>,             // (webview label, http URL), used once
+    upgraded: Mutex<HashMap<String, (String, Instant)>>, // webview label -> last upgraded http URL
+}
+
+impl HttpsOnlyManager {
+    pub fn new() -> Self {
+        Self { bypass: Mutex::new(HashSet::new()), upgraded: Mutex::new(HashMap::new()) }
+    }
+
+    /// Lets the next navigation of `webview_label` to `url` through over HTTP.
+    pub fn allow_once(&self, webview_label: &str, url: &str) {
+        self.bypass.lock().unwrap().insert((webview_label.to_string(), url.to_string()));
+    }
+
+    fn take_bypass(&self, webview_label: &str, url: &str) -> bool {
+        self.bypass.lock().unwrap().remove(&(webview_label.to_string(), url.to_string()))
+    }
+
+    /// Records an upgrade; returns false if the same URL was just upgraded
+    /// (a redirect loop back to http://).
+    fn record_upgrade(&self, webview_label: &str, url: &str, now: Instant) -> bool {
+        let mut upgraded = self.upgraded.lock().unwrap();
+        if let Some((last, at)) = upgraded.get(webview_label) {
+            if last == url && now.duration_since(*at) < REDIRECT_LOOP_WINDOW {
+                return false;
+            }
+        }
+        upgraded.insert(webview_label.to_string(), (url.to_string(), now));
+        true
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.bypass.lock().unwrap().retain(|(label, _)| label != webview_label);
+        self.upgraded.lock().unwrap().remove(webview_label);
+    }
+
+    /// Drops bypasses of webviews that are gone and loop guards that have run out.
+    /// Returns how many.
+    pub fn prune(&self, live_labels: &HashSet<String>, now: Instant) -> usize {
+        let mut bypass = self.bypass.lock().unwrap();
+        let mut upgraded = self.upgraded.lock().unwrap();
+        let before = bypass.len() + upgraded.len();
+        bypass.retain(|(label, _)| live_labels.contains(label));
+        upgraded.retain(|label, (_, at)| live_labels.contains(label) && now.duration_since(*at) < REDIRECT_LOOP_WINDOW);
+        before - bypass.len() - upgraded.len()
+    }
+}
+
+impl Default for HttpsOnlyManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Any response (even an error status) means the site speaks HTTPS.
+async fn probe(url: &Url, client: Result<reqwest::Client, BrowserError>) -> bool {
+    match client {
+        Ok(client) => client.head(url.as_str()).send().await.is_ok(),
+        Err(e) => {
+            eprintln!("[HttpsOnly] Can't probe {}: {}", url, e);
+            false
+        }
+    }
+}
+
+/// Navigation handler for tab webviews. Returns false to hold the navigation back
+/// while the upgrade is probed.
+pub fn on_navigation(app: &AppHandle, webview_label: &str, url: &Url) -> bool {
+    let Some(state) = app.try_state::<AppState>() else {
+        return true;
+    };
+    let upgraded = {
+        let settings = state.settings.read().unwrap();
+        if !settings.https_only {
+            return true;
+        }
+        match upgrade_url(url, &settings.https_only_exceptions) {
+            Some(u) => u,
+            None => return true,
+        }
+    };
+    if state.https_only.take_bypass(webview_label, url.as_str()) {
+        println!("[HttpsOnly] Continuing over HTTP: {}", url);
+        return true;
+    }
+
+    let loop_detected = !state.https_only.record_upgrade(webview_label, url.as_str(), Instant::now());
+    let client = proxy::http_client_builder(&state, PROBE_TIMEOUT)
+        .and_then(|b| Ok(b.redirect(reqwest::redirect::Policy::none()).build()?));
+    let app = app.clone();
+    let label = webview_label.to_string();
+    let original = url.clone();
+    tauri::async_runtime::spawn(async move {
+        let target = if !loop_detected && probe(&upgraded, client).await {
+            println!("[HttpsOnly] Upgraded {}", original);
+            if let Some(state) = app.try_state::<AppState>() {
+                state.block_stats.record_protection(Protection::HttpsUpgrade);
+            }
+            upgraded
+        } else {
+            println!("[HttpsOnly] No HTTPS for {}", original);
+            interstitial_url(&original)
+        };
+        if let Some(webview) = app.get_webview(&label) {
+            if let Err(e) = webview.navigate(target) {
+                eprintln!("[HttpsOnly] Failed to navigate: {}", e);
+            }
+        }
+    });
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("http://example.com/a?b=1", Some("https://example.com/a?b=1"))]
+    #[case("http://example.com:80/", Some("https://example.com/"))]
+    #[case("http://example.com:8080/", Some("https://example.com:8080/"))]
+    #[case("https://example.com/", None)]
+    #[case("http://localhost:3000/", None)]
+    #[case("http://app.localhost/", None)]
+    #[case("http://printer.local/", None)]
+    #[case("http://router/", None)]
+    #[case("http://192.168.1.1/", None)]
+    #[case("http://127.0.0.1:8000/", None)]
+    #[case("http://[::1]/", None)]
+    #[case("http://old.legacy.org/", None)] // Remembered exception
+    #[case("http://8.8.8.8/", Some("https://8.8.8.8/"))]
+    fn test_upgrade_url(#[case] url: &str, #[case] expected: Option<&str>) {
+        let exceptions = vec!["legacy.org".to_string()];
+        let upgraded = upgrade_url(&Url::parse(url).unwrap(), &exceptions);
+        assert_eq!(upgraded.as_ref().map(Url::as_str), expected);
+    }
+
+    #[test]
+    fn test_interstitial_round_trip() {
+        let blocked = Url::parse("http://example.com/a?b=1&c=2").unwrap();
+        let page = interstitial_url(&blocked);
+        assert!(is_app_page(&page));
+        assert_eq!(page_url(&page), blocked.as_str());
+        assert_eq!(page_url(&blocked), blocked.as_str());
+    }
+
+    #[test]
+    fn test_bypass_is_single_use() {
+        let manager = HttpsOnlyManager::new();
+        manager.allow_once("webview-tab-1", "http://example.com/");
+        assert!(!manager.take_bypass("webview-tab-2", "http://example.com/"));
+        assert!(manager.take_bypass("webview-tab-1", "http://example.com/"));
+        assert!(!manager.take_bypass("webview-tab-1", "http://example.com/"));
+    }
+
+    #[test]
+    fn test_redirect_loop_detection() {
+        let manager = HttpsOnlyManager::new();
+        let start = Instant::now();
+        assert!(manager.record_upgrade("webview-tab-1", "http://example.com/", start));
+        assert!(!manager.record_upgrade("webview-tab-1", "http://example.com/", start + Duration::from_secs(1)));
+        assert!(manager.record_upgrade("webview-tab-1", "http://example.com/", start + REDIRECT_LOOP_WINDOW * 2));
+        assert!(manager.record_upgrade("webview-tab-2", "http://example.com/", start));
+    }
+
+    #[test]
+    fn test_prune_drops_gone_webviews_and_old_guards() {
+        let manager = HttpsOnlyManager::new();
+        let start = Instant::now();
+        manager.allow_once("webview-tab-1", "http://example.com/");
+        manager.allow_once("webview-tab-2", "http://example.com/");
+        manager.record_upgrade("webview-tab-1", "http://example.com/", start);
+        let live = HashSet::from(["webview-tab-1".to_string()]);
+
+        assert_eq!(manager.prune(&live, start), 1);
+        assert_eq!(manager.prune(&live, start + REDIRECT_LOOP_WINDOW), 1);
+        assert!(manager.take_bypass("webview-tab-1", "http://example.com/"));
+    }
+}
@@ -0,0 +1,205 @@
+// Image blocking.
+//
+// `Settings.block_images` blocks images everywhere; `SiteSettings.block_images`
+// overrides it per site, either way. The effective settings are turned into
+// "image" type rules:
+// - Windows/Linux: adblock filters (`*$image,domain=...`) in a small engine of their
+//   own, checked on the interception path against the issuing frame. It's rebuilt
+//   whenever settings are saved, so it applies to open tabs right away.
+// - macOS: a WKContentRuleList scoped with if-domain/unless-domain.
+// On top of that, `guard_script` drops image sources before they load so blocked
+// pages don't fill up with broken-image icons. The rule list and the guard are
+// fixed when a tab is created.
+
+use adblock::engine::Engine;
+use adblock::lists::ParseOptions;
+use arc_swap::ArcSwap;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::settings::{Settings, SiteSettings};
+
+const SAFARI_RULE_LIST_ID: &str = "SovereignBrowserImageBlocking";
+
+/// Sites whose override differs from the global setting.
+fn overridden_sites(global: bool, sites: &BTreeMap<String, SiteSettings>) -> Vec<&str> {
+    sites
+        .iter()
+        .filter(|(_, s)| s.block_images.is_some_and(|block| block != global))
+        .map(|(site, _)| site.as_str())
+        .collect()
+}
+
+/// Adblock filters for the image settings.
+pub fn filter_rules(global: bool, sites: &BTreeMap<String, SiteSettings>) -> Vec<String> {
+    let overridden = overridden_sites(global, sites);
+    let domains = overridden.join("|");
+    match (global, overridden.is_empty()) {
+        (true, true) => vec!["*$image".to_string()],
+        (true, false) => vec!["*$image".to_string(), format!("@@*$image,domain={}", domains)],
+        (false, true) => Vec::new(),
+        (false, false) => vec![format!("*$image,domain={}", domains)],
+    }
+}
+
+/// WKContentRuleList JSON for the image settings, or None when nothing is blocked.
+pub fn safari_rules(global: bool, sites: &BTreeMap<String, SiteSettings>) -> Option<String> {
+    let overridden: Vec<String> = overridden_sites(global, sites).iter().map(|d| format!("*{}", d)).collect();
+    let mut trigger = serde_json::json!({
+        "url-filter": ".*",
+        "resource-type": ["image"],
+    });
+    match (global, overridden.is_empty()) {
+        (false, true) => return None,
+        (true, true) => {}
+        (true, false) => trigger["unless-domain"] = serde_json::json!(overridden),
+        (false, false) => trigger["if-domain"] = serde_json::json!(overridden),
+    }
+    Some(
+        serde_json::json!([{
+            "trigger": trigger,
+            "action": { "type": "block" },
+        }])
+        .to_string(),
+    )
+}
+
+/// Identifier for the macOS content rule list, kept apart from the adblock list.
+pub fn safari_rule_list_id() -> &'static str {
+    SAFARI_RULE_LIST_ID
+}
+
+/// Clears image sources on pages where images are blocked.
+const GUARD_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const BLOCK_BY_DEFAULT = __BLOCK_BY_DEFAULT__;
+    const SITES = __SITES__;
+    const host = location.hostname.toLowerCase();
+    const site = Object.keys(SITES)
+        .filter(s => host === s || host.endsWith('.' + s))
+        .sort((a, b) => b.length - a.length)[0];
+    if (!(site ? SITES[site] : BLOCK_BY_DEFAULT)) return;
+
+    function strip(el) {
+        if (el.tagName === 'IMG') {
+            el.removeAttribute('srcset');
+            el.removeAttribute('src');
+        } else if (el.tagName === 'SOURCE' && el.parentElement && el.parentElement.tagName === 'PICTURE') {
+            el.removeAttribute('srcset');
+        }
+    }
+    function scan(root) {
+        if (root.nodeType !== 1) return;
+        strip(root);
+        root.querySelectorAll('img,picture>source').forEach(strip);
+    }
+    new MutationObserver(mutations => {
+        for (const m of mutations) {
+            if (m.type === 'attributes') strip(m.target);
+            else m.addedNodes.forEach(scan);
+        }
+    }).observe(document, { subtree: true, childList: true, attributes: true, attributeFilter: ['src', 'srcset'] });
+})();
+"#;
+
+/// The `<img>` guard, or None when no page blocks images.
+pub fn guard_script(settings: &Settings) -> Option<String> {
+    let sites: BTreeMap<&str, bool> = settings
+        .site_settings
+        .iter()
+        .filter_map(|(site, s)| s.block_images.map(|block| (site.as_str(), block)))
+        .collect();
+    if !settings.block_images && !sites.values().any(|block| *block) {
+        return None;
+    }
+    Some(
+        GUARD_SCRIPT_TEMPLATE
+            .replace("__BLOCK_BY_DEFAULT__", if settings.block_images { "true" } else { "false" })
+            .replace("__SITES__", &serde_json::to_string(&sites).unwrap_or_else(|_| "{}".to_string())),
+    )
+}
+
+/// Engine for the image rules, checked on the Windows/Linux interception path.
+pub struct ImageBlocker {
+    engine: ArcSwap<Option<Engine>>, // None when no rules
+}
+
+impl ImageBlocker {
+    pub fn new(settings: &Settings) -> Self {
+        let blocker = Self { engine: ArcSwap::from_pointee(None) };
+        blocker.update(settings);
+        blocker
+    }
+
+    /// Rebuilds the rules from the current settings.
+    pub fn update(&self, settings: &Settings) {
+        let rules = filter_rules(settings.block_images, &settings.site_settings);
+        let engine = (!rules.is_empty()).then(|| Engine::from_rules(&rules, ParseOptions::default()));
+        self.engine.store(Arc::new(engine));
+    }
+
+    /// Whether an image request made by the document at `frame_url` is blocked.
+    pub fn should_block(&self, url: &str, frame_url: &str) -> bool {
+        let engine = self.engine.load();
+        let Some(engine) = engine.as_ref() else {
+            return false;
+        };
+        adblock::request::Request::new(url, frame_url, "image")
+            .is_ok_and(|request| engine.check_network_request(&request).matched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sites(entries: &[(&str, Option<bool>)]) -> BTreeMap<String, SiteSettings> {
+        entries
+            .iter()
+            .map(|(site, block)| (site.to_string(), SiteSettings { block_images: *block, ..Default::default() }))
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_rules() {
+        let per_site = sites(&[("a.com", Some(true)), ("b.com", Some(false)), ("c.com", None)]);
+        assert_eq!(filter_rules(false, &per_site), vec!["*$image,domain=a.com"]);
+        assert_eq!(filter_rules(true, &per_site), vec!["*$image", "@@*$image,domain=b.com"]);
+        assert!(filter_rules(false, &sites(&[("b.com", Some(false))])).is_empty());
+    }
+
+    #[test]
+    fn test_safari_rules() {
+        let per_site = sites(&[("a.com", Some(true)), ("b.com", Some(false))]);
+        let rules: serde_json::Value = serde_json::from_str(&safari_rules(true, &per_site).unwrap()).unwrap();
+        assert_eq!(rules[0]["trigger"]["unless-domain"], serde_json::json!(["*b.com"]));
+        let rules: serde_json::Value = serde_json::from_str(&safari_rules(false, &per_site).unwrap()).unwrap();
+        assert_eq!(rules[0]["trigger"]["if-domain"], serde_json::json!(["*a.com"]));
+        assert_eq!(safari_rules(false, &BTreeMap::new()), None);
+    }
+
+    #[test]
+    fn test_image_blocker() {
+        let mut settings = Settings::default();
+        settings.site_settings = sites(&[("photos.example", Some(true))]);
+        let blocker = ImageBlocker::new(&settings);
+        assert!(blocker.should_block("https://cdn.net/a.jpg", "https://www.photos.example/album"));
+        assert!(!blocker.should_block("https://cdn.net/a.jpg", "https://news.example/"));
+
+        settings.block_images = true;
+        settings.site_settings = sites(&[("photos.example", Some(false))]);
+        blocker.update(&settings);
+        assert!(!blocker.should_block("https://cdn.net/a.jpg", "https://photos.example/album"));
+        assert!(blocker.should_block("https://cdn.net/a.jpg", "https://news.example/"));
+    }
+
+    #[test]
+    fn test_guard_script() {
+        let mut settings = Settings::default();
+        assert_eq!(guard_script(&settings), None);
+        settings.site_settings = sites(&[("photos.example", Some(true))]);
+        let script = guard_script(&settings).unwrap();
+        assert!(script.contains(r#"const SITES = {"photos.example":true};"#));
+        assert!(script.contains("const BLOCK_BY_DEFAULT = false;"));
+    }
+}
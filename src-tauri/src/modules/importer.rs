@@ -0,0 +1,703 @@
+// Importing bookmarks and history from Chrome and Firefox.
+//
+// `list_import_sources` finds the browsers' profiles where they keep them:
+// - Chrome: `Default`, `Profile 1`, ... under Google/Chrome (User Data on
+//   Windows, ~/.config/google-chrome on Linux), each with a `Bookmarks` JSON
+//   file and a `History` SQLite database
+// - Firefox: the folders under Firefox/Profiles (~/.mozilla/firefox on Linux),
+//   with bookmarks and history both in `places.sqlite`
+//
+// A running browser keeps its databases locked, so they're copied (with their
+// -wal journal) to a temporary folder and read from there, never opened in
+// place. Only http(s) pages come over: Firefox's `place:` queries, bookmarklets
+// and the like are skipped, as are Firefox's tag folders, which only repeat
+// bookmarks that are already elsewhere.
+//
+// `preview_import` is a dry run: it reads everything and reports what an import
+// would add without touching the stores. `import_browser_data` merges history
+// into HistoryStore (see `HistoryStore::import`) and bookmarks into
+// BookmarkStore, emitting "import-progress" as it goes. Both skip what's already
+// there, so importing the same profile twice adds nothing. All three commands
+// only answer Settings: pages must not read other browsers' profiles.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::history::HistoryEntry;
+use crate::modules::bookmarks::Bookmark;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const CHROME_BOOKMARKS_FILE: &str = "Bookmarks";
+const CHROME_HISTORY_FILE: &str = "History";
+const FIREFOX_PLACES_FILE: &str = "places.sqlite";
+const PREVIEW_SAMPLE: usize = 10;
+const HISTORY_BATCH: usize = 1000;
+/// Seconds from 1601-01-01, where Chrome's timestamps count from, to 1970-01-01.
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+const FIREFOX_ROOT: &str = "root________";
+const FIREFOX_TAGS: &str = "tags________";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportSource {
+    Chrome,
+    Firefox,
+}
+
+impl ImportSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Chrome => "Google Chrome",
+            Self::Firefox => "Firefox",
+        }
+    }
+}
+
+/// A browser profile found on this machine.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceProfile {
+    pub source: ImportSource,
+    pub label: &'static str,
+    pub name: String,
+    pub path: String,
+    pub bookmarks: bool,
+    pub history: bool,
+}
+
+/// Everything read from a profile.
+#[derive(Debug, Clone, Default)]
+pub struct ImportData {
+    pub bookmarks: Vec<Bookmark>,
+    pub history: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub bookmarks: usize,
+    pub new_bookmarks: usize,
+    pub history: usize,
+    pub new_history: usize,
+    pub sample_bookmarks: Vec<Bookmark>,
+    pub sample_history: Vec<HistoryEntry>, // Most recently visited first
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub bookmarks_added: usize,
+    pub history_added: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStage {
+    Reading,
+    Bookmarks,
+    History,
+    Done,
+}
+
+/// The "import-progress" payload.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProgress {
+    pub stage: ImportStage,
+    pub done: usize,
+    pub total: usize,
+}
+
+fn is_importable(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+/// Chrome counts microseconds since 1601-01-01.
+fn chrome_time(micros: i64) -> u64 {
+    (micros / 1_000_000 - WINDOWS_EPOCH_OFFSET).max(0) as u64
+}
+
+/// Firefox counts microseconds since 1970-01-01.
+fn firefox_time(micros: i64) -> u64 {
+    (micros / 1_000_000).max(0) as u64
+}
+
+fn join_folder(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+// --- Chrome ---
+
+#[derive(Deserialize)]
+struct ChromeNode {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    url: Option<String>,
+    date_added: Option<String>, // Chrome time, as a string
+    #[serde(default)]
+    children: Vec<ChromeNode>,
+}
+
+#[derive(Deserialize)]
+struct ChromeBookmarksFile {
+    roots: HashMap<String, serde_json::Value>,
+}
+
+fn walk_chrome(folder: &ChromeNode, path: &str, out: &mut Vec<Bookmark>) {
+    for node in &folder.children {
+        match (node.kind.as_str(), &node.url) {
+            ("url", Some(url)) if is_importable(url) => out.push(Bookmark {
+                url: url.clone(),
+                title: node.name.clone(),
+                folder: path.to_string(),
+                added_at: node.date_added.as_deref().and_then(|d| d.parse().ok()).map_or(0, chrome_time),
+            }),
+            ("folder", _) => walk_chrome(node, &join_folder(path, &node.name), out),
+            _ => {}
+        }
+    }
+}
+
+/// Bookmarks from Chrome's `Bookmarks` file, with the root folder names it uses
+/// ("Bookmarks bar", "Other bookmarks", "Mobile bookmarks").
+pub fn parse_chrome_bookmarks(json: &str) -> Result<Vec<Bookmark>, String> {
+    let file: ChromeBookmarksFile =
+        serde_json::from_str(json).map_err(|e| format!("Unreadable Chrome bookmarks: {}", e))?;
+    let mut bookmarks = Vec::new();
+    for key in ["bookmark_bar", "other", "synced"] {
+        if let Some(root) = file.roots.get(key).and_then(|v| serde_json::from_value::<ChromeNode>(v.clone()).ok()) {
+            walk_chrome(&root, &root.name, &mut bookmarks);
+        }
+    }
+    Ok(bookmarks)
+}
+
+pub fn read_chrome_history(conn: &Connection) -> Result<Vec<HistoryEntry>, String> {
+    let mut stmt = conn
+        .prepare("SELECT url, title, visit_count, typed_count, last_visit_time FROM urls WHERE hidden = 0")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(HistoryEntry {
+                url: row.get(0)?,
+                title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                visit_count: row.get::<_, i64>(2)?.max(0) as u64,
+                typed_count: row.get::<_, i64>(3)?.max(0) as u64,
+                last_visit: chrome_time(row.get(4)?),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(Result::ok).filter(|e| e.visit_count > 0 && is_importable(&e.url)).collect())
+}
+
+// --- Firefox ---
+
+struct FirefoxItem {
+    parent: i64,
+    kind: i64, // 1 bookmark, 2 folder, 3 separator
+    title: String,
+    guid: String,
+    url: Option<String>,
+    added: i64,
+}
+
+fn firefox_root_name(guid: &str) -> Option<&'static str> {
+    match guid {
+        "toolbar_____" => Some("Bookmarks Toolbar"),
+        "menu________" => Some("Bookmarks Menu"),
+        "unfiled_____" => Some("Other Bookmarks"),
+        "mobile______" => Some("Mobile Bookmarks"),
+        _ => None,
+    }
+}
+
+/// Path of a Firefox folder; None for anything under the tags root.
+fn firefox_folder(items: &HashMap<i64, FirefoxItem>, mut id: i64) -> Option<String> {
+    let mut names = Vec::new();
+    // Bounded in case a damaged database has a cycle
+    for _ in 0..64 {
+        let Some(item) = items.get(&id) else {
+            break;
+        };
+        if item.guid == FIREFOX_TAGS {
+            return None;
+        }
+        if item.guid == FIREFOX_ROOT || item.parent == 0 {
+            break;
+        }
+        names.push(firefox_root_name(&item.guid).map_or_else(|| item.title.clone(), String::from));
+        id = item.parent;
+    }
+    names.reverse();
+    Some(names.join("/"))
+}
+
+pub fn read_firefox_bookmarks(conn: &Connection) -> Result<Vec<Bookmark>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT b.id, b.parent, b.type, b.title, b.guid, p.url, b.dateAdded \
+             FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk ORDER BY b.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                FirefoxItem {
+                    parent: row.get(1)?,
+                    kind: row.get(2)?,
+                    title: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    guid: row.get(4)?,
+                    url: row.get(5)?,
+                    added: row.get::<_, Option<i64>>(6)?.unwrap_or(0),
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let ordered: Vec<(i64, FirefoxItem)> = rows.filter_map(Result::ok).collect();
+    let ids: Vec<i64> = ordered.iter().map(|(id, _)| *id).collect();
+    let items: HashMap<i64, FirefoxItem> = ordered.into_iter().collect();
+
+    let mut bookmarks = Vec::new();
+    for id in ids {
+        let item = &items[&id];
+        let Some(url) = item.url.as_ref().filter(|url| item.kind == 1 && is_importable(url)) else {
+            continue;
+        };
+        if let Some(folder) = firefox_folder(&items, item.parent) {
+            bookmarks.push(Bookmark {
+                url: url.clone(),
+                title: item.title.clone(),
+                folder,
+                added_at: firefox_time(item.added),
+            });
+        }
+    }
+    Ok(bookmarks)
+}
+
+pub fn read_firefox_history(conn: &Connection) -> Result<Vec<HistoryEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT url, title, visit_count, typed, last_visit_date FROM moz_places \
+             WHERE visit_count > 0 AND hidden = 0",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(HistoryEntry {
+                url: row.get(0)?,
+                title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                visit_count: row.get::<_, i64>(2)?.max(0) as u64,
+                typed_count: row.get::<_, i64>(3)?.max(0) as u64,
+                last_visit: row.get::<_, Option<i64>>(4)?.map_or(0, firefox_time),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.filter_map(Result::ok).filter(|e| is_importable(&e.url)).collect())
+}
+
+// --- Reading a profile ---
+
+/// A copy of a database in a temporary folder, removed when dropped.
+struct DatabaseCopy {
+    dir: PathBuf,
+    conn: Connection,
+}
+
+impl DatabaseCopy {
+    fn open(db: &Path) -> Result<Self, String> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!("sovereign-import-{}", nanos));
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let name = db.file_name().ok_or_else(|| format!("Not a database: {}", db.display()))?;
+        let copy = dir.join(name);
+        let copied = fs::copy(db, &copy).map_err(|e| format!("Couldn't read {}: {}", db.display(), e));
+        if let Err(e) = copied {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+        for suffix in ["-wal", "-shm"] {
+            let journal = with_suffix(db, suffix);
+            if journal.exists() {
+                let _ = fs::copy(&journal, with_suffix(&copy, suffix));
+            }
+        }
+        match Connection::open(&copy) {
+            Ok(conn) => Ok(Self { dir, conn }),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                Err(format!("Couldn't open {}: {}", db.display(), e))
+            }
+        }
+    }
+}
+
+impl Drop for DatabaseCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Reads what's asked for from a profile folder.
+pub fn read_profile(source: ImportSource, dir: &Path, bookmarks: bool, history: bool) -> Result<ImportData, String> {
+    if !dir.is_dir() {
+        return Err(format!("No {} profile at {}", source.label(), dir.display()));
+    }
+    let mut data = ImportData::default();
+    match source {
+        ImportSource::Chrome => {
+            let bookmarks_file = dir.join(CHROME_BOOKMARKS_FILE);
+            if bookmarks && bookmarks_file.exists() {
+                let json = fs::read_to_string(&bookmarks_file).map_err(|e| e.to_string())?;
+                data.bookmarks = parse_chrome_bookmarks(&json)?;
+            }
+            let history_db = dir.join(CHROME_HISTORY_FILE);
+            if history && history_db.exists() {
+                data.history = read_chrome_history(&DatabaseCopy::open(&history_db)?.conn)?;
+            }
+        }
+        ImportSource::Firefox => {
+            let places = dir.join(FIREFOX_PLACES_FILE);
+            if (bookmarks || history) && places.exists() {
+                let copy = DatabaseCopy::open(&places)?;
+                if bookmarks {
+                    data.bookmarks = read_firefox_bookmarks(&copy.conn)?;
+                }
+                if history {
+                    data.history = read_firefox_history(&copy.conn)?;
+                }
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Profiles of `source` in `root`, the folder the browser keeps them in.
+pub fn find_profiles(source: ImportSource, root: &Path) -> Vec<SourceProfile> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut profiles: Vec<SourceProfile> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let dir_name = path.file_name()?.to_string_lossy().into_owned();
+            let (name, bookmarks, history) = match source {
+                ImportSource::Chrome => {
+                    (dir_name, path.join(CHROME_BOOKMARKS_FILE).exists(), path.join(CHROME_HISTORY_FILE).exists())
+                }
+                ImportSource::Firefox => {
+                    let places = path.join(FIREFOX_PLACES_FILE).exists();
+                    // "x1y2z3.default-release" is shown as "default-release"
+                    let name = dir_name.split_once('.').map_or(dir_name.as_str(), |(_, name)| name).to_string();
+                    (name, places, places)
+                }
+            };
+            (bookmarks || history).then(|| SourceProfile {
+                source,
+                label: source.label(),
+                name,
+                path: path.to_string_lossy().into_owned(),
+                bookmarks,
+                history,
+            })
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+fn profiles_root(app: &AppHandle, source: ImportSource) -> Option<PathBuf> {
+    let paths = app.path();
+    match source {
+        #[cfg(target_os = "macos")]
+        ImportSource::Chrome => paths.data_dir().ok().map(|d| d.join("Google").join("Chrome")),
+        #[cfg(windows)]
+        ImportSource::Chrome => paths.local_data_dir().ok().map(|d| d.join("Google").join("Chrome").join("User Data")),
+        #[cfg(not(any(target_os = "macos", windows)))]
+        ImportSource::Chrome => paths.config_dir().ok().map(|d| d.join("google-chrome")),
+        #[cfg(target_os = "macos")]
+        ImportSource::Firefox => paths.data_dir().ok().map(|d| d.join("Firefox").join("Profiles")),
+        #[cfg(windows)]
+        ImportSource::Firefox => paths.data_dir().ok().map(|d| d.join("Mozilla").join("Firefox").join("Profiles")),
+        #[cfg(not(any(target_os = "macos", windows)))]
+        ImportSource::Firefox => paths.home_dir().ok().map(|d| d.join(".mozilla").join("firefox")),
+    }
+}
+
+fn preview(state: &AppState, mut data: ImportData) -> ImportPreview {
+    let new_bookmarks = data.bookmarks.iter().filter(|b| !state.bookmarks.contains(&b.url, &b.folder)).count();
+    let new_history = data.history.iter().filter(|e| !state.history.contains(&e.url)).count();
+    data.history.sort_by(|a, b| b.last_visit.cmp(&a.last_visit));
+    ImportPreview {
+        bookmarks: data.bookmarks.len(),
+        new_bookmarks,
+        history: data.history.len(),
+        new_history,
+        sample_bookmarks: data.bookmarks.into_iter().take(PREVIEW_SAMPLE).collect(),
+        sample_history: data.history.into_iter().take(PREVIEW_SAMPLE).collect(),
+    }
+}
+
+fn emit_progress(app: &AppHandle, stage: ImportStage, done: usize, total: usize) {
+    let _ = app.emit("import-progress", ImportProgress { stage, done, total });
+}
+
+fn merge(app: &AppHandle, state: &AppState, data: ImportData) -> Result<ImportSummary, String> {
+    emit_progress(app, ImportStage::Bookmarks, 0, data.bookmarks.len());
+    let total_bookmarks = data.bookmarks.len();
+    let bookmarks_added = state.bookmarks.add_all(data.bookmarks);
+    emit_progress(app, ImportStage::Bookmarks, total_bookmarks, total_bookmarks);
+
+    let total = data.history.len();
+    let mut history_added = 0;
+    for (i, batch) in data.history.chunks(HISTORY_BATCH).enumerate() {
+        emit_progress(app, ImportStage::History, i * HISTORY_BATCH, total);
+        history_added += state.history.import(batch).map_err(|e| e.to_string())?;
+    }
+    emit_progress(app, ImportStage::History, total, total);
+    Ok(ImportSummary { bookmarks_added, history_added })
+}
+
+async fn read_in_background(
+    source: ImportSource,
+    path: String,
+    bookmarks: bool,
+    history: bool,
+) -> Result<ImportData, BrowserError> {
+    tauri::async_runtime::spawn_blocking(move || read_profile(source, Path::new(&path), bookmarks, history))
+        .await?
+        .map_err(BrowserError::Io)
+}
+
+#[tauri::command]
+pub fn list_import_sources(app: AppHandle, webview: tauri::Webview) -> Result<Vec<SourceProfile>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok([ImportSource::Chrome, ImportSource::Firefox]
+        .into_iter()
+        .flat_map(|source| profiles_root(&app, source).map(|root| find_profiles(source, &root)).unwrap_or_default())
+        .collect())
+}
+
+/// Dry run: what importing from a profile would bring over, without storing anything.
+#[tauri::command]
+pub async fn preview_import(
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    source: ImportSource,
+    path: String,
+    bookmarks: bool,
+    history: bool,
+) -> Result<ImportPreview, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let data = read_in_background(source, path, bookmarks, history).await?;
+    Ok(preview(&state, data))
+}
+
+#[tauri::command]
+pub async fn import_browser_data(
+    app: AppHandle,
+    webview: tauri::Webview,
+    source: ImportSource,
+    path: String,
+    bookmarks: bool,
+    history: bool,
+) -> Result<ImportSummary, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    emit_progress(&app, ImportStage::Reading, 0, 0);
+    let data = read_in_background(source, path, bookmarks, history).await?;
+    let handle = app.clone();
+    let summary = tauri::async_runtime::spawn_blocking(move || merge(&handle, &handle.state::<AppState>(), data))
+        .await?
+        .map_err(BrowserError::Io)?;
+    emit_progress(&app, ImportStage::Done, 0, 0);
+    println!(
+        "[Importer] {}: {} bookmarks and {} history entries added",
+        source.label(),
+        summary.bookmarks_added,
+        summary.history_added
+    );
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME_BOOKMARKS: &str = r#"{
+        "roots": {
+            "bookmark_bar": { "type": "folder", "name": "Bookmarks bar", "children": [
+                { "type": "url", "name": "Rust", "url": "https://www.rust-lang.org/", "date_added": "13300000000000000" },
+                { "type": "folder", "name": "Work", "children": [
+                    { "type": "url", "name": "Docs", "url": "https://docs.example.com/" },
+                    { "type": "url", "name": "Bookmarklet", "url": "javascript:void(0)" }
+                ] }
+            ] },
+            "other": { "type": "folder", "name": "Other bookmarks", "children": [
+                { "type": "url", "name": "News", "url": "http://news.example.com/" }
+            ] },
+            "synced": { "type": "folder", "name": "Mobile bookmarks", "children": [] }
+        },
+        "version": 1
+    }"#;
+
+    #[test]
+    fn test_parse_chrome_bookmarks() {
+        let bookmarks = parse_chrome_bookmarks(CHROME_BOOKMARKS).unwrap();
+        let summary: Vec<(&str, &str)> = bookmarks.iter().map(|b| (b.url.as_str(), b.folder.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("https://www.rust-lang.org/", "Bookmarks bar"),
+                ("https://docs.example.com/", "Bookmarks bar/Work"),
+                ("http://news.example.com/", "Other bookmarks"),
+            ]
+        );
+        assert_eq!(bookmarks[0].added_at, 13_300_000_000 - 11_644_473_600);
+        assert!(parse_chrome_bookmarks("not json").is_err());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        assert_eq!(chrome_time(13_300_000_000_000_000), 1_655_526_400);
+        assert_eq!(chrome_time(0), 0);
+        assert_eq!(firefox_time(1_655_526_400_123_456), 1_655_526_400);
+    }
+
+    #[test]
+    fn test_read_chrome_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE urls (id INTEGER PRIMARY KEY, url TEXT, title TEXT, visit_count INTEGER, \
+                 typed_count INTEGER, last_visit_time INTEGER, hidden INTEGER);
+             INSERT INTO urls VALUES (1, 'https://a.com/', 'A', 5, 2, 13300000000000000, 0);
+             INSERT INTO urls VALUES (2, 'https://hidden.com/', 'H', 1, 0, 13300000000000000, 1);
+             INSERT INTO urls VALUES (3, 'chrome://settings/', 'S', 3, 0, 13300000000000000, 0);
+             INSERT INTO urls VALUES (4, 'https://b.com/', NULL, 1, 0, 13300000000000000, 0);",
+        )
+        .unwrap();
+        let history = read_chrome_history(&conn).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0],
+            HistoryEntry {
+                url: "https://a.com/".to_string(),
+                title: "A".to_string(),
+                last_visit: 1_655_526_400,
+                visit_count: 5,
+                typed_count: 2,
+            }
+        );
+        assert_eq!(history[1].title, "");
+    }
+
+    fn firefox_places() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT, visit_count INTEGER, \
+                 typed INTEGER, last_visit_date INTEGER, hidden INTEGER);
+             CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER, \
+                 title TEXT, guid TEXT, dateAdded INTEGER);
+             INSERT INTO moz_places VALUES (1, 'https://a.com/', 'A', 3, 1, 1655526400000000, 0);
+             INSERT INTO moz_places VALUES (2, 'https://b.com/', 'B', 0, 0, NULL, 0);
+             INSERT INTO moz_places VALUES (3, 'place:sort=8', NULL, 0, 0, NULL, 1);
+             INSERT INTO moz_bookmarks VALUES (1, 2, NULL, 0, '', 'root________', 0);
+             INSERT INTO moz_bookmarks VALUES (2, 2, NULL, 1, 'toolbar', 'toolbar_____', 0);
+             INSERT INTO moz_bookmarks VALUES (3, 2, NULL, 1, 'tags', 'tags________', 0);
+             INSERT INTO moz_bookmarks VALUES (4, 2, NULL, 2, 'Reading', 'folder000001', 0);
+             INSERT INTO moz_bookmarks VALUES (5, 1, 1, 2, 'A on toolbar', 'bookmark0001', 1655526400000000);
+             INSERT INTO moz_bookmarks VALUES (6, 1, 2, 4, 'B', 'bookmark0002', 0);
+             INSERT INTO moz_bookmarks VALUES (7, 2, NULL, 3, 'some-tag', 'folder000002', 0);
+             INSERT INTO moz_bookmarks VALUES (8, 1, 1, 7, 'A tagged', 'bookmark0003', 0);
+             INSERT INTO moz_bookmarks VALUES (9, 1, 3, 2, 'Most Visited', 'bookmark0004', 0);
+             INSERT INTO moz_bookmarks VALUES (10, 3, NULL, 2, NULL, 'separator001', 0);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_read_firefox_bookmarks() {
+        let bookmarks = read_firefox_bookmarks(&firefox_places()).unwrap();
+        let summary: Vec<(&str, &str, &str)> =
+            bookmarks.iter().map(|b| (b.url.as_str(), b.title.as_str(), b.folder.as_str())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("https://a.com/", "A on toolbar", "Bookmarks Toolbar"),
+                ("https://b.com/", "B", "Bookmarks Toolbar/Reading"),
+            ]
+        );
+        assert_eq!(bookmarks[0].added_at, 1_655_526_400);
+    }
+
+    #[test]
+    fn test_read_firefox_history() {
+        let history = read_firefox_history(&firefox_places()).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].url.as_str(), history[0].visit_count, history[0].typed_count), ("https://a.com/", 3, 1));
+        assert_eq!(history[0].last_visit, 1_655_526_400);
+    }
+
+    #[test]
+    fn test_read_profile_copies_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join(FIREFOX_PLACES_FILE);
+        firefox_places().execute("VACUUM INTO ?1", [places.to_string_lossy()]).unwrap();
+
+        let data = read_profile(ImportSource::Firefox, dir.path(), true, false).unwrap();
+        assert_eq!((data.bookmarks.len(), data.history.len()), (2, 0));
+        // Nothing is written next to the original
+        let names: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, vec![OsString::from(FIREFOX_PLACES_FILE)]);
+
+        assert!(read_profile(ImportSource::Chrome, &dir.path().join("missing"), true, true).is_err());
+    }
+
+    #[test]
+    fn test_find_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, file) in [("Default", "Bookmarks"), ("Profile 1", "History"), ("Crashpad", "settings.dat")] {
+            fs::create_dir(dir.path().join(name)).unwrap();
+            fs::write(dir.path().join(name).join(file), "").unwrap();
+        }
+        let chrome = find_profiles(ImportSource::Chrome, dir.path());
+        let summary: Vec<(&str, bool, bool)> =
+            chrome.iter().map(|p| (p.name.as_str(), p.bookmarks, p.history)).collect();
+        assert_eq!(summary, vec![("Default", true, false), ("Profile 1", false, true)]);
+
+        let firefox_dir = tempfile::tempdir().unwrap();
+        let profile = firefox_dir.path().join("x1y2z3.default-release");
+        fs::create_dir(&profile).unwrap();
+        fs::write(profile.join(FIREFOX_PLACES_FILE), "").unwrap();
+        let firefox = find_profiles(ImportSource::Firefox, firefox_dir.path());
+        assert_eq!(firefox.len(), 1);
+        assert_eq!((firefox[0].name.as_str(), firefox[0].label), ("default-release", "Firefox"));
+
+        assert!(find_profiles(ImportSource::Chrome, &dir.path().join("missing")).is_empty());
+    }
+}
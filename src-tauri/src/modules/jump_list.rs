@@ -0,0 +1,386 @@
+// Windows jump list and macOS Dock menu.
+//
+// Both offer New Tab and New Private Window, then the top sites (see
+// modules::top_sites) and the most recent pages from the history not already
+// among them. The list is rebuilt shortly after launch and then periodically.
+//
+// Every entry is a set of command-line flags (see modules::cli), so a click
+// goes through the same path as a second launch: on Windows the jump list
+// starts the browser with them and the single-instance plugin forwards them
+// here, on macOS the menu hands them to cli::handle_second_launch directly.
+//
+// Linux: nothing is shown. Launchers read quicklists from the .desktop file,
+// which the app can't change while it runs.
+
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::history::HistoryEntry;
+use crate::modules::top_sites::{self, TopSite};
+use crate::modules::{cli, profile};
+use crate::settings::Settings;
+use crate::state::AppState;
+
+const TOP_SITES: usize = 5;
+const RECENT_PAGES: usize = 5;
+const STARTUP_DELAY: Duration = Duration::from_secs(10);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpItem {
+    pub title: String,
+    pub args: Vec<String>, // Flags for modules::cli, without the program name
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JumpList {
+    pub tasks: Vec<JumpItem>,
+    pub top: Vec<JumpItem>,
+    pub recent: Vec<JumpItem>,
+}
+
+/// Flags that open `url` in a new tab of this profile's browser.
+fn open_args(url: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(name) = profile::active() {
+        args.extend(["--profile".to_string(), name.to_string()]);
+    }
+    args.extend(["--new-tab".to_string(), url.to_string()]);
+    args
+}
+
+fn page(title: &str, url: &str) -> Option<JumpItem> {
+    // Only what the command line accepts back
+    let url = cli::normalize_url(url).ok().filter(|u| u.starts_with("http"))?;
+    let title = if title.trim().is_empty() { url.clone() } else { title.trim().to_string() };
+    Some(JumpItem { title, args: open_args(&url) })
+}
+
+pub fn build(settings: &Settings, top: &[TopSite], history: &[HistoryEntry]) -> JumpList {
+    let new_tab =
+        if settings.new_tab_top_sites { top_sites::NEW_TAB_ALIAS.to_string() } else { settings.homepage.clone() };
+    let mut private = open_args(&settings.homepage);
+    private.push("--private".to_string());
+    let tasks = vec![
+        JumpItem { title: "New Tab".to_string(), args: open_args(&new_tab) },
+        JumpItem { title: "New Private Window".to_string(), args: private },
+    ];
+
+    let top: Vec<JumpItem> = top.iter().filter_map(|site| page(&site.title, &site.url)).take(TOP_SITES).collect();
+    let recent = history
+        .iter()
+        .filter_map(|entry| page(&entry.title, &entry.url))
+        .filter(|item| !top.iter().any(|t| t.args == item.args))
+        .take(RECENT_PAGES)
+        .collect();
+
+    JumpList { tasks, top, recent }
+}
+
+/// The list for the current settings and history.
+pub fn current(state: &AppState) -> JumpList {
+    let top = top_sites::current(state, TOP_SITES);
+    // Newest first; a few extra in case some are among the top sites
+    let history = state.history.list(0, RECENT_PAGES + TOP_SITES, None, None).entries;
+    build(&state.settings.read().unwrap(), &top, &history)
+}
+
+/// Runs the flags of a clicked entry like a second launch would.
+pub fn open(app: &AppHandle, args: &[String]) {
+    let argv: Vec<String> = std::iter::once("sovereign".to_string()).chain(args.iter().cloned()).collect();
+    if !cli::handle_second_launch(app, &argv) {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.set_focus();
+        }
+    }
+}
+
+/// Keeps the jump list or Dock menu current until the app exits.
+pub fn spawn_updater(app: AppHandle) {
+    std::thread::spawn(move || {
+        std::thread::sleep(STARTUP_DELAY);
+        loop {
+            if let Some(state) = app.try_state::<AppState>() {
+                platform::apply(&app, current(&state));
+            }
+            std::thread::sleep(REFRESH_INTERVAL);
+        }
+    });
+}
+
+/// A command line for the flags, each quoted.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn command_line(args: &[String]) -> String {
+    args.iter().map(|arg| format!("\"{}\"", arg.replace('"', "%22"))).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{JumpItem, JumpList};
+    use tauri::AppHandle;
+    use windows::core::{Error, Interface, HSTRING};
+    use windows::Win32::Foundation::E_FAIL;
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::PROPVARIANT;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::System::Variant::VT_LPWSTR;
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::{IPropertyStore, PropVariantChangeType, PVCHF_DEFAULT};
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+
+    unsafe fn link(exe: &HSTRING, item: &JumpItem) -> windows::core::Result<IShellLinkW> {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(exe)?;
+        link.SetArguments(&HSTRING::from(super::command_line(&item.args)))?;
+        link.SetIconLocation(exe, 0)?;
+        // The shell shows PKEY_Title, which has to be a plain wide string
+        let mut title = PROPVARIANT::default();
+        PropVariantChangeType(&mut title, &PROPVARIANT::from(item.title.as_str()), PVCHF_DEFAULT, VT_LPWSTR)?;
+        let store: IPropertyStore = link.cast()?;
+        store.SetValue(&PKEY_Title, &title)?;
+        store.Commit()?;
+        Ok(link)
+    }
+
+    /// Arguments of the entries the user removed from the list, which mustn't come back.
+    unsafe fn removed_args(removed: &IObjectArray) -> windows::core::Result<Vec<String>> {
+        let mut args = Vec::new();
+        for i in 0..removed.GetCount()? {
+            let link: IShellLinkW = removed.GetAt(i)?;
+            let mut buffer = [0u16; 2048];
+            link.GetArguments(&mut buffer)?;
+            let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+            args.push(String::from_utf16_lossy(&buffer[..len]));
+        }
+        Ok(args)
+    }
+
+    unsafe fn collection(exe: &HSTRING, items: &[&JumpItem]) -> windows::core::Result<IObjectArray> {
+        let collection: IObjectCollection = CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        for item in items {
+            collection.AddObject(&link(exe, item)?)?;
+        }
+        collection.cast()
+    }
+
+    fn kept<'a>(items: &'a [JumpItem], removed: &[String]) -> Vec<&'a JumpItem> {
+        items.iter().filter(|item| !removed.contains(&super::command_line(&item.args))).collect()
+    }
+
+    unsafe fn commit(list: &JumpList) -> windows::core::Result<()> {
+        // Already initialized on this thread is fine
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let exe = std::env::current_exe().map_err(|e| Error::new(E_FAIL, e.to_string()))?;
+        let exe = HSTRING::from(exe.as_path());
+
+        let destinations: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        let mut slots = 0u32;
+        let removed = removed_args(&destinations.BeginList::<IObjectArray>(&mut slots)?)?;
+        for (name, items) in [("Top Sites", kept(&list.top, &removed)), ("Recent", kept(&list.recent, &removed))] {
+            if !items.is_empty() {
+                destinations.AppendCategory(&HSTRING::from(name), &collection(&exe, &items)?)?;
+            }
+        }
+        destinations.AddUserTasks(&collection(&exe, &list.tasks.iter().collect::<Vec<_>>())?)?;
+        destinations.CommitList()
+    }
+
+    pub fn apply(_app: &AppHandle, list: JumpList) {
+        if let Err(e) = unsafe { commit(&list) } {
+            eprintln!("[JumpList] Failed to update the jump list: {}", e);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{JumpItem, JumpList};
+    use objc::declare::ClassDecl;
+    use objc::runtime::{class_addMethod, Class, Object, Sel, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::cell::Cell;
+    use std::ffi::{c_char, CStr, CString};
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+
+    thread_local! {
+        // Only touched on the main thread
+        static MENU: Cell<*mut Object> = const { Cell::new(std::ptr::null_mut()) };
+        static TARGET: Cell<*mut Object> = const { Cell::new(std::ptr::null_mut()) };
+    }
+
+    /// applicationDockMenu:, added to the app delegate.
+    extern "C" fn dock_menu(_this: &Object, _sel: Sel, _app: *mut Object) -> *mut Object {
+        MENU.with(Cell::get)
+    }
+
+    /// The menu items' action; each item carries its flags as an NSArray.
+    extern "C" fn open_item(_this: &Object, _sel: Sel, item: *mut Object) {
+        let args: Vec<String> = unsafe {
+            let list: *mut Object = msg_send![item, representedObject];
+            let count: usize = msg_send![list, count];
+            (0..count)
+                .map(|i| {
+                    let arg: *mut Object = msg_send![list, objectAtIndex: i];
+                    let utf8: *const c_char = msg_send![arg, UTF8String];
+                    CStr::from_ptr(utf8).to_string_lossy().into_owned()
+                })
+                .collect()
+        };
+        if let Some(app) = APP.get() {
+            super::open(app, &args);
+        }
+    }
+
+    unsafe fn ns_string(text: &str) -> *mut Object {
+        let text = CString::new(text.replace('\0', "")).unwrap_or_default();
+        msg_send![class!(NSString), stringWithUTF8String: text.as_ptr()]
+    }
+
+    /// Registers the menu target and teaches the app delegate to hand AppKit the menu.
+    unsafe fn install() -> *mut Object {
+        let mut decl =
+            ClassDecl::new("SovereignDockMenuTarget", class!(NSObject)).expect("dock menu target registered twice");
+        decl.add_method(sel!(openItem:), open_item as extern "C" fn(&Object, Sel, *mut Object));
+        let target: *mut Object = msg_send![decl.register(), new];
+
+        let ns_app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+        let delegate: *mut Object = msg_send![ns_app, delegate];
+        let delegate_class: *const Class = msg_send![delegate, class];
+        let imp: extern "C" fn(&Object, Sel, *mut Object) -> *mut Object = dock_menu;
+        class_addMethod(
+            delegate_class as *mut Class,
+            sel!(applicationDockMenu:),
+            std::mem::transmute(imp),
+            c"@@:@".as_ptr(),
+        );
+        // AppKit checks what the delegate responds to when it's set
+        let _: () = msg_send![ns_app, setDelegate: delegate];
+        target
+    }
+
+    unsafe fn menu_item(target: *mut Object, item: &JumpItem) -> *mut Object {
+        let args: *mut Object = msg_send![class!(NSMutableArray), array];
+        for arg in &item.args {
+            let _: () = msg_send![args, addObject: ns_string(arg)];
+        }
+        let menu_item: *mut Object = msg_send![class!(NSMenuItem), alloc];
+        let menu_item: *mut Object = msg_send![menu_item,
+            initWithTitle: ns_string(&item.title) action: sel!(openItem:) keyEquivalent: ns_string("")];
+        let _: () = msg_send![menu_item, setTarget: target];
+        let _: () = msg_send![menu_item, setRepresentedObject: args];
+        menu_item
+    }
+
+    unsafe fn build_menu(target: *mut Object, list: &JumpList) -> *mut Object {
+        let menu: *mut Object = msg_send![class!(NSMenu), new];
+        let _: () = msg_send![menu, setAutoenablesItems: NO];
+        for section in [&list.tasks, &list.top, &list.recent].into_iter().filter(|s| !s.is_empty()) {
+            let count: isize = msg_send![menu, numberOfItems];
+            if count > 0 {
+                let separator: *mut Object = msg_send![class!(NSMenuItem), separatorItem];
+                let _: () = msg_send![menu, addItem: separator];
+            }
+            for item in section {
+                let menu_item = menu_item(target, item);
+                let _: () = msg_send![menu, addItem: menu_item];
+                let _: () = msg_send![menu_item, release];
+            }
+        }
+        menu
+    }
+
+    pub fn apply(app: &AppHandle, list: JumpList) {
+        let _ = APP.set(app.clone());
+        let result = app.run_on_main_thread(move || unsafe {
+            let target = TARGET.with(|cell| {
+                if cell.get().is_null() {
+                    cell.set(install());
+                }
+                cell.get()
+            });
+            let old = MENU.with(|cell| cell.replace(build_menu(target, &list)));
+            if !old.is_null() {
+                let _: () = msg_send![old, release];
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("[JumpList] Failed to update the Dock menu: {}", e);
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+    use super::JumpList;
+    use tauri::AppHandle;
+
+    pub fn apply(_app: &AppHandle, _list: JumpList) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, title: &str, last_visit: u64) -> HistoryEntry {
+        HistoryEntry { url: url.to_string(), title: title.to_string(), last_visit, visit_count: 1, typed_count: 0 }
+    }
+
+    fn site(url: &str, title: &str) -> TopSite {
+        let host = url::Url::parse(url).unwrap().host_str().unwrap().to_string();
+        TopSite { url: url.to_string(), title: title.to_string(), host, favicon: None, thumbnail: None, pinned: false }
+    }
+
+    #[test]
+    fn test_tasks_follow_new_tab_setting() {
+        let mut settings = Settings::default();
+        let list = build(&settings, &[], &[]);
+        assert_eq!(list.tasks[0].args, ["--new-tab", top_sites::NEW_TAB_ALIAS]);
+        assert_eq!(list.tasks[1].args, ["--new-tab", settings.homepage.as_str(), "--private"]);
+
+        settings.new_tab_top_sites = false;
+        let list = build(&settings, &[], &[]);
+        assert_eq!(list.tasks[0].args, ["--new-tab", settings.homepage.as_str()]);
+        assert!(list.top.is_empty() && list.recent.is_empty());
+    }
+
+    #[test]
+    fn test_recent_skips_top_sites_and_app_pages() {
+        let top = [site("https://news.example/", "News")];
+        let history = [
+            entry("https://news.example/", "News", 30),
+            entry("tauri://localhost/settings.html", "Settings", 20),
+            entry("https://mail.example/inbox", "", 10),
+        ];
+        let list = build(&Settings::default(), &top, &history);
+        assert_eq!(list.top, [JumpItem { title: "News".to_string(), args: open_args("https://news.example/") }]);
+        assert_eq!(
+            list.recent,
+            [JumpItem {
+                title: "https://mail.example/inbox".to_string(),
+                args: open_args("https://mail.example/inbox")
+            }]
+        );
+    }
+
+    #[test]
+    fn test_recent_is_limited() {
+        let history: Vec<HistoryEntry> =
+            (0..20).map(|i| entry(&format!("https://site{}.example/", i), "Page", 100 - i)).collect();
+        let list = build(&Settings::default(), &[], &history);
+        assert_eq!(list.recent.len(), RECENT_PAGES);
+        assert_eq!(list.recent[0].args, open_args("https://site0.example/"));
+    }
+
+    #[test]
+    fn test_command_line_quotes_each_arg() {
+        let args = ["--new-tab".to_string(), "https://a.example/?q=\"x y\"".to_string()];
+        assert_eq!(command_line(&args), "\"--new-tab\" \"https://a.example/?q=%22x y%22\"");
+    }
+}
@@ -0,0 +1,142 @@
+// Small secrets in the OS keychain, keyed by service and account.
+//
+// macOS: generic passwords in the login keychain, through the Security
+// framework's SecItem calls (NSDictionary is toll-free bridged to the
+// CFDictionary they take).
+// Windows/Linux: no keychain support yet. `is_available()` is false and callers
+// keep the secret in their own file in the app data directory.
+
+pub const SERVICE: &str = "Sovereign Browser";
+
+pub fn is_available() -> bool {
+    cfg!(target_os = "macos")
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::{c_void, CString};
+
+    const ERR_SEC_SUCCESS: i32 = 0;
+    const ERR_SEC_DUPLICATE_ITEM: i32 = -25299;
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    #[link(name = "Security", kind = "framework")]
+    #[allow(non_upper_case_globals)]
+    extern "C" {
+        static kSecClass: *mut Object;
+        static kSecClassGenericPassword: *mut Object;
+        static kSecAttrService: *mut Object;
+        static kSecAttrAccount: *mut Object;
+        static kSecValueData: *mut Object;
+        static kSecReturnData: *mut Object;
+        fn SecItemAdd(attributes: *mut Object, result: *mut *mut c_void) -> i32;
+        fn SecItemUpdate(query: *mut Object, attributes: *mut Object) -> i32;
+        fn SecItemCopyMatching(query: *mut Object, result: *mut *mut c_void) -> i32;
+        fn SecItemDelete(query: *mut Object) -> i32;
+    }
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+
+    unsafe fn ns_string(s: &str) -> *mut Object {
+        let s = CString::new(s).unwrap_or_default();
+        msg_send![class!(NSString), stringWithUTF8String: s.as_ptr()]
+    }
+
+    unsafe fn ns_data(bytes: &[u8]) -> *mut Object {
+        msg_send![class!(NSData), dataWithBytes: bytes.as_ptr() as *const c_void length: bytes.len()]
+    }
+
+    /// The item's class, service and account. Released by the caller.
+    unsafe fn query(service: &str, account: &str) -> *mut Object {
+        let query: *mut Object = msg_send![class!(NSMutableDictionary), new];
+        let _: () = msg_send![query, setObject: kSecClassGenericPassword forKey: kSecClass];
+        let _: () = msg_send![query, setObject: ns_string(service) forKey: kSecAttrService];
+        let _: () = msg_send![query, setObject: ns_string(account) forKey: kSecAttrAccount];
+        query
+    }
+
+    fn check(status: i32) -> Result<(), String> {
+        if status == ERR_SEC_SUCCESS {
+            Ok(())
+        } else {
+            Err(format!("Keychain error {}", status))
+        }
+    }
+
+    pub fn set(service: &str, account: &str, secret: &str) -> Result<(), String> {
+        unsafe {
+            let query = query(service, account);
+            let data = ns_data(secret.as_bytes());
+            let _: () = msg_send![query, setObject: data forKey: kSecValueData];
+            let mut status = SecItemAdd(query, std::ptr::null_mut());
+            if status == ERR_SEC_DUPLICATE_ITEM {
+                let _: () = msg_send![query, removeObjectForKey: kSecValueData];
+                let update: *mut Object = msg_send![class!(NSMutableDictionary), new];
+                let _: () = msg_send![update, setObject: data forKey: kSecValueData];
+                status = SecItemUpdate(query, update);
+                let _: () = msg_send![update, release];
+            }
+            let _: () = msg_send![query, release];
+            check(status)
+        }
+    }
+
+    pub fn get(service: &str, account: &str) -> Result<Option<String>, String> {
+        unsafe {
+            let query = query(service, account);
+            let yes: *mut Object = msg_send![class!(NSNumber), numberWithBool: true];
+            let _: () = msg_send![query, setObject: yes forKey: kSecReturnData];
+            let mut result: *mut c_void = std::ptr::null_mut();
+            let status = SecItemCopyMatching(query, &mut result);
+            let _: () = msg_send![query, release];
+            if status == ERR_SEC_ITEM_NOT_FOUND {
+                return Ok(None);
+            }
+            check(status)?;
+            if result.is_null() {
+                return Ok(None);
+            }
+            let data = result as *mut Object;
+            let bytes: *const u8 = msg_send![data, bytes];
+            let length: usize = msg_send![data, length];
+            let secret = String::from_utf8_lossy(std::slice::from_raw_parts(bytes, length)).into_owned();
+            CFRelease(result);
+            Ok(Some(secret))
+        }
+    }
+
+    pub fn delete(service: &str, account: &str) -> Result<(), String> {
+        unsafe {
+            let query = query(service, account);
+            let status = SecItemDelete(query);
+            let _: () = msg_send![query, release];
+            if status == ERR_SEC_ITEM_NOT_FOUND {
+                return Ok(());
+            }
+            check(status)
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    const UNSUPPORTED: &str = "No keychain support on this platform";
+
+    pub fn set(_service: &str, _account: &str, _secret: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn get(_service: &str, _account: &str) -> Result<Option<String>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn delete(_service: &str, _account: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+pub use platform::{delete, get, set};
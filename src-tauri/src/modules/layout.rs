@@ -0,0 +1,304 @@
+// Content area layout - where tab webviews go in the main window. Pure logic, no Tauri imports.
+//
+// The toolbar webview (ui/index.html) covers the whole window; tab webviews are
+// placed over it below the tab and URL bars. With vertical tabs the tab strip
+// becomes a sidebar on the left under the URL bar, so pages start lower by only
+// the URL bar and further right by the sidebar. The toolbar page gets the same
+// `ChromeLayout` in the "update-tabs" payload and draws to match. Split view puts
+// two pages side by side with a gap between them where the toolbar page draws
+// the divider.
+//
+// The window controls share the top row with the toolbar page. On macOS the
+// traffic lights float over the left end of the tab strip. On Windows there's
+// no system titlebar: the tab strip is the titlebar, the toolbar page draws the
+// caption buttons at its right end, and a restored window keeps a strip above
+// the tabs for resizing (gone when maximized, like other Windows browsers). On
+// Linux the system titlebar sits above the window's content and takes no room.
+//
+// Tablet mode makes the tab strip and URL bar taller so tabs and buttons are
+// big enough to hit with a finger; pages start lower to match.
+
+use serde::{Deserialize, Serialize};
+
+// --- Layout Constants (logical pixels) ---
+pub const TAB_BAR_HEIGHT: f64 = 40.0;
+pub const URL_BAR_HEIGHT: f64 = 56.0; // Includes padding
+pub const TOTAL_TOOLBAR_HEIGHT: f64 = TAB_BAR_HEIGHT + URL_BAR_HEIGHT;
+pub const SPLIT_DIVIDER_WIDTH: f64 = 6.0;
+pub const MIN_CONTENT_HEIGHT: u32 = 100;
+pub const DEFAULT_SIDEBAR_WIDTH: f64 = 240.0;
+pub const MIN_SIDEBAR_WIDTH: f64 = 160.0;
+pub const MAX_SIDEBAR_WIDTH: f64 = 480.0;
+pub const TRAFFIC_LIGHTS_WIDTH: f64 = 80.0;
+pub const CAPTION_BUTTON_WIDTH: f64 = 46.0; // Windows 11 minimize/maximize/close
+pub const RESIZE_BORDER_HEIGHT: f64 = 8.0;
+pub const TABLET_TAB_BAR_HEIGHT: f64 = 52.0;
+pub const TABLET_URL_BAR_HEIGHT: f64 = 72.0; // 44px buttons and address bar, the usual minimum touch target
+
+/// How much of the width the left pane may take.
+pub const MIN_SPLIT_RATIO: f64 = 0.2;
+pub const MAX_SPLIT_RATIO: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TabPlacement {
+    #[default]
+    Top, // Tab strip above the URL bar
+    Left, // Vertical tabs in a sidebar
+}
+
+/// Who draws the window controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitlebarStyle {
+    Native,  // System titlebar above the content (Linux)
+    Overlay, // Traffic lights over the tab strip (macOS)
+    Custom,  // No system titlebar, the toolbar page draws the caption buttons (Windows)
+}
+
+impl TitlebarStyle {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Overlay
+        } else if cfg!(windows) {
+            Self::Custom
+        } else {
+            Self::Native
+        }
+    }
+}
+
+/// Room the window controls take in the top row, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionInsets {
+    pub left: f64,  // Traffic lights
+    pub right: f64, // Caption buttons
+    pub top: f64,   // Resize border above the tab strip
+}
+
+impl CaptionInsets {
+    pub fn new(titlebar: TitlebarStyle, maximized: bool) -> Self {
+        match titlebar {
+            TitlebarStyle::Native => Self { left: 0.0, right: 0.0, top: 0.0 },
+            TitlebarStyle::Overlay => Self { left: TRAFFIC_LIGHTS_WIDTH, right: 0.0, top: 0.0 },
+            TitlebarStyle::Custom => Self {
+                left: 0.0,
+                right: 3.0 * CAPTION_BUTTON_WIDTH,
+                top: if maximized { 0.0 } else { RESIZE_BORDER_HEIGHT },
+            },
+        }
+    }
+}
+
+/// What the toolbar page takes up around the tab webviews.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChromeLayout {
+    pub tabs: TabPlacement,
+    pub sidebar_width: f64, // Logical pixels, only used with TabPlacement::Left
+    pub titlebar: TitlebarStyle,
+    pub maximized: bool,
+    pub caption: CaptionInsets,
+    pub tablet: bool,
+    pub tab_bar_height: f64,
+    pub url_bar_height: f64,
+}
+
+impl ChromeLayout {
+    /// For this platform's titlebar and a restored window.
+    pub fn new(tabs: TabPlacement, sidebar_width: f64) -> Self {
+        Self {
+            tabs,
+            sidebar_width: clamp_sidebar_width(sidebar_width),
+            titlebar: TitlebarStyle::current(),
+            maximized: false,
+            caption: CaptionInsets::new(TitlebarStyle::current(), false),
+            tablet: false,
+            tab_bar_height: TAB_BAR_HEIGHT,
+            url_bar_height: URL_BAR_HEIGHT,
+        }
+    }
+
+    pub fn with_tablet_mode(self, tablet: bool) -> Self {
+        let (tab_bar_height, url_bar_height) =
+            if tablet { (TABLET_TAB_BAR_HEIGHT, TABLET_URL_BAR_HEIGHT) } else { (TAB_BAR_HEIGHT, URL_BAR_HEIGHT) };
+        Self { tablet, tab_bar_height, url_bar_height, ..self }
+    }
+
+    pub fn with_window(self, titlebar: TitlebarStyle, maximized: bool) -> Self {
+        Self { titlebar, maximized, caption: CaptionInsets::new(titlebar, maximized), ..self }
+    }
+
+    /// Logical pixels above the content.
+    pub fn top_inset(&self) -> f64 {
+        let toolbar = match self.tabs {
+            TabPlacement::Top => self.tab_bar_height + self.url_bar_height,
+            TabPlacement::Left => self.url_bar_height,
+        };
+        toolbar + self.caption.top
+    }
+
+    /// Logical pixels left of the content.
+    pub fn left_inset(&self) -> f64 {
+        match self.tabs {
+            TabPlacement::Top => 0.0,
+            TabPlacement::Left => self.sidebar_width,
+        }
+    }
+}
+
+impl Default for ChromeLayout {
+    fn default() -> Self {
+        Self::new(TabPlacement::Top, DEFAULT_SIDEBAR_WIDTH)
+    }
+}
+
+pub fn clamp_sidebar_width(width: f64) -> f64 {
+    if width.is_finite() {
+        width.clamp(MIN_SIDEBAR_WIDTH, MAX_SIDEBAR_WIDTH)
+    } else {
+        DEFAULT_SIDEBAR_WIDTH
+    }
+}
+
+/// A webview's bounds in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaneRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn clamp_split_ratio(ratio: f64) -> f64 {
+    if ratio.is_finite() {
+        ratio.clamp(MIN_SPLIT_RATIO, MAX_SPLIT_RATIO)
+    } else {
+        0.5
+    }
+}
+
+/// Everything outside the toolbar (and sidebar), for a window of `width` x `height`
+/// physical pixels.
+pub fn content_area(width: u32, height: u32, scale: f64, chrome: ChromeLayout) -> PaneRect {
+    let top = (chrome.top_inset() * scale) as u32;
+    // Never squeezes the page out entirely on a narrow window
+    let left = ((chrome.left_inset() * scale) as u32).min(width / 2);
+    PaneRect {
+        x: left as i32,
+        y: top as i32,
+        width: width - left,
+        height: height.saturating_sub(top).max(MIN_CONTENT_HEIGHT),
+    }
+}
+
+/// The left and right panes of a split, `ratio` being the left pane's share of the
+/// width left after the divider.
+pub fn split_panes(width: u32, height: u32, scale: f64, chrome: ChromeLayout, ratio: f64) -> (PaneRect, PaneRect) {
+    let area = content_area(width, height, scale, chrome);
+    let divider = ((SPLIT_DIVIDER_WIDTH * scale).round() as u32).min(area.width);
+    let available = area.width - divider;
+    let left_width = (available as f64 * clamp_split_ratio(ratio)).round() as u32;
+    let left = PaneRect { width: left_width, ..area };
+    let right = PaneRect { x: area.x + (left_width + divider) as i32, width: available - left_width, ..area };
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn horizontal() -> ChromeLayout {
+        ChromeLayout::default().with_window(TitlebarStyle::Native, false)
+    }
+
+    fn vertical(sidebar_width: f64) -> ChromeLayout {
+        ChromeLayout::new(TabPlacement::Left, sidebar_width).with_window(TitlebarStyle::Native, false)
+    }
+
+    #[test]
+    fn test_content_area() {
+        let top = horizontal();
+        assert_eq!(content_area(1024, 768, 1.0, top), PaneRect { x: 0, y: 96, width: 1024, height: 672 });
+        assert_eq!(content_area(2048, 1536, 2.0, top), PaneRect { x: 0, y: 192, width: 2048, height: 1344 });
+        // Never collapses entirely
+        assert_eq!(content_area(800, 50, 1.0, top).height, MIN_CONTENT_HEIGHT);
+    }
+
+    #[test]
+    fn test_content_area_vertical_tabs() {
+        assert_eq!(content_area(1024, 768, 1.0, vertical(240.0)), PaneRect { x: 240, y: 56, width: 784, height: 712 });
+        assert_eq!(
+            content_area(2048, 1536, 2.0, vertical(200.0)),
+            PaneRect { x: 400, y: 112, width: 1648, height: 1424 }
+        );
+        // The sidebar gives way on narrow windows
+        assert_eq!(content_area(400, 768, 1.0, vertical(300.0)).x, 200);
+    }
+
+    #[test]
+    fn test_split_panes_even() {
+        let (left, right) = split_panes(1006, 768, 1.0, horizontal(), 0.5);
+        assert_eq!(left, PaneRect { x: 0, y: 96, width: 500, height: 672 });
+        assert_eq!(right, PaneRect { x: 506, y: 96, width: 500, height: 672 });
+
+        let (left, right) = split_panes(1206, 768, 1.0, vertical(200.0), 0.5);
+        assert_eq!(left, PaneRect { x: 200, y: 56, width: 500, height: 712 });
+        assert_eq!(right, PaneRect { x: 706, y: 56, width: 500, height: 712 });
+    }
+
+    #[test]
+    fn test_split_panes_fill_the_width() {
+        for chrome in [horizontal(), vertical(260.0), horizontal().with_window(TitlebarStyle::Custom, false)] {
+            for (width, scale, ratio) in [(1023, 1.0, 0.37), (2560, 2.0, 0.61), (1501, 1.5, 0.8)] {
+                let (left, right) = split_panes(width, 900, scale, chrome, ratio);
+                let divider = (SPLIT_DIVIDER_WIDTH * scale).round() as u32;
+                assert_eq!(left.x as u32 + left.width + divider + right.width, width);
+                assert_eq!(right.x, left.x + (left.width + divider) as i32);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tablet_mode_lowers_the_content() {
+        let tablet = horizontal().with_tablet_mode(true);
+        assert_eq!(content_area(1024, 768, 1.0, tablet), PaneRect { x: 0, y: 124, width: 1024, height: 644 });
+        assert_eq!(content_area(1024, 768, 1.0, vertical(240.0).with_tablet_mode(true)).y, 72);
+        // And back
+        assert_eq!(tablet.with_tablet_mode(false), horizontal());
+    }
+
+    #[test]
+    fn test_custom_titlebar_resize_border() {
+        let restored = horizontal().with_window(TitlebarStyle::Custom, false);
+        let maximized = horizontal().with_window(TitlebarStyle::Custom, true);
+        assert_eq!(content_area(1024, 768, 1.0, restored), PaneRect { x: 0, y: 104, width: 1024, height: 664 });
+        assert_eq!(content_area(2048, 1536, 2.0, restored).y, 208);
+        assert_eq!(content_area(1024, 768, 1.0, maximized), content_area(1024, 768, 1.0, horizontal()));
+        assert_eq!(restored.caption.right, 138.0);
+        // The macOS traffic lights sit beside the tabs, not above them
+        let overlay = horizontal().with_window(TitlebarStyle::Overlay, false);
+        assert_eq!(content_area(1024, 768, 1.0, overlay).y, 96);
+        assert_eq!(overlay.caption.left, TRAFFIC_LIGHTS_WIDTH);
+    }
+
+    #[rstest]
+    #[case(240.0, 240.0)]
+    #[case(20.0, MIN_SIDEBAR_WIDTH)]
+    #[case(2000.0, MAX_SIDEBAR_WIDTH)]
+    #[case(f64::INFINITY, DEFAULT_SIDEBAR_WIDTH)]
+    fn test_clamp_sidebar_width(#[case] width: f64, #[case] expected: f64) {
+        assert_eq!(clamp_sidebar_width(width), expected);
+    }
+
+    #[rstest]
+    #[case(0.5, 0.5)]
+    #[case(0.05, MIN_SPLIT_RATIO)]
+    #[case(0.95, MAX_SPLIT_RATIO)]
+    #[case(f64::NAN, 0.5)]
+    fn test_clamp_split_ratio(#[case] ratio: f64, #[case] expected: f64) {
+        assert_eq!(clamp_split_ratio(ratio), expected);
+    }
+}
@@ -0,0 +1,97 @@
+// Where a link goes, shown before it's clicked.
+//
+// Tabs run `HOVER_SCRIPT`, which reports the link under the pointer (or focused
+// from the keyboard) to `link_hovered` when it changes. For the tab on screen
+// that's emitted to the main window as "link-hover", formatted like the URL bar
+// (see modules::url_display, so look-alike hosts stay in punycode), and the
+// toolbar shows it in place of the page's address until the pointer leaves.
+// The link's real href is shown, not its text, so a link reading one address
+// can't hide another. Frames aren't covered.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::BrowserError;
+use crate::modules::url_display::{self, DisplayUrl};
+use crate::state::AppState;
+
+// data: links can be megabytes; the toolbar only has room for the start
+const MAX_SHOWN_CHARS: usize = 2048;
+
+/// Reports the hovered or focused link, null once there's none.
+pub const HOVER_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_LINK_HOVER__ || !window.__TAURI__ || window.top !== window) return;
+    window.__SOVEREIGN_LINK_HOVER__ = true;
+    let current = null;
+
+    function report(link) {
+        const href = link ? link.href : null;
+        if (href === current) return;
+        current = href;
+        window.__TAURI__.core.invoke('link_hovered', { href }).catch(() => {});
+    }
+    const linkOf = (node) => node instanceof Element ? node.closest('a[href], area[href]') : null;
+
+    document.addEventListener('mouseover', (e) => report(linkOf(e.target)), true);
+    document.addEventListener('focusin', (e) => report(linkOf(e.target)), true);
+    document.addEventListener('mouseout', (e) => { if (!e.relatedTarget) report(null); }, true);
+    window.addEventListener('blur', () => report(null));
+    window.addEventListener('pagehide', () => report(null));
+})();
+"#;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkHover {
+    pub tab_id: String,
+    pub link: Option<DisplayUrl>, // None once the pointer left the link
+}
+
+/// How the toolbar shows `href`: formatted like the URL bar, cut to MAX_SHOWN_CHARS.
+pub fn preview(href: &str) -> Option<DisplayUrl> {
+    let href = href.trim();
+    if href.is_empty() {
+        return None;
+    }
+    let shown: String = href.chars().take(MAX_SHOWN_CHARS).collect();
+    Some(url_display::format_url_for_display(&shown))
+}
+
+/// From `HOVER_SCRIPT`: the link under the pointer changed.
+#[tauri::command]
+pub fn link_hovered(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    href: Option<String>,
+) -> Result<(), BrowserError> {
+    let tab = {
+        let tabs = state.tabs.lock().unwrap();
+        let active = state.active_tab_id.lock().unwrap();
+        // Detached tabs have a window of their own
+        let tab = tabs.iter().find(|t| t.webview_label == webview.label() && t.window.is_none());
+        tab.map(|t| (t.id.clone(), active.as_ref() == Some(&t.id)))
+    };
+    // Only the tab on screen, or either pane of split view
+    let Some(tab_id) = tab.filter(|(id, active)| *active || state.split_view.contains(id)).map(|(id, _)| id) else {
+        return Ok(());
+    };
+    app.emit_to("main", "link-hover", LinkHover { tab_id, link: href.as_deref().and_then(preview) })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview() {
+        let shown = preview("https://www.example.com/a/b?c=d").unwrap();
+        assert_eq!(shown.full, "https://www.example.com/a/b?c=d");
+        assert!(preview("  ").is_none());
+
+        let long = format!("data:text/plain,{}", "x".repeat(10_000));
+        assert_eq!(preview(&long).unwrap().full.chars().count(), MAX_SHOWN_CHARS);
+    }
+}
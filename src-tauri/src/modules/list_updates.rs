@@ -0,0 +1,58 @@
+// Scheduled downloads of the filter lists and threat feeds.
+//
+// The ad block filter lists are refreshed at launch and then whenever an
+// enabled list is older than FILTER_MAX_AGE, checked every CHECK_INTERVAL. The
+// safe browsing feeds keep their own schedule (see modules::safebrowsing). Both
+// are several megabytes, so on a metered connection the scheduled downloads wait
+// until the connection isn't metered, unless `Settings.list_updates_on_metered`
+// is on. Until then the cached or bundled copies stay in use. Updates the user
+// asks for (adding or toggling a list, `update_filter_lists`) are never deferred.
+// The metered state is probed each time (see data_saver::probe_metered).
+//
+// Downloads go through `fetch_text`, so they use the same proxy as the tabs.
+// There's no HSTS preload list to schedule: each engine ships its own.
+
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::modules::{data_saver, proxy};
+use crate::state::AppState;
+
+const FILTER_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30); // reqwest's blocking default
+
+/// Whether the scheduled download of `what` may go ahead now. Logs when it's deferred.
+pub fn may_download(app: &AppHandle, what: &str) -> bool {
+    let on_metered = app.try_state::<AppState>().is_some_and(|s| s.settings.read().unwrap().list_updates_on_metered);
+    if on_metered || !data_saver::probe_metered() {
+        return true;
+    }
+    println!("[ListUpdates] Metered connection, deferring {} download", what);
+    false
+}
+
+/// Downloads `url` as text through the configured proxy (see proxy::http_client).
+pub fn fetch_text(app: &AppHandle, url: &str) -> Result<String, String> {
+    let state = app.try_state::<AppState>().ok_or("App state not ready")?;
+    let client = proxy::blocking_http_client(&state, DOWNLOAD_TIMEOUT).map_err(|e| e.to_string())?;
+    client.get(url).send().and_then(|r| r.error_for_status()?.text()).map_err(|e| e.to_string())
+}
+
+/// Refreshes the filter lists at launch and when they go stale.
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || {
+        // The launch refresh, until it gets to run
+        let mut pending = true;
+        loop {
+            if let Some(state) = app.try_state::<AppState>() {
+                let due = pending || state.adblock.lists_due(FILTER_MAX_AGE);
+                if due && !state.adblock.is_updating() && may_download(&app, "filter list") {
+                    state.adblock.spawn_update_thread();
+                    pending = false;
+                }
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
@@ -0,0 +1,305 @@
+// Data hygiene that runs by itself while the browser is idle.
+//
+// Each task has an interval. A background thread wakes every CHECK_INTERVAL and
+// runs the tasks that are due, but only when the browser is idle: no tab loading
+// and no page started for IDLE_AFTER (page loads call `touch`). Each task's last
+// run (when, what it did, or why it failed) is kept in maintenance.json and
+// reported by `get_maintenance_status`.
+//
+// The tasks:
+// - history: drops visits past their retention and compacts the append-only
+//   history.log to one line per URL
+// - exceptions: forgets "continue anyway" exceptions (HTTPS-Only, threat
+//   warnings) of tabs that are gone, and expired redirect loop guards
+// - closed tabs: drops tabs closed more than CLOSED_TAB_MAX_AGE ago
+// - favicons: forgets the new tab page's favicons of sites no longer in history
+// - logs: rotates the saved console log
+//
+// Nothing is stored in SQLite, so there's nothing to vacuum: the stores are JSON
+// files rewritten whole, and the logs are covered above.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::{closed_tabs, closed_tabs_store};
+use crate::state::AppState;
+
+const MAINTENANCE_FILE: &str = "maintenance.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const IDLE_AFTER: Duration = Duration::from_secs(2 * 60);
+const CLOSED_TAB_MAX_AGE: Duration = Duration::from_secs(30 * 86400);
+const HOUR: u64 = 3600;
+const DAY: u64 = 24 * HOUR;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTask {
+    History,
+    Exceptions,
+    ClosedTabs,
+    Favicons,
+    Logs,
+}
+
+impl MaintenanceTask {
+    pub const ALL: [MaintenanceTask; 5] =
+        [Self::History, Self::Exceptions, Self::ClosedTabs, Self::Favicons, Self::Logs];
+
+    /// Seconds between runs.
+    fn interval(self) -> u64 {
+        match self {
+            Self::History | Self::ClosedTabs | Self::Logs => DAY,
+            Self::Exceptions => 6 * HOUR,
+            Self::Favicons => 7 * DAY,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::History => "Compact history",
+            Self::Exceptions => "Prune expired exceptions",
+            Self::ClosedTabs => "Prune closed tabs",
+            Self::Favicons => "Trim favicon cache",
+            Self::Logs => "Rotate logs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    pub finished_at: u64, // Unix seconds
+    pub ok: bool,
+    pub outcome: String, // What it did, or the error
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub task: MaintenanceTask,
+    pub label: &'static str,
+    pub last_run: Option<TaskRun>,
+    pub next_due: u64, // Unix seconds; runs at the first idle moment after
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    pub tasks: Vec<TaskStatus>,
+    pub running: bool,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+pub struct MaintenanceScheduler {
+    path: PathBuf,
+    runs: Mutex<BTreeMap<MaintenanceTask, TaskRun>>,
+    last_activity: Mutex<Instant>,
+    running: AtomicBool,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(MAINTENANCE_FILE);
+        let runs = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self {
+            path,
+            runs: Mutex::new(runs),
+            last_activity: Mutex::new(Instant::now()),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    fn save(&self, runs: &BTreeMap<MaintenanceTask, TaskRun>) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(runs).unwrap_or_default()) {
+            eprintln!("[Maintenance] Failed to save: {}", e);
+        }
+    }
+
+    /// Marks the browser busy; called when a page starts loading.
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    fn quiet_since(&self, now: Instant) -> bool {
+        now.duration_since(*self.last_activity.lock().unwrap()) >= IDLE_AFTER
+    }
+
+    /// Tasks never run, or whose interval has passed since their last run.
+    fn due(&self, now: u64) -> Vec<MaintenanceTask> {
+        let runs = self.runs.lock().unwrap();
+        MaintenanceTask::ALL
+            .into_iter()
+            .filter(|task| runs.get(task).map_or(true, |run| now >= run.finished_at + task.interval()))
+            .collect()
+    }
+
+    fn record(&self, task: MaintenanceTask, result: Result<String, String>, now: u64) {
+        let run = match result {
+            Ok(outcome) => TaskRun { finished_at: now, ok: true, outcome },
+            Err(e) => TaskRun { finished_at: now, ok: false, outcome: e },
+        };
+        let mut runs = self.runs.lock().unwrap();
+        runs.insert(task, run);
+        self.save(&runs);
+    }
+
+    pub fn status(&self, now: u64) -> MaintenanceStatus {
+        let runs = self.runs.lock().unwrap();
+        let tasks = MaintenanceTask::ALL
+            .into_iter()
+            .map(|task| {
+                let last_run = runs.get(&task).cloned();
+                let next_due = last_run.as_ref().map_or(now, |run| run.finished_at + task.interval());
+                TaskStatus { task, label: task.label(), last_run, next_due }
+            })
+            .collect();
+        MaintenanceStatus { tasks, running: self.running.load(Ordering::Relaxed) }
+    }
+}
+
+fn run_task(app: &AppHandle, state: &AppState, task: MaintenanceTask) -> Result<String, String> {
+    match task {
+        MaintenanceTask::History => {
+            let before = state.history.log_bytes();
+            let pruned = state.history.prune_visits(now_secs()).map_err(|e| e.to_string())?;
+            state.history.compact().map_err(|e| e.to_string())?;
+            let after = state.history.log_bytes();
+            Ok(format!("History log {} KB to {} KB, {} old visits dropped", before / 1024, after / 1024, pruned))
+        }
+        MaintenanceTask::Exceptions => {
+            let live: HashSet<String> = app.webviews().into_keys().collect();
+            let removed = state.https_only.prune(&live, Instant::now()) + state.safe_browsing.prune(&live);
+            Ok(format!("{} expired exceptions removed", removed))
+        }
+        MaintenanceTask::ClosedTabs => {
+            let cutoff = SystemTime::now() - CLOSED_TAB_MAX_AGE;
+            let (removed, store) = {
+                let mut closed = state.closed_tabs.lock().unwrap();
+                let removed = closed_tabs::expire_closed_tabs(&mut closed, cutoff);
+                (removed, closed_tabs_store::ClosedTabsStore { tabs: closed.clone() })
+            };
+            if removed > 0 {
+                store.save(app)?;
+            }
+            Ok(format!("{} closed tabs older than 30 days removed", removed))
+        }
+        MaintenanceTask::Favicons => {
+            let history = state.history.list(0, usize::MAX, None, None).entries;
+            let removed = state.top_sites.trim_favicons(&history);
+            Ok(format!("{} unused favicons removed", removed))
+        }
+        MaintenanceTask::Logs => {
+            let rotated = state.console_log.rotate_now().map_err(|e| e.to_string())?;
+            Ok(if rotated { "Console log rotated" } else { "Console log empty, nothing to rotate" }.to_string())
+        }
+    }
+}
+
+/// Runs `tasks` one after another, unless a run is already going.
+fn run_tasks(app: &AppHandle, tasks: &[MaintenanceTask]) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let scheduler = &state.maintenance;
+    if tasks.is_empty() || scheduler.running.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    for &task in tasks {
+        let result = run_task(app, &state, task);
+        match &result {
+            Ok(outcome) => println!("[Maintenance] {}: {}", task.label(), outcome),
+            Err(e) => eprintln!("[Maintenance] {} failed: {}", task.label(), e),
+        }
+        scheduler.record(task, result, now_secs());
+    }
+    scheduler.running.store(false, Ordering::Relaxed);
+}
+
+fn is_idle(state: &AppState) -> bool {
+    state.maintenance.quiet_since(Instant::now()) && !state.tabs.lock().unwrap().iter().any(|t| t.is_loading)
+}
+
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        let due = match app.try_state::<AppState>() {
+            Some(state) if is_idle(&state) => state.maintenance.due(now_secs()),
+            _ => continue,
+        };
+        run_tasks(&app, &due);
+    });
+}
+
+#[tauri::command]
+pub fn get_maintenance_status(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<MaintenanceStatus, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.maintenance.status(now_secs()))
+}
+
+/// Runs every task now, idle or not.
+#[tauri::command]
+pub fn run_maintenance(app: AppHandle, webview: tauri::Webview) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    std::thread::spawn(move || run_tasks(&app, &MaintenanceTask::ALL));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_follows_intervals() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = MaintenanceScheduler::new(dir.path().to_path_buf());
+        let now = 1_000 * DAY;
+        assert_eq!(scheduler.due(now), MaintenanceTask::ALL.to_vec());
+
+        for task in MaintenanceTask::ALL {
+            scheduler.record(task, Ok("done".to_string()), now);
+        }
+        assert!(scheduler.due(now + HOUR).is_empty());
+        assert_eq!(scheduler.due(now + 6 * HOUR), vec![MaintenanceTask::Exceptions]);
+        assert_eq!(scheduler.due(now + DAY).len(), 4);
+        assert_eq!(scheduler.due(now + 7 * DAY).len(), 5);
+    }
+
+    #[test]
+    fn test_runs_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = MaintenanceScheduler::new(dir.path().to_path_buf());
+        scheduler.record(MaintenanceTask::Logs, Err("disk full".to_string()), 100);
+
+        let reopened = MaintenanceScheduler::new(dir.path().to_path_buf());
+        let status = reopened.status(200);
+        let logs = status.tasks.iter().find(|t| t.task == MaintenanceTask::Logs).unwrap();
+        assert_eq!(logs.last_run, Some(TaskRun { finished_at: 100, ok: false, outcome: "disk full".to_string() }));
+        assert_eq!(logs.next_due, 100 + DAY);
+        let history = status.tasks.iter().find(|t| t.task == MaintenanceTask::History).unwrap();
+        assert_eq!((history.last_run.as_ref(), history.next_due), (None, 200));
+    }
+
+    #[test]
+    fn test_idle_after_quiet_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let scheduler = MaintenanceScheduler::new(dir.path().to_path_buf());
+        scheduler.touch();
+        let now = Instant::now();
+        assert!(!scheduler.quiet_since(now));
+        assert!(scheduler.quiet_since(now + IDLE_AFTER));
+    }
+}
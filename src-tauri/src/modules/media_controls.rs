@@ -0,0 +1,289 @@
+// Media playback controls.
+//
+// `page_script` gives every page a small controller for its <video>/<audio>
+// elements (`window.__SOVEREIGN_MEDIA__`), driven from the toolbar's playback
+// popover: speed, skipping back/forward and looping. The speed is saved per site
+// in `SiteSettings.playback_rate` and applied to every element as it starts
+// playing, so a site watched at 1.5x stays at 1.5x. Skip and loop act on the
+// element that's playing (else the largest video); looping lasts until the tab
+// navigates. It also hands the main video to a pop-out window and takes it back
+// (see modules::video_popout). Only media in the top document is reached, not in
+// cross-origin frames.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::cookie_policy;
+use crate::settings::SiteSettings;
+use crate::state::AppState;
+
+pub const MIN_RATE: f64 = 0.25;
+pub const MAX_RATE: f64 = 4.0;
+
+const PAGE_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_MEDIA__) return;
+    const SITE_RATES = __SITE_RATES__;
+    const host = location.hostname.toLowerCase();
+    const site = Object.keys(SITE_RATES)
+        .filter(s => host === s || host.endsWith('.' + s))
+        .sort((a, b) => b.length - a.length)[0];
+    let rate = site ? SITE_RATES[site] : 1;
+
+    function mediaElements() {
+        return [...document.querySelectorAll('video,audio')];
+    }
+    function primary() {
+        const media = mediaElements();
+        const playing = media.find(m => !m.paused && !m.ended);
+        if (playing) return playing;
+        const area = m => m.clientWidth * m.clientHeight;
+        return media.sort((a, b) => area(b) - area(a))[0] || null;
+    }
+
+    // Players reset the speed when they load a new source; media events don't
+    // bubble, so listen in the capture phase
+    for (const type of ['play', 'loadedmetadata']) {
+        document.addEventListener(type, e => {
+            if (rate !== 1 && e.target.playbackRate !== rate) e.target.playbackRate = rate;
+        }, true);
+    }
+
+    window.__SOVEREIGN_MEDIA__ = {
+        setRate(value) {
+            rate = value;
+            mediaElements().forEach(m => { m.playbackRate = rate; });
+        },
+        skip(seconds) {
+            const m = primary();
+            if (!m) return;
+            const end = isFinite(m.duration) ? m.duration : Infinity;
+            m.currentTime = Math.max(0, Math.min(m.currentTime + seconds, end));
+        },
+        setLoop(value) {
+            const m = primary();
+            if (m) m.loop = value;
+        },
+        popOut() {
+            const m = primary();
+            const video = m && m.tagName === 'VIDEO' ? m : null;
+            if (!window.__TAURI__) return;
+            window.__TAURI__.core.invoke('open_video_popout', {
+                src: video ? video.currentSrc || null : null,
+                time: video ? video.currentTime : 0,
+                playing: !!video && !video.paused,
+                title: document.title
+            }).then(() => {
+                video.pause();
+                video.dataset.sovereignPopout = '';
+            }).catch(() => {});
+        },
+        resumeFromPopOut(time, playing) {
+            const video = document.querySelector('video[data-sovereign-popout]');
+            if (!video) return;
+            delete video.dataset.sovereignPopout;
+            video.currentTime = time;
+            if (playing) video.play().catch(() => {});
+        }
+    };
+})();
+"#;
+
+/// The controller, with each site's saved speed.
+pub fn page_script(sites: &BTreeMap<String, SiteSettings>) -> String {
+    let rates: BTreeMap<&str, f64> =
+        sites.iter().filter_map(|(site, s)| s.playback_rate.map(|rate| (site.as_str(), rate))).collect();
+    PAGE_SCRIPT_TEMPLATE.replace("__SITE_RATES__", &serde_json::to_string(&rates).unwrap_or_else(|_| "{}".to_string()))
+}
+
+pub fn validate_rate(rate: f64) -> Result<f64, String> {
+    if rate.is_finite() && (MIN_RATE..=MAX_RATE).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(format!("Playback rate must be between {} and {}", MIN_RATE, MAX_RATE))
+    }
+}
+
+/// The speed saved for the site of `url`, 1.0 without one.
+pub fn rate_for(sites: &BTreeMap<String, SiteSettings>, url: &str) -> f64 {
+    site_of_url(url).and_then(|site| sites.get(&site)).and_then(|s| s.playback_rate).unwrap_or(1.0)
+}
+
+fn site_of_url(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    cookie_policy::normalize_site(url.host_str()?)
+}
+
+/// Tabs with looping on, until they navigate.
+pub struct MediaControlsManager {
+    looping: Mutex<HashSet<String>>, // webview labels
+}
+
+impl MediaControlsManager {
+    pub fn new() -> Self {
+        Self { looping: Mutex::new(HashSet::new()) }
+    }
+
+    pub fn set_looping(&self, webview_label: &str, looping: bool) {
+        let mut tabs = self.looping.lock().unwrap();
+        if looping {
+            tabs.insert(webview_label.to_string());
+        } else {
+            tabs.remove(webview_label);
+        }
+    }
+
+    pub fn is_looping(&self, webview_label: &str) -> bool {
+        self.looping.lock().unwrap().contains(webview_label)
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.looping.lock().unwrap().remove(webview_label);
+    }
+}
+
+impl Default for MediaControlsManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn set_rate_script(rate: f64) -> String {
+    format!("window.__SOVEREIGN_MEDIA__ && window.__SOVEREIGN_MEDIA__.setRate({});", rate)
+}
+
+/// Re-applies the site's saved speed after a load; the init script only knows the
+/// speeds saved when the tab was created.
+pub fn on_page_finished(webview: &tauri::Webview, state: &AppState, url: &Url) {
+    let rate = rate_for(&state.settings.read().unwrap().site_settings, url.as_str());
+    let _ = webview.eval(&set_rate_script(rate));
+}
+
+/// A tab's webview label and URL.
+fn tab_of(state: &AppState, tab_id: &str) -> Result<(String, String), BrowserError> {
+    state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| (t.webview_label.clone(), t.url.clone()))
+        .ok_or_else(|| BrowserError::tab_not_found(tab_id))
+}
+
+fn eval_in_tab(app: &AppHandle, state: &AppState, tab_id: &str, script: &str) -> Result<String, BrowserError> {
+    let (label, url) = tab_of(state, tab_id)?;
+    let webview =
+        app.get_webview(&label).ok_or_else(|| BrowserError::NotFound(format!("Webview not found: {}", label)))?;
+    webview.eval(script)?;
+    Ok(url)
+}
+
+/// Applies a speed to a tab's media. Returns the site to save it for, if the tab
+/// shows a web page.
+pub fn apply_rate(app: &AppHandle, state: &AppState, tab_id: &str, rate: f64) -> Result<Option<String>, BrowserError> {
+    let rate = validate_rate(rate).map_err(BrowserError::InvalidInput)?;
+    let url = eval_in_tab(app, state, tab_id, &set_rate_script(rate))?;
+    Ok(site_of_url(&url))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaControls {
+    pub site: Option<String>, // Where the speed is saved; None for non-web pages
+    pub rate: f64,
+    pub looping: bool,
+}
+
+/// The popover's state for a tab.
+#[tauri::command]
+pub fn get_media_controls(state: tauri::State<AppState>, tab_id: String) -> Result<MediaControls, BrowserError> {
+    let (label, url) = tab_of(&state, &tab_id)?;
+    let rate = rate_for(&state.settings.read().unwrap().site_settings, &url);
+    Ok(MediaControls { site: site_of_url(&url), rate, looping: state.media_controls.is_looping(&label) })
+}
+
+/// Seeks a tab's media by `seconds` (negative to go back).
+#[tauri::command]
+pub fn skip_media(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    tab_id: String,
+    seconds: f64,
+) -> Result<(), BrowserError> {
+    if !seconds.is_finite() {
+        return Err(BrowserError::InvalidInput("Invalid skip".to_string()));
+    }
+    let script = format!("window.__SOVEREIGN_MEDIA__ && window.__SOVEREIGN_MEDIA__.skip({});", seconds);
+    eval_in_tab(&app, &state, &tab_id, &script).map(|_| ())
+}
+
+#[tauri::command]
+pub fn set_media_loop(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    tab_id: String,
+    enabled: bool,
+) -> Result<(), BrowserError> {
+    let script = format!("window.__SOVEREIGN_MEDIA__ && window.__SOVEREIGN_MEDIA__.setLoop({});", enabled);
+    eval_in_tab(&app, &state, &tab_id, &script)?;
+    let (label, _) = tab_of(&state, &tab_id)?;
+    state.media_controls.set_looping(&label, enabled);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn sites(entries: &[(&str, f64)]) -> BTreeMap<String, SiteSettings> {
+        entries
+            .iter()
+            .map(|(site, rate)| (site.to_string(), SiteSettings { playback_rate: Some(*rate), ..Default::default() }))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(1.5, true)]
+    #[case(MIN_RATE, true)]
+    #[case(MAX_RATE, true)]
+    #[case(0.1, false)]
+    #[case(16.0, false)]
+    #[case(f64::NAN, false)]
+    fn test_validate_rate(#[case] rate: f64, #[case] valid: bool) {
+        assert_eq!(validate_rate(rate).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_rate_for() {
+        let per_site = sites(&[("youtube.com", 1.5)]);
+        assert_eq!(rate_for(&per_site, "https://www.youtube.com/watch?v=x"), 1.5);
+        assert_eq!(rate_for(&per_site, "https://vimeo.com/1"), 1.0);
+        assert_eq!(rate_for(&per_site, "tauri://localhost/settings.html"), 1.0);
+    }
+
+    #[test]
+    fn test_page_script_embeds_site_rates() {
+        let script = page_script(&sites(&[("youtube.com", 1.5)]));
+        assert!(script.contains(r#"const SITE_RATES = {"youtube.com":1.5};"#));
+        assert!(page_script(&BTreeMap::new()).contains("const SITE_RATES = {};"));
+    }
+
+    #[test]
+    fn test_looping() {
+        let manager = MediaControlsManager::new();
+        manager.set_looping("webview-tab-1", true);
+        assert!(manager.is_looping("webview-tab-1"));
+        assert!(!manager.is_looping("webview-tab-2"));
+        manager.forget_webview("webview-tab-1");
+        assert!(!manager.is_looping("webview-tab-1"));
+    }
+}
@@ -4,3 +4,86 @@ pub mod devtools;
 pub mod closed_tabs;         // Tab archival logic
 pub mod closed_tabs_store;   // Persistence layer
 pub mod tabs;                // Tab reordering logic
+pub mod context_menu;        // Link dispositions (new tab/window/private)
+pub mod suggest;             // Opt-in remote search suggestions
+pub mod drag_out;            // Native URL drag-out via temporary shortcut files
+pub mod badges;              // Unread count parsing for tab badges
+pub mod session_store;       // Open tab session persistence
+pub mod commands;            // Command registry for menu and palette
+pub mod browsing_data;       // Granular clear browsing data
+pub mod stash;               // Named tab stashes for later reading
+pub mod cookie_policy;       // Third-party cookie blocking
+pub mod appearance;          // Window vibrancy and titlebar tint
+pub mod toolbar_layout;      // Customizable toolbar widget order
+pub mod doh;                 // DNS-over-HTTPS local proxy
+pub mod tab_status;          // Consistent per-tab status snapshots
+pub mod proxy;
+pub mod focus;
+pub mod fingerprint;
+pub mod url_display;
+pub mod site_storage;
+pub mod site_data;
+pub mod https_only;
+pub mod element_picker;
+pub mod filter_subscribe;
+pub mod channel_blocking;     // WebSocket and service worker blocking
+pub mod block_stats;          // Blocked request counters
+pub mod frames;               // Frame context for network blocking
+pub mod user_agent;           // Honest UA and per-site compatibility overrides
+pub mod data_saver;           // Bandwidth saver for metered connections
+pub mod image_blocking;       // Global and per-site image blocking
+pub mod regional_lists;       // Language-specific filter lists
+pub mod safebrowsing;         // Local malware/phishing blocklist
+pub mod audio_output;         // Audio output device selection
+pub mod media_controls;       // Playback speed, skip and loop for page media
+pub mod network_log;          // Per-tab request log and HAR export
+pub mod permissions;          // Site permission requests awaiting the user
+pub mod screen_capture;       // getDisplayMedia source picker
+pub mod console_log;          // Per-tab console capture and log file
+pub mod webauthn;             // Passkey support check
+pub mod task_manager;         // Per-tab memory/CPU sampling
+pub mod video_popout;         // Floating always-on-top video window
+pub mod read_aloud;           // Article text-to-speech
+pub mod tab_audio;            // Audible tab detection and muting
+pub mod text_replace;         // Find-and-replace rules for page text
+pub mod snippets;             // CSS/JS snippets with hotkeys
+pub mod tab_search;           // Fuzzy search over open and closed tabs
+pub mod tab_windows;          // Tabs detached into their own windows
+pub mod tab_engine;           // Tab lifecycle: create, switch, close, what's shown next
+pub mod form_audit;           // Warnings before risky form submissions
+pub mod email_alias;          // Per-site aliases from SimpleLogin/Firefox Relay
+pub mod layout;               // Content area and split view geometry
+pub mod split_view;           // Two tabs side by side
+pub mod keychain;             // OS keychain for small secrets
+pub mod totp;                 // One-time code vault and autofill
+pub mod breach_check;         // Saved sites and passwords against HIBP
+pub mod top_sites;            // Frecency-ranked sites for the new tab page
+pub mod thumbnails;           // Cached page snapshots for top site tiles
+pub mod annotations;          // Highlights and notes on pages
+pub mod site_apps;            // Sites installed as standalone app windows
+pub mod tracking_params;      // utm_*/fbclid stripping from links
+pub mod privacy_report;       // Weekly summary of what was blocked
+pub mod maintenance;          // Idle-time data hygiene tasks
+pub mod external_protocols;   // Prompting before mailto:/zoommtg: links open other apps
+pub mod headless;             // --headless: full startup without visible windows
+pub mod cli;                  // --new-tab/--private/--profile launch flags
+pub mod profile;              // Per-profile data directories (--profile)
+pub mod titlebar;             // Custom Windows titlebar, caption buttons and Snap Layouts
+pub mod bookmarks;            // Bookmarks with their folders
+pub mod importer;             // Bookmarks and history from Chrome and Firefox
+pub mod dropdown;             // Omnibox dropdown as a window or, on Wayland, a child webview
+pub mod user_data;            // Whole-profile export/import archive
+pub mod settings_migration;   // settings.json schema versions and upgrades
+pub mod touch;                // Pinch zoom, swipe navigation and scrolling for touch input
+pub mod containers;           // Tabs with their own cookie jars, and sites that always open in one
+pub mod text_input;           // Spellcheck, autocorrect, smart quotes and dictation (macOS)
+pub mod handoff;              // Active page offered to other devices via Handoff (macOS)
+pub mod dock;                 // Download progress and unread badge on the Dock/taskbar icon
+pub mod guest;                // Throwaway guest windows for someone borrowing the browser
+pub mod jump_list;            // Windows jump list and macOS Dock menu with top and recent sites
+pub mod offline;              // "Go Offline" switch that cuts tabs off the network
+pub mod list_updates;         // Scheduled filter and threat list downloads, deferred on metered connections
+pub mod site_search;          // Tab-to-search with search engines learned from sites
+pub mod link_hover;           // Destination of the hovered link, shown in the toolbar
+pub mod browsing_webview;     // Setup shared by tabs and browsing windows
+pub mod clipboard;           // Copied link detection
@@ -4,6 +4,27 @@
 use url::Url;
 use crate::settings::Settings;
 
+/// Bidi controls that ride along when RTL text is pasted or typed. They are
+/// invisible, never valid in a hostname, and would otherwise push input to search.
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{200E}' | '\u{200F}' | '\u{061C}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Folds what CJK IMEs produce in full-width mode back to ASCII so "ｅｘａｍｐｌｅ．ｃｏｍ"
+/// or "例え。テスト" is recognized as a domain. Only used for URL detection;
+/// searches keep the text as typed.
+fn fold_ime_input(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c), // Full-width ASCII
+            '\u{3000}' => ' ',                                                        // Ideographic space
+            '\u{3002}' | '\u{FF61}' => '.',                                           // Ideographic full stops
+            _ => c,
+        })
+        .collect()
+}
+
 /// Logic for parsing input into a navigable URL.
 ///
 /// PRIVACY NOTICE:
@@ -13,12 +34,29 @@ use crate::settings::Settings;
 /// 3. It does NOT send any data to autocomplete servers.
 /// 4. The only external request happens when the user explicitly commits navigation (Enter/Go),
 ///    at which point the Webview initiates a standard navigation.
+///
+/// Unicode hostnames come back in punycode (IDNA, via the url crate). Hosts that
+/// fail IDNA validation (e.g. mixed-direction labels) fall through to search.
 pub fn smart_parse_url(input: &str, settings: &Settings) -> String {
-    let trimmed = input.trim();
-    if trimmed.is_empty() {
+    let typed: String = input.chars().filter(|c| !is_bidi_control(*c)).collect();
+    let typed = typed.trim();
+    if typed.is_empty() {
         return "about:blank".to_string();
     }
 
+    // 0. Keyword search: "w rust" -> Wikipedia, "yt cats" -> YouTube
+    if let Some((keyword, rest)) = typed.split_once(char::is_whitespace) {
+        let rest = rest.trim();
+        if !rest.is_empty() {
+            if let Some(engine) = settings.engine_for_keyword(&fold_ime_input(keyword)) {
+                return engine.query_url(rest);
+            }
+        }
+    }
+
+    let folded = fold_ime_input(typed);
+    let trimmed = folded.as_str();
+
     // 1. Force HTTP for implicit localhost/IP (if no scheme present)
     let has_scheme_separator = trimmed.contains("://");
     let is_localhost = trimmed.starts_with("localhost") || trimmed.starts_with("127.0.0.1");
@@ -43,7 +81,7 @@ pub fn smart_parse_url(input: &str, settings: &Settings) -> String {
 
     // 3. Heuristic: Dot implies domain? -> Try HTTPS (or HTTP if https_only is false)
     // (Exclude spaces which imply search)
-    if !trimmed.contains(' ') && trimmed.contains('.') && !trimmed.ends_with('.') {
+    if !trimmed.contains(char::is_whitespace) && trimmed.contains('.') && !trimmed.ends_with('.') {
         let scheme = if settings.https_only { "https" } else { "http" };
         let candidate = format!("{}://{}", scheme, trimmed);
         if let Ok(u) = Url::parse(&candidate) {
@@ -54,7 +92,7 @@ pub fn smart_parse_url(input: &str, settings: &Settings) -> String {
     }
 
     // 4. Fallback to configured Search Engine
-    settings.search_engine.query_url(trimmed)
+    settings.default_engine().query_url(typed)
 }
 
 /// Guess the resource type based on URL extension (for adblock engine).
@@ -139,13 +177,79 @@ mod tests {
     #[test]
     fn test_google_search_engine() {
         let mut settings = Settings::default();
-        settings.search_engine = SearchEngine::Google;
+        settings.search_engine = "Google".to_string();
         assert_eq!(
             smart_parse_url("test query", &settings),
             "https://google.com/search?q=test%20query"
         );
     }
 
+    // Test keyword bangs routing to the matching engine
+    #[rstest]
+    #[case("w rust", "https://en.wikipedia.org/wiki/Special:Search?search=rust")]
+    #[case("yt cats", "https://www.youtube.com/results?search_query=cats")]
+    #[case("YT funny cats", "https://www.youtube.com/results?search_query=funny%20cats")]
+    // Unknown keyword or bare keyword falls back to the default engine
+    #[case("zz cats", "https://duckduckgo.com/?q=zz%20cats")]
+    #[case("w", "https://duckduckgo.com/?q=w")]
+    fn test_keyword_search(#[case] input: &str, #[case] expected: &str) {
+        let settings = Settings::default();
+        assert_eq!(smart_parse_url(input, &settings), expected);
+    }
+
+    #[test]
+    fn test_custom_search_engine() {
+        let mut settings = Settings::default();
+        settings
+            .add_search_engine(SearchEngine::new("Docs.rs", "rs", "https://docs.rs/releases/search?query=%s"))
+            .unwrap();
+        assert_eq!(
+            smart_parse_url("rs serde json", &settings),
+            "https://docs.rs/releases/search?query=serde%20json"
+        );
+
+        // Duplicate keyword and missing placeholder are rejected
+        assert!(settings.add_search_engine(SearchEngine::new("Other", "rs", "https://x.com/?q=%s")).is_err());
+        assert!(settings.add_search_engine(SearchEngine::new("Bad", "bad", "https://x.com/")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_default_engine_falls_back() {
+        let mut settings = Settings::default();
+        settings.search_engine = "Removed Engine".to_string();
+        assert_eq!(smart_parse_url("hello world", &settings), "https://duckduckgo.com/?q=hello%20world");
+    }
+
+    // IME and non-Latin input
+    #[rstest]
+    // IDN: Unicode hostnames -> punycode
+    #[case("münchen.de", "https://xn--mnchen-3ya.de/")]
+    #[case("https://bücher.example/straße", "https://xn--bcher-kva.example/stra%C3%9Fe")]
+    #[case("пример.рф", "https://xn--e1afmkfd.xn--p1ai/")]
+    #[case("日本.jp/パス", "https://xn--wgv71a.jp/%E3%83%91%E3%82%B9")]
+    #[case("例え.テスト", "https://xn--r8jz45g.xn--zckzah/")]
+    // Full-width characters and ideographic punctuation from CJK IMEs
+    #[case("ｅｘａｍｐｌｅ．ｃｏｍ", "https://example.com/")]
+    #[case("例え。テスト", "https://xn--r8jz45g.xn--zckzah/")]
+    #[case("ｈｔｔｐｓ：／／ｅｘａｍｐｌｅ．ｃｏｍ／ａ", "https://example.com/a")]
+    #[case("ｌｏｃａｌｈｏｓｔ：３０００", "http://localhost:3000/")]
+    #[case("１２７．０．０．１", "http://127.0.0.1/")]
+    // Right-to-left: logical order is what matters; stray bidi marks are dropped
+    #[case("مثال.إختبار", "https://xn--mgbh0fb.xn--kgbechtv/")]
+    #[case("\u{200F}مثال.إختبار\u{200F}", "https://xn--mgbh0fb.xn--kgbechtv/")]
+    #[case("\u{202B}example.com\u{202C}", "https://example.com/")]
+    // Non-Latin text without a domain shape is still a search, as typed
+    #[case("東京　天気", "https://duckduckgo.com/?q=%E6%9D%B1%E4%BA%AC%E3%80%80%E5%A4%A9%E6%B0%97")]
+    #[case("ｒｕｓｔ　ｌａｎｇ", "https://duckduckgo.com/?q=%EF%BD%92%EF%BD%95%EF%BD%93%EF%BD%94%E3%80%80%EF%BD%8C%EF%BD%81%EF%BD%8E%EF%BD%87")]
+    #[case("שלום עולם", "https://duckduckgo.com/?q=%D7%A9%D7%9C%D7%95%D7%9D%20%D7%A2%D7%95%D7%9C%D7%9D")]
+    #[case("\u{200F}", "about:blank")]
+    // Keyword typed in full-width still routes to its engine
+    #[case("ｗ　rust", "https://en.wikipedia.org/wiki/Special:Search?search=rust")]
+    fn test_non_latin_input(#[case] input: &str, #[case] expected: &str) {
+        let settings = Settings::default();
+        assert_eq!(smart_parse_url(input, &settings), expected);
+    }
+
     #[test]
     fn test_https_only_off() {
         let mut settings = Settings::default();
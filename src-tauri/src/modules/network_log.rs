@@ -0,0 +1,417 @@
+// Per-tab network log with HAR export.
+//
+// Two sources fill the log:
+// - Windows/Linux: the interception path records every request as it's made
+//   (method, URL, type, announced size) and whether the browser refused it.
+// - Everywhere, `TIMING_SCRIPT` reports the page's Resource Timing entries
+//   (status, timings, transfer size) to `report_resource_timings`. An entry fills in
+//   the oldest matching request without timings, or is added on its own; on macOS
+//   that's the only source, so requests blocked by the content rules don't show up.
+//
+// Cross-origin resources without Timing-Allow-Origin report no sizes or phase
+// timings. The log is cleared when the tab starts loading a new page and holds
+// at most MAX_ENTRIES requests. Only the browser's UI may read it, and the HAR is
+// always saved where the user picks in a save dialog.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const MAX_ENTRIES: usize = 2000;
+
+/// Reports Resource Timing entries to `report_resource_timings`, in batches.
+pub const TIMING_SCRIPT: &str = r#"
+(function() {
+    if (!window.__TAURI__ || !window.PerformanceObserver || window !== window.top) return;
+    const invoke = window.__TAURI__.core.invoke;
+    let pending = [];
+    let timer = null;
+
+    const phase = (start, end) => (start > 0 && end >= start ? end - start : -1);
+    function entryOf(e) {
+        return {
+            url: e.name,
+            initiator: e.initiatorType || 'navigation',
+            started: Math.round(performance.timeOrigin + e.startTime),
+            duration: e.duration,
+            status: e.responseStatus || null,
+            protocol: e.nextHopProtocol || null,
+            transferSize: e.transferSize || null,
+            bodySize: e.decodedBodySize || null,
+            dns: phase(e.domainLookupStart, e.domainLookupEnd),
+            connect: phase(e.connectStart, e.connectEnd),
+            ssl: phase(e.secureConnectionStart, e.connectEnd),
+            wait: phase(e.requestStart, e.responseStart),
+            receive: phase(e.responseStart, e.responseEnd)
+        };
+    }
+    function flush() {
+        timer = null;
+        if (!pending.length) return;
+        const timings = pending;
+        pending = [];
+        invoke('report_resource_timings', { timings }).catch(() => {});
+    }
+    new PerformanceObserver(list => {
+        for (const e of list.getEntries()) {
+            if (!/^https?:/.test(e.name)) continue;
+            pending.push(entryOf(e));
+        }
+        if (pending.length && !timer) timer = setTimeout(flush, 1000);
+    }).observe({ entryTypes: ['navigation', 'resource'] });
+})();
+"#;
+
+/// A Resource Timing entry as reported by `TIMING_SCRIPT`. Times are in
+/// milliseconds, phases -1 when unknown.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTiming {
+    pub url: String,
+    pub initiator: String,
+    pub started: i64, // Unix time
+    pub duration: f64,
+    pub status: Option<u16>,
+    pub protocol: Option<String>,
+    pub transfer_size: Option<u64>,
+    pub body_size: Option<u64>,
+    pub dns: f64,
+    pub connect: f64,
+    pub ssl: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkEntry {
+    pub url: String,
+    pub method: String,
+    pub request_type: String,
+    pub started: i64, // Unix time in milliseconds
+    pub size: Option<u64>,
+    pub blocked: bool,
+    pub timing: Option<ResourceTiming>,
+}
+
+impl NetworkEntry {
+    pub fn status(&self) -> Option<u16> {
+        if self.blocked {
+            return Some(403);
+        }
+        self.timing.as_ref().and_then(|t| t.status)
+    }
+}
+
+pub struct NetworkLogManager {
+    logs: Mutex<HashMap<String, VecDeque<NetworkEntry>>>, // webview label -> requests, oldest first
+}
+
+impl NetworkLogManager {
+    pub fn new() -> Self {
+        Self { logs: Mutex::new(HashMap::new()) }
+    }
+
+    fn push(log: &mut VecDeque<NetworkEntry>, entry: NetworkEntry) {
+        if log.len() >= MAX_ENTRIES {
+            log.pop_front();
+        }
+        log.push_back(entry);
+    }
+
+    /// Records a request from the interception path.
+    pub fn record_request(&self, webview_label: &str, url: &str, method: &str, request_type: &str, size: Option<u64>) {
+        let mut logs = self.logs.lock().unwrap();
+        Self::push(
+            logs.entry(webview_label.to_string()).or_default(),
+            NetworkEntry {
+                url: url.to_string(),
+                method: method.to_string(),
+                request_type: request_type.to_string(),
+                started: chrono::Utc::now().timestamp_millis(),
+                size,
+                blocked: false,
+                timing: None,
+            },
+        );
+    }
+
+    /// Flags the latest request for `url` as refused by the browser.
+    pub fn mark_blocked(&self, webview_label: &str, url: &str) {
+        if let Some(entry) =
+            self.logs.lock().unwrap().get_mut(webview_label).and_then(|log| log.iter_mut().rev().find(|e| e.url == url))
+        {
+            entry.blocked = true;
+        }
+    }
+
+    /// Attaches timings to the requests they belong to, adding the ones the
+    /// interception path didn't see.
+    pub fn record_timings(&self, webview_label: &str, timings: Vec<ResourceTiming>) {
+        let mut logs = self.logs.lock().unwrap();
+        let log = logs.entry(webview_label.to_string()).or_default();
+        for timing in timings {
+            if let Some(entry) = log.iter_mut().find(|e| e.url == timing.url && e.timing.is_none() && !e.blocked) {
+                entry.size = timing.transfer_size.or(entry.size);
+                entry.timing = Some(timing);
+                continue;
+            }
+            Self::push(
+                log,
+                NetworkEntry {
+                    url: timing.url.clone(),
+                    method: "GET".to_string(),
+                    request_type: timing.initiator.clone(),
+                    started: timing.started,
+                    size: timing.transfer_size,
+                    blocked: false,
+                    timing: Some(timing),
+                },
+            );
+        }
+    }
+
+    pub fn entries(&self, webview_label: &str) -> Vec<NetworkEntry> {
+        self.logs.lock().unwrap().get(webview_label).map(|log| log.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn clear_tab(&self, webview_label: &str) {
+        self.logs.lock().unwrap().remove(webview_label);
+    }
+}
+
+impl Default for NetworkLogManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn iso_time(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// A HAR 1.2 document for one page's log. Headers, cookies and bodies aren't
+/// recorded, so they're left empty.
+pub fn to_har(entries: &[NetworkEntry], page_url: &str, page_title: &str) -> serde_json::Value {
+    let page_started = entries.iter().map(|e| e.started).min().unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    let har_entries: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|entry| {
+            let timing = entry.timing.as_ref();
+            let http_version = timing.and_then(|t| t.protocol.clone()).unwrap_or_default();
+            let query: Vec<serde_json::Value> = Url::parse(&entry.url)
+                .map(|url| {
+                    url.query_pairs().map(|(name, value)| serde_json::json!({ "name": name, "value": value })).collect()
+                })
+                .unwrap_or_default();
+            serde_json::json!({
+                "pageref": "page_1",
+                "startedDateTime": iso_time(timing.map_or(entry.started, |t| t.started)),
+                "time": timing.map_or(0.0, |t| t.duration),
+                "request": {
+                    "method": entry.method,
+                    "url": entry.url,
+                    "httpVersion": http_version,
+                    "cookies": [],
+                    "headers": [],
+                    "queryString": query,
+                    "headersSize": -1,
+                    "bodySize": -1,
+                },
+                "response": {
+                    "status": entry.status().unwrap_or(0),
+                    "statusText": if entry.blocked { "Blocked" } else { "" },
+                    "httpVersion": http_version,
+                    "cookies": [],
+                    "headers": [],
+                    "content": {
+                        "size": timing.and_then(|t| t.body_size).unwrap_or(0),
+                        "mimeType": "",
+                    },
+                    "redirectURL": "",
+                    "headersSize": -1,
+                    "bodySize": entry.size.map_or(-1, |s| s as i64),
+                },
+                "cache": {},
+                "timings": {
+                    "blocked": -1,
+                    "dns": timing.map_or(-1.0, |t| t.dns),
+                    "connect": timing.map_or(-1.0, |t| t.connect),
+                    "ssl": timing.map_or(-1.0, |t| t.ssl),
+                    "send": 0,
+                    "wait": timing.map_or(0.0, |t| t.wait.max(0.0)),
+                    "receive": timing.map_or(0.0, |t| t.receive.max(0.0)),
+                },
+                "_resourceType": entry.request_type,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "Sovereign Browser", "version": env!("CARGO_PKG_VERSION") },
+            "pages": [{
+                "startedDateTime": iso_time(page_started),
+                "id": "page_1",
+                "title": if page_title.is_empty() { page_url } else { page_title },
+                "pageTimings": {},
+            }],
+            "entries": har_entries,
+        }
+    })
+}
+
+/// A tab's webview label, URL and title.
+fn tab_of(state: &AppState, tab_id: &str) -> Result<(String, String, String), BrowserError> {
+    state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| (t.webview_label.clone(), t.url.clone(), t.title.clone()))
+        .ok_or_else(|| BrowserError::tab_not_found(tab_id))
+}
+
+/// Writes a tab's log to `path` as HAR.
+pub fn write_har(state: &AppState, tab_id: &str, path: PathBuf) -> Result<(), BrowserError> {
+    let (label, url, title) = tab_of(state, tab_id)?;
+    let har = to_har(&state.network_log.entries(&label), &url, &title);
+    let json = serde_json::to_string_pretty(&har).map_err(|e| BrowserError::Internal(e.to_string()))?;
+    fs::write(&path, json)?;
+    println!("[NetworkLog] Exported {} to {}", tab_id, path.display());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn report_resource_timings(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    timings: Vec<ResourceTiming>,
+) -> Result<(), BrowserError> {
+    state.network_log.record_timings(webview.label(), timings);
+    Ok(())
+}
+
+/// Asks where to save the tab's HAR, then writes it there.
+pub fn export(app: &AppHandle, tab_id: String) {
+    use tauri_plugin_dialog::DialogExt;
+
+    let h = app.clone();
+    app.dialog().file().add_filter("HAR", &["har"]).set_file_name("network-log.har").save_file(move |path| {
+        let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+            return;
+        };
+        if let Some(state) = h.try_state::<AppState>() {
+            if let Err(e) = write_har(&state, &tab_id, path) {
+                eprintln!("[NetworkLog] Failed to export HAR: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_network_log(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    tab_id: String,
+) -> Result<Vec<NetworkEntry>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let (label, _, _) = tab_of(&state, &tab_id)?;
+    Ok(state.network_log.entries(&label))
+}
+
+#[tauri::command]
+pub fn export_har(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    tab_id: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    tab_of(&state, &tab_id)?;
+    export(&app, tab_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(url: &str, status: u16) -> ResourceTiming {
+        ResourceTiming {
+            url: url.to_string(),
+            initiator: "script".to_string(),
+            started: 1_700_000_000_000,
+            duration: 120.5,
+            status: Some(status),
+            protocol: Some("h2".to_string()),
+            transfer_size: Some(2048),
+            body_size: Some(8000),
+            dns: -1.0,
+            connect: -1.0,
+            ssl: -1.0,
+            wait: 80.0,
+            receive: 20.0,
+        }
+    }
+
+    #[test]
+    fn test_timings_fill_in_requests() {
+        let log = NetworkLogManager::new();
+        log.record_request("tab", "https://a.example/app.js", "GET", "script", None);
+        log.record_request("tab", "https://ads.example/ad.js", "GET", "script", None);
+        log.mark_blocked("tab", "https://ads.example/ad.js");
+        log.record_timings(
+            "tab",
+            vec![timing("https://a.example/app.js", 200), timing("https://a.example/late.js", 404)],
+        );
+
+        let entries = log.entries("tab");
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].status(), Some(200));
+        assert_eq!(entries[0].size, Some(2048));
+        assert_eq!(entries[1].status(), Some(403));
+        assert_eq!(entries[2].url, "https://a.example/late.js");
+        assert_eq!(entries[2].status(), Some(404));
+
+        log.clear_tab("tab");
+        assert!(log.entries("tab").is_empty());
+    }
+
+    #[test]
+    fn test_log_is_bounded() {
+        let log = NetworkLogManager::new();
+        for i in 0..MAX_ENTRIES + 5 {
+            log.record_request("tab", &format!("https://a.example/{}", i), "GET", "image", Some(10));
+        }
+        let entries = log.entries("tab");
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].url, "https://a.example/5");
+    }
+
+    #[test]
+    fn test_to_har() {
+        let log = NetworkLogManager::new();
+        log.record_timings("tab", vec![timing("https://a.example/api?q=1&lang=en", 200)]);
+        let har = to_har(&log.entries("tab"), "https://a.example/", "");
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(har["log"]["version"], "1.2");
+        assert_eq!(har["log"]["pages"][0]["title"], "https://a.example/");
+        assert_eq!(entry["startedDateTime"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(entry["response"]["status"], 200);
+        assert_eq!(entry["response"]["bodySize"], 2048);
+        assert_eq!(entry["request"]["queryString"][1], serde_json::json!({ "name": "lang", "value": "en" }));
+        assert_eq!(entry["timings"]["wait"], 80.0);
+    }
+}
@@ -0,0 +1,186 @@
+// Network kill switch ("Go Offline").
+//
+// While it's on, tabs can't reach the network: web, WebSocket and FTP requests
+// are refused and app pages (new tab, settings, interstitials) keep working.
+// It's a switch for the session, for demos and focus time, and starts off at
+// every launch.
+//
+// This covers every webview showing web pages: tabs and the windows of
+// modules::browsing_webview (links, private and guest browsing, site apps), which
+// register through `apply`. Video pop-outs play straight from the network, so
+// they're closed on going offline and can't be opened until back online.
+//
+// Navigations are refused on every platform. Requests from pages already open:
+// on Windows the WebView2 request filter answers them 403 (see
+// channel_blocking::install_webview2_filter); on macOS a block-everything
+// content rule list is added to every webview and taken off again. On Linux the
+// switch is navigation-only: open pages can still reach the network, and the
+// offline chip says so.
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Webview};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::{https_only, video_popout};
+use crate::state::AppState;
+
+/// Schemes that go over the network.
+const NETWORK_SCHEMES: [&str; 5] = ["http", "https", "ws", "wss", "ftp"];
+
+pub const SAFARI_RULE_LIST_ID: &str = "SovereignOffline";
+
+/// Labels of the browsing webviews set up through `apply`; closed ones are
+/// dropped on the next switch.
+static WEBVIEWS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Blocks every network URL. App pages are tauri:// on macOS, so they aren't matched.
+pub const SAFARI_RULES: &str = r#"[{"trigger":{"url-filter":"^(https?|wss?|ftp)://"},"action":{"type":"block"}}]"#;
+
+/// Whether going offline cuts off `url`.
+pub fn is_network_url(url: &Url) -> bool {
+    NETWORK_SCHEMES.contains(&url.scheme()) && !https_only::is_app_page(url)
+}
+
+pub fn is_offline(state: &AppState) -> bool {
+    state.offline.load(Ordering::SeqCst)
+}
+
+/// Whether a request for `url` is refused right now.
+pub fn blocks(state: &AppState, url: &str) -> bool {
+    is_offline(state) && Url::parse(url).is_ok_and(|u| is_network_url(&u))
+}
+
+/// Navigation handler for browsing webviews.
+pub fn on_navigation(app: &AppHandle, url: &Url) -> bool {
+    let Some(state) = app.try_state::<AppState>() else {
+        return true;
+    };
+    if is_offline(&state) && is_network_url(url) {
+        println!("[Offline] Refused navigation to {}", url);
+        return false;
+    }
+    true
+}
+
+/// Sets up a new browsing webview for the current state.
+pub fn apply(webview: &Webview, state: &AppState) {
+    WEBVIEWS.lock().unwrap().insert(webview.label().to_string());
+    if is_offline(state) {
+        platform::set_rules(webview, true);
+    }
+}
+
+pub fn set(app: &AppHandle, state: &AppState, offline: bool) {
+    if state.offline.swap(offline, Ordering::SeqCst) == offline {
+        return;
+    }
+    println!("[Offline] {}", if offline { "Went offline" } else { "Back online" });
+    let webviews: Vec<Webview> = {
+        let mut labels = WEBVIEWS.lock().unwrap();
+        labels.retain(|label| app.get_webview(label).is_some());
+        labels.iter().filter_map(|label| app.get_webview(label)).collect()
+    };
+    for webview in &webviews {
+        platform::set_rules(webview, offline);
+    }
+    if offline {
+        video_popout::close_all(app);
+    }
+    let _ = app.emit("offline-changed", offline);
+}
+
+#[tauri::command]
+pub fn get_offline(state: tauri::State<AppState>) -> Result<bool, BrowserError> {
+    Ok(is_offline(&state))
+}
+
+#[tauri::command]
+pub fn set_offline(app: AppHandle, state: tauri::State<AppState>, offline: bool) -> Result<(), BrowserError> {
+    set(&app, &state, offline);
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{SAFARI_RULES, SAFARI_RULE_LIST_ID};
+    use block::ConcreteBlock;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+    use tauri::Webview;
+
+    /// Adds the block-everything rule list to the tab or takes it off.
+    pub fn set_rules(webview: &Webview, on: bool) {
+        let result = webview.with_webview(move |platform| unsafe {
+            let wk_webview = platform.inner() as *mut Object;
+            let config: *mut Object = msg_send![wk_webview, configuration];
+            let controller: *mut Object = msg_send![config, userContentController];
+            let store: *mut Object = msg_send![class!(WKContentRuleListStore), defaultStore];
+            let id = CString::new(SAFARI_RULE_LIST_ID).unwrap_or_default();
+            let identifier: *mut Object = msg_send![class!(NSString), stringWithUTF8String: id.as_ptr()];
+
+            let completion = ConcreteBlock::new(move |list: *mut Object, error: *mut Object| {
+                if list.is_null() || !error.is_null() {
+                    eprintln!("[Offline] Content rule list unavailable");
+                    return;
+                }
+                if on {
+                    let _: () = msg_send![controller, addContentRuleList: list];
+                } else {
+                    let _: () = msg_send![controller, removeContentRuleList: list];
+                }
+            })
+            .copy();
+            if on {
+                let rules = CString::new(SAFARI_RULES).unwrap_or_default();
+                let rules: *mut Object = msg_send![class!(NSString), stringWithUTF8String: rules.as_ptr()];
+                let _: () = msg_send![store, compileContentRuleListForIdentifier: identifier
+                                             encodedContentRuleList: rules
+                                             completionHandler: &*completion];
+            } else {
+                let _: () = msg_send![store, lookUpContentRuleListForIdentifier: identifier
+                                             completionHandler: &*completion];
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("[Offline] Failed to access webview: {}", e);
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use tauri::Webview;
+
+    // Windows checks the switch per request; Linux only stops navigations
+    pub fn set_rules(_webview: &Webview, _on: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://example.com/", true)]
+    #[case("http://example.com/a.png", true)]
+    #[case("wss://chat.example.com/socket", true)]
+    #[case("ftp://files.example.com/", true)]
+    #[case("tauri://localhost/new-tab.html", false)]
+    #[case("http://tauri.localhost/settings.html", false)]
+    #[case("about:blank", false)]
+    #[case("data:text/plain,hi", false)]
+    #[case("file:///tmp/a.html", false)]
+    fn test_is_network_url(#[case] url: &str, #[case] expected: bool) {
+        assert_eq!(is_network_url(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_safari_rules_are_json() {
+        let rules: serde_json::Value = serde_json::from_str(SAFARI_RULES).unwrap();
+        assert_eq!(rules[0]["action"]["type"], "block");
+    }
+}
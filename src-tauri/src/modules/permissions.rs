@@ -0,0 +1,113 @@
+// Site permission requests that wait for the user.
+//
+// A feature registers a request with `PermissionsManager::request` and awaits the
+// receiver it gets back; whatever UI asks the user answers with `respond`. The
+// answer is the user's choice (e.g. the capture source), or None for a denial.
+// Nothing is remembered: every request needs its own answer. Requests still
+// pending when their tab navigates or closes are denied.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    DisplayCapture,
+    FormSubmission, // Answered Some to send a form the audit flagged, see modules::form_audit
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionRequest {
+    pub id: u64,
+    #[serde(skip)]
+    pub webview_label: String,
+    pub origin: String,
+    pub kind: PermissionKind,
+}
+
+type Answer = Option<String>;
+
+pub struct PermissionsManager {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, (PermissionRequest, oneshot::Sender<Answer>)>>,
+}
+
+impl PermissionsManager {
+    pub fn new() -> Self {
+        Self { next_id: AtomicU64::new(1), pending: Mutex::new(HashMap::new()) }
+    }
+
+    /// Registers a request; the receiver yields the answer, or an error if the
+    /// request was dropped unanswered.
+    pub fn request(
+        &self,
+        webview_label: &str,
+        origin: &str,
+        kind: PermissionKind,
+    ) -> (PermissionRequest, oneshot::Receiver<Answer>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request =
+            PermissionRequest { id, webview_label: webview_label.to_string(), origin: origin.to_string(), kind };
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, (request.clone(), tx));
+        (request, rx)
+    }
+
+    pub fn get(&self, id: u64) -> Option<PermissionRequest> {
+        self.pending.lock().unwrap().get(&id).map(|(request, _)| request.clone())
+    }
+
+    /// Answers a request. Returns false if it's no longer pending.
+    pub fn respond(&self, id: u64, answer: Answer) -> bool {
+        match self.pending.lock().unwrap().remove(&id) {
+            Some((_, tx)) => tx.send(answer).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Denies the webview's pending requests.
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.pending.lock().unwrap().retain(|_, (request, _)| request.webview_label != webview_label);
+    }
+}
+
+impl Default for PermissionsManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let manager = PermissionsManager::new();
+        let (request, mut rx) =
+            manager.request("webview-tab-1", "https://meet.example", PermissionKind::DisplayCapture);
+        assert_eq!(manager.get(request.id).map(|r| r.origin), Some("https://meet.example".to_string()));
+        assert!(manager.respond(request.id, Some("screen:0".to_string())));
+        assert_eq!(rx.try_recv(), Ok(Some("screen:0".to_string())));
+        // Answered once
+        assert!(!manager.respond(request.id, None));
+        assert!(manager.get(request.id).is_none());
+    }
+
+    #[test]
+    fn test_forget_webview_denies() {
+        let manager = PermissionsManager::new();
+        let (first, mut first_rx) =
+            manager.request("webview-tab-1", "https://a.example", PermissionKind::DisplayCapture);
+        let (second, _second_rx) =
+            manager.request("webview-tab-2", "https://b.example", PermissionKind::DisplayCapture);
+        assert_ne!(first.id, second.id);
+        manager.forget_webview("webview-tab-1");
+        assert!(first_rx.try_recv().is_err());
+        assert!(manager.get(second.id).is_some());
+    }
+}
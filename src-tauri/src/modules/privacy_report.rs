@@ -0,0 +1,379 @@
+// Weekly privacy report.
+//
+// A report sums one week (Monday to Sunday, local time) of the daily counters
+// BlockStatsManager keeps: trackers blocked, HTTPS upgrades, popups blocked and
+// tracking parameters stripped. Those days are only kept for 90, so a finished
+// week's report is saved to privacy_reports.json the first time it's built and
+// read from there after that. Nothing leaves the device.
+//
+// With `privacy_report_notification` on, the week's summary ("2,314 trackers
+// blocked this week") is posted as an OS notification on Sunday evening, or at
+// the first check after that if the browser wasn't running. Notifications go
+// through the platform's own tool: osascript on macOS, notify-send on Linux.
+// Other platforms have no notifications yet.
+
+use chrono::{Datelike, Days, NaiveDate, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::block_stats::{DailyBlockStats, DomainCount};
+use crate::state::AppState;
+
+const REPORTS_FILE: &str = "privacy_reports.json";
+const KEPT_REPORTS: usize = 104; // Two years of weeks
+const TOP_TRACKERS: usize = 5;
+const NOTIFY_HOUR: u32 = 18; // On Sundays, local time
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacyReport {
+    pub week: String,  // ISO week, "2026-W42"
+    pub start: String, // Monday, "YYYY-MM-DD"
+    pub end: String,   // Sunday
+    pub trackers_blocked: u64,
+    pub https_upgrades: u64,
+    pub popups_blocked: u64,
+    pub params_stripped: u64,
+    pub saved_bytes: u64,
+    pub top_trackers: Vec<DomainCount>,
+    pub complete: bool, // False for the current week, counted so far
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredReports {
+    #[serde(default)]
+    reports: BTreeMap<String, PrivacyReport>, // By week
+    #[serde(default)]
+    last_notified: Option<String>, // Week
+}
+
+pub fn week_of(day: NaiveDate) -> String {
+    let week = day.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+/// The Monday of an ISO week ("2026-W42").
+fn week_start(week: &str) -> Option<NaiveDate> {
+    let (year, number) = week.split_once("-W")?;
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, number.parse().ok()?, Weekday::Mon)
+}
+
+fn summarize(start: NaiveDate, today: NaiveDate, daily: &BTreeMap<String, DailyBlockStats>) -> PrivacyReport {
+    let end = start + Days::new(6);
+    let mut report = PrivacyReport {
+        week: week_of(start),
+        start: start.format("%Y-%m-%d").to_string(),
+        end: end.format("%Y-%m-%d").to_string(),
+        trackers_blocked: 0,
+        https_upgrades: 0,
+        popups_blocked: 0,
+        params_stripped: 0,
+        saved_bytes: 0,
+        top_trackers: Vec::new(),
+        complete: end < today,
+    };
+    let mut domains: HashMap<&str, u64> = HashMap::new();
+    for (_, day) in daily.range(report.start.clone()..=report.end.clone()) {
+        report.trackers_blocked += day.blocked;
+        report.https_upgrades += day.https_upgrades;
+        report.popups_blocked += day.popups_blocked;
+        report.params_stripped += day.params_stripped;
+        report.saved_bytes += day.saved_bytes;
+        for (domain, count) in &day.domains {
+            *domains.entry(domain.as_str()).or_insert(0) += count;
+        }
+    }
+    report.top_trackers = domains
+        .into_iter()
+        .filter(|(domain, _)| !domain.is_empty())
+        .map(|(domain, blocked)| DomainCount { domain: domain.to_string(), blocked })
+        .collect();
+    report.top_trackers.sort_by(|a, b| b.blocked.cmp(&a.blocked).then_with(|| a.domain.cmp(&b.domain)));
+    report.top_trackers.truncate(TOP_TRACKERS);
+    report
+}
+
+/// The week whose summary is due at `hour` on `today`, unless it was already posted.
+fn due_week(today: NaiveDate, hour: u32, last_notified: Option<&str>) -> Option<String> {
+    let week = if today.weekday() == Weekday::Sun && hour >= NOTIFY_HOUR {
+        week_of(today)
+    } else {
+        week_of(today - Days::new(7))
+    };
+    // "YYYY-Www" sorts by date
+    match last_notified {
+        Some(last) if last >= week.as_str() => None,
+        _ => Some(week),
+    }
+}
+
+/// "2314" -> "2,314"
+pub fn with_commas(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The notification's text for a report.
+pub fn summary(report: &PrivacyReport, today: NaiveDate) -> String {
+    let when = if week_of(today) == report.week { "this week" } else { "last week" };
+    let trackers = if report.trackers_blocked == 1 { "tracker" } else { "trackers" };
+    let mut text = format!("{} {} blocked {}", with_commas(report.trackers_blocked), trackers, when);
+    let mut also = Vec::new();
+    if report.https_upgrades > 0 {
+        also.push(format!("{} HTTPS upgrades", with_commas(report.https_upgrades)));
+    }
+    if report.popups_blocked > 0 {
+        also.push(format!("{} popups stopped", with_commas(report.popups_blocked)));
+    }
+    if report.params_stripped > 0 {
+        also.push(format!("{} tracking parameters removed", with_commas(report.params_stripped)));
+    }
+    if !also.is_empty() {
+        text.push_str(&format!(", plus {}", also.join(", ")));
+    }
+    text
+}
+
+pub struct PrivacyReportStore {
+    path: PathBuf,
+    stored: Mutex<StoredReports>,
+}
+
+impl PrivacyReportStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(REPORTS_FILE);
+        let stored = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, stored: Mutex::new(stored) }
+    }
+
+    fn save(&self, stored: &StoredReports) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(stored).unwrap_or_default()) {
+            eprintln!("[PrivacyReport] Failed to save: {}", e);
+        }
+    }
+
+    /// The report for `week`, saving it once the week is over.
+    pub fn report(
+        &self,
+        week: &str,
+        today: NaiveDate,
+        daily: &BTreeMap<String, DailyBlockStats>,
+    ) -> Result<PrivacyReport, String> {
+        let start = week_start(week).ok_or_else(|| format!("Not a week: {}", week))?;
+        if start > today {
+            return Err("That week hasn't started yet".to_string());
+        }
+        let mut stored = self.stored.lock().unwrap();
+        if let Some(saved) = stored.reports.get(&week_of(start)) {
+            return Ok(saved.clone());
+        }
+        let report = summarize(start, today, daily);
+        if report.complete {
+            stored.reports.insert(report.week.clone(), report.clone());
+            while stored.reports.len() > KEPT_REPORTS {
+                stored.reports.pop_first();
+            }
+            self.save(&stored);
+        }
+        Ok(report)
+    }
+
+    /// Weeks with a report, newest first.
+    pub fn weeks(&self, today: NaiveDate, daily: &BTreeMap<String, DailyBlockStats>) -> Vec<String> {
+        let mut weeks: Vec<String> = self.stored.lock().unwrap().reports.keys().cloned().collect();
+        weeks.push(week_of(today));
+        weeks.extend(daily.keys().filter_map(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()).map(week_of));
+        weeks.sort_unstable_by(|a, b| b.cmp(a));
+        weeks.dedup();
+        weeks
+    }
+
+    /// The report to post at `hour` on `today`, marked as posted. None if it isn't due.
+    fn take_due(
+        &self,
+        today: NaiveDate,
+        hour: u32,
+        daily: &BTreeMap<String, DailyBlockStats>,
+    ) -> Option<PrivacyReport> {
+        let week = {
+            let stored = self.stored.lock().unwrap();
+            due_week(today, hour, stored.last_notified.as_deref())?
+        };
+        let report = self.report(&week, today, daily).ok()?;
+        let mut stored = self.stored.lock().unwrap();
+        stored.last_notified = Some(week);
+        self.save(&stored);
+        Some(report)
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn notify(title: &str, body: &str) -> Result<(), String> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!("display notification {} with title {}", quote(body), quote(title));
+    let status = std::process::Command::new("osascript").arg("-e").arg(script).status().map_err(|e| e.to_string())?;
+    status.success().then_some(()).ok_or_else(|| format!("osascript exited with {}", status))
+}
+
+#[cfg(target_os = "linux")]
+fn notify(title: &str, body: &str) -> Result<(), String> {
+    let status = std::process::Command::new("notify-send")
+        .args(["--app-name", "Sovereign Browser", title, body])
+        .status()
+        .map_err(|e| e.to_string())?;
+    status.success().then_some(()).ok_or_else(|| format!("notify-send exited with {}", status))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn notify(_title: &str, _body: &str) -> Result<(), String> {
+    Err("Notifications aren't supported on this platform yet".to_string())
+}
+
+/// Posts the week's summary when it's due, if the user asked for it.
+pub fn spawn_notification_thread(app: AppHandle) {
+    std::thread::spawn(move || {
+        std::thread::sleep(STARTUP_DELAY);
+        loop {
+            if let Some(state) = app.try_state::<AppState>() {
+                if state.settings.read().unwrap().privacy_report_notification {
+                    let now = chrono::Local::now();
+                    let daily = state.block_stats.daily();
+                    if let Some(report) = state.privacy_reports.take_due(now.date_naive(), now.hour(), &daily) {
+                        let text = summary(&report, now.date_naive());
+                        println!("[PrivacyReport] {}", text);
+                        if let Err(e) = notify("Privacy Report", &text) {
+                            eprintln!("[PrivacyReport] Failed to notify: {}", e);
+                        }
+                    }
+                }
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+/// The report for `week` ("2026-W42"), this week's if None.
+#[tauri::command]
+pub fn get_privacy_report(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    week: Option<String>,
+) -> Result<PrivacyReport, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let today = chrono::Local::now().date_naive();
+    let week = week.unwrap_or_else(|| week_of(today));
+    state.privacy_reports.report(&week, today, &state.block_stats.daily()).map_err(BrowserError::InvalidInput)
+}
+
+#[tauri::command]
+pub fn list_privacy_report_weeks(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<String>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.privacy_reports.weeks(chrono::Local::now().date_naive(), &state.block_stats.daily()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn daily() -> BTreeMap<String, DailyBlockStats> {
+        let mut daily = BTreeMap::new();
+        let mut monday = DailyBlockStats { blocked: 2000, https_upgrades: 4, ..Default::default() };
+        monday.domains.insert("ads.example.com".to_string(), 1500);
+        monday.domains.insert("tracker.net".to_string(), 500);
+        daily.insert("2026-10-05".to_string(), monday);
+        daily.insert(
+            "2026-10-11".to_string(),
+            DailyBlockStats { blocked: 314, popups_blocked: 2, params_stripped: 7, ..Default::default() },
+        );
+        daily.insert("2026-10-12".to_string(), DailyBlockStats { blocked: 9, ..Default::default() });
+        daily
+    }
+
+    #[rstest]
+    #[case("2026-10-05", "2026-W41")]
+    #[case("2026-10-11", "2026-W41")]
+    #[case("2026-10-12", "2026-W42")]
+    #[case("2027-01-01", "2026-W53")]
+    fn test_week_of(#[case] day: &str, #[case] expected: &str) {
+        assert_eq!(week_of(date(day)), expected);
+        assert!(week_start(expected).unwrap() <= date(day));
+    }
+
+    #[rstest]
+    #[case(0, "0")]
+    #[case(999, "999")]
+    #[case(2314, "2,314")]
+    #[case(1234567, "1,234,567")]
+    fn test_with_commas(#[case] n: u64, #[case] expected: &str) {
+        assert_eq!(with_commas(n), expected);
+    }
+
+    #[test]
+    fn test_summarize_week() {
+        let report = summarize(date("2026-10-05"), date("2026-10-16"), &daily());
+        assert_eq!((report.start.as_str(), report.end.as_str()), ("2026-10-05", "2026-10-11"));
+        assert_eq!(report.trackers_blocked, 2314);
+        assert_eq!((report.https_upgrades, report.popups_blocked, report.params_stripped), (4, 2, 7));
+        assert_eq!(report.top_trackers[0], DomainCount { domain: "ads.example.com".to_string(), blocked: 1500 });
+        assert!(report.complete);
+        assert_eq!(
+            summary(&report, date("2026-10-16")),
+            "2,314 trackers blocked last week, plus 4 HTTPS upgrades, 2 popups stopped, 7 tracking parameters removed"
+        );
+    }
+
+    #[rstest]
+    #[case("2026-10-16", 9, None, Some("2026-W41"))]
+    #[case("2026-10-16", 9, Some("2026-W41"), None)]
+    #[case("2026-10-18", 17, Some("2026-W41"), None)]
+    #[case("2026-10-18", 18, Some("2026-W41"), Some("2026-W42"))]
+    #[case("2027-01-04", 9, Some("2026-W52"), Some("2026-W53"))]
+    fn test_due_week(#[case] today: &str, #[case] hour: u32, #[case] last: Option<&str>, #[case] due: Option<&str>) {
+        assert_eq!(due_week(date(today), hour, last).as_deref(), due);
+    }
+
+    #[test]
+    fn test_finished_weeks_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PrivacyReportStore::new(dir.path().to_path_buf());
+        let today = date("2026-10-16");
+        let current = store.report("2026-W42", today, &daily()).unwrap();
+        assert!(!current.complete);
+        assert!(store.report("2026-W43", today, &daily()).is_err());
+        assert!(store.report("last week", today, &daily()).is_err());
+
+        assert!(store.take_due(today, 9, &daily()).is_some());
+        assert!(store.take_due(today, 10, &daily()).is_none());
+
+        // Once the days are gone the saved report still answers
+        let reopened = PrivacyReportStore::new(dir.path().to_path_buf());
+        let past = reopened.report("2026-W41", today, &BTreeMap::new()).unwrap();
+        assert_eq!(past.trackers_blocked, 2314);
+        assert_eq!(reopened.weeks(today, &BTreeMap::new()), vec!["2026-W42".to_string(), "2026-W41".to_string()]);
+        assert!(reopened.take_due(today, 11, &daily()).is_none());
+    }
+}
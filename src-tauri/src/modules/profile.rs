@@ -0,0 +1,125 @@
+// Profiles: separate sets of browsing data chosen at launch with `--profile <name>`.
+//
+// The default profile keeps its data where it always has, directly in the app
+// data directory. A named profile keeps its own settings, history, sessions,
+// closed tabs, stores and console log under `profiles/<name>/`, and its tabs get
+// their own cookies and site storage (a data directory on Windows and Linux, a
+// website data store on macOS). Downloaded caches that hold nothing personal
+// (the adblock engine, Safe Browsing lists) stay shared.
+//
+// The profile is picked once, before the app starts, and holds for the whole
+// run: a second launch with a different profile goes to the running one.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+const PROFILES_DIR: &str = "profiles";
+const WEBVIEW_DIR: &str = "webview";
+const MAX_NAME_LEN: usize = 64;
+
+static ACTIVE: OnceLock<String> = OnceLock::new();
+
+/// Names become directory names, so only letters, digits, `-` and `_`.
+pub fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!("Profile names must be 1 to {} characters", MAX_NAME_LEN));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Picks the profile for this run; only the first call counts.
+pub fn set_active(name: &str) {
+    if ACTIVE.set(name.to_string()).is_err() {
+        eprintln!("[Profile] Profile already chosen, ignoring '{}'", name);
+    }
+}
+
+/// The named profile in use, or None for the default one.
+pub fn active() -> Option<&'static str> {
+    ACTIVE.get().map(String::as_str)
+}
+
+fn profile_subdir(base: PathBuf, name: Option<&str>) -> PathBuf {
+    match name {
+        Some(name) => base.join(PROFILES_DIR).join(name),
+        None => base,
+    }
+}
+
+/// Where the active profile's stores live. Created if missing.
+pub fn data_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
+    let dir = profile_subdir(app.path().app_data_dir()?, active());
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("[Profile] Failed to create {}: {}", dir.display(), e);
+    }
+    Ok(dir)
+}
+
+pub fn log_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
+    Ok(profile_subdir(app.path().app_log_dir()?, active()))
+}
+
+/// Web data directory for a named profile's tabs (Windows, Linux); the default
+/// profile uses the webview's own.
+pub fn webview_data_dir(app: &AppHandle) -> Option<PathBuf> {
+    active()?;
+    data_dir(app).ok().map(|dir| dir.join(WEBVIEW_DIR))
+}
+
+/// WKWebsiteDataStore identifier for a named profile's tabs (macOS): a
+/// FNV-1a 128 hash of the name, so it's the same on every launch.
+pub fn store_identifier() -> Option<[u8; 16]> {
+    active().map(identifier_for)
+}
+
+pub fn identifier_for(name: &str) -> [u8; 16] {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let hash = name.bytes().fold(OFFSET, |hash, b| (hash ^ b as u128).wrapping_mul(PRIME));
+    hash.to_be_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("work", true)]
+    #[case(" Work_2-test ", true)]
+    #[case("", false)]
+    #[case("../evil", false)]
+    #[case("a/b", false)]
+    #[case("with space", false)]
+    fn test_validate_name(#[case] name: &str, #[case] ok: bool) {
+        assert_eq!(validate_name(name).is_ok(), ok);
+    }
+
+    #[test]
+    fn test_validate_name_trims_and_limits_length() {
+        assert_eq!(validate_name(" Work_2-test ").unwrap(), "Work_2-test");
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN)).is_ok());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_profile_subdir() {
+        let base = PathBuf::from("/data");
+        assert_eq!(profile_subdir(base.clone(), None), base);
+        assert_eq!(profile_subdir(base, Some("work")), PathBuf::from("/data/profiles/work"));
+    }
+
+    #[test]
+    fn test_identifier_is_stable_and_distinct() {
+        assert_eq!(identifier_for("work"), identifier_for("work"));
+        assert_ne!(identifier_for("work"), identifier_for("home"));
+        // FNV-1a 128 of the empty input is the offset basis
+        assert_eq!(u128::from_be_bytes(identifier_for("")), 0x6c62272e07bb014262b821756295c58d);
+    }
+}
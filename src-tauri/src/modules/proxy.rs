@@ -0,0 +1,509 @@
+// Proxy configuration (HTTP / SOCKS5) for tab traffic.
+//
+// Webviews only accept a bare proxy URL: no credentials and no bypass list. A
+// manual proxy without either is handed to the webview directly. Otherwise tabs
+// go through the local proxy in `doh`, which dials the upstream itself (adding
+// Proxy-Authorization or SOCKS5 auth) and connects bypassed hosts directly.
+// "none" also uses the local proxy, since a webview without a proxy URL follows
+// the system settings.
+//
+// The password is never written to settings.json nor sent to the frontend with
+// the rest of Settings. It goes to the login keychain on macOS (see
+// modules::keychain), elsewhere to a file of its own readable by this user only.
+// Settings sends it back only when the user types a new one, so a missing one
+// keeps the stored password for as long as the proxy and username stay the same.
+//
+// The browser's own requests (suggestions, list downloads, OpenSearch, ...) are
+// made with `http_client`, which sends them through the same proxy as the tabs.
+// A proxy that can't be used fails them rather than letting them go direct.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::keychain;
+use crate::state::AppState;
+
+const PASSWORD_FILE: &str = "proxy_password";
+const KEYCHAIN_ACCOUNT: &str = "proxy:password";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TEST_HOST: &str = "example.com";
+const TEST_PORT: u16 = 443;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    None,    // Always connect directly
+    #[default]
+    System,  // Whatever the OS is configured with
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyScheme {
+    #[default]
+    Http,
+    Socks5,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    #[serde(default)]
+    pub scheme: ProxyScheme,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>, // Read from settings.json only to move it out (see load_password)
+    #[serde(default)]
+    pub bypass: Vec<String>, // "localhost", "example.com" (and subdomains), "*.corp", "10.0.0.1"
+}
+
+/// How new webviews should be configured.
+#[derive(Debug, PartialEq)]
+pub enum WebviewProxy {
+    System,           // No proxy_url
+    Direct(url::Url), // Webview talks to the upstream itself
+    Local,            // Needs the local proxy
+}
+
+impl ProxySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode != ProxyMode::Manual {
+            return Ok(());
+        }
+        let host = self.host.trim();
+        if host.is_empty() || host.contains(char::is_whitespace) || host.contains('/') {
+            return Err("Proxy host is invalid".to_string());
+        }
+        if self.port == 0 {
+            return Err("Proxy port is required".to_string());
+        }
+        if self.password.is_some() && self.username.as_deref().unwrap_or("").is_empty() {
+            return Err("A proxy password needs a username".to_string());
+        }
+        Ok(())
+    }
+
+    /// Takes the current password when none was entered and it's still for the same proxy and user.
+    pub fn keep_password(&mut self, current: &ProxySettings) {
+        let same_proxy = self.scheme == current.scheme
+            && self.host.trim() == current.host.trim()
+            && self.port == current.port
+            && self.username == current.username;
+        if self.password.is_none() && same_proxy {
+            self.password = current.password.clone();
+        }
+    }
+
+    fn has_credentials(&self) -> bool {
+        self.username.as_deref().is_some_and(|u| !u.is_empty())
+    }
+
+    pub fn webview_proxy(&self) -> WebviewProxy {
+        match self.mode {
+            ProxyMode::System => WebviewProxy::System,
+            ProxyMode::None => WebviewProxy::Local,
+            ProxyMode::Manual if self.validate().is_err() => WebviewProxy::System,
+            ProxyMode::Manual if self.has_credentials() || !self.bypass.is_empty() => WebviewProxy::Local,
+            ProxyMode::Manual => {
+                let scheme = match self.scheme {
+                    ProxyScheme::Http => "http",
+                    ProxyScheme::Socks5 => "socks5",
+                };
+                url::Url::parse(&format!("{}://{}:{}", scheme, self.host.trim(), self.port))
+                    .map(WebviewProxy::Direct)
+                    .unwrap_or(WebviewProxy::System)
+            }
+        }
+    }
+
+    /// The upstream to dial for `host`, or None to connect directly.
+    pub fn upstream_for(&self, host: &str) -> Option<&ProxySettings> {
+        if self.mode != ProxyMode::Manual || self.validate().is_err() || self.is_bypassed(host) {
+            return None;
+        }
+        Some(self)
+    }
+
+    pub fn is_bypassed(&self, host: &str) -> bool {
+        let host = host.trim_matches(|c| c == '[' || c == ']').to_ascii_lowercase();
+        self.bypass.iter().any(|rule| {
+            let rule = rule.trim().trim_start_matches("*.").trim_start_matches('.').to_ascii_lowercase();
+            !rule.is_empty() && (host == rule || host.ends_with(&format!(".{}", rule)))
+        })
+    }
+
+    fn basic_auth(&self) -> Option<String> {
+        if !self.has_credentials() {
+            return None;
+        }
+        let credentials = format!(
+            "{}:{}",
+            self.username.as_deref().unwrap_or(""),
+            self.password.as_deref().unwrap_or("")
+        );
+        Some(format!("Basic {}", base64_encode(credentials.as_bytes())))
+    }
+}
+
+/// The stored proxy password, from the keychain or the profile's password file.
+pub fn load_password(profile_dir: &Path) -> Option<String> {
+    if keychain::is_available() {
+        return keychain::get(keychain::SERVICE, KEYCHAIN_ACCOUNT).unwrap_or_else(|e| {
+            eprintln!("[Proxy] Failed to read password: {}", e);
+            None
+        });
+    }
+    fs::read_to_string(profile_dir.join(PASSWORD_FILE)).ok().filter(|p| !p.is_empty())
+}
+
+/// Stores the proxy password, or removes it when None.
+pub fn store_password(profile_dir: &Path, password: Option<&str>) -> Result<(), String> {
+    if keychain::is_available() {
+        return match password {
+            Some(password) => keychain::set(keychain::SERVICE, KEYCHAIN_ACCOUNT, password),
+            None => keychain::delete(keychain::SERVICE, KEYCHAIN_ACCOUNT),
+        };
+    }
+    let path = profile_dir.join(PASSWORD_FILE);
+    let Some(password) = password else {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    };
+    fs::write(&path, password).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn own_proxy(state: &AppState) -> Result<Option<reqwest::Proxy>, BrowserError> {
+    state
+        .doh
+        .proxy_url()
+        .map(|url| reqwest::Proxy::all(url.as_str()))
+        .transpose()
+        .map_err(|e| BrowserError::Network(format!("The proxy can't be used: {}", e)))
+}
+
+/// A client builder for the browser's own requests, for callers that need more options.
+pub fn http_client_builder(state: &AppState, timeout: Duration) -> Result<reqwest::ClientBuilder, BrowserError> {
+    let builder = reqwest::Client::builder().timeout(timeout);
+    Ok(match own_proxy(state)? {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    })
+}
+
+/// A client for the browser's own requests, through the tabs' proxy.
+pub fn http_client(state: &AppState, timeout: Duration) -> Result<reqwest::Client, BrowserError> {
+    Ok(http_client_builder(state, timeout)?.build()?)
+}
+
+/// The same for background threads.
+pub fn blocking_http_client(state: &AppState, timeout: Duration) -> Result<reqwest::blocking::Client, BrowserError> {
+    let builder = reqwest::blocking::Client::builder().timeout(timeout);
+    let builder = match own_proxy(state)? {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    };
+    Ok(builder.build()?)
+}
+
+/// "host:port", bracketing IPv6 literals.
+fn authority(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Turns an origin-form request head back into absolute-form for an HTTP
+/// upstream, adding Proxy-Authorization when credentials are set.
+pub fn upstream_head(origin_head: &str, host: &str, port: u16, proxy: &ProxySettings) -> String {
+    let (request_line, rest) = origin_head.split_once("\r\n").unwrap_or((origin_head, "\r\n"));
+    let mut parts = request_line.splitn(3, ' ');
+    let (method, path, version) = (
+        parts.next().unwrap_or("GET"),
+        parts.next().unwrap_or("/"),
+        parts.next().unwrap_or("HTTP/1.1"),
+    );
+    let mut head = format!("{} http://{}{} {}\r\n", method, authority(host, port), path, version);
+    if let Some(auth) = proxy.basic_auth() {
+        head.push_str(&format!("Proxy-Authorization: {}\r\n", auth));
+    }
+    head.push_str(rest);
+    head
+}
+
+async fn read_response_head(stream: &mut TcpStream) -> Result<String, String> {
+    let mut buf = Vec::new();
+    let mut byte = [0u8; 1];
+    // Byte-at-a-time so nothing after the head is consumed; heads are short
+    while !buf.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte).await.map_err(|e| e.to_string())? == 0 {
+            return Err("Proxy closed the connection".to_string());
+        }
+        buf.push(byte[0]);
+        if buf.len() > 16 * 1024 {
+            return Err("Proxy response too large".to_string());
+        }
+    }
+    Ok(String::from_utf8_lossy(&buf).to_string())
+}
+
+async fn http_connect(proxy: &ProxySettings, host: &str, port: u16) -> Result<TcpStream, String> {
+    let mut stream = TcpStream::connect((proxy.host.trim(), proxy.port)).await.map_err(|e| e.to_string())?;
+    let target = authority(host, port);
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some(auth) = proxy.basic_auth() {
+        request.push_str(&format!("Proxy-Authorization: {}\r\n", auth));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await.map_err(|e| e.to_string())?;
+
+    let head = read_response_head(&mut stream).await?;
+    let status = head.split(' ').nth(1).unwrap_or("");
+    match status {
+        "200" => Ok(stream),
+        "407" => Err("Proxy authentication failed".to_string()),
+        _ => Err(format!("Proxy refused CONNECT: {}", head.lines().next().unwrap_or(""))),
+    }
+}
+
+async fn socks5_connect(proxy: &ProxySettings, host: &str, port: u16) -> Result<TcpStream, String> {
+    let mut stream = TcpStream::connect((proxy.host.trim(), proxy.port)).await.map_err(|e| e.to_string())?;
+    let io = |e: std::io::Error| e.to_string();
+
+    // Greeting: offer no-auth, plus username/password when configured (RFC 1928 / 1929)
+    let greeting: &[u8] = if proxy.has_credentials() { &[5, 2, 0, 2] } else { &[5, 1, 0] };
+    stream.write_all(greeting).await.map_err(io)?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await.map_err(io)?;
+    match choice[1] {
+        0 => {}
+        2 => {
+            let user = proxy.username.as_deref().unwrap_or("").as_bytes();
+            let pass = proxy.password.as_deref().unwrap_or("").as_bytes();
+            if user.len() > 255 || pass.len() > 255 {
+                return Err("SOCKS credentials are too long".to_string());
+            }
+            let mut auth = vec![1, user.len() as u8];
+            auth.extend_from_slice(user);
+            auth.push(pass.len() as u8);
+            auth.extend_from_slice(pass);
+            stream.write_all(&auth).await.map_err(io)?;
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await.map_err(io)?;
+            if status[1] != 0 {
+                return Err("Proxy authentication failed".to_string());
+            }
+        }
+        _ => return Err("SOCKS proxy offered no usable auth method".to_string()),
+    }
+
+    // Connect by name so DNS happens on the proxy side
+    let name = host.trim_matches(|c| c == '[' || c == ']').as_bytes();
+    if name.len() > 255 {
+        return Err("Host name too long".to_string());
+    }
+    let mut request = vec![5, 1, 0, 3, name.len() as u8];
+    request.extend_from_slice(name);
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await.map_err(io)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await.map_err(io)?;
+    if reply[1] != 0 {
+        return Err(format!("SOCKS connect failed (code {})", reply[1]));
+    }
+    // Skip the bound address
+    let addr_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await.map_err(io)?;
+            len[0] as usize
+        }
+        _ => return Err("Malformed SOCKS reply".to_string()),
+    };
+    let mut skip = vec![0u8; addr_len + 2];
+    stream.read_exact(&mut skip).await.map_err(io)?;
+    Ok(stream)
+}
+
+/// Opens a tunnel to host:port through the upstream proxy.
+pub async fn connect_via(proxy: &ProxySettings, host: &str, port: u16) -> Result<TcpStream, String> {
+    let dial = async {
+        match proxy.scheme {
+            ProxyScheme::Http => http_connect(proxy, host, port).await,
+            ProxyScheme::Socks5 => socks5_connect(proxy, host, port).await,
+        }
+    };
+    tokio::time::timeout(CONNECT_TIMEOUT, dial)
+        .await
+        .map_err(|_| "Timed out connecting through the proxy".to_string())?
+}
+
+/// Checks that a connection can be made with these settings (saved or not).
+/// Returns the connect time in milliseconds.
+#[tauri::command]
+pub async fn test_proxy(
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    mut proxy: ProxySettings,
+) -> Result<u64, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    proxy.keep_password(&state.settings.read().unwrap().proxy);
+    proxy.validate().map_err(BrowserError::InvalidInput)?;
+    let started = Instant::now();
+    match proxy.upstream_for(TEST_HOST) {
+        Some(upstream) => {
+            connect_via(upstream, TEST_HOST, TEST_PORT).await.map_err(BrowserError::Network)?;
+        }
+        None => {
+            tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((TEST_HOST, TEST_PORT)))
+                .await
+                .map_err(|_| BrowserError::Network("Timed out".to_string()))?
+                .map_err(|e| BrowserError::Network(e.to_string()))?;
+        }
+    }
+    let elapsed = started.elapsed().as_millis() as u64;
+    println!("[Proxy] Test via {:?} succeeded in {}ms", proxy.mode, elapsed);
+    Ok(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn manual(scheme: ProxyScheme) -> ProxySettings {
+        ProxySettings {
+            mode: ProxyMode::Manual,
+            scheme,
+            host: "proxy.local".to_string(),
+            port: 8080,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(ProxySettings::default().validate().is_ok());
+        assert!(manual(ProxyScheme::Http).validate().is_ok());
+        assert!(ProxySettings { port: 0, ..manual(ProxyScheme::Http) }.validate().is_err());
+        assert!(ProxySettings { host: " ".into(), ..manual(ProxyScheme::Http) }.validate().is_err());
+        assert!(ProxySettings { password: Some("x".into()), ..manual(ProxyScheme::Http) }.validate().is_err());
+    }
+
+    #[test]
+    fn test_password_is_kept_for_the_same_proxy_only() {
+        let current =
+            ProxySettings { username: Some("me".into()), password: Some("pass".into()), ..manual(ProxyScheme::Http) };
+        assert!(!serde_json::to_string(&current).unwrap().contains("password"));
+
+        let mut unchanged = ProxySettings { password: None, ..current.clone() };
+        unchanged.keep_password(&current);
+        assert_eq!(unchanged.password.as_deref(), Some("pass"));
+
+        let mut other_host = ProxySettings { host: "evil.example".into(), password: None, ..current.clone() };
+        other_host.keep_password(&current);
+        assert_eq!(other_host.password, None);
+
+        let mut new_password = ProxySettings { password: Some("new".into()), ..current.clone() };
+        new_password.keep_password(&current);
+        assert_eq!(new_password.password.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_webview_proxy() {
+        assert_eq!(ProxySettings::default().webview_proxy(), WebviewProxy::System);
+        assert_eq!(
+            ProxySettings { mode: ProxyMode::None, ..Default::default() }.webview_proxy(),
+            WebviewProxy::Local
+        );
+        assert_eq!(
+            manual(ProxyScheme::Socks5).webview_proxy(),
+            WebviewProxy::Direct(url::Url::parse("socks5://proxy.local:8080").unwrap())
+        );
+        let with_auth = ProxySettings { username: Some("me".into()), ..manual(ProxyScheme::Http) };
+        assert_eq!(with_auth.webview_proxy(), WebviewProxy::Local);
+        let with_bypass = ProxySettings { bypass: vec!["localhost".into()], ..manual(ProxyScheme::Http) };
+        assert_eq!(with_bypass.webview_proxy(), WebviewProxy::Local);
+    }
+
+    #[rstest]
+    #[case("localhost", true)]
+    #[case("intranet.corp", true)]
+    #[case("wiki.intranet.corp", true)]
+    #[case("example.com", true)]
+    #[case("www.example.com", true)]
+    #[case("notexample.com", false)]
+    #[case("[::1]", true)]
+    #[case("github.com", false)]
+    fn test_bypass(#[case] host: &str, #[case] bypassed: bool) {
+        let proxy = ProxySettings {
+            bypass: vec!["localhost".into(), "*.intranet.corp".into(), ".example.com".into(), "::1".into()],
+            ..manual(ProxyScheme::Http)
+        };
+        assert_eq!(proxy.is_bypassed(host), bypassed);
+        assert_eq!(proxy.upstream_for(host).is_none(), bypassed);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64_encode(b"a"), "YQ==");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b""), "");
+    }
+
+    #[test]
+    fn test_upstream_head() {
+        let proxy = ProxySettings {
+            username: Some("user".into()),
+            password: Some("pass".into()),
+            ..manual(ProxyScheme::Http)
+        };
+        let head = upstream_head("GET /a?b=1 HTTP/1.1\r\nHost: example.com\r\n\r\n", "example.com", 80, &proxy);
+        assert_eq!(
+            head,
+            "GET http://example.com:80/a?b=1 HTTP/1.1\r\nProxy-Authorization: Basic dXNlcjpwYXNz\r\nHost: example.com\r\n\r\n"
+        );
+    }
+}
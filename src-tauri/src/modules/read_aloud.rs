@@ -0,0 +1,577 @@
+// Read Aloud: a tab's article through the system voice.
+//
+// `read_aloud` runs `EXTRACT_SCRIPT` in the tab, which picks out the article text
+// the way a reader view would (the <article>/<main> element, else the block with
+// the most paragraph text) and sends it back through `speak_article`. The text is
+// split into sentences and spoken one at a time by a driver thread that owns the
+// voice: AVSpeechSynthesizer on macOS, SAPI on Windows and speech-dispatcher
+// (`spd-say`) on Linux. Each sentence is announced to the main window with a
+// `read-aloud-progress` event; play/pause/stop changes with `read-aloud-state`.
+//
+// One article is read at a time; starting another, navigating the tab or closing
+// it stops the current one. Speed changes apply from the next sentence. On Linux
+// pausing stops the sentence and resuming starts it over.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 2.0;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_TEXT_LEN: usize = 200_000;
+
+/// Pulls the article text out of the page and hands it to `speak_article`.
+pub const EXTRACT_SCRIPT: &str = r#"
+(function() {
+    if (!window.__TAURI__) return;
+    const SKIP = 'nav,aside,header,footer,figure,form,button,script,style,noscript,[aria-hidden="true"]';
+    const BLOCKS = 'h1,h2,h3,h4,h5,h6,p,li,blockquote,pre';
+
+    function textLength(el) {
+        return [...el.querySelectorAll('p')].reduce((n, p) => n + p.textContent.trim().length, 0);
+    }
+    let root = document.querySelector('article') || document.querySelector('main,[role="main"]');
+    if (!root || textLength(root) < 200) {
+        const candidates = [...document.querySelectorAll('p')].map(p => p.parentElement).filter(Boolean);
+        root = candidates.sort((a, b) => textLength(b) - textLength(a))[0] || document.body;
+    }
+
+    const parts = [];
+    for (const el of root.querySelectorAll(BLOCKS)) {
+        if (el.closest(SKIP) || el.parentElement.closest(BLOCKS)) continue;
+        if (!el.offsetParent && el !== document.body) continue; // Hidden
+        const text = el.innerText.replace(/\s+/g, ' ').trim();
+        if (text) parts.push(text);
+    }
+    const title = (document.querySelector('h1') || {}).innerText || document.title;
+    window.__TAURI__.core.invoke('speak_article', { title: title.trim(), text: parts.join('\n') }).catch(() => {});
+})();
+"#;
+
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "no", "fig", "inc", "ltd", "u.s",
+    "a.m", "p.m",
+];
+
+/// Splits article text into sentences to speak one by one. Paragraphs (lines)
+/// always end a sentence.
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    for paragraph in text.lines().map(str::trim).filter(|p| !p.is_empty()) {
+        let mut current = String::new();
+        let mut chars = paragraph.chars().peekable();
+        while let Some(c) = chars.next() {
+            current.push(c);
+            if !matches!(c, '.' | '!' | '?' | '…') {
+                continue;
+            }
+            // Closing quotes and brackets belong to the sentence they end
+            while let Some(&next) = chars.peek() {
+                if matches!(next, '"' | '\'' | '”' | '’' | ')' | ']') {
+                    current.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if !chars.peek().is_some_and(|next| next.is_whitespace()) {
+                continue; // 3.14, example.com
+            }
+            let last_word = current.trim_end_matches(['.', '"', '\'', '”', '’', ')', ']']);
+            let last_word = last_word.rsplit(char::is_whitespace).next().unwrap_or("").to_lowercase();
+            if c == '.' && (ABBREVIATIONS.contains(&last_word.as_str()) || last_word.chars().count() == 1) {
+                continue; // Mr. Smith, J. R. R. Tolkien
+            }
+            sentences.push(current.trim().to_string());
+            current.clear();
+        }
+        if !current.trim().is_empty() {
+            sentences.push(current.trim().to_string());
+        }
+    }
+    sentences
+}
+
+pub fn validate_speed(speed: f64) -> Result<f64, String> {
+    if speed.is_finite() && (MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        Ok(speed)
+    } else {
+        Err(format!("Speed must be between {} and {}", MIN_SPEED, MAX_SPEED))
+    }
+}
+
+/// The platform voice, owned by the driver thread.
+trait Voice {
+    fn start(&mut self, sentence: &str, speed: f64) -> Result<(), String>;
+    fn is_speaking(&mut self) -> bool;
+    fn pause(&mut self);
+    fn resume(&mut self);
+    fn stop(&mut self);
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Voice;
+    use objc::runtime::{Object, BOOL, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {}
+
+    const BOUNDARY_IMMEDIATE: i64 = 0;
+    const DEFAULT_RATE: f64 = 0.5; // AVSpeechUtteranceDefaultSpeechRate; the range is 0-1
+
+    pub struct SystemVoice(*mut Object);
+
+    impl SystemVoice {
+        pub fn new() -> Result<Self, String> {
+            let synthesizer: *mut Object = unsafe { msg_send![class!(AVSpeechSynthesizer), new] };
+            if synthesizer.is_null() {
+                return Err("Speech synthesis isn't available".to_string());
+            }
+            Ok(Self(synthesizer))
+        }
+    }
+
+    impl Voice for SystemVoice {
+        fn start(&mut self, sentence: &str, speed: f64) -> Result<(), String> {
+            let text = CString::new(sentence).map_err(|e| e.to_string())?;
+            unsafe {
+                let text: *mut Object = msg_send![class!(NSString), stringWithUTF8String: text.as_ptr()];
+                let utterance: *mut Object = msg_send![class!(AVSpeechUtterance), speechUtteranceWithString: text];
+                let rate = (DEFAULT_RATE * speed).clamp(0.0, 1.0) as f32;
+                let _: () = msg_send![utterance, setRate: rate];
+                let _: () = msg_send![self.0, speakUtterance: utterance];
+            }
+            Ok(())
+        }
+
+        fn is_speaking(&mut self) -> bool {
+            let speaking: BOOL = unsafe { msg_send![self.0, isSpeaking] };
+            speaking != NO
+        }
+
+        fn pause(&mut self) {
+            let _: BOOL = unsafe { msg_send![self.0, pauseSpeakingAtBoundary: BOUNDARY_IMMEDIATE] };
+        }
+
+        fn resume(&mut self) {
+            let _: BOOL = unsafe { msg_send![self.0, continueSpeaking] };
+        }
+
+        fn stop(&mut self) {
+            let _: BOOL = unsafe { msg_send![self.0, stopSpeakingAtBoundary: BOUNDARY_IMMEDIATE] };
+        }
+    }
+
+    impl Drop for SystemVoice {
+        fn drop(&mut self) {
+            let _: () = unsafe { msg_send![self.0, release] };
+        }
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::Voice;
+    use windows::core::PCWSTR;
+    use windows::Win32::Media::Speech::{
+        ISpVoice, SpVoice, SPF_ASYNC, SPF_IS_NOT_XML, SPF_PURGEBEFORESPEAK, SPRS_DONE, SPVOICESTATUS,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED};
+
+    pub struct SystemVoice {
+        voice: ISpVoice,
+        text: Vec<u16>, // Kept alive while SAPI speaks it
+    }
+
+    impl SystemVoice {
+        pub fn new() -> Result<Self, String> {
+            unsafe {
+                let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+                let voice: ISpVoice = CoCreateInstance(&SpVoice, None, CLSCTX_ALL).map_err(|e| e.to_string())?;
+                Ok(Self { voice, text: Vec::new() })
+            }
+        }
+    }
+
+    impl Voice for SystemVoice {
+        fn start(&mut self, sentence: &str, speed: f64) -> Result<(), String> {
+            // SAPI rates run from -10 to 10, +10 being about three times as fast
+            let rate = (10.0 * speed.ln() / 3f64.ln()).round().clamp(-10.0, 10.0) as i32;
+            self.text = sentence.encode_utf16().chain(std::iter::once(0)).collect();
+            unsafe {
+                self.voice.SetRate(rate).map_err(|e| e.to_string())?;
+                self.voice
+                    .Speak(PCWSTR(self.text.as_ptr()), (SPF_ASYNC.0 | SPF_IS_NOT_XML.0) as u32, None)
+                    .map_err(|e| e.to_string())
+            }
+        }
+
+        fn is_speaking(&mut self) -> bool {
+            let mut status = SPVOICESTATUS::default();
+            if unsafe { self.voice.GetStatus(&mut status, None) }.is_err() {
+                return false;
+            }
+            status.dwRunningState & SPRS_DONE.0 as u32 == 0
+        }
+
+        fn pause(&mut self) {
+            let _ = unsafe { self.voice.Pause() };
+        }
+
+        fn resume(&mut self) {
+            let _ = unsafe { self.voice.Resume() };
+        }
+
+        fn stop(&mut self) {
+            let _ = unsafe { self.voice.Speak(PCWSTR::null(), SPF_PURGEBEFORESPEAK.0 as u32, None) };
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::Voice;
+    use std::process::{Child, Command, Stdio};
+
+    /// speech-dispatcher has no pause for a single client, so pausing cancels the
+    /// sentence and resuming says it again.
+    pub struct SystemVoice {
+        child: Option<Child>,
+        current: Option<(String, f64)>,
+    }
+
+    impl SystemVoice {
+        pub fn new() -> Result<Self, String> {
+            Command::new("spd-say")
+                .arg("--version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map_err(|_| "Read Aloud needs speech-dispatcher (spd-say)".to_string())?;
+            Ok(Self { child: None, current: None })
+        }
+
+        fn cancel(&mut self) {
+            let _ = Command::new("spd-say").arg("--cancel").status();
+            if let Some(mut child) = self.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+    }
+
+    impl Voice for SystemVoice {
+        fn start(&mut self, sentence: &str, speed: f64) -> Result<(), String> {
+            // -100 to 100, 0 being the user's default rate
+            let rate = ((speed - 1.0) * 100.0).round().clamp(-100.0, 100.0) as i32;
+            self.current = Some((sentence.to_string(), speed));
+            let child = Command::new("spd-say")
+                .args(["--wait", "--rate", &rate.to_string(), "--"])
+                .arg(sentence)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|e| e.to_string())?;
+            self.child = Some(child);
+            Ok(())
+        }
+
+        fn is_speaking(&mut self) -> bool {
+            self.child.as_mut().is_some_and(|child| matches!(child.try_wait(), Ok(None)))
+        }
+
+        fn pause(&mut self) {
+            self.cancel();
+        }
+
+        fn resume(&mut self) {
+            if let Some((sentence, speed)) = self.current.clone() {
+                let _ = self.start(&sentence, speed);
+            }
+        }
+
+        fn stop(&mut self) {
+            self.cancel();
+            self.current = None;
+        }
+    }
+}
+
+enum Control {
+    Pause,
+    Resume,
+    Stop,
+    Speed(f64),
+}
+
+struct Session {
+    id: u64,
+    tab_id: String,
+    webview_label: String,
+    control: Sender<Control>,
+}
+
+pub struct ReadAloudManager {
+    next_id: AtomicU64,
+    requested: Mutex<HashSet<String>>, // Webview labels asked for their article
+    session: Mutex<Option<Session>>,
+    speed: Mutex<f64>,
+}
+
+impl ReadAloudManager {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            requested: Mutex::new(HashSet::new()),
+            session: Mutex::new(None),
+            speed: Mutex::new(1.0),
+        }
+    }
+
+    fn send(&self, control: Control) -> Result<(), BrowserError> {
+        let nothing = || BrowserError::NotFound("Nothing is being read".to_string());
+        let session = self.session.lock().unwrap();
+        session.as_ref().ok_or_else(nothing)?.control.send(control).map_err(|_| nothing())
+    }
+
+    /// Stops reading if it's this webview's article.
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.requested.lock().unwrap().remove(webview_label);
+        let mut session = self.session.lock().unwrap();
+        if session.as_ref().is_some_and(|s| s.webview_label == webview_label) {
+            if let Some(session) = session.take() {
+                let _ = session.control.send(Control::Stop);
+            }
+        }
+    }
+}
+
+impl Default for ReadAloudManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress<'a> {
+    tab_id: &'a str,
+    index: usize,
+    count: usize,
+    sentence: &'a str,
+}
+
+fn show_error(app: &AppHandle, message: &str) {
+    app.dialog().message(message).title("Read Aloud").kind(MessageDialogKind::Info).show(|_| {});
+}
+
+fn emit_state(app: &AppHandle, tab_id: &str, state: &str) {
+    let _ = app.emit_to("main", "read-aloud-state", serde_json::json!({ "tabId": tab_id, "state": state }));
+}
+
+/// Speaks the sentences until they run out or the session is stopped.
+fn drive(app: &AppHandle, tab_id: &str, sentences: &[String], mut speed: f64, control: Receiver<Control>) {
+    let mut voice = match platform::SystemVoice::new() {
+        Ok(voice) => voice,
+        Err(e) => {
+            eprintln!("[ReadAloud] {}", e);
+            show_error(app, &e);
+            emit_state(app, tab_id, "stopped");
+            return;
+        }
+    };
+    emit_state(app, tab_id, "playing");
+    let mut paused = false;
+    'sentences: for (index, sentence) in sentences.iter().enumerate() {
+        let progress = Progress { tab_id, index, count: sentences.len(), sentence };
+        let _ = app.emit_to("main", "read-aloud-progress", &progress);
+        if let Err(e) = voice.start(sentence, speed) {
+            eprintln!("[ReadAloud] Failed to speak: {}", e);
+            break;
+        }
+        loop {
+            match control.recv_timeout(POLL_INTERVAL) {
+                Ok(Control::Pause) if !paused => {
+                    voice.pause();
+                    paused = true;
+                    emit_state(app, tab_id, "paused");
+                }
+                Ok(Control::Resume) if paused => {
+                    voice.resume();
+                    paused = false;
+                    emit_state(app, tab_id, "playing");
+                }
+                Ok(Control::Speed(value)) => speed = value,
+                Ok(Control::Stop) | Err(RecvTimeoutError::Disconnected) => {
+                    voice.stop();
+                    break 'sentences;
+                }
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    if !paused && !voice.is_speaking() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    emit_state(app, tab_id, "stopped");
+}
+
+/// Starts reading a tab's article (the active tab by default).
+#[tauri::command]
+pub fn read_aloud(app: AppHandle, state: tauri::State<AppState>, tab_id: Option<String>) -> Result<(), BrowserError> {
+    let tab_id = tab_id
+        .or_else(|| state.active_tab_id.lock().unwrap().clone())
+        .ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+    let label = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| t.webview_label.clone())
+        .ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+    let webview =
+        app.get_webview(&label).ok_or_else(|| BrowserError::NotFound(format!("Webview not found: {}", label)))?;
+    state.read_aloud.requested.lock().unwrap().insert(label);
+    Ok(webview.eval(EXTRACT_SCRIPT)?)
+}
+
+/// The page's answer to `read_aloud`.
+#[tauri::command]
+pub fn speak_article(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    title: String,
+    mut text: String,
+) -> Result<(), BrowserError> {
+    let label = webview.label().to_string();
+    if !state.read_aloud.requested.lock().unwrap().remove(&label) {
+        return Err(BrowserError::NotAllowed("Not requested".to_string()));
+    }
+    let tab_id = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.webview_label == label)
+        .map(|t| t.id.clone())
+        .ok_or_else(|| BrowserError::NotFound("Tab not found".to_string()))?;
+    if text.len() > MAX_TEXT_LEN {
+        let mut end = MAX_TEXT_LEN;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    let mut sentences = split_sentences(&text);
+    if sentences.is_empty() {
+        show_error(&app, "There's no article text on this page to read.");
+        return Err(BrowserError::NotFound("No article text found".to_string()));
+    }
+    if !title.is_empty() && sentences[0] != title {
+        sentences.insert(0, title);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let id = state.read_aloud.next_id.fetch_add(1, Ordering::Relaxed);
+    let session = Session { id, tab_id: tab_id.clone(), webview_label: label, control: tx };
+    let previous = state.read_aloud.session.lock().unwrap().replace(session);
+    if let Some(previous) = previous {
+        let _ = previous.control.send(Control::Stop);
+    }
+    let speed = *state.read_aloud.speed.lock().unwrap();
+    println!("[ReadAloud] Reading {} sentences from {}", sentences.len(), tab_id);
+    std::thread::spawn(move || {
+        drive(&app, &tab_id, &sentences, speed, rx);
+        // Clear the session unless another one replaced it
+        if let Some(state) = app.try_state::<AppState>() {
+            let mut session = state.read_aloud.session.lock().unwrap();
+            if session.as_ref().is_some_and(|s| s.id == id) {
+                *session = None;
+            }
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_read_aloud(state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    state.read_aloud.send(Control::Pause)
+}
+
+#[tauri::command]
+pub fn resume_read_aloud(state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    state.read_aloud.send(Control::Resume)
+}
+
+#[tauri::command]
+pub fn stop_read_aloud(state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    let session = state.read_aloud.session.lock().unwrap().take();
+    if let Some(session) = session {
+        let _ = session.control.send(Control::Stop);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_read_aloud_speed(state: tauri::State<AppState>, speed: f64) -> Result<(), BrowserError> {
+    let speed = validate_speed(speed).map_err(BrowserError::InvalidInput)?;
+    *state.read_aloud.speed.lock().unwrap() = speed;
+    // Nothing being read is fine: the speed is kept for the next article
+    let _ = state.read_aloud.send(Control::Speed(speed));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_split_sentences() {
+        let text = "Dr. Smith arrived at 3.30 p.m. on Monday. Was it late? \"Yes!\" she said.\n\
+                    J. R. R. Tolkien wrote it… Visit example.com for more";
+        assert_eq!(
+            split_sentences(text),
+            vec![
+                "Dr. Smith arrived at 3.30 p.m. on Monday.",
+                "Was it late?",
+                "\"Yes!\"",
+                "she said.",
+                "J. R. R. Tolkien wrote it…",
+                "Visit example.com for more",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_sentences_skips_blank_lines() {
+        assert_eq!(split_sentences("\n  \nOne.\n\nTwo"), vec!["One.", "Two"]);
+        assert!(split_sentences("   ").is_empty());
+    }
+
+    #[rstest]
+    #[case(1.0, true)]
+    #[case(MIN_SPEED, true)]
+    #[case(MAX_SPEED, true)]
+    #[case(0.1, false)]
+    #[case(f64::INFINITY, false)]
+    fn test_validate_speed(#[case] speed: f64, #[case] valid: bool) {
+        assert_eq!(validate_speed(speed).is_ok(), valid);
+    }
+}
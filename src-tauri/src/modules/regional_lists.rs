@@ -0,0 +1,205 @@
+// Regional filter lists.
+//
+// EasyList and EasyPrivacy mostly cover English-language sites. The lists below
+// cover other languages; subscribing one just adds it to AdBlockManager's filter
+// lists, so `update_rules` fetches and merges it like any other subscription.
+// Lists for the OS locale's language are flagged as recommended in settings.
+
+use serde::Serialize;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+pub struct RegionalList {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub languages: &'static [&'static str], // ISO 639-1 codes
+}
+
+pub const REGIONAL_LISTS: &[RegionalList] = &[
+    RegionalList {
+        name: "EasyList China",
+        url: "https://easylist-downloads.adblockplus.org/easylistchina.txt",
+        languages: &["zh"],
+    },
+    RegionalList {
+        name: "EasyList Dutch",
+        url: "https://easylist-downloads.adblockplus.org/easylistdutch.txt",
+        languages: &["nl"],
+    },
+    RegionalList {
+        name: "EasyList Germany",
+        url: "https://easylist.to/easylistgermany/easylistgermany.txt",
+        languages: &["de"],
+    },
+    RegionalList {
+        name: "EasyList Italy",
+        url: "https://easylist-downloads.adblockplus.org/easylistitaly.txt",
+        languages: &["it"],
+    },
+    RegionalList {
+        name: "EasyList Polish",
+        url: "https://easylist-downloads.adblockplus.org/easylistpolish.txt",
+        languages: &["pl"],
+    },
+    RegionalList {
+        name: "EasyList Portuguese",
+        url: "https://easylist-downloads.adblockplus.org/easylistportuguese.txt",
+        languages: &["pt"],
+    },
+    RegionalList {
+        name: "EasyList Spanish",
+        url: "https://easylist-downloads.adblockplus.org/easylistspanish.txt",
+        languages: &["es"],
+    },
+    RegionalList {
+        name: "IndianList",
+        url: "https://easylist-downloads.adblockplus.org/indianlist.txt",
+        languages: &["hi", "bn", "gu", "mr", "pa", "ta", "te", "kn", "ml"],
+    },
+    RegionalList {
+        name: "Liste FR",
+        url: "https://easylist-downloads.adblockplus.org/liste_fr.txt",
+        languages: &["fr"],
+    },
+    RegionalList {
+        name: "RU AdList",
+        url: "https://easylist-downloads.adblockplus.org/advblock.txt",
+        languages: &["ru", "uk", "be"],
+    },
+    RegionalList {
+        name: "AdGuard Japanese",
+        url: "https://filters.adtidy.org/extension/ublock/filters/7.txt",
+        languages: &["ja"],
+    },
+    RegionalList {
+        name: "AdGuard Turkish",
+        url: "https://filters.adtidy.org/extension/ublock/filters/13.txt",
+        languages: &["tr"],
+    },
+];
+
+/// The language of a locale name ("de_DE.UTF-8", "pt-BR", "zh-Hans-CN").
+pub fn language_of(locale: &str) -> Option<String> {
+    let language = locale.split(['-', '_', '.', '@']).next()?.to_ascii_lowercase();
+    let valid = (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic());
+    valid.then_some(language)
+}
+
+/// The lists recommended for a language.
+pub fn recommended_for(language: &str) -> impl Iterator<Item = &'static RegionalList> + '_ {
+    REGIONAL_LISTS.iter().filter(move |l| l.languages.contains(&language))
+}
+
+#[cfg(target_os = "macos")]
+fn system_locale() -> Option<String> {
+    let output = std::process::Command::new("defaults").args(["read", "-g", "AppleLocale"]).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    let mut buffer = [0u16; 85]; // LOCALE_NAME_MAX_LENGTH
+    let len = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+    (len > 1).then(|| String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionalListInfo {
+    pub name: &'static str,
+    pub url: &'static str,
+    pub languages: &'static [&'static str],
+    pub subscribed: bool,
+    pub recommended: bool,
+}
+
+#[tauri::command]
+pub fn get_regional_lists(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<RegionalListInfo>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let language = system_locale().as_deref().and_then(language_of);
+    let subscribed = state.adblock.filter_lists();
+    Ok(REGIONAL_LISTS
+        .iter()
+        .map(|list| RegionalListInfo {
+            name: list.name,
+            url: list.url,
+            languages: list.languages,
+            subscribed: subscribed.iter().any(|l| l.url == list.url),
+            recommended: language.as_deref().is_some_and(|lang| list.languages.contains(&lang)),
+        })
+        .collect())
+}
+
+/// Subscribes to or drops a regional list, then rebuilds the engine.
+#[tauri::command]
+pub fn set_regional_list(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    subscribed: bool,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let list = REGIONAL_LISTS
+        .iter()
+        .find(|l| l.url == url)
+        .ok_or_else(|| BrowserError::NotFound(format!("Unknown regional list {}", url)))?;
+    let is_subscribed = state.adblock.filter_lists().iter().any(|l| l.url == list.url);
+    match (subscribed, is_subscribed) {
+        (true, false) => {
+            state.adblock.add_filter_list(list.url, Some(list.name))?;
+        }
+        (false, true) => state.adblock.remove_filter_list(list.url)?,
+        _ => return Ok(()),
+    }
+    state.adblock.spawn_update_thread();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adblock_manager::normalize_list_url;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("de_DE.UTF-8", Some("de"))]
+    #[case("pt-BR", Some("pt"))]
+    #[case("zh-Hans-CN", Some("zh"))]
+    #[case("fil_PH", Some("fil"))]
+    #[case("sr_RS@latin", Some("sr"))]
+    #[case("C", None)]
+    #[case("", None)]
+    fn test_language_of(#[case] locale: &str, #[case] expected: Option<&str>) {
+        assert_eq!(language_of(locale).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_recommended_for() {
+        let names: Vec<&str> = recommended_for("uk").map(|l| l.name).collect();
+        assert_eq!(names, vec!["RU AdList"]);
+        assert_eq!(recommended_for("en").count(), 0);
+    }
+
+    #[test]
+    fn test_catalog_urls_are_normalized() {
+        // Subscriptions are matched by URL, so catalog entries must already be in stored form
+        for list in REGIONAL_LISTS {
+            assert_eq!(normalize_list_url(list.url).as_deref(), Ok(list.url));
+        }
+    }
+}
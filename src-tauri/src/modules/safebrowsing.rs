@@ -0,0 +1,327 @@
+// Local malware and phishing protection.
+//
+// Public threat feeds (FEEDS) are downloaded whole into the app data dir and
+// refreshed every UPDATE_INTERVAL, so checking a navigation never sends the URL
+// anywhere. With `Settings.safe_browsing` on, a tab navigation to a listed URL is
+// held back and the tab shows ui/safebrowsing-interstitial.html instead, where the
+// user can go back or proceed anyway. Proceeding allows that URL for the rest of
+// the tab's life. On metered connections the scheduled feed downloads may be
+// deferred (see modules::list_updates).
+//
+// Feed entries are full URLs. One pointing at a site's root flags the whole host;
+// any other matches that exact URL, ignoring scheme and fragment. As with
+// HTTPS-Only, a subframe navigating to a listed URL takes the whole tab to the
+// warning.
+
+use arc_swap::ArcSwap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::{https_only, list_updates};
+use crate::state::AppState;
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const INTERSTITIAL_PAGE: &str = "safebrowsing-interstitial.html";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threat {
+    Malware,
+    Phishing,
+}
+
+struct Feed {
+    id: &'static str, // Cache file name
+    url: &'static str,
+    threat: Threat,
+}
+
+const FEEDS: &[Feed] = &[
+    Feed { id: "urlhaus", url: "https://urlhaus.abuse.ch/downloads/text_online/", threat: Threat::Malware },
+    Feed { id: "openphish", url: "https://openphish.com/feed.txt", threat: Threat::Phishing },
+];
+
+/// Host (with non-default port), path and query: what feed entries are matched on.
+fn url_key(url: &Url) -> Option<String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let mut key = url.host_str()?.trim_end_matches('.').to_string();
+    if let Some(port) = url.port() {
+        key.push_str(&format!(":{}", port));
+    }
+    key.push_str(url.path());
+    if let Some(query) = url.query() {
+        key.push('?');
+        key.push_str(query);
+    }
+    Some(key)
+}
+
+fn host_key(url: &Url) -> Option<String> {
+    Some(url.host_str()?.trim_end_matches('.').to_string())
+}
+
+#[derive(Debug, Default)]
+pub struct Blocklist {
+    hosts: HashMap<String, Threat>,
+    urls: HashMap<String, Threat>,
+}
+
+impl Blocklist {
+    /// Adds a feed: one URL per line, `#` comments.
+    pub fn add_feed(&mut self, text: &str, threat: Threat) {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Ok(url) = Url::parse(line) else {
+                continue;
+            };
+            if url.path() == "/" && url.query().is_none() {
+                if let Some(host) = host_key(&url) {
+                    self.hosts.insert(host, threat);
+                }
+            } else if let Some(key) = url_key(&url) {
+                self.urls.insert(key, threat);
+            }
+        }
+    }
+
+    pub fn lookup(&self, url: &Url) -> Option<Threat> {
+        let key = url_key(url)?;
+        host_key(url)
+            .and_then(|host| self.hosts.get(&host))
+            .or_else(|| self.urls.get(&key))
+            .copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.hosts.len() + self.urls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct SafeBrowsingManager {
+    dir: PathBuf,
+    blocklist: ArcSwap<Blocklist>,
+    allowed: Mutex<HashSet<(String, String)>>, // (webview label, URL key) the user proceeded to
+}
+
+impl SafeBrowsingManager {
+    pub fn new(app: &AppHandle) -> Self {
+        Self::with_dir(app.path().app_data_dir().expect("failed to get app data dir").join("safebrowsing"))
+    }
+
+    fn with_dir(dir: PathBuf) -> Self {
+        let manager = Self {
+            dir,
+            blocklist: ArcSwap::from_pointee(Blocklist::default()),
+            allowed: Mutex::new(HashSet::new()),
+        };
+        manager.reload();
+        manager
+    }
+
+    fn feed_path(&self, feed: &Feed) -> PathBuf {
+        self.dir.join(format!("{}.txt", feed.id))
+    }
+
+    /// Rebuilds the blocklist from the cached feeds.
+    fn reload(&self) {
+        let mut blocklist = Blocklist::default();
+        for feed in FEEDS {
+            if let Ok(text) = fs::read_to_string(self.feed_path(feed)) {
+                blocklist.add_feed(&text, feed.threat);
+            }
+        }
+        println!("[SafeBrowsing] Loaded {} entries", blocklist.len());
+        self.blocklist.store(Arc::new(blocklist));
+    }
+
+    fn is_stale(&self, feed: &Feed) -> bool {
+        fs::metadata(self.feed_path(feed))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map_or(true, |age| age >= UPDATE_INTERVAL)
+    }
+
+    /// Downloads the stale feeds; returns whether any changed.
+    fn update_feeds(&self, app: &AppHandle) -> bool {
+        let mut updated = false;
+        for feed in FEEDS.iter().filter(|f| self.is_stale(f)) {
+            let fetched = list_updates::fetch_text(app, feed.url).and_then(|text| {
+                fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+                fs::write(self.feed_path(feed), text).map_err(|e| e.to_string())
+            });
+            match fetched {
+                Ok(()) => updated = true,
+                Err(e) => eprintln!("[SafeBrowsing] Failed to update {}: {}", feed.id, e),
+            }
+        }
+        updated
+    }
+
+    /// Keeps the feeds fresh while protection is on.
+    pub fn spawn_update_thread(self: &Arc<Self>, app: AppHandle) {
+        let manager = self.clone();
+        std::thread::spawn(move || loop {
+            let enabled = app.try_state::<AppState>().map_or(true, |s| s.settings.read().unwrap().safe_browsing);
+            let due = enabled && FEEDS.iter().any(|f| manager.is_stale(f));
+            if due && list_updates::may_download(&app, "threat feed") && manager.update_feeds(&app) {
+                manager.reload();
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        });
+    }
+
+    pub fn lookup(&self, url: &Url) -> Option<Threat> {
+        self.blocklist.load().lookup(url)
+    }
+
+    /// Lets `webview_label` load `url` despite the warning.
+    pub fn allow(&self, webview_label: &str, url: &Url) {
+        if let Some(key) = url_key(url) {
+            self.allowed.lock().unwrap().insert((webview_label.to_string(), key));
+        }
+    }
+
+    fn is_allowed(&self, webview_label: &str, url: &Url) -> bool {
+        url_key(url).is_some_and(|key| self.allowed.lock().unwrap().contains(&(webview_label.to_string(), key)))
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.allowed.lock().unwrap().retain(|(label, _)| label != webview_label);
+    }
+
+    /// Drops what the user proceeded to in webviews that are gone. Returns how many.
+    pub fn prune(&self, live_labels: &HashSet<String>) -> usize {
+        let mut allowed = self.allowed.lock().unwrap();
+        let before = allowed.len();
+        allowed.retain(|(label, _)| live_labels.contains(label));
+        before - allowed.len()
+    }
+}
+
+pub fn interstitial_url(blocked: &Url, threat: Threat) -> Url {
+    let mut url = https_only::app_page_url(INTERSTITIAL_PAGE);
+    url.query_pairs_mut()
+        .append_pair("url", blocked.as_str())
+        .append_pair("threat", if threat == Threat::Malware { "malware" } else { "phishing" });
+    url
+}
+
+/// Navigation handler for tab webviews. Returns false when the tab is sent to the
+/// warning instead.
+pub fn on_navigation(app: &AppHandle, webview_label: &str, url: &Url) -> bool {
+    let Some(state) = app.try_state::<AppState>() else {
+        return true;
+    };
+    if !state.settings.read().unwrap().safe_browsing {
+        return true;
+    }
+    let Some(threat) = state.safe_browsing.lookup(url) else {
+        return true;
+    };
+    if state.safe_browsing.is_allowed(webview_label, url) {
+        return true;
+    }
+
+    println!("[SafeBrowsing] Blocked {:?} site: {}", threat, url);
+    let target = interstitial_url(url, threat);
+    let app = app.clone();
+    let label = webview_label.to_string();
+    tauri::async_runtime::spawn(async move {
+        if let Some(webview) = app.get_webview(&label) {
+            if let Err(e) = webview.navigate(target) {
+                eprintln!("[SafeBrowsing] Failed to show warning: {}", e);
+            }
+        }
+    });
+    false
+}
+
+/// Called by the warning page to load the flagged URL anyway.
+#[tauri::command]
+pub fn proceed_to_unsafe_site(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+) -> Result<(), BrowserError> {
+    // Only the warning (an app page) may lift the block
+    check_caller(&webview, Caller::AppPage)?;
+    let target = Url::parse(&url)?;
+    println!("[SafeBrowsing] Proceeding to flagged site: {}", target);
+    state.safe_browsing.allow(webview.label(), &target);
+    Ok(webview.navigate(target)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    const URLHAUS_SAMPLE: &str = "\
+################################################################
+# abuse.ch URLhaus Database Dump (TEXT)                        #
+################################################################
+http://198.51.100.7:8080/bins/x86
+https://files.example/payload.exe?id=1
+http://bad-host.example/
+";
+
+    #[rstest]
+    #[case("http://198.51.100.7:8080/bins/x86", Some(Threat::Malware))]
+    #[case("https://198.51.100.7:8080/bins/x86#top", Some(Threat::Malware))]
+    #[case("http://198.51.100.7/bins/x86", None)]
+    #[case("https://files.example/payload.exe?id=1", Some(Threat::Malware))]
+    #[case("https://files.example/payload.exe?id=2", None)]
+    #[case("https://files.example/", None)]
+    #[case("https://bad-host.example/any/page", Some(Threat::Malware))]
+    #[case("https://BAD-HOST.example./", Some(Threat::Malware))]
+    #[case("https://sub.bad-host.example/", None)]
+    #[case("file:///bad-host.example/", None)]
+    fn test_lookup(#[case] url: &str, #[case] expected: Option<Threat>) {
+        let mut blocklist = Blocklist::default();
+        blocklist.add_feed(URLHAUS_SAMPLE, Threat::Malware);
+        assert_eq!(blocklist.len(), 3);
+        assert_eq!(blocklist.lookup(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_reload_from_cache_and_allow() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("openphish.txt"), "https://login-bank.example/verify\n").unwrap();
+        let manager = SafeBrowsingManager::with_dir(dir.path().to_path_buf());
+        let phishing = Url::parse("https://login-bank.example/verify").unwrap();
+        assert_eq!(manager.lookup(&phishing), Some(Threat::Phishing));
+        assert!(!manager.is_stale(&FEEDS[1]));
+        assert!(manager.is_stale(&FEEDS[0]));
+
+        manager.allow("webview-tab-1", &phishing);
+        assert!(manager.is_allowed("webview-tab-1", &Url::parse("http://login-bank.example/verify").unwrap()));
+        assert!(!manager.is_allowed("webview-tab-2", &phishing));
+        manager.forget_webview("webview-tab-1");
+        assert!(!manager.is_allowed("webview-tab-1", &phishing));
+    }
+
+    #[test]
+    fn test_interstitial_reports_blocked_url() {
+        let blocked = Url::parse("https://login-bank.example/verify?x=1").unwrap();
+        let page = interstitial_url(&blocked, Threat::Phishing);
+        assert!(page.query_pairs().any(|(k, v)| k == "threat" && v == "phishing"));
+        assert_eq!(https_only::page_url(&page), blocked.as_str());
+    }
+}
@@ -0,0 +1,312 @@
+// Screen capture (getDisplayMedia).
+//
+// `PAGE_SCRIPT` puts a gate in front of `navigator.mediaDevices.getDisplayMedia`:
+// each call asks `request_display_capture`, which registers a request with the
+// PermissionsManager and opens ui/capture-picker.html listing the screens (from
+// the windowing system) and windows (CGWindowList on macOS, EnumWindows on Windows;
+// not listed on Linux, where the desktop portal picks) that could be shared. Only
+// a source the user picks there grants the call; closing the picker or letting it
+// time out denies it. A granted call goes on to the engine with a `displaySurface`
+// hint for the chosen kind, so engines with a picker of their own (WebView2, the
+// Linux portal) may still ask which exact source to share.
+//
+// WebKit only starts a capture from a user gesture, which waiting for the picker
+// can outlast; the page then gets the engine's error and can try again.
+
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::permissions::PermissionKind;
+use crate::state::AppState;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const PICKER_PAGE: &str = "capture-picker.html";
+const PICKER_WINDOW_PREFIX: &str = "capture-picker-";
+
+/// Routes getDisplayMedia through the source picker.
+pub const PAGE_SCRIPT: &str = r#"
+(function() {
+    const media = navigator.mediaDevices;
+    if (!media || typeof media.getDisplayMedia !== 'function' || !window.__TAURI__) return;
+    const invoke = window.__TAURI__.core.invoke;
+    const nativeGetDisplayMedia = media.getDisplayMedia.bind(media);
+
+    media.getDisplayMedia = async function(options = {}) {
+        const grant = await invoke('request_display_capture', { pageUrl: location.href }).catch(() => null);
+        if (!grant) throw new DOMException('Permission denied', 'NotAllowedError');
+        const video = options.video && typeof options.video === 'object' ? { ...options.video } : {};
+        video.displaySurface = grant === 'screen' ? 'monitor' : 'window';
+        return nativeGetDisplayMedia({ ...options, video });
+    };
+})();
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Screen,
+    Window,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureSource {
+    pub id: String, // "screen:<n>" or "window:<n>"
+    pub kind: SourceKind,
+    pub name: String,
+}
+
+/// The kind of source an id from `list_sources` names.
+pub fn kind_of(source_id: &str) -> Option<SourceKind> {
+    let (kind, index) = source_id.split_once(':')?;
+    index.parse::<usize>().ok()?;
+    match kind {
+        "screen" => Some(SourceKind::Screen),
+        "window" => Some(SourceKind::Window),
+        _ => None,
+    }
+}
+
+fn sources_from(screens: Vec<String>, windows: Vec<String>) -> Vec<CaptureSource> {
+    let screens = screens.into_iter().enumerate().map(|(i, name)| CaptureSource {
+        id: format!("screen:{}", i),
+        kind: SourceKind::Screen,
+        name: if name.is_empty() { format!("Screen {}", i + 1) } else { name },
+    });
+    let windows = windows
+        .into_iter()
+        .filter(|name| !name.trim().is_empty())
+        .enumerate()
+        .map(|(i, name)| CaptureSource { id: format!("window:{}", i), kind: SourceKind::Window, name });
+    screens.chain(windows).collect()
+}
+
+#[cfg(target_os = "macos")]
+fn window_names() -> Vec<String> {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::{CStr, CString};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGWindowListCopyWindowInfo(option: u32, relative_to_window: u32) -> *mut Object;
+    }
+    const ON_SCREEN_ONLY: u32 = 1 << 0;
+    const EXCLUDE_DESKTOP_ELEMENTS: u32 = 1 << 4;
+
+    unsafe fn value(dict: *mut Object, key: &str) -> *mut Object {
+        let key = CString::new(key).unwrap_or_default();
+        let key: *mut Object = msg_send![class!(NSString), stringWithUTF8String: key.as_ptr()];
+        msg_send![dict, objectForKey: key]
+    }
+    unsafe fn string(value: *mut Object) -> String {
+        if value.is_null() {
+            return String::new();
+        }
+        let utf8: *const std::os::raw::c_char = msg_send![value, UTF8String];
+        if utf8.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(utf8).to_string_lossy().into_owned()
+        }
+    }
+
+    let mut names = Vec::new();
+    unsafe {
+        // CFArray of CFDictionary, toll-free bridged to NSArray/NSDictionary
+        let windows = CGWindowListCopyWindowInfo(ON_SCREEN_ONLY | EXCLUDE_DESKTOP_ELEMENTS, 0);
+        if windows.is_null() {
+            return names;
+        }
+        let count: usize = msg_send![windows, count];
+        for i in 0..count {
+            let window: *mut Object = msg_send![windows, objectAtIndex: i];
+            let layer = value(window, "kCGWindowLayer");
+            let layer: i64 = if layer.is_null() { -1 } else { msg_send![layer, integerValue] };
+            if layer != 0 {
+                continue; // Menu bar, Dock, overlays
+            }
+            let owner = string(value(window, "kCGWindowOwnerName"));
+            // Titles need the Screen Recording permission; without it only the app is known
+            let title = string(value(window, "kCGWindowName"));
+            names.push(if title.is_empty() { owner } else { format!("{} - {}", owner, title) });
+        }
+        let _: () = msg_send![windows, release];
+    }
+    names
+}
+
+#[cfg(windows)]
+fn window_names() -> Vec<String> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{HWND, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindowTextW, IsWindowVisible};
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let names = &mut *(lparam.0 as *mut Vec<String>);
+        if IsWindowVisible(hwnd).as_bool() {
+            let mut buffer = [0u16; 256];
+            let len = GetWindowTextW(hwnd, &mut buffer);
+            if len > 0 {
+                names.push(String::from_utf16_lossy(&buffer[..len as usize]));
+            }
+        }
+        true.into()
+    }
+
+    let mut names: Vec<String> = Vec::new();
+    let _ = unsafe { EnumWindows(Some(collect), LPARAM(&mut names as *mut Vec<String> as isize)) };
+    names
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn window_names() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn list_sources(app: &AppHandle) -> Vec<CaptureSource> {
+    let screens = app
+        .available_monitors()
+        .map(|monitors| monitors.iter().map(|m| m.name().cloned().unwrap_or_default()).collect())
+        .unwrap_or_default();
+    sources_from(screens, window_names())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureRequestInfo {
+    pub origin: String,
+    pub sources: Vec<CaptureSource>,
+}
+
+/// Asks the user what the page may capture. Resolves to "screen" or "window" for
+/// the kind they picked, or fails when they didn't.
+#[tauri::command]
+pub async fn request_display_capture(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    page_url: String,
+) -> Result<SourceKind, BrowserError> {
+    let page = Url::parse(&page_url)?;
+    if !matches!(page.scheme(), "http" | "https") {
+        return Err(BrowserError::Unsupported("Screen capture is only available to web pages".to_string()));
+    }
+    let origin = page.origin().ascii_serialization();
+    let (request, answer) = state.permissions.request(webview.label(), &origin, PermissionKind::DisplayCapture);
+    println!("[ScreenCapture] {} asks to capture (request {})", origin, request.id);
+
+    let window_label = format!("{}{}", PICKER_WINDOW_PREFIX, request.id);
+    let picker = tauri::WebviewWindowBuilder::new(
+        &app,
+        &window_label,
+        tauri::WebviewUrl::App(format!("{}?request={}", PICKER_PAGE, request.id).into()),
+    )
+    .title("Share Your Screen")
+    .inner_size(420.0, 460.0)
+    .resizable(false)
+    .minimizable(false)
+    .maximizable(false)
+    .always_on_top(true)
+    .center()
+    .focused(true)
+    .build();
+    match picker {
+        Ok(win) => {
+            // Closing the picker is a denial
+            let h = app.clone();
+            let id = request.id;
+            win.on_window_event(move |event| {
+                if let tauri::WindowEvent::Destroyed = event {
+                    if let Some(state) = h.try_state::<AppState>() {
+                        state.permissions.respond(id, None);
+                    }
+                }
+            });
+        }
+        Err(e) => {
+            state.permissions.respond(request.id, None);
+            return Err(BrowserError::Webview(format!("Failed to open the source picker: {}", e)));
+        }
+    }
+
+    let choice = match tokio::time::timeout(REQUEST_TIMEOUT, answer).await {
+        Ok(Ok(choice)) => choice,
+        _ => {
+            state.permissions.respond(request.id, None);
+            None
+        }
+    };
+    if let Some(win) = app.get_webview_window(&window_label) {
+        if let Err(e) = win.close() {
+            eprintln!("[ScreenCapture] Failed to close the source picker: {}", e);
+        }
+    }
+    match choice.as_deref().and_then(kind_of) {
+        Some(kind) => {
+            println!("[ScreenCapture] Granted {:?} capture to {}", kind, origin);
+            Ok(kind)
+        }
+        None => Err(BrowserError::NotAllowed("Permission denied".to_string())),
+    }
+}
+
+/// The picker's request: who's asking and what could be shared.
+#[tauri::command]
+pub fn get_capture_request(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    request_id: u64,
+) -> Result<CaptureRequestInfo, BrowserError> {
+    let request =
+        state.permissions.get(request_id).ok_or_else(|| BrowserError::NotFound("No such request".to_string()))?;
+    Ok(CaptureRequestInfo { origin: request.origin, sources: list_sources(&app) })
+}
+
+/// The user's answer from the picker: a source id, or null to deny.
+#[tauri::command]
+pub fn respond_display_capture(
+    window: tauri::Window,
+    state: tauri::State<AppState>,
+    request_id: u64,
+    source_id: Option<String>,
+) -> Result<(), BrowserError> {
+    // Only the request's own picker may answer it, never the page that asked
+    if window.label() != format!("{}{}", PICKER_WINDOW_PREFIX, request_id) {
+        return Err(BrowserError::NotAllowed("Not allowed from this window".to_string()));
+    }
+    let answer = source_id.filter(|id| kind_of(id).is_some());
+    state.permissions.respond(request_id, answer);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("screen:0", Some(SourceKind::Screen))]
+    #[case("window:12", Some(SourceKind::Window))]
+    #[case("window:", None)]
+    #[case("tab:1", None)]
+    #[case("screen", None)]
+    fn test_kind_of(#[case] id: &str, #[case] expected: Option<SourceKind>) {
+        assert_eq!(kind_of(id), expected);
+    }
+
+    #[test]
+    fn test_sources_from() {
+        let sources = sources_from(
+            vec!["Built-in Display".to_string(), String::new()],
+            vec!["Editor - notes.txt".to_string(), " ".to_string(), "Mail".to_string()],
+        );
+        let ids: Vec<&str> = sources.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["screen:0", "screen:1", "window:0", "window:1"]);
+        assert_eq!(sources[1].name, "Screen 2");
+        assert_eq!(sources[3].name, "Mail");
+        assert!(sources.iter().all(|s| kind_of(&s.id) == Some(s.kind)));
+    }
+}
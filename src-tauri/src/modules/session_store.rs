@@ -0,0 +1,98 @@
+use crate::modules::profile;
+use crate::state::{AppState, Tab, TabMarker};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// The user-facing parts of an open tab that survive a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTab {
+    pub url: String,
+    pub title: String,
+    #[serde(default)]
+    pub custom_title: Option<String>,
+    pub favicon: Option<String>,
+    #[serde(default)]
+    pub marker: Option<TabMarker>,
+    #[serde(default)]
+    pub container: Option<String>,
+}
+
+impl From<&Tab> for SessionTab {
+    fn from(tab: &Tab) -> Self {
+        SessionTab {
+            url: tab.url.clone(),
+            title: tab.title.clone(),
+            custom_title: tab.custom_title.clone(),
+            favicon: tab.favicon.clone(),
+            marker: tab.marker.clone(),
+            container: tab.container.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionStore {
+    pub tabs: Vec<SessionTab>,
+    pub active_index: Option<usize>,
+}
+
+impl SessionStore {
+    fn get_path(app: &AppHandle) -> PathBuf {
+        profile::data_dir(app)
+            .expect("Failed to get app data dir")
+            .join("session.json")
+    }
+
+    /// Snapshot the current tab strip.
+    pub fn from_state(state: &AppState) -> Self {
+        let tabs = state.tabs.lock().unwrap();
+        let active = state.active_tab_id.lock().unwrap();
+        SessionStore {
+            tabs: tabs.iter().map(SessionTab::from).collect(),
+            active_index: active.as_ref().and_then(|id| tabs.iter().position(|t| &t.id == id)),
+        }
+    }
+
+    pub fn load(app: &AppHandle) -> Self {
+        let path = Self::get_path(app);
+
+        if path.exists() {
+            match fs::read_to_string(&path) {
+                Ok(json) => {
+                    match serde_json::from_str(&json) {
+                        Ok(store) => return store,
+                        Err(e) => eprintln!("Failed to parse session.json: {}", e),
+                    }
+                }
+                Err(e) => eprintln!("Failed to read session.json: {}", e),
+            }
+        }
+
+        SessionStore::default()
+    }
+
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = Self::get_path(app);
+        let tmp_path = path.with_extension("tmp");
+        let parent = path.parent().unwrap();
+
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        // Atomic write: tmp + rename (pattern from settings.rs)
+        fs::write(&tmp_path, json).map_err(|e| e.to_string())?;
+        fs::rename(tmp_path, path).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
+
+/// Persist the current session, logging instead of failing the caller.
+pub fn save_session(app: &AppHandle, state: &AppState) {
+    if let Err(e) = SessionStore::from_state(state).save(app) {
+        eprintln!("[Session] Failed to save session: {}", e);
+    }
+}
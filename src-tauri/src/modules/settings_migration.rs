@@ -0,0 +1,199 @@
+// Versioned settings.json.
+//
+// The file carries a `schema_version`. A change to `Settings` that older files
+// wouldn't survive (a field renamed or required, a type changed, a value moved)
+// bumps CURRENT_VERSION and adds a step to MIGRATIONS that rewrites the JSON of
+// the version before. A file is upgraded from its own version one step at a
+// time, so any older file goes through every change since it was written.
+// Fields that are only added need no step, just a serde default.
+//
+// Files without a version were written before it existed and are version 0.
+// Files from a newer build are read as they are and keep their version, so
+// that build doesn't migrate them again.
+//
+// Whatever still doesn't parse after upgrading, like a hand-edited value of the
+// wrong type, costs only that field: it gets its default, the rest is kept.
+
+use serde_json::{json, Map, Value};
+
+use crate::settings::Settings;
+
+pub const CURRENT_VERSION: u32 = 1;
+pub const VERSION_KEY: &str = "schema_version";
+
+/// Rewrites a settings object from one version to the next.
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades version n to n + 1.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [v0_fill_required];
+
+/// 0 → 1: the fields from the first release have no serde default, so a file
+/// missing any of them failed to parse and every setting fell back to default.
+fn v0_fill_required(settings: &mut Map<String, Value>) {
+    let defaults = Settings::default();
+    let required = [
+        ("homepage", json!(defaults.homepage)),
+        ("search_engine", json!(defaults.search_engine)),
+        ("block_trackers", json!(defaults.block_trackers)),
+        ("https_only", json!(defaults.https_only)),
+        ("clear_on_exit", json!(defaults.clear_on_exit)),
+        ("theme", json!(defaults.theme)),
+        ("compact_mode", json!(defaults.compact_mode)),
+    ];
+    for (key, value) in required {
+        settings.entry(key).or_insert(value);
+    }
+}
+
+pub fn version_of(settings: &Map<String, Value>) -> u32 {
+    settings.get(VERSION_KEY).and_then(Value::as_u64).map_or(0, |v| u32::try_from(v).unwrap_or(u32::MAX))
+}
+
+/// Runs the steps from the object's version up to CURRENT_VERSION.
+pub fn upgrade(mut settings: Map<String, Value>) -> Map<String, Value> {
+    let from = version_of(&settings);
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        migration(&mut settings);
+        println!("[Settings] Migrated settings from version {} to {}", version, version + 1);
+    }
+    settings.insert(VERSION_KEY.to_string(), json!(from.max(CURRENT_VERSION)));
+    settings
+}
+
+/// Settings from upgraded JSON. A field whose value doesn't fit is dropped
+/// and gets its default.
+pub fn parse(settings: Map<String, Value>) -> Settings {
+    if let Ok(parsed) = serde_json::from_value(Value::Object(settings.clone())) {
+        return parsed;
+    }
+    // Add the fields to the defaults one by one, keeping those that parse
+    let Ok(Value::Object(mut kept)) = serde_json::to_value(Settings::default()) else {
+        return Settings::default();
+    };
+    for (key, value) in settings {
+        let mut candidate = kept.clone();
+        candidate.insert(key.clone(), value);
+        if serde_json::from_value::<Settings>(Value::Object(candidate.clone())).is_ok() {
+            kept = candidate;
+        } else {
+            eprintln!("[Settings] Dropping unreadable setting '{}', using its default", key);
+        }
+    }
+    serde_json::from_value(Value::Object(kept)).unwrap_or_default()
+}
+
+/// Settings from a settings.json of any version.
+pub fn load(value: Value) -> Settings {
+    match value {
+        Value::Object(settings) => parse(upgrade(settings)),
+        _ => {
+            eprintln!("[Settings] Settings file isn't a JSON object, using defaults");
+            Settings::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("not an object"),
+        }
+    }
+
+    /// settings.json as the first release wrote it.
+    fn first_release() -> Value {
+        json!({
+            "homepage": "https://start.example/",
+            "search_engine": "Brave",
+            "block_trackers": false,
+            "https_only": true,
+            "clear_on_exit": true,
+            "theme": "light",
+            "compact_mode": true
+        })
+    }
+
+    #[test]
+    fn test_migrations_cover_every_version() {
+        assert_eq!(MIGRATIONS.len(), CURRENT_VERSION as usize);
+        assert_eq!(Settings::default().schema_version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_v0_fill_required_keeps_present_values() {
+        let mut settings = object(json!({ "homepage": "https://start.example/", "theme": "light" }));
+        v0_fill_required(&mut settings);
+        assert_eq!(settings["homepage"], "https://start.example/");
+        assert_eq!(settings["theme"], "light");
+        assert_eq!(settings["search_engine"], "DuckDuckGo");
+        assert_eq!(settings["block_trackers"], true);
+        assert_eq!(settings["compact_mode"], false);
+    }
+
+    #[test]
+    fn test_v0_file_missing_a_required_field_keeps_the_rest() {
+        let mut file = object(first_release());
+        file.remove("homepage");
+        let settings = load(Value::Object(file));
+        assert_eq!(settings.homepage, Settings::default().homepage);
+        assert_eq!(settings.search_engine, "Brave");
+        assert_eq!(settings.theme, "light");
+        assert!(!settings.block_trackers && settings.clear_on_exit && settings.compact_mode);
+        assert_eq!(settings.schema_version, CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_first_release_file_upgrades_to_current() {
+        let settings = load(first_release());
+        assert_eq!(settings.homepage, "https://start.example/");
+        assert_eq!(settings.default_engine().name, "Brave");
+        assert_eq!(settings.schema_version, CURRENT_VERSION);
+        // Fields added since then get their defaults
+        assert!(settings.pinch_zoom);
+    }
+
+    #[rstest]
+    #[case(json!({}), 0)]
+    #[case(json!({ "schema_version": 1 }), 1)]
+    #[case(json!({ "schema_version": "1" }), 0)]
+    #[case(json!({ "schema_version": 7 }), 7)]
+    fn test_version_of(#[case] settings: Value, #[case] expected: u32) {
+        assert_eq!(version_of(&object(settings)), expected);
+    }
+
+    #[test]
+    fn test_current_file_is_unchanged() {
+        let file = object(serde_json::to_value(Settings::default()).unwrap());
+        assert_eq!(upgrade(file.clone()), file);
+    }
+
+    #[test]
+    fn test_newer_file_keeps_its_version() {
+        let mut file = object(serde_json::to_value(Settings::default()).unwrap());
+        file.insert(VERSION_KEY.to_string(), json!(CURRENT_VERSION + 1));
+        file.insert("homepage".to_string(), json!("https://newer.example/"));
+        let settings = load(Value::Object(file));
+        assert_eq!(settings.schema_version, CURRENT_VERSION + 1);
+        assert_eq!(settings.homepage, "https://newer.example/");
+    }
+
+    #[test]
+    fn test_unreadable_field_falls_back_alone() {
+        let mut file = object(first_release());
+        file.insert("sidebar_width".to_string(), json!("wide"));
+        let settings = load(Value::Object(file));
+        assert_eq!(settings.sidebar_width, Settings::default().sidebar_width);
+        assert_eq!(settings.homepage, "https://start.example/");
+        assert_eq!(settings.theme, "light");
+    }
+
+    #[test]
+    fn test_non_object_gives_defaults() {
+        assert_eq!(load(json!([1, 2])).homepage, Settings::default().homepage);
+    }
+}
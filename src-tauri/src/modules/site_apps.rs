@@ -0,0 +1,327 @@
+// Sites installed as apps: a window of their own with no browser toolbar.
+//
+// `install_site_as_app` saves a start URL and a name, keyed by the site's
+// origin, and opens the app's `site-app-<id>` window. The window stays on that
+// origin: links and redirects anywhere else open in a tab of the main window
+// instead. It shares cookies with the browser, so you stay signed in, and gets
+// the same protections and proxy as a tab (see modules::browsing_webview). Installed
+// apps are kept in site_apps.json and listed in the native Apps menu, whose
+// items open (or focus) the app's window.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::menu::{MenuItemBuilder, MenuItemKind, PredefinedMenuItem};
+use tauri::{AppHandle, Emitter, LogicalSize, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{self, check_caller, Caller, DataStore};
+use crate::state::AppState;
+
+const SITE_APPS_FILE: &str = "site_apps.json";
+const WINDOW_PREFIX: &str = "site-app-";
+const MAX_NAME_LEN: usize = 80;
+pub const MENU_ID: &str = "apps_menu";
+pub const MENU_ITEM_PREFIX: &str = "site_app:";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteApp {
+    pub id: String,
+    pub name: String,
+    pub url: String,    // Where the window starts
+    pub origin: String, // Where it may go
+    pub installed_at: u64,
+}
+
+pub fn window_label(id: &str) -> String {
+    format!("{}{}", WINDOW_PREFIX, id)
+}
+
+/// Whether an app window on `origin` may load `url` itself.
+pub fn stays_in_app(origin: &str, url: &Url) -> bool {
+    match url.scheme() {
+        "http" | "https" => url.origin().ascii_serialization() == origin,
+        "about" | "blob" | "data" => true, // Blank and generated documents the page makes itself
+        _ => false,
+    }
+}
+
+fn parse_start_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| e.to_string())?;
+    match parsed.scheme() {
+        "http" | "https" if parsed.host_str().is_some() => Ok(parsed),
+        _ => Err("Only web pages can be installed as apps".to_string()),
+    }
+}
+
+pub struct SiteAppStore {
+    path: PathBuf,
+    apps: Mutex<Vec<SiteApp>>,
+}
+
+impl SiteAppStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(SITE_APPS_FILE);
+        let apps = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, apps: Mutex::new(apps) }
+    }
+
+    fn save(&self, apps: &[SiteApp]) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(apps).unwrap_or_default()) {
+            eprintln!("[SiteApps] Failed to save: {}", e);
+        }
+    }
+
+    pub fn list(&self) -> Vec<SiteApp> {
+        self.apps.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<SiteApp> {
+        self.apps.lock().unwrap().iter().find(|a| a.id == id).cloned()
+    }
+
+    /// Adds an app, or renames and re-points the one already installed for the origin.
+    pub fn install(&self, url: &str, name: &str) -> Result<SiteApp, String> {
+        let url = parse_start_url(url)?;
+        let origin = url.origin().ascii_serialization();
+        let name = match name.trim() {
+            "" => url.host_str().unwrap_or_default().trim_start_matches("www.").to_string(),
+            name => name.chars().take(MAX_NAME_LEN).collect(),
+        };
+
+        let mut apps = self.apps.lock().unwrap();
+        let app = match apps.iter_mut().find(|a| a.origin == origin) {
+            Some(existing) => {
+                existing.name = name;
+                existing.url = url.to_string();
+                existing.clone()
+            }
+            None => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                let app = SiteApp {
+                    id: format!("{:x}", now.as_nanos()),
+                    name,
+                    url: url.to_string(),
+                    origin,
+                    installed_at: now.as_secs(),
+                };
+                apps.push(app.clone());
+                app
+            }
+        };
+        apps.sort_by_key(|a| a.name.to_lowercase());
+        self.save(&apps);
+        Ok(app)
+    }
+
+    pub fn uninstall(&self, id: &str) -> bool {
+        let mut apps = self.apps.lock().unwrap();
+        let before = apps.len();
+        apps.retain(|a| a.id != id);
+        let removed = apps.len() != before;
+        if removed {
+            self.save(&apps);
+        }
+        removed
+    }
+}
+
+/// Opens the app's window, or focuses it if it's already open.
+pub fn open(app: &AppHandle, site_app: &SiteApp) -> Result<(), String> {
+    let label = window_label(&site_app.id);
+    if let Some(window) = app.get_window(&label) {
+        let _ = window.unminimize();
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    let url = parse_start_url(&site_app.url)?;
+    println!("[SiteApps] Opening {} ({})", site_app.name, site_app.origin);
+
+    let origin = site_app.origin.clone();
+    let handle = app.clone();
+    // Popups and target="_blank" links become browser tabs
+    let open_new = |app: &AppHandle, url: String| {
+        if let Ok(url) = Url::parse(&url) {
+            open_in_browser(app, &url);
+        }
+    };
+    let stay = move |url: &Url| {
+        if stays_in_app(&origin, url) {
+            return true;
+        }
+        open_in_browser(&handle, url);
+        false
+    };
+    let window = browsing_webview::open_window(app, &label, &site_app.name, url, DataStore::Profile, open_new, stay)?;
+    window.set_min_size(Some(LogicalSize::new(320.0, 240.0))).map_err(|e| e.to_string())
+}
+
+/// Hands a link that left the app to the main window, which opens it in a new tab.
+fn open_in_browser(app: &AppHandle, url: &Url) {
+    if !matches!(url.scheme(), "http" | "https") {
+        return;
+    }
+    let _ = app.emit_to("main", "request-open-url", url.to_string());
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_focus();
+    }
+}
+
+/// Refills the Apps menu from the installed apps.
+pub fn rebuild_menu(app: &AppHandle) -> Result<(), String> {
+    let Some(state) = app.try_state::<AppState>() else {
+        return Ok(());
+    };
+    let Some(MenuItemKind::Submenu(submenu)) = app.menu().and_then(|menu| menu.get(MENU_ID)) else {
+        return Ok(());
+    };
+    for item in submenu.items().map_err(|e| e.to_string())? {
+        submenu.remove(&item).map_err(|e| e.to_string())?;
+    }
+    let install =
+        MenuItemBuilder::with_id("install_site_app", "Install Site as App...").build(app).map_err(|e| e.to_string())?;
+    submenu.append(&install).map_err(|e| e.to_string())?;
+    let separator = PredefinedMenuItem::separator(app).map_err(|e| e.to_string())?;
+    submenu.append(&separator).map_err(|e| e.to_string())?;
+
+    let apps = state.site_apps.list();
+    if apps.is_empty() {
+        let placeholder = MenuItemBuilder::new("No Apps").enabled(false).build(app).map_err(|e| e.to_string())?;
+        return submenu.append(&placeholder).map_err(|e| e.to_string());
+    }
+    for site_app in apps {
+        let item = MenuItemBuilder::with_id(format!("{}{}", MENU_ITEM_PREFIX, site_app.id), &site_app.name)
+            .build(app)
+            .map_err(|e| e.to_string())?;
+        submenu.append(&item).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Apps menu item: open the app.
+pub fn open_by_id(app: &AppHandle, id: &str) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let result = state.site_apps.get(id).ok_or_else(|| format!("No app {}", id)).and_then(|a| open(app, &a));
+    if let Err(e) = result {
+        eprintln!("[SiteApps] {}", e);
+    }
+}
+
+/// Palette and Apps menu: install the active tab's site, named after the page.
+pub fn install_active_tab(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let active = {
+        let active_id = state.active_tab_id.lock().unwrap().clone();
+        let tabs = state.tabs.lock().unwrap();
+        tabs.iter().find(|t| Some(&t.id) == active_id.as_ref()).map(|t| (t.url.clone(), t.title.clone()))
+    };
+    let result = active
+        .ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))
+        .and_then(|(url, title)| install(app, &state, &url, &title));
+    if let Err(e) = result {
+        eprintln!("[SiteApps] {}", e);
+        app.dialog().message(e.to_string()).title("Install Site as App").kind(MessageDialogKind::Info).show(|_| {});
+    }
+}
+
+fn install(app: &AppHandle, state: &AppState, url: &str, name: &str) -> Result<SiteApp, BrowserError> {
+    let site_app = state.site_apps.install(url, name).map_err(BrowserError::InvalidInput)?;
+    println!("[SiteApps] Installed {} ({})", site_app.name, site_app.origin);
+    rebuild_menu(app).map_err(BrowserError::Webview)?;
+    open(app, &site_app).map_err(BrowserError::Webview)?;
+    Ok(site_app)
+}
+
+#[tauri::command]
+pub fn install_site_as_app(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    name: String,
+) -> Result<SiteApp, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    install(&app, &state, &url, &name)
+}
+
+#[tauri::command]
+pub fn list_site_apps(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<Vec<SiteApp>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.site_apps.list())
+}
+
+#[tauri::command]
+pub fn open_site_app(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let site_app = state.site_apps.get(&id).ok_or_else(|| BrowserError::NotFound("No such app".to_string()))?;
+    open(&app, &site_app).map_err(BrowserError::Webview)
+}
+
+#[tauri::command]
+pub fn uninstall_site_app(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: String,
+) -> Result<bool, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let removed = state.site_apps.uninstall(&id);
+    if removed {
+        if let Some(window) = app.get_window(&window_label(&id)) {
+            window.close()?;
+        }
+        rebuild_menu(&app).map_err(BrowserError::Webview)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://mail.example.com/inbox", true)]
+    #[case("https://mail.example.com:443/", true)]
+    #[case("http://mail.example.com/", false)]
+    #[case("https://example.com/", false)]
+    #[case("https://accounts.other.com/login", false)]
+    #[case("about:blank", true)]
+    #[case("mailto:someone@example.com", false)]
+    fn test_stays_in_app(#[case] url: &str, #[case] expected: bool) {
+        assert_eq!(stays_in_app("https://mail.example.com", &Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_install_is_per_origin() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SiteAppStore::new(dir.path().to_path_buf());
+        let mail = store.install("https://mail.example.com/inbox", "Mail").unwrap();
+        assert_eq!(mail.origin, "https://mail.example.com");
+        let again = store.install("https://mail.example.com/", "  ").unwrap();
+        assert_eq!(again.id, mail.id);
+        assert_eq!(again.name, "mail.example.com");
+        assert!(store.install("file:///tmp/page.html", "Local").is_err());
+
+        store.install("https://www.chat.example/", "").unwrap();
+        let reopened = SiteAppStore::new(dir.path().to_path_buf());
+        let names: Vec<String> = reopened.list().into_iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["chat.example".to_string(), "mail.example.com".to_string()]);
+        assert!(reopened.uninstall(&mail.id));
+        assert!(!reopened.uninstall(&mail.id));
+        assert_eq!(reopened.list().len(), 1);
+    }
+}
@@ -0,0 +1,533 @@
+// Site data usage - which sites store data on this machine, how much, and which
+// are worth purging.
+//
+// On macOS the website data store is enumerated directly (record sizes come from
+// WebKit's size-computing fetch where available). Other platforms don't expose
+// per-site storage through the webview, so usage there is cookie-based only and
+// purging removes cookies. Suggestions flag sites that hold data but haven't been
+// visited in a while, and sites using a lot of space.
+//
+// `clear_page_data` clears one page's site from the data store of its own tab,
+// on every platform (by origin on Windows, through the DevTools protocol).
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::cookie_policy;
+use crate::state::AppState;
+
+const UNVISITED_DAYS: u64 = 30;
+const LARGE_SITE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// One site's entry from the platform data store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlatformRecord {
+    pub site: String,
+    pub data_types: Vec<String>, // "LocalStorage", "IndexedDBDatabases", "DiskCache", ...
+    pub bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurgeReason {
+    Unvisited, // Holds data but no visit in UNVISITED_DAYS
+    Large,     // Over LARGE_SITE_BYTES
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteDataUsage {
+    pub site: String,
+    pub bytes: Option<u64>, // None when the platform can't measure it
+    pub cookie_count: usize,
+    pub data_types: Vec<String>,
+    pub last_visit: Option<u64>,
+    pub purge_reason: Option<PurgeReason>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Cookie count and approximate bytes (name + value) per site.
+pub fn cookie_usage<'a>(cookies: impl IntoIterator<Item = (&'a str, usize)>) -> HashMap<String, (usize, u64)> {
+    let mut usage: HashMap<String, (usize, u64)> = HashMap::new();
+    for (domain, bytes) in cookies {
+        let domain = domain.trim_start_matches('.');
+        if domain.is_empty() {
+            continue;
+        }
+        let entry = usage.entry(cookie_policy::site_of(domain)).or_default();
+        entry.0 += 1;
+        entry.1 += bytes as u64;
+    }
+    usage
+}
+
+pub fn purge_reason(usage: &SiteDataUsage, now: u64) -> Option<PurgeReason> {
+    if usage.bytes.is_some_and(|b| b >= LARGE_SITE_BYTES) {
+        return Some(PurgeReason::Large);
+    }
+    let stale_before = now.saturating_sub(UNVISITED_DAYS * 24 * 60 * 60);
+    match usage.last_visit {
+        Some(visit) if visit >= stale_before => None,
+        _ => Some(PurgeReason::Unvisited),
+    }
+}
+
+/// Combines platform records, cookies and history into one list, largest first.
+pub fn merge_usage(
+    records: Vec<PlatformRecord>,
+    cookies: HashMap<String, (usize, u64)>,
+    last_visits: &HashMap<String, u64>,
+    now: u64,
+) -> Vec<SiteDataUsage> {
+    fn entry_for<'a>(
+        by_site: &'a mut HashMap<String, SiteDataUsage>,
+        last_visits: &HashMap<String, u64>,
+        site: String,
+    ) -> &'a mut SiteDataUsage {
+        by_site.entry(site.clone()).or_insert_with(|| SiteDataUsage {
+            last_visit: last_visits.get(&site).copied(),
+            site,
+            bytes: None,
+            cookie_count: 0,
+            data_types: Vec::new(),
+            purge_reason: None,
+        })
+    }
+
+    let mut by_site: HashMap<String, SiteDataUsage> = HashMap::new();
+
+    for record in records {
+        let entry = entry_for(&mut by_site, last_visits, cookie_policy::site_of(&record.site));
+        if let Some(bytes) = record.bytes {
+            entry.bytes = Some(entry.bytes.unwrap_or(0) + bytes);
+        }
+        for data_type in record.data_types {
+            if !entry.data_types.contains(&data_type) {
+                entry.data_types.push(data_type);
+            }
+        }
+    }
+    for (site, (count, bytes)) in cookies {
+        let entry = entry_for(&mut by_site, last_visits, site);
+        entry.cookie_count = count;
+        if !entry.data_types.iter().any(|t| t == "Cookies") {
+            entry.data_types.push("Cookies".to_string());
+            // Platform sizes already include cookies; only add them when nothing was measured
+            entry.bytes = Some(entry.bytes.unwrap_or(0) + bytes);
+        }
+    }
+
+    let mut usage: Vec<SiteDataUsage> = by_site.into_values().collect();
+    for site in usage.iter_mut() {
+        site.data_types.sort();
+        site.purge_reason = purge_reason(site, now);
+    }
+    usage.sort_by(|a, b| b.bytes.unwrap_or(0).cmp(&a.bytes.unwrap_or(0)).then_with(|| a.site.cmp(&b.site)));
+    usage
+}
+
+pub struct SiteDataManager {
+    app: AppHandle,
+}
+
+impl SiteDataManager {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+
+    // All tabs share the default data store; the toolbar webview is always around
+    fn any_webview(&self) -> Result<tauri::Webview, String> {
+        self.app.get_webview("main").ok_or_else(|| "Main webview not found".to_string())
+    }
+
+    pub async fn usage(&self, state: &AppState) -> Result<Vec<SiteDataUsage>, String> {
+        let cookies = self.any_webview()?.cookies().map_err(|e| e.to_string())?;
+        let cookies = cookie_usage(
+            cookies.iter().map(|c| (c.domain().unwrap_or(""), c.name().len() + c.value().len())),
+        );
+
+        let records = platform::fetch_records(&self.app).await?;
+
+        let mut last_visits: HashMap<String, u64> = HashMap::new();
+        for entry in state.history.list(0, usize::MAX, None, None).entries {
+            if let Some(host) = url::Url::parse(&entry.url).ok().and_then(|u| u.host_str().map(str::to_string)) {
+                let visit = last_visits.entry(cookie_policy::site_of(&host)).or_default();
+                *visit = (*visit).max(entry.last_visit);
+            }
+        }
+
+        Ok(merge_usage(records, cookies, &last_visits, now_secs()))
+    }
+
+    /// Removes all stored data for the given sites. Returns how many sites had data.
+    pub async fn purge(&self, sites: &[String]) -> Result<usize, String> {
+        let sites: Vec<String> = sites.iter().map(|s| cookie_policy::site_of(s.trim())).collect();
+        let webview = self.any_webview()?;
+
+        let mut purged: Vec<String> = Vec::new();
+        for cookie in webview.cookies().map_err(|e| e.to_string())? {
+            let site = cookie_policy::site_of(cookie.domain().unwrap_or("").trim_start_matches('.'));
+            if sites.contains(&site) {
+                webview.delete_cookie(cookie).map_err(|e| e.to_string())?;
+                if !purged.contains(&site) {
+                    purged.push(site);
+                }
+            }
+        }
+        for site in platform::remove_records(&self.app, sites).await? {
+            if !purged.contains(&site) {
+                purged.push(site);
+            }
+        }
+
+        println!("[SiteData] Purged data for {} sites", purged.len());
+        Ok(purged.len())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::PlatformRecord;
+    use crate::modules::cookie_policy;
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::{Arc, Mutex};
+    use tauri::AppHandle;
+    use tokio::sync::oneshot;
+
+    // _WKWebsiteDataStoreFetchOptionComputeSizes
+    const FETCH_OPTION_COMPUTE_SIZES: u64 = 1;
+
+    unsafe fn to_string(ns_string: *mut Object) -> String {
+        if ns_string.is_null() {
+            return String::new();
+        }
+        let utf8: *const std::os::raw::c_char = msg_send![ns_string, UTF8String];
+        if utf8.is_null() {
+            return String::new();
+        }
+        std::ffi::CStr::from_ptr(utf8).to_string_lossy().into_owned()
+    }
+
+    unsafe fn read_record(record: *mut Object) -> PlatformRecord {
+        let name: *mut Object = msg_send![record, displayName];
+        let types: *mut Object = msg_send![record, dataTypes];
+        let types: *mut Object = msg_send![types, allObjects];
+        let type_count: usize = msg_send![types, count];
+        let data_types = (0..type_count)
+            .map(|i| {
+                let t: *mut Object = msg_send![types, objectAtIndex: i];
+                to_string(t).trim_start_matches("WKWebsiteDataType").to_string()
+            })
+            .collect();
+
+        // Private, only filled in by the size-computing fetch
+        let has_size: BOOL = msg_send![record, respondsToSelector: sel!(_dataSize)];
+        let bytes = if has_size != NO {
+            let size: *mut Object = msg_send![record, _dataSize];
+            if size.is_null() {
+                None
+            } else {
+                let total: u64 = msg_send![size, totalSize];
+                Some(total)
+            }
+        } else {
+            None
+        };
+
+        PlatformRecord { site: to_string(name), data_types, bytes }
+    }
+
+    /// Runs `f` on the main thread with the default data store and all data types,
+    /// handing it a sender for the result.
+    async fn with_store<T: Send + 'static>(
+        app: &AppHandle,
+        f: impl FnOnce(*mut Object, *mut Object, Arc<Mutex<Option<oneshot::Sender<T>>>>) + Send + 'static,
+    ) -> Result<T, String> {
+        let (tx, rx) = oneshot::channel();
+        let tx = Arc::new(Mutex::new(Some(tx)));
+        app.run_on_main_thread(move || unsafe {
+            let store: *mut Object = msg_send![class!(WKWebsiteDataStore), defaultDataStore];
+            let types: *mut Object = msg_send![class!(WKWebsiteDataStore), allWebsiteDataTypes];
+            f(store, types, tx);
+        })
+        .map_err(|e| e.to_string())?;
+        rx.await.map_err(|_| "Website data store did not respond".to_string())
+    }
+
+    pub async fn fetch_records(app: &AppHandle) -> Result<Vec<PlatformRecord>, String> {
+        with_store(app, |store, types, tx| unsafe {
+            let completion = ConcreteBlock::new(move |records: *mut Object| {
+                let count: usize = msg_send![records, count];
+                let out: Vec<PlatformRecord> = (0..count)
+                    .map(|i| {
+                        let record: *mut Object = msg_send![records, objectAtIndex: i];
+                        read_record(record)
+                    })
+                    .collect();
+                if let Some(tx) = tx.lock().unwrap().take() {
+                    let _ = tx.send(out);
+                }
+            })
+            .copy();
+
+            let sized: BOOL = msg_send![store, respondsToSelector: sel!(_fetchDataRecordsOfTypes:withOptions:completionHandler:)];
+            if sized != NO {
+                let _: () = msg_send![store, _fetchDataRecordsOfTypes: types
+                                             withOptions: FETCH_OPTION_COMPUTE_SIZES
+                                             completionHandler: &*completion];
+            } else {
+                let _: () = msg_send![store, fetchDataRecordsOfTypes: types completionHandler: &*completion];
+            }
+        })
+        .await
+    }
+
+    /// Removes every data type for records belonging to `sites`. Returns the sites removed.
+    pub async fn remove_records(app: &AppHandle, sites: Vec<String>) -> Result<Vec<String>, String> {
+        with_store(app, move |store, types, tx| unsafe {
+            let fetched = ConcreteBlock::new(move |records: *mut Object| {
+                let matching: *mut Object = msg_send![class!(NSMutableArray), array];
+                let mut removed: Vec<String> = Vec::new();
+                let count: usize = msg_send![records, count];
+                for i in 0..count {
+                    let record: *mut Object = msg_send![records, objectAtIndex: i];
+                    let name: *mut Object = msg_send![record, displayName];
+                    let site = cookie_policy::site_of(&to_string(name));
+                    if sites.contains(&site) {
+                        let _: () = msg_send![matching, addObject: record];
+                        removed.push(site);
+                    }
+                }
+
+                let tx = tx.clone();
+                let done = ConcreteBlock::new(move || {
+                    if let Some(tx) = tx.lock().unwrap().take() {
+                        let _ = tx.send(removed.clone());
+                    }
+                })
+                .copy();
+                let all_types: *mut Object = msg_send![class!(WKWebsiteDataStore), allWebsiteDataTypes];
+                let store: *mut Object = msg_send![class!(WKWebsiteDataStore), defaultDataStore];
+                let _: () = msg_send![store, removeDataOfTypes: all_types forDataRecords: matching completionHandler: &*done];
+            })
+            .copy();
+            let _: () = msg_send![store, fetchDataRecordsOfTypes: types completionHandler: &*fetched];
+        })
+        .await
+    }
+
+    /// Removes every data type `site` has in the data store `webview` uses.
+    pub async fn clear_page_data(webview: &tauri::Webview, url: &url::Url) -> Result<(), String> {
+        let site = cookie_policy::site_of(url.host_str().unwrap_or(""));
+        let (tx, rx) = oneshot::channel();
+        let tx = Arc::new(Mutex::new(Some(tx)));
+        webview
+            .with_webview(move |platform| unsafe {
+                let wk_webview = platform.inner() as *mut Object;
+                let config: *mut Object = msg_send![wk_webview, configuration];
+                let store: *mut Object = msg_send![config, websiteDataStore];
+                let types: *mut Object = msg_send![class!(WKWebsiteDataStore), allWebsiteDataTypes];
+                let fetched = ConcreteBlock::new(move |records: *mut Object| {
+                    let matching: *mut Object = msg_send![class!(NSMutableArray), array];
+                    let count: usize = msg_send![records, count];
+                    for i in 0..count {
+                        let record: *mut Object = msg_send![records, objectAtIndex: i];
+                        let name: *mut Object = msg_send![record, displayName];
+                        if cookie_policy::site_of(&to_string(name)) == site {
+                            let _: () = msg_send![matching, addObject: record];
+                        }
+                    }
+
+                    let tx = tx.clone();
+                    let done = ConcreteBlock::new(move || {
+                        if let Some(tx) = tx.lock().unwrap().take() {
+                            let _ = tx.send(());
+                        }
+                    })
+                    .copy();
+                    let all_types: *mut Object = msg_send![class!(WKWebsiteDataStore), allWebsiteDataTypes];
+                    let _: () = msg_send![store, removeDataOfTypes: all_types
+                                                 forDataRecords: matching
+                                                 completionHandler: &*done];
+                })
+                .copy();
+                let _: () = msg_send![store, fetchDataRecordsOfTypes: types completionHandler: &*fetched];
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await.map_err(|_| "Website data store did not respond".to_string())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use super::PlatformRecord;
+    use tauri::AppHandle;
+    use tokio::sync::oneshot;
+
+    pub async fn fetch_records(_app: &AppHandle) -> Result<Vec<PlatformRecord>, String> {
+        Ok(Vec::new())
+    }
+
+    pub async fn remove_records(_app: &AppHandle, _sites: Vec<String>) -> Result<Vec<String>, String> {
+        Ok(Vec::new())
+    }
+
+    /// Removes everything the page's origin stores, through the DevTools protocol
+    /// (WebView2 has no per-site API).
+    #[cfg(windows)]
+    pub async fn clear_page_data(webview: &tauri::Webview, url: &url::Url) -> Result<(), String> {
+        use webview2_com::CallDevToolsProtocolMethodCompletedHandler;
+        use windows::core::HSTRING;
+
+        let params = serde_json::json!({ "origin": url.origin().ascii_serialization(), "storageTypes": "all" });
+        let (tx, rx) = oneshot::channel();
+        webview
+            .with_webview(move |platform| unsafe {
+                let core = match platform.controller().CoreWebView2() {
+                    Ok(core) => core,
+                    Err(e) => {
+                        let _ = tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |result, _| {
+                    let _ = tx.send(result.map_err(|e| e.to_string()));
+                    Ok(())
+                }));
+                let method = HSTRING::from("Storage.clearDataForOrigin");
+                if let Err(e) = core.CallDevToolsProtocolMethod(&method, &HSTRING::from(params.to_string()), &handler) {
+                    eprintln!("[SiteData] Failed to clear site data: {}", e);
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await.map_err(|_| "Website data store did not respond".to_string())?
+    }
+
+    /// Removes every data type the page's site has in the webview's data manager.
+    #[cfg(not(windows))]
+    pub async fn clear_page_data(webview: &tauri::Webview, url: &url::Url) -> Result<(), String> {
+        use crate::modules::cookie_policy;
+        use webkit2gtk::{gio, WebViewExt, WebsiteData, WebsiteDataManagerExt, WebsiteDataTypes};
+
+        let site = cookie_policy::site_of(url.host_str().unwrap_or(""));
+        let (tx, rx) = oneshot::channel();
+        webview
+            .with_webview(move |platform| {
+                let Some(manager) = platform.inner().website_data_manager() else {
+                    let _ = tx.send(Err("No data manager for the webview".to_string()));
+                    return;
+                };
+                let remover = manager.clone();
+                manager.fetch(WebsiteDataTypes::ALL, None::<&gio::Cancellable>, move |result| {
+                    let data = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            let _ = tx.send(Err(e.to_string()));
+                            return;
+                        }
+                    };
+                    let of_site = |d: &&WebsiteData| d.name().is_some_and(|n| cookie_policy::site_of(&n) == site);
+                    let matching: Vec<_> = data.iter().filter(of_site).collect();
+                    remover.remove(WebsiteDataTypes::ALL, &matching, None::<&gio::Cancellable>, move |result| {
+                        let _ = tx.send(result.map_err(|e| e.to_string()));
+                    });
+                });
+            })
+            .map_err(|e| e.to_string())?;
+        rx.await.map_err(|_| "Website data store did not respond".to_string())?
+    }
+}
+
+/// Removes the cookies, storage and caches of `url`'s site (its origin on
+/// Windows) from the data store `webview` uses, so container tabs work too.
+pub async fn clear_page_data(webview: &tauri::Webview, url: &url::Url) -> Result<(), String> {
+    platform::clear_page_data(webview, url).await
+}
+
+#[tauri::command]
+pub async fn get_site_data_usage(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SiteDataUsage>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(SiteDataManager::new(app).usage(&state).await?)
+}
+
+#[tauri::command]
+pub async fn purge_site_data(
+    app: AppHandle,
+    webview: tauri::Webview,
+    sites: Vec<String>,
+) -> Result<usize, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(SiteDataManager::new(app).purge(&sites).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_cookie_usage_groups_by_site() {
+        let usage = cookie_usage(vec![(".example.com", 10), ("www.example.com", 5), ("other.co.uk", 3), ("", 1)]);
+        assert_eq!(usage.get("example.com"), Some(&(2, 15)));
+        assert_eq!(usage.get("other.co.uk"), Some(&(1, 3)));
+        assert_eq!(usage.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_usage() {
+        let now = 100 * DAY;
+        let records = vec![
+            PlatformRecord { site: "big.com".into(), data_types: vec!["IndexedDBDatabases".into()], bytes: Some(LARGE_SITE_BYTES) },
+            PlatformRecord { site: "news.com".into(), data_types: vec!["LocalStorage".into(), "Cookies".into()], bytes: Some(2048) },
+        ];
+        let mut cookies = HashMap::new();
+        cookies.insert("news.com".to_string(), (4, 100));
+        cookies.insert("tracker.net".to_string(), (1, 40));
+        let mut visits = HashMap::new();
+        visits.insert("news.com".to_string(), now - DAY);
+        visits.insert("big.com".to_string(), now - DAY);
+
+        let usage = merge_usage(records, cookies, &visits, now);
+        assert_eq!(usage.iter().map(|u| u.site.as_str()).collect::<Vec<_>>(), vec!["big.com", "news.com", "tracker.net"]);
+
+        assert_eq!(usage[0].purge_reason, Some(PurgeReason::Large));
+
+        // Platform size already counts cookies
+        assert_eq!(usage[1].bytes, Some(2048));
+        assert_eq!(usage[1].cookie_count, 4);
+        assert_eq!(usage[1].data_types, vec!["Cookies", "LocalStorage"]);
+        assert_eq!(usage[1].purge_reason, None);
+
+        // Never visited, cookie-only
+        assert_eq!(usage[2].bytes, Some(40));
+        assert_eq!(usage[2].purge_reason, Some(PurgeReason::Unvisited));
+    }
+
+    #[test]
+    fn test_purge_reason_stale_visit() {
+        let now = 100 * DAY;
+        let usage = SiteDataUsage {
+            site: "old.com".into(),
+            bytes: Some(10),
+            cookie_count: 1,
+            data_types: vec![],
+            last_visit: Some(now - (UNVISITED_DAYS + 1) * DAY),
+            purge_reason: None,
+        };
+        assert_eq!(purge_reason(&usage, now), Some(PurgeReason::Unvisited));
+    }
+}
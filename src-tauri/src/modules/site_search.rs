@@ -0,0 +1,301 @@
+// Tab-to-search: searching a site straight from the omnibox.
+//
+// Sites the user searches on are remembered in `Settings.search_engines` as
+// learned engines, keyed by the site's host without "www.". `DETECT_SCRIPT`
+// finds them in two ways:
+// - OpenSearch: a page that links a description (<link rel="search">) on its
+//   own host. The description is fetched once per host and session, and its
+//   results page replaces a pattern learned from a form.
+// - URL patterns: submitting a GET form with a single filled-in text field. The
+//   form's action with that field as the query becomes the template.
+// Every browsing webview runs the script, but only tabs teach: private, guest
+// and link windows must leave nothing in the profile, so their reports are
+// ignored.
+//
+// In the omnibox, typing the start of a learned host (at least MIN_TYPED
+// characters) offers the site, and Tab scopes the query to it by turning the
+// input into "<host> ", which smart_parse_url routes like any keyword search.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::{offline, proxy};
+use crate::settings::{SearchEngine, QUERY_PLACEHOLDER};
+use crate::state::AppState;
+
+const MIN_TYPED: usize = 3;
+const OPENSEARCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hosts whose OpenSearch description was already fetched this session.
+static CHECKED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// Reports OpenSearch descriptions and searches made through GET forms.
+pub const DETECT_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_SITE_SEARCH__ || !window.__TAURI__ || window.top !== window) return;
+    window.__SOVEREIGN_SITE_SEARCH__ = true;
+    const invoke = window.__TAURI__.core.invoke;
+
+    function reportOpenSearch() {
+        const link = document.querySelector('link[rel="search"][type="application/opensearchdescription+xml"][href]');
+        if (link) invoke('site_search_opensearch', { href: link.href }).catch(() => {});
+    }
+    if (document.readyState === 'loading') {
+        document.addEventListener('DOMContentLoaded', reportOpenSearch, { once: true });
+    } else {
+        reportOpenSearch();
+    }
+
+    document.addEventListener('submit', (e) => {
+        const form = e.target;
+        if (!(form instanceof HTMLFormElement) || form.method !== 'get') return;
+        const fields = [...form.elements].filter(el => el instanceof HTMLInputElement
+            && (el.type === 'search' || el.type === 'text') && el.name && el.value.trim());
+        if (fields.length !== 1) return;
+        invoke('site_search_form', { action: form.action, field: fields[0].name }).catch(() => {});
+    }, true);
+})();
+"#;
+
+/// What the omnibox offers for the typed text.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SiteSearch {
+    pub name: String,
+    pub keyword: String,
+    pub scoped: bool, // The input already is "<keyword> query"
+}
+
+/// The keyword of a site: its host without "www.".
+pub fn keyword_for(host: &str) -> String {
+    let host = host.to_ascii_lowercase();
+    host.strip_prefix("www.").map(str::to_string).unwrap_or(host)
+}
+
+/// The template for a search form: its action with `field` as the only parameter.
+pub fn form_template(action: &Url, field: &str) -> Option<String> {
+    if !matches!(action.scheme(), "http" | "https") || field.is_empty() {
+        return None;
+    }
+    let mut url = action.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+    Some(format!("{}?{}={}", url, urlencoding::encode(field), QUERY_PLACEHOLDER))
+}
+
+/// Value of an attribute in an element's attribute text.
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{}=", name);
+    attrs.match_indices(&pattern).find_map(|(i, _)| {
+        if !attrs[..i].ends_with(char::is_whitespace) {
+            return None;
+        }
+        let rest = &attrs[i + pattern.len()..];
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        rest[1..].split(quote).next()
+    })
+}
+
+/// The results-page template of an OpenSearch description, as a query template.
+/// Optional parameters are dropped; other required ones aren't supported.
+pub fn parse_opensearch(xml: &str) -> Option<String> {
+    let xml = xml.replace("<os:Url", "<Url");
+    let template = xml.split("<Url").skip(1).find_map(|element| {
+        let attrs = element.split('>').next().unwrap_or("");
+        (attribute(attrs, "type") == Some("text/html")).then(|| attribute(attrs, "template")).flatten()
+    })?;
+
+    let mut template = template.replace("&amp;", "&").replace("{searchTerms}", QUERY_PLACEHOLDER);
+    while let Some(start) = template.find('{') {
+        let end = start + template[start..].find('}')?;
+        if !template[start..end].ends_with('?') {
+            return None;
+        }
+        template.replace_range(start..=end, "");
+    }
+    template.contains(QUERY_PLACEHOLDER).then_some(template)
+}
+
+/// An engine for `template` found on `page`. Only templates on the page's own host count.
+pub fn engine_for(page: &Url, template: &str) -> Option<SearchEngine> {
+    let target = Url::parse(&template.replace(QUERY_PLACEHOLDER, "q")).ok()?;
+    if !matches!(target.scheme(), "http" | "https") || target.host_str()? != page.host_str()? {
+        return None;
+    }
+    let keyword = keyword_for(target.host_str()?);
+    Some(SearchEngine::new(&keyword, &keyword, template))
+}
+
+/// The learned site search for what's typed: "<keyword> query" once scoped, else
+/// the shortest keyword starting with the typed host.
+pub fn find(engines: &[SearchEngine], input: &str) -> Option<SiteSearch> {
+    let hint = |e: &SearchEngine, scoped| SiteSearch { name: e.name.clone(), keyword: e.keyword.clone(), scoped };
+    let input = input.trim_start();
+    if let Some((keyword, _)) = input.split_once(char::is_whitespace) {
+        return engines.iter().find(|e| e.learned && e.keyword.eq_ignore_ascii_case(keyword)).map(|e| hint(e, true));
+    }
+
+    let typed = input.trim_end_matches('/').to_ascii_lowercase();
+    let typed = typed.strip_prefix("https://").or_else(|| typed.strip_prefix("http://")).unwrap_or(&typed);
+    let typed = typed.strip_prefix("www.").unwrap_or(typed);
+    if typed.len() < MIN_TYPED {
+        return None;
+    }
+    engines
+        .iter()
+        .filter(|e| e.learned && e.keyword.starts_with(typed))
+        .min_by_key(|e| e.keyword.len())
+        .map(|e| hint(e, false))
+}
+
+fn is_tab(state: &AppState, webview: &tauri::Webview) -> bool {
+    state.tabs.lock().unwrap().iter().any(|t| t.webview_label == webview.label())
+}
+
+fn learn(app: &AppHandle, state: &AppState, engine: SearchEngine, replace: bool) {
+    let settings = {
+        let mut settings = state.settings.write().unwrap();
+        let keyword = engine.keyword.clone();
+        if !settings.learn_search_engine(engine, replace) {
+            return;
+        }
+        println!("[SiteSearch] Learned search for {}", keyword);
+        settings.clone()
+    };
+    if let Err(e) = settings.save(app) {
+        eprintln!("[SiteSearch] Failed to save settings: {}", e);
+    }
+    let _ = app.emit("settings-update", settings);
+}
+
+/// From `DETECT_SCRIPT`: a GET form with one filled-in field was submitted.
+#[tauri::command]
+pub fn site_search_form(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    action: String,
+    field: String,
+) -> Result<(), BrowserError> {
+    if !is_tab(&state, &webview) {
+        return Ok(());
+    }
+    let page = webview.url()?;
+    let template = form_template(&Url::parse(&action)?, &field);
+    if let Some(engine) = template.and_then(|t| engine_for(&page, &t)) {
+        learn(&app, &state, engine, false);
+    }
+    Ok(())
+}
+
+/// From `DETECT_SCRIPT`: the page links an OpenSearch description.
+#[tauri::command]
+pub async fn site_search_opensearch(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    href: String,
+) -> Result<(), BrowserError> {
+    if !is_tab(&state, &webview) {
+        return Ok(());
+    }
+    let page = webview.url()?;
+    let description = Url::parse(&href)?;
+    let Some(host) = description.host_str().filter(|h| Some(*h) == page.host_str()) else {
+        return Ok(());
+    };
+    if !CHECKED.lock().unwrap().get_or_insert_with(HashSet::new).insert(host.to_string())
+        || offline::blocks(&state, description.as_str())
+    {
+        return Ok(());
+    }
+
+    let client = proxy::http_client(&state, OPENSEARCH_TIMEOUT)?;
+    let xml = client.get(description.as_str()).send().await?.error_for_status()?.text().await?;
+    if let Some(engine) = parse_opensearch(&xml).and_then(|t| engine_for(&page, &t)) {
+        learn(&app, &state, engine, true);
+    }
+    Ok(())
+}
+
+/// The site search to offer in the omnibox for `input`, if any.
+#[tauri::command]
+pub fn find_site_search(state: tauri::State<AppState>, input: String) -> Result<Option<SiteSearch>, BrowserError> {
+    Ok(find(&state.settings.read().unwrap().search_engines, &input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::navigation::smart_parse_url;
+    use crate::settings::Settings;
+    use rstest::rstest;
+
+    fn learned(keyword: &str) -> SearchEngine {
+        SearchEngine { learned: true, ..SearchEngine::new(keyword, keyword, "https://example.com/?q=%s") }
+    }
+
+    #[rstest]
+    #[case("https://github.com/search?type=code", "q", Some("https://github.com/search?q=%s"))]
+    #[case("https://example.com/find#top", "search query", Some("https://example.com/find?search%20query=%s"))]
+    #[case("ftp://example.com/find", "q", None)]
+    #[case("https://example.com/find", "", None)]
+    fn test_form_template(#[case] action: &str, #[case] field: &str, #[case] expected: Option<&str>) {
+        assert_eq!(form_template(&Url::parse(action).unwrap(), field).as_deref(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        r#"<Url type="application/x-suggestions+json" template="https://x.org/s?q={searchTerms}"/>
+           <Url type="text/html" method="get" template="https://x.org/find?q={searchTerms}&amp;page={startPage?}"/>"#,
+        Some("https://x.org/find?q=%s&page=")
+    )]
+    #[case(r#"<os:Url template='https://x.org/?q={searchTerms}' type='text/html'/>"#, Some("https://x.org/?q=%s"))]
+    #[case(r#"<Url type="text/html" template="https://x.org/?q={searchTerms}&n={count}"/>"#, None)]
+    #[case(r#"<Url type="text/html" template="https://x.org/"/>"#, None)]
+    #[case("<OpenSearchDescription/>", None)]
+    fn test_parse_opensearch(#[case] xml: &str, #[case] expected: Option<&str>) {
+        assert_eq!(parse_opensearch(xml).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_engine_for_needs_the_same_host() {
+        let page = Url::parse("https://www.example.com/wiki").unwrap();
+        let engine = engine_for(&page, "https://www.example.com/search?q=%s").unwrap();
+        assert_eq!(engine.keyword, "example.com");
+        assert!(engine_for(&page, "https://search.other.com/?q=%s").is_none());
+        assert!(engine_for(&page, "javascript:alert('%s')").is_none());
+    }
+
+    #[test]
+    fn test_find() {
+        let engines = vec![SearchEngine::new("GitHub", "gh", "https://github.com/search?q=%s"), learned("github.com")];
+        assert_eq!(find(&engines, "git").map(|s| s.keyword), Some("github.com".to_string()));
+        assert_eq!(find(&engines, "https://www.GitHub.com/").map(|s| s.scoped), Some(false));
+        assert_eq!(find(&engines, "github.com rust").map(|s| s.scoped), Some(true));
+        // Too short, engines the user added, and other keywords
+        assert!(find(&engines, "gi").is_none());
+        assert!(find(&engines, "gh rust").is_none());
+        assert!(find(&engines, "gitlab").is_none());
+    }
+
+    #[test]
+    fn test_learned_engines_never_replace_the_users() {
+        let mut settings = Settings::default();
+        let engine = SearchEngine::new("example.com", "example.com", "https://example.com/search?q=%s");
+        assert!(settings.learn_search_engine(engine.clone(), false));
+        assert!(!settings.learn_search_engine(engine, false));
+        assert_eq!(smart_parse_url("example.com cats", &settings), "https://example.com/search?q=cats");
+
+        // OpenSearch replaces a learned pattern, not one the user added
+        let opensearch = SearchEngine::new("example.com", "example.com", "https://example.com/s/%s");
+        assert!(settings.learn_search_engine(opensearch, true));
+        let youtube = SearchEngine::new("youtube.com", "yt", "https://youtube.com/find?q=%s");
+        assert!(!settings.learn_search_engine(youtube, true));
+        assert_eq!(settings.engine_for_keyword("yt").map(|e| e.learned), Some(false));
+    }
+}
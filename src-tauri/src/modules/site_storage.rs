@@ -0,0 +1,298 @@
+// Per-tab storage viewer for the page-info panel.
+//
+// Cookies come from the platform cookie store (which also sees HttpOnly cookies).
+// Web storage and IndexedDB are only visible from inside the page, so an enumerator
+// is evaluated in the tab and reports back through `report_tab_storage`. Reports are
+// matched to requests by id and by the reporting webview's label, so a page can't
+// answer for another tab. Nothing is persisted; the summary is always live.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const REPORT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntry {
+    pub key: String,
+    pub bytes: usize, // UTF-16 length of key + value, as browsers count quota
+}
+
+/// What the in-page enumerator sends back.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PageStorageReport {
+    pub origin: String,
+    pub local_storage: Vec<StorageEntry>,
+    pub session_storage_keys: usize,
+    pub session_storage_bytes: usize,
+    pub indexed_db: Vec<String>,
+    pub error: Option<String>, // e.g. storage access denied on opaque origins
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabStorageSummary {
+    pub tab_id: String,
+    pub origin: String,
+    pub local_storage: Vec<StorageEntry>,
+    pub session_storage_keys: usize,
+    pub session_storage_bytes: usize,
+    pub indexed_db: Vec<String>,
+    pub cookie_count: usize,
+    pub cookie_names: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl TabStorageSummary {
+    pub fn new(tab_id: &str, mut report: PageStorageReport, mut cookie_names: Vec<String>) -> Self {
+        report.local_storage.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.key.cmp(&b.key)));
+        report.indexed_db.sort();
+        cookie_names.sort();
+        TabStorageSummary {
+            tab_id: tab_id.to_string(),
+            origin: report.origin,
+            local_storage: report.local_storage,
+            session_storage_keys: report.session_storage_keys,
+            session_storage_bytes: report.session_storage_bytes,
+            indexed_db: report.indexed_db,
+            cookie_count: cookie_names.len(),
+            cookie_names,
+            error: report.error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageKind {
+    Cookie,
+    LocalStorage,
+    SessionStorage, // Cleared as a whole; `name` is ignored
+    IndexedDb,
+}
+
+/// Tracks enumerator requests waiting for the page to answer.
+pub struct StorageInspector {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, (String, oneshot::Sender<PageStorageReport>)>>,
+}
+
+impl StorageInspector {
+    pub fn new() -> Self {
+        Self { next_id: AtomicU64::new(1), pending: Mutex::new(HashMap::new()) }
+    }
+
+    /// Registers a request that only `webview_label` may answer.
+    pub fn begin(&self, webview_label: &str) -> (u64, oneshot::Receiver<PageStorageReport>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, (webview_label.to_string(), tx));
+        (id, rx)
+    }
+
+    /// Delivers a report. False if the id is unknown or the label doesn't match.
+    pub fn complete(&self, id: u64, webview_label: &str, report: PageStorageReport) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(&id) {
+            Some((label, _)) if label == webview_label => {}
+            _ => return false,
+        }
+        match pending.remove(&id) {
+            Some((_, tx)) => tx.send(report).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn cancel(&self, id: u64) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+}
+
+impl Default for StorageInspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn enumerator_script(request_id: u64) -> String {
+    format!(
+        r#"
+        (async function() {{
+            const report = {{ origin: location.origin, localStorage: [], sessionStorageKeys: 0, sessionStorageBytes: 0, indexedDb: [] }};
+            try {{
+                for (let i = 0; i < localStorage.length; i++) {{
+                    const key = localStorage.key(i);
+                    const value = localStorage.getItem(key) || '';
+                    report.localStorage.push({{ key, bytes: key.length + value.length }});
+                }}
+                report.sessionStorageKeys = sessionStorage.length;
+                for (let i = 0; i < sessionStorage.length; i++) {{
+                    const key = sessionStorage.key(i);
+                    report.sessionStorageBytes += key.length + (sessionStorage.getItem(key) || '').length;
+                }}
+                if (indexedDB.databases) {{
+                    report.indexedDb = (await indexedDB.databases()).map(db => db.name).filter(Boolean);
+                }}
+            }} catch (e) {{
+                report.error = String(e);
+            }}
+            window.__TAURI__.core.invoke('report_tab_storage', {{ requestId: {}, report }});
+        }})();
+        "#,
+        request_id
+    )
+}
+
+fn tab_webview(app: &AppHandle, state: &AppState, tab_id: &str) -> Result<tauri::Webview, BrowserError> {
+    let label = {
+        let tabs = state.tabs.lock().unwrap();
+        tabs.iter().find(|t| t.id == tab_id).map(|t| t.webview_label.clone())
+    };
+    label.and_then(|l| app.get_webview(&l)).ok_or_else(|| BrowserError::tab_not_found(tab_id))
+}
+
+#[tauri::command]
+pub async fn get_tab_storage_summary(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    tab_id: String,
+) -> Result<TabStorageSummary, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let webview = tab_webview(&app, &state, &tab_id)?;
+    let url = webview.url()?;
+
+    let cookie_names: Vec<String> = webview
+        .cookies_for_url(url)?
+        .iter()
+        .map(|c| c.name().to_string())
+        .collect();
+
+    let (request_id, rx) = state.storage_inspector.begin(webview.label());
+    if let Err(e) = webview.eval(enumerator_script(request_id)) {
+        state.storage_inspector.cancel(request_id);
+        return Err(e.into());
+    }
+    let report = match tokio::time::timeout(REPORT_TIMEOUT, rx).await {
+        Ok(Ok(report)) => report,
+        _ => {
+            state.storage_inspector.cancel(request_id);
+            PageStorageReport { error: Some("Page did not respond".to_string()), ..Default::default() }
+        }
+    };
+
+    Ok(TabStorageSummary::new(&tab_id, report, cookie_names))
+}
+
+#[tauri::command]
+pub fn report_tab_storage(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    request_id: u64,
+    report: PageStorageReport,
+) -> Result<(), BrowserError> {
+    if !state.storage_inspector.complete(request_id, webview.label(), report) {
+        println!("[SiteStorage] Ignored report {} from {}", request_id, webview.label());
+    }
+    Ok(())
+}
+
+/// Deletes one item shown in the summary (a cookie, localStorage key, IndexedDB
+/// database, or all of sessionStorage).
+#[tauri::command]
+pub async fn delete_tab_storage_item(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<'_, AppState>,
+    tab_id: String,
+    kind: StorageKind,
+    name: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let webview = tab_webview(&app, &state, &tab_id)?;
+    let name_js = serde_json::to_string(&name)?;
+
+    match kind {
+        StorageKind::Cookie => {
+            let url = webview.url()?;
+            let cookies = webview.cookies_for_url(url)?;
+            for cookie in cookies.into_iter().filter(|c| c.name() == name) {
+                webview.delete_cookie(cookie)?;
+            }
+        }
+        StorageKind::LocalStorage => {
+            webview.eval(format!("localStorage.removeItem({});", name_js))?;
+        }
+        StorageKind::SessionStorage => {
+            webview.eval("sessionStorage.clear();")?;
+        }
+        StorageKind::IndexedDb => {
+            webview.eval(format!("indexedDB.deleteDatabase({});", name_js))?;
+        }
+    }
+    println!("[SiteStorage] Deleted {:?} '{}' in tab {}", kind, name, tab_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_matched_by_id_and_label() {
+        let inspector = StorageInspector::new();
+        let (id, mut rx) = inspector.begin("webview-tab-1");
+
+        // Another tab can't answer, and unknown ids are ignored
+        assert!(!inspector.complete(id, "webview-tab-2", PageStorageReport::default()));
+        assert!(!inspector.complete(id + 100, "webview-tab-1", PageStorageReport::default()));
+
+        let report = PageStorageReport { origin: "https://example.com".into(), ..Default::default() };
+        assert!(inspector.complete(id, "webview-tab-1", report.clone()));
+        assert_eq!(rx.try_recv().unwrap(), report);
+
+        // One answer per request
+        assert!(!inspector.complete(id, "webview-tab-1", PageStorageReport::default()));
+    }
+
+    #[test]
+    fn test_summary_sorting() {
+        let report = PageStorageReport {
+            origin: "https://example.com".into(),
+            local_storage: vec![
+                StorageEntry { key: "small".into(), bytes: 10 },
+                StorageEntry { key: "big".into(), bytes: 500 },
+            ],
+            indexed_db: vec!["z".into(), "a".into()],
+            ..Default::default()
+        };
+        let summary = TabStorageSummary::new("tab-1", report, vec!["sid".into(), "_ga".into()]);
+        assert_eq!(summary.local_storage[0].key, "big");
+        assert_eq!(summary.indexed_db, vec!["a", "z"]);
+        assert_eq!(summary.cookie_count, 2);
+        assert_eq!(summary.cookie_names, vec!["_ga", "sid"]);
+    }
+
+    #[test]
+    fn test_report_deserializes_partial_json() {
+        let report: PageStorageReport = serde_json::from_str(r#"{"origin":"null","error":"SecurityError"}"#).unwrap();
+        assert_eq!(report.origin, "null");
+        assert!(report.local_storage.is_empty());
+        assert_eq!(report.error.as_deref(), Some("SecurityError"));
+    }
+
+    #[test]
+    fn test_enumerator_script_carries_request_id() {
+        assert!(enumerator_script(42).contains("requestId: 42"));
+    }
+}
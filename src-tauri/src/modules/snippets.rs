@@ -0,0 +1,462 @@
+// CSS/JS snippets run on the active tab by hotkey.
+//
+// A snippet is a piece of CSS or JavaScript the user keeps for quick fixes:
+// widening a column, dismissing a nag, dumping some state. It can be limited to
+// one site (and its subdomains) and bound to a hotkey. Snippets are listed in the
+// native Snippets menu, whose accelerators carry the hotkeys the same way the
+// command registry's do (modules::commands), so a hotkey can't take one that a
+// browser command or another snippet already has.
+//
+// `run_snippet` evaluates JavaScript in the tab's top document and toggles CSS:
+// the first run adds a <style>, the next removes it. Both last until the page
+// navigates. Since that runs code in any site, the commands only answer the
+// browser's own UI, never pages.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::menu::{MenuItemBuilder, MenuItemKind};
+use tauri::{AppHandle, Manager};
+
+use crate::adblock_manager::AdBlockManager;
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::commands;
+use crate::state::AppState;
+
+const SNIPPETS_FILE: &str = "snippets.json";
+const MAX_NAME_LEN: usize = 80;
+const MAX_CODE_LEN: usize = 64 * 1024;
+pub const MENU_ID: &str = "snippets_menu";
+pub const MENU_ITEM_PREFIX: &str = "snippet:";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetKind {
+    Css,
+    Js,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub id: u64,
+    pub name: String,
+    pub kind: SnippetKind,
+    pub code: String,
+    #[serde(default)]
+    pub domain: Option<String>, // None: any site
+    #[serde(default)]
+    pub hotkey: Option<String>, // Accelerator, e.g. "CmdOrCtrl+Shift+1"
+}
+
+/// A snippet as saved from the UI; no id adds a new one.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetInput {
+    pub id: Option<u64>,
+    pub name: String,
+    pub kind: SnippetKind,
+    pub code: String,
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub hotkey: Option<String>,
+}
+
+const CMD_OR_CTRL: u8 = 1 << 0;
+const SUPER: u8 = 1 << 1;
+const CTRL: u8 = 1 << 2;
+const ALT: u8 = 1 << 3;
+const SHIFT: u8 = 1 << 4;
+
+const NAMED_KEYS: &[&str] = &[
+    "Space",
+    "Enter",
+    "Tab",
+    "Backspace",
+    "Delete",
+    "Escape",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Hotkey {
+    modifiers: u8,
+    key: String,
+}
+
+impl Hotkey {
+    fn parse(accelerator: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+        let (key, modifiers) = parts.split_last().ok_or("Empty hotkey")?;
+        let mut bits = 0;
+        for modifier in modifiers {
+            bits |= match modifier.to_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" => CMD_OR_CTRL,
+                "cmd" | "command" | "super" | "meta" => SUPER,
+                "ctrl" | "control" => CTRL,
+                "alt" | "option" => ALT,
+                "shift" => SHIFT,
+                _ => return Err(format!("Unknown modifier: {}", modifier)),
+            };
+        }
+        let is_function_key =
+            key.strip_prefix(['f', 'F']).and_then(|n| n.parse::<u8>().ok()).is_some_and(|n| (1..=24).contains(&n));
+        let key = if is_function_key {
+            key.to_uppercase()
+        } else if let Some(named) = NAMED_KEYS.iter().find(|k| k.eq_ignore_ascii_case(key)) {
+            named.to_string()
+        } else if key.chars().count() == 1 && !key.chars().all(char::is_whitespace) {
+            key.to_uppercase()
+        } else {
+            return Err(format!("Unknown key: {}", key));
+        };
+        // Plain or shifted keys belong to the page
+        if !is_function_key && bits & !SHIFT == 0 {
+            return Err("A hotkey needs Cmd/Ctrl or Alt".to_string());
+        }
+        Ok(Self { modifiers: bits, key })
+    }
+
+    /// The same key combination as pressed on this platform: CmdOrCtrl becomes Cmd or Ctrl.
+    fn resolved(&self, macos: bool) -> Self {
+        let mut modifiers = self.modifiers & !CMD_OR_CTRL;
+        if self.modifiers & CMD_OR_CTRL != 0 {
+            modifiers |= if macos { SUPER } else { CTRL };
+        }
+        Self { modifiers, key: self.key.clone() }
+    }
+
+    fn to_accelerator(&self) -> String {
+        let names = [(CMD_OR_CTRL, "CmdOrCtrl"), (SUPER, "Super"), (CTRL, "Ctrl"), (ALT, "Alt"), (SHIFT, "Shift")];
+        let mut parts: Vec<&str> =
+            names.iter().filter(|(bit, _)| self.modifiers & bit != 0).map(|(_, name)| *name).collect();
+        parts.push(&self.key);
+        parts.join("+")
+    }
+}
+
+/// Hotkeys the browser itself uses.
+fn reserved_hotkeys() -> Vec<String> {
+    let mut reserved: Vec<String> =
+        commands::COMMANDS.iter().filter_map(|c| c.accelerator.map(str::to_string)).collect();
+    reserved.extend((1..=9).map(|n| format!("CmdOrCtrl+{}", n))); // Window menu: Tab 1-9
+    reserved
+}
+
+/// Normalizes a hotkey and checks it doesn't clash with `taken` on either platform.
+pub fn validate_hotkey(accelerator: &str, taken: &[String]) -> Result<String, String> {
+    let hotkey = Hotkey::parse(accelerator)?;
+    for other in taken.iter().filter_map(|t| Hotkey::parse(t).ok()) {
+        if [true, false].iter().any(|&macos| hotkey.resolved(macos) == other.resolved(macos)) {
+            return Err(format!("{} is already in use", other.to_accelerator()));
+        }
+    }
+    Ok(hotkey.to_accelerator())
+}
+
+pub fn validate_snippet(input: SnippetInput, others: &[Snippet]) -> Result<SnippetInput, String> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("The name must be 1 to {} characters", MAX_NAME_LEN));
+    }
+    if input.code.trim().is_empty() {
+        return Err("The snippet is empty".to_string());
+    }
+    if input.code.len() > MAX_CODE_LEN {
+        return Err("The snippet is too long".to_string());
+    }
+    let domain = match input.domain.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(domain) => {
+            Some(AdBlockManager::normalize_domain(domain).ok_or_else(|| format!("Invalid site: {}", domain))?)
+        }
+        None => None,
+    };
+    let hotkey = match input.hotkey.as_deref().map(str::trim).filter(|h| !h.is_empty()) {
+        Some(hotkey) => {
+            let mut taken = reserved_hotkeys();
+            taken.extend(others.iter().filter(|s| Some(s.id) != input.id).filter_map(|s| s.hotkey.clone()));
+            Some(validate_hotkey(hotkey, &taken)?)
+        }
+        None => None,
+    };
+    Ok(SnippetInput { name, domain, hotkey, ..input })
+}
+
+/// Whether a snippet may run on a page of this host.
+pub fn applies_to(snippet: &Snippet, host: Option<&str>) -> bool {
+    match (&snippet.domain, host) {
+        (None, _) => true,
+        (Some(domain), Some(host)) => {
+            let host = host.to_lowercase();
+            host == *domain || host.ends_with(&format!(".{}", domain))
+        }
+        (Some(_), None) => false,
+    }
+}
+
+/// The script that runs a snippet in the page.
+pub fn snippet_script(snippet: &Snippet) -> String {
+    match snippet.kind {
+        SnippetKind::Js => format!(
+            "(function() {{ try {{\n{}\n}} catch (e) {{ console.error('[Snippet] ' + {} + ' failed:', e); }} }})();",
+            snippet.code,
+            serde_json::to_string(&snippet.name).unwrap_or_default()
+        ),
+        SnippetKind::Css => format!(
+            "(function() {{ const id = 'sovereign-snippet-{}'; const existing = document.getElementById(id); \
+             if (existing) {{ existing.remove(); return; }} const style = document.createElement('style'); \
+             style.id = id; style.textContent = {}; (document.head || document.documentElement).appendChild(style); }})();",
+            snippet.id,
+            serde_json::to_string(&snippet.code).unwrap_or_default()
+        ),
+    }
+}
+
+pub struct SnippetsManager {
+    path: PathBuf,
+    snippets: Mutex<Vec<Snippet>>,
+}
+
+impl SnippetsManager {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(SNIPPETS_FILE);
+        let snippets: Vec<Snippet> =
+            fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        if !snippets.is_empty() {
+            println!("[Snippets] Loaded {} snippets", snippets.len());
+        }
+        Self { path, snippets: Mutex::new(snippets) }
+    }
+
+    fn save(&self, snippets: &[Snippet]) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(snippets).unwrap_or_default()) {
+            eprintln!("[Snippets] Failed to save snippets: {}", e);
+        }
+    }
+
+    pub fn list(&self) -> Vec<Snippet> {
+        self.snippets.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: u64) -> Option<Snippet> {
+        self.snippets.lock().unwrap().iter().find(|s| s.id == id).cloned()
+    }
+
+    pub fn upsert(&self, input: SnippetInput) -> Result<Snippet, String> {
+        let mut snippets = self.snippets.lock().unwrap();
+        let input = validate_snippet(input, &snippets)?;
+        let id = match input.id {
+            Some(id) if snippets.iter().any(|s| s.id == id) => id,
+            Some(id) => return Err(format!("No snippet {}", id)),
+            None => snippets.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+        };
+        let snippet = Snippet {
+            id,
+            name: input.name,
+            kind: input.kind,
+            code: input.code,
+            domain: input.domain,
+            hotkey: input.hotkey,
+        };
+        match snippets.iter_mut().find(|s| s.id == id) {
+            Some(existing) => *existing = snippet.clone(),
+            None => snippets.push(snippet.clone()),
+        }
+        self.save(&snippets);
+        Ok(snippet)
+    }
+
+    pub fn remove(&self, id: u64) -> Result<(), String> {
+        let mut snippets = self.snippets.lock().unwrap();
+        let before = snippets.len();
+        snippets.retain(|s| s.id != id);
+        if snippets.len() == before {
+            return Err(format!("No snippet {}", id));
+        }
+        self.save(&snippets);
+        Ok(())
+    }
+}
+
+/// Refills the Snippets menu, so its accelerators match the saved hotkeys.
+pub fn rebuild_menu(app: &AppHandle) -> Result<(), String> {
+    let Some(state) = app.try_state::<AppState>() else {
+        return Ok(());
+    };
+    let Some(MenuItemKind::Submenu(submenu)) = app.menu().and_then(|menu| menu.get(MENU_ID)) else {
+        return Ok(());
+    };
+    for item in submenu.items().map_err(|e| e.to_string())? {
+        submenu.remove(&item).map_err(|e| e.to_string())?;
+    }
+    let snippets = state.snippets.list();
+    if snippets.is_empty() {
+        let placeholder = MenuItemBuilder::new("No Snippets").enabled(false).build(app).map_err(|e| e.to_string())?;
+        return submenu.append(&placeholder).map_err(|e| e.to_string());
+    }
+    for snippet in snippets {
+        let mut builder = MenuItemBuilder::with_id(format!("{}{}", MENU_ITEM_PREFIX, snippet.id), &snippet.name);
+        if let Some(hotkey) = &snippet.hotkey {
+            builder = builder.accelerator(hotkey);
+        }
+        let item = builder.build(app).map_err(|e| e.to_string())?;
+        submenu.append(&item).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Runs a snippet on the active tab.
+pub fn run(app: &AppHandle, state: &AppState, id: u64) -> Result<(), BrowserError> {
+    let snippet = state.snippets.get(id).ok_or_else(|| BrowserError::NotFound(format!("No snippet {}", id)))?;
+    let label = {
+        let active = state.active_tab_id.lock().unwrap();
+        let tabs = state.tabs.lock().unwrap();
+        active.as_ref().and_then(|id| tabs.iter().find(|t| &t.id == id).map(|t| t.webview_label.clone()))
+    };
+    let webview =
+        label.and_then(|l| app.get_webview(&l)).ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+    let url = webview.url()?;
+    if !applies_to(&snippet, url.host_str()) {
+        let domain = snippet.domain.as_deref().unwrap_or_default();
+        return Err(BrowserError::NotAllowed(format!("{} only runs on {}", snippet.name, domain)));
+    }
+    println!("[Snippets] Running {} on {}", snippet.name, url);
+    Ok(webview.eval(&snippet_script(&snippet))?)
+}
+
+#[tauri::command]
+pub fn list_snippets(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<Vec<Snippet>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.snippets.list())
+}
+
+#[tauri::command]
+pub fn save_snippet(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    snippet: SnippetInput,
+) -> Result<Snippet, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let snippet = state.snippets.upsert(snippet).map_err(BrowserError::InvalidInput)?;
+    rebuild_menu(&app).map_err(BrowserError::Webview)?;
+    Ok(snippet)
+}
+
+#[tauri::command]
+pub fn delete_snippet(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: u64,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    state.snippets.remove(id).map_err(BrowserError::NotFound)?;
+    rebuild_menu(&app).map_err(BrowserError::Webview)
+}
+
+#[tauri::command]
+pub fn run_snippet(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: u64,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    run(&app, &state, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn input(name: &str, hotkey: Option<&str>) -> SnippetInput {
+        SnippetInput {
+            id: None,
+            name: name.to_string(),
+            kind: SnippetKind::Css,
+            code: "body { max-width: 60em; }".to_string(),
+            domain: None,
+            hotkey: hotkey.map(str::to_string),
+        }
+    }
+
+    #[rstest]
+    #[case("cmdorctrl+shift+1", Ok("CmdOrCtrl+Shift+1"))]
+    #[case("Shift + Option + k", Ok("Alt+Shift+K"))]
+    #[case("f5", Ok("F5"))]
+    #[case("Ctrl+pageup", Ok("Ctrl+PageUp"))]
+    #[case("Shift+A", Err(()))]
+    #[case("Hyper+A", Err(()))]
+    #[case("CmdOrCtrl+", Err(()))]
+    fn test_validate_hotkey(#[case] hotkey: &str, #[case] expected: Result<&str, ()>) {
+        assert_eq!(validate_hotkey(hotkey, &[]).map_err(|_| ()), expected.map(str::to_string));
+    }
+
+    #[rstest]
+    #[case("CmdOrCtrl+T")] // New Tab
+    #[case("Cmd+T")] // The same on macOS
+    #[case("Ctrl+Shift+T")] // Reopen Closed Tab elsewhere
+    #[case("CmdOrCtrl+Alt+I")] // Developer Tools, written with Option
+    #[case("CmdOrCtrl+4")] // Tab 4
+    fn test_reserved_hotkeys_are_refused(#[case] hotkey: &str) {
+        assert!(validate_hotkey(hotkey, &reserved_hotkeys()).is_err());
+    }
+
+    #[test]
+    fn test_snippet_hotkeys_must_be_unique() {
+        let dir = TempDir::new().unwrap();
+        let manager = SnippetsManager::new(dir.path().to_path_buf());
+        let wide = manager.upsert(input("Wide", Some("CmdOrCtrl+Alt+W"))).unwrap();
+        assert!(manager.upsert(input("Other", Some("cmdorctrl+option+w"))).is_err());
+
+        // Saving a snippet again keeps its own hotkey
+        let renamed = manager.upsert(SnippetInput { id: Some(wide.id), ..input("Wider", Some("CmdOrCtrl+Alt+W")) });
+        assert_eq!(renamed.map(|s| s.name), Ok("Wider".to_string()));
+        assert_eq!(SnippetsManager::new(dir.path().to_path_buf()).list(), manager.list());
+    }
+
+    #[rstest]
+    #[case(None, Some("example.com"), true)]
+    #[case(Some("example.com"), Some("docs.example.com"), true)]
+    #[case(Some("example.com"), Some("notexample.com"), false)]
+    #[case(Some("example.com"), None, false)]
+    fn test_applies_to(#[case] domain: Option<&str>, #[case] host: Option<&str>, #[case] expected: bool) {
+        let snippet = Snippet {
+            id: 1,
+            name: "Test".to_string(),
+            kind: SnippetKind::Js,
+            code: "1".to_string(),
+            domain: domain.map(str::to_string),
+            hotkey: None,
+        };
+        assert_eq!(applies_to(&snippet, host), expected);
+    }
+
+    #[test]
+    fn test_css_snippet_script_escapes_code() {
+        let snippet = Snippet {
+            id: 7,
+            name: "Quotes".to_string(),
+            kind: SnippetKind::Css,
+            code: "a::after { content: \"'\" }".to_string(),
+            domain: None,
+            hotkey: None,
+        };
+        let script = snippet_script(&snippet);
+        assert!(script.contains("'sovereign-snippet-7'"));
+        assert!(script.contains(r#"style.textContent = "a::after { content: \"'\" }";"#));
+    }
+}
@@ -0,0 +1,179 @@
+// Split view: two tabs side by side in the main window.
+//
+// `enter_split_view(left_tab, right_tab)` (main.rs) records the pair here and
+// switches to the left one; from then on the pair is laid out together (see
+// modules::layout) whenever either is shown or the window resizes. The divider
+// between them is drawn by the toolbar page, which shows through the gap, and
+// dragging it sets the left pane's share with `set_split_ratio`. The active tab
+// stays one of the two, so toolbar actions go to whichever was switched to last.
+//
+// Switching to any other tab, closing or detaching one of the pair, or
+// `exit_split_view` ends the split; the active pane then fills the content area.
+// Splits aren't saved with the session.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::layout;
+use crate::modules::tabs::{self, WindowMetrics};
+use crate::state::AppState;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Split {
+    pub left: String, // Tab ids
+    pub right: String,
+    pub ratio: f64, // Left pane's share of the width, see layout::split_panes
+}
+
+impl Split {
+    pub fn contains(&self, tab_id: &str) -> bool {
+        self.left == tab_id || self.right == tab_id
+    }
+}
+
+pub struct SplitViewManager {
+    split: Mutex<Option<Split>>,
+}
+
+impl SplitViewManager {
+    pub fn new() -> Self {
+        Self { split: Mutex::new(None) }
+    }
+
+    pub fn get(&self) -> Option<Split> {
+        self.split.lock().unwrap().clone()
+    }
+
+    pub fn contains(&self, tab_id: &str) -> bool {
+        self.split.lock().unwrap().as_ref().is_some_and(|s| s.contains(tab_id))
+    }
+
+    pub fn set(&self, left: &str, right: &str) -> Result<(), String> {
+        if left == right {
+            return Err("Split view needs two different tabs".to_string());
+        }
+        *self.split.lock().unwrap() = Some(Split { left: left.to_string(), right: right.to_string(), ratio: 0.5 });
+        Ok(())
+    }
+
+    pub fn set_ratio(&self, ratio: f64) -> bool {
+        match self.split.lock().unwrap().as_mut() {
+            Some(split) => {
+                split.ratio = layout::clamp_split_ratio(ratio);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn take(&self) -> Option<Split> {
+        self.split.lock().unwrap().take()
+    }
+}
+
+impl Default for SplitViewManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn webview_of(app: &AppHandle, state: &AppState, tab_id: &str) -> Option<tauri::Webview> {
+    let label = state.tabs.lock().unwrap().iter().find(|t| t.id == tab_id).map(|t| t.webview_label.clone())?;
+    app.get_webview(&label)
+}
+
+pub fn emit(app: &AppHandle, state: &AppState) {
+    let _ = app.emit_to("main", "split-view", state.split_view.get());
+}
+
+/// Places and shows both panes. Does nothing outside split view.
+pub fn arrange(app: &AppHandle, state: &AppState) {
+    if let Some(metrics) = tabs::main_metrics(app) {
+        arrange_in(app, state, metrics);
+    }
+}
+
+/// `arrange` for a main window of `metrics`.
+pub fn arrange_in(app: &AppHandle, state: &AppState, metrics: WindowMetrics) {
+    let Some(split) = state.split_view.get() else {
+        return;
+    };
+    let chrome = tabs::chrome_layout(state);
+    let WindowMetrics { size, scale } = metrics;
+    let (left, right) = layout::split_panes(size.width, size.height, scale, chrome, split.ratio);
+    for (tab_id, pane) in [(&split.left, left), (&split.right, right)] {
+        if let Some(webview) = webview_of(app, state, tab_id) {
+            let _ = webview.set_bounds(tabs::pane_bounds(pane));
+            let _ = webview.show();
+        }
+    }
+}
+
+/// Ends split view: the active pane fills the content area, the other is hidden.
+pub fn exit(app: &AppHandle, state: &AppState) -> bool {
+    let Some(split) = state.split_view.take() else {
+        return false;
+    };
+    let active = state.active_tab_id.lock().unwrap().clone();
+    let mut full = tabs::content_bounds(app, state);
+    for tab_id in [&split.left, &split.right] {
+        let Some(webview) = webview_of(app, state, tab_id) else {
+            continue;
+        };
+        if active.as_ref() == Some(tab_id) {
+            if let Some(full) = full.take() {
+                let _ = webview.set_bounds(full);
+            }
+        } else {
+            let _ = webview.hide();
+        }
+    }
+    println!("[SplitView] Closed split of {} and {}", split.left, split.right);
+    emit(app, state);
+    true
+}
+
+/// Ends split view if the tab is one of the pair (it's closing or leaving the window).
+pub fn forget_tab(app: &AppHandle, state: &AppState, tab_id: &str) {
+    if state.split_view.contains(tab_id) {
+        exit(app, state);
+    }
+}
+
+/// Divider drags from the toolbar page.
+#[tauri::command]
+pub fn set_split_ratio(app: AppHandle, state: tauri::State<AppState>, ratio: f64) -> Result<(), BrowserError> {
+    if !state.split_view.set_ratio(ratio) {
+        return Err(BrowserError::NotFound("Not in split view".to_string()));
+    }
+    arrange(&app, &state);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_split_view(state: tauri::State<AppState>) -> Result<Option<Split>, BrowserError> {
+    Ok(state.split_view.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_manager() {
+        let manager = SplitViewManager::new();
+        assert!(manager.set("tab-1", "tab-1").is_err());
+        assert!(!manager.set_ratio(0.3)); // Not split yet
+
+        manager.set("tab-1", "tab-2").unwrap();
+        assert!(manager.contains("tab-2") && !manager.contains("tab-3"));
+        assert!(manager.set_ratio(0.99));
+        assert_eq!(manager.get().map(|s| s.ratio), Some(layout::MAX_SPLIT_RATIO));
+
+        assert!(manager.take().is_some());
+        assert!(manager.get().is_none() && !manager.contains("tab-1"));
+    }
+}
@@ -0,0 +1,171 @@
+// Tab stashes - named collections of closed tabs kept for "read later" triage.
+// Lighter than a session: only URL, title and favicon are kept, and a stash is
+// removed once it is restored.
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::profile;
+use crate::state::Tab;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+const DEFAULT_STASH_NAME: &str = "Stashed tabs";
+const MAX_STASH_NAME_LEN: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StashedTab {
+    pub url: String,
+    pub title: String,
+    pub favicon: Option<String>,
+}
+
+impl From<&Tab> for StashedTab {
+    fn from(tab: &Tab) -> Self {
+        StashedTab {
+            url: tab.url.clone(),
+            title: tab.custom_title.clone().unwrap_or_else(|| tab.title.clone()),
+            favicon: tab.favicon.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabStash {
+    pub id: String,
+    pub name: String,
+    pub created_at: SystemTime,
+    pub tabs: Vec<StashedTab>,
+}
+
+impl TabStash {
+    /// Blank names fall back to a default; long names are truncated.
+    pub fn new(name: &str, tabs: Vec<StashedTab>, created_at: SystemTime) -> Self {
+        let trimmed = name.trim();
+        let name = if trimmed.is_empty() {
+            DEFAULT_STASH_NAME.to_string()
+        } else {
+            trimmed.chars().take(MAX_STASH_NAME_LEN).collect()
+        };
+        let nanos = created_at.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+
+        TabStash {
+            id: format!("stash-{}", nanos),
+            name,
+            created_at,
+            tabs,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StashStore {
+    pub stashes: Vec<TabStash>,
+}
+
+impl StashStore {
+    fn get_path(app: &AppHandle) -> PathBuf {
+        profile::data_dir(app)
+            .expect("Failed to get app data dir")
+            .join("stashes.json")
+    }
+
+    pub fn load(app: &AppHandle) -> Self {
+        let path = Self::get_path(app);
+
+        if path.exists() {
+            match fs::read_to_string(&path) {
+                Ok(json) => {
+                    match serde_json::from_str(&json) {
+                        Ok(store) => return store,
+                        Err(e) => eprintln!("Failed to parse stashes.json: {}", e),
+                    }
+                }
+                Err(e) => eprintln!("Failed to read stashes.json: {}", e),
+            }
+        }
+
+        StashStore::default()
+    }
+
+    pub fn save(&self, app: &AppHandle) -> Result<(), String> {
+        let path = Self::get_path(app);
+        let tmp_path = path.with_extension("tmp");
+        let parent = path.parent().unwrap();
+
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+
+        // Atomic write: tmp + rename (pattern from settings.rs)
+        fs::write(&tmp_path, json).map_err(|e| e.to_string())?;
+        fs::rename(tmp_path, path).map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Newest stash first.
+    pub fn push(&mut self, stash: TabStash) {
+        self.stashes.insert(0, stash);
+    }
+
+    /// Remove and return a stash by id.
+    pub fn take(&mut self, id: &str) -> Option<TabStash> {
+        let index = self.stashes.iter().position(|s| s.id == id)?;
+        Some(self.stashes.remove(index))
+    }
+}
+
+/// Tauri command: list saved stashes, newest first.
+#[tauri::command]
+pub fn get_stashes(app: AppHandle, webview: tauri::Webview) -> Result<Vec<TabStash>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(StashStore::load(&app).stashes)
+}
+
+/// Tauri command: discard a stash without opening its tabs.
+#[tauri::command]
+pub fn delete_stash(app: AppHandle, webview: tauri::Webview, stash_id: String) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let mut store = StashStore::load(&app);
+    store.take(&stash_id).ok_or_else(|| BrowserError::NotFound("Stash not found".to_string()))?;
+    store.save(&app).map_err(BrowserError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn stashed(url: &str) -> StashedTab {
+        StashedTab { url: url.to_string(), title: url.to_string(), favicon: None }
+    }
+
+    #[test]
+    fn test_stash_name_normalization() {
+        let now = UNIX_EPOCH + Duration::from_secs(42);
+        assert_eq!(TabStash::new("  Reading  ", vec![], now).name, "Reading");
+        assert_eq!(TabStash::new("   ", vec![], now).name, DEFAULT_STASH_NAME);
+        assert_eq!(TabStash::new(&"x".repeat(500), vec![], now).name.len(), MAX_STASH_NAME_LEN);
+        assert_eq!(TabStash::new("a", vec![], now).id, "stash-42000000000");
+    }
+
+    #[test]
+    fn test_push_and_take() {
+        let mut store = StashStore::default();
+        let first = TabStash::new("first", vec![stashed("https://a.com/")], UNIX_EPOCH + Duration::from_secs(1));
+        let second = TabStash::new("second", vec![stashed("https://b.com/")], UNIX_EPOCH + Duration::from_secs(2));
+        let second_id = second.id.clone();
+
+        store.push(first);
+        store.push(second);
+        assert_eq!(store.stashes[0].name, "second");
+
+        let taken = store.take(&second_id).unwrap();
+        assert_eq!(taken.tabs, vec![stashed("https://b.com/")]);
+        assert_eq!(store.stashes.len(), 1);
+        assert!(store.take(&second_id).is_none());
+    }
+}
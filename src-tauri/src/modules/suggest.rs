@@ -0,0 +1,165 @@
+// Opt-in search suggestions from the selected engine's suggest endpoint.
+//
+// PRIVACY NOTICE:
+// Unlike `smart_parse_url`, this module DOES send what the user types to a remote server.
+// It must only be used when `Settings::search_suggestions` is explicitly enabled.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::settings::SearchEngine;
+
+/// Wait this long after the last keystroke before hitting the network.
+pub const DEBOUNCE_MS: u64 = 150;
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_SUGGESTIONS: usize = 4;
+
+pub struct SuggestManager {
+    // Bumped on every request; a fetch whose generation is no longer current is stale.
+    generation: AtomicU64,
+    // Last successful (query, suggestions) pair, merged into the dropdown on re-render
+    cache: Mutex<Option<(String, Vec<String>)>>,
+}
+
+impl SuggestManager {
+    pub fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Cached suggestions, only if they were fetched for exactly this query.
+    pub fn cached(&self, query: &str) -> Option<Vec<String>> {
+        let cache = self.cache.lock().unwrap();
+        match cache.as_ref() {
+            Some((q, suggestions)) if q == query => Some(suggestions.clone()),
+            _ => None,
+        }
+    }
+
+    /// Invalidate any in-flight fetch (e.g. dropdown hidden).
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Debounced fetch with `client` (see `proxy::http_client`).
+    /// Returns None if a newer request superseded this one (before or after the
+    /// network round-trip) or the request failed.
+    pub async fn fetch(&self, engine: &SearchEngine, query: &str, client: reqwest::Client) -> Option<Vec<String>> {
+        let url = engine.suggest_url(query)?;
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        tokio::time::sleep(Duration::from_millis(DEBOUNCE_MS)).await;
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return None;
+        }
+
+        let body = client
+            .get(&url)
+            .send()
+            .await
+            .ok()?
+            .text()
+            .await
+            .ok()?;
+
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return None;
+        }
+
+        let suggestions = parse_suggestions(&body);
+        *self.cache.lock().unwrap() = Some((query.to_string(), suggestions.clone()));
+        Some(suggestions)
+    }
+}
+
+/// Parse the OpenSearch suggestions format used by DuckDuckGo, Google, Bing and Brave:
+/// `["query", ["suggestion 1", "suggestion 2", ...], ...]`
+pub fn parse_suggestions(body: &str) -> Vec<String> {
+    let value: serde_json::Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+
+    value.get(1)
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items.iter()
+                .filter_map(|s| s.as_str())
+                .map(|s| s.to_string())
+                .take(MAX_SUGGESTIONS)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Merge engine suggestions into the omnibox results.
+/// Suggestions go after history entries and before the "Search for ..." fallback.
+/// Any previously merged suggestions are replaced, so re-rendering is idempotent.
+pub fn merge_results(
+    results: Vec<serde_json::Value>,
+    query: &str,
+    suggestions: &[String],
+    engine: &SearchEngine,
+) -> Vec<serde_json::Value> {
+    let mut merged: Vec<serde_json::Value> = results
+        .into_iter()
+        .filter(|r| r.get("type").and_then(|t| t.as_str()) != Some("suggestion"))
+        .collect();
+
+    let insert_at = merged.iter()
+        .position(|r| r.get("type").and_then(|t| t.as_str()) == Some("search"))
+        .unwrap_or(merged.len());
+
+    let query_lower = query.trim().to_lowercase();
+    let items: Vec<serde_json::Value> = suggestions.iter()
+        .filter(|s| s.to_lowercase() != query_lower)
+        .map(|s| serde_json::json!({
+            "type": "suggestion",
+            "title": s,
+            "url": engine.query_url(s),
+            "score": 0
+        }))
+        .collect();
+
+    merged.splice(insert_at..insert_at, items);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+
+    #[test]
+    fn test_parse_opensearch_suggestions() {
+        let body = r#"["rust", ["rust lang", "rust game", "rustup", "rust book", "rust belt"]]"#;
+        let parsed = parse_suggestions(body);
+        assert_eq!(parsed, vec!["rust lang", "rust game", "rustup", "rust book"]);
+
+        assert!(parse_suggestions("not json").is_empty());
+        assert!(parse_suggestions(r#"{"q": "rust"}"#).is_empty());
+    }
+
+    #[test]
+    fn test_merge_inserts_before_search_fallback() {
+        let engine = Settings::default().default_engine();
+        let results = vec![
+            serde_json::json!({ "type": "history", "url": "https://rust-lang.org/" }),
+            serde_json::json!({ "type": "search", "url": "rust" }),
+        ];
+        let suggestions = vec!["rust".to_string(), "rust lang".to_string()];
+
+        let merged = merge_results(results, "rust", &suggestions, &engine);
+        assert_eq!(merged.len(), 3); // exact-query suggestion dropped
+        assert_eq!(merged[1]["type"], "suggestion");
+        assert_eq!(merged[1]["url"], "https://duckduckgo.com/?q=rust%20lang");
+        assert_eq!(merged[2]["type"], "search");
+
+        // Re-merging the merged list does not duplicate suggestions
+        let again = merge_results(merged, "rust", &suggestions, &engine);
+        assert_eq!(again.len(), 3);
+    }
+}
@@ -0,0 +1,232 @@
+// Tab audio indicators and muting.
+//
+// `AUDIO_SCRIPT` watches the page's <video>/<audio> elements, including ones
+// never added to the document (`new Audio()`), and reports through
+// `report_tab_audio` whenever the tab starts or stops making sound. That's
+// `Tab.is_audible`, which the tab strip shows as a speaker. Sound from Web Audio
+// alone isn't seen.
+//
+// `mute_tab`/`unmute_tab` set `Tab.is_muted` and the webview's own page mute:
+// WKWebView's `_setPageMuted:` on macOS and `ICoreWebView2_8::SetIsMuted` on
+// Windows, which also cover Web Audio. WebKitGTK's mute isn't reachable through
+// the webview handle we get, so on Linux the script mutes the media elements
+// instead, again after each page load. A muted tab that is playing stays audible,
+// so the strip can offer to unmute it.
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::BrowserError;
+use crate::modules::tabs;
+use crate::state::{AppState, Tab};
+
+/// Installs `window.__SOVEREIGN_AUDIO__` and reports audibility changes.
+pub const AUDIO_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_AUDIO__ || !window.__TAURI__) return;
+    const media = new Set();
+    let muted = false; // Linux only, see modules::tab_audio
+    let reported = false;
+    let timer = null;
+
+    function audible(m) {
+        return !m.paused && !m.ended && m.volume > 0 && (!m.muted || m.__sovereignMuted);
+    }
+    function check() {
+        clearTimeout(timer);
+        timer = setTimeout(() => {
+            for (const m of media) {
+                if (!m.isConnected && (m.paused || m.ended)) media.delete(m);
+            }
+            const now = [...media].some(audible);
+            if (now === reported) return;
+            reported = now;
+            window.__TAURI__.core.invoke('report_tab_audio', { audible: now }).catch(() => {});
+        }, 250);
+    }
+    function applyMute(m) {
+        if (muted && !m.muted) {
+            m.muted = true;
+            m.__sovereignMuted = true;
+        } else if (!muted && m.__sovereignMuted) {
+            m.__sovereignMuted = false;
+            m.muted = false;
+        }
+    }
+    function track(m) {
+        if (!media.has(m)) {
+            media.add(m);
+            for (const type of ['play', 'pause', 'ended', 'volumechange', 'emptied']) {
+                m.addEventListener(type, check);
+            }
+        }
+        applyMute(m);
+        check();
+    }
+
+    // Media events don't bubble, so catch elements in the document in the capture phase
+    for (const type of ['play', 'playing']) {
+        document.addEventListener(type, e => {
+            if (e.target instanceof HTMLMediaElement) track(e.target);
+        }, true);
+    }
+    const play = HTMLMediaElement.prototype.play;
+    HTMLMediaElement.prototype.play = function() {
+        track(this);
+        return play.apply(this, arguments);
+    };
+
+    window.__SOVEREIGN_AUDIO__ = {
+        setMuted(value) {
+            muted = value;
+            media.forEach(applyMute);
+            document.querySelectorAll('video,audio').forEach(applyMute);
+        }
+    };
+})();
+"#;
+
+/// Records a tab's audibility. Returns the tab's id if it changed.
+pub fn set_audible(tabs: &mut [Tab], webview_label: &str, audible: bool) -> Option<String> {
+    let tab = tabs.iter_mut().find(|t| t.webview_label == webview_label)?;
+    if tab.is_audible == audible {
+        return None;
+    }
+    tab.is_audible = audible;
+    Some(tab.id.clone())
+}
+
+fn emit_tabs(app: &AppHandle, state: &AppState) {
+    let tabs = state.tabs.lock().unwrap();
+    let active_id = state.active_tab_id.lock().unwrap().clone();
+    let _ = app.emit("update-tabs", tabs::update_payload(state, &tabs, active_id));
+}
+
+#[cfg(target_os = "macos")]
+fn set_page_muted(webview: &tauri::Webview, muted: bool) {
+    use objc::runtime::Object;
+    use objc::{msg_send, sel, sel_impl};
+
+    const AUDIO_MUTED: u64 = 1 << 0; // _WKMediaAudioMuted
+    let result = webview.with_webview(move |platform| unsafe {
+        let wk_webview = platform.inner() as *mut Object;
+        let state: u64 = if muted { AUDIO_MUTED } else { 0 };
+        let _: () = msg_send![wk_webview, _setPageMuted: state];
+    });
+    if let Err(e) = result {
+        eprintln!("[TabAudio] Failed to mute page: {}", e);
+    }
+}
+
+#[cfg(windows)]
+fn set_page_muted(webview: &tauri::Webview, muted: bool) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2_8;
+    use windows::core::Interface;
+
+    let result = webview.with_webview(move |platform| unsafe {
+        let muted = platform
+            .controller()
+            .CoreWebView2()
+            .and_then(|core| core.cast::<ICoreWebView2_8>())
+            .and_then(|core| core.SetIsMuted(muted));
+        if let Err(e) = muted {
+            eprintln!("[TabAudio] Failed to mute page: {}", e);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[TabAudio] Failed to mute page: {}", e);
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn set_page_muted(webview: &tauri::Webview, muted: bool) {
+    let _ = webview.eval(&format!("window.__SOVEREIGN_AUDIO__ && window.__SOVEREIGN_AUDIO__.setMuted({});", muted));
+}
+
+/// Re-applies a muted tab's mute to its new page where it's done in the page.
+pub fn on_page_finished(webview: &tauri::Webview, state: &AppState) {
+    if cfg!(any(target_os = "macos", windows)) {
+        return; // The webview's mute outlives navigations
+    }
+    let muted = state.tabs.lock().unwrap().iter().any(|t| t.webview_label == webview.label() && t.is_muted);
+    if muted {
+        set_page_muted(webview, true);
+    }
+}
+
+pub fn set_tab_muted(app: &AppHandle, state: &AppState, tab_id: &str, muted: bool) -> Result<(), BrowserError> {
+    let label = {
+        let mut tabs = state.tabs.lock().unwrap();
+        let tab = tabs.iter_mut().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(tab_id))?;
+        tab.is_muted = muted;
+        tab.webview_label.clone()
+    };
+    if let Some(webview) = app.get_webview(&label) {
+        set_page_muted(&webview, muted);
+    }
+    emit_tabs(app, state);
+    Ok(())
+}
+
+/// Audibility reports from the page.
+#[tauri::command]
+pub fn report_tab_audio(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    audible: bool,
+) -> Result<(), BrowserError> {
+    let changed = set_audible(&mut state.tabs.lock().unwrap(), webview.label(), audible);
+    if changed.is_some() {
+        emit_tabs(&app, &state);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn mute_tab(app: AppHandle, state: tauri::State<AppState>, tab_id: String) -> Result<(), BrowserError> {
+    set_tab_muted(&app, &state, &tab_id, true)
+}
+
+#[tauri::command]
+pub fn unmute_tab(app: AppHandle, state: tauri::State<AppState>, tab_id: String) -> Result<(), BrowserError> {
+    set_tab_muted(&app, &state, &tab_id, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tab(id: &str) -> Tab {
+        Tab {
+            id: id.to_string(),
+            webview_label: format!("webview-{}", id),
+            title: id.to_string(),
+            url: "https://example.com".to_string(),
+            favicon: None,
+            last_accessed: None,
+            is_loading: false,
+            can_go_back: false,
+            can_go_forward: false,
+            last_focus_was_content: false,
+            screenshot: None,
+            unread_count: None,
+            custom_title: None,
+            marker: None,
+            is_audible: false,
+            is_muted: false,
+            load_error: None,
+            window: None,
+            container: None,
+        }
+    }
+
+    #[test]
+    fn test_set_audible_reports_changes_only() {
+        let mut tabs = vec![tab("tab-1"), tab("tab-2")];
+        assert_eq!(set_audible(&mut tabs, "webview-tab-2", true), Some("tab-2".to_string()));
+        assert_eq!(set_audible(&mut tabs, "webview-tab-2", true), None);
+        assert!(tabs[1].is_audible && !tabs[0].is_audible);
+        assert_eq!(set_audible(&mut tabs, "webview-tab-2", false), Some("tab-2".to_string()));
+        assert_eq!(set_audible(&mut tabs, "webview-missing", true), None);
+    }
+}
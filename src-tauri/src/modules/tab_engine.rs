@@ -0,0 +1,368 @@
+// The tab lifecycle: adding, switching and closing tabs, which tab is shown
+// next, and what happens when the last one closes.
+//
+// TabEngine works on the shared tab list and active tab id. Everything with a
+// side effect goes through a TabHost: `tabs::TauriTabHost` shows, hides and
+// closes the real webviews and emits "update-tabs", and the tests use a host
+// that records the calls, so the lifecycle can be tested without a window.
+//
+// Locking: the tab list, then the active id, the same order as every
+// "update-tabs" emit. A switch checks the target and sets the active id under
+// both locks, so a tab closing at the same time can't leave the active id
+// pointing at a tab that's gone.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+use crate::error::BrowserError;
+use crate::modules::tab_windows;
+use crate::state::Tab;
+
+/// The side effects of the lifecycle.
+pub trait TabHost {
+    fn show_webview(&self, tab: &Tab);
+    fn hide_webview(&self, tab: &Tab);
+    /// Destroys the tab's webview and anything that belongs to it (inspector, detached window).
+    fn close_webview(&self, tab: &Tab);
+    /// Whether the tab is a pane of split view, which `arrange_split` lays out.
+    fn in_split(&self, tab_id: &str) -> bool;
+    fn arrange_split(&self);
+    fn emit_tabs(&self, tabs: &[Tab], active_id: Option<String>);
+}
+
+/// What `close` did after removing the tab.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AfterClose {
+    Unchanged,        // It wasn't the active tab
+    Switched(String), // Switched to the nearest main-window tab
+    NewTab,           // It was the last one; the caller opens a new tab
+}
+
+#[derive(Debug, Clone)]
+pub struct Closed {
+    pub tab: Tab,
+    pub next: AfterClose,
+}
+
+pub struct TabEngine<'a, H: TabHost> {
+    tabs: &'a Mutex<Vec<Tab>>,
+    active: &'a Mutex<Option<String>>,
+    host: H,
+}
+
+impl<'a, H: TabHost> TabEngine<'a, H> {
+    pub fn new(tabs: &'a Mutex<Vec<Tab>>, active: &'a Mutex<Option<String>>, host: H) -> Self {
+        Self { tabs, active, host }
+    }
+
+    fn lock(&self) -> (MutexGuard<'a, Vec<Tab>>, MutexGuard<'a, Option<String>>) {
+        let tabs = self.tabs.lock().unwrap();
+        let active = self.active.lock().unwrap();
+        (tabs, active)
+    }
+
+    fn emit(&self) {
+        let (tabs, active) = self.lock();
+        self.host.emit_tabs(&tabs, active.clone());
+    }
+
+    /// Adds a tab at the end of the strip; `switch` to show it.
+    pub fn add(&self, tab: Tab) {
+        self.tabs.lock().unwrap().push(tab);
+    }
+
+    /// Adds a tab at the end of the strip without showing it; the active tab stays.
+    pub fn add_in_background(&self, tab: Tab) {
+        self.host.hide_webview(&tab);
+        self.add(tab);
+        self.emit();
+    }
+
+    /// Makes a tab the active one: hides the one before it (unless it stays
+    /// visible as a split pane) and shows it.
+    pub fn switch(&self, tab_id: &str) -> Result<(), BrowserError> {
+        let (previous, target) = {
+            let (mut tabs, mut active) = self.lock();
+            let target = tabs.iter_mut().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(tab_id))?;
+            target.last_accessed = Some(Instant::now());
+            let target = target.clone();
+            let previous = active
+                .replace(tab_id.to_string())
+                .filter(|id| id != tab_id)
+                .and_then(|id| tabs.iter().find(|t| t.id == id).cloned());
+            (previous, target)
+        };
+
+        if let Some(previous) = previous.filter(|p| !self.host.in_split(&p.id)) {
+            self.host.hide_webview(&previous);
+        }
+        if self.host.in_split(tab_id) {
+            self.host.arrange_split();
+        } else {
+            self.host.show_webview(&target);
+        }
+        self.emit();
+        Ok(())
+    }
+
+    /// Removes a tab and destroys its webview. Closing the active tab switches
+    /// to its right neighbour in the main window, else the left one; closing the
+    /// last one leaves no active tab and asks for a new one. None if there's no
+    /// such tab.
+    pub fn close(&self, tab_id: &str) -> Result<Option<Closed>, BrowserError> {
+        let (tab, was_active, neighbour) = {
+            let (mut tabs, mut active) = self.lock();
+            let Some(index) = tabs.iter().position(|t| t.id == tab_id) else {
+                return Ok(None);
+            };
+            let neighbour = tab_windows::main_neighbour(&tabs, index);
+            let tab = tabs.remove(index);
+            let was_active = active.as_deref() == Some(tab_id);
+            if was_active {
+                *active = None;
+            }
+            (tab, was_active, neighbour)
+        };
+        self.host.close_webview(&tab);
+
+        let next = match (was_active, neighbour) {
+            (false, _) => AfterClose::Unchanged,
+            (true, Some(next_id)) => {
+                self.switch(&next_id)?;
+                AfterClose::Switched(next_id)
+            }
+            (true, None) => AfterClose::NewTab,
+        };
+        self.emit();
+        Ok(Some(Closed { tab, next }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
+    #[derive(Default)]
+    struct RecordingHost {
+        calls: RefCell<Vec<String>>,
+        visible: RefCell<HashSet<String>>,
+        split: Vec<String>,
+    }
+
+    impl TabHost for &RecordingHost {
+        fn show_webview(&self, tab: &Tab) {
+            self.calls.borrow_mut().push(format!("show {}", tab.id));
+            self.visible.borrow_mut().insert(tab.id.clone());
+        }
+        fn hide_webview(&self, tab: &Tab) {
+            self.calls.borrow_mut().push(format!("hide {}", tab.id));
+            self.visible.borrow_mut().remove(&tab.id);
+        }
+        fn close_webview(&self, tab: &Tab) {
+            self.calls.borrow_mut().push(format!("close {}", tab.id));
+            self.visible.borrow_mut().remove(&tab.id);
+        }
+        fn in_split(&self, tab_id: &str) -> bool {
+            self.split.iter().any(|id| id == tab_id)
+        }
+        fn arrange_split(&self) {
+            self.calls.borrow_mut().push("arrange".to_string());
+            self.visible.borrow_mut().extend(self.split.iter().cloned());
+        }
+        fn emit_tabs(&self, _tabs: &[Tab], active_id: Option<String>) {
+            self.calls.borrow_mut().push(format!("emit {}", active_id.unwrap_or_default()));
+        }
+    }
+
+    impl RecordingHost {
+        fn take_calls(&self) -> Vec<String> {
+            self.calls.take()
+        }
+    }
+
+    fn tab(id: &str, detached: bool) -> Tab {
+        Tab {
+            id: id.to_string(),
+            webview_label: format!("webview-{}", id),
+            title: id.to_string(),
+            url: "https://example.com".to_string(),
+            favicon: None,
+            last_accessed: None,
+            is_loading: false,
+            can_go_back: false,
+            can_go_forward: false,
+            last_focus_was_content: false,
+            screenshot: None,
+            unread_count: None,
+            custom_title: None,
+            marker: None,
+            is_audible: false,
+            is_muted: false,
+            load_error: None,
+            window: detached.then(|| tab_windows::window_label(id)),
+            container: None,
+        }
+    }
+
+    fn state(ids: &[&str]) -> (Mutex<Vec<Tab>>, Mutex<Option<String>>) {
+        (Mutex::new(ids.iter().map(|id| tab(id, false)).collect()), Mutex::new(None))
+    }
+
+    #[test]
+    fn test_switch_hides_previous_and_shows_target() {
+        let (tabs, active) = state(&["a", "b"]);
+        let host = RecordingHost::default();
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch("a").unwrap();
+        assert_eq!(host.take_calls(), vec!["show a", "emit a"]);
+
+        engine.switch("b").unwrap();
+        assert_eq!(host.take_calls(), vec!["hide a", "show b", "emit b"]);
+        assert!(tabs.lock().unwrap()[1].last_accessed.is_some());
+
+        // Switching to the active tab doesn't hide it first
+        engine.switch("b").unwrap();
+        assert_eq!(host.take_calls(), vec!["show b", "emit b"]);
+    }
+
+    #[test]
+    fn test_add_in_background_keeps_the_active_tab() {
+        let (tabs, active) = state(&["a"]);
+        let host = RecordingHost::default();
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch("a").unwrap();
+        host.take_calls();
+
+        engine.add_in_background(tab("b", false));
+        assert_eq!(host.take_calls(), vec!["hide b", "emit a"]);
+        assert_eq!(active.lock().unwrap().as_deref(), Some("a"));
+        assert_eq!(tabs.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_switch_to_missing_tab_changes_nothing() {
+        let (tabs, active) = state(&["a"]);
+        let host = RecordingHost::default();
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch("a").unwrap();
+        host.take_calls();
+
+        assert_eq!(engine.switch("gone"), Err(BrowserError::tab_not_found("gone")));
+        assert_eq!(active.lock().unwrap().as_deref(), Some("a"));
+        assert!(host.take_calls().is_empty());
+    }
+
+    #[test]
+    fn test_switch_within_split_keeps_both_panes() {
+        let (tabs, active) = state(&["a", "b", "c"]);
+        let host = RecordingHost { split: vec!["a".to_string(), "b".to_string()], ..Default::default() };
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch("a").unwrap();
+        engine.switch("b").unwrap();
+        assert_eq!(host.take_calls(), vec!["arrange", "emit a", "arrange", "emit b"]);
+    }
+
+    #[rstest]
+    #[case(&["a", "b", "c"], "b", AfterClose::Switched("c".to_string()))] // Right neighbour
+    #[case(&["a", "b", "c"], "c", AfterClose::Switched("b".to_string()))] // Else the left one
+    #[case(&["a"], "a", AfterClose::NewTab)] // Last tab
+    fn test_close_active_picks_next(#[case] ids: &[&str], #[case] closing: &str, #[case] expected: AfterClose) {
+        let (tabs, active) = state(ids);
+        let host = RecordingHost::default();
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch(closing).unwrap();
+
+        let closed = engine.close(closing).unwrap().unwrap();
+        assert_eq!(closed.tab.id, closing);
+        assert_eq!(closed.next, expected);
+        let expected_active = match expected {
+            AfterClose::Switched(id) => Some(id),
+            _ => None,
+        };
+        assert_eq!(*active.lock().unwrap(), expected_active);
+        assert!(!host.visible.borrow().contains(closing));
+    }
+
+    #[test]
+    fn test_close_skips_detached_tabs() {
+        let tabs = Mutex::new(vec![tab("a", false), tab("b", false), tab("c", true)]);
+        let active = Mutex::new(None);
+        let host = RecordingHost::default();
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch("b").unwrap();
+        assert_eq!(engine.close("b").unwrap().unwrap().next, AfterClose::Switched("a".to_string()));
+
+        // A detached tab isn't a main window tab to fall back on
+        assert_eq!(engine.close("a").unwrap().unwrap().next, AfterClose::NewTab);
+    }
+
+    #[test]
+    fn test_close_inactive_or_missing_tab() {
+        let (tabs, active) = state(&["a", "b"]);
+        let host = RecordingHost::default();
+        let engine = TabEngine::new(&tabs, &active, &host);
+        engine.switch("a").unwrap();
+        host.take_calls();
+
+        assert_eq!(engine.close("b").unwrap().unwrap().next, AfterClose::Unchanged);
+        assert_eq!(host.take_calls(), vec!["close b", "emit a"]);
+        assert!(engine.close("b").unwrap().is_none());
+        assert!(host.take_calls().is_empty());
+    }
+
+    /// Random add/switch/close sequences: the active tab always exists and is
+    /// the only visible webview, and closing the active tab only asks for a new
+    /// one when no tab is left.
+    #[test]
+    fn test_lifecycle_invariants() {
+        let mut seed: u64 = 0x5eed;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+
+        for _ in 0..50 {
+            let (tabs, active) = state(&[]);
+            let host = RecordingHost::default();
+            let engine = TabEngine::new(&tabs, &active, &host);
+            let mut created = 0;
+
+            for _ in 0..40 {
+                let ids: Vec<String> = tabs.lock().unwrap().iter().map(|t| t.id.clone()).collect();
+                match next(3) {
+                    0 => {
+                        created += 1;
+                        let id = format!("tab-{}", created);
+                        engine.add(tab(&id, false));
+                        engine.switch(&id).unwrap();
+                    }
+                    1 if !ids.is_empty() => engine.switch(&ids[next(ids.len())]).unwrap(),
+                    2 if !ids.is_empty() => {
+                        let was_active = active.lock().unwrap().clone();
+                        let closing = &ids[next(ids.len())];
+                        let closed = engine.close(closing).unwrap().unwrap();
+                        let left = tabs.lock().unwrap().len();
+                        match closed.next {
+                            AfterClose::NewTab => assert_eq!(left, 0),
+                            AfterClose::Switched(_) => assert_eq!(was_active.as_ref(), Some(closing)),
+                            AfterClose::Unchanged => assert_ne!(was_active.as_ref(), Some(closing)),
+                        }
+                    }
+                    _ => {}
+                }
+
+                let tabs = tabs.lock().unwrap();
+                let active = active.lock().unwrap().clone();
+                match &active {
+                    Some(id) => assert!(tabs.iter().any(|t| &t.id == id)),
+                    None => assert!(tabs.is_empty()),
+                }
+                let visible: Vec<String> = host.visible.borrow().iter().cloned().collect();
+                assert_eq!(visible, active.into_iter().collect::<Vec<_>>());
+            }
+        }
+    }
+}
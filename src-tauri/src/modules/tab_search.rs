@@ -0,0 +1,234 @@
+// Tab search: fuzzy matching over open and recently closed tabs.
+//
+// `search_open_tabs` backs the tab search overlay. Every word of the query has
+// to match the tab's title or URL, either as a substring or as letters in order
+// ("gh iss" finds "GitHub Issues"); substrings, word starts and runs of letters
+// score higher, and titles count more than URLs. Open tabs come first, best match
+// first (most recently used on ties); recently closed tabs follow as secondary
+// results, minus any whose URL is still open. An empty query lists open tabs by
+// recent use. Closed results reopen through `restore_closed_tab_by_id`.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::{AppState, ClosedTab, Tab};
+
+const MAX_OPEN_RESULTS: usize = 50;
+const MAX_CLOSED_RESULTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TabSearchKind {
+    Open,
+    Closed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabSearchResult {
+    pub kind: TabSearchKind,
+    pub tab_id: String, // For closed tabs, the id they had when open
+    pub title: String,
+    pub url: String,
+    pub favicon: Option<String>,
+    pub score: u32,
+}
+
+fn is_word_start(chars: &[char], i: usize) -> bool {
+    i == 0 || !chars[i - 1].is_alphanumeric()
+}
+
+/// Scores one query word against a text, None if it doesn't match.
+pub fn fuzzy_score(word: &str, text: &str) -> Option<u32> {
+    let word: Vec<char> = word.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    if word.is_empty() {
+        return Some(0);
+    }
+    // Substring: earlier and at a word start is better
+    if let Some(pos) = text.windows(word.len()).position(|w| w == word.as_slice()) {
+        let start_bonus = if is_word_start(&text, pos) { 200 } else { 0 };
+        return Some(1000 + start_bonus - pos.min(100) as u32);
+    }
+    // Letters in order
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for c in &word {
+        let i = next + text[next..].iter().position(|t| t == c)?;
+        score += 10;
+        if previous == Some(i.wrapping_sub(1)) {
+            score += 15;
+        }
+        if is_word_start(&text, i) {
+            score += 20;
+        }
+        previous = Some(i);
+        next = i + 1;
+    }
+    Some(score)
+}
+
+/// Scores a query against a tab's title and URL; every word has to match one of them.
+fn score_tab(words: &[&str], title: &str, url: &str) -> Option<u32> {
+    words.iter().try_fold(0, |total, word| {
+        let title_score = fuzzy_score(word, title);
+        let url_score = fuzzy_score(word, url).map(|s| s * 3 / 4);
+        Some(total + title_score.max(url_score)?)
+    })
+}
+
+fn tab_title(tab: &Tab) -> String {
+    tab.custom_title.clone().unwrap_or_else(|| tab.title.clone())
+}
+
+/// Ranks open tabs, then recently closed ones (`closed` oldest first, as stored).
+pub fn search<'a>(
+    query: &str,
+    tabs: &[Tab],
+    closed: impl DoubleEndedIterator<Item = &'a ClosedTab>,
+) -> Vec<TabSearchResult> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+
+    let mut open: Vec<(&Tab, u32)> =
+        tabs.iter().filter_map(|tab| Some((tab, score_tab(&words, &tab_title(tab), &tab.url)?))).collect();
+    open.sort_by_key(|(tab, score)| (Reverse(*score), Reverse(tab.last_accessed)));
+    let mut results: Vec<TabSearchResult> = open
+        .into_iter()
+        .take(MAX_OPEN_RESULTS)
+        .map(|(tab, score)| TabSearchResult {
+            kind: TabSearchKind::Open,
+            tab_id: tab.id.clone(),
+            title: tab_title(tab),
+            url: tab.url.clone(),
+            favicon: tab.favicon.clone(),
+            score,
+        })
+        .collect();
+
+    if words.is_empty() {
+        return results;
+    }
+    let mut recent: Vec<(&ClosedTab, u32)> = closed
+        .rev()
+        .filter(|c| !tabs.iter().any(|t| t.url == c.url))
+        .filter_map(|c| Some((c, score_tab(&words, &c.title, &c.url)?)))
+        .collect();
+    recent.sort_by_key(|(_, score)| Reverse(*score)); // Stable: most recently closed first on ties
+    results.extend(recent.into_iter().take(MAX_CLOSED_RESULTS).map(|(c, score)| TabSearchResult {
+        kind: TabSearchKind::Closed,
+        tab_id: c.id.clone(),
+        title: c.title.clone(),
+        url: c.url.clone(),
+        favicon: c.favicon.clone(),
+        score,
+    }));
+    results
+}
+
+#[tauri::command]
+pub fn search_open_tabs(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    query: String,
+) -> Result<Vec<TabSearchResult>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let tabs = state.tabs.lock().unwrap();
+    let closed = state.closed_tabs.lock().unwrap();
+    Ok(search(&query, &tabs, closed.iter()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::time::{Duration, Instant, SystemTime};
+
+    fn tab(id: &str, title: &str, url: &str, age_secs: u64) -> Tab {
+        Tab {
+            id: id.to_string(),
+            webview_label: format!("webview-{}", id),
+            title: title.to_string(),
+            url: url.to_string(),
+            favicon: None,
+            last_accessed: Instant::now().checked_sub(Duration::from_secs(age_secs)),
+            is_loading: false,
+            can_go_back: false,
+            can_go_forward: false,
+            last_focus_was_content: false,
+            screenshot: None,
+            unread_count: None,
+            custom_title: None,
+            marker: None,
+            is_audible: false,
+            is_muted: false,
+            load_error: None,
+            window: None,
+            container: None,
+        }
+    }
+
+    fn closed(id: &str, title: &str, url: &str) -> ClosedTab {
+        ClosedTab {
+            id: id.to_string(),
+            title: title.to_string(),
+            url: url.to_string(),
+            favicon: None,
+            closed_at: SystemTime::now(),
+            container: None,
+        }
+    }
+
+    #[rstest]
+    #[case("issues", "GitHub Issues", true)]
+    #[case("gh", "GitHub Issues", true)] // Letters in order
+    #[case("ghi", "GitHub Issues", true)]
+    #[case("xyz", "GitHub Issues", false)]
+    #[case("sseu", "GitHub Issues", false)] // Out of order
+    fn test_fuzzy_score_matches(#[case] word: &str, #[case] text: &str, #[case] matches: bool) {
+        assert_eq!(fuzzy_score(word, text).is_some(), matches);
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_substrings_and_word_starts() {
+        let substring = fuzzy_score("hub", "GitHub").unwrap();
+        let word_start = fuzzy_score("git", "GitHub").unwrap();
+        let scattered = fuzzy_score("gthb", "GitHub").unwrap();
+        assert!(word_start > substring);
+        assert!(substring > scattered);
+    }
+
+    #[test]
+    fn test_search_ranks_open_then_closed() {
+        let tabs = vec![
+            tab("tab-1", "Rust Documentation", "https://doc.rust-lang.org/", 30),
+            tab("tab-2", "Inbox", "https://mail.example.com/", 10),
+            tab("tab-3", "crates.io: Rust Package Registry", "https://crates.io/", 20),
+        ];
+        let closed_tabs = vec![
+            closed("tab-0", "The Rust Book", "https://doc.rust-lang.org/book/"),
+            closed("tab-9", "Rust Documentation", "https://doc.rust-lang.org/"), // Still open
+        ];
+
+        let results = search("rust", &tabs, closed_tabs.iter());
+        let ids: Vec<(&str, TabSearchKind)> = results.iter().map(|r| (r.tab_id.as_str(), r.kind)).collect();
+        assert_eq!(
+            ids,
+            vec![("tab-1", TabSearchKind::Open), ("tab-3", TabSearchKind::Open), ("tab-0", TabSearchKind::Closed)]
+        );
+
+        // Every word has to match, in the title or the URL
+        let results = search("mail inbox", &tabs, closed_tabs.iter());
+        assert_eq!(results.iter().map(|r| r.tab_id.as_str()).collect::<Vec<_>>(), vec!["tab-2"]);
+    }
+
+    #[test]
+    fn test_empty_query_lists_open_tabs_by_recent_use() {
+        let tabs = vec![tab("tab-1", "A", "https://a.com/", 30), tab("tab-2", "B", "https://b.com/", 10)];
+        let closed_tabs = vec![closed("tab-0", "C", "https://c.com/")];
+        let results = search("  ", &tabs, closed_tabs.iter());
+        assert_eq!(results.iter().map(|r| r.tab_id.as_str()).collect::<Vec<_>>(), vec!["tab-2", "tab-1"]);
+    }
+}
@@ -0,0 +1,129 @@
+// Per-tab status snapshots.
+//
+// URL, security, loading, audio and error state used to travel in separate events
+// that could arrive out of order. A TabStatus carries all of them at once, taken
+// under the tabs lock, with a sequence number so the toolbar can drop stale snapshots.
+
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter};
+
+use crate::modules::handoff;
+use crate::state::{AppState, Tab};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityState {
+    Secure,   // https
+    Insecure, // http
+    Local,    // file
+    Internal, // about:, data:, app pages
+}
+
+pub fn security_state(url: &str) -> SecurityState {
+    match url.split_once(':').map(|(scheme, _)| scheme.to_ascii_lowercase()).as_deref() {
+        Some("https") | Some("wss") => SecurityState::Secure,
+        Some("http") | Some("ws") => SecurityState::Insecure,
+        Some("file") => SecurityState::Local,
+        _ => SecurityState::Internal,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabStatus {
+    pub tab_id: String,
+    pub seq: u64,
+    pub url: String,
+    pub security: SecurityState,
+    pub is_loading: bool,
+    pub can_go_back: bool,
+    pub can_go_forward: bool,
+    pub is_audible: bool,
+    pub is_muted: bool,
+    pub error: Option<String>,
+}
+
+impl TabStatus {
+    pub fn from_tab(tab: &Tab, seq: u64) -> Self {
+        TabStatus {
+            tab_id: tab.id.clone(),
+            seq,
+            url: tab.url.clone(),
+            security: security_state(&tab.url),
+            is_loading: tab.is_loading,
+            can_go_back: tab.can_go_back,
+            can_go_forward: tab.can_go_forward,
+            is_audible: tab.is_audible,
+            is_muted: tab.is_muted,
+            error: tab.load_error.clone(),
+        }
+    }
+}
+
+/// Take a consistent snapshot of one tab. The sequence number is assigned while
+/// the tabs lock is held, so a higher seq always reflects newer state.
+pub fn snapshot(state: &AppState, tab_id: &str) -> Option<TabStatus> {
+    let tabs = state.tabs.lock().unwrap();
+    let tab = tabs.iter().find(|t| t.id == tab_id)?;
+    let seq = state.status_seq.fetch_add(1, Ordering::SeqCst) + 1;
+    Some(TabStatus::from_tab(tab, seq))
+}
+
+/// Emit "tab-status" for a tab. Replaces the old url-changed event.
+/// Handoff follows the same updates.
+pub fn emit_tab_status(app: &AppHandle, state: &AppState, tab_id: &str) {
+    if let Some(status) = snapshot(state, tab_id) {
+        let _ = app.emit("tab-status", status);
+    }
+    handoff::update(app, state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://example.com", SecurityState::Secure)]
+    #[case("HTTPS://example.com", SecurityState::Secure)]
+    #[case("http://example.com", SecurityState::Insecure)]
+    #[case("file:///tmp/a.html", SecurityState::Local)]
+    #[case("about:blank", SecurityState::Internal)]
+    #[case("", SecurityState::Internal)]
+    fn test_security_state(#[case] url: &str, #[case] expected: SecurityState) {
+        assert_eq!(security_state(url), expected);
+    }
+
+    #[test]
+    fn test_from_tab_serializes_camel_case() {
+        let tab = Tab {
+            id: "tab-1".to_string(),
+            webview_label: "webview-tab-1".to_string(),
+            title: "Example".to_string(),
+            url: "http://example.com".to_string(),
+            favicon: None,
+            last_accessed: None,
+            is_loading: true,
+            can_go_back: true,
+            can_go_forward: false,
+            last_focus_was_content: true,
+            screenshot: None,
+            unread_count: None,
+            custom_title: None,
+            marker: None,
+            is_audible: false,
+            is_muted: false,
+            load_error: None,
+            window: None,
+            container: None,
+        };
+
+        let json = serde_json::to_value(TabStatus::from_tab(&tab, 7)).unwrap();
+        assert_eq!(json["tabId"], "tab-1");
+        assert_eq!(json["seq"], 7);
+        assert_eq!(json["security"], "insecure");
+        assert_eq!(json["isLoading"], true);
+        assert_eq!(json["canGoBack"], true);
+    }
+}
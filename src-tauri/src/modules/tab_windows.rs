@@ -0,0 +1,261 @@
+// Detached tabs: a tab moved out of the main window into a window of its own.
+//
+// `detach_tab` (main.rs) opens a `tab-window-<tab id>` window whose own webview
+// is a small toolbar (ui/tab-window.html) and moves the tab's webview into it
+// with `Webview::reparent`, so the page, its history and any form state come
+// along untouched. The tab stays in `AppState.tabs` with `Tab.window` set: the
+// main tab strip hides it, tab cycling skips it, and switching to it (tab search,
+// links) focuses its window instead. `attach_tab` moves it back. Closing the
+// window closes the tab like any other; quitting keeps it in the session, where
+// it is restored into the main window.
+
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize};
+
+use crate::error::BrowserError;
+use crate::state::{AppState, Tab};
+
+const WINDOW_PREFIX: &str = "tab-window-";
+const TOOLBAR_HEIGHT: f64 = 44.0;
+
+pub fn window_label(tab_id: &str) -> String {
+    format!("{}{}", WINDOW_PREFIX, tab_id)
+}
+
+pub fn tab_of_window(label: &str) -> Option<&str> {
+    label.strip_prefix(WINDOW_PREFIX)
+}
+
+/// The tab to show in the main window once the tab at `index` leaves it:
+/// the nearest main-window tab to the right, else to the left.
+pub fn main_neighbour(tabs: &[Tab], index: usize) -> Option<String> {
+    let in_main = |t: &&Tab| t.window.is_none();
+    tabs.iter()
+        .skip(index + 1)
+        .find(in_main)
+        .or_else(|| tabs.iter().take(index).rev().find(in_main))
+        .map(|t| t.id.clone())
+}
+
+fn content_rect(size: PhysicalSize<u32>, scale: f64) -> tauri::Rect {
+    let toolbar = (TOOLBAR_HEIGHT * scale) as u32;
+    tauri::Rect {
+        position: tauri::Position::Physical(PhysicalPosition::new(0, toolbar as i32)),
+        size: tauri::Size::Physical(PhysicalSize::new(size.width, size.height.saturating_sub(toolbar).max(100))),
+    }
+}
+
+fn layout(app: &AppHandle, window: &tauri::WebviewWindow, webview_label: &str) {
+    let (Ok(size), Ok(scale)) = (window.inner_size(), window.scale_factor()) else {
+        return;
+    };
+    if let Some(webview) = app.get_webview(webview_label) {
+        let _ = webview.set_bounds(content_rect(size, scale));
+    }
+}
+
+/// Opens the tab's window and moves its webview there. The caller has already
+/// switched the main window away from it.
+pub fn open(
+    app: &AppHandle,
+    state: &AppState,
+    tab_id: &str,
+    position: Option<(f64, f64)>,
+) -> Result<tauri::WebviewWindow, BrowserError> {
+    let (webview_label, title) = {
+        let tabs = state.tabs.lock().unwrap();
+        let tab = tabs.iter().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(tab_id))?;
+        (tab.webview_label.clone(), tab.custom_title.clone().unwrap_or_else(|| tab.title.clone()))
+    };
+    let webview =
+        app.get_webview(&webview_label).ok_or_else(|| BrowserError::NotFound("Tab webview not found".to_string()))?;
+    let main_size = app.get_window("main").and_then(|w| w.inner_size().ok().zip(w.scale_factor().ok()));
+
+    let label = window_label(tab_id);
+    let mut builder = tauri::WebviewWindowBuilder::new(
+        app,
+        &label,
+        tauri::WebviewUrl::App(format!("tab-window.html?tab={}", tab_id).into()),
+    )
+    .title(if title.is_empty() { "New Tab".to_string() } else { title })
+    .min_inner_size(400.0, 300.0);
+    builder = match main_size {
+        Some((size, scale)) => {
+            let size = size.to_logical::<f64>(scale);
+            builder.inner_size(size.width, size.height)
+        }
+        None => builder.inner_size(1024.0, 768.0),
+    };
+    if let Some((x, y)) = position {
+        builder = builder.position(x, y);
+    }
+    let window = builder.build().map_err(|e| BrowserError::Webview(format!("Failed to open window: {}", e)))?;
+
+    let moved = app
+        .get_window(&label)
+        .ok_or("Window not found".to_string())
+        .and_then(|native| webview.reparent(&native).map_err(|e| e.to_string()));
+    if let Err(e) = moved {
+        let _ = window.destroy();
+        return Err(BrowserError::Webview(format!("Failed to move tab: {}", e)));
+    }
+    if let Some(tab) = state.tabs.lock().unwrap().iter_mut().find(|t| t.id == tab_id) {
+        tab.window = Some(label.clone());
+    }
+    layout(app, &window, &webview_label);
+    let _ = webview.show();
+    let _ = webview.set_focus();
+
+    let h = app.clone();
+    let w = window.clone();
+    window.on_window_event(move |event| match event {
+        tauri::WindowEvent::Resized(_) => layout(&h, &w, &webview_label),
+        // The window reports the new monitor's scale and size only after this
+        tauri::WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size, .. } => {
+            if let Some(webview) = h.get_webview(&webview_label) {
+                let _ = webview.set_bounds(content_rect(*new_inner_size, *scale_factor));
+            }
+        }
+        _ => {}
+    });
+    println!("[TabWindows] Detached {} into {}", tab_id, label);
+    Ok(window)
+}
+
+/// Moves a detached tab's webview back into the main window and closes its window.
+/// The caller switches to the tab afterwards, which sizes and shows it.
+pub fn attach(app: &AppHandle, state: &AppState, tab_id: &str) -> Result<(), BrowserError> {
+    let (webview_label, label) = {
+        let tabs = state.tabs.lock().unwrap();
+        let tab = tabs.iter().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(tab_id))?;
+        let window = tab.window.clone().ok_or_else(|| BrowserError::InvalidInput("Tab isn't detached".to_string()))?;
+        (tab.webview_label.clone(), window)
+    };
+    let main = app.get_window("main").ok_or_else(|| BrowserError::NotFound("Main window not found".to_string()))?;
+    if let Some(webview) = app.get_webview(&webview_label) {
+        webview.reparent(&main).map_err(|e| BrowserError::Webview(format!("Failed to move tab: {}", e)))?;
+    }
+    if let Some(tab) = state.tabs.lock().unwrap().iter_mut().find(|t| t.id == tab_id) {
+        tab.window = None;
+    }
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.destroy();
+    }
+    println!("[TabWindows] Attached {}", tab_id);
+    Ok(())
+}
+
+/// Focuses a detached tab's window. False if the tab lives in the main window.
+pub fn focus(app: &AppHandle, state: &AppState, tab_id: &str) -> bool {
+    let label = state.tabs.lock().unwrap().iter().find(|t| t.id == tab_id).and_then(|t| t.window.clone());
+    let Some(label) = label else {
+        return false;
+    };
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    true
+}
+
+/// Closes a detached tab's window, without asking it first. The tab's webview is
+/// closed by then.
+pub fn close_window(app: &AppHandle, tab_id: &str) {
+    if let Some(window) = app.get_webview_window(&window_label(tab_id)) {
+        let _ = window.destroy();
+    }
+}
+
+/// Closes every detached tab's window along with the main window.
+pub fn close_all(app: &AppHandle, state: &AppState) {
+    let labels: Vec<String> = state.tabs.lock().unwrap().iter().filter_map(|t| t.window.clone()).collect();
+    for label in labels {
+        if let Some(window) = app.get_webview_window(&label) {
+            let _ = window.destroy();
+        }
+    }
+}
+
+pub fn set_title(app: &AppHandle, tab: &Tab) {
+    if let Some(window) = tab.window.as_ref().and_then(|label| app.get_webview_window(label)) {
+        let _ = window.set_title(tab.custom_title.as_deref().unwrap_or(&tab.title));
+    }
+}
+
+/// Back, forward and reload from a detached tab's toolbar.
+#[tauri::command]
+pub fn tab_window_navigate(
+    app: AppHandle,
+    window: tauri::Window,
+    state: tauri::State<AppState>,
+    action: String,
+) -> Result<(), BrowserError> {
+    let tab_id =
+        tab_of_window(window.label()).ok_or_else(|| BrowserError::NotAllowed("Not a tab window".to_string()))?;
+    let label = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| t.webview_label.clone())
+        .ok_or_else(|| BrowserError::tab_not_found(tab_id))?;
+    let script = match action.as_str() {
+        "back" => "history.back()",
+        "forward" => "history.forward()",
+        "reload" => "location.reload()",
+        _ => return Err(BrowserError::InvalidInput(format!("Unknown action: {}", action))),
+    };
+    let webview = app.get_webview(&label).ok_or_else(|| BrowserError::NotFound("Tab webview not found".to_string()))?;
+    Ok(webview.eval(script)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn tab(id: &str, detached: bool) -> Tab {
+        Tab {
+            id: id.to_string(),
+            webview_label: format!("webview-{}", id),
+            title: id.to_string(),
+            url: "https://example.com".to_string(),
+            favicon: None,
+            last_accessed: None,
+            is_loading: false,
+            can_go_back: false,
+            can_go_forward: false,
+            last_focus_was_content: false,
+            screenshot: None,
+            unread_count: None,
+            custom_title: None,
+            marker: None,
+            is_audible: false,
+            is_muted: false,
+            load_error: None,
+            window: detached.then(|| window_label(id)),
+            container: None,
+        }
+    }
+
+    #[test]
+    fn test_window_label_round_trip() {
+        assert_eq!(tab_of_window(&window_label("tab-7")), Some("tab-7"));
+        assert_eq!(tab_of_window("main"), None);
+    }
+
+    #[rstest]
+    #[case(0, Some("tab-3"))] // Skips the detached tab to the right
+    #[case(2, Some("tab-4"))]
+    #[case(3, Some("tab-3"))] // Nothing to the right, nearest main tab to the left
+    fn test_main_neighbour(#[case] index: usize, #[case] expected: Option<&str>) {
+        let tabs = vec![tab("tab-1", false), tab("tab-2", true), tab("tab-3", false), tab("tab-4", false)];
+        assert_eq!(main_neighbour(&tabs, index).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_main_neighbour_none_left() {
+        let tabs = vec![tab("tab-1", false), tab("tab-2", true)];
+        assert_eq!(main_neighbour(&tabs, 0), None);
+    }
+}
@@ -1,9 +1,20 @@
-// Tab reordering module - Pure logic + Tauri command
+// Tab reordering, renaming and marker module - Pure logic + Tauri commands
 // Follows strict modular monolith pattern
 
-use tauri::{AppHandle, State, Emitter};
-use crate::state::{Tab, AppState};
+use tauri::{AppHandle, State, Emitter, Manager, PhysicalPosition, PhysicalSize};
+use crate::error::BrowserError;
+use crate::state::{Tab, TabMarker, AppState};
+use crate::modules::layout::{self, ChromeLayout, PaneRect, TitlebarStyle};
+use crate::modules::dock;
+use crate::modules::session_store;
+use crate::modules::split_view;
+use crate::modules::tab_engine::{TabEngine, TabHost};
+use crate::modules::tab_windows;
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+const MAX_CUSTOM_TITLE_LEN: usize = 100;
+const MAX_EMOJI_CHARS: usize = 8; // Allows ZWJ sequences and skin-tone modifiers
 
 /// Pure logic for reordering tabs.
 /// Returns true if the order changed, false otherwise.
@@ -45,13 +56,211 @@ fn reorder_logic(tabs: &mut Vec<Tab>, new_order: &[String]) -> bool {
     changed
 }
 
+/// Pure logic for pinning a user-chosen title on a tab.
+/// An empty/whitespace title clears the override. Returns true if the tab was found.
+fn set_custom_title(tabs: &mut [Tab], tab_id: &str, custom_title: Option<String>) -> bool {
+    let normalized = custom_title
+        .map(|t| t.trim().chars().take(MAX_CUSTOM_TITLE_LEN).collect::<String>())
+        .filter(|t| !t.is_empty());
+
+    match tabs.iter_mut().find(|t| t.id == tab_id) {
+        Some(tab) => {
+            tab.custom_title = normalized;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Validates a marker: colors must be hex (#rgb / #rrggbb), emoji must be a short
+/// non-alphanumeric string so the marker can't be abused as a second title.
+fn validate_marker(marker: TabMarker) -> Result<TabMarker, String> {
+    match marker {
+        TabMarker::Color(color) => {
+            let color = color.trim().to_lowercase();
+            let hex = color.strip_prefix('#').unwrap_or("");
+            if (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(TabMarker::Color(color))
+            } else {
+                Err(format!("Invalid marker color: {}", color))
+            }
+        }
+        TabMarker::Emoji(emoji) => {
+            let emoji = emoji.trim().to_string();
+            let count = emoji.chars().count();
+            if count == 0 || count > MAX_EMOJI_CHARS || emoji.chars().any(|c| c.is_ascii_alphanumeric() || c.is_whitespace()) {
+                Err(format!("Invalid marker emoji: {}", emoji))
+            } else {
+                Ok(TabMarker::Emoji(emoji))
+            }
+        }
+    }
+}
+
+/// The "update-tabs" payload. `layout` tells the toolbar page where the tab
+/// strip goes, so it draws around the same content area as `content_bounds`.
+pub fn update_payload(state: &AppState, tabs: &[Tab], active_id: Option<String>) -> serde_json::Value {
+    serde_json::json!({
+        "tabs": tabs,
+        "activeTabId": active_id,
+        "layout": chrome_layout(state),
+        "containers": state.containers.data().containers
+    })
+}
+
+/// `chrome` for the main window as it is now: the platform's titlebar, and
+/// whether the window is maximized.
+pub fn window_layout(state: &AppState, chrome: ChromeLayout) -> ChromeLayout {
+    chrome.with_window(TitlebarStyle::current(), state.main_maximized.load(Ordering::Relaxed))
+}
+
+pub fn chrome_layout(state: &AppState) -> ChromeLayout {
+    let chrome = state.settings.read().unwrap().chrome_layout();
+    window_layout(state, chrome)
+}
+
+pub fn emit_tabs(app: &AppHandle, state: &AppState) -> Result<(), BrowserError> {
+    let tabs = state.tabs.lock().map_err(|e| e.to_string())?;
+    let active_id = state.active_tab_id.lock().map_err(|e| e.to_string())?.clone();
+    app.emit("update-tabs", update_payload(state, &tabs, active_id))?;
+    dock::update(app, state, &tabs);
+    Ok(())
+}
+
+pub fn pane_bounds(pane: PaneRect) -> tauri::Rect {
+    tauri::Rect {
+        position: tauri::Position::Physical(PhysicalPosition::new(pane.x, pane.y)),
+        size: tauri::Size::Physical(PhysicalSize::new(pane.width, pane.height)),
+    }
+}
+
+/// A window's inner size in physical pixels and the scale factor of the monitor
+/// it's on. Every webview bound is worked out from both at once: after the
+/// window moves to a monitor with another scale factor, the
+/// ScaleFactorChanged event carries the new pair before the window reports it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowMetrics {
+    pub size: PhysicalSize<u32>,
+    pub scale: f64,
+}
+
+impl WindowMetrics {
+    pub fn of(window: &tauri::Window) -> Option<Self> {
+        Some(Self { size: window.inner_size().ok()?, scale: window.scale_factor().ok()? })
+    }
+}
+
+pub fn main_metrics(app: &AppHandle) -> Option<WindowMetrics> {
+    WindowMetrics::of(&app.get_window("main")?)
+}
+
+/// Where a single tab's webview goes in the main window right now.
+pub fn content_bounds(app: &AppHandle, state: &AppState) -> Option<tauri::Rect> {
+    main_metrics(app).map(|metrics| content_bounds_in(state, metrics))
+}
+
+/// Where a single tab's webview goes in a main window of `metrics`.
+pub fn content_bounds_in(state: &AppState, metrics: WindowMetrics) -> tauri::Rect {
+    let chrome = chrome_layout(state);
+    pane_bounds(layout::content_area(metrics.size.width, metrics.size.height, metrics.scale, chrome))
+}
+
+/// The tab lifecycle's side effects on the real webviews, see modules::tab_engine.
+pub struct TauriTabHost<'a> {
+    app: &'a AppHandle,
+    state: &'a AppState,
+}
+
+impl TabHost for TauriTabHost<'_> {
+    fn show_webview(&self, tab: &Tab) {
+        if let Some(webview) = self.app.get_webview(&tab.webview_label) {
+            // Cheap if nothing changed, and catches resizes while the tab was hidden
+            if let Some(bounds) = content_bounds(self.app, self.state) {
+                let _ = webview.set_bounds(bounds);
+            }
+            let _ = webview.show();
+        }
+    }
+
+    fn hide_webview(&self, tab: &Tab) {
+        if let Some(webview) = self.app.get_webview(&tab.webview_label) {
+            let _ = webview.hide();
+        }
+    }
+
+    fn close_webview(&self, tab: &Tab) {
+        if let Some(webview) = self.app.get_webview(&tab.webview_label) {
+            let _ = webview.close();
+        }
+        if let Some(inspector) = self.app.get_webview_window(&format!("devtools-{}", tab.id)) {
+            let _ = inspector.close();
+        }
+        tab_windows::close_window(self.app, &tab.id);
+    }
+
+    fn in_split(&self, tab_id: &str) -> bool {
+        self.state.split_view.contains(tab_id)
+    }
+
+    fn arrange_split(&self) {
+        split_view::arrange(self.app, self.state);
+    }
+
+    fn emit_tabs(&self, tabs: &[Tab], active_id: Option<String>) {
+        let _ = self.app.emit("update-tabs", update_payload(self.state, tabs, active_id));
+    }
+}
+
+pub fn engine<'a>(app: &'a AppHandle, state: &'a AppState) -> TabEngine<'a, TauriTabHost<'a>> {
+    TabEngine::new(&state.tabs, &state.active_tab_id, TauriTabHost { app, state })
+}
+
+/// Tauri command to rename a tab. Pass `None` (or an empty string) to restore the page title.
+#[tauri::command]
+pub fn rename_tab(
+    app: AppHandle,
+    state: State<AppState>,
+    tab_id: String,
+    custom_title: Option<String>,
+) -> Result<(), BrowserError> {
+    {
+        let mut tabs = state.tabs.lock().map_err(|e| e.to_string())?;
+        if !set_custom_title(&mut tabs, &tab_id, custom_title) {
+            return Err(BrowserError::tab_not_found(&tab_id));
+        }
+    }
+
+    println!("[Tabs] Renamed tab: {}", tab_id);
+    session_store::save_session(&app, &state);
+    emit_tabs(&app, &state)
+}
+
+/// Tauri command to tag a tab with a color or emoji marker. `None` removes the marker.
+#[tauri::command]
+pub fn set_tab_marker(
+    app: AppHandle,
+    state: State<AppState>,
+    tab_id: String,
+    marker: Option<TabMarker>,
+) -> Result<(), BrowserError> {
+    let marker = marker.map(validate_marker).transpose().map_err(BrowserError::InvalidInput)?;
+    {
+        let mut tabs = state.tabs.lock().map_err(|e| e.to_string())?;
+        let tab = tabs.iter_mut().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+        tab.marker = marker;
+    }
+
+    session_store::save_session(&app, &state);
+    emit_tabs(&app, &state)
+}
+
 /// Tauri command to reorder tabs
 #[tauri::command]
 pub fn reorder_tabs(
     app: AppHandle,
     state: State<AppState>,
     new_order: Vec<String>
-) -> Result<(), String> {
+) -> Result<(), BrowserError> {
     println!("[Tab Reorder] Received new order: {:?}", new_order);
 
     let changed = {
@@ -69,10 +278,7 @@ pub fn reorder_tabs(
         let active_id = state.active_tab_id.lock().map_err(|e| e.to_string())?.clone();
 
         println!("[Tab Reorder] Emitting update-tabs event");
-        let _ = app.emit("update-tabs", serde_json::json!({
-            "tabs": *tabs,
-            "activeTabId": active_id
-        }));
+        app.emit("update-tabs", update_payload(&state, &tabs, active_id))?;
     } else {
         println!("[Tab Reorder] No change detected, skipping emit");
     }
@@ -99,6 +305,14 @@ mod tests {
             can_go_forward: false,
             last_focus_was_content: true,
             screenshot: None,
+            unread_count: None,
+            custom_title: None,
+            marker: None,
+            is_audible: false,
+            is_muted: false,
+            load_error: None,
+            window: None,
+            container: None,
         }
     }
 
@@ -165,4 +379,35 @@ mod tests {
         assert!(!changed);
         assert_eq!(tabs.len(), 1); // No data loss
     }
+
+    #[test]
+    fn test_set_custom_title() {
+        let mut tabs = vec![create_test_tab("tab-1", "Inbox"), create_test_tab("tab-2", "Inbox")];
+
+        assert!(set_custom_title(&mut tabs, "tab-2", Some("  Work mail  ".to_string())));
+        assert_eq!(tabs[1].custom_title.as_deref(), Some("Work mail"));
+        assert_eq!(tabs[0].custom_title, None);
+
+        // Empty title clears the override
+        assert!(set_custom_title(&mut tabs, "tab-2", Some("   ".to_string())));
+        assert_eq!(tabs[1].custom_title, None);
+
+        assert!(!set_custom_title(&mut tabs, "missing", Some("x".to_string())));
+    }
+
+    #[test]
+    fn test_validate_marker() {
+        assert_eq!(
+            validate_marker(TabMarker::Color(" #FF453A ".to_string())),
+            Ok(TabMarker::Color("#ff453a".to_string()))
+        );
+        assert!(validate_marker(TabMarker::Color("#abc".to_string())).is_ok());
+        assert!(validate_marker(TabMarker::Color("red".to_string())).is_err());
+        assert!(validate_marker(TabMarker::Color("#12345g".to_string())).is_err());
+
+        assert!(validate_marker(TabMarker::Emoji("🔥".to_string())).is_ok());
+        assert!(validate_marker(TabMarker::Emoji("👩‍💻".to_string())).is_ok());
+        assert!(validate_marker(TabMarker::Emoji("work".to_string())).is_err());
+        assert!(validate_marker(TabMarker::Emoji("".to_string())).is_err());
+    }
 }
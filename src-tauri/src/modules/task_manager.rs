@@ -0,0 +1,434 @@
+// Per-tab memory and CPU usage (Task Manager).
+//
+// A sampler thread looks up each tab's web content process every SAMPLE_INTERVAL
+// and reads its resident memory and CPU time from the OS:
+// - macOS: WKWebView's `_webProcessIdentifier`, then proc_pidinfo.
+// - Windows: the WebView2 renderer whose frames include the tab's main frame
+//   (needs WebView2 1.0.2210+), then GetProcessTimes/GetProcessMemoryInfo.
+// - Linux: WebKitGTK doesn't say which process belongs to a webview, so tabs
+//   report no usage; only the browser process is measured (from /proc).
+// Tabs of the same site can share a renderer; they're marked `shared` and show
+// the process's total. CPU is a percentage of one core since the last sample.
+//
+// A tab is flagged as runaway when its process stays above RUNAWAY_CPU for
+// RUNAWAY_SAMPLES samples in a row, or grows past RUNAWAY_MEMORY. The main window
+// gets a `tab-runaway` event when that starts; the flag clears once usage drops.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+const PID_TIMEOUT: Duration = Duration::from_secs(1);
+const RUNAWAY_CPU: f64 = 90.0;
+const RUNAWAY_SAMPLES: u32 = 5;
+const RUNAWAY_MEMORY: u64 = 2 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ProcessTimes {
+    cpu_ns: u64, // User + system time since the process started
+    memory: u64, // Resident bytes
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessUsage {
+    pub pid: Option<u32>,
+    pub memory: Option<u64>,
+    pub cpu: Option<f64>, // Percent of one core; None until a second sample
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabUsage {
+    pub tab_id: String,
+    pub title: String,
+    pub url: String,
+    #[serde(flatten)]
+    pub usage: ProcessUsage,
+    pub shared: bool, // Another tab runs in the same process
+    pub runaway: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub tabs: Vec<TabUsage>,
+    pub browser: Option<ProcessUsage>,
+    pub sampled_at: i64, // Unix time in milliseconds, 0 before the first sample
+}
+
+#[derive(Default)]
+struct SamplerState {
+    last: HashMap<u32, (Instant, u64)>, // pid -> when, cpu_ns
+    hot_samples: HashMap<String, u32>,  // webview label -> samples in a row above RUNAWAY_CPU
+    runaway: HashMap<String, bool>,     // webview label -> flagged
+}
+
+pub struct TaskManager {
+    sampler: Mutex<SamplerState>,
+    latest: Mutex<ResourceUsage>,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self { sampler: Mutex::new(SamplerState::default()), latest: Mutex::new(ResourceUsage::default()) }
+    }
+
+    /// CPU percentage for a process since its previous reading.
+    fn cpu_percent(sampler: &mut SamplerState, pid: u32, now: Instant, cpu_ns: u64) -> Option<f64> {
+        let previous = sampler.last.insert(pid, (now, cpu_ns));
+        let (then, then_cpu) = previous?;
+        let wall = now.duration_since(then).as_nanos() as f64;
+        (wall > 0.0).then(|| cpu_ns.saturating_sub(then_cpu) as f64 / wall * 100.0)
+    }
+
+    /// Updates a tab's runaway state. Returns the reason when it's newly flagged.
+    fn update_runaway(sampler: &mut SamplerState, label: &str, usage: &ProcessUsage) -> (bool, Option<String>) {
+        let hot = sampler.hot_samples.entry(label.to_string()).or_insert(0);
+        *hot = if usage.cpu.is_some_and(|cpu| cpu >= RUNAWAY_CPU) { *hot + 1 } else { 0 };
+        let reason = if *hot >= RUNAWAY_SAMPLES {
+            Some(format!("using {:.0}% CPU", usage.cpu.unwrap_or_default()))
+        } else if usage.memory.is_some_and(|memory| memory >= RUNAWAY_MEMORY) {
+            Some(format!("using {} MB of memory", usage.memory.unwrap_or_default() / (1024 * 1024)))
+        } else {
+            None
+        };
+        let was = sampler.runaway.insert(label.to_string(), reason.is_some()).unwrap_or(false);
+        let newly = if was { None } else { reason.clone() };
+        (reason.is_some(), newly)
+    }
+
+    fn measure(sampler: &mut SamplerState, pid: Option<u32>, now: Instant) -> ProcessUsage {
+        let times = pid.and_then(process_times);
+        ProcessUsage {
+            pid,
+            memory: times.map(|t| t.memory),
+            cpu: pid.zip(times).and_then(|(pid, t)| Self::cpu_percent(sampler, pid, now, t.cpu_ns)),
+        }
+    }
+
+    /// Takes a sample; returns the tabs that just became runaway, with why.
+    fn sample(&self, tabs: Vec<(String, String, String, String, Option<u32>)>) -> Vec<(String, String, String)> {
+        let now = Instant::now();
+        let mut sampler = self.sampler.lock().unwrap();
+        let mut per_pid: HashMap<u32, usize> = HashMap::new();
+        for pid in tabs.iter().filter_map(|t| t.4) {
+            *per_pid.entry(pid).or_default() += 1;
+        }
+
+        // One reading per process, however many tabs share it
+        let mut usage_by_pid: HashMap<u32, ProcessUsage> = HashMap::new();
+        let mut flagged = Vec::new();
+        let mut usage = Vec::new();
+        for (tab_id, label, title, url, pid) in tabs {
+            let process = match pid {
+                Some(pid) => {
+                    usage_by_pid.entry(pid).or_insert_with(|| Self::measure(&mut sampler, Some(pid), now)).clone()
+                }
+                None => ProcessUsage { pid: None, memory: None, cpu: None },
+            };
+            let (runaway, newly) = Self::update_runaway(&mut sampler, &label, &process);
+            if let Some(reason) = newly {
+                flagged.push((tab_id.clone(), title.clone(), reason));
+            }
+            usage.push(TabUsage {
+                tab_id,
+                title,
+                url,
+                shared: pid.is_some_and(|pid| per_pid.get(&pid).copied().unwrap_or(0) > 1),
+                usage: process,
+                runaway,
+            });
+        }
+        let browser = Self::measure(&mut sampler, Some(std::process::id()), now);
+
+        // Forget processes that are gone
+        let live: Vec<u32> = usage_by_pid.keys().copied().chain([std::process::id()]).collect();
+        sampler.last.retain(|pid, _| live.contains(pid));
+        drop(sampler);
+
+        *self.latest.lock().unwrap() =
+            ResourceUsage { tabs: usage, browser: Some(browser), sampled_at: chrono::Utc::now().timestamp_millis() };
+        flagged
+    }
+
+    pub fn latest(&self) -> ResourceUsage {
+        self.latest.lock().unwrap().clone()
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        let mut sampler = self.sampler.lock().unwrap();
+        sampler.hot_samples.remove(webview_label);
+        sampler.runaway.remove(webview_label);
+    }
+}
+
+impl Default for TaskManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn webview_pid(webview: &tauri::Webview) -> Option<u32> {
+    use objc::runtime::Object;
+    use objc::{msg_send, sel, sel_impl};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    webview
+        .with_webview(move |platform| unsafe {
+            let wk_webview = platform.inner() as *mut Object;
+            let pid: i32 = msg_send![wk_webview, _webProcessIdentifier];
+            let _ = tx.send(pid);
+        })
+        .ok()?;
+    rx.recv_timeout(PID_TIMEOUT).ok().filter(|pid| *pid > 0).map(|pid| pid as u32)
+}
+
+#[cfg(windows)]
+fn webview_pid(webview: &tauri::Webview) -> Option<u32> {
+    use webview2_com::GetProcessExtendedInfosCompletedHandler;
+    use webview2_com::Microsoft::Web::WebView2::Win32::*;
+    use windows::core::Interface;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    webview
+        .with_webview(move |platform| unsafe {
+            let Ok(core) = platform.controller().CoreWebView2() else {
+                return;
+            };
+            let mut main_frame = 0;
+            if core.cast::<ICoreWebView2_20>().and_then(|core| core.FrameId(&mut main_frame)).is_err() {
+                return;
+            }
+            let Ok(environment) = platform.environment().cast::<ICoreWebView2Environment13>() else {
+                return;
+            };
+            let handler = GetProcessExtendedInfosCompletedHandler::create(Box::new(move |result, infos| {
+                result?;
+                let Some(infos) = infos else {
+                    return Ok(());
+                };
+                let mut count = 0;
+                infos.Count(&mut count)?;
+                for i in 0..count {
+                    let info = infos.GetValueAtIndex(i)?;
+                    let process = info.ProcessInfo()?;
+                    let mut kind = COREWEBVIEW2_PROCESS_KIND_BROWSER;
+                    process.Kind(&mut kind)?;
+                    if kind != COREWEBVIEW2_PROCESS_KIND_RENDERER {
+                        continue;
+                    }
+                    let frames = info.AssociatedFrameInfos()?.GetIterator()?;
+                    let mut has_current = windows::core::BOOL::default();
+                    frames.HasCurrent(&mut has_current)?;
+                    while has_current.as_bool() {
+                        let mut frame_id = 0;
+                        if let Ok(frame) = frames.GetCurrent()?.cast::<ICoreWebView2FrameInfo2>() {
+                            frame.FrameId(&mut frame_id)?;
+                        }
+                        if frame_id == main_frame {
+                            let mut pid = 0;
+                            process.ProcessId(&mut pid)?;
+                            let _ = tx.send(pid as u32);
+                            return Ok(());
+                        }
+                        frames.MoveNext(&mut has_current)?;
+                    }
+                }
+                Ok(())
+            }));
+            if let Err(e) = environment.GetProcessExtendedInfos(&handler) {
+                eprintln!("[TaskManager] Failed to list WebView2 processes: {}", e);
+            }
+        })
+        .ok()?;
+    rx.recv_timeout(PID_TIMEOUT).ok()
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn webview_pid(_webview: &tauri::Webview) -> Option<u32> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn process_times(pid: u32) -> Option<ProcessTimes> {
+    #[repr(C)]
+    struct TimebaseInfo {
+        numer: u32,
+        denom: u32,
+    }
+    extern "C" {
+        fn mach_timebase_info(info: *mut TimebaseInfo) -> i32;
+    }
+
+    let mut info: libc::proc_taskinfo = unsafe { std::mem::zeroed() };
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as i32;
+    let read = unsafe {
+        libc::proc_pidinfo(pid as i32, libc::PROC_PIDTASKINFO, 0, &mut info as *mut _ as *mut libc::c_void, size)
+    };
+    if read != size {
+        return None;
+    }
+    // Task times are in Mach absolute time units (not nanoseconds on Apple silicon)
+    let mut timebase = TimebaseInfo { numer: 1, denom: 1 };
+    unsafe { mach_timebase_info(&mut timebase) };
+    let ticks = (info.pti_total_user + info.pti_total_system) as u128;
+    let cpu_ns = ticks * timebase.numer as u128 / timebase.denom.max(1) as u128;
+    Some(ProcessTimes { cpu_ns: cpu_ns as u64, memory: info.pti_resident_size })
+}
+
+#[cfg(windows)]
+fn process_times(pid: u32) -> Option<ProcessTimes> {
+    use windows::Win32::Foundation::{CloseHandle, FILETIME};
+    use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::{GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    let filetime = |t: FILETIME| ((t.dwHighDateTime as u64) << 32) | t.dwLowDateTime as u64;
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let (mut created, mut exited, mut kernel, mut user) =
+            (FILETIME::default(), FILETIME::default(), FILETIME::default(), FILETIME::default());
+        let times = GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user);
+        let mut counters = PROCESS_MEMORY_COUNTERS::default();
+        let memory =
+            GetProcessMemoryInfo(process, &mut counters, std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32);
+        let _ = CloseHandle(process);
+        times.ok()?;
+        memory.ok()?;
+        // FILETIME counts 100ns intervals
+        Some(ProcessTimes { cpu_ns: (filetime(kernel) + filetime(user)) * 100, memory: counters.WorkingSetSize as u64 })
+    }
+}
+
+/// utime + stime (clock ticks) from /proc/<pid>/stat.
+#[cfg_attr(any(target_os = "macos", windows), allow(dead_code))]
+fn parse_proc_stat(stat: &str) -> Option<u64> {
+    // The command name can contain spaces and parentheses; fields follow the last ')'
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 1..)?.split_whitespace().collect();
+    // Fields 14 and 15 overall, counted from 3 (state) here
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Resident pages from /proc/<pid>/statm.
+#[cfg_attr(any(target_os = "macos", windows), allow(dead_code))]
+fn parse_statm(statm: &str) -> Option<u64> {
+    statm.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn process_times(pid: u32) -> Option<ProcessTimes> {
+    let ticks = parse_proc_stat(&std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)?;
+    let pages = parse_statm(&std::fs::read_to_string(format!("/proc/{}/statm", pid)).ok()?)?;
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+    Some(ProcessTimes { cpu_ns: ticks * 1_000_000_000 / ticks_per_second, memory: pages * page_size })
+}
+
+/// Samples every tab until the app exits.
+pub fn spawn_sampler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SAMPLE_INTERVAL);
+        let Some(state) = app.try_state::<AppState>() else {
+            continue;
+        };
+        let tabs: Vec<(String, String, String, String)> = state
+            .tabs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| (t.id.clone(), t.webview_label.clone(), t.title.clone(), t.url.clone()))
+            .collect();
+        // Looked up every time: navigating to another site can move a tab to a new process
+        let tabs = tabs
+            .into_iter()
+            .map(|(id, label, title, url)| {
+                let pid = app.get_webview(&label).and_then(|webview| webview_pid(&webview));
+                (id, label, title, url, pid)
+            })
+            .collect();
+        for (tab_id, title, reason) in state.task_manager.sample(tabs) {
+            println!("[TaskManager] {} is {}", tab_id, reason);
+            let _ = app.emit_to(
+                "main",
+                "tab-runaway",
+                serde_json::json!({ "tabId": tab_id, "title": title, "reason": reason }),
+            );
+        }
+    });
+}
+
+/// Memory and CPU of every tab's process, and of the browser itself, as of the
+/// last sample.
+#[tauri::command]
+pub fn get_tab_resource_usage(state: tauri::State<AppState>) -> Result<ResourceUsage, BrowserError> {
+    Ok(state.task_manager.latest())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(cpu: Option<f64>, memory: Option<u64>) -> ProcessUsage {
+        ProcessUsage { pid: Some(42), memory, cpu }
+    }
+
+    #[test]
+    fn test_cpu_percent() {
+        let mut sampler = SamplerState::default();
+        let start = Instant::now();
+        assert_eq!(TaskManager::cpu_percent(&mut sampler, 1, start, 1_000_000_000), None);
+        let cpu = TaskManager::cpu_percent(&mut sampler, 1, start + Duration::from_secs(2), 2_000_000_000);
+        assert_eq!(cpu, Some(50.0));
+    }
+
+    #[test]
+    fn test_runaway_cpu_needs_consecutive_samples() {
+        let mut sampler = SamplerState::default();
+        for _ in 0..RUNAWAY_SAMPLES - 1 {
+            assert_eq!(TaskManager::update_runaway(&mut sampler, "tab", &usage(Some(99.0), None)), (false, None));
+        }
+        let (runaway, newly) = TaskManager::update_runaway(&mut sampler, "tab", &usage(Some(99.0), None));
+        assert!(runaway);
+        assert_eq!(newly.as_deref(), Some("using 99% CPU"));
+        // Reported once while it lasts
+        assert_eq!(TaskManager::update_runaway(&mut sampler, "tab", &usage(Some(99.0), None)), (true, None));
+        assert_eq!(TaskManager::update_runaway(&mut sampler, "tab", &usage(Some(3.0), None)), (false, None));
+    }
+
+    #[test]
+    fn test_runaway_memory() {
+        let mut sampler = SamplerState::default();
+        let (runaway, newly) = TaskManager::update_runaway(&mut sampler, "tab", &usage(None, Some(RUNAWAY_MEMORY)));
+        assert!(runaway);
+        assert_eq!(newly.as_deref(), Some("using 2048 MB of memory"));
+    }
+
+    #[test]
+    fn test_sample_marks_shared_processes() {
+        let manager = TaskManager::new();
+        let tab =
+            |id: &str, pid: Option<u32>| (id.to_string(), format!("webview-{}", id), String::new(), String::new(), pid);
+        manager.sample(vec![tab("a", Some(u32::MAX)), tab("b", Some(u32::MAX)), tab("c", None)]);
+        let latest = manager.latest();
+        let shared: Vec<bool> = latest.tabs.iter().map(|t| t.shared).collect();
+        assert_eq!(shared, vec![true, true, false]);
+        assert!(latest.sampled_at > 0);
+    }
+
+    #[test]
+    fn test_parse_proc() {
+        let stat = "1234 (Web Content (x)) S 1 1234 1234 0 -1 4194560 500 0 0 0 250 50 0 0 20 0 12 0 100";
+        assert_eq!(parse_proc_stat(stat), Some(300));
+        assert_eq!(parse_statm("51200 2560 800 10 0 3000 0"), Some(2560));
+        assert_eq!(parse_proc_stat("garbage"), None);
+    }
+}
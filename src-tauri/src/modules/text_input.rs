@@ -0,0 +1,97 @@
+// Spelling and typing substitutions in page text fields (macOS).
+//
+// WKWebView takes AppKit's text checking: continuous spellchecking,
+// autocorrection and smart quotes and dashes, as last toggled in any app's
+// Edit > Spelling and Grammar or Substitutions menu. These settings set them on
+// tab webviews when they're created and on open tabs when settings change.
+// WebKit keeps the state for the whole app rather than per view, so all tabs
+// always agree.
+//
+// Dictation is the Edit > Start Dictation item AppKit adds to the menu bar at
+// launch. NSDisabledDictationMenuItem in the app's defaults leaves it out, so
+// turning it off or on takes effect after a restart.
+//
+// Windows/Linux: nothing is applied. WebView2 and WebKitGTK spellcheck with
+// their own defaults and have no autocorrection or smart quotes.
+
+use tauri::{AppHandle, Manager, Webview};
+
+use crate::settings::Settings;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextInputSettings {
+    pub spellcheck: bool,
+    pub autocorrect: bool,
+    pub smart_quotes: bool,
+}
+
+impl TextInputSettings {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self { spellcheck: settings.spellcheck, autocorrect: settings.autocorrect, smart_quotes: settings.smart_quotes }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn apply(webview: &Webview, text: TextInputSettings) {
+    use objc::runtime::{Object, BOOL, NO, YES};
+    use objc::{msg_send, sel, sel_impl};
+
+    let flag = |on: bool| if on { YES } else { NO };
+    let result = webview.with_webview(move |platform| unsafe {
+        let wk_webview = platform.inner() as *mut Object;
+        // Spellchecking only has a toggle
+        let spellcheck: BOOL = msg_send![wk_webview, isContinuousSpellCheckingEnabled];
+        if (spellcheck != NO) != text.spellcheck {
+            let _: () = msg_send![wk_webview, toggleContinuousSpellChecking: std::ptr::null_mut::<Object>()];
+        }
+        let _: () = msg_send![wk_webview, setAutomaticSpellingCorrectionEnabled: flag(text.autocorrect)];
+        let _: () = msg_send![wk_webview, setAutomaticQuoteSubstitutionEnabled: flag(text.smart_quotes)];
+        let _: () = msg_send![wk_webview, setAutomaticDashSubstitutionEnabled: flag(text.smart_quotes)];
+    });
+    if let Err(e) = result {
+        eprintln!("[TextInput] Failed to apply text settings: {}", e);
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn apply(_webview: &Webview, _text: TextInputSettings) {}
+
+/// Re-applies the text settings to every open tab.
+pub fn apply_to_tabs(app: &AppHandle, state: &AppState) {
+    let text = TextInputSettings::from_settings(&state.settings.read().unwrap());
+    let labels: Vec<String> = state.tabs.lock().unwrap().iter().map(|t| t.webview_label.clone()).collect();
+    for label in labels {
+        if let Some(webview) = app.get_webview(&label) {
+            apply(&webview, text);
+        }
+    }
+}
+
+/// Shows or hides Edit > Start Dictation from the next launch.
+#[cfg(target_os = "macos")]
+pub fn set_dictation_menu(enabled: bool) {
+    use objc::runtime::{Object, NO, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let defaults: *mut Object = msg_send![class!(NSUserDefaults), standardUserDefaults];
+        let key: *mut Object =
+            msg_send![class!(NSString), stringWithUTF8String: c"NSDisabledDictationMenuItem".as_ptr()];
+        let _: () = msg_send![defaults, setBool: if enabled { NO } else { YES } forKey: key];
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn set_dictation_menu(_enabled: bool) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_settings_defaults() {
+        let text = TextInputSettings::from_settings(&Settings::default());
+        assert_eq!(text, TextInputSettings { spellcheck: true, autocorrect: false, smart_quotes: false });
+    }
+}
@@ -0,0 +1,447 @@
+// Find-and-replace rules for page text.
+//
+// User rules rewrite text on pages: untranslating jargon, a personal glossary,
+// or masking names while sharing the screen. A rule applies on one site (and its
+// subdomains) or everywhere, and belongs to a named pack, which can be exported
+// as JSON and imported elsewhere; importing a pack replaces the rules of the
+// same name.
+//
+// `REPLACER_SCRIPT` asks `get_text_replacements` for the rules of the page's
+// site (taken from the webview, not from the page) and rewrites text nodes and
+// the title, then keeps watching for added content. Inputs, editable content,
+// scripts and styles are left alone. Rules apply in order, and changes apply
+// from the next page load.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::adblock_manager::AdBlockManager;
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::state::AppState;
+
+const RULES_FILE: &str = "text_replacements.json";
+const MAX_FIND_LEN: usize = 200;
+const MAX_REPLACE_LEN: usize = 500;
+pub const DEFAULT_PACK: &str = "My Rules";
+
+/// Rewrites the page's text with the rules `get_text_replacements` returns.
+pub const REPLACER_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_REPLACE__ || !window.__TAURI__) return;
+    window.__SOVEREIGN_REPLACE__ = true;
+    const SKIP = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEXTAREA', 'INPUT', 'SELECT', 'CODE', 'PRE']);
+
+    function escape(text) {
+        return text.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+    }
+
+    window.__TAURI__.core.invoke('get_text_replacements').then(rules => {
+        if (!rules.length) return;
+        const compiled = rules.map(r => {
+            const body = escape(r.find);
+            const source = r.wholeWord ? `(?<![\\p{L}\\p{N}_])${body}(?![\\p{L}\\p{N}_])` : body;
+            // Replacements are literal text: no $1 expansion
+            return { re: new RegExp(source, r.caseSensitive ? 'gu' : 'giu'), replace: () => r.replace };
+        });
+        const rewrite = text => compiled.reduce((t, c) => t.replace(c.re, c.replace), text);
+
+        const written = new WeakMap(); // Text node -> what we last set it to
+        function rewriteNode(node) {
+            if (skipped(node) || written.get(node) === node.data) return;
+            const text = rewrite(node.data);
+            written.set(node, text);
+            if (text !== node.data) node.data = text;
+        }
+        function skipped(node) {
+            const parent = node.parentElement;
+            return !parent || SKIP.has(parent.tagName) || parent.isContentEditable;
+        }
+        function apply(root) {
+            if (root.nodeType === Node.TEXT_NODE) {
+                rewriteNode(root);
+                return;
+            }
+            const walker = document.createTreeWalker(root, NodeFilter.SHOW_TEXT);
+            for (let node = walker.nextNode(); node; node = walker.nextNode()) rewriteNode(node);
+        }
+        let writtenTitle = null;
+        function applyTitle() {
+            if (document.title === writtenTitle) return;
+            writtenTitle = rewrite(document.title);
+            if (writtenTitle !== document.title) document.title = writtenTitle;
+        }
+
+        const start = () => {
+            apply(document.body || document.documentElement);
+            applyTitle();
+            // Our own edits come back as characterData changes; `written` skips them
+            new MutationObserver(mutations => {
+                for (const m of mutations) {
+                    if (m.type === 'characterData') apply(m.target);
+                    else m.addedNodes.forEach(apply);
+                }
+                applyTitle();
+            }).observe(document.documentElement, { childList: true, subtree: true, characterData: true });
+        };
+        if (document.readyState === 'loading') document.addEventListener('DOMContentLoaded', start, { once: true });
+        else start();
+    }).catch(() => {});
+})();
+"#;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplacementRule {
+    pub id: u64,
+    #[serde(default)]
+    pub domain: Option<String>, // None: every site
+    pub find: String,
+    pub replace: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_pack")]
+    pub pack: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_pack() -> String {
+    DEFAULT_PACK.to_string()
+}
+
+/// What a page gets: just enough to apply the rule.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageReplacement {
+    pub find: String,
+    pub replace: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+}
+
+/// A rule as added by the user or found in an imported pack.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleInput {
+    #[serde(default)]
+    pub domain: Option<String>,
+    pub find: String,
+    pub replace: String,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RulePack {
+    pub name: String,
+    pub rules: Vec<RuleInput>,
+}
+
+/// Checks a rule and normalizes its domain.
+pub fn validate_rule(input: RuleInput) -> Result<RuleInput, String> {
+    if input.find.trim().is_empty() {
+        return Err("The text to find is empty".to_string());
+    }
+    if input.find.chars().count() > MAX_FIND_LEN {
+        return Err(format!("The text to find is longer than {} characters", MAX_FIND_LEN));
+    }
+    if input.replace.chars().count() > MAX_REPLACE_LEN {
+        return Err(format!("The replacement is longer than {} characters", MAX_REPLACE_LEN));
+    }
+    let domain = match input.domain.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(domain) => {
+            Some(AdBlockManager::normalize_domain(domain).ok_or_else(|| format!("Invalid site: {}", domain))?)
+        }
+        None => None,
+    };
+    Ok(RuleInput { domain, ..input })
+}
+
+/// The enabled rules for a host, in order.
+pub fn rules_for_host(rules: &[ReplacementRule], host: &str) -> Vec<PageReplacement> {
+    let host = host.to_lowercase();
+    rules
+        .iter()
+        .filter(|r| r.enabled)
+        .filter(|r| match &r.domain {
+            Some(d) => host == *d || host.ends_with(&format!(".{}", d)),
+            None => true,
+        })
+        .map(|r| PageReplacement {
+            find: r.find.clone(),
+            replace: r.replace.clone(),
+            case_sensitive: r.case_sensitive,
+            whole_word: r.whole_word,
+        })
+        .collect()
+}
+
+pub struct TextReplaceManager {
+    path: PathBuf,
+    rules: Mutex<Vec<ReplacementRule>>,
+}
+
+impl TextReplaceManager {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(RULES_FILE);
+        let rules: Vec<ReplacementRule> =
+            fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        if !rules.is_empty() {
+            println!("[TextReplace] Loaded {} rules", rules.len());
+        }
+        Self { path, rules: Mutex::new(rules) }
+    }
+
+    fn save(&self, rules: &[ReplacementRule]) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(rules).unwrap_or_default()) {
+            eprintln!("[TextReplace] Failed to save rules: {}", e);
+        }
+    }
+
+    pub fn rules(&self) -> Vec<ReplacementRule> {
+        self.rules.lock().unwrap().clone()
+    }
+
+    pub fn for_host(&self, host: &str) -> Vec<PageReplacement> {
+        rules_for_host(&self.rules.lock().unwrap(), host)
+    }
+
+    pub fn add(&self, input: RuleInput, pack: Option<String>) -> Result<ReplacementRule, String> {
+        let input = validate_rule(input)?;
+        let mut rules = self.rules.lock().unwrap();
+        let rule = ReplacementRule {
+            id: rules.iter().map(|r| r.id).max().unwrap_or(0) + 1,
+            domain: input.domain,
+            find: input.find,
+            replace: input.replace,
+            case_sensitive: input.case_sensitive,
+            whole_word: input.whole_word,
+            enabled: true,
+            pack: pack.filter(|p| !p.trim().is_empty()).unwrap_or_else(default_pack),
+        };
+        rules.push(rule.clone());
+        self.save(&rules);
+        Ok(rule)
+    }
+
+    pub fn remove(&self, id: u64) -> Result<(), String> {
+        let mut rules = self.rules.lock().unwrap();
+        let before = rules.len();
+        rules.retain(|r| r.id != id);
+        if rules.len() == before {
+            return Err(format!("No rule {}", id));
+        }
+        self.save(&rules);
+        Ok(())
+    }
+
+    pub fn set_enabled(&self, id: u64, enabled: bool) -> Result<(), String> {
+        let mut rules = self.rules.lock().unwrap();
+        let rule = rules.iter_mut().find(|r| r.id == id).ok_or_else(|| format!("No rule {}", id))?;
+        rule.enabled = enabled;
+        self.save(&rules);
+        Ok(())
+    }
+
+    pub fn export_pack(&self, name: &str) -> Result<RulePack, String> {
+        let rules: Vec<RuleInput> = self
+            .rules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.pack == name)
+            .map(|r| RuleInput {
+                domain: r.domain.clone(),
+                find: r.find.clone(),
+                replace: r.replace.clone(),
+                case_sensitive: r.case_sensitive,
+                whole_word: r.whole_word,
+            })
+            .collect();
+        if rules.is_empty() {
+            return Err(format!("No rule pack named {}", name));
+        }
+        Ok(RulePack { name: name.to_string(), rules })
+    }
+
+    /// Replaces the rules of the pack's name with its rules. All of them must be valid.
+    pub fn import_pack(&self, pack: RulePack) -> Result<usize, String> {
+        let name = pack.name.trim().to_string();
+        if name.is_empty() {
+            return Err("The rule pack has no name".to_string());
+        }
+        let inputs = pack.rules.into_iter().map(validate_rule).collect::<Result<Vec<_>, _>>()?;
+        let mut rules = self.rules.lock().unwrap();
+        rules.retain(|r| r.pack != name);
+        let mut next_id = rules.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+        let count = inputs.len();
+        for input in inputs {
+            rules.push(ReplacementRule {
+                id: next_id,
+                domain: input.domain,
+                find: input.find,
+                replace: input.replace,
+                case_sensitive: input.case_sensitive,
+                whole_word: input.whole_word,
+                enabled: true,
+                pack: name.clone(),
+            });
+            next_id += 1;
+        }
+        self.save(&rules);
+        println!("[TextReplace] Imported {} rules into {}", count, name);
+        Ok(count)
+    }
+}
+
+/// Asked by `REPLACER_SCRIPT` for the rules of the page's site.
+#[tauri::command]
+pub fn get_text_replacements(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<PageReplacement>, BrowserError> {
+    Ok(match webview.url()?.host_str() {
+        Some(host) => state.text_replace.for_host(host),
+        None => Vec::new(),
+    })
+}
+
+#[tauri::command]
+pub fn list_replacement_rules(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<ReplacementRule>, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    Ok(state.text_replace.rules())
+}
+
+#[tauri::command]
+pub fn add_replacement_rule(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    rule: RuleInput,
+    pack: Option<String>,
+) -> Result<ReplacementRule, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    state.text_replace.add(rule, pack).map_err(BrowserError::InvalidInput)
+}
+
+#[tauri::command]
+pub fn delete_replacement_rule(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: u64,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    state.text_replace.remove(id).map_err(BrowserError::NotFound)
+}
+
+#[tauri::command]
+pub fn set_replacement_rule_enabled(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: u64,
+    enabled: bool,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    state.text_replace.set_enabled(id, enabled).map_err(BrowserError::NotFound)
+}
+
+/// A pack as JSON, for sharing.
+#[tauri::command]
+pub fn export_replacement_pack(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    name: String,
+) -> Result<String, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let pack = state.text_replace.export_pack(&name).map_err(BrowserError::NotFound)?;
+    serde_json::to_string_pretty(&pack).map_err(|e| BrowserError::Internal(e.to_string()))
+}
+
+#[tauri::command]
+pub fn import_replacement_pack(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    json: String,
+) -> Result<usize, BrowserError> {
+    check_caller(&webview, Caller::BrowserUi)?;
+    let pack: RulePack =
+        serde_json::from_str(&json).map_err(|e| BrowserError::InvalidInput(format!("Not a rule pack: {}", e)))?;
+    state.text_replace.import_pack(pack).map_err(BrowserError::InvalidInput)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn input(domain: Option<&str>, find: &str, replace: &str) -> RuleInput {
+        RuleInput {
+            domain: domain.map(str::to_string),
+            find: find.to_string(),
+            replace: replace.to_string(),
+            case_sensitive: false,
+            whole_word: false,
+        }
+    }
+
+    #[rstest]
+    #[case(input(None, "LGTM", "looks good"), true)]
+    #[case(input(Some("Example.COM"), "Alice", "███"), true)]
+    #[case(input(None, "  ", "x"), false)]
+    #[case(input(None, &"a".repeat(MAX_FIND_LEN + 1), "x"), false)]
+    #[case(input(Some("not a domain!"), "a", "b"), false)]
+    fn test_validate_rule(#[case] rule: RuleInput, #[case] valid: bool) {
+        assert_eq!(validate_rule(rule).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_rules_for_host() {
+        let dir = TempDir::new().unwrap();
+        let manager = TextReplaceManager::new(dir.path().to_path_buf());
+        manager.add(input(None, "k8s", "Kubernetes"), None).unwrap();
+        let scoped = manager.add(input(Some("example.com"), "Alice", "A."), None).unwrap();
+        manager.add(input(Some("other.org"), "Bob", "B."), None).unwrap();
+
+        let finds = |host: &str| manager.for_host(host).into_iter().map(|r| r.find).collect::<Vec<_>>();
+        assert_eq!(finds("docs.example.com"), vec!["k8s", "Alice"]);
+        assert_eq!(finds("notexample.com"), vec!["k8s"]);
+
+        manager.set_enabled(scoped.id, false).unwrap();
+        assert_eq!(finds("example.com"), vec!["k8s"]);
+
+        // Survives a restart
+        let reloaded = TextReplaceManager::new(dir.path().to_path_buf());
+        assert_eq!(reloaded.rules(), manager.rules());
+    }
+
+    #[test]
+    fn test_import_replaces_pack() {
+        let dir = TempDir::new().unwrap();
+        let manager = TextReplaceManager::new(dir.path().to_path_buf());
+        manager.add(input(None, "mine", "kept"), None).unwrap();
+        manager.add(input(None, "old", "gone"), Some("Jargon".to_string())).unwrap();
+
+        let pack = RulePack { name: "Jargon".to_string(), rules: vec![input(None, "PTAL", "please take a look")] };
+        assert_eq!(manager.import_pack(pack.clone()), Ok(1));
+        assert_eq!(manager.export_pack("Jargon"), Ok(pack));
+        assert_eq!(manager.rules().iter().map(|r| r.find.as_str()).collect::<Vec<_>>(), vec!["mine", "PTAL"]);
+
+        let invalid = RulePack { name: "Jargon".to_string(), rules: vec![input(None, "", "x")] };
+        assert!(manager.import_pack(invalid).is_err());
+        assert_eq!(manager.rules().len(), 2);
+    }
+}
@@ -0,0 +1,306 @@
+// Page thumbnails for the new tab page's top sites.
+//
+// When a page of a top site finishes loading we wait for the tab to sit idle for
+// a few seconds, then, if it's still the active tab on that site, take a scaled
+// snapshot of the webview and cache it as a PNG in <app data>/thumbnails/, one
+// per origin, named by a hash of it salted with a secret of the profile's (so a
+// page can't work out the name for a site). A thumbnail is retaken once it's
+// older than `MAX_AGE`. The new tab page loads them through the `thumbnail` URI
+// scheme (thumbnail://localhost/<key>.png, http://thumbnail.localhost/ on
+// Windows), so image data never goes over IPC; requests from any other page are
+// refused. Clearing history clears them too. Bookmarks don't get thumbnails,
+// since nothing shows bookmarks as tiles.
+//
+// Snapshots use WKWebView's takeSnapshotWithConfiguration: on macOS. Other
+// platforms take none yet and tiles fall back to the favicon.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::modules::{fingerprint, top_sites, totp};
+use crate::state::AppState;
+
+pub const SCHEME: &str = "thumbnail";
+const THUMBNAIL_DIR: &str = "thumbnails";
+const SALT_FILE: &str = "thumbnail_secret";
+const IDLE_DELAY: Duration = Duration::from_secs(3);
+const MAX_AGE: Duration = Duration::from_secs(7 * 86400);
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const THUMBNAIL_WIDTH: f64 = 320.0; // Points; the height follows the page's aspect ratio
+
+/// The cache key for a page's site: a hash of `salt` and its origin. None for non-web pages.
+fn key_for(salt: &str, url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let digest = totp::sha1(format!("{}{}", salt, url.origin().ascii_serialization()).as_bytes());
+    Some(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Where pages load thumbnails from.
+pub fn base_url() -> &'static str {
+    if cfg!(windows) {
+        "http://thumbnail.localhost/"
+    } else {
+        "thumbnail://localhost/"
+    }
+}
+
+/// The key in a thumbnail request path, if it names one ("/<40 hex digits>.png").
+fn key_in_path(path: &str) -> Option<&str> {
+    let key = path.trim_start_matches('/').strip_suffix(".png")?;
+    (key.len() == 40 && key.bytes().all(|b| b.is_ascii_hexdigit())).then_some(key)
+}
+
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    salt: String,
+    pending: Mutex<HashSet<String>>, // Keys waiting for a snapshot
+}
+
+impl ThumbnailCache {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let dir = app_dir.join(THUMBNAIL_DIR);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            eprintln!("[Thumbnails] Failed to create {}: {}", dir.display(), e);
+        }
+        let salt_path = app_dir.join(SALT_FILE);
+        let new_salt = !salt_path.exists();
+        let salt = fingerprint::load_or_create_secret(&salt_path);
+        let cache = Self { dir, salt, pending: Mutex::new(HashSet::new()) };
+        if new_salt {
+            // Named with another salt (or none), so nothing would find them again
+            cache.clear(None);
+        }
+        cache
+    }
+
+    pub fn key_for(&self, url: &str) -> Option<String> {
+        key_for(&self.salt, url)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.png", key))
+    }
+
+    fn modified(&self, key: &str) -> Option<SystemTime> {
+        fs::metadata(self.path(key)).and_then(|m| m.modified()).ok()
+    }
+
+    fn is_fresh(&self, key: &str, now: SystemTime) -> bool {
+        self.modified(key).is_some_and(|t| now.duration_since(t).unwrap_or_default() < MAX_AGE)
+    }
+
+    /// The thumbnail URL for a page's site, if one is cached. Versioned by its
+    /// time so a retaken thumbnail isn't served from the page's image cache.
+    pub fn url_for(&self, page_url: &str) -> Option<String> {
+        let key = self.key_for(page_url)?;
+        let version = self.modified(&key)?.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Some(format!("{}{}.png?v={}", base_url(), key, version))
+    }
+
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(key_in_path(path)?)).ok()
+    }
+
+    pub fn store(&self, key: &str, png: &[u8]) {
+        if let Err(e) = fs::write(self.path(key), png) {
+            eprintln!("[Thumbnails] Failed to save {}: {}", key, e);
+        }
+    }
+
+    /// Claims `key` for a snapshot unless one is pending or it's still fresh.
+    fn begin(&self, key: &str) -> bool {
+        !self.is_fresh(key, SystemTime::now()) && self.pending.lock().unwrap().insert(key.to_string())
+    }
+
+    fn finish(&self, key: &str) {
+        self.pending.lock().unwrap().remove(key);
+    }
+
+    /// Deletes thumbnails taken since `since` (all of them for None). Returns how many.
+    pub fn clear(&self, since: Option<SystemTime>) -> usize {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        entries
+            .flatten()
+            .filter(|e| {
+                let modified = e.metadata().and_then(|m| m.modified()).ok();
+                since.map_or(true, |since| modified.map_or(true, |m| m >= since))
+            })
+            .filter(|e| fs::remove_file(e.path()).is_ok())
+            .count()
+    }
+}
+
+/// Whether the webview `label` may load thumbnails: only while it shows the new tab page.
+fn may_load(app: &AppHandle, label: &str) -> bool {
+    app.get_webview(label).and_then(|w| w.url().ok()).is_some_and(|url| top_sites::is_new_tab_page(url.as_str()))
+}
+
+/// Serves `thumbnail` scheme requests from the cache to the new tab page.
+pub fn handle_request(app: &AppHandle, label: &str, request: &http::Request<Vec<u8>>) -> http::Response<Vec<u8>> {
+    let png = if may_load(app, label) {
+        app.try_state::<AppState>().and_then(|state| state.thumbnails.read(request.uri().path()))
+    } else {
+        eprintln!("[Thumbnails] Refused request from {}", label);
+        None
+    };
+    let response = match png {
+        Some(png) => http::Response::builder().header(http::header::CONTENT_TYPE, "image/png").body(png),
+        None => http::Response::builder().status(http::StatusCode::NOT_FOUND).body(Vec::new()),
+    };
+    response.unwrap_or_default()
+}
+
+/// Schedules a snapshot of a top site's page once the tab has been idle for a while.
+pub fn on_page_finished(app: &AppHandle, webview: &tauri::Webview, state: &AppState, url: &Url) {
+    let page_url = url.to_string();
+    let Some(key) = state.thumbnails.key_for(&page_url) else {
+        return;
+    };
+    // Cheap checks first; ranking the top sites reads the whole history
+    if state.thumbnails.is_fresh(&key, SystemTime::now()) {
+        return;
+    }
+    let origin = url.origin();
+    let is_top_site = top_sites::current(state, top_sites::MAX_LIMIT)
+        .iter()
+        .any(|site| Url::parse(&site.url).is_ok_and(|u| u.origin() == origin));
+    if !is_top_site || !state.thumbnails.begin(&key) {
+        return;
+    }
+
+    let app = app.clone();
+    let label = webview.label().to_string();
+    std::thread::spawn(move || {
+        std::thread::sleep(IDLE_DELAY);
+        let Some(state) = app.try_state::<AppState>() else {
+            return;
+        };
+        let cache = state.thumbnails.clone();
+        // Only the visible page, and only if it hasn't moved on
+        let still_showing = {
+            let tabs = state.tabs.lock().unwrap();
+            let active_id = state.active_tab_id.lock().unwrap().clone();
+            tabs.iter().any(|t| {
+                t.webview_label == label && Some(&t.id) == active_id.as_ref() && t.url == page_url && !t.is_loading
+            })
+        };
+        match app.get_webview(&label) {
+            Some(webview) if still_showing => snapshot(&webview, cache, key),
+            _ => cache.finish(&key),
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn snapshot(webview: &tauri::Webview, cache: Arc<ThumbnailCache>, key: String) {
+    use block::ConcreteBlock;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    const PNG_FILE_TYPE: u64 = 4; // NSBitmapImageFileTypePNG
+
+    let done = cache.clone();
+    let done_key = key.clone();
+    let result = webview.with_webview(move |platform| unsafe {
+        let wk_webview = platform.inner() as *mut Object;
+        let config: *mut Object = msg_send![class!(WKSnapshotConfiguration), new];
+        let width: *mut Object = msg_send![class!(NSNumber), numberWithDouble: THUMBNAIL_WIDTH];
+        let _: () = msg_send![config, setSnapshotWidth: width];
+
+        let completion = ConcreteBlock::new(move |image: *mut Object, _error: *mut Object| {
+            if !image.is_null() {
+                let tiff: *mut Object = msg_send![image, TIFFRepresentation];
+                let rep: *mut Object = msg_send![class!(NSBitmapImageRep), imageRepWithData: tiff];
+                let properties: *mut Object = msg_send![class!(NSDictionary), dictionary];
+                let png: *mut Object = if rep.is_null() {
+                    std::ptr::null_mut()
+                } else {
+                    msg_send![rep, representationUsingType: PNG_FILE_TYPE properties: properties]
+                };
+                if !png.is_null() {
+                    let bytes: *const u8 = msg_send![png, bytes];
+                    let length: usize = msg_send![png, length];
+                    cache.store(&key, std::slice::from_raw_parts(bytes, length));
+                    println!("[Thumbnails] Saved {}", key);
+                }
+            }
+            cache.finish(&key);
+        })
+        .copy();
+        let _: () = msg_send![wk_webview, takeSnapshotWithConfiguration: config completionHandler: &*completion];
+        let _: () = msg_send![config, release];
+    });
+    if let Err(e) = result {
+        eprintln!("[Thumbnails] Failed to take snapshot: {}", e);
+        done.finish(&done_key);
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn snapshot(_webview: &tauri::Webview, cache: Arc<ThumbnailCache>, key: String) {
+    cache.finish(&key); // No snapshot support yet, see the top of this file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_for_is_per_origin() {
+        let key = key_for("salt", "https://example.com/a?b=c").unwrap();
+        assert_eq!(key.len(), 40);
+        assert_eq!(key_for("salt", "https://example.com/other").as_ref(), Some(&key));
+        assert_ne!(key_for("salt", "http://example.com/").as_ref(), Some(&key));
+        assert_eq!(key_for("salt", "tauri://localhost/new-tab.html"), None);
+    }
+
+    #[test]
+    fn test_key_for_is_salted_per_profile() {
+        let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let key = ThumbnailCache::new(a.path().to_path_buf()).key_for("https://example.com/").unwrap();
+        assert_ne!(key_for("", "https://example.com/").as_ref(), Some(&key));
+        assert_ne!(ThumbnailCache::new(b.path().to_path_buf()).key_for("https://example.com/").as_ref(), Some(&key));
+        // Kept across launches
+        assert_eq!(ThumbnailCache::new(a.path().to_path_buf()).key_for("https://example.com/").as_ref(), Some(&key));
+    }
+
+    #[test]
+    fn test_key_in_path_rejects_other_files() {
+        let key = key_for("salt", "https://example.com/").unwrap();
+        assert_eq!(key_in_path(&format!("/{}.png", key)), Some(key.as_str()));
+        assert_eq!(key_in_path("/../settings.json"), None);
+        assert_eq!(key_in_path("/abc.png"), None);
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().to_path_buf());
+        let key = cache.key_for("https://example.com/").unwrap();
+        assert!(cache.url_for("https://example.com/").is_none());
+        assert!(cache.begin(&key));
+        assert!(!cache.begin(&key)); // Already pending
+
+        cache.store(&key, b"png");
+        cache.finish(&key);
+        assert!(!cache.begin(&key)); // Fresh
+        let url = cache.url_for("https://example.com/page").unwrap();
+        assert!(url.starts_with(&format!("{}{}.png?v=", base_url(), key)));
+        assert_eq!(cache.read(&format!("/{}.png", key)).as_deref(), Some(&b"png"[..]));
+
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(cache.clear(Some(later)), 0);
+        assert_eq!(cache.clear(None), 1);
+        assert!(cache.read(&format!("/{}.png", key)).is_none());
+    }
+}
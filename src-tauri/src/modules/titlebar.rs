@@ -0,0 +1,100 @@
+// Titlebar and window controls of the main window.
+//
+// macOS keeps the system titlebar, overlaid on the tab strip with the traffic
+// lights at its left end. Windows gets a custom one: `apply` removes the system
+// titlebar (keeping the frame's shadow and rounded corners), the tab strip
+// becomes the titlebar, and the toolbar page draws minimize, maximize and close
+// at its right end where modules::layout leaves room. Empty tab strip space
+// drags the window and double-clicking it maximizes. A restored window keeps a
+// strip above the tabs for resizing from the top edge; the page leaves it alone
+// rather than starting a drag there. Linux keeps the system titlebar.
+//
+// Snap Layouts (Windows 11) open when the system sees the pointer resting on a
+// maximize button. The webview takes all pointer input, so the system never
+// sees the page's button; hovering it asks for the flyout directly with Win+Z,
+// which opens it for the foreground window.
+
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Manager, Window};
+
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+pub fn apply(window: &Window) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = window.set_title_bar_style(tauri::TitleBarStyle::Overlay);
+    }
+    #[cfg(windows)]
+    {
+        if let Err(e) = window.set_decorations(false) {
+            eprintln!("[Titlebar] Failed to remove the system titlebar: {}", e);
+        }
+        let _ = window.set_shadow(true);
+    }
+    #[cfg(not(any(target_os = "macos", windows)))]
+    let _ = window;
+}
+
+/// Called on the main window's Resized events. True when it was just maximized
+/// or restored, so the toolbar page should redraw its top row.
+pub fn track_maximized(window: &Window, state: &AppState) -> bool {
+    let maximized = window.is_maximized().unwrap_or(false);
+    state.main_maximized.swap(maximized, Ordering::Relaxed) != maximized
+}
+
+#[tauri::command]
+pub fn minimize_window(app: AppHandle) -> Result<(), BrowserError> {
+    if let Some(window) = app.get_window("main") {
+        window.minimize()?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn close_window(app: AppHandle) -> Result<(), BrowserError> {
+    if let Some(window) = app.get_window("main") {
+        window.close()?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn show_snap_layouts(app: AppHandle) -> Result<(), BrowserError> {
+    let window = app.get_window("main").ok_or_else(|| BrowserError::NotFound("Main window not found".to_string()))?;
+    // Win+Z goes to whichever window is in front
+    if !window.is_focused()? {
+        return Ok(());
+    }
+    send_snap_shortcut().map_err(BrowserError::Unsupported)
+}
+
+#[cfg(windows)]
+fn send_snap_shortcut() -> Result<(), String> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_LWIN,
+    };
+
+    const VK_Z: VIRTUAL_KEY = VIRTUAL_KEY(0x5A);
+    let key = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 { ki: KEYBDINPUT { wVk: vk, wScan: 0, dwFlags: flags, time: 0, dwExtraInfo: 0 } },
+    };
+    let inputs = [
+        key(VK_LWIN, KEYBD_EVENT_FLAGS(0)),
+        key(VK_Z, KEYBD_EVENT_FLAGS(0)),
+        key(VK_Z, KEYEVENTF_KEYUP),
+        key(VK_LWIN, KEYEVENTF_KEYUP),
+    ];
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize == inputs.len() {
+        Ok(())
+    } else {
+        Err(format!("Only {} of {} key events were sent", sent, inputs.len()))
+    }
+}
+
+#[cfg(not(windows))]
+fn send_snap_shortcut() -> Result<(), String> {
+    Err("Snap Layouts are a Windows feature".to_string())
+}
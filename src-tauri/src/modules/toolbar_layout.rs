@@ -0,0 +1,115 @@
+// Toolbar layout - ordered widget list persisted in Settings. Pure logic, no Tauri imports.
+// The toolbar UI renders widgets in this order and drives drag-customization
+// through `get_toolbar_layout` / `set_toolbar_layout`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolbarWidget {
+    BackForward,
+    Reload,
+    UrlBar,
+    Shield,
+    Reader,
+    Downloads,
+    Extensions,
+    FlexibleSpace,
+    // Widgets written by a newer version; dropped on load
+    #[serde(other)]
+    Unknown,
+}
+
+impl ToolbarWidget {
+    /// Flexible spaces are the only widget that may appear more than once.
+    pub fn is_repeatable(&self) -> bool {
+        matches!(self, Self::FlexibleSpace)
+    }
+}
+
+pub fn default_layout() -> Vec<ToolbarWidget> {
+    vec![
+        ToolbarWidget::BackForward,
+        ToolbarWidget::Reload,
+        ToolbarWidget::UrlBar,
+        ToolbarWidget::Shield,
+        ToolbarWidget::Downloads,
+    ]
+}
+
+/// Rejects layouts the toolbar can't render: the URL bar is required and every
+/// widget except flexible space is unique.
+pub fn validate(layout: &[ToolbarWidget]) -> Result<(), String> {
+    if !layout.contains(&ToolbarWidget::UrlBar) {
+        return Err("The toolbar must contain the URL bar".to_string());
+    }
+    for (i, widget) in layout.iter().enumerate() {
+        if *widget == ToolbarWidget::Unknown {
+            return Err("Unknown toolbar widget".to_string());
+        }
+        if !widget.is_repeatable() && layout[..i].contains(widget) {
+            return Err(format!("{:?} can only appear once", widget));
+        }
+    }
+    Ok(())
+}
+
+/// Repairs a stored layout: drops unknown and duplicate widgets and restores the
+/// URL bar. An empty layout (settings from before this option existed) gets the default.
+pub fn migrate(layout: Vec<ToolbarWidget>) -> Vec<ToolbarWidget> {
+    if layout.is_empty() {
+        return default_layout();
+    }
+
+    let mut migrated: Vec<ToolbarWidget> = Vec::with_capacity(layout.len());
+    for widget in layout {
+        if widget == ToolbarWidget::Unknown {
+            continue;
+        }
+        if !widget.is_repeatable() && migrated.contains(&widget) {
+            continue;
+        }
+        migrated.push(widget);
+    }
+
+    if !migrated.contains(&ToolbarWidget::UrlBar) {
+        // Put it back after navigation controls, where it lives by default
+        let index = migrated.iter()
+            .position(|w| !matches!(w, ToolbarWidget::BackForward | ToolbarWidget::Reload))
+            .unwrap_or(migrated.len());
+        migrated.insert(index, ToolbarWidget::UrlBar);
+    }
+    migrated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ToolbarWidget::*;
+
+    #[test]
+    fn test_default_layout_is_valid() {
+        assert!(validate(&default_layout()).is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[BackForward, FlexibleSpace, UrlBar, FlexibleSpace]).is_ok());
+        assert!(validate(&[BackForward, Shield]).is_err());
+        assert!(validate(&[UrlBar, Shield, Shield]).is_err());
+        assert!(validate(&[UrlBar, Unknown]).is_err());
+    }
+
+    #[test]
+    fn test_migrate() {
+        assert_eq!(migrate(vec![]), default_layout());
+        assert_eq!(migrate(vec![Reload, Shield, Shield, Unknown]), vec![Reload, UrlBar, Shield]);
+        assert_eq!(migrate(vec![FlexibleSpace, UrlBar, FlexibleSpace]), vec![FlexibleSpace, UrlBar, FlexibleSpace]);
+    }
+
+    #[test]
+    fn test_unknown_widgets_deserialize() {
+        let layout: Vec<ToolbarWidget> = serde_json::from_str(r#"["url_bar", "sidebar_toggle"]"#).unwrap();
+        assert_eq!(migrate(layout), vec![UrlBar]);
+    }
+}
@@ -0,0 +1,436 @@
+// Top sites for the new tab page (ui/new-tab.html).
+//
+// Sites are ranked by frecency over the history: each page scores its visits
+// (typed ones count double) times a weight for how recently it was last seen,
+// and pages add up per origin, so a site you use every day beats a page you
+// once opened a hundred times. Pinned sites come first in the order they were
+// pinned; hidden ones never show. Pins, hidden sites and the last favicon seen
+// for each host are kept in top_sites.json. Nothing here touches the network.
+// Tiles show a page thumbnail when modules::thumbnails has one for the site.
+//
+// New tabs open the page when `new_tab_top_sites` is on (the default), or the
+// homepage otherwise. "about:newtab" is accepted as a short name for it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::history::HistoryEntry;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::https_only;
+use crate::settings::Settings;
+use crate::state::AppState;
+
+const TOP_SITES_FILE: &str = "top_sites.json";
+pub const NEW_TAB_PAGE: &str = "new-tab.html";
+pub const NEW_TAB_ALIAS: &str = "about:newtab";
+const DEFAULT_LIMIT: usize = 8;
+pub const MAX_LIMIT: usize = 24;
+const MAX_FAVICON_LEN: usize = 8 * 1024; // Skip big data: URLs
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopSite {
+    pub url: String,
+    pub title: String,
+    pub host: String,
+    pub favicon: Option<String>,
+    pub thumbnail: Option<String>, // thumbnail:// URL, see modules::thumbnails
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinnedSite {
+    pub url: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopSitesFile {
+    #[serde(default)]
+    pub pinned: Vec<PinnedSite>,
+    #[serde(default)]
+    pub hidden: Vec<String>, // Origins
+    #[serde(default)]
+    pub favicons: HashMap<String, String>, // Host -> favicon URL
+}
+
+/// The new tab page's URL, for creating tabs.
+pub fn new_tab_page_url() -> String {
+    https_only::app_page_url(NEW_TAB_PAGE).to_string()
+}
+
+pub fn is_new_tab_page(url: &str) -> bool {
+    url == NEW_TAB_ALIAS
+        || Url::parse(url).is_ok_and(|u| https_only::is_app_page(&u) && u.path() == format!("/{}", NEW_TAB_PAGE))
+}
+
+/// What a new tab opens.
+pub fn new_tab_url(settings: &Settings) -> String {
+    if settings.new_tab_top_sites {
+        new_tab_page_url()
+    } else {
+        settings.homepage.clone()
+    }
+}
+
+fn origin_of(url: &str) -> Option<(String, String)> {
+    let url = Url::parse(url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    Some((url.origin().ascii_serialization(), url.host_str()?.to_string()))
+}
+
+/// How much a visit last seen `age_days` ago is worth.
+fn recency_weight(age_days: u64) -> f64 {
+    match age_days {
+        0..=4 => 100.0,
+        5..=14 => 70.0,
+        15..=31 => 50.0,
+        32..=90 => 30.0,
+        _ => 10.0,
+    }
+}
+
+pub fn frecency(entry: &HistoryEntry, now: u64) -> f64 {
+    let age_days = now.saturating_sub(entry.last_visit) / 86400;
+    (entry.visit_count + entry.typed_count) as f64 * recency_weight(age_days)
+}
+
+/// Pinned sites, then the best scoring origins that aren't pinned or hidden.
+pub fn rank(history: &[HistoryEntry], prefs: &TopSitesFile, now: u64, limit: usize) -> Vec<TopSite> {
+    struct Site {
+        host: String,
+        score: f64,
+        title: String,
+        title_score: f64,
+        root_title: Option<String>,
+    }
+
+    let mut sites: HashMap<String, Site> = HashMap::new();
+    for entry in history {
+        let Some((origin, host)) = origin_of(&entry.url) else {
+            continue;
+        };
+        let score = frecency(entry, now);
+        let site = sites.entry(origin.clone()).or_insert(Site {
+            host,
+            score: 0.0,
+            title: String::new(),
+            title_score: -1.0,
+            root_title: None,
+        });
+        site.score += score;
+        if !entry.title.is_empty() {
+            // The home page's title names the site best; otherwise the busiest page's
+            if entry.url.trim_end_matches('/') == origin {
+                site.root_title = Some(entry.title.clone());
+            } else if score > site.title_score {
+                site.title = entry.title.clone();
+                site.title_score = score;
+            }
+        }
+    }
+
+    let favicon = |host: &str| prefs.favicons.get(host).cloned();
+    let pinned_origins: Vec<String> = prefs.pinned.iter().filter_map(|p| origin_of(&p.url)).map(|(o, _)| o).collect();
+    let mut result: Vec<TopSite> = prefs
+        .pinned
+        .iter()
+        .map(|pin| {
+            let host = origin_of(&pin.url).map(|(_, host)| host).unwrap_or_default();
+            TopSite {
+                url: pin.url.clone(),
+                title: if pin.title.is_empty() { host.clone() } else { pin.title.clone() },
+                favicon: favicon(&host),
+                thumbnail: None,
+                host,
+                pinned: true,
+            }
+        })
+        .collect();
+
+    let mut ranked: Vec<(String, Site)> = sites
+        .into_iter()
+        .filter(|(origin, _)| !prefs.hidden.contains(origin) && !pinned_origins.contains(origin))
+        .collect();
+    ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then_with(|| a.0.cmp(&b.0)));
+    for (origin, site) in ranked {
+        if result.len() >= limit {
+            break;
+        }
+        let title = site.root_title.unwrap_or(site.title);
+        result.push(TopSite {
+            url: format!("{}/", origin),
+            title: if title.is_empty() { site.host.clone() } else { title },
+            favicon: favicon(&site.host),
+            thumbnail: None,
+            host: site.host,
+            pinned: false,
+        });
+    }
+    result.truncate(limit);
+    result
+}
+
+/// The top sites right now, with their cached thumbnails.
+pub fn current(state: &AppState, limit: usize) -> Vec<TopSite> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let history = state.history.list(0, usize::MAX, None, None).entries;
+    let mut sites = rank(&history, &state.top_sites.prefs(), now, limit);
+    for site in &mut sites {
+        site.thumbnail = state.thumbnails.url_for(&site.url);
+    }
+    sites
+}
+
+pub struct TopSitesStore {
+    path: PathBuf,
+    data: Mutex<TopSitesFile>,
+}
+
+impl TopSitesStore {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(TOP_SITES_FILE);
+        let data = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, data: Mutex::new(data) }
+    }
+
+    fn save(&self, data: &TopSitesFile) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(data).unwrap_or_default()) {
+            eprintln!("[TopSites] Failed to save: {}", e);
+        }
+    }
+
+    pub fn prefs(&self) -> TopSitesFile {
+        self.data.lock().unwrap().clone()
+    }
+
+    pub fn pin(&self, url: &str, title: &str) -> Result<(), String> {
+        let (origin, _) = origin_of(url).ok_or("Only web pages can be pinned")?;
+        let mut data = self.data.lock().unwrap();
+        if !data.pinned.iter().any(|p| p.url == url) {
+            data.pinned.push(PinnedSite { url: url.to_string(), title: title.to_string() });
+        }
+        data.hidden.retain(|h| h != &origin);
+        self.save(&data);
+        Ok(())
+    }
+
+    pub fn unpin(&self, url: &str) {
+        let mut data = self.data.lock().unwrap();
+        data.pinned.retain(|p| p.url != url);
+        self.save(&data);
+    }
+
+    pub fn hide(&self, url: &str) -> Result<(), String> {
+        let (origin, _) = origin_of(url).ok_or("Not a web page")?;
+        let mut data = self.data.lock().unwrap();
+        data.pinned.retain(|p| origin_of(&p.url).map(|(o, _)| o) != Some(origin.clone()));
+        if !data.hidden.contains(&origin) {
+            data.hidden.push(origin);
+        }
+        self.save(&data);
+        Ok(())
+    }
+
+    pub fn restore_hidden(&self) {
+        let mut data = self.data.lock().unwrap();
+        data.hidden.clear();
+        self.save(&data);
+    }
+
+    /// Remembers the tab's favicon for its site's tile.
+    pub fn remember_favicon(&self, page_url: &str, favicon: &str) {
+        let Some((_, host)) = origin_of(page_url) else {
+            return;
+        };
+        if favicon.len() > MAX_FAVICON_LEN {
+            return;
+        }
+        let mut data = self.data.lock().unwrap();
+        if data.favicons.get(&host).map(String::as_str) != Some(favicon) {
+            data.favicons.insert(host, favicon.to_string());
+            self.save(&data);
+        }
+    }
+
+    /// Forgets the favicons of sites that are neither in history nor pinned. Returns how many.
+    pub fn trim_favicons(&self, history: &[HistoryEntry]) -> usize {
+        let mut data = self.data.lock().unwrap();
+        let keep: HashSet<String> = history
+            .iter()
+            .map(|e| e.url.as_str())
+            .chain(data.pinned.iter().map(|p| p.url.as_str()))
+            .filter_map(origin_of)
+            .map(|(_, host)| host)
+            .collect();
+        let before = data.favicons.len();
+        data.favicons.retain(|host, _| keep.contains(host));
+        let removed = before - data.favicons.len();
+        if removed > 0 {
+            self.save(&data);
+        }
+        removed
+    }
+}
+
+#[tauri::command]
+pub fn get_top_sites(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<TopSite>, BrowserError> {
+    check_caller(&webview, Caller::AppPage)?;
+    Ok(current(&state, limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)))
+}
+
+#[tauri::command]
+pub fn pin_top_site(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    url: String,
+    title: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::AppPage)?;
+    state.top_sites.pin(&url, &title).map_err(BrowserError::InvalidInput)
+}
+
+#[tauri::command]
+pub fn unpin_top_site(webview: tauri::Webview, state: tauri::State<AppState>, url: String) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::AppPage)?;
+    state.top_sites.unpin(&url);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn hide_top_site(webview: tauri::Webview, state: tauri::State<AppState>, url: String) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::AppPage)?;
+    state.top_sites.hide(&url).map_err(BrowserError::InvalidInput)
+}
+
+#[tauri::command]
+pub fn restore_hidden_top_sites(webview: tauri::Webview, state: tauri::State<AppState>) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::AppPage)?;
+    state.top_sites.restore_hidden();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86400;
+    const NOW: u64 = 1_000 * DAY;
+
+    fn visit(url: &str, title: &str, visits: u64, days_ago: u64) -> HistoryEntry {
+        HistoryEntry {
+            url: url.to_string(),
+            title: title.to_string(),
+            last_visit: NOW - days_ago * DAY,
+            visit_count: visits,
+            typed_count: 0,
+        }
+    }
+
+    fn urls(sites: &[TopSite]) -> Vec<&str> {
+        sites.iter().map(|s| s.url.as_str()).collect()
+    }
+
+    #[test]
+    fn test_frecency_prefers_recent_sites() {
+        let history = vec![
+            visit("https://old.example/", "Old", 100, 400),  // 100 * 10
+            visit("https://daily.example/", "Daily", 20, 1), // 20 * 100
+            visit("https://daily.example/inbox", "Inbox", 5, 0),
+        ];
+        let sites = rank(&history, &TopSitesFile::default(), NOW, 8);
+        assert_eq!(urls(&sites), vec!["https://daily.example/", "https://old.example/"]);
+        assert_eq!(sites[0].title, "Daily"); // The home page names the site
+    }
+
+    #[test]
+    fn test_rank_groups_by_origin_and_skips_non_web() {
+        let history = vec![
+            visit("https://news.example/a", "Story A", 3, 0),
+            visit("https://news.example/b", "Story B", 5, 0),
+            visit("file:///tmp/notes.txt", "Notes", 50, 0),
+            visit("about:blank", "", 50, 0),
+        ];
+        let sites = rank(&history, &TopSitesFile::default(), NOW, 8);
+        assert_eq!(urls(&sites), vec!["https://news.example/"]);
+        assert_eq!(sites[0].title, "Story B");
+        assert_eq!(sites[0].host, "news.example");
+    }
+
+    #[test]
+    fn test_pinned_and_hidden() {
+        let history = vec![
+            visit("https://a.example/", "A", 10, 0),
+            visit("https://b.example/", "B", 9, 0),
+            visit("https://c.example/", "C", 8, 0),
+        ];
+        let mut prefs = TopSitesFile::default();
+        prefs.pinned.push(PinnedSite { url: "https://c.example/".to_string(), title: "Pinned C".to_string() });
+        prefs.pinned.push(PinnedSite { url: "https://z.example/docs".to_string(), title: String::new() });
+        prefs.hidden.push("https://a.example".to_string());
+        prefs.favicons.insert("b.example".to_string(), "https://b.example/icon.png".to_string());
+
+        let sites = rank(&history, &prefs, NOW, 3);
+        assert_eq!(urls(&sites), vec!["https://c.example/", "https://z.example/docs", "https://b.example/"]);
+        assert!(sites[0].pinned && !sites[2].pinned);
+        assert_eq!(sites[1].title, "z.example");
+        assert_eq!(sites[2].favicon.as_deref(), Some("https://b.example/icon.png"));
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TopSitesStore::new(dir.path().to_path_buf());
+        store.pin("https://a.example/", "A").unwrap();
+        store.hide("https://b.example/page").unwrap();
+        store.remember_favicon("https://a.example/x", "https://a.example/favicon.ico");
+        assert!(store.pin("about:blank", "").is_err());
+
+        let reopened = TopSitesStore::new(dir.path().to_path_buf());
+        let prefs = reopened.prefs();
+        assert_eq!(prefs.pinned.len(), 1);
+        assert_eq!(prefs.hidden, vec!["https://b.example".to_string()]);
+        assert_eq!(prefs.favicons.get("a.example").map(String::as_str), Some("https://a.example/favicon.ico"));
+
+        // Hiding a pinned site unpins it; pinning brings a hidden one back
+        reopened.hide("https://a.example/").unwrap();
+        reopened.pin("https://b.example/", "B").unwrap();
+        let prefs = reopened.prefs();
+        assert_eq!(prefs.pinned.iter().map(|p| p.url.as_str()).collect::<Vec<_>>(), vec!["https://b.example/"]);
+        assert_eq!(prefs.hidden, vec!["https://a.example".to_string()]);
+    }
+
+    #[test]
+    fn test_trim_favicons_keeps_history_and_pinned() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TopSitesStore::new(dir.path().to_path_buf());
+        store.pin("https://pinned.example/", "Pinned").unwrap();
+        for site in ["visited.example", "pinned.example", "gone.example"] {
+            store.remember_favicon(&format!("https://{}/", site), &format!("https://{}/favicon.ico", site));
+        }
+
+        assert_eq!(store.trim_favicons(&[visit("https://visited.example/a", "A", 1, 0)]), 1);
+        let mut hosts: Vec<String> = store.prefs().favicons.into_keys().collect();
+        hosts.sort();
+        assert_eq!(hosts, vec!["pinned.example".to_string(), "visited.example".to_string()]);
+    }
+
+    #[test]
+    fn test_is_new_tab_page() {
+        assert!(is_new_tab_page(NEW_TAB_ALIAS));
+        assert!(is_new_tab_page(&new_tab_page_url()));
+        assert!(!is_new_tab_page("https://example.com/new-tab.html"));
+    }
+}
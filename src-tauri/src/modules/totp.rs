@@ -0,0 +1,655 @@
+// One-time codes (TOTP, RFC 6238) from a local vault, filled into login pages.
+//
+// Accounts are added in Settings with the secret from the site's 2FA setup,
+// either the otpauth:// link behind its QR code or the base32 key. Only the
+// site, the account name and the code shape go to totp_vault.json. On macOS the
+// secret goes to the login keychain (see modules::keychain). Elsewhere it stays
+// in the vault file, which is readable by this user only.
+//
+// With `totp_autofill` on, `DETECT_SCRIPT` watches pages for a one-time code
+// field and tells `otp_field_detected`. That looks up the tab's real URL (not
+// whatever the page says), and when the vault has accounts for the site it
+// emits "totp-available" to the main window, which offers to fill. The code is
+// computed here and typed in by `fill_script`, the same way an email alias is
+// filled. Pages never see the secret, and they get a code only when the user
+// asks for one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::browsing_webview::{check_caller, Caller};
+use crate::modules::{cookie_policy, keychain};
+use crate::state::AppState;
+
+const VAULT_FILE: &str = "totp_vault.json";
+const KEYCHAIN_ACCOUNT_PREFIX: &str = "totp:";
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+/// Reports one-time code fields to `otp_field_detected`, once per page.
+pub const DETECT_SCRIPT: &str = r#"
+(function() {
+    if (window.__SOVEREIGN_OTP__ || !window.__TAURI__ || window.top !== window) return;
+    window.__SOVEREIGN_OTP__ = true;
+    const HINTS = /(one.?time|otp|totp|2fa|mfa|two.?factor|verification.?code|security.?code|auth.*code|passcode)/i;
+    let reported = false;
+
+    function isOtpField(el) {
+        if (!(el instanceof HTMLInputElement) || el.disabled || el.readOnly) return false;
+        if (!['text', 'tel', 'number', 'password', ''].includes(el.type)) return false;
+        if (el.autocomplete === 'one-time-code') return true;
+        const hints = [el.name, el.id, el.placeholder, el.getAttribute('aria-label')].join(' ');
+        return HINTS.test(hints);
+    }
+    function scan() {
+        if (reported) return;
+        const field = [...document.querySelectorAll('input')].find(el => isOtpField(el) && el.offsetParent !== null);
+        if (!field) return;
+        reported = true;
+        window.__TAURI__.core.invoke('otp_field_detected').catch(() => {});
+    }
+
+    let timer = null;
+    const observer = new MutationObserver(() => {
+        if (reported) return observer.disconnect();
+        clearTimeout(timer);
+        timer = setTimeout(scan, 300);
+    });
+    function start() {
+        scan();
+        if (!reported) observer.observe(document.documentElement, { childList: true, subtree: true });
+    }
+    if (document.readyState === 'loading') document.addEventListener('DOMContentLoaded', start);
+    else start();
+})();
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpAccount {
+    pub id: String,
+    pub site: String, // eTLD+1, see cookie_policy::site_of
+    pub account: String,
+    pub digits: u32,
+    pub period: u64, // Seconds
+    pub created_at: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct VaultFile {
+    #[serde(default)]
+    accounts: Vec<TotpAccount>,
+    // Account id -> base32 secret, only where there's no keychain
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    secrets: HashMap<String, String>,
+}
+
+/// What the user pasted: an otpauth:// link or a bare key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtpParams {
+    pub secret: String, // Normalized base32
+    pub account: Option<String>,
+    pub issuer: Option<String>,
+    pub digits: u32,
+    pub period: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpOffer {
+    pub tab_id: String,
+    pub site: String,
+    pub accounts: Vec<TotpAccount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpCode {
+    pub code: String,
+    pub remaining: u64, // Seconds until it changes
+}
+
+// --- Crypto (SHA-1 is what authenticator apps use) ---
+
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+pub fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    const BLOCK: usize = 64;
+    let mut key_block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        key_block[..20].copy_from_slice(&sha1(key));
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner: Vec<u8> = key_block.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = key_block.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&sha1(&inner));
+    sha1(&outer)
+}
+
+/// RFC 4648 base32, ignoring case, spaces, dashes and padding.
+pub fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0u32);
+    for c in input.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// RFC 4226 HOTP value for `counter`, zero-padded to `digits`.
+pub fn hotp(key: &[u8], counter: u64, digits: u32) -> String {
+    let mac = hmac_sha1(key, &counter.to_be_bytes());
+    let offset = (mac[19] & 0x0f) as usize;
+    let value = u32::from_be_bytes([mac[offset] & 0x7f, mac[offset + 1], mac[offset + 2], mac[offset + 3]]);
+    format!("{:0width$}", value % 10u32.pow(digits), width = digits as usize)
+}
+
+/// The code at `unix_time` and how long it stays valid.
+pub fn totp(key: &[u8], unix_time: u64, digits: u32, period: u64) -> TotpCode {
+    TotpCode { code: hotp(key, unix_time / period, digits), remaining: period - unix_time % period }
+}
+
+// --- Secrets as pasted by the user ---
+
+fn normalize_secret(secret: &str) -> Result<String, String> {
+    let normalized: String =
+        secret.chars().filter(|c| !matches!(c, ' ' | '-' | '=')).collect::<String>().to_uppercase();
+    match decode_base32(&normalized) {
+        Some(key) if key.len() >= 10 => Ok(normalized),
+        Some(_) => Err("That key is too short".to_string()),
+        None => Err("That isn't a valid base32 key".to_string()),
+    }
+}
+
+/// Reads an otpauth://totp/ link (the text behind a 2FA QR code) or a bare base32 key.
+pub fn parse_secret(input: &str) -> Result<OtpParams, String> {
+    let input = input.trim();
+    if !input.to_ascii_lowercase().starts_with("otpauth://") {
+        return Ok(OtpParams {
+            secret: normalize_secret(input)?,
+            account: None,
+            issuer: None,
+            digits: DEFAULT_DIGITS,
+            period: DEFAULT_PERIOD,
+        });
+    }
+
+    let url = Url::parse(input).map_err(|e| e.to_string())?;
+    if url.host_str() != Some("totp") {
+        return Err("Only time-based (TOTP) codes are supported".to_string());
+    }
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    if query.get("algorithm").is_some_and(|a| !a.eq_ignore_ascii_case("SHA1")) {
+        return Err("Only SHA-1 codes are supported".to_string());
+    }
+    let secret = normalize_secret(query.get("secret").ok_or("The link has no secret")?)?;
+    let digits = match query.get("digits") {
+        Some(d) => d.parse().ok().filter(|d| (6..=8).contains(d)).ok_or("Codes must have 6 to 8 digits")?,
+        None => DEFAULT_DIGITS,
+    };
+    let period = match query.get("period") {
+        Some(p) => p.parse().ok().filter(|p| *p > 0).ok_or("Invalid code period")?,
+        None => DEFAULT_PERIOD,
+    };
+
+    // The label is "Issuer:account" or just "account"
+    let label = urlencoding::decode(url.path().trim_start_matches('/')).map(|l| l.into_owned()).unwrap_or_default();
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+        None => (None, label.trim().to_string()),
+    };
+    Ok(OtpParams {
+        secret,
+        account: Some(account).filter(|a| !a.is_empty()),
+        issuer: query.get("issuer").cloned().or(label_issuer).filter(|i| !i.is_empty()),
+        digits,
+        period,
+    })
+}
+
+/// Types `code` into the page's one-time code field, or spreads it over a row
+/// of single-digit boxes.
+fn fill_script(code: &str) -> String {
+    format!(
+        r#"(function(code) {{
+    const HINTS = /(one.?time|otp|totp|2fa|mfa|two.?factor|verification.?code|security.?code|auth.*code|passcode)/i;
+    function usable(el) {{
+        return el instanceof HTMLInputElement && !el.disabled && !el.readOnly && el.offsetParent !== null;
+    }}
+    function isOtpField(el) {{
+        if (!usable(el)) return false;
+        const hints = [el.name, el.id, el.placeholder, el.getAttribute('aria-label')].join(' ');
+        return el.autocomplete === 'one-time-code' || HINTS.test(hints);
+    }}
+    const setValue = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+    function put(field, value) {{
+        field.focus();
+        setValue.call(field, value);
+        field.dispatchEvent(new Event('input', {{ bubbles: true }}));
+        field.dispatchEvent(new Event('change', {{ bubbles: true }}));
+    }}
+    let field = document.activeElement;
+    if (!isOtpField(field)) field = [...document.querySelectorAll('input')].find(isOtpField);
+    if (!field) return;
+    if (field.maxLength === 1) {{
+        const boxes = [...(field.form || document).querySelectorAll('input')]
+            .filter(el => usable(el) && el.maxLength === 1);
+        const start = boxes.indexOf(field);
+        if (boxes.length - start >= code.length) {{
+            [...code].forEach((digit, i) => put(boxes[start + i], digit));
+            return;
+        }}
+    }}
+    put(field, code);
+}})({});"#,
+        serde_json::to_string(code).unwrap_or_default()
+    )
+}
+
+// --- Vault ---
+
+pub struct TotpVault {
+    path: PathBuf,
+    data: Mutex<VaultFile>,
+}
+
+impl TotpVault {
+    pub fn new(app_dir: PathBuf) -> Self {
+        let path = app_dir.join(VAULT_FILE);
+        let data: VaultFile =
+            fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()).unwrap_or_default();
+        Self { path, data: Mutex::new(data) }
+    }
+
+    fn save(&self, data: &VaultFile) {
+        if let Err(e) = fs::write(&self.path, serde_json::to_string_pretty(data).unwrap_or_default()) {
+            eprintln!("[TOTP] Failed to save: {}", e);
+            return;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600));
+        }
+    }
+
+    fn store_secret(&self, data: &mut VaultFile, id: &str, secret: &str) -> Result<(), String> {
+        if keychain::is_available() {
+            keychain::set(keychain::SERVICE, &format!("{}{}", KEYCHAIN_ACCOUNT_PREFIX, id), secret)
+        } else {
+            data.secrets.insert(id.to_string(), secret.to_string());
+            Ok(())
+        }
+    }
+
+    fn secret(&self, id: &str) -> Result<String, String> {
+        let stored = if keychain::is_available() {
+            keychain::get(keychain::SERVICE, &format!("{}{}", KEYCHAIN_ACCOUNT_PREFIX, id))?
+        } else {
+            self.data.lock().unwrap().secrets.get(id).cloned()
+        };
+        stored.ok_or_else(|| "The secret for this account is missing".to_string())
+    }
+
+    /// `site` is anything normalize_site takes; `account` falls back to the link's.
+    pub fn add(&self, site: &str, account: &str, secret: &str) -> Result<TotpAccount, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Enter the site these codes are for")?;
+        let params = parse_secret(secret)?;
+        let account = Some(account.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .or(params.account)
+            .or(params.issuer)
+            .unwrap_or_else(|| site.clone());
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let entry = TotpAccount {
+            id: format!("{:x}", now.as_nanos()),
+            site,
+            account,
+            digits: params.digits,
+            period: params.period,
+            created_at: now.as_secs(),
+        };
+
+        let mut data = self.data.lock().unwrap();
+        self.store_secret(&mut data, &entry.id, &params.secret)?;
+        data.accounts.push(entry.clone());
+        self.save(&data);
+        Ok(entry)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        let before = data.accounts.len();
+        data.accounts.retain(|a| a.id != id);
+        if data.accounts.len() == before {
+            return Err("No such account".to_string());
+        }
+        if keychain::is_available() {
+            if let Err(e) = keychain::delete(keychain::SERVICE, &format!("{}{}", KEYCHAIN_ACCOUNT_PREFIX, id)) {
+                eprintln!("[TOTP] Failed to delete secret: {}", e);
+            }
+        }
+        data.secrets.remove(id);
+        self.save(&data);
+        Ok(())
+    }
+
+    pub fn accounts(&self) -> Vec<TotpAccount> {
+        self.data.lock().unwrap().accounts.clone()
+    }
+
+    pub fn for_site(&self, site: &str) -> Vec<TotpAccount> {
+        self.data.lock().unwrap().accounts.iter().filter(|a| a.site == site).cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Option<TotpAccount> {
+        self.data.lock().unwrap().accounts.iter().find(|a| a.id == id).cloned()
+    }
+
+    pub fn code(&self, id: &str, unix_time: u64) -> Result<TotpCode, String> {
+        let account = self.get(id).ok_or("No such account")?;
+        let key = decode_base32(&self.secret(id)?).ok_or("The stored secret is damaged")?;
+        Ok(totp(&key, unix_time, account.digits, account.period))
+    }
+}
+
+// --- Tabs ---
+
+/// The tab showing `label` and the site it's on.
+fn tab_site(app: &AppHandle, state: &AppState, label: &str) -> Option<(String, String)> {
+    let tab_id = state.tabs.lock().unwrap().iter().find(|t| t.webview_label == label).map(|t| t.id.clone())?;
+    let url = app.get_webview(label)?.url().ok()?;
+    match url.scheme() {
+        "http" | "https" => Some((tab_id, cookie_policy::site_of(url.host_str()?))),
+        _ => None,
+    }
+}
+
+fn fill(app: &AppHandle, state: &AppState, tab_id: &str, account_id: &str) -> Result<(), BrowserError> {
+    let label = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| t.webview_label.clone())
+        .ok_or_else(|| BrowserError::tab_not_found(tab_id))?;
+    let account = state.totp.get(account_id).ok_or_else(|| BrowserError::NotFound("No such account".to_string()))?;
+    // The tab may have moved on since the offer
+    let (_, site) = tab_site(app, state, &label)
+        .ok_or_else(|| BrowserError::Unsupported("This tab isn't showing a web page".to_string()))?;
+    if site != account.site {
+        return Err(BrowserError::NotAllowed(format!("This code is for {}, not {}", account.site, site)));
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let code = state.totp.code(account_id, now).map_err(BrowserError::NotFound)?;
+    let webview = app.get_webview(&label).ok_or_else(|| BrowserError::NotFound("Tab webview not found".to_string()))?;
+    webview.eval(&fill_script(&code.code))?;
+    println!("[TOTP] Filled a code for {} on {}", account.account, site);
+    Ok(())
+}
+
+/// The "Fill One-Time Code" palette command: the active tab's first account.
+pub fn run(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let result = (|| {
+        let tab_id = state.active_tab_id.lock().unwrap().clone().ok_or("No active tab")?;
+        let label = state
+            .tabs
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|t| t.id == tab_id)
+            .map(|t| t.webview_label.clone())
+            .ok_or("Tab not found")?;
+        let (_, site) = tab_site(app, &state, &label).ok_or("This tab isn't showing a web page")?;
+        let account = state
+            .totp
+            .for_site(&site)
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("No one-time codes saved for {}", site))?;
+        fill(app, &state, &tab_id, &account.id)
+    })();
+    if let Err(e) = result {
+        eprintln!("[TOTP] {}", e);
+        app.dialog().message(e.to_string()).title("One-Time Code").kind(MessageDialogKind::Info).show(|_| {});
+    }
+}
+
+/// From `DETECT_SCRIPT`: the page has a one-time code field.
+#[tauri::command]
+pub fn otp_field_detected(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<(), BrowserError> {
+    if !state.settings.read().unwrap().totp_autofill {
+        return Ok(());
+    }
+    let Some((tab_id, site)) = tab_site(&app, &state, webview.label()) else {
+        return Ok(());
+    };
+    let accounts = state.totp.for_site(&site);
+    if accounts.is_empty() {
+        return Ok(());
+    }
+    Ok(app.emit_to("main", "totp-available", TotpOffer { tab_id, site, accounts })?)
+}
+
+/// The main window's "Fill code" button.
+#[tauri::command]
+pub fn fill_totp(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    tab_id: String,
+    account_id: String,
+) -> Result<(), BrowserError> {
+    // Pages may not ask for codes themselves
+    if webview.label() != "main" {
+        return Err(BrowserError::NotAllowed("Not allowed from this webview".to_string()));
+    }
+    fill(&app, &state, &tab_id, &account_id)
+}
+
+#[tauri::command]
+pub fn list_totp_accounts(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+) -> Result<Vec<TotpAccount>, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    Ok(state.totp.accounts())
+}
+
+#[tauri::command]
+pub fn add_totp_account(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    site: String,
+    account: String,
+    secret: String,
+) -> Result<TotpAccount, BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    let entry = state.totp.add(&site, &account, &secret).map_err(BrowserError::InvalidInput)?;
+    println!("[TOTP] Added {} for {}", entry.account, entry.site);
+    Ok(entry)
+}
+
+#[tauri::command]
+pub fn remove_totp_account(
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    id: String,
+) -> Result<(), BrowserError> {
+    check_caller(&webview, Caller::Settings)?;
+    state.totp.remove(&id).map_err(BrowserError::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::tempdir;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_sha1() {
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        let long = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(hex(&sha1(long)), "84983e441c3bd26ebaae4aa1f95129e5e54670f1");
+    }
+
+    #[test]
+    fn test_hmac_sha1() {
+        // RFC 2202
+        assert_eq!(hex(&hmac_sha1(&[0x0b; 20], b"Hi There")), "b617318655057264e28bc0b6fb378c8ef146be00");
+        assert_eq!(
+            hex(&hmac_sha1(&[0xaa; 80], b"Test Using Larger Than Block-Size Key - Hash Key First")),
+            "aa4ae5e15272d00e95705637ce8a3b55ed402112"
+        );
+    }
+
+    #[test]
+    fn test_hotp() {
+        // RFC 4226 appendix D
+        let key = b"12345678901234567890";
+        let expected = ["755224", "287082", "359152", "969429", "338314"];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(key, counter as u64, 6), *code);
+        }
+    }
+
+    #[rstest]
+    #[case(59, "94287082")]
+    #[case(1111111109, "07081804")]
+    #[case(1234567890, "89005924")]
+    #[case(20000000000, "65353130")]
+    fn test_totp_rfc6238(#[case] time: u64, #[case] expected: &str) {
+        let code = totp(b"12345678901234567890", time, 8, 30);
+        assert_eq!(code.code, expected);
+        assert_eq!(code.remaining, 30 - time % 30);
+    }
+
+    #[test]
+    fn test_decode_base32() {
+        assert_eq!(decode_base32("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap(), b"12345678901234567890");
+        assert_eq!(decode_base32("mzxw 6ytb oi======").unwrap(), b"foobar");
+        assert!(decode_base32("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_parse_secret() {
+        let params = parse_secret(
+            "otpauth://totp/Example:alice%40example.com?secret=JBSWY3DPEHPK3PXP&issuer=Example&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(
+            params,
+            OtpParams {
+                secret: "JBSWY3DPEHPK3PXP".to_string(),
+                account: Some("alice@example.com".to_string()),
+                issuer: Some("Example".to_string()),
+                digits: 8,
+                period: 60,
+            }
+        );
+
+        let bare = parse_secret("jbsw y3dp ehpk 3pxp").unwrap();
+        assert_eq!((bare.secret.as_str(), bare.digits, bare.period), ("JBSWY3DPEHPK3PXP", 6, 30));
+
+        assert!(parse_secret("otpauth://hotp/Example?secret=JBSWY3DPEHPK3PXP&counter=1").is_err());
+        assert!(parse_secret("otpauth://totp/Example?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256").is_err());
+        assert!(parse_secret("otpauth://totp/Example").is_err());
+        assert!(parse_secret("ABC").is_err()); // Too short
+    }
+
+    #[test]
+    fn test_vault_roundtrip() {
+        if keychain::is_available() {
+            return; // Would write to the real keychain
+        }
+        let dir = tempdir().unwrap();
+        let vault = TotpVault::new(dir.path().to_path_buf());
+        assert!(vault.add("", "alice", "JBSWY3DPEHPK3PXP").is_err());
+        let entry = vault.add("https://login.example.com/mfa", "", "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap();
+        assert_eq!((entry.site.as_str(), entry.account.as_str()), ("example.com", "example.com"));
+
+        let reopened = TotpVault::new(dir.path().to_path_buf());
+        assert_eq!(reopened.for_site("example.com"), vec![entry.clone()]);
+        assert_eq!(reopened.code(&entry.id, 59).unwrap().code, "287082");
+
+        reopened.remove(&entry.id).unwrap();
+        assert!(reopened.accounts().is_empty() && reopened.code(&entry.id, 59).is_err());
+        assert!(!fs::read_to_string(dir.path().join(VAULT_FILE)).unwrap().contains("GEZDGNBV"));
+    }
+}
@@ -0,0 +1,129 @@
+// Touchscreen and touchpad gestures in tab webviews.
+//
+// - Pinch to zoom: WebView2's own switch on Windows. WebKitGTK has none, so on
+//   Linux pages get `touch-action: pan-x pan-y` while it's off, which stops
+//   touchscreen pinches (touchpad pinches still zoom there).
+// - Swipe back/forward: two-finger touchpad swipes and edge swipes, through
+//   WebView2's swipe navigation, WebKitGTK's and WKWebView's back-forward
+//   navigation gestures.
+// - Smooth scrolling: WebKitGTK's animated, kinetic scrolling. WebView2 has no
+//   switch for it per webview, so Windows always scrolls smoothly.
+//
+// Applied when a tab is created and to open tabs when settings change. Tablet
+// mode, the larger toolbar for fingers, is part of modules::layout.
+
+use tauri::{AppHandle, Manager, Webview};
+
+use crate::settings::Settings;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchSettings {
+    pub pinch_zoom: bool,
+    pub swipe_navigation: bool,
+    pub smooth_scrolling: bool,
+}
+
+impl TouchSettings {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            pinch_zoom: settings.pinch_zoom,
+            swipe_navigation: settings.swipe_navigation,
+            smooth_scrolling: settings.smooth_scrolling,
+        }
+    }
+}
+
+/// Turns touchscreen pinch zoom on or off in the page (Linux).
+pub fn pinch_script(enabled: bool) -> String {
+    format!(
+        r#"(function () {{
+    const apply = () => document.documentElement.style.setProperty('touch-action', {} ? '' : 'pan-x pan-y');
+    if (document.documentElement) apply();
+    else document.addEventListener('DOMContentLoaded', apply, {{ once: true }});
+}})();"#,
+        enabled
+    )
+}
+
+#[cfg(windows)]
+pub fn apply(webview: &Webview, touch: TouchSettings) {
+    use webview2_com::Microsoft::Web::WebView2::Win32::{ICoreWebView2Settings5, ICoreWebView2Settings6};
+    use windows::core::Interface;
+
+    let result = webview.with_webview(move |platform| unsafe {
+        let applied = platform.controller().CoreWebView2().and_then(|core| core.Settings()).and_then(|settings| {
+            settings.cast::<ICoreWebView2Settings5>()?.SetIsPinchZoomEnabled(touch.pinch_zoom)?;
+            settings.cast::<ICoreWebView2Settings6>()?.SetIsSwipeNavigationEnabled(touch.swipe_navigation)
+        });
+        if let Err(e) = applied {
+            eprintln!("[Touch] Failed to apply gesture settings: {}", e);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[Touch] Failed to apply gesture settings: {}", e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn apply(webview: &Webview, touch: TouchSettings) {
+    use webkit2gtk::{SettingsExt, WebViewExt};
+
+    let result = webview.with_webview(move |platform| {
+        if let Some(settings) = WebViewExt::settings(&platform.inner()) {
+            settings.set_enable_back_forward_navigation_gestures(touch.swipe_navigation);
+            settings.set_enable_smooth_scrolling(touch.smooth_scrolling);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[Touch] Failed to apply gesture settings: {}", e);
+    }
+    // New pages get it from the initialization script
+    let _ = webview.eval(&pinch_script(touch.pinch_zoom));
+}
+
+#[cfg(target_os = "macos")]
+pub fn apply(webview: &Webview, touch: TouchSettings) {
+    use objc::runtime::{NO, YES};
+    use objc::{msg_send, sel, sel_impl};
+
+    let allow = if touch.swipe_navigation { YES } else { NO };
+    let result = webview.with_webview(move |platform| unsafe {
+        let wk_webview = platform.inner() as *mut objc::runtime::Object;
+        let _: () = msg_send![wk_webview, setAllowsBackForwardNavigationGestures: allow];
+    });
+    if let Err(e) = result {
+        eprintln!("[Touch] Failed to apply gesture settings: {}", e);
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+pub fn apply(_webview: &Webview, _touch: TouchSettings) {}
+
+/// Re-applies the gesture settings to every open tab.
+pub fn apply_to_tabs(app: &AppHandle, state: &AppState) {
+    let touch = TouchSettings::from_settings(&state.settings.read().unwrap());
+    let labels: Vec<String> = state.tabs.lock().unwrap().iter().map(|t| t.webview_label.clone()).collect();
+    for label in labels {
+        if let Some(webview) = app.get_webview(&label) {
+            apply(&webview, touch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_settings_defaults_keep_gestures_on() {
+        let touch = TouchSettings::from_settings(&Settings::default());
+        assert_eq!(touch, TouchSettings { pinch_zoom: true, swipe_navigation: true, smooth_scrolling: true });
+    }
+
+    #[test]
+    fn test_pinch_script() {
+        assert!(pinch_script(false).contains("false ? '' : 'pan-x pan-y'"));
+        assert!(pinch_script(true).contains("true ? '' : 'pan-x pan-y'"));
+    }
+}
@@ -0,0 +1,149 @@
+// Tracking parameters stripped from the links tabs open.
+//
+// Campaign tags and click IDs (utm_*, fbclid, gclid, ...) tell a site, and the ad
+// network behind it, where a visit came from. With `strip_tracking_params` on
+// they're removed from addresses entered in the URL bar, from links opened in new
+// tabs, and, through the page script, from links clicked in pages. Redirects and
+// script navigations keep theirs: on_navigation can't tell a subframe load from
+// the tab's own, so it can't rewrite one safely. Every parameter removed is
+// counted for the privacy report.
+
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::block_stats::Protection;
+use crate::settings::Settings;
+use crate::state::AppState;
+
+const PARAMS: &[&str] = &[
+    "fbclid",
+    "gclid",
+    "gclsrc",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "msclkid",
+    "yclid",
+    "twclid",
+    "ttclid",
+    "li_fat_id",
+    "igshid",
+    "mc_cid",
+    "mc_eid",
+    "_hsenc",
+    "_hsmi",
+    "mkt_tok",
+    "oly_anon_id",
+    "oly_enc_id",
+    "vero_id",
+    "rb_clickid",
+];
+const PREFIXES: &[&str] = &["utm_"];
+const MAX_REPORTED: u64 = 32; // Per link, so a page can't inflate the report
+
+/// Cleans the href of a link when it's clicked, just before the navigation.
+const LINK_CLEANER_TEMPLATE: &str = r#"
+(function() {
+    const PARAMS = new Set(__PARAMS__);
+    const PREFIXES = __PREFIXES__;
+    const isTracking = name => {
+        const n = name.toLowerCase();
+        return PARAMS.has(n) || PREFIXES.some(p => n.startsWith(p));
+    };
+
+    function clean(e) {
+        const a = e.target.closest && e.target.closest('a[href]');
+        if (!a || !/^https?:$/.test(a.protocol) || !a.search) return;
+        const pairs = a.search.slice(1).split('&');
+        const kept = pairs.filter(p => !isTracking(p.split('=')[0]));
+        const removed = pairs.length - kept.length;
+        if (!removed) return;
+        a.search = kept.length ? '?' + kept.join('&') : '';
+        if (window.__TAURI__) {
+            window.__TAURI__.core.invoke('report_stripped_params', { count: removed }).catch(() => {});
+        }
+    }
+
+    document.addEventListener('click', clean, true);
+    document.addEventListener('auxclick', clean, true);
+})();
+"#;
+
+pub fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    PARAMS.contains(&name.as_str()) || PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+/// `url` without its tracking parameters and how many there were, if it had any.
+/// The other parameters are kept as they were, encoding and order included.
+pub fn strip(url: &Url) -> Option<(Url, u64)> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let pairs: Vec<&str> = url.query()?.split('&').collect();
+    let kept: Vec<&str> =
+        pairs.iter().copied().filter(|p| !is_tracking_param(p.split('=').next().unwrap_or(p))).collect();
+    let removed = (pairs.len() - kept.len()) as u64;
+    if removed == 0 {
+        return None;
+    }
+    let mut cleaned = url.clone();
+    cleaned.set_query(if kept.is_empty() { None } else { Some(&kept.join("&")) });
+    Some((cleaned, removed))
+}
+
+/// Strips `url` if the setting is on, counting what was removed.
+pub fn clean(state: &AppState, settings: &Settings, url: &str) -> String {
+    if !settings.strip_tracking_params {
+        return url.to_string();
+    }
+    match Url::parse(url).ok().as_ref().and_then(strip) {
+        Some((cleaned, removed)) => {
+            println!("[TrackingParams] Removed {} from {}", removed, cleaned);
+            state.block_stats.record_protection(Protection::ParamsStripped(removed));
+            cleaned.to_string()
+        }
+        None => url.to_string(),
+    }
+}
+
+pub fn link_cleaner_script() -> String {
+    LINK_CLEANER_TEMPLATE
+        .replace("__PARAMS__", &serde_json::to_string(PARAMS).unwrap_or_else(|_| "[]".to_string()))
+        .replace("__PREFIXES__", &serde_json::to_string(PREFIXES).unwrap_or_else(|_| "[]".to_string()))
+}
+
+/// The link cleaner removed `count` parameters from a clicked link.
+#[tauri::command]
+pub fn report_stripped_params(state: tauri::State<AppState>, count: u64) -> Result<(), BrowserError> {
+    if count > 0 && state.settings.read().unwrap().strip_tracking_params {
+        state.block_stats.record_protection(Protection::ParamsStripped(count.min(MAX_REPORTED)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://example.com/a?utm_source=x&id=7&fbclid=abc", Some(("https://example.com/a?id=7", 2)))]
+    #[case("https://example.com/?UTM_Medium=mail", Some(("https://example.com/", 1)))]
+    #[case("https://example.com/?q=a%20b&gclid=1#top", Some(("https://example.com/?q=a%20b#top", 1)))]
+    #[case("https://example.com/?q=utm_source", None)]
+    #[case("https://example.com/", None)]
+    #[case("ftp://example.com/?utm_source=x", None)]
+    fn test_strip(#[case] url: &str, #[case] expected: Option<(&str, u64)>) {
+        let stripped = strip(&Url::parse(url).unwrap());
+        assert_eq!(stripped.as_ref().map(|(u, n)| (u.as_str(), *n)), expected);
+    }
+
+    #[test]
+    fn test_script_has_the_lists() {
+        let script = link_cleaner_script();
+        assert!(script.contains(r#""fbclid""#));
+        assert!(script.contains(r#"["utm_"]"#));
+        assert!(!script.contains("__PARAMS__"));
+    }
+}
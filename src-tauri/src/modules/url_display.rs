@@ -0,0 +1,322 @@
+// URL display formatting - pure logic, no Tauri imports.
+//
+// The toolbar shows a simplified URL while the page is being viewed ("example.com/path"
+// instead of "https://www.example.com/path") and the full URL once the user starts
+// editing. The full URL in tab state stays authoritative; this only formats it.
+//
+// Internationalized hostnames are shown in Unicode only when that can't be mistaken
+// for another domain (single script per label, no Latin look-alike labels);
+// otherwise the punycode form is shown.
+
+use serde::Serialize;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplayUrl {
+    pub full: String,
+    pub display: String,
+    /// Origin (host and port) within `display`, as UTF-16 offsets for the toolbar's JS.
+    pub origin_start: usize,
+    pub origin_end: usize,
+    /// The host contains IDN labels that were kept in punycode for safety.
+    pub punycode_shown: bool,
+}
+
+pub fn format_url_for_display(url: &str) -> DisplayUrl {
+    let plain = |text: &str| DisplayUrl {
+        full: url.to_string(),
+        display: text.to_string(),
+        origin_start: 0,
+        origin_end: 0,
+        punycode_shown: false,
+    };
+
+    let parsed = match Url::parse(url) {
+        Ok(u) => u,
+        Err(_) => return plain(url),
+    };
+    let host = match parsed.host_str() {
+        Some(h) if matches!(parsed.scheme(), "http" | "https") => h,
+        // about:, data:, file: etc. are shown exactly as they are
+        _ => return plain(url),
+    };
+
+    let (host_display, punycode_shown) = display_host(host);
+    let host_display = match host_display.strip_prefix("www.") {
+        Some(rest) if rest.contains('.') => rest.to_string(),
+        _ => host_display,
+    };
+    let origin = match parsed.port() {
+        Some(port) => format!("{}:{}", host_display, port),
+        None => host_display,
+    };
+
+    let mut rest = decode_path(parsed.path());
+    if let Some(query) = parsed.query() {
+        rest.push('?');
+        rest.push_str(query);
+    }
+    if let Some(fragment) = parsed.fragment() {
+        rest.push('#');
+        rest.push_str(fragment);
+    }
+    if rest == "/" {
+        rest.clear();
+    }
+
+    // Credentials are never displayed; they're a common spoofing trick
+    let origin_end = origin.encode_utf16().count();
+    DisplayUrl {
+        full: url.to_string(),
+        display: format!("{}{}", origin, rest),
+        origin_start: 0,
+        origin_end,
+        punycode_shown,
+    }
+}
+
+/// Percent-decodes the path for readability, unless that would reveal characters
+/// that could disguise it (spaces, controls, bidi overrides) or isn't valid UTF-8.
+fn decode_path(path: &str) -> String {
+    match urlencoding::decode(path) {
+        Ok(decoded) if !decoded.chars().any(|c| c.is_whitespace() || c.is_control() || is_format_char(c)) => {
+            decoded.into_owned()
+        }
+        _ => path.to_string(),
+    }
+}
+
+fn is_format_char(c: char) -> bool {
+    matches!(c, '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2069}' | '\u{FEFF}' | '\u{061C}')
+}
+
+/// Returns the host to show and whether any label was left in punycode.
+fn display_host(host: &str) -> (String, bool) {
+    let mut punycode_shown = false;
+    let labels: Vec<String> = host
+        .split('.')
+        .map(|label| {
+            let Some(encoded) = label.strip_prefix("xn--") else {
+                return label.to_string();
+            };
+            match punycode_decode(encoded) {
+                Some(unicode) if is_safe_label(&unicode) => unicode,
+                _ => {
+                    punycode_shown = true;
+                    label.to_string()
+                }
+            }
+        })
+        .collect();
+    (labels.join("."), punycode_shown)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Common, // Digits, hyphen
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Han,
+    Hiragana,
+    Katakana,
+    Hangul,
+    Other,
+}
+
+fn script_of(c: char) -> Script {
+    match c {
+        '0'..='9' | '-' | '\u{30FC}' => Script::Common, // U+30FC: prolonged sound mark, used with kana
+        'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => Script::Latin,
+        '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Script::Greek,
+        '\u{0400}'..='\u{052F}' => Script::Cyrillic,
+        '\u{0530}'..='\u{058F}' => Script::Armenian,
+        '\u{0590}'..='\u{05FF}' => Script::Hebrew,
+        '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' => Script::Arabic,
+        '\u{0900}'..='\u{097F}' => Script::Devanagari,
+        '\u{0E00}'..='\u{0E7F}' => Script::Thai,
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' => Script::Han,
+        '\u{3040}'..='\u{309F}' => Script::Hiragana,
+        '\u{30A0}'..='\u{30FF}' => Script::Katakana,
+        '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' => Script::Hangul,
+        _ => Script::Other,
+    }
+}
+
+/// Cyrillic and Greek letters that render like Latin ones. A label made only of
+/// these (e.g. "аррӏе") is a whole-script confusable.
+const LATIN_LOOKALIKES: &str = "аеорсухіјѕԁһӏԛԝвкмнтуүοαικνρτυχ";
+
+/// Chrome-style (simplified) IDN display policy.
+fn is_safe_label(label: &str) -> bool {
+    let mut scripts: Vec<Script> = label.chars().map(script_of).filter(|s| *s != Script::Common).collect();
+    scripts.sort_by_key(|s| *s as u8);
+    scripts.dedup();
+
+    let single_script_ok = match scripts.as_slice() {
+        [] => true,
+        [Script::Other] => false,
+        [_] => true,
+        // Japanese and Korean mix scripts legitimately
+        s => s.iter().all(|x| matches!(x, Script::Han | Script::Hiragana | Script::Katakana))
+            || s.iter().all(|x| matches!(x, Script::Han | Script::Hangul)),
+    };
+    if !single_script_ok {
+        return false;
+    }
+
+    let looks_latin = label
+        .chars()
+        .filter(|c| script_of(*c) != Script::Common)
+        .all(|c| LATIN_LOOKALIKES.contains(c));
+    !(matches!(scripts.as_slice(), [Script::Cyrillic] | [Script::Greek]) && looks_latin)
+}
+
+// --- Punycode (RFC 3492) ---
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+
+fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + ((BASE - T_MIN + 1) * delta) / (delta + SKEW)
+}
+
+/// Decodes the part after "xn--". None for malformed input.
+fn punycode_decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n: u32 = 128;
+    let mut i: u32 = 0;
+    let mut bias: u32 = 72;
+    let mut digits = extended.bytes().peekable();
+
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = match digits.next()? {
+                b @ b'a'..=b'z' => b - b'a',
+                b @ b'A'..=b'Z' => b - b'A',
+                b @ b'0'..=b'9' => b - b'0' + 26,
+                _ => return None,
+            } as u32;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let t = if k <= bias {
+                T_MIN
+            } else if k >= bias + T_MAX {
+                T_MAX
+            } else {
+                k - bias
+            };
+            if digit < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("mnchen-3ya", "münchen")]
+    #[case("r8jz45g", "例え")]
+    #[case("zckzah", "テスト")]
+    #[case("e1afmkfd", "пример")]
+    #[case("mgbh0fb", "مثال")]
+    #[case("bcher-kva", "bücher")]
+    fn test_punycode_decode(#[case] encoded: &str, #[case] expected: &str) {
+        assert_eq!(punycode_decode(encoded).as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn test_punycode_rejects_garbage() {
+        assert_eq!(punycode_decode("!!"), None);
+    }
+
+    #[rstest]
+    // Scheme and www. elision
+    #[case("https://www.example.com/", "example.com", 0, 11)]
+    #[case("http://example.com/a/b?q=1#top", "example.com/a/b?q=1#top", 0, 11)]
+    #[case("https://www.com/", "www.com", 0, 7)]
+    #[case("https://example.com:8443/x", "example.com:8443/x", 0, 16)]
+    #[case("https://user:pw@example.com/", "example.com", 0, 11)]
+    // Percent-decoded paths, unless that would hide something
+    #[case("https://example.com/stra%C3%9Fe", "example.com/straße", 0, 11)]
+    #[case("https://example.com/a%20b", "example.com/a%20b", 0, 11)]
+    #[case("https://example.com/%E2%80%AEfdp.exe", "example.com/%E2%80%AEfdp.exe", 0, 11)]
+    // IDN shown in Unicode when safe; offsets are UTF-16
+    #[case("https://xn--mnchen-3ya.de/", "münchen.de", 0, 10)]
+    #[case("https://xn--r8jz45g.xn--zckzah/", "例え.テスト", 0, 6)]
+    // Non-web schemes are left alone
+    #[case("about:blank", "about:blank", 0, 0)]
+    #[case("file:///tmp/a.html", "file:///tmp/a.html", 0, 0)]
+    fn test_format_url_for_display(
+        #[case] url: &str,
+        #[case] display: &str,
+        #[case] origin_start: usize,
+        #[case] origin_end: usize,
+    ) {
+        let formatted = format_url_for_display(url);
+        assert_eq!(formatted.full, url);
+        assert_eq!(formatted.display, display);
+        assert_eq!((formatted.origin_start, formatted.origin_end), (origin_start, origin_end));
+        assert!(!formatted.punycode_shown);
+    }
+
+    #[rstest]
+    // Whole-script confusable: Cyrillic "аррӏе"
+    #[case("https://xn--80ak6aa92e.com/", "xn--80ak6aa92e.com")]
+    // Mixed Latin + Cyrillic in one label
+    #[case("https://xn--pypal-4ve.com/", "xn--pypal-4ve.com")]
+    fn test_unsafe_idn_stays_punycode(#[case] url: &str, #[case] display: &str) {
+        let formatted = format_url_for_display(url);
+        assert_eq!(formatted.display, display);
+        assert!(formatted.punycode_shown);
+    }
+
+    #[test]
+    fn test_safe_labels() {
+        assert!(is_safe_label("münchen"));
+        assert!(is_safe_label("пример"));
+        assert!(is_safe_label("日本語のテスト"));
+        assert!(is_safe_label("한국어"));
+        assert!(!is_safe_label("аррӏе"));
+        assert!(!is_safe_label("pаypal")); // Cyrillic а
+    }
+}
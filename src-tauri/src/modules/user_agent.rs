@@ -0,0 +1,312 @@
+// User agent and client hints.
+//
+// Tabs present an honest user agent: the engine the platform really runs plus a
+// Sovereign product token. WebKit (macOS, Linux) identifies as Safari on the right
+// OS; WebView2 on Windows keeps its own Chromium/Edge string. Sites that refuse
+// WebKit outright are listed in `COMPAT_OVERRIDES`, each with the reason, and get a
+// Chrome UA while `Settings.ua_compat_overrides` is on. The page-info panel shows
+// which UA a tab presents and why through `get_tab_user_agent`.
+//
+// On macOS the override is also set natively (WKWebView customUserAgent), so
+// request headers match navigator.userAgent. Switching to it holds the navigation
+// back and reissues it, so the first request already carries the Chrome UA;
+// switching back happens when the next top-level page starts, so that page's own
+// document request still carries the Chrome UA. WebKitGTK isn't reachable from
+// here, so on Linux only navigator.userAgent is overridden.
+//
+// Client hints (navigator.userAgentData) only exist in WebView2.
+// `Settings.client_hints` can reduce them to the low-entropy values or hide them
+// from scripts; the Sec-CH-UA request headers are left to WebView2.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::settings::Settings;
+use crate::state::AppState;
+
+const SAFARI_MACOS: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15";
+const SAFARI_LINUX: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15";
+const CHROME_MACOS: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+const CHROME_LINUX: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+/// How much of navigator.userAgentData pages can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientHintsMode {
+    #[default]
+    Default,
+    Reduced, // getHighEntropyValues() only returns brands, mobile and platform
+    Off,     // navigator.userAgentData is undefined
+}
+
+/// A site known to refuse WebKit, and why it gets a Chrome UA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CompatOverride {
+    pub domain: &'static str, // Matches the domain and its subdomains
+    pub reason: &'static str,
+}
+
+/// Sites that block WebKit by user agent even though they work in it.
+pub const COMPAT_OVERRIDES: &[CompatOverride] = &[
+    CompatOverride {
+        domain: "teams.microsoft.com",
+        reason: "Refuses to start meetings and calls outside Chromium-based browsers",
+    },
+    CompatOverride {
+        domain: "teams.live.com",
+        reason: "Refuses to start meetings and calls outside Chromium-based browsers",
+    },
+];
+
+/// The UA tabs present by default, or None to keep the engine's own (WebView2).
+pub fn default_user_agent() -> Option<String> {
+    let engine = if cfg!(target_os = "macos") {
+        SAFARI_MACOS
+    } else if cfg!(windows) {
+        return None;
+    } else {
+        SAFARI_LINUX
+    };
+    Some(format!("{} Sovereign/{}", engine, env!("CARGO_PKG_VERSION")))
+}
+
+/// The Chrome UA used for compatibility overrides; None where the engine is
+/// already Chromium.
+pub fn chrome_user_agent() -> Option<&'static str> {
+    if cfg!(target_os = "macos") {
+        Some(CHROME_MACOS)
+    } else if cfg!(windows) {
+        None
+    } else {
+        Some(CHROME_LINUX)
+    }
+}
+
+/// The override for `url`'s host, if any.
+pub fn compat_override(url: &str) -> Option<&'static CompatOverride> {
+    let host = Url::parse(url).ok()?.host_str()?.trim_end_matches('.').to_ascii_lowercase();
+    COMPAT_OVERRIDES
+        .iter()
+        .find(|o| host == o.domain || host.ends_with(&format!(".{}", o.domain)))
+}
+
+/// The override that applies to `url` under `settings`.
+pub fn active_override(settings: &Settings, url: &str) -> Option<&'static CompatOverride> {
+    if !settings.ua_compat_overrides || chrome_user_agent().is_none() {
+        return None;
+    }
+    compat_override(url)
+}
+
+/// Overrides navigator for compatibility sites and applies the client hints mode.
+const NAVIGATOR_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const OVERRIDE_DOMAINS = __OVERRIDE_DOMAINS__;
+    const CHROME_UA = __CHROME_UA__;
+    const CLIENT_HINTS = __CLIENT_HINTS__;
+
+    const host = location.hostname.toLowerCase();
+    if (CHROME_UA && OVERRIDE_DOMAINS.some(d => host === d || host.endsWith('.' + d))) {
+        Object.defineProperty(Navigator.prototype, 'userAgent', { get: () => CHROME_UA });
+        Object.defineProperty(Navigator.prototype, 'appVersion', { get: () => CHROME_UA.slice('Mozilla/'.length) });
+        Object.defineProperty(Navigator.prototype, 'vendor', { get: () => 'Google Inc.' });
+    }
+
+    if (CLIENT_HINTS === 'default' || !('userAgentData' in navigator)) return;
+    if (CLIENT_HINTS === 'off') {
+        Object.defineProperty(Navigator.prototype, 'userAgentData', { get: () => undefined });
+        return;
+    }
+    const proto = Object.getPrototypeOf(navigator.userAgentData);
+    proto.getHighEntropyValues = function() {
+        return Promise.resolve({ brands: this.brands, mobile: this.mobile, platform: this.platform });
+    };
+})();
+"#;
+
+pub fn navigator_script(settings: &Settings) -> String {
+    let chrome_ua = if settings.ua_compat_overrides { chrome_user_agent() } else { None };
+    let domains: Vec<&str> = COMPAT_OVERRIDES.iter().map(|o| o.domain).collect();
+    NAVIGATOR_SCRIPT_TEMPLATE
+        .replace("__OVERRIDE_DOMAINS__", &serde_json::to_string(&domains).unwrap_or_else(|_| "[]".to_string()))
+        .replace("__CHROME_UA__", &serde_json::to_string(&chrome_ua).unwrap_or_else(|_| "null".to_string()))
+        .replace(
+            "__CLIENT_HINTS__",
+            &serde_json::to_string(&settings.client_hints).unwrap_or_else(|_| "\"default\"".to_string()),
+        )
+}
+
+/// The override currently set natively on each webview (macOS only).
+pub struct UserAgentManager {
+    applied: Mutex<HashMap<String, &'static str>>, // webview label -> override domain
+}
+
+impl UserAgentManager {
+    pub fn new() -> Self {
+        Self { applied: Mutex::new(HashMap::new()) }
+    }
+
+    /// Records the override for `webview_label`; returns false if it was already set.
+    fn set_applied(&self, webview_label: &str, domain: Option<&'static str>) -> bool {
+        let mut applied = self.applied.lock().unwrap();
+        let previous = match domain {
+            Some(d) => applied.insert(webview_label.to_string(), d),
+            None => applied.remove(webview_label),
+        };
+        previous != domain
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.applied.lock().unwrap().remove(webview_label);
+    }
+}
+
+impl Default for UserAgentManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn set_native_user_agent(webview: &tauri::Webview, user_agent: Option<String>) {
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CString;
+
+    let user_agent = user_agent.and_then(|ua| CString::new(ua).ok());
+    let result = webview.with_webview(move |platform| unsafe {
+        let wk_webview = platform.inner() as *mut Object;
+        let ns_string: *mut Object = match &user_agent {
+            Some(ua) => msg_send![class!(NSString), stringWithUTF8String: ua.as_ptr()],
+            None => std::ptr::null_mut(),
+        };
+        let _: () = msg_send![wk_webview, setCustomUserAgent: ns_string];
+    });
+    if let Err(e) = result {
+        eprintln!("[UserAgent] Failed to set user agent: {}", e);
+    }
+}
+
+/// Navigation handler for tab webviews. On macOS, returns false to hold back a
+/// navigation to a compatibility site while the Chrome UA is set.
+pub fn on_navigation(app: &AppHandle, webview_label: &str, url: &Url) -> bool {
+    let Some(state) = app.try_state::<AppState>() else {
+        return true;
+    };
+    let Some(entry) = active_override(&state.settings.read().unwrap(), url.as_str()) else {
+        return true;
+    };
+    if !cfg!(target_os = "macos") || !state.user_agents.set_applied(webview_label, Some(entry.domain)) {
+        return true;
+    }
+    #[cfg(target_os = "macos")]
+    if let Some(webview) = app.get_webview(webview_label) {
+        println!("[UserAgent] Using Chrome UA for {}: {}", entry.domain, entry.reason);
+        set_native_user_agent(&webview, chrome_user_agent().map(str::to_string));
+        if let Err(e) = webview.navigate(url.clone()) {
+            eprintln!("[UserAgent] Failed to reload with the new user agent: {}", e);
+        }
+        return false;
+    }
+    true
+}
+
+/// Puts the native UA back to the default when a top-level page without an
+/// override starts loading.
+pub fn on_page_started(webview: &tauri::Webview, state: &AppState, url: &Url) {
+    let entry = active_override(&state.settings.read().unwrap(), url.as_str());
+    if !state.user_agents.set_applied(webview.label(), entry.map(|e| e.domain)) {
+        return;
+    }
+    #[cfg(target_os = "macos")]
+    set_native_user_agent(webview, entry.and(chrome_user_agent()).map(str::to_string).or_else(default_user_agent));
+}
+
+/// What the page-info panel shows about a tab's user agent.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabUserAgent {
+    pub tab_id: String,
+    pub user_agent: Option<String>, // None = the engine's own
+    pub compat_override: Option<CompatOverride>,
+    pub headers_overridden: bool, // False when only navigator.userAgent changes
+    pub client_hints: ClientHintsMode,
+}
+
+#[tauri::command]
+pub fn get_tab_user_agent(state: tauri::State<AppState>, tab_id: String) -> Result<TabUserAgent, BrowserError> {
+    let url = {
+        let tabs = state.tabs.lock().unwrap();
+        let tab = tabs.iter().find(|t| t.id == tab_id).ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+        tab.url.clone()
+    };
+    let settings = state.settings.read().unwrap();
+    let compat_override = active_override(&settings, &url).copied();
+    let user_agent = match compat_override {
+        Some(_) => chrome_user_agent().map(str::to_string),
+        None => default_user_agent(),
+    };
+    Ok(TabUserAgent {
+        tab_id,
+        user_agent,
+        headers_overridden: compat_override.is_some() && cfg!(target_os = "macos"),
+        compat_override,
+        client_hints: settings.client_hints,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://teams.microsoft.com/v2/", Some("teams.microsoft.com"))]
+    #[case("https://TEAMS.live.com./meet/123", Some("teams.live.com"))]
+    #[case("https://eu.teams.microsoft.com/", Some("teams.microsoft.com"))]
+    #[case("https://microsoft.com/", None)]
+    #[case("https://notteams.microsoft.com/", None)]
+    #[case("not a url", None)]
+    fn test_compat_override(#[case] url: &str, #[case] expected: Option<&str>) {
+        assert_eq!(compat_override(url).map(|o| o.domain), expected);
+    }
+
+    #[test]
+    fn test_active_override_follows_settings() {
+        let mut settings = Settings::default();
+        let url = "https://teams.microsoft.com/";
+        assert_eq!(active_override(&settings, url).is_some(), chrome_user_agent().is_some());
+        settings.ua_compat_overrides = false;
+        assert_eq!(active_override(&settings, url), None);
+    }
+
+    #[test]
+    fn test_set_applied_reports_changes() {
+        let manager = UserAgentManager::new();
+        assert!(!manager.set_applied("webview-tab-1", None));
+        assert!(manager.set_applied("webview-tab-1", Some("teams.live.com")));
+        assert!(!manager.set_applied("webview-tab-1", Some("teams.live.com")));
+        assert!(manager.set_applied("webview-tab-1", None));
+
+        manager.set_applied("webview-tab-1", Some("teams.live.com"));
+        manager.forget_webview("webview-tab-1");
+        assert!(manager.set_applied("webview-tab-1", Some("teams.live.com")));
+    }
+
+    #[test]
+    fn test_navigator_script() {
+        let mut settings = Settings::default();
+        settings.client_hints = ClientHintsMode::Reduced;
+        let script = navigator_script(&settings);
+        assert!(script.contains(r#"const CLIENT_HINTS = "reduced";"#));
+        assert!(script.contains(r#""teams.microsoft.com""#));
+        assert!(!script.contains("__"));
+
+        settings.ua_compat_overrides = false;
+        assert!(navigator_script(&settings).contains("const CHROME_UA = null;"));
+    }
+}
@@ -0,0 +1,344 @@
+// Moving a profile to another machine: one archive with everything the user made.
+//
+// `export_user_data` writes a zip with a manifest and a JSON file per kind of data:
+//
+//   manifest.json          format, version, when and by which version it was written, counts
+//   history.json           visited pages (HistoryEntry)
+//   bookmarks.json         bookmarks with their folders
+//   settings.json          settings, including per-site exceptions and overrides
+//   allowlist.json         ad blocking exceptions, in the `export_allowlist` format
+//   closed_tabs.json       recently closed tabs
+//   site_permissions.json  remembered "open in app" decisions per scheme
+//
+// `import_user_data` merges an archive in: history, bookmarks, exceptions,
+// closed tabs and decisions are added to what's there (nothing is deleted, and
+// importing the same archive twice changes nothing), settings replace the
+// current ones. Files missing from an archive are skipped, so a trimmed archive
+// restores what it has. Archives of a newer version are refused rather than
+// half read.
+//
+// The proxy password is left out of the archive; the one already set on the
+// machine importing it is kept.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::adblock_manager::AllowlistExport;
+use crate::history::HistoryEntry;
+use crate::modules::bookmarks::Bookmark;
+use crate::modules::closed_tabs;
+use crate::modules::external_protocols::ProtocolDecision;
+use crate::settings::Settings;
+use crate::state::{AppState, ClosedTab};
+
+pub const FORMAT: &str = "sovereign-user-data";
+pub const VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const HISTORY_FILE: &str = "history.json";
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+const SETTINGS_FILE: &str = "settings.json";
+const ALLOWLIST_FILE: &str = "allowlist.json";
+const CLOSED_TABS_FILE: &str = "closed_tabs.json";
+const SITE_PERMISSIONS_FILE: &str = "site_permissions.json";
+/// Largest file read out of an archive, so a malformed one can't exhaust memory.
+const MAX_FILE_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: u64, // Unix seconds
+    pub app_version: String,
+    #[serde(default)]
+    pub counts: BTreeMap<String, usize>, // Items per file, for showing what an archive holds
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SitePermissions {
+    #[serde(default)]
+    pub external_protocols: BTreeMap<String, ProtocolDecision>,
+}
+
+/// The contents of an archive; None for files it doesn't have.
+#[derive(Debug, Clone, Default)]
+pub struct UserData {
+    pub history: Option<Vec<HistoryEntry>>,
+    pub bookmarks: Option<Vec<Bookmark>>,
+    pub settings: Option<Settings>,
+    pub allowlist: Option<AllowlistExport>,
+    pub closed_tabs: Option<Vec<ClosedTab>>,
+    pub site_permissions: Option<SitePermissions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSummary {
+    pub history_added: usize,
+    pub bookmarks_added: usize,
+    pub exceptions_added: usize,
+    pub closed_tabs_added: usize,
+    pub site_permissions_added: usize,
+    pub settings_restored: bool,
+}
+
+impl UserData {
+    fn counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        let mut count = |file: &str, n: Option<usize>| {
+            if let Some(n) = n {
+                counts.insert(file.to_string(), n);
+            }
+        };
+        count(HISTORY_FILE, self.history.as_ref().map(Vec::len));
+        count(BOOKMARKS_FILE, self.bookmarks.as_ref().map(Vec::len));
+        count(SETTINGS_FILE, self.settings.as_ref().map(|_| 1));
+        count(ALLOWLIST_FILE, self.allowlist.as_ref().map(|a| a.exceptions.len()));
+        count(CLOSED_TABS_FILE, self.closed_tabs.as_ref().map(Vec::len));
+        count(SITE_PERMISSIONS_FILE, self.site_permissions.as_ref().map(|p| p.external_protocols.len()));
+        counts
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Everything in the running profile.
+pub fn collect(state: &AppState) -> UserData {
+    let mut settings = state.settings.read().unwrap().clone();
+    settings.proxy.password = None;
+    UserData {
+        history: Some(state.history.list(0, usize::MAX, None, None).entries),
+        bookmarks: Some(state.bookmarks.list()),
+        settings: Some(settings),
+        allowlist: Some(state.adblock.export_allowlist()),
+        closed_tabs: Some(state.closed_tabs.lock().unwrap().iter().cloned().collect()),
+        site_permissions: Some(SitePermissions {
+            external_protocols: state.external_protocols.list().into_iter().map(|p| (p.scheme, p.decision)).collect(),
+        }),
+    }
+}
+
+fn write_json<T: Serialize>(zip: &mut ZipWriter<File>, name: &str, value: &T) -> Result<(), String> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(&mut *zip, value).map_err(|e| e.to_string())
+}
+
+fn write_files(mut zip: ZipWriter<File>, manifest: &Manifest, data: &UserData) -> Result<(), String> {
+    write_json(&mut zip, MANIFEST_FILE, manifest)?;
+    if let Some(history) = &data.history {
+        write_json(&mut zip, HISTORY_FILE, history)?;
+    }
+    if let Some(bookmarks) = &data.bookmarks {
+        write_json(&mut zip, BOOKMARKS_FILE, bookmarks)?;
+    }
+    if let Some(settings) = &data.settings {
+        write_json(&mut zip, SETTINGS_FILE, settings)?;
+    }
+    if let Some(allowlist) = &data.allowlist {
+        write_json(&mut zip, ALLOWLIST_FILE, allowlist)?;
+    }
+    if let Some(closed_tabs) = &data.closed_tabs {
+        write_json(&mut zip, CLOSED_TABS_FILE, closed_tabs)?;
+    }
+    if let Some(site_permissions) = &data.site_permissions {
+        write_json(&mut zip, SITE_PERMISSIONS_FILE, site_permissions)?;
+    }
+    zip.finish().map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Writes `data` as an archive at `path`. Returns its manifest.
+pub fn write_archive(path: &Path, data: &UserData) -> Result<Manifest, String> {
+    let manifest = Manifest {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        created_at: unix_now(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        counts: data.counts(),
+    };
+    // Written next to the target and renamed, so a failed export leaves no half archive
+    let tmp_path = path.with_extension("tmp");
+    let file = File::create(&tmp_path).map_err(|e| format!("Couldn't create {}: {}", path.display(), e))?;
+    let written = write_files(ZipWriter::new(file), &manifest, data);
+    match written.and_then(|()| std::fs::rename(&tmp_path, path).map_err(|e| e.to_string())) {
+        Ok(()) => Ok(manifest),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_path);
+            Err(format!("Failed to write {}: {}", path.display(), e))
+        }
+    }
+}
+
+fn read_json<T: DeserializeOwned>(zip: &mut ZipArchive<File>, name: &str) -> Result<Option<T>, String> {
+    let file = match zip.by_name(name) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", name, e)),
+    };
+    if file.size() > MAX_FILE_BYTES {
+        return Err(format!("{} is too large", name));
+    }
+    let mut json = String::new();
+    file.take(MAX_FILE_BYTES).read_to_string(&mut json).map_err(|e| format!("{}: {}", name, e))?;
+    serde_json::from_str(&json).map(Some).map_err(|e| format!("{} is damaged: {}", name, e))
+}
+
+/// Reads an archive written by `write_archive` (this version or an older one).
+pub fn read_archive(path: &Path) -> Result<(Manifest, UserData), String> {
+    let file = File::open(path).map_err(|e| format!("Couldn't open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a user data archive: {}", e))?;
+    let manifest: Manifest = read_json(&mut zip, MANIFEST_FILE)?.ok_or("Not a user data archive: no manifest")?;
+    if manifest.format != FORMAT {
+        return Err(format!("Not a user data archive: format '{}'", manifest.format));
+    }
+    if manifest.version > VERSION {
+        return Err(format!("Archive version {} needs a newer version of the browser", manifest.version));
+    }
+    let data = UserData {
+        history: read_json(&mut zip, HISTORY_FILE)?,
+        bookmarks: read_json(&mut zip, BOOKMARKS_FILE)?,
+        settings: read_json(&mut zip, SETTINGS_FILE)?.map(Settings::from_json),
+        allowlist: read_json(&mut zip, ALLOWLIST_FILE)?,
+        closed_tabs: read_json(&mut zip, CLOSED_TABS_FILE)?,
+        site_permissions: read_json(&mut zip, SITE_PERMISSIONS_FILE)?,
+    };
+    Ok((manifest, data))
+}
+
+/// Merges an archive's data into the stores. Settings aren't applied here but
+/// returned, ready to save, for the caller to apply like any settings change.
+pub fn restore(state: &AppState, data: UserData) -> Result<(RestoreSummary, Option<Settings>), String> {
+    let mut summary = RestoreSummary::default();
+    if let Some(history) = data.history {
+        summary.history_added = state.history.import(&history).map_err(|e| e.to_string())?;
+    }
+    if let Some(bookmarks) = data.bookmarks {
+        summary.bookmarks_added = state.bookmarks.add_all(bookmarks);
+    }
+    if let Some(allowlist) = data.allowlist {
+        summary.exceptions_added = state.adblock.import_allowlist(allowlist)?.imported;
+    }
+    if let Some(tabs) = data.closed_tabs {
+        summary.closed_tabs_added = closed_tabs::merge_closed_tabs(&mut state.closed_tabs.lock().unwrap(), tabs);
+    }
+    if let Some(permissions) = data.site_permissions {
+        for (scheme, decision) in permissions.external_protocols {
+            if state.external_protocols.decision(&scheme) == Some(decision) {
+                continue;
+            }
+            match state.external_protocols.set(&scheme, Some(decision)) {
+                Ok(_) => summary.site_permissions_added += 1,
+                Err(e) => eprintln!("[UserData] Skipping {}: {}", scheme, e),
+            }
+        }
+    }
+    let settings = data.settings.map(|mut settings| {
+        if settings.proxy.password.is_none() {
+            settings.proxy.password = state.settings.read().unwrap().proxy.password.clone();
+        }
+        settings
+    });
+    summary.settings_restored = settings.is_some();
+    Ok((summary, settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn sample() -> UserData {
+        UserData {
+            history: Some(vec![HistoryEntry {
+                url: "https://a.com/".to_string(),
+                title: "A".to_string(),
+                last_visit: 1_700_000_000,
+                visit_count: 3,
+                typed_count: 1,
+            }]),
+            bookmarks: Some(vec![Bookmark {
+                url: "https://b.com/".to_string(),
+                title: "B".to_string(),
+                folder: "Bar/Work".to_string(),
+                added_at: 1_700_000_000,
+            }]),
+            settings: Some(Settings { homepage: "https://home.example/".to_string(), ..Settings::default() }),
+            allowlist: None,
+            closed_tabs: Some(Vec::new()),
+            site_permissions: Some(SitePermissions {
+                external_protocols: BTreeMap::from([("mailto".to_string(), ProtocolDecision::Allow)]),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.zip");
+        let written = write_archive(&path, &sample()).unwrap();
+        assert_eq!(written.counts.get(HISTORY_FILE), Some(&1));
+        assert_eq!(written.counts.get(ALLOWLIST_FILE), None);
+        assert!(!path.with_extension("tmp").exists());
+
+        let (manifest, data) = read_archive(&path).unwrap();
+        assert_eq!(manifest, written);
+        assert_eq!(data.history, sample().history);
+        assert_eq!(data.bookmarks, sample().bookmarks);
+        assert_eq!(data.settings.unwrap().homepage, "https://home.example/");
+        assert!(data.allowlist.is_none());
+        assert_eq!(data.closed_tabs.map(|t| t.len()), Some(0));
+        assert_eq!(data.site_permissions, sample().site_permissions);
+    }
+
+    fn archive_with(files: &[(&str, &str)]) -> tempfile::TempPath {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+        file.into_temp_path()
+    }
+
+    #[test]
+    fn test_read_archive_skips_missing_files() {
+        let manifest = r#"{"format":"sovereign-user-data","version":1,"createdAt":0,"appVersion":"0.1.0"}"#;
+        let bookmarks = r#"[{"url":"https://b.com/","title":"B","folder":"","addedAt":0}]"#;
+        let path = archive_with(&[(MANIFEST_FILE, manifest), (BOOKMARKS_FILE, bookmarks)]);
+        let (_, data) = read_archive(&path).unwrap();
+        assert_eq!(data.bookmarks.unwrap().len(), 1);
+        assert!(data.history.is_none() && data.settings.is_none() && data.closed_tabs.is_none());
+    }
+
+    #[test]
+    fn test_read_archive_refusals() {
+        let newer = r#"{"format":"sovereign-user-data","version":2,"createdAt":0,"appVersion":"9.0.0"}"#;
+        assert!(read_archive(&archive_with(&[(MANIFEST_FILE, newer)])).unwrap_err().contains("newer"));
+
+        let other = r#"{"format":"something-else","version":1,"createdAt":0,"appVersion":"0.1.0"}"#;
+        assert!(read_archive(&archive_with(&[(MANIFEST_FILE, other)])).is_err());
+        assert!(read_archive(&archive_with(&[(HISTORY_FILE, "[]")])).unwrap_err().contains("no manifest"));
+
+        let manifest = r#"{"format":"sovereign-user-data","version":1,"createdAt":0,"appVersion":"0.1.0"}"#;
+        let damaged = archive_with(&[(MANIFEST_FILE, manifest), (HISTORY_FILE, "{not json")]);
+        assert!(read_archive(&damaged).unwrap_err().contains("damaged"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let not_zip = dir.path().join("notes.zip");
+        std::fs::write(&not_zip, "hello").unwrap();
+        assert!(read_archive(&not_zip).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+// Pop-out video: a tab's video in a small always-on-top window.
+//
+// `pop_out_video` asks the tab's media controller (modules::media_controls) for
+// its main video; the page answers through `open_video_popout` with the video's
+// URL and position, pauses it and marks it. The window (ui/video-popout.html)
+// plays the same URL from there and reports its position back; when it closes,
+// by its button or otherwise, the marked video in the tab picks up where the
+// window left off. Only plain http(s) video files can move: streams built with
+// Media Source Extensions (blob: URLs, used by most large video sites) live in
+// the page and can't be loaded elsewhere, so those are refused with a message
+// suggesting Picture in Picture instead.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::modules::offline;
+use crate::state::AppState;
+
+const WINDOW_PREFIX: &str = "video-popout-";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub time: f64,
+    pub playing: bool,
+}
+
+pub struct VideoPopoutManager {
+    requested: Mutex<HashSet<String>>,           // Webview labels asked for their video
+    positions: Mutex<HashMap<String, Position>>, // Tab id -> where its pop-out is
+}
+
+impl VideoPopoutManager {
+    pub fn new() -> Self {
+        Self { requested: Mutex::new(HashSet::new()), positions: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn request(&self, webview_label: &str) {
+        self.requested.lock().unwrap().insert(webview_label.to_string());
+    }
+
+    /// Whether the webview was asked for its video; pages can't pop out on their own.
+    pub fn take_request(&self, webview_label: &str) -> bool {
+        self.requested.lock().unwrap().remove(webview_label)
+    }
+
+    pub fn set_position(&self, tab_id: &str, position: Position) {
+        self.positions.lock().unwrap().insert(tab_id.to_string(), position);
+    }
+
+    pub fn take_position(&self, tab_id: &str) -> Option<Position> {
+        self.positions.lock().unwrap().remove(tab_id)
+    }
+
+    pub fn forget_webview(&self, webview_label: &str) {
+        self.requested.lock().unwrap().remove(webview_label);
+    }
+}
+
+impl Default for VideoPopoutManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn window_label(tab_id: &str) -> String {
+    format!("{}{}", WINDOW_PREFIX, tab_id)
+}
+
+/// Checks the video's URL can be loaded by the pop-out window.
+pub fn validate_source(src: Option<&str>) -> Result<Url, String> {
+    let src = src.ok_or("There's no video on this page")?;
+    let url = Url::parse(src).map_err(|_| "This video can't be popped out".to_string())?;
+    match url.scheme() {
+        "http" | "https" => Ok(url),
+        "blob" | "mediasource" => {
+            Err("This video is streamed in a way that can't leave the page. Try Picture in Picture instead.".into())
+        }
+        _ => Err("This video can't be popped out".to_string()),
+    }
+}
+
+fn popout_url(tab_id: &str, src: &Url, position: Position) -> String {
+    format!(
+        "video-popout.html?tab={}&src={}&t={}&play={}",
+        urlencoding::encode(tab_id),
+        urlencoding::encode(src.as_str()),
+        position.time.max(0.0),
+        position.playing as u8
+    )
+}
+
+fn show_error(app: &AppHandle, message: &str) {
+    app.dialog().message(message).title("Pop Out Video").kind(MessageDialogKind::Info).show(|_| {});
+}
+
+/// Hands the video back to its tab, at the pop-out's position.
+fn resume_in_tab(app: &AppHandle, tab_id: &str) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let position = state.video_popout.take_position(tab_id);
+    let label = state.tabs.lock().unwrap().iter().find(|t| t.id == tab_id).map(|t| t.webview_label.clone());
+    let (Some(position), Some(webview)) = (position, label.and_then(|label| app.get_webview(&label))) else {
+        return;
+    };
+    let _ = webview.eval(&format!(
+        "window.__SOVEREIGN_MEDIA__ && window.__SOVEREIGN_MEDIA__.resumeFromPopOut({}, {});",
+        position.time, position.playing
+    ));
+}
+
+/// Closes a tab's pop-out, e.g. when the tab closes.
+pub fn close_for_tab(app: &AppHandle, tab_id: &str) {
+    if let Some(window) = app.get_webview_window(&window_label(tab_id)) {
+        let _ = window.close();
+    }
+}
+
+/// Closes every pop-out, e.g. on going offline. Their videos go back to their tabs.
+pub fn close_all(app: &AppHandle) {
+    for (label, window) in app.webview_windows() {
+        if label.starts_with(WINDOW_PREFIX) {
+            let _ = window.close();
+        }
+    }
+}
+
+/// Pops out the main video of a tab (the active one by default).
+#[tauri::command]
+pub fn pop_out_video(
+    app: AppHandle,
+    state: tauri::State<AppState>,
+    tab_id: Option<String>,
+) -> Result<(), BrowserError> {
+    if offline::is_offline(&state) {
+        return Err(BrowserError::NotAllowed("Can't pop out videos while offline".to_string()));
+    }
+    let tab_id = tab_id
+        .or_else(|| state.active_tab_id.lock().unwrap().clone())
+        .ok_or_else(|| BrowserError::NotFound("No active tab".to_string()))?;
+    if let Some(window) = app.get_webview_window(&window_label(&tab_id)) {
+        return Ok(window.set_focus()?);
+    }
+    let label = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.id == tab_id)
+        .map(|t| t.webview_label.clone())
+        .ok_or_else(|| BrowserError::tab_not_found(&tab_id))?;
+    let webview =
+        app.get_webview(&label).ok_or_else(|| BrowserError::NotFound(format!("Webview not found: {}", label)))?;
+    state.video_popout.request(&label);
+    Ok(webview.eval("window.__SOVEREIGN_MEDIA__ && window.__SOVEREIGN_MEDIA__.popOut();")?)
+}
+
+/// The page's answer to `pop_out_video`: opens the window.
+#[tauri::command]
+pub fn open_video_popout(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    src: Option<String>,
+    time: f64,
+    playing: bool,
+    title: String,
+) -> Result<(), BrowserError> {
+    if !state.video_popout.take_request(webview.label()) {
+        return Err(BrowserError::NotAllowed("Not requested".to_string()));
+    }
+    let src = match validate_source(src.as_deref()) {
+        Ok(src) => src,
+        Err(e) => {
+            show_error(&app, &e);
+            return Err(BrowserError::Unsupported(e));
+        }
+    };
+    let tab_id = state
+        .tabs
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|t| t.webview_label == webview.label())
+        .map(|t| t.id.clone())
+        .ok_or_else(|| BrowserError::NotFound("Tab not found".to_string()))?;
+    let position = Position { time: if time.is_finite() { time } else { 0.0 }, playing };
+    state.video_popout.set_position(&tab_id, position);
+
+    let window = tauri::WebviewWindowBuilder::new(
+        &app,
+        window_label(&tab_id),
+        tauri::WebviewUrl::App(popout_url(&tab_id, &src, position).into()),
+    )
+    .title(if title.is_empty() { "Video".to_string() } else { title })
+    .inner_size(400.0, 225.0)
+    .min_inner_size(200.0, 112.0)
+    .decorations(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .build()
+    .map_err(|e| {
+        state.video_popout.take_position(&tab_id);
+        BrowserError::Webview(format!("Failed to open the video window: {}", e))
+    })?;
+
+    let h = app.clone();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Destroyed = event {
+            resume_in_tab(&h, &tab_id);
+        }
+    });
+    println!("[VideoPopout] Popped out {}", src);
+    Ok(())
+}
+
+/// Position reports from the pop-out window.
+#[tauri::command]
+pub fn update_video_popout(
+    window: tauri::Window,
+    state: tauri::State<AppState>,
+    time: f64,
+    playing: bool,
+) -> Result<(), BrowserError> {
+    let tab_id = window
+        .label()
+        .strip_prefix(WINDOW_PREFIX)
+        .ok_or_else(|| BrowserError::NotAllowed("Not a video window".to_string()))?;
+    if time.is_finite() {
+        state.video_popout.set_position(tab_id, Position { time, playing });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(Some("https://cdn.example/clip.mp4"), true)]
+    #[case(Some("blob:https://video.example/6c1e-4d"), false)]
+    #[case(Some("data:video/mp4;base64,AAAA"), false)]
+    #[case(None, false)]
+    fn test_validate_source(#[case] src: Option<&str>, #[case] valid: bool) {
+        assert_eq!(validate_source(src).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_popout_url() {
+        let src = Url::parse("https://cdn.example/clip.mp4?sig=a&b=c").unwrap();
+        let url = popout_url("tab-1", &src, Position { time: 12.5, playing: true });
+        assert_eq!(
+            url,
+            "video-popout.html?tab=tab-1&src=https%3A%2F%2Fcdn.example%2Fclip.mp4%3Fsig%3Da%26b%3Dc&t=12.5&play=1"
+        );
+    }
+
+    #[test]
+    fn test_requests_are_single_use() {
+        let manager = VideoPopoutManager::new();
+        assert!(!manager.take_request("webview-tab-1"));
+        manager.request("webview-tab-1");
+        assert!(manager.take_request("webview-tab-1"));
+        assert!(!manager.take_request("webview-tab-1"));
+
+        manager.set_position("tab-1", Position { time: 3.0, playing: false });
+        manager.set_position("tab-1", Position { time: 4.0, playing: true });
+        assert_eq!(manager.take_position("tab-1"), Some(Position { time: 4.0, playing: true }));
+        assert_eq!(manager.take_position("tab-1"), None);
+    }
+}
@@ -0,0 +1,290 @@
+// WebAuthn (passkeys) support check.
+//
+// Whether `navigator.credentials.create/get({ publicKey })` can work depends on
+// the engine and how the app is signed:
+// - macOS: WKWebView only talks to the platform authenticator (iCloud Keychain,
+//   Touch ID) and security keys for apps that carry the
+//   `com.apple.developer.web-browser.public-key-credential` entitlement
+//   (Entitlements.plist). It's a managed entitlement: builds signed without a
+//   provisioning profile that grants it don't get it, and calls fail.
+// - Windows: WebView2 goes through the system WebAuthn API (Windows Hello and
+//   security keys), so it works wherever webauthn.dll does.
+// - Linux: WebKitGTK doesn't ship WebAuthn.
+//
+// `support()` reports which of these holds. Where WebAuthn can't work,
+// `page_script` makes the page's calls fail straight away with a
+// NotSupportedError that says why, and the toolbar shows a chip leading to the
+// full report, rather than leaving a sign-in to hang or fail silently.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use url::Url;
+
+use crate::error::BrowserError;
+use crate::state::AppState;
+
+const PAGE_SCRIPT_TEMPLATE: &str = r#"
+(function() {
+    const creds = navigator.credentials;
+    if (!creds || !window.__TAURI__) return;
+    const reason = __UNAVAILABLE__ || (window.PublicKeyCredential ? null : 'This browser engine has no WebAuthn support');
+    if (!reason) return;
+    const invoke = window.__TAURI__.core.invoke;
+
+    for (const method of ['create', 'get']) {
+        const original = typeof creds[method] === 'function' ? creds[method].bind(creds) : null;
+        creds[method] = function(options) {
+            if (!options || !options.publicKey) {
+                return original ? original(options) : Promise.reject(new DOMException(reason, 'NotSupportedError'));
+            }
+            invoke('report_webauthn_unavailable', { pageUrl: location.href }).catch(() => {});
+            return Promise.reject(new DOMException('Passkeys are unavailable: ' + reason, 'NotSupportedError'));
+        };
+    }
+})();
+"#;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebAuthnSupport {
+    pub engine: &'static str,
+    pub entitled: Option<bool>, // macOS: signed with the browser passkey entitlement
+    pub platform_authenticator: Option<bool>, // Touch ID / Windows Hello / device passcode set up
+    pub api_version: Option<u32>, // Windows: webauthn.dll API version
+    pub problems: Vec<String>,  // Why WebAuthn can't work; empty when it can
+    pub notes: Vec<String>,     // Limits that still leave it usable
+}
+
+impl WebAuthnSupport {
+    pub fn supported(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// The report as text, for the diagnostics dialog.
+    pub fn to_text(&self) -> String {
+        let yes_no = |value: Option<bool>| match value {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "n/a",
+        };
+        let mut text = format!(
+            "Passkeys: {}\nEngine: {}\nBrowser entitlement: {}\nPlatform authenticator: {}\n",
+            if self.supported() { "available" } else { "unavailable" },
+            self.engine,
+            yes_no(self.entitled),
+            yes_no(self.platform_authenticator),
+        );
+        if let Some(version) = self.api_version {
+            text.push_str(&format!("WebAuthn API version: {}\n", version));
+        }
+        for line in self.problems.iter().chain(&self.notes) {
+            text.push_str("\n- ");
+            text.push_str(line);
+        }
+        text
+    }
+
+    fn finish(mut self) -> Self {
+        if self.platform_authenticator == Some(false) && self.supported() {
+            self.notes.push("No platform authenticator is set up; only security keys and phones can be used".into());
+        }
+        self
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn support() -> WebAuthnSupport {
+    use objc::runtime::{Object, BOOL, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::{c_void, CString};
+
+    const ENTITLEMENT: &str = "com.apple.developer.web-browser.public-key-credential";
+    const POLICY_DEVICE_OWNER_AUTHENTICATION: i64 = 2; // Biometrics or the login password
+
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        fn SecTaskCreateFromSelf(allocator: *const c_void) -> *mut c_void;
+        fn SecTaskCopyValueForEntitlement(
+            task: *mut c_void,
+            entitlement: *mut Object,
+            error: *mut c_void,
+        ) -> *mut Object;
+    }
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(cf: *const c_void);
+    }
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    let entitled = unsafe {
+        let task = SecTaskCreateFromSelf(std::ptr::null());
+        if task.is_null() {
+            false
+        } else {
+            let key = CString::new(ENTITLEMENT).unwrap_or_default();
+            let key: *mut Object = msg_send![class!(NSString), stringWithUTF8String: key.as_ptr()];
+            let value = SecTaskCopyValueForEntitlement(task, key, std::ptr::null_mut());
+            CFRelease(task);
+            if value.is_null() {
+                false
+            } else {
+                let enabled: BOOL = msg_send![value, boolValue];
+                CFRelease(value as *const c_void);
+                enabled != NO
+            }
+        }
+    };
+    let platform_authenticator = unsafe {
+        let context: *mut Object = msg_send![class!(LAContext), new];
+        if context.is_null() {
+            None
+        } else {
+            let error = std::ptr::null_mut::<*mut Object>();
+            let available: BOOL =
+                msg_send![context, canEvaluatePolicy: POLICY_DEVICE_OWNER_AUTHENTICATION error: error];
+            let _: () = msg_send![context, release];
+            Some(available != NO)
+        }
+    };
+
+    let mut problems = Vec::new();
+    if !entitled {
+        problems.push(format!("This build isn't signed with the {} entitlement", ENTITLEMENT));
+    }
+    WebAuthnSupport {
+        engine: "WebKit (WKWebView)",
+        entitled: Some(entitled),
+        platform_authenticator,
+        api_version: None,
+        problems,
+        notes: Vec::new(),
+    }
+    .finish()
+}
+
+#[cfg(windows)]
+pub fn support() -> WebAuthnSupport {
+    use windows::Win32::Networking::WindowsWebServices::{
+        WebAuthNGetApiVersionNumber, WebAuthNIsUserVerifyingPlatformAuthenticatorAvailable,
+    };
+
+    let api_version = unsafe { WebAuthNGetApiVersionNumber() };
+    let platform_authenticator =
+        unsafe { WebAuthNIsUserVerifyingPlatformAuthenticatorAvailable() }.ok().map(|available| available.as_bool());
+    let mut problems = Vec::new();
+    if api_version == 0 {
+        problems.push("The Windows WebAuthn API isn't available on this system".to_string());
+    }
+    WebAuthnSupport {
+        engine: "WebView2",
+        entitled: None,
+        platform_authenticator,
+        api_version: Some(api_version),
+        problems,
+        notes: Vec::new(),
+    }
+    .finish()
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn support() -> WebAuthnSupport {
+    WebAuthnSupport {
+        engine: "WebKitGTK",
+        entitled: None,
+        platform_authenticator: None,
+        api_version: None,
+        problems: vec!["WebKitGTK doesn't support WebAuthn".to_string()],
+        notes: Vec::new(),
+    }
+}
+
+/// Checked once: neither the signature nor the engine change while running.
+fn cached_support() -> &'static WebAuthnSupport {
+    static SUPPORT: OnceLock<WebAuthnSupport> = OnceLock::new();
+    SUPPORT.get_or_init(support)
+}
+
+fn script_for(support: &WebAuthnSupport) -> String {
+    let reason = if support.supported() { None } else { Some(support.problems.join("; ")) };
+    PAGE_SCRIPT_TEMPLATE.replace("__UNAVAILABLE__", &serde_json::to_string(&reason).unwrap_or_else(|_| "null".into()))
+}
+
+/// Fails WebAuthn calls up front where they can't work.
+pub fn page_script() -> String {
+    script_for(cached_support())
+}
+
+/// The report in a dialog (command palette: "Check Passkey Support").
+pub fn show_report(app: &AppHandle) {
+    let support = support();
+    app.dialog()
+        .message(support.to_text())
+        .title("Passkey Support")
+        .kind(if support.supported() { MessageDialogKind::Info } else { MessageDialogKind::Warning })
+        .show(|_| {});
+}
+
+#[tauri::command]
+pub fn get_webauthn_support() -> Result<WebAuthnSupport, BrowserError> {
+    Ok(support())
+}
+
+/// A page's WebAuthn call was refused; shows the toolbar chip for its tab.
+#[tauri::command]
+pub fn report_webauthn_unavailable(
+    app: AppHandle,
+    webview: tauri::Webview,
+    state: tauri::State<AppState>,
+    page_url: String,
+) -> Result<(), BrowserError> {
+    let tab_id = state.tabs.lock().unwrap().iter().find(|t| t.webview_label == webview.label()).map(|t| t.id.clone());
+    let tab_id = tab_id.ok_or_else(|| BrowserError::NotAllowed("Not from a tab".to_string()))?;
+    let host = Url::parse(&page_url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default();
+    println!("[WebAuthn] Refused a passkey request from {}", host);
+    Ok(app.emit_to(
+        "main",
+        "webauthn-unavailable",
+        serde_json::json!({ "tabId": tab_id, "host": host, "reason": cached_support().problems.join("; ") }),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(problems: Vec<&str>, platform_authenticator: Option<bool>) -> WebAuthnSupport {
+        WebAuthnSupport {
+            engine: "Test",
+            entitled: None,
+            platform_authenticator,
+            api_version: None,
+            problems: problems.into_iter().map(String::from).collect(),
+            notes: Vec::new(),
+        }
+        .finish()
+    }
+
+    #[test]
+    fn test_script_embeds_reason() {
+        let unsupported = script_for(&report(vec!["No engine support", "Unsigned"], None));
+        assert!(unsupported.contains(r#"const reason = "No engine support; Unsigned" ||"#));
+        let supported = script_for(&report(vec![], Some(true)));
+        assert!(supported.contains("const reason = null ||"));
+    }
+
+    #[test]
+    fn test_missing_authenticator_is_a_note() {
+        let support = report(vec![], Some(false));
+        assert!(support.supported());
+        assert_eq!(support.notes.len(), 1);
+        assert!(support.to_text().starts_with("Passkeys: available\n"));
+
+        let support = report(vec!["Unsigned"], Some(false));
+        assert!(support.notes.is_empty());
+        assert!(support.to_text().contains("Passkeys: unavailable"));
+        assert!(support.to_text().ends_with("\n- Unsigned"));
+    }
+}
@@ -1,76 +1,527 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use tauri::AppHandle;
-use tauri::Manager;
+use crate::modules::appearance::WindowMaterial;
+use crate::modules::cookie_policy;
+use crate::modules::data_saver::DataSaverMode;
+use crate::modules::doh::DohMode;
+use crate::modules::fingerprint::{self, SpoofingProfile};
+use crate::modules::layout::{self, ChromeLayout, TabPlacement};
+use crate::modules::media_controls;
+use crate::modules::profile;
+use crate::modules::proxy::{self, ProxySettings};
+use crate::modules::settings_migration;
+use crate::modules::toolbar_layout::{self, ToolbarWidget};
+use crate::modules::user_agent::ClientHintsMode;
 
+/// Placeholder substituted with the URL-encoded query in `query_template`.
+pub const QUERY_PLACEHOLDER: &str = "%s";
+
+/// Learned site searches kept at most, see modules::site_search.
+const MAX_LEARNED_ENGINES: usize = 100;
+
+/// A search engine entry in the user-extensible registry.
+/// `keyword` lets the omnibox route "w rust" straight to Wikipedia.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum SearchEngine {
-    DuckDuckGo,
-    Google,
-    Bing,
-    Brave,
+pub struct SearchEngine {
+    pub name: String,
+    pub keyword: String,
+    pub query_template: String, // e.g. "https://duckduckgo.com/?q=%s"
+    #[serde(default)]
+    pub suggest_template: Option<String>, // OpenSearch suggestions endpoint, if any
+    #[serde(default)]
+    pub learned: bool, // Found on a site the user searched, see modules::site_search
 }
 
-impl Default for SearchEngine {
-    fn default() -> Self {
-        Self::DuckDuckGo
+impl SearchEngine {
+    pub fn new(name: &str, keyword: &str, query_template: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            keyword: keyword.to_string(),
+            query_template: query_template.to_string(),
+            suggest_template: None,
+            learned: false,
+        }
+    }
+
+    pub fn with_suggest(mut self, suggest_template: &str) -> Self {
+        self.suggest_template = Some(suggest_template.to_string());
+        self
     }
-}
 
-impl SearchEngine {
     pub fn query_url(&self, query: &str) -> String {
         let q = urlencoding::encode(query);
-        match self {
-            Self::DuckDuckGo => format!("https://duckduckgo.com/?q={}", q),
-            Self::Google => format!("https://google.com/search?q={}", q),
-            Self::Bing => format!("https://bing.com/search?q={}", q),
-            Self::Brave => format!("https://search.brave.com/search?q={}", q),
-        }
+        self.query_template.replace(QUERY_PLACEHOLDER, &q)
+    }
+
+    pub fn suggest_url(&self, query: &str) -> Option<String> {
+        let q = urlencoding::encode(query);
+        self.suggest_template.as_ref().map(|t| t.replace(QUERY_PLACEHOLDER, &q))
+    }
+
+    /// Engines shipped with the browser. Names match the old enum variants
+    /// so existing settings.json files keep their selected engine.
+    pub fn builtins() -> Vec<SearchEngine> {
+        vec![
+            Self::new("DuckDuckGo", "ddg", "https://duckduckgo.com/?q=%s")
+                .with_suggest("https://duckduckgo.com/ac/?q=%s&type=list"),
+            Self::new("Google", "g", "https://google.com/search?q=%s")
+                .with_suggest("https://suggestqueries.google.com/complete/search?client=firefox&q=%s"),
+            Self::new("Bing", "b", "https://bing.com/search?q=%s")
+                .with_suggest("https://api.bing.com/osjson.aspx?query=%s"),
+            Self::new("Brave", "br", "https://search.brave.com/search?q=%s")
+                .with_suggest("https://search.brave.com/api/suggest?q=%s"),
+            Self::new("Wikipedia", "w", "https://en.wikipedia.org/wiki/Special:Search?search=%s"),
+            Self::new("YouTube", "yt", "https://www.youtube.com/results?search_query=%s"),
+        ]
+    }
+}
+
+/// Per-site overrides of global settings, keyed by site (eTLD+1) in
+/// `Settings.site_settings`. Unset fields follow the global setting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SiteSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spoofing_profile: Option<SpoofingProfile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_images: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>, // IANA name reported to the site's scripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>, // BCP 47 tag reported to the site's scripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playback_rate: Option<f64>, // Media speed, see modules::media_controls
+}
+
+impl SiteSettings {
+    fn is_empty(&self) -> bool {
+        self.spoofing_profile.is_none()
+            && self.block_images.is_none()
+            && self.timezone.is_none()
+            && self.locale.is_none()
+            && self.playback_rate.is_none()
     }
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_sidebar_width() -> f64 {
+    layout::DEFAULT_SIDEBAR_WIDTH
+}
+
+fn default_search_engine_name() -> String {
+    "DuckDuckGo".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
+    #[serde(default)]
+    pub schema_version: u32, // 0 for files from before it existed, see modules::settings_migration
     pub homepage: String,
-    pub search_engine: SearchEngine,
+    #[serde(default = "default_true")]
+    pub new_tab_top_sites: bool, // New tabs open the top sites page instead of the homepage, see modules::top_sites
+    pub search_engine: String, // Name of the default engine in `search_engines`
+    #[serde(default = "SearchEngine::builtins")]
+    pub search_engines: Vec<SearchEngine>,
     pub block_trackers: bool,
     pub https_only: bool,
+    #[serde(default)]
+    pub https_only_exceptions: Vec<String>, // Sites (eTLD+1) the user chose to keep loading over HTTP
+    #[serde(default = "default_true")]
+    pub strip_tracking_params: bool, // Drop utm_*, fbclid and the like from links, see modules::tracking_params
     pub clear_on_exit: bool,
+    #[serde(default)]
+    pub search_suggestions: bool, // Opt-in: sends omnibox input to the search engine
+    #[serde(default)]
+    pub block_third_party_cookies: bool,
+    #[serde(default)]
+    pub third_party_cookie_exceptions: Vec<String>, // Sites (eTLD+1) where third-party cookies stay allowed
+    #[serde(default = "default_true")]
+    pub fingerprint_protection: bool, // Per-site canvas/audio/WebGL noise
+    #[serde(default)]
+    pub fingerprint_exceptions: Vec<String>, // Sites (eTLD+1) that get an unmodified fingerprint
+    #[serde(default)]
+    pub spoofing_profile: SpoofingProfile, // Anti-bot navigator spoofing
+    #[serde(default = "default_true")]
+    pub safe_browsing: bool, // Local malware/phishing blocklist, see modules::safebrowsing
+    #[serde(default)]
+    pub list_updates_on_metered: bool, // Scheduled list downloads on metered networks, see modules::list_updates
+    #[serde(default = "default_true")]
+    pub form_audit: bool, // Warn before forms post to other sites or over http, see modules::form_audit
+    #[serde(default = "default_true")]
+    pub totp_autofill: bool, // Offer one-time codes from the vault on 2FA pages, see modules::totp
+    #[serde(default)]
+    pub breach_check: bool, // Opt-in: allow checks against Have I Been Pwned, see modules::breach_check
+    #[serde(default)]
+    pub privacy_report_notification: bool, // Weekly OS notification, see modules::privacy_report
+    #[serde(default)]
+    pub limit_font_detection: bool, // Hide installed fonts from canvas measureText probing
+    #[serde(default)]
+    pub audio_output: Option<String>, // Output device name for tabs, None for the system default
+    #[serde(default)]
+    pub site_settings: BTreeMap<String, SiteSettings>,
+    #[serde(default)]
+    pub block_images: bool,
+    #[serde(default = "default_true")]
+    pub ua_compat_overrides: bool, // Chrome UA for the sites in user_agent::COMPAT_OVERRIDES
+    #[serde(default)]
+    pub client_hints: ClientHintsMode,
+    #[serde(default)]
+    pub data_saver: DataSaverMode,
+    #[serde(default = "default_true")]
+    pub data_saver_placeholders: bool, // Click-to-load boxes for images that didn't load
+    #[serde(default)]
+    pub data_saver_exceptions: Vec<String>, // Sites (eTLD+1) that always load everything
+    #[serde(default)]
+    pub doh_mode: DohMode,
+    #[serde(default)]
+    pub doh_custom_url: Option<String>, // https resolver with a JSON API, used when doh_mode = custom
+    #[serde(default)]
+    pub proxy: ProxySettings,
+    #[serde(default)]
+    pub clipboard_url_detection: bool, // Opt-in: look for a copied link when the window gains focus
+    #[serde(default)]
+    pub save_console_log: bool, // Also write page console output to a log file, see modules::console_log
     pub theme: String, // "dark", "light", "system"
+    #[serde(default)]
+    pub window_material: WindowMaterial,
+    #[serde(default)]
+    pub titlebar_tint: Option<String>, // "#rrggbb", None = theme default
     pub compact_mode: bool,
+    #[serde(default)]
+    pub tablet_mode: bool, // Taller toolbar with larger touch targets, see modules::layout
+    #[serde(default = "default_true")]
+    pub pinch_zoom: bool, // Touchscreen/touchpad pinch zooms pages, see modules::touch
+    #[serde(default = "default_true")]
+    pub swipe_navigation: bool, // Two-finger and edge swipes go back and forward
+    #[serde(default = "default_true")]
+    pub smooth_scrolling: bool, // Animated, kinetic scrolling (Linux)
+    #[serde(default = "default_true")]
+    pub spellcheck: bool, // Continuous spellchecking in page text fields (macOS), see modules::text_input
+    #[serde(default)]
+    pub autocorrect: bool,
+    #[serde(default)]
+    pub smart_quotes: bool, // Curly quotes and dashes while typing
+    #[serde(default = "default_true")]
+    pub dictation: bool, // Edit > Start Dictation, from the next launch
+    #[serde(default)]
+    pub tab_placement: TabPlacement, // Top strip or vertical sidebar, see modules::layout
+    #[serde(default = "default_sidebar_width")]
+    pub sidebar_width: f64, // Logical pixels, for vertical tabs
+    #[serde(default = "toolbar_layout::default_layout")]
+    pub toolbar_layout: Vec<ToolbarWidget>,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            schema_version: settings_migration::CURRENT_VERSION,
             homepage: "https://duckduckgo.com".to_string(),
-            search_engine: SearchEngine::default(),
+            new_tab_top_sites: true,
+            search_engine: default_search_engine_name(),
+            search_engines: SearchEngine::builtins(),
             block_trackers: true,
             https_only: true,
+            https_only_exceptions: Vec::new(),
+            strip_tracking_params: true,
             clear_on_exit: false,
+            search_suggestions: false,
+            block_third_party_cookies: false,
+            third_party_cookie_exceptions: Vec::new(),
+            fingerprint_protection: true,
+            fingerprint_exceptions: Vec::new(),
+            spoofing_profile: SpoofingProfile::Full,
+            safe_browsing: true,
+            list_updates_on_metered: false,
+            form_audit: true,
+            totp_autofill: true,
+            breach_check: false,
+            privacy_report_notification: false,
+            limit_font_detection: false,
+            audio_output: None,
+            site_settings: BTreeMap::new(),
+            block_images: false,
+            ua_compat_overrides: true,
+            client_hints: ClientHintsMode::Default,
+            data_saver: DataSaverMode::Off,
+            data_saver_placeholders: true,
+            data_saver_exceptions: Vec::new(),
+            doh_mode: DohMode::Off,
+            doh_custom_url: None,
+            proxy: ProxySettings::default(),
+            clipboard_url_detection: false,
+            save_console_log: false,
             theme: "dark".to_string(),
+            window_material: WindowMaterial::None,
+            titlebar_tint: None,
             compact_mode: false,
+            tablet_mode: false,
+            pinch_zoom: true,
+            swipe_navigation: true,
+            smooth_scrolling: true,
+            spellcheck: true,
+            autocorrect: false,
+            smart_quotes: false,
+            dictation: true,
+            tab_placement: TabPlacement::Top,
+            sidebar_width: layout::DEFAULT_SIDEBAR_WIDTH,
+            toolbar_layout: toolbar_layout::default_layout(),
         }
     }
 }
 
 impl Settings {
+    /// The engine used for plain (non-keyword) queries.
+    /// Falls back to the first registered engine, then to DuckDuckGo.
+    pub fn default_engine(&self) -> SearchEngine {
+        self.search_engines
+            .iter()
+            .find(|e| e.name == self.search_engine)
+            .or_else(|| self.search_engines.first())
+            .cloned()
+            .unwrap_or_else(|| SearchEngine::new("DuckDuckGo", "ddg", "https://duckduckgo.com/?q=%s"))
+    }
+
+    /// Look up an engine by its keyword (case-insensitive).
+    pub fn engine_for_keyword(&self, keyword: &str) -> Option<&SearchEngine> {
+        if keyword.is_empty() {
+            return None;
+        }
+        self.search_engines
+            .iter()
+            .find(|e| !e.keyword.is_empty() && e.keyword.eq_ignore_ascii_case(keyword))
+    }
+
+    pub fn add_search_engine(&mut self, engine: SearchEngine) -> Result<(), String> {
+        let name = engine.name.trim();
+        let keyword = engine.keyword.trim();
+        if name.is_empty() {
+            return Err("Search engine name cannot be empty".to_string());
+        }
+        if keyword.contains(char::is_whitespace) {
+            return Err("Keyword cannot contain spaces".to_string());
+        }
+        if !engine.query_template.contains(QUERY_PLACEHOLDER) {
+            return Err(format!("Query template must contain '{}'", QUERY_PLACEHOLDER));
+        }
+        if self.search_engines.iter().any(|e| e.name == name) {
+            return Err(format!("A search engine named '{}' already exists", name));
+        }
+        if self.engine_for_keyword(keyword).is_some() {
+            return Err(format!("Keyword '{}' is already in use", keyword));
+        }
+
+        let mut entry = SearchEngine::new(name, keyword, engine.query_template.trim());
+        entry.suggest_template = engine.suggest_template.filter(|t| t.contains(QUERY_PLACEHOLDER));
+        self.search_engines.push(entry);
+        Ok(())
+    }
+
+    /// Adds an engine found on a site, or with `replace` updates the learned one of that
+    /// site. Engines the user added are never touched. Returns whether anything changed.
+    pub fn learn_search_engine(&mut self, engine: SearchEngine, replace: bool) -> bool {
+        let existing = self
+            .search_engines
+            .iter_mut()
+            .find(|e| e.name == engine.name || e.keyword.eq_ignore_ascii_case(&engine.keyword));
+        if let Some(existing) = existing {
+            if !existing.learned || !replace || existing.query_template == engine.query_template {
+                return false;
+            }
+            existing.query_template = engine.query_template;
+            return true;
+        }
+        if self.search_engines.iter().filter(|e| e.learned).count() >= MAX_LEARNED_ENGINES {
+            return false;
+        }
+        self.search_engines.push(SearchEngine { learned: true, ..engine });
+        true
+    }
+
+    pub fn remove_search_engine(&mut self, name: &str) -> Result<(), String> {
+        if self.search_engine == name {
+            return Err("Cannot remove the default search engine".to_string());
+        }
+        let before = self.search_engines.len();
+        self.search_engines.retain(|e| e.name != name);
+        if self.search_engines.len() == before {
+            return Err(format!("No search engine named '{}'", name));
+        }
+        Ok(())
+    }
+
+    /// The DoH resolver endpoint, or None when DoH is off (or the custom URL is invalid).
+    pub fn doh_endpoint(&self) -> Option<String> {
+        self.doh_mode.endpoint(self.doh_custom_url.as_deref())
+    }
+
+    /// Where the toolbar page leaves room for tab webviews.
+    pub fn chrome_layout(&self) -> ChromeLayout {
+        ChromeLayout::new(self.tab_placement, self.sidebar_width).with_tablet_mode(self.tablet_mode)
+    }
+
+    /// Allow third-party cookies on a site. Returns the normalized site.
+    pub fn add_cookie_exception(&mut self, site: &str) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        if !self.third_party_cookie_exceptions.contains(&site) {
+            self.third_party_cookie_exceptions.push(site.clone());
+        }
+        Ok(site)
+    }
+
+    pub fn remove_cookie_exception(&mut self, site: &str) -> Result<(), String> {
+        let site = cookie_policy::normalize_site(site).unwrap_or_else(|| site.to_string());
+        let before = self.third_party_cookie_exceptions.len();
+        self.third_party_cookie_exceptions.retain(|s| s != &site);
+        if self.third_party_cookie_exceptions.len() == before {
+            return Err(format!("No cookie exception for '{}'", site));
+        }
+        Ok(())
+    }
+
+    /// Remembers that a site may load over HTTP in HTTPS-Only mode. Returns the normalized site.
+    pub fn add_https_only_exception(&mut self, site: &str) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        if !self.https_only_exceptions.contains(&site) {
+            self.https_only_exceptions.push(site.clone());
+        }
+        Ok(site)
+    }
+
+    /// Adds (`excepted = true`) or removes a site from the fingerprint noise
+    /// exceptions. Returns the normalized site.
+    pub fn set_fingerprint_exception(&mut self, site: &str, excepted: bool) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        self.fingerprint_exceptions.retain(|s| s != &site);
+        if excepted {
+            self.fingerprint_exceptions.push(site.clone());
+        }
+        Ok(site)
+    }
+
+    /// Adds (`excepted = true`) or removes a site from the data saver exceptions.
+    /// Returns the normalized site.
+    pub fn set_data_saver_exception(&mut self, site: &str, excepted: bool) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        self.data_saver_exceptions.retain(|s| s != &site);
+        if excepted {
+            self.data_saver_exceptions.push(site.clone());
+        }
+        Ok(site)
+    }
+
+    /// Sets (or with None, clears) a site's spoofing profile override. Returns the
+    /// normalized site.
+    pub fn set_site_spoofing_profile(&mut self, site: &str, profile: Option<SpoofingProfile>) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        let entry = self.site_settings.entry(site.clone()).or_default();
+        entry.spoofing_profile = profile;
+        if entry.is_empty() {
+            self.site_settings.remove(&site);
+        }
+        Ok(site)
+    }
+
+    /// Sets (or with None, clears) whether a site's images are blocked regardless
+    /// of `block_images`. Returns the normalized site.
+    pub fn set_site_block_images(&mut self, site: &str, blocked: Option<bool>) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        let entry = self.site_settings.entry(site.clone()).or_default();
+        entry.block_images = blocked;
+        if entry.is_empty() {
+            self.site_settings.remove(&site);
+        }
+        Ok(site)
+    }
+
+    /// Sets (or with None, clears) the timezone and locale a site sees. Returns the
+    /// normalized site.
+    pub fn set_site_region(
+        &mut self,
+        site: &str,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+    ) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        let timezone = timezone.map(fingerprint::normalize_timezone).transpose()?;
+        let locale = locale.map(fingerprint::normalize_locale).transpose()?;
+        let entry = self.site_settings.entry(site.clone()).or_default();
+        entry.timezone = timezone;
+        entry.locale = locale;
+        if entry.is_empty() {
+            self.site_settings.remove(&site);
+        }
+        Ok(site)
+    }
+
+    /// Sets (or with None, clears) the speed a site's media plays at. Returns the
+    /// normalized site.
+    pub fn set_site_playback_rate(&mut self, site: &str, rate: Option<f64>) -> Result<String, String> {
+        let site = cookie_policy::normalize_site(site).ok_or("Invalid site")?;
+        let rate = rate.map(media_controls::validate_rate).transpose()?;
+        let entry = self.site_settings.entry(site.clone()).or_default();
+        entry.playback_rate = rate;
+        if entry.is_empty() {
+            self.site_settings.remove(&site);
+        }
+        Ok(site)
+    }
+
+    /// Repairs values written by older or newer versions.
+    fn migrate(mut self) -> Self {
+        self.toolbar_layout = toolbar_layout::migrate(std::mem::take(&mut self.toolbar_layout));
+        self
+    }
+
     pub fn get_path(app: &AppHandle) -> PathBuf {
-        app.path()
-            .app_data_dir()
+        profile::data_dir(app)
             .expect("failed to get app data dir")
             .join("settings.json")
     }
 
+    /// Settings from the JSON of a settings.json of any version.
+    pub fn from_json(value: serde_json::Value) -> Self {
+        settings_migration::load(value).migrate()
+    }
+
     pub fn load(app: &AppHandle) -> Self {
+        let mut settings = Self::read_file(app);
+        // The proxy password is kept outside settings.json; one an older version wrote there moves out
+        let path = Self::get_path(app);
+        let dir = path.parent().unwrap();
+        match settings.proxy.password.clone() {
+            Some(password) => match proxy::store_password(dir, Some(&password)) {
+                Ok(()) => {
+                    if let Err(e) = settings.save(app) {
+                        eprintln!("[Settings] Failed to save settings: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("[Settings] Failed to store the proxy password: {}", e),
+            },
+            None => settings.proxy.password = proxy::load_password(dir),
+        }
+        settings
+    }
+
+    fn read_file(app: &AppHandle) -> Self {
         let path = Self::get_path(app);
         if path.exists() {
             match fs::read_to_string(&path) {
-                Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
-                    println!("[Settings] Failed to parse settings: {}, returning defaults", e);
-                    Self::default()
-                }),
+                Ok(content) => serde_json::from_str(&content)
+                    .map(Self::from_json)
+                    .unwrap_or_else(|e| {
+                        println!("[Settings] Failed to parse settings: {}, returning defaults", e);
+                        // Keep the file around; the next save would overwrite it
+                        let backup = path.with_extension("json.bak");
+                        if let Err(e) = fs::copy(&path, &backup) {
+                            eprintln!("[Settings] Failed to back up {}: {}", path.display(), e);
+                        }
+                        Self::default()
+                    }),
                 Err(e) => {
                     println!("[Settings] Failed to read file: {}, returning defaults", e);
                     Self::default()
@@ -2,6 +2,7 @@
 // These are used by main.rs and can be tested independently.
 
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Instant, SystemTime};
 use serde::{Deserialize, Serialize};
@@ -10,6 +11,42 @@ use crate::history::HistoryStore;
 use crate::settings::Settings;
 use crate::adblock_manager::AdBlockManager;
 use crate::modules::devtools::DevToolsManager;
+use crate::modules::suggest::SuggestManager;
+use crate::modules::clipboard::ClipboardManager;
+use crate::modules::focus::FocusManager;
+use crate::modules::site_storage::StorageInspector;
+use crate::modules::https_only::HttpsOnlyManager;
+use crate::modules::block_stats::BlockStatsManager;
+use crate::modules::frames::FrameTracker;
+use crate::modules::user_agent::UserAgentManager;
+use crate::modules::data_saver::DataSaverManager;
+use crate::modules::image_blocking::ImageBlocker;
+use crate::modules::safebrowsing::SafeBrowsingManager;
+use crate::modules::audio_output::AudioOutputManager;
+use crate::modules::media_controls::MediaControlsManager;
+use crate::modules::network_log::NetworkLogManager;
+use crate::modules::permissions::PermissionsManager;
+use crate::modules::console_log::ConsoleLogManager;
+use crate::modules::task_manager::TaskManager;
+use crate::modules::video_popout::VideoPopoutManager;
+use crate::modules::read_aloud::ReadAloudManager;
+use crate::modules::text_replace::TextReplaceManager;
+use crate::modules::snippets::SnippetsManager;
+use crate::modules::email_alias::EmailAliasManager;
+use crate::modules::split_view::SplitViewManager;
+use crate::modules::totp::TotpVault;
+use crate::modules::breach_check::BreachCheckStore;
+use crate::modules::top_sites::TopSitesStore;
+use crate::modules::thumbnails::ThumbnailCache;
+use crate::modules::annotations::AnnotationStore;
+use crate::modules::site_apps::SiteAppStore;
+use crate::modules::privacy_report::PrivacyReportStore;
+use crate::modules::maintenance::MaintenanceScheduler;
+use crate::modules::external_protocols::ExternalProtocolStore;
+use crate::modules::bookmarks::BookmarkStore;
+use crate::modules::containers::ContainerStore;
+use crate::modules::dock::DockManager;
+use crate::modules::doh::DohManager;
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Tab {
@@ -25,6 +62,31 @@ pub struct Tab {
     pub can_go_forward: bool,
     pub last_focus_was_content: bool,
     pub screenshot: Option<String>,
+    #[serde(default)]
+    pub unread_count: Option<u32>, // Parsed from "(3) Inbox" titles or badged favicons
+    #[serde(default)]
+    pub custom_title: Option<String>, // User-pinned title, survives page title changes
+    #[serde(default)]
+    pub marker: Option<TabMarker>,
+    #[serde(default)]
+    pub is_audible: bool, // A media element is playing sound, see modules::tab_audio
+    #[serde(default)]
+    pub is_muted: bool,
+    #[serde(default)]
+    pub load_error: Option<String>, // Last navigation failure, cleared when a new load starts
+    #[serde(default)]
+    pub window: Option<String>, // Label of the window the tab was detached into, None for the main window
+    #[serde(default)]
+    pub container: Option<String>, // Id of the container whose cookies the tab uses, see modules::containers
+}
+
+/// User-chosen visual tag for a tab.
+/// Serialized as `{ "kind": "color", "value": "#ff453a" }` or `{ "kind": "emoji", "value": "🔥" }`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum TabMarker {
+    Color(String),
+    Emoji(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +96,8 @@ pub struct ClosedTab {
     pub url: String,          // Current URL when closed
     pub favicon: Option<String>,  // Favicon data URL
     pub closed_at: SystemTime,    // When tab was closed (for sorting/expiry)
+    #[serde(default)]
+    pub container: Option<String>, // Reopens in the same container
 }
 
 impl From<&Tab> for ClosedTab {
@@ -44,6 +108,7 @@ impl From<&Tab> for ClosedTab {
             url: tab.url.clone(),
             favicon: tab.favicon.clone(),
             closed_at: SystemTime::now(),
+            container: tab.container.clone(),
         }
     }
 }
@@ -68,4 +133,44 @@ pub struct AppState {
     pub adblock: Arc<AdBlockManager>,
     pub devtools: Arc<DevToolsManager>,
     pub closed_tabs: Arc<Mutex<VecDeque<ClosedTab>>>,  // LIFO queue, max 25 tabs
+    pub suggest: Arc<SuggestManager>,
+    pub clipboard: Arc<ClipboardManager>,
+    pub doh: Arc<DohManager>,
+    pub status_seq: Arc<AtomicU64>, // Monotonic sequence for tab-status snapshots
+    pub focus: Arc<FocusManager>,   // Desired keyboard focus; see modules::focus
+    pub fingerprint_secret: String, // Per-profile key for fingerprint noise
+    pub storage_inspector: Arc<StorageInspector>,
+    pub https_only: Arc<HttpsOnlyManager>,
+    pub block_stats: Arc<BlockStatsManager>, // Blocked request counters per tab and per day
+    pub frames: Arc<FrameTracker>,           // Top-level document per webview, for frame-aware blocking
+    pub user_agents: Arc<UserAgentManager>,  // Compatibility UA overrides set natively per webview
+    pub data_saver: Arc<DataSaverManager>,
+    pub image_blocker: Arc<ImageBlocker>, // Image rules for Windows/Linux, rebuilt on settings save
+    pub safe_browsing: Arc<SafeBrowsingManager>,
+    pub audio_output: Arc<AudioOutputManager>, // Per-tab output device overrides
+    pub media_controls: Arc<MediaControlsManager>,
+    pub network_log: Arc<NetworkLogManager>, // Requests of each tab's current page
+    pub permissions: Arc<PermissionsManager>, // Requests waiting for the user's answer
+    pub console_log: Arc<ConsoleLogManager>,
+    pub task_manager: Arc<TaskManager>, // Latest resource usage sample, see modules::task_manager
+    pub video_popout: Arc<VideoPopoutManager>,
+    pub read_aloud: Arc<ReadAloudManager>, // The article being read, see modules::read_aloud
+    pub text_replace: Arc<TextReplaceManager>,
+    pub snippets: Arc<SnippetsManager>,
+    pub email_aliases: Arc<EmailAliasManager>,
+    pub split_view: Arc<SplitViewManager>,
+    pub totp: Arc<TotpVault>,
+    pub breach_check: Arc<BreachCheckStore>,
+    pub top_sites: Arc<TopSitesStore>,
+    pub thumbnails: Arc<ThumbnailCache>,
+    pub annotations: Arc<AnnotationStore>,
+    pub site_apps: Arc<SiteAppStore>,
+    pub privacy_reports: Arc<PrivacyReportStore>,
+    pub maintenance: Arc<MaintenanceScheduler>,
+    pub external_protocols: Arc<ExternalProtocolStore>, // Remembered "open in app" decisions per scheme
+    pub main_maximized: Arc<AtomicBool>, // Kept current by the main window's Resized handler, see modules::titlebar
+    pub bookmarks: Arc<BookmarkStore>,
+    pub containers: Arc<ContainerStore>,
+    pub dock: Arc<DockManager>, // Running downloads and what the Dock/taskbar icon shows
+    pub offline: Arc<AtomicBool>, // Network kill switch, see modules::offline
 }